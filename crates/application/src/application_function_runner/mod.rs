@@ -643,6 +643,10 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         self.http_actions.aggregate_heap_stats()
     }
 
+    pub(crate) fn key_broker(&self) -> &KeyBroker {
+        &self.key_broker
+    }
+
     // Only used for running queries from REPLs.
     pub async fn run_query_without_caching(
         &self,
@@ -1252,8 +1256,9 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                     .get(source_package_id)
                     .await?
                     .into_value();
-                let mut environment_variables =
-                    EnvironmentVariablesModel::new(&mut tx).get_all().await?;
+                let mut environment_variables = EnvironmentVariablesModel::new(&mut tx)
+                    .get_all(&self.key_broker)
+                    .await?;
                 // Insert special environment variables if not already provided by user
                 environment_variables.extend(self.system_env_vars.clone());
 
@@ -1538,7 +1543,9 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         // We use the latest environment variables at the time of the deployment
         // this is not transactional with the rest of the deploy.
         let mut tx = self.database.begin(Identity::system()).await?;
-        let mut environment_variables = EnvironmentVariablesModel::new(&mut tx).get_all().await?;
+        let mut environment_variables = EnvironmentVariablesModel::new(&mut tx)
+            .get_all(&self.key_broker)
+            .await?;
         // Insert special environment variables if not already provided by user
         environment_variables.extend(self.system_env_vars.clone());
 
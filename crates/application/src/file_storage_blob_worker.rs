@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    runtime::Runtime,
+};
+use database::Database;
+use file_storage::FileStorage;
+use futures::Future;
+use keybroker::Identity;
+use model::file_storage::FileStorageModel;
+
+use crate::metrics::log_worker_starting;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(900); // 15 minutes
+
+/// Deletes the storage objects of deduplicated files once no `_file_storage`
+/// entry references them.
+pub struct FileStorageBlobWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    file_storage: FileStorage<RT>,
+    backoff: Backoff,
+}
+
+impl<RT: Runtime> FileStorageBlobWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        file_storage: FileStorage<RT>,
+    ) -> impl Future<Output = ()> + Send {
+        let mut worker = Self {
+            runtime,
+            database,
+            file_storage,
+            backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
+        };
+        async move {
+            loop {
+                if let Err(e) = worker.run().await {
+                    report_error(&mut e.context("FileStorageBlobWorker died"));
+                    let delay = worker.runtime.with_rng(|rng| worker.backoff.fail(rng));
+                    worker.runtime.wait(delay).await;
+                } else {
+                    worker.backoff.reset();
+                }
+            }
+        }
+    }
+
+    // Subscribe to unreferenced blobs, and delete them in batches as they
+    // appear.
+    async fn run(&mut self) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let unreferenced = FileStorageModel::new(&mut tx).unreferenced_blobs(1).await?;
+        if unreferenced.is_empty() {
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            subscription.wait_for_invalidation().await;
+            return Ok(());
+        }
+        let _status = log_worker_starting("FileStorageBlobWorker");
+        let num_deleted = self.file_storage.delete_unreferenced_blobs().await?;
+        tracing::info!("Deleted {num_deleted} unreferenced file storage blobs");
+        Ok(())
+    }
+}
@@ -17,7 +17,10 @@ use common::{
         report_error,
         JsError,
     },
-    execution_context::ExecutionContext,
+    execution_context::{
+        ExecutionContext,
+        RequestId,
+    },
     identity::InertIdentity,
     knobs::MAX_UDF_EXECUTION,
     log_lines::{
@@ -81,6 +84,7 @@ use value::{
     },
     ConvexArray,
 };
+
 /// A function's execution is summarized by this structure and stored in the
 /// UdfExecutionLog
 #[derive(Debug, Clone)]
@@ -184,6 +188,7 @@ impl FunctionExecution {
         match &self.params {
             UdfParams::Function { identifier, .. } => UdfIdentifier::Function(identifier.clone()),
             UdfParams::Http { identifier, .. } => UdfIdentifier::Http(identifier.clone()),
+            UdfParams::System { name } => UdfIdentifier::System(name.clone()),
         }
     }
 
@@ -342,6 +347,8 @@ pub enum UdfParams {
         result: Result<HttpActionStatusCode, JsError>,
         identifier: HttpActionRoute,
     },
+    /// Work done by the backend itself rather than by a function.
+    System { name: String },
 }
 
 impl HeapSize for UdfParams {
@@ -349,6 +356,7 @@ impl HeapSize for UdfParams {
         match self {
             UdfParams::Function { error, identifier } => error.heap_size() + identifier.heap_size(),
             UdfParams::Http { result, identifier } => result.heap_size() + identifier.heap_size(),
+            UdfParams::System { name } => name.heap_size(),
         }
     }
 }
@@ -358,6 +366,7 @@ impl UdfParams {
         match self {
             UdfParams::Function { ref error, .. } => error.is_some(),
             UdfParams::Http { ref result, .. } => result.is_err(),
+            UdfParams::System { .. } => false,
         }
     }
 
@@ -373,6 +382,7 @@ impl UdfParams {
         match self {
             Self::Function { identifier, .. } => identifier.clone().strip().to_string(),
             Self::Http { identifier, .. } => identifier.to_string(),
+            Self::System { name } => UdfIdentifier::System(name.clone()).to_string(),
         }
     }
 }
@@ -764,6 +774,47 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
         self.log_execution(execution, true);
     }
 
+    /// Logs a batch of documents deleted for being past their table's TTL,
+    /// as a mutation run by the system.
+    pub fn log_ttl_deletions(
+        &self,
+        table_name: TableName,
+        num_deleted: usize,
+        execution_time: Duration,
+    ) {
+        let tables_touched = BTreeMap::from([(
+            table_name,
+            TableStats {
+                rows_read: num_deleted as u64,
+                rows_written: 0,
+                rows_created: 0,
+                rows_deleted: num_deleted as u64,
+            },
+        )]);
+        let unix_timestamp = self.rt.unix_timestamp();
+        let caller = FunctionCaller::Ttl;
+        let execution = FunctionExecution {
+            params: UdfParams::System {
+                name: "ttl".to_string(),
+            },
+            unix_timestamp,
+            execution_timestamp: unix_timestamp,
+            udf_type: UdfType::Mutation,
+            log_lines: vec![].into(),
+            tables_touched: tables_touched.into(),
+            cached_result: false,
+            execution_time: execution_time.as_secs_f64(),
+            context: ExecutionContext::new(RequestId::new(), &caller),
+            caller,
+            environment: ModuleEnvironment::Isolate,
+            syscall_trace: SyscallTrace::new(),
+            usage_stats: AggregatedFunctionUsageStats::default(),
+            udf_server_version: None,
+            identity: InertIdentity::System,
+        };
+        self.log_execution(execution, false);
+    }
+
     pub fn log_action(&self, completion: ActionCompletion, usage: FunctionUsageTracker) {
         self._log_action(completion, TrackUsage::Track(usage))
     }
@@ -1326,6 +1377,7 @@ impl UdfMetrics {
         let is_err = match &row.params {
             UdfParams::Function { error, .. } => error.is_some(),
             UdfParams::Http { result, .. } => result.is_err(),
+            UdfParams::System { .. } => false,
         };
         if is_err {
             self.errors.append(ts)?;
@@ -34,6 +34,8 @@ use common::{
         schema::{
             invalid_schema_id,
             parse_schema_id,
+            SchemaMetadata,
+            SchemaState,
         },
     },
     document::{
@@ -47,6 +49,7 @@ use common::{
     },
     http::fetch::FetchClient,
     knobs::{
+        DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS,
         MAX_JOBS_CANCEL_BATCH,
         SNAPSHOT_LIST_LIMIT,
     },
@@ -54,13 +57,21 @@ use common::{
     paths::FieldPath,
     pause::PauseClient,
     persistence::Persistence,
+    query::{
+        Query,
+        SerializedCursor,
+    },
     query_journal::QueryJournal,
     runtime::{
+        new_rate_limiter,
         Runtime,
         SpawnHandle,
         UnixTimestamp,
     },
-    schemas::DatabaseSchema,
+    schemas::{
+        DatabaseSchema,
+        SchemaValidationError,
+    },
     types::{
         env_var_limit_met,
         env_var_name_not_unique,
@@ -69,6 +80,7 @@ use common::{
         ConvexSite,
         CursorMs,
         EnvVarName,
+        EnvVarValue,
         FunctionCaller,
         IndexId,
         IndexName,
@@ -87,15 +99,22 @@ use common::{
 };
 use cron_jobs::CronJobExecutor;
 use database::{
+    query::{
+        QueryExplanation,
+        TableFilter,
+    },
     unauthorized_error,
     Database,
+    DeveloperQuery,
     DocumentDeltas,
     FastForwardIndexWorker,
     IndexModel,
     IndexWorker,
     OccRetryStats,
+    SchemaModel,
     SearchIndexWorker,
     ShortBoxFuture,
+    SlowQuery,
     Snapshot,
     SnapshotPage,
     Subscription,
@@ -113,6 +132,7 @@ use file_storage::{
     FileRangeStream,
     FileStorage,
     FileStream,
+    FileUploadProgress,
 };
 use function_log::{
     FunctionExecution,
@@ -124,6 +144,7 @@ use futures::{
     stream::BoxStream,
     Stream,
 };
+use governor::Quota;
 use headers::{
     ContentLength,
     ContentType,
@@ -144,10 +165,12 @@ use keybroker::{
     KeyBroker,
 };
 use maplit::btreemap;
+use migration_worker::MigrationWorker;
 use model::{
     auth::AuthInfoModel,
     config::{
         types::{
+            ConfigDiff,
             ConfigFile,
             ConfigMetadata,
             ModuleConfig,
@@ -156,9 +179,17 @@ use model::{
         ConfigModel,
     },
     deployment_audit_log::{
-        types::DeploymentAuditLogEvent,
+        types::{
+            DeploymentAuditLog,
+            DeploymentAuditLogEvent,
+        },
+        DeploymentAuditLogFilter,
         DeploymentAuditLogModel,
     },
+    deployment_history::{
+        types::Deployment,
+        DeploymentHistoryModel,
+    },
     environment_variables::{
         types::EnvironmentVariable,
         EnvironmentVariablesModel,
@@ -206,7 +237,12 @@ use node_executor::{
 use parking_lot::Mutex;
 use rand::Rng;
 use scheduled_jobs::ScheduledJobRunner;
-use schema_worker::SchemaWorker;
+use schema_worker::{
+    check_existing_documents,
+    ExistingDocumentsCheck,
+    SchemaWorker,
+    TABLE_ITERATOR_RATE_LIMIT,
+};
 use search::{
     query::RevisionWithKeys,
     searcher::Searcher,
@@ -224,6 +260,7 @@ use storage::{
     Storage,
     StorageExt,
     StorageGetStream,
+    StorageUseCase,
     Upload,
 };
 use sync_types::{
@@ -256,6 +293,7 @@ use vector::{
 use crate::{
     application_function_runner::ApplicationFunctionRunner,
     export_worker::ExportWorker,
+    file_storage_blob_worker::FileStorageBlobWorker,
     function_log::{
         FunctionExecutionLog,
         MetricsWindow,
@@ -275,21 +313,30 @@ use crate::{
         RedactedLogLines,
     },
     snapshot_import::SnapshotImportWorker,
+    storage_gc::{
+        StorageGarbageCollector,
+        StorageGcUseCaseReport,
+    },
+    ttl_worker::TtlWorker,
 };
 
 pub mod application_function_runner;
 mod cache;
 pub mod cron_jobs;
 mod export_worker;
+mod file_storage_blob_worker;
 pub mod function_log;
 pub mod log_visibility;
 mod metrics;
+mod migration_worker;
 mod module_cache;
 pub mod redaction;
 pub mod scheduled_jobs;
 mod schema_worker;
 pub mod snapshot_import;
+pub mod storage_gc;
 mod table_summary_worker;
+mod ttl_worker;
 pub mod valid_identifier;
 
 #[cfg(any(test, feature = "testing"))]
@@ -307,6 +354,32 @@ pub struct ConfigMetadataAndSchema {
     pub schema: Option<DatabaseSchema>,
 }
 
+/// What pushing a config would change, as computed by
+/// [`Application::plan_config`].
+pub struct DeployPlan {
+    pub config_diff: ConfigDiff,
+    /// The state of the pushed schema, if any.
+    pub schema_state: Option<SchemaState>,
+    /// Whether existing documents match the pushed schema, if it's pending.
+    pub schema_validation: Option<SchemaValidationEstimate>,
+    pub index_backfills: Vec<IndexBackfillEstimate>,
+}
+
+/// Whether existing documents match a pending schema, as checked by a plan.
+pub enum SchemaValidationEstimate {
+    Passed,
+    Failed(SchemaValidationError),
+    /// There were too many existing documents to check them all.
+    Unknown,
+}
+
+pub struct IndexBackfillEstimate {
+    pub index_name: IndexName,
+    /// Documents currently in the index's table, which will all be read to
+    /// backfill it.
+    pub num_documents: u64,
+}
+
 #[derive(Clone)]
 pub struct ApplyConfigArgs {
     pub auth_module: Option<ModuleConfig>,
@@ -398,6 +471,11 @@ pub struct Application<RT: Runtime> {
     schema_worker: Arc<Mutex<RT::Handle>>,
     snapshot_import_worker: Arc<Mutex<RT::Handle>>,
     export_worker: Arc<Mutex<RT::Handle>>,
+    file_storage_blob_worker: Arc<Mutex<RT::Handle>>,
+    storage_gc: StorageGarbageCollector<RT>,
+    storage_gc_worker: Arc<Mutex<RT::Handle>>,
+    ttl_worker: Arc<Mutex<RT::Handle>>,
+    migration_worker: Arc<Mutex<RT::Handle>>,
     log_sender: Arc<dyn LogSender>,
     log_visibility: Arc<dyn LogVisibility<RT>>,
     module_cache: ModuleCache<RT>,
@@ -430,6 +508,11 @@ impl<RT: Runtime> Clone for Application<RT> {
             schema_worker: self.schema_worker.clone(),
             snapshot_import_worker: self.snapshot_import_worker.clone(),
             export_worker: self.export_worker.clone(),
+            file_storage_blob_worker: self.file_storage_blob_worker.clone(),
+            storage_gc: self.storage_gc.clone(),
+            storage_gc_worker: self.storage_gc_worker.clone(),
+            ttl_worker: self.ttl_worker.clone(),
+            migration_worker: self.migration_worker.clone(),
             log_sender: self.log_sender.clone(),
             log_visibility: self.log_visibility.clone(),
             module_cache: self.module_cache.clone(),
@@ -464,6 +547,8 @@ impl<RT: Runtime> Application<RT> {
         snapshot_import_pause_client: PauseClient,
         scheduled_jobs_pause_client: PauseClient,
     ) -> anyhow::Result<Self> {
+        Self::encrypt_plaintext_environment_variables(&database, &key_broker).await?;
+
         let module_cache =
             ModuleCacheWorker::start(runtime.clone(), database.clone(), modules_storage.clone())
                 .await;
@@ -564,6 +649,38 @@ impl<RT: Runtime> Application<RT> {
             runtime.spawn("snapshot_import_worker", snapshot_import_worker),
         ));
 
+        let file_storage_blob_worker =
+            FileStorageBlobWorker::new(runtime.clone(), database.clone(), file_storage.clone());
+        let file_storage_blob_worker = Arc::new(Mutex::new(
+            runtime.spawn("file_storage_blob_worker", file_storage_blob_worker),
+        ));
+
+        let storage_gc = StorageGarbageCollector::new(
+            runtime.clone(),
+            database.clone(),
+            vec![
+                (StorageUseCase::Files, files_storage.clone()),
+                (StorageUseCase::Modules, modules_storage.clone()),
+                (StorageUseCase::SearchIndexes, search_storage.clone()),
+                (StorageUseCase::Exports, exports_storage.clone()),
+                (
+                    StorageUseCase::SnapshotImports,
+                    snapshot_imports_storage.clone(),
+                ),
+            ],
+        );
+        let storage_gc_worker = Arc::new(Mutex::new(
+            runtime.spawn("storage_gc_worker", storage_gc.clone().start()),
+        ));
+
+        let ttl_worker = TtlWorker::new(runtime.clone(), database.clone(), function_log.clone());
+        let ttl_worker = Arc::new(Mutex::new(runtime.spawn("ttl_worker", ttl_worker.start())));
+
+        let migration_worker = Arc::new(Mutex::new(runtime.spawn(
+            "migration_worker",
+            MigrationWorker::start(runtime.clone(), database.clone(), runner.clone()),
+        )));
+
         Ok(Self {
             runtime,
             database,
@@ -588,6 +705,11 @@ impl<RT: Runtime> Application<RT> {
             schema_worker,
             export_worker,
             snapshot_import_worker,
+            file_storage_blob_worker,
+            storage_gc,
+            storage_gc_worker,
+            ttl_worker,
+            migration_worker,
             log_sender,
             log_visibility,
             module_cache,
@@ -595,6 +717,31 @@ impl<RT: Runtime> Application<RT> {
         })
     }
 
+    /// Environment variables set before values were encrypted at rest are
+    /// stored in plaintext. Encrypt them on startup so every deployment
+    /// converges on encrypted storage.
+    async fn encrypt_plaintext_environment_variables(
+        database: &Database<RT>,
+        key_broker: &KeyBroker,
+    ) -> anyhow::Result<()> {
+        let mut tx = database.begin(Identity::system()).await?;
+        let count = EnvironmentVariablesModel::new(&mut tx)
+            .encrypt_plaintext_values(key_broker)
+            .await?;
+        if count > 0 {
+            database
+                .commit_with_write_source(tx, "encrypt_environment_variables")
+                .await?;
+            tracing::warn!(
+                "Encrypted {count} plaintext environment variables. Their plaintext values remain \
+                 in the document log until document retention deletes them, and in backups or \
+                 snapshot exports taken before now. Rotate those secrets to invalidate the old \
+                 values."
+            );
+        }
+        Ok(())
+    }
+
     pub fn runtime(&self) -> RT {
         self.runtime.clone()
     }
@@ -619,6 +766,33 @@ impl<RT: Runtime> Application<RT> {
         self.database.now_ts_for_reads()
     }
 
+    /// Checks that a caller-provided timestamp can be read at: it must not be
+    /// past the latest snapshot and must still be within the retention window.
+    pub async fn validate_read_ts(&self, ts: Timestamp) -> anyhow::Result<Timestamp> {
+        let latest_ts = *self.now_ts_for_reads();
+        if ts > latest_ts {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "TimestampInFuture",
+                format!("Timestamp {ts} is after the latest snapshot {latest_ts}"),
+            ));
+        }
+        let min_snapshot_ts = self
+            .database
+            .retention_validator()
+            .min_snapshot_ts()
+            .await?;
+        if ts < min_snapshot_ts {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "TimestampOutOfRetention",
+                format!(
+                    "Timestamp {ts} is outside the retention window. The oldest timestamp that \
+                     can be read is {min_snapshot_ts}"
+                ),
+            ));
+        }
+        Ok(ts)
+    }
+
     pub fn instance_name(&self) -> String {
         self.instance_name.clone()
     }
@@ -731,12 +905,15 @@ impl<RT: Runtime> Application<RT> {
         self.database.vector_search(identity, query).await
     }
 
-    pub async fn storage_generate_upload_url(&self) -> anyhow::Result<String> {
+    pub async fn storage_generate_upload_url(&self, resumable: bool) -> anyhow::Result<String> {
         let issued_ts = self.runtime().unix_timestamp();
-        let url = self
-            .file_storage
-            .transactional_file_storage
-            .generate_upload_url(self.key_broker(), issued_ts)?;
+        let transactional_file_storage = &self.file_storage.transactional_file_storage;
+        let url = if resumable {
+            transactional_file_storage
+                .generate_resumable_upload_url(self.key_broker(), issued_ts)?
+        } else {
+            transactional_file_storage.generate_upload_url(self.key_broker(), issued_ts)?
+        };
 
         Ok(url)
     }
@@ -1113,6 +1290,20 @@ impl<RT: Runtime> Application<RT> {
         }
     }
 
+    /// Find storage objects that no metadata refers to, deleting them unless
+    /// `dry_run` is set.
+    pub async fn collect_storage_garbage(
+        &self,
+        identity: Identity,
+        dry_run: bool,
+    ) -> anyhow::Result<Vec<StorageGcUseCaseReport>> {
+        anyhow::ensure!(
+            identity.is_admin(),
+            unauthorized_error("collect_storage_garbage")
+        );
+        self.storage_gc.collect(dry_run).await
+    }
+
     pub async fn request_export(
         &self,
         identity: Identity,
@@ -1132,7 +1323,7 @@ impl<RT: Runtime> Application<RT> {
         let mut tx = self.begin(identity).await?;
         let export_requested = ExportWorker::export_in_state(&mut tx, "requested").await?;
         let export_in_progress = ExportWorker::export_in_state(&mut tx, "in_progress").await?;
-        match (export_requested, export_in_progress) {
+        let format = match (export_requested, export_in_progress) {
             (None, None) => {
                 let format = if zip {
                     ExportFormat::Zip { include_storage }
@@ -1155,7 +1346,7 @@ impl<RT: Runtime> Application<RT> {
                 SystemMetadataModel::new(&mut tx)
                     .insert(&EXPORTS_TABLE, Export::requested(format).try_into()?)
                     .await?;
-                Ok(())
+                Ok(format)
             },
             _ => Err(
                 anyhow::anyhow!("Can only have one export requested or in progress at once")
@@ -1165,7 +1356,14 @@ impl<RT: Runtime> Application<RT> {
                     )),
             ),
         }?;
-        self.commit(tx, "request_export").await?;
+        self.commit_with_audit_log_events(
+            tx,
+            vec![DeploymentAuditLogEvent::RequestExport {
+                export_format: format,
+            }],
+            "request_export",
+        )
+        .await?;
         Ok(())
     }
 
@@ -1270,7 +1468,7 @@ impl<RT: Runtime> Application<RT> {
             match change {
                 EnvVarChange::Set(env_var) => {
                     let name = env_var.name();
-                    if let Some(_existing) = model.delete(name).await? {
+                    if model.delete(name).await? {
                         audit_events.push(DeploymentAuditLogEvent::UpdateEnvironmentVariable {
                             name: name.clone(),
                         });
@@ -1279,10 +1477,12 @@ impl<RT: Runtime> Application<RT> {
                             name: name.clone(),
                         });
                     }
-                    model.create(env_var, &self.system_env_var_names).await?;
+                    model
+                        .create(env_var, &self.system_env_var_names, &self.key_broker)
+                        .await?;
                 },
                 EnvVarChange::Unset(name) => {
-                    if let Some(_existing) = model.delete(&name).await? {
+                    if model.delete(&name).await? {
                         audit_events
                             .push(DeploymentAuditLogEvent::DeleteEnvironmentVariable { name });
                     };
@@ -1338,14 +1538,18 @@ impl<RT: Runtime> Application<RT> {
     ) -> anyhow::Result<()> {
         let mut env_var_model = EnvironmentVariablesModel::new(tx);
         if env_var_model
-            .get(environment_variable.name())
+            .get_persisted(environment_variable.name())
             .await?
             .is_some()
         {
             anyhow::bail!(env_var_name_not_unique(None));
         }
         env_var_model
-            .create(environment_variable, &self.system_env_var_names)
+            .create(
+                environment_variable,
+                &self.system_env_var_names,
+                &self.key_broker,
+            )
             .await?;
         Ok(())
     }
@@ -1358,7 +1562,7 @@ impl<RT: Runtime> Application<RT> {
         let mut tx = self.begin(identity).await?;
 
         if !EnvironmentVariablesModel::new(&mut tx)
-            .get_all()
+            .get_all_names()
             .await?
             .is_empty()
         {
@@ -1397,17 +1601,47 @@ impl<RT: Runtime> Application<RT> {
         id: ResolvedDocumentId,
     ) -> anyhow::Result<DeploymentAuditLogEvent> {
         let mut model = EnvironmentVariablesModel::new(tx);
-        let Some(env_var) = model.get_by_id_legacy(id).await? else {
+        let Some(name) = model.get_name_by_id_legacy(id).await? else {
             anyhow::bail!(ErrorMetadata::bad_request(
                 "EnvironmentVariableNotFound",
                 "Environment variable not found"
             ));
         };
-        let name = env_var.name().to_owned();
         model.delete(&name).await?;
         Ok(DeploymentAuditLogEvent::DeleteEnvironmentVariable { name })
     }
 
+    /// Decrypts a single environment variable's value. Listings only expose
+    /// names, so this is the one place admins can read a value back, and each
+    /// call is recorded in the deployment audit log.
+    pub async fn reveal_environment_variable(
+        &self,
+        identity: Identity,
+        name: EnvVarName,
+    ) -> anyhow::Result<EnvVarValue> {
+        anyhow::ensure!(
+            identity.is_admin(),
+            unauthorized_error("reveal_environment_variable")
+        );
+        let mut tx = self.begin(identity).await?;
+        let Some(env_var) = EnvironmentVariablesModel::new(&mut tx)
+            .get(&name, &self.key_broker)
+            .await?
+        else {
+            anyhow::bail!(ErrorMetadata::not_found(
+                "EnvironmentVariableNotFound",
+                format!("Environment variable {name} not found"),
+            ));
+        };
+        self.commit_with_audit_log_events(
+            tx,
+            vec![DeploymentAuditLogEvent::RevealEnvironmentVariable { name }],
+            "reveal_env_var",
+        )
+        .await?;
+        Ok(env_var.into_value().value)
+    }
+
     pub async fn analyze(
         &self,
         udf_config: UdfConfig,
@@ -1537,7 +1771,9 @@ impl<RT: Runtime> Application<RT> {
             .evaluate_auth_config(
                 auth_config_module.source,
                 auth_config_module.source_map,
-                EnvironmentVariablesModel::new(tx).get_all().await?,
+                EnvironmentVariablesModel::new(tx)
+                    .get_all(runner.key_broker())
+                    .await?,
             )
             .await
             .map_err(|error| {
@@ -1577,6 +1813,19 @@ impl<RT: Runtime> Application<RT> {
 
     #[minitrace::trace]
     async fn _apply_config(
+        runner: Arc<ApplicationFunctionRunner<RT>>,
+        tx: &mut Transaction<RT>,
+        apply_config_args: ApplyConfigArgs,
+    ) -> anyhow::Result<(ConfigMetadataAndSchema, Vec<DeploymentAuditLogEvent>)> {
+        let (config_metadata_and_schema, config_diff) =
+            Self::apply_config_in_transaction(runner, tx, apply_config_args).await?;
+        Ok((
+            config_metadata_and_schema,
+            vec![DeploymentAuditLogEvent::PushConfig { config_diff }],
+        ))
+    }
+
+    async fn apply_config_in_transaction(
         runner: Arc<ApplicationFunctionRunner<RT>>,
         tx: &mut Transaction<RT>,
         ApplyConfigArgs {
@@ -1588,7 +1837,7 @@ impl<RT: Runtime> Application<RT> {
             source_package,
             analyze_results,
         }: ApplyConfigArgs,
-    ) -> anyhow::Result<(ConfigMetadataAndSchema, Vec<DeploymentAuditLogEvent>)> {
+    ) -> anyhow::Result<(ConfigMetadataAndSchema, ConfigDiff)> {
         let schema_id = schema_id
             .map(|schema_id| {
                 parse_schema_id(&schema_id, tx.table_mapping())
@@ -1617,7 +1866,156 @@ impl<RT: Runtime> Application<RT> {
                 config_metadata,
                 schema,
             },
-            vec![DeploymentAuditLogEvent::PushConfig { config_diff }],
+            config_diff,
+        ))
+    }
+
+    /// Computes what pushing this config would change, without committing
+    /// anything. A schema that's still being validated is checked against up
+    /// to `DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS` existing documents, and a
+    /// schema that fails validation is left out of the rest of the plan, since
+    /// pushing it would fail.
+    #[minitrace::trace]
+    pub async fn plan_config(
+        &self,
+        identity: Identity,
+        mut apply_config_args: ApplyConfigArgs,
+    ) -> anyhow::Result<DeployPlan> {
+        let mut tx = self.begin(identity).await?;
+        let mut schema_validation = None;
+        let schema_state = match apply_config_args.schema_id.clone() {
+            Some(schema_id) => {
+                let id = parse_schema_id(&schema_id, tx.table_mapping())
+                    .context(invalid_schema_id(&schema_id))?;
+                let SchemaMetadata { state, schema } = tx
+                    .get(id)
+                    .await?
+                    .context(invalid_schema_id(&schema_id))?
+                    .into_value()
+                    .into_value()
+                    .try_into()?;
+                let fails = match state {
+                    SchemaState::Pending => {
+                        let validation = self.validate_pending_schema(&mut tx, &schema).await?;
+                        let fails = matches!(validation, SchemaValidationEstimate::Failed(_));
+                        if !fails {
+                            SchemaModel::new(&mut tx).mark_validated(id).await?;
+                        }
+                        schema_validation = Some(validation);
+                        fails
+                    },
+                    SchemaState::Failed { .. } | SchemaState::Overwritten => true,
+                    SchemaState::Validated | SchemaState::Active => false,
+                };
+                if fails {
+                    apply_config_args.schema_id = SchemaModel::new(&mut tx)
+                        .get_by_state(SchemaState::Active)
+                        .await?
+                        .map(|(id, _schema)| DocumentIdV6::from(id).encode());
+                }
+                Some(state)
+            },
+            None => None,
+        };
+        let (_, config_diff) =
+            Self::apply_config_in_transaction(self.runner.clone(), &mut tx, apply_config_args)
+                .await?;
+        let mut index_backfills = vec![];
+        for index_name in &config_diff.index_diff.added {
+            let index_name: IndexName = index_name.parse()?;
+            let num_documents = tx.count(index_name.table()).await?;
+            index_backfills.push(IndexBackfillEstimate {
+                index_name,
+                num_documents,
+            });
+        }
+        // Drop the transaction without committing it.
+        drop(tx);
+        Ok(DeployPlan {
+            config_diff,
+            schema_state,
+            schema_validation,
+            index_backfills,
+        })
+    }
+
+    /// Checks a pending schema against the existing documents it would
+    /// validate, up to `DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS` of them.
+    async fn validate_pending_schema(
+        &self,
+        tx: &mut Transaction<RT>,
+        schema: &DatabaseSchema,
+    ) -> anyhow::Result<SchemaValidationEstimate> {
+        let snapshot = self.database.snapshot(tx.begin_timestamp())?;
+        let active_schema = SchemaModel::new(tx)
+            .get_by_state(SchemaState::Active)
+            .await?
+            .map(|(_id, active_schema)| active_schema);
+        let tables_to_check = DatabaseSchema::tables_to_validate(
+            schema,
+            active_schema,
+            tx.table_mapping(),
+            tx.virtual_table_mapping(),
+            &|table_name| snapshot.table_summary(table_name).inferred_type().clone(),
+        )?;
+        let rate_limiter = new_rate_limiter(
+            self.runtime.clone(),
+            Quota::per_second(*TABLE_ITERATOR_RATE_LIMIT),
+        );
+        let check = check_existing_documents(
+            &self.database,
+            tx,
+            schema,
+            tables_to_check,
+            &rate_limiter,
+            Some(*DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS),
+        )
+        .await?;
+        Ok(match check {
+            ExistingDocumentsCheck::Valid => SchemaValidationEstimate::Passed,
+            ExistingDocumentsCheck::Invalid(error) => SchemaValidationEstimate::Failed(error),
+            ExistingDocumentsCheck::Incomplete => SchemaValidationEstimate::Unknown,
+        })
+    }
+
+    /// Returns the retained deployment history, newest first.
+    pub async fn list_deployments(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<ParsedDocument<Deployment>>> {
+        let mut tx = self.begin(identity).await?;
+        DeploymentHistoryModel::new(&mut tx).list().await
+    }
+
+    /// Atomically restore the modules and config of a previous deployment.
+    #[minitrace::trace]
+    pub async fn rollback_config(
+        &self,
+        identity: Identity,
+        deployment_id: String,
+    ) -> anyhow::Result<ConfigDiff> {
+        let deployment_id =
+            DocumentIdV6::decode(&deployment_id).context(ErrorMetadata::bad_request(
+                "InvalidDeploymentId",
+                format!("Invalid deployment id: {deployment_id}"),
+            ))?;
+        self.execute_with_audit_log_events_and_occ_retries(identity, "rollback_config", |tx| {
+            Self::_rollback_config(tx, deployment_id).into()
+        })
+        .await
+    }
+
+    async fn _rollback_config(
+        tx: &mut Transaction<RT>,
+        deployment_id: DocumentIdV6,
+    ) -> anyhow::Result<(ConfigDiff, Vec<DeploymentAuditLogEvent>)> {
+        let config_diff = ConfigModel::new(tx).rollback(deployment_id).await?;
+        Ok((
+            config_diff.clone(),
+            vec![DeploymentAuditLogEvent::RollbackConfig {
+                deployment_id,
+                config_diff,
+            }],
         ))
     }
 
@@ -1824,6 +2222,9 @@ impl<RT: Runtime> Application<RT> {
         let analyzed_function = analyzed_function.context("Missing default export.")?;
 
         // 3. Add the module
+        let retained_versions = DeploymentHistoryModel::new(&mut tx)
+            .retained_module_versions()
+            .await?;
         ModuleModel::new(&mut tx)
             .put(
                 module_path.clone(),
@@ -1832,6 +2233,7 @@ impl<RT: Runtime> Application<RT> {
                 module.source_map,
                 Some(analyzed_module),
                 ModuleEnvironment::Isolate,
+                &retained_versions,
             )
             .await?;
 
@@ -1939,7 +2341,7 @@ impl<RT: Runtime> Application<RT> {
     ) -> anyhow::Result<u64> {
         let mut tx = self.begin(identity.clone()).await?;
         let mut count = 0;
-        for table_name in table_names {
+        for table_name in table_names.clone() {
             anyhow::ensure!(
                 !table_name.is_system(),
                 "cannot delete system table {table_name}"
@@ -1948,10 +2350,40 @@ impl<RT: Runtime> Application<RT> {
             count += table_model.count(&table_name).await?;
             table_model.delete_table(table_name).await?;
         }
-        self.commit(tx, "delete_tables").await?;
+        self.commit_with_audit_log_events(
+            tx,
+            vec![DeploymentAuditLogEvent::DeleteTables {
+                table_names,
+                document_count: count,
+            }],
+            "delete_tables",
+        )
+        .await?;
         Ok(count)
     }
 
+    /// Runs `query` until it's exhausted or approaching the transaction's
+    /// read limits, and reports how it executed.
+    pub async fn explain_query(
+        &self,
+        identity: Identity,
+        query: Query,
+    ) -> anyhow::Result<QueryExplanation> {
+        let mut tx = self.begin(identity).await?;
+        let mut compiled_query =
+            DeveloperQuery::new(&mut tx, query, TableFilter::ExcludePrivateSystemTables)?;
+        while !compiled_query.is_approaching_data_limit()
+            && compiled_query.next(&mut tx, None).await?.is_some()
+        {}
+        Ok(compiled_query.explain())
+    }
+
+    /// The most recent queries that exceeded the slow query thresholds,
+    /// oldest first. This is empty unless `SLOW_QUERY_LOG_ENABLED` is set.
+    pub fn slow_queries(&self) -> Vec<SlowQuery> {
+        self.database.slow_query_log().queries()
+    }
+
     /// Add system indexes if they do not already exist and update
     /// existing indexes if needed.
     pub async fn _add_system_indexes(
@@ -2010,6 +2442,56 @@ impl<RT: Runtime> Application<RT> {
         Ok(storage_id)
     }
 
+    /// Start a resumable upload bound to `authorization`, the store file
+    /// authorization token, which the session's other methods must be passed.
+    pub async fn start_file_upload_session(
+        &self,
+        authorization: &str,
+        content_type: Option<ContentType>,
+    ) -> anyhow::Result<FileUploadProgress> {
+        self.file_storage
+            .start_upload_session(authorization, content_type)
+            .await
+    }
+
+    pub async fn get_file_upload_session(
+        &self,
+        authorization: &str,
+        upload_id: &str,
+    ) -> anyhow::Result<FileUploadProgress> {
+        self.file_storage
+            .get_upload_session(authorization, upload_id)
+            .await
+    }
+
+    pub async fn upload_file_part(
+        &self,
+        authorization: &str,
+        upload_id: &str,
+        offset: u64,
+        part: Bytes,
+    ) -> anyhow::Result<FileUploadProgress> {
+        self.file_storage
+            .upload_session_part(authorization, upload_id, offset, part)
+            .await
+    }
+
+    pub async fn finish_file_upload_session(
+        &self,
+        authorization: &str,
+        upload_id: &str,
+        expected_sha256: Sha256Digest,
+    ) -> anyhow::Result<DocumentIdV6> {
+        self.file_storage
+            .finish_upload_session(
+                authorization,
+                upload_id,
+                expected_sha256,
+                &self.usage_tracking,
+            )
+            .await
+    }
+
     pub async fn get_file(&self, storage_id: FileStorageId) -> anyhow::Result<FileStream> {
         let mut file_storage_tx = self.begin(Identity::system()).await?;
 
@@ -2227,7 +2709,42 @@ impl<RT: Runtime> Application<RT> {
         let count = SchedulerModel::new(tx)
             .cancel_all(udf_path.clone(), max_jobs)
             .await?;
-        Ok((count, vec![]))
+        Ok((
+            count,
+            vec![DeploymentAuditLogEvent::CancelAllJobs {
+                udf_path,
+                job_count: count as u64,
+            }],
+        ))
+    }
+
+    /// Returns a page of the deployment audit log, newest first, along with
+    /// the cursor for the next page.
+    pub async fn list_deployment_audit_log(
+        &self,
+        identity: Identity,
+        filter: DeploymentAuditLogFilter,
+        cursor: Option<SerializedCursor>,
+        limit: usize,
+    ) -> anyhow::Result<(
+        Vec<ParsedDocument<DeploymentAuditLog>>,
+        Option<SerializedCursor>,
+    )> {
+        let cursor = cursor
+            .map(|cursor| {
+                self.key_broker
+                    .decrypt_cursor(cursor, self.persistence_version())
+            })
+            .transpose()?;
+        let mut tx = self.begin(identity).await?;
+        let (entries, cursor) = DeploymentAuditLogModel::new(&mut tx)
+            .list(&filter, cursor, limit)
+            .await?;
+        let cursor = cursor.map(|cursor| {
+            self.key_broker
+                .encrypt_cursor(&cursor, self.persistence_version())
+        });
+        Ok((entries, cursor))
     }
 
     /// Commit a transaction and send audit log events to the log manager if the
@@ -2402,6 +2919,10 @@ impl<RT: Runtime> Application<RT> {
         self.search_and_vector_bootstrap_worker.lock().shutdown();
         self.export_worker.lock().shutdown();
         self.snapshot_import_worker.lock().shutdown();
+        self.file_storage_blob_worker.lock().shutdown();
+        self.storage_gc_worker.lock().shutdown();
+        self.ttl_worker.lock().shutdown();
+        self.migration_worker.lock().shutdown();
         self.runner.shutdown().await?;
         self.scheduled_job_runner.shutdown();
         self.cron_job_executor.lock().shutdown();
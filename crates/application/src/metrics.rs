@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use metrics::{
+    log_counter,
     log_counter_with_labels,
     log_distribution,
     log_distribution_with_labels,
@@ -13,6 +14,7 @@ use metrics::{
     STATUS_LABEL,
 };
 use model::source_packages::types::PackageSize;
+use storage::StorageUseCase;
 
 register_convex_counter!(
     EXTERNAL_DEPS_PACKAGES_TOTAL,
@@ -103,3 +105,49 @@ fn log_worker_status(is_working: bool, name: &'static str) {
         vec![MetricLabel::new("worker", name)],
     )
 }
+
+register_convex_gauge!(
+    STORAGE_GC_ORPHANED_BYTES,
+    "Bytes in unreferenced storage objects found by the last storage GC run",
+    &["use_case"],
+);
+register_convex_counter!(
+    STORAGE_GC_DELETED_OBJECTS_TOTAL,
+    "Number of unreferenced storage objects deleted",
+    &["use_case"],
+);
+register_convex_counter!(
+    STORAGE_GC_RECLAIMED_BYTES_TOTAL,
+    "Bytes reclaimed by deleting unreferenced storage objects",
+    &["use_case"],
+);
+pub fn log_storage_gc_orphaned_bytes(use_case: StorageUseCase, bytes: u64) {
+    log_gauge_with_labels(
+        &STORAGE_GC_ORPHANED_BYTES,
+        bytes as f64,
+        vec![MetricLabel::new("use_case", use_case.to_string())],
+    );
+}
+
+pub fn log_storage_gc_deleted(use_case: StorageUseCase, bytes: u64) {
+    let labels = vec![MetricLabel::new("use_case", use_case.to_string())];
+    log_counter_with_labels(&STORAGE_GC_DELETED_OBJECTS_TOTAL, 1, labels.clone());
+    log_counter_with_labels(&STORAGE_GC_RECLAIMED_BYTES_TOTAL, bytes, labels);
+}
+
+register_convex_counter!(
+    TTL_DOCUMENTS_DELETED_TOTAL,
+    "Number of documents deleted for being past their table's TTL",
+);
+pub fn log_ttl_documents_deleted(num_deleted: usize) {
+    log_counter(&TTL_DOCUMENTS_DELETED_TOTAL, num_deleted as u64);
+}
+
+register_convex_histogram!(
+    TTL_WORKER_RUN_SECONDS,
+    "Time taken to delete expired documents from all tables with a TTL",
+    &STATUS_LABEL
+);
+pub fn ttl_worker_run_timer() -> StatusTimer {
+    StatusTimer::new(&TTL_WORKER_RUN_SECONDS)
+}
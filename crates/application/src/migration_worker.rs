@@ -0,0 +1,321 @@
+use std::{
+    num::NonZeroU32,
+    sync::{
+        Arc,
+        LazyLock,
+    },
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    errors::{
+        report_error,
+        JsError,
+    },
+    execution_context::ExecutionContext,
+    pause::PauseClient,
+    runtime::{
+        new_rate_limiter,
+        RateLimiter,
+        Runtime,
+    },
+    types::{
+        AllowedVisibility,
+        FunctionCaller,
+    },
+    RequestId,
+};
+use database::{
+    Database,
+    IndexModel,
+    Transaction,
+};
+use errors::ErrorMetadataAnyhowExt;
+use futures::{
+    pin_mut,
+    Future,
+    TryStreamExt,
+};
+use governor::Quota;
+use keybroker::Identity;
+use model::{
+    backend_state::{
+        types::BackendState,
+        BackendStateModel,
+    },
+    migrations::{
+        types::MigrationState,
+        MigrationModel,
+    },
+};
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    id_v6::DocumentIdV6,
+    ConvexArray,
+    ConvexValue,
+    ResolvedDocumentId,
+};
+
+use crate::{
+    application_function_runner::ApplicationFunctionRunner,
+    metrics::log_worker_starting,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+static TABLE_ITERATOR_RATE_LIMIT: LazyLock<NonZeroU32> =
+    LazyLock::new(|| NonZeroU32::new(1000).unwrap());
+
+/// Runs data migrations: pages through each running migration's table in id
+/// order, running its mutation on every document in bounded transactions and
+/// persisting a cursor after each batch so it can resume where it left off.
+pub struct MigrationWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    runner: Arc<ApplicationFunctionRunner<RT>>,
+    rate_limiter: RateLimiter<RT>,
+}
+
+/// What happened to a migration after running one batch.
+enum BatchOutcome {
+    /// There may be more documents to process.
+    Continue,
+    /// The migration finished, failed, or was paused or canceled.
+    Stopped,
+}
+
+impl<RT: Runtime> MigrationWorker<RT> {
+    pub fn start(
+        runtime: RT,
+        database: Database<RT>,
+        runner: Arc<ApplicationFunctionRunner<RT>>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime: runtime.clone(),
+            database,
+            runner,
+            rate_limiter: new_rate_limiter(runtime, Quota::per_second(*TABLE_ITERATOR_RATE_LIMIT)),
+        };
+        async move {
+            tracing::info!("Starting MigrationWorker");
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            loop {
+                if let Err(e) = worker.run().await {
+                    let delay = worker.runtime.with_rng(|rng| backoff.fail(rng));
+                    report_error(&mut e.context("MigrationWorker died"));
+                    tracing::error!("Migration worker failed, sleeping {delay:?}");
+                    worker.runtime.wait(delay).await;
+                } else {
+                    backoff.reset();
+                }
+            }
+        }
+    }
+
+    /// Runs every running migration to completion, then waits for a migration
+    /// to be started, resumed, or for the backend to be unpaused.
+    pub async fn run(&self) -> anyhow::Result<()> {
+        let status = log_worker_starting("MigrationWorker");
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let running = match BackendStateModel::new(&mut tx).get_backend_state().await? {
+            BackendState::Running => MigrationModel::new(&mut tx)
+                .list()
+                .await?
+                .into_iter()
+                .filter(|migration| migration.state == MigrationState::Running)
+                .collect(),
+            BackendState::Paused | BackendState::Disabled => vec![],
+        };
+        for migration in running {
+            tracing::info!("Running migration {}", migration.name);
+            while let BatchOutcome::Continue = self.run_batch(migration.id()).await? {}
+        }
+        drop(status);
+        let token = tx.into_token()?;
+        let subscription = self.database.subscribe(token).await?;
+        subscription.wait_for_invalidation().await;
+        Ok(())
+    }
+
+    /// Runs the migration's mutation on the next batch of documents after its
+    /// cursor, all in one transaction. Dry runs discard the transaction and
+    /// only record how many documents would have changed.
+    async fn run_batch(&self, id: ResolvedDocumentId) -> anyhow::Result<BatchOutcome> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        // Reading the backend state and migration in the same transaction means
+        // pausing either one conflicts with an in-progress batch.
+        match BackendStateModel::new(&mut tx).get_backend_state().await? {
+            BackendState::Running => {},
+            BackendState::Paused | BackendState::Disabled => return Ok(BatchOutcome::Stopped),
+        }
+        let Some(migration) = MigrationModel::new(&mut tx).get(id).await? else {
+            return Ok(BatchOutcome::Stopped);
+        };
+        if migration.state != MigrationState::Running {
+            return Ok(BatchOutcome::Stopped);
+        }
+        let table_mapping = tx.table_mapping().clone();
+        let Some(table_id) = table_mapping.id_if_exists(&migration.table_name) else {
+            self.fail(id, format!("Table {} was deleted", migration.table_name))
+                .await?;
+            return Ok(BatchOutcome::Stopped);
+        };
+        let cursor = migration
+            .cursor
+            .as_ref()
+            .map(|cursor| cursor.to_resolved(&table_mapping.inject_table_id()))
+            .transpose()?;
+        let by_id_indexes = IndexModel::new(&mut tx).by_id_indexes().await?;
+        let by_id = *by_id_indexes
+            .get(&table_id)
+            .ok_or_else(|| anyhow::anyhow!("Failed to find id index for table id {table_id}"))?;
+
+        let batch_size = migration.batch_size as usize;
+        let table_iterator = self
+            .database
+            .table_iterator(tx.begin_timestamp(), batch_size, None);
+        let stream =
+            table_iterator.stream_documents_in_table(table_id, by_id, cursor, &self.rate_limiter);
+        pin_mut!(stream);
+        let mut batch = vec![];
+        while batch.len() < batch_size {
+            let Some((doc, _)) = stream.try_next().await? else {
+                break;
+            };
+            batch.push(doc.id());
+        }
+        let Some(last_id) = batch.last().copied() else {
+            MigrationModel::new(&mut tx).complete(id).await?;
+            self.database
+                .commit_with_write_source(tx, "migration_worker_complete")
+                .await?;
+            tracing::info!("Migration {} completed", migration.name);
+            return Ok(BatchOutcome::Stopped);
+        };
+
+        let caller = FunctionCaller::Migration;
+        let mut num_changed = 0;
+        for doc_id in &batch {
+            // An earlier mutation in the batch may have modified or deleted
+            // this document, so read its latest value.
+            let Some(before) = tx.get(*doc_id).await? else {
+                continue;
+            };
+            let before = before.into_value().0;
+            let arguments = ConvexArray::try_from(vec![ConvexValue::Object(before.clone())])?;
+            let (new_tx, outcome) = self
+                .runner
+                .run_mutation_no_udf_log(
+                    tx,
+                    migration.udf_path.clone(),
+                    arguments,
+                    AllowedVisibility::All,
+                    ExecutionContext::new(RequestId::new(), &caller),
+                )
+                .await?;
+            tx = new_tx;
+            if let Err(e) = outcome.result {
+                // It isn't safe to commit a transaction where the mutation
+                // failed, so record the failure in a new one.
+                drop(tx);
+                self.fail(id, e.to_string()).await?;
+                return Ok(BatchOutcome::Stopped);
+            }
+            let after = tx.get(*doc_id).await?.map(|doc| doc.into_value().0);
+            if after.as_ref() != Some(&before) {
+                num_changed += 1;
+            }
+        }
+
+        let num_processed = batch.len() as u64;
+        let cursor = last_id.into();
+        if migration.dry_run {
+            drop(tx);
+            self.database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "migration_worker_dry_run",
+                    |tx| {
+                        Self::record_dry_run_progress(tx, id, cursor, num_processed, num_changed)
+                            .into()
+                    },
+                )
+                .await?;
+            return Ok(BatchOutcome::Continue);
+        }
+        MigrationModel::new(&mut tx)
+            .record_progress(id, cursor, num_processed, num_changed)
+            .await?;
+        if let Err(e) = self
+            .database
+            .commit_with_write_source(tx, "migration_worker")
+            .await
+        {
+            if e.is_occ() {
+                // Retry the batch, picking up any change to the migration's
+                // state.
+                return Ok(BatchOutcome::Continue);
+            }
+            if e.is_deterministic_user_error() {
+                self.fail(id, JsError::from_error(e).to_string()).await?;
+                return Ok(BatchOutcome::Stopped);
+            }
+            return Err(e);
+        }
+        Ok(BatchOutcome::Continue)
+    }
+
+    async fn record_dry_run_progress(
+        tx: &mut Transaction<RT>,
+        id: ResolvedDocumentId,
+        cursor: DocumentIdV6,
+        num_processed: u64,
+        num_changed: u64,
+    ) -> anyhow::Result<()> {
+        let mut model = MigrationModel::new(tx);
+        // The migration may have been paused or canceled while the batch ran.
+        if model
+            .get(id)
+            .await?
+            .is_some_and(|migration| migration.state == MigrationState::Running)
+        {
+            model
+                .record_progress(id, cursor, num_processed, num_changed)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn fail(&self, id: ResolvedDocumentId, error: String) -> anyhow::Result<()> {
+        tracing::info!("Migration failed: {error}");
+        self.database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "migration_worker_failed",
+                |tx| Self::fail_inner(tx, id, error.clone()).into(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn fail_inner(
+        tx: &mut Transaction<RT>,
+        id: ResolvedDocumentId,
+        error: String,
+    ) -> anyhow::Result<()> {
+        let mut model = MigrationModel::new(tx);
+        if model
+            .get(id)
+            .await?
+            .is_some_and(|migration| migration.state == MigrationState::Running)
+        {
+            model.fail(id, error).await?;
+        }
+        Ok(())
+    }
+}
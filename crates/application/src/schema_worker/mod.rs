@@ -1,9 +1,11 @@
 use std::{
+    collections::BTreeSet,
     num::NonZeroU32,
     sync::LazyLock,
     time::Duration,
 };
 
+use anyhow::Context;
 use common::{
     backoff::Backoff,
     bootstrap_model::schema::SchemaState,
@@ -13,7 +15,11 @@ use common::{
         RateLimiter,
         Runtime,
     },
-    schemas::DatabaseSchema,
+    schemas::{
+        DatabaseSchema,
+        SchemaValidationError,
+    },
+    types::TableName,
 };
 use database::{
     Database,
@@ -34,6 +40,8 @@ use metrics::{
     log_document_validated,
     schema_validation_timer,
 };
+use model::migrations::MigrationModel;
+use usage_tracking::FunctionUsageTracker;
 
 use crate::metrics::log_worker_starting;
 
@@ -44,7 +52,10 @@ const MAX_BACKOFF: Duration = Duration::from_secs(5);
 const INITIAL_COMMIT_BACKOFF: Duration = Duration::from_millis(10);
 const MAX_COMMIT_BACKOFF: Duration = Duration::from_secs(2);
 const MAX_COMMIT_FAILURES: u32 = 3;
-static TABLE_ITERATOR_RATE_LIMIT: LazyLock<NonZeroU32> =
+/// Foreign key targets are looked up in short-lived transactions so the
+/// lookups for a large table don't exceed the transaction read limits.
+const MAX_FOREIGN_KEY_LOOKUPS_PER_TRANSACTION: usize = 1000;
+pub(crate) static TABLE_ITERATOR_RATE_LIMIT: LazyLock<NonZeroU32> =
     LazyLock::new(|| NonZeroU32::new(1000).unwrap());
 
 pub struct SchemaWorker<RT: Runtime> {
@@ -100,64 +111,88 @@ impl<RT: Runtime> SchemaWorker<RT> {
                 &virtual_table_mapping,
                 &|table_name| snapshot.table_summary(table_name).inferred_type().clone(),
             )?;
-            let ts = tx.begin_timestamp();
-            let by_id_indexes = IndexModel::new(&mut tx).by_id_indexes().await?;
-
-            for table_name in tables_to_check {
-                let table_iterator = self.database.table_iterator(ts, 1000, None);
-                let table_id = table_mapping.id(table_name)?;
-                let stream = table_iterator.stream_documents_in_table(
-                    table_id.table_id,
-                    *by_id_indexes.get(&table_id.table_id).ok_or_else(|| {
-                        anyhow::anyhow!("Failed to find id index for table id {table_id}")
-                    })?,
-                    None,
-                    &self.rate_limiter,
+            // A stricter schema often depends on a data migration backfilling
+            // its tables, so leave the schema pending until every migration
+            // on those tables finishes. Completing a migration invalidates
+            // this transaction's read set, which reruns validation.
+            let migrations = MigrationModel::new(&mut tx).list().await?;
+            if let Some(migration) = migrations.iter().find(|migration| {
+                migration.is_active()
+                    && !migration.dry_run
+                    && tables_to_check.contains(&migration.table_name)
+            }) {
+                tracing::info!(
+                    "Schema validation is waiting for migration {} to finish",
+                    migration.name
                 );
-
-                pin_mut!(stream);
-                while let Some((doc, _ts)) = stream.try_next().await? {
-                    let table_name = table_mapping.tablet_name(doc.table().table_id)?;
-                    log_document_validated();
-                    log_document_bytes(doc.size());
-                    if let Err(schema_error) = db_schema.check_existing_document(
-                        &doc,
-                        table_name,
-                        &tx.table_mapping().clone(),
-                        &tx.virtual_table_mapping().clone(),
-                    ) {
-                        let mut backoff = Backoff::new(INITIAL_COMMIT_BACKOFF, MAX_COMMIT_BACKOFF);
-                        while backoff.failures() < MAX_COMMIT_FAILURES {
-                            let mut tx = self.database.begin(Identity::system()).await?;
-                            SchemaModel::new(&mut tx)
-                                .mark_failed(id, schema_error.clone())
-                                .await?;
-                            if let Err(e) = self
-                                .database
-                                .commit_with_write_source(tx, "schema_worker_mark_failed")
-                                .await
-                            {
-                                if e.is_occ() {
-                                    let delay = self.runtime.with_rng(|rng| backoff.fail(rng));
-                                    tracing::error!(
-                                        "Schema worker failed to commit ({e}), retrying after \
-                                         {delay:?}"
-                                    );
-                                    self.runtime.wait(delay).await;
-                                } else {
-                                    return Err(e);
-                                }
-                            } else {
-                                break;
-                            }
-                        }
-
-                        tracing::info!("Schema is invalid");
-                        timer.finish_developer_error();
+                timer.finish();
+                drop(status);
+                let subscription = self.database.subscribe(tx.into_token()?).await?;
+                subscription.wait_for_invalidation().await;
+                return Ok(());
+            }
+            // Deletes only check a pending schema's foreign keys once their
+            // indexes are enabled, so wait for them before checking existing
+            // documents. Enabling an index invalidates this transaction's
+            // read set, which reruns validation.
+            for table_definition in db_schema.tables.values() {
+                for field in table_definition.foreign_keys.keys() {
+                    let index_name = table_definition.foreign_key_index(field)?;
+                    if IndexModel::new(&mut tx)
+                        .enabled_index_metadata(&index_name)?
+                        .is_none()
+                    {
+                        tracing::info!(
+                            "Schema validation is waiting for foreign key index {index_name} to \
+                             finish backfilling"
+                        );
+                        timer.finish();
+                        drop(status);
+                        let subscription = self.database.subscribe(tx.into_token()?).await?;
+                        subscription.wait_for_invalidation().await;
                         return Ok(());
                     }
                 }
             }
+            let check = check_existing_documents(
+                &self.database,
+                &mut tx,
+                &db_schema,
+                tables_to_check,
+                &self.rate_limiter,
+                None,
+            )
+            .await?;
+            if let ExistingDocumentsCheck::Invalid(schema_error) = check {
+                let mut backoff = Backoff::new(INITIAL_COMMIT_BACKOFF, MAX_COMMIT_BACKOFF);
+                while backoff.failures() < MAX_COMMIT_FAILURES {
+                    let mut tx = self.database.begin(Identity::system()).await?;
+                    SchemaModel::new(&mut tx)
+                        .mark_failed(id, schema_error.clone())
+                        .await?;
+                    if let Err(e) = self
+                        .database
+                        .commit_with_write_source(tx, "schema_worker_mark_failed")
+                        .await
+                    {
+                        if e.is_occ() {
+                            let delay = self.runtime.with_rng(|rng| backoff.fail(rng));
+                            tracing::error!(
+                                "Schema worker failed to commit ({e}), retrying after {delay:?}"
+                            );
+                            self.runtime.wait(delay).await;
+                        } else {
+                            return Err(e);
+                        }
+                    } else {
+                        break;
+                    }
+                }
+
+                tracing::info!("Schema is invalid");
+                timer.finish_developer_error();
+                return Ok(());
+            }
             let mut tx = self.database.begin(Identity::system()).await?;
             if let Err(error) = SchemaModel::new(&mut tx).mark_validated(id).await {
                 if error.is_bad_request() {
@@ -182,8 +217,100 @@ impl<RT: Runtime> SchemaWorker<RT> {
     }
 }
 
+/// The result of checking existing documents against a schema.
+pub enum ExistingDocumentsCheck {
+    Valid,
+    Invalid(SchemaValidationError),
+    /// The document limit was reached before every table was checked.
+    Incomplete,
+}
+
+/// Checks the documents in `tables` at `tx`'s begin timestamp against
+/// `db_schema`, stopping at the first invalid one. With `max_documents`, gives
+/// up after checking that many documents.
+pub async fn check_existing_documents<RT: Runtime>(
+    database: &Database<RT>,
+    tx: &mut Transaction<RT>,
+    db_schema: &DatabaseSchema,
+    tables: BTreeSet<&TableName>,
+    rate_limiter: &RateLimiter<RT>,
+    max_documents: Option<usize>,
+) -> anyhow::Result<ExistingDocumentsCheck> {
+    let ts = tx.begin_timestamp();
+    let table_mapping = tx.table_mapping().clone();
+    let virtual_table_mapping = tx.virtual_table_mapping().clone();
+    let by_id_indexes = IndexModel::new(tx).by_id_indexes().await?;
+    let mut num_documents = 0;
+    let mut foreign_key_tx = None;
+    let mut num_foreign_key_lookups = 0;
+    for table_name in tables {
+        let table_iterator = database.table_iterator(ts, 1000, None);
+        let table_id = table_mapping.id(table_name)?;
+        let stream = table_iterator.stream_documents_in_table(
+            table_id.table_id,
+            *by_id_indexes.get(&table_id.table_id).ok_or_else(|| {
+                anyhow::anyhow!("Failed to find id index for table id {table_id}")
+            })?,
+            None,
+            rate_limiter,
+        );
+
+        pin_mut!(stream);
+        while let Some((doc, _ts)) = stream.try_next().await? {
+            if let Some(max_documents) = max_documents
+                && num_documents >= max_documents
+            {
+                return Ok(ExistingDocumentsCheck::Incomplete);
+            }
+            num_documents += 1;
+            let table_name = table_mapping.tablet_name(doc.table().table_id)?;
+            log_document_validated();
+            log_document_bytes(doc.size());
+            if let Err(schema_error) = db_schema.check_existing_document(
+                &doc,
+                table_name,
+                &table_mapping,
+                &virtual_table_mapping,
+            ) {
+                return Ok(ExistingDocumentsCheck::Invalid(schema_error));
+            }
+            let Some(table_definition) = db_schema.tables.get(table_name) else {
+                continue;
+            };
+            for (field, foreign_key) in &table_definition.foreign_keys {
+                if num_foreign_key_lookups % MAX_FOREIGN_KEY_LOOKUPS_PER_TRANSACTION == 0 {
+                    foreign_key_tx = Some(
+                        database
+                            .begin_with_ts(Identity::system(), *ts, FunctionUsageTracker::new())
+                            .await?,
+                    );
+                }
+                num_foreign_key_lookups += 1;
+                let lookup_tx = foreign_key_tx
+                    .as_mut()
+                    .context("Missing foreign key lookup transaction")?;
+                if !SchemaModel::new(lookup_tx)
+                    .references_existing_document(doc.value().get_path(field), &foreign_key.table)
+                    .await?
+                {
+                    return Ok(ExistingDocumentsCheck::Invalid(
+                        SchemaValidationError::ForeignKeyViolation {
+                            table_name: table_name.clone(),
+                            id: (*doc.id()).into(),
+                            field: field.clone(),
+                            referenced_table: foreign_key.table.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+    }
+    Ok(ExistingDocumentsCheck::Valid)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
 
     use common::{
         assert_obj,
@@ -193,6 +320,7 @@ mod tests {
         },
         db_schema,
         object_validator,
+        persistence::NoopRetentionValidator,
         runtime::new_rate_limiter,
         schemas::{
             validator::{
@@ -201,11 +329,18 @@ mod tests {
             },
             DatabaseSchema,
             DocumentSchema,
+            ForeignKey,
+            OnDelete,
             TableDefinition,
         },
     };
     use database::{
-        test_helpers::new_test_database,
+        test_helpers::{
+            new_test_database,
+            DbFixtures,
+        },
+        IndexModel,
+        IndexWorker,
         SchemaModel,
         UserFacingModel,
     };
@@ -234,6 +369,8 @@ mod tests {
             search_indexes: btreemap! {},
             vector_indexes: btreemap! {},
             document_type: Some(DocumentSchema::Any),
+            ttl: None,
+            foreign_keys: btreemap! {},
         };
         let db_schema = DatabaseSchema {
             tables: btreemap! { table_name.clone() => table_definition },
@@ -272,4 +409,93 @@ mod tests {
         assert!(matches!(schema.state, SchemaState::Failed { .. }));
         Ok(())
     }
+    #[convex_macro::test_runtime]
+    async fn test_schema_validation_foreign_keys(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+        let schema_worker = SchemaWorker {
+            runtime: rt.clone(),
+            database: db.clone(),
+            rate_limiter: new_rate_limiter(
+                rt.clone(),
+                Quota::per_second(*TABLE_ITERATOR_RATE_LIMIT),
+            ),
+        };
+        let users = "users".parse::<TableName>()?;
+        let messages = "messages".parse::<TableName>()?;
+        let schema_with_foreign_key = || DatabaseSchema {
+            tables: btreemap! {
+                messages.clone() => TableDefinition {
+                    table_name: messages.clone(),
+                    indexes: btreemap! {},
+                    search_indexes: btreemap! {},
+                    vector_indexes: btreemap! {},
+                    document_type: None,
+                    ttl: None,
+                    foreign_keys: btreemap! {
+                        "author".parse().unwrap() => ForeignKey {
+                            table: users.clone(),
+                            on_delete: OnDelete::Restrict,
+                        },
+                    },
+                },
+            },
+            // Foreign keys are checked even without schema validation.
+            schema_validation: false,
+        };
+
+        // Validation waits for the foreign key's index.
+        let mut tx = db.begin(Identity::system()).await?;
+        IndexModel::new(&mut tx)
+            .build_indexes(&schema_with_foreign_key())
+            .await?;
+        db.commit(tx).await?;
+        IndexWorker::new_terminating(rt, tp, Arc::new(NoopRetentionValidator), db.clone()).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let alice = UserFacingModel::new(&mut tx)
+            .insert(users.clone(), assert_obj!())
+            .await?;
+        let bob = UserFacingModel::new(&mut tx)
+            .insert(users.clone(), assert_obj!())
+            .await?;
+        UserFacingModel::new(&mut tx)
+            .insert(messages.clone(), assert_obj!("author" => alice))
+            .await?;
+        UserFacingModel::new(&mut tx)
+            .insert(messages.clone(), assert_obj!("author" => null))
+            .await?;
+        let message = UserFacingModel::new(&mut tx)
+            .insert(messages.clone(), assert_obj!("author" => bob))
+            .await?;
+        let (id, _) = SchemaModel::new(&mut tx)
+            .submit_pending(schema_with_foreign_key())
+            .await?;
+        db.commit(tx).await?;
+
+        // Every message references an existing user or null.
+        schema_worker.run().await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let doc = tx.get(id).await?.unwrap();
+        let schema: SchemaMetadata = doc.into_value().into_value().try_into()?;
+        assert_eq!(schema.state, SchemaState::Validated);
+
+        // Deleting a referenced user without an active schema leaves a
+        // dangling reference, so the schema can't be validated anymore.
+        UserFacingModel::new(&mut tx).delete(bob).await?;
+        let (id, _) = SchemaModel::new(&mut tx)
+            .submit_pending(schema_with_foreign_key())
+            .await?;
+        db.commit(tx).await?;
+        schema_worker.run().await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let doc = tx.get(id).await?.unwrap();
+        let schema: SchemaMetadata = doc.into_value().into_value().try_into()?;
+        let SchemaState::Failed { error, .. } = schema.state else {
+            anyhow::bail!("Expected the schema to fail, got {:?}", schema.state);
+        };
+        assert!(error.contains(&message.encode()), "{error}");
+        assert!(error.contains("table \"users\""), "{error}");
+        Ok(())
+    }
 }
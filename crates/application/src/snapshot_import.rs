@@ -103,6 +103,7 @@ use model::{
     },
     file_storage::{
         types::StorageUuid,
+        FileStorageModel,
         FILE_STORAGE_TABLE,
         FILE_STORAGE_VIRTUAL_TABLE,
     },
@@ -1133,9 +1134,17 @@ pub async fn store_uploaded_import<RT: Runtime>(
             |tx| {
                 async {
                     let mut model = SnapshotImportModel::new(tx);
-                    model
+                    let import_id = model
                         .start_import(format.clone(), mode, object_key.clone())
-                        .await
+                        .await?;
+                    DeploymentAuditLogModel::new(tx)
+                        .insert(vec![DeploymentAuditLogEvent::RequestImport {
+                            import_id: import_id.into(),
+                            import_mode: mode,
+                            import_format: format.clone(),
+                        }])
+                        .await?;
+                    Ok(import_id)
                 }
                 .into()
             },
@@ -1527,6 +1536,16 @@ async fn finalize_import<RT: Runtime>(
                 async {
                     let mut documents_deleted = 0;
                     schema_constraints.validate(tx).await?;
+                    // Replacing `_storage` drops its existing entries, so release the
+                    // deduplicated blobs they reference.
+                    if let Some(imported_table_id) =
+                        table_mapping_for_import.id_if_exists(&FILE_STORAGE_TABLE)
+                        && let Some(existing_table_id) =
+                            tx.table_mapping().id_if_exists(&FILE_STORAGE_TABLE)
+                        && imported_table_id != existing_table_id
+                    {
+                        FileStorageModel::new(tx).release_all_blobs().await?;
+                    }
                     let mut table_model = TableModel::new(tx);
                     for (table_id, table_number, table_name) in table_mapping_for_import.iter() {
                         documents_deleted += table_model
@@ -1763,8 +1782,14 @@ async fn import_storage_table<RT: Runtime>(
                                 &table_mapping,
                                 tx.virtual_table_mapping(),
                             )?;
-                        let mut entry_object_map =
-                            BTreeMap::from(ConvexObject::try_from(entry.clone())?);
+                        // Deduplicate imported files just like uploads, taking the blob
+                        // reference in the same transaction that inserts the entry.
+                        let mut entry = entry.clone();
+                        file_storage
+                            .transactional_file_storage
+                            .reference_blob(tx, &mut entry)
+                            .await?;
+                        let mut entry_object_map = BTreeMap::from(ConvexObject::try_from(entry)?);
                         entry_object_map.insert(ID_FIELD.clone().into(), val!(physical_id));
                         if let Some(creation_time) = creation_time {
                             entry_object_map.insert(
@@ -2307,6 +2332,8 @@ mod tests {
         UserFacingModel,
     };
     use errors::ErrorMetadataAnyhowExt;
+    use events::usage::NoOpUsageEventLogger;
+    use file_storage::FileStorage;
     use futures::{
         pin_mut,
         stream::{
@@ -2322,7 +2349,13 @@ mod tests {
         Identity,
     };
     use maplit::btreemap;
-    use model::snapshot_imports::types::ImportState;
+    use model::{
+        file_storage::{
+            FileStorageId,
+            FileStorageModel,
+        },
+        snapshot_imports::types::ImportState,
+    };
     use must_let::must_let;
     use runtime::testing::TestRuntime;
     use serde_json::{
@@ -2336,7 +2369,10 @@ mod tests {
         StorageUseCase,
         Upload,
     };
-    use usage_tracking::FunctionUsageTracker;
+    use usage_tracking::{
+        FunctionUsageTracker,
+        UsageCounter,
+    };
     use value::{
         assert_obj,
         assert_val,
@@ -2348,8 +2384,11 @@ mod tests {
 
     use super::{
         do_import,
+        finalize_import,
         import_objects,
         parse_objects,
+        schemas_for_import,
+        DeploymentAuditLogEvent,
         ImportFormat,
         ImportMode,
         ImportUnit,
@@ -2994,6 +3033,85 @@ a
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_replace_storage_releases_deduplicated_blobs(
+        rt: TestRuntime,
+    ) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let file_storage = FileStorage {
+            database: app.database.clone(),
+            transactional_file_storage: app
+                .file_storage
+                .transactional_file_storage
+                .clone()
+                .with_deduplication(true),
+        };
+        let existing_id = file_storage
+            .store_file(
+                None,
+                None,
+                stream::iter([Ok(b"existing".to_vec())]),
+                None,
+                &UsageCounter::new(Arc::new(NoOpUsageEventLogger)),
+            )
+            .await?;
+        let mut tx = app.begin(new_admin_id()).await?;
+        let existing_entry = file_storage
+            .transactional_file_storage
+            .get_file_entry(&mut tx, FileStorageId::DocumentId(existing_id))
+            .await?
+            .context("file not found")?;
+        assert!(FileStorageModel::new(&mut tx)
+            .unreferenced_blobs(10)
+            .await?
+            .is_empty());
+
+        let storage_id = "kg21pzwemsm55e1fnt2kcsvgjh6h6gtf";
+        let objects = stream::iter(vec![
+            Ok(ImportUnit::NewTable("_storage".parse()?)),
+            Ok(ImportUnit::Object(json!({"_id": storage_id}))),
+            Ok(ImportUnit::StorageFileChunk(
+                DocumentIdV6::decode(storage_id)?,
+                Bytes::from_static(b"imported"),
+            )),
+        ])
+        .boxed()
+        .peekable();
+        let initial_schemas = schemas_for_import(&mut tx).await?;
+        let (table_mapping_for_import, _) = import_objects(
+            &app.database,
+            &file_storage,
+            new_admin_id(),
+            ImportMode::Replace,
+            objects,
+            FunctionUsageTracker::new(),
+            None,
+        )
+        .await?;
+        finalize_import(
+            &app.database,
+            &app.usage_tracking,
+            new_admin_id(),
+            None,
+            initial_schemas,
+            table_mapping_for_import,
+            FunctionUsageTracker::new(),
+            DeploymentAuditLogEvent::ClearTables,
+        )
+        .await?;
+
+        // The replaced entry's blob is released, while the imported file keeps
+        // its own.
+        let mut tx = app.begin(new_admin_id()).await?;
+        let unreferenced = FileStorageModel::new(&mut tx)
+            .unreferenced_blobs(10)
+            .await?;
+        assert_eq!(unreferenced.len(), 1);
+        assert_eq!(unreferenced[0].storage_key, existing_entry.storage_key);
+        assert_eq!(file_storage.delete_unreferenced_blobs().await?, 1);
+        Ok(())
+    }
+
     async fn activate_schema<RT: Runtime>(
         app: &Application<RT>,
         schema: DatabaseSchema,
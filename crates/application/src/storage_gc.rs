@@ -0,0 +1,637 @@
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    bootstrap_model::index::{
+        search_index::{
+            SearchIndexSnapshotData,
+            SearchIndexState,
+        },
+        IndexConfig,
+    },
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    errors::report_error,
+    knobs::{
+        STORAGE_GC_DRY_RUN,
+        STORAGE_GC_GRACE_PERIOD,
+        STORAGE_GC_INTERVAL,
+    },
+    runtime::{
+        new_rate_limiter,
+        Runtime,
+    },
+    types::{
+        IndexId,
+        ObjectKey,
+        RepeatableTimestamp,
+        TableName,
+    },
+};
+use database::{
+    Database,
+    IndexModel,
+    Transaction,
+};
+use futures::{
+    pin_mut,
+    Future,
+    TryStreamExt,
+};
+use governor::Quota;
+use keybroker::Identity;
+use model::{
+    exports::{
+        types::{
+            Export,
+            ExportObjectKeys,
+        },
+        EXPORTS_TABLE,
+    },
+    external_packages::{
+        types::ExternalDepsPackage,
+        EXTERNAL_PACKAGES_TABLE,
+    },
+    file_storage::{
+        types::{
+            FileStorageBlob,
+            FileStorageEntry,
+        },
+        FILE_STORAGE_BLOBS_TABLE,
+        FILE_STORAGE_TABLE,
+    },
+    snapshot_imports::{
+        types::SnapshotImport,
+        SNAPSHOT_IMPORTS_TABLE,
+    },
+    source_packages::{
+        types::SourcePackage,
+        SOURCE_PACKAGES_TABLE,
+    },
+};
+use storage::{
+    Storage,
+    StorageUseCase,
+};
+use value::{
+    TableId,
+    TableMapping,
+};
+
+use crate::metrics::{
+    log_storage_gc_deleted,
+    log_storage_gc_orphaned_bytes,
+    log_worker_starting,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(900); // 15 minutes
+
+/// Finds storage objects that no metadata refers to, e.g. from aborted
+/// uploads, expired exports or compacted search segments, and deletes them.
+#[derive(Clone)]
+pub struct StorageGarbageCollector<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    storages: Vec<(StorageUseCase, Arc<dyn Storage>)>,
+}
+
+/// The result of a storage garbage collection pass over one use case.
+pub struct StorageGcUseCaseReport {
+    pub use_case: StorageUseCase,
+    pub num_objects: usize,
+    /// Unreferenced objects older than the grace period.
+    pub orphaned_objects: Vec<ObjectKey>,
+    pub orphaned_bytes: u64,
+    /// Whether the orphaned objects were deleted, as opposed to just reported.
+    pub deleted: bool,
+    /// Set if we couldn't determine which objects are referenced, in which
+    /// case nothing is reported or deleted.
+    pub skipped_reason: Option<String>,
+}
+
+impl<RT: Runtime> StorageGarbageCollector<RT> {
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        storages: Vec<(StorageUseCase, Arc<dyn Storage>)>,
+    ) -> Self {
+        Self {
+            runtime,
+            database,
+            storages,
+        }
+    }
+
+    /// Periodically delete orphaned objects, or only report them if
+    /// `STORAGE_GC_DRY_RUN` is set.
+    pub fn start(self) -> impl Future<Output = ()> + Send {
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        async move {
+            loop {
+                self.runtime.wait(*STORAGE_GC_INTERVAL).await;
+                let _status = log_worker_starting("StorageGarbageCollector");
+                match self.collect(*STORAGE_GC_DRY_RUN).await {
+                    Ok(reports) => {
+                        backoff.reset();
+                        for report in reports {
+                            tracing::info!(
+                                "Storage GC for {}: {} of {} objects orphaned ({} bytes), \
+                                 deleted: {}",
+                                report.use_case,
+                                report.orphaned_objects.len(),
+                                report.num_objects,
+                                report.orphaned_bytes,
+                                report.deleted,
+                            );
+                        }
+                    },
+                    Err(e) => {
+                        report_error(&mut e.context("StorageGarbageCollector died"));
+                        let delay = self.runtime.with_rng(|rng| backoff.fail(rng));
+                        self.runtime.wait(delay).await;
+                    },
+                }
+            }
+        }
+    }
+
+    /// Find objects in each storage that aren't referenced by any metadata and
+    /// are older than the grace period, deleting them unless `dry_run` is set.
+    pub async fn collect(&self, dry_run: bool) -> anyhow::Result<Vec<StorageGcUseCaseReport>> {
+        let mut reports = vec![];
+        for (use_case, storage) in &self.storages {
+            reports.push(self.collect_use_case(*use_case, storage, dry_run).await?);
+        }
+        Ok(reports)
+    }
+
+    async fn collect_use_case(
+        &self,
+        use_case: StorageUseCase,
+        storage: &Arc<dyn Storage>,
+        dry_run: bool,
+    ) -> anyhow::Result<StorageGcUseCaseReport> {
+        // List objects before reading the metadata, so any object whose metadata
+        // is committed in between is seen as referenced.
+        let objects = storage.list_objects().await?;
+        let mut report = StorageGcUseCaseReport {
+            use_case,
+            num_objects: objects.len(),
+            orphaned_objects: vec![],
+            orphaned_bytes: 0,
+            deleted: false,
+            skipped_reason: None,
+        };
+        let referenced = match self.referenced_object_keys(use_case).await {
+            Ok(referenced) => referenced,
+            Err(e) => {
+                tracing::warn!("Skipping storage GC for {use_case}: {e:#}");
+                report.skipped_reason = Some(format!("{e:#}"));
+                return Ok(report);
+            },
+        };
+        let now = self.runtime.system_time();
+        let orphaned: Vec<_> = objects
+            .into_iter()
+            .filter(|object| !referenced.contains(&object.key))
+            .filter(|object| {
+                now.duration_since(object.last_modified)
+                    .is_ok_and(|age| age > *STORAGE_GC_GRACE_PERIOD)
+            })
+            .collect();
+        report.orphaned_bytes = orphaned.iter().map(|object| object.size).sum();
+        log_storage_gc_orphaned_bytes(use_case, report.orphaned_bytes);
+        if !dry_run {
+            for object in &orphaned {
+                storage.delete_object(&object.key).await?;
+                log_storage_gc_deleted(use_case, object.size);
+            }
+            report.deleted = true;
+        }
+        report.orphaned_objects = orphaned.into_iter().map(|object| object.key).collect();
+        Ok(report)
+    }
+
+    /// All object keys in `use_case`'s storage that metadata refers to, as of
+    /// the latest snapshot.
+    async fn referenced_object_keys(
+        &self,
+        use_case: StorageUseCase,
+    ) -> anyhow::Result<BTreeSet<ObjectKey>> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let snapshot_ts = tx.begin_timestamp();
+        let indexes = IndexModel::new(&mut tx).get_all_indexes().await?;
+        let tables = TableScanner::new(&self.database, &self.runtime, &mut tx).await?;
+
+        let mut keys = BTreeSet::new();
+        match use_case {
+            StorageUseCase::Files => {
+                tables
+                    .for_each_document(&FILE_STORAGE_TABLE, |doc| {
+                        let entry: ParsedDocument<FileStorageEntry> = doc.try_into()?;
+                        keys.insert(entry.into_value().storage_key);
+                        Ok(())
+                    })
+                    .await?;
+                // Unreferenced blobs are deleted by the `FileStorageBlobWorker`,
+                // so leave them to it.
+                tables
+                    .for_each_document(&FILE_STORAGE_BLOBS_TABLE, |doc| {
+                        let blob: ParsedDocument<FileStorageBlob> = doc.try_into()?;
+                        keys.insert(blob.into_value().storage_key);
+                        Ok(())
+                    })
+                    .await?;
+            },
+            StorageUseCase::Modules => {
+                tables
+                    .for_each_document(&SOURCE_PACKAGES_TABLE, |doc| {
+                        let package: ParsedDocument<SourcePackage> = doc.try_into()?;
+                        keys.insert(package.into_value().storage_key);
+                        Ok(())
+                    })
+                    .await?;
+                tables
+                    .for_each_document(&EXTERNAL_PACKAGES_TABLE, |doc| {
+                        let package: ParsedDocument<ExternalDepsPackage> = doc.try_into()?;
+                        keys.insert(package.into_value().storage_key);
+                        Ok(())
+                    })
+                    .await?;
+            },
+            StorageUseCase::Exports => {
+                let now: u64 = (*snapshot_ts).into();
+                tables
+                    .for_each_document(&EXPORTS_TABLE, |doc| {
+                        let export: ParsedDocument<Export> = doc.try_into()?;
+                        let Export::Completed {
+                            expiration_ts,
+                            object_keys,
+                            ..
+                        } = export.into_value()
+                        else {
+                            return Ok(());
+                        };
+                        if expiration_ts <= now {
+                            return Ok(());
+                        }
+                        match object_keys {
+                            ExportObjectKeys::ByTable(tables) => keys.extend(tables.into_values()),
+                            ExportObjectKeys::Zip(object_key) => {
+                                keys.insert(object_key);
+                            },
+                        }
+                        Ok(())
+                    })
+                    .await?;
+            },
+            StorageUseCase::SnapshotImports => {
+                tables
+                    .for_each_document(&SNAPSHOT_IMPORTS_TABLE, |doc| {
+                        let snapshot_import: ParsedDocument<SnapshotImport> = doc.try_into()?;
+                        keys.insert(snapshot_import.into_value().object_key);
+                        Ok(())
+                    })
+                    .await?;
+            },
+            StorageUseCase::SearchIndexes => {
+                for index in indexes {
+                    match index.into_value().config {
+                        IndexConfig::Database { .. } => (),
+                        IndexConfig::Search { on_disk_state, .. } => match on_disk_state {
+                            SearchIndexState::Backfilling => (),
+                            SearchIndexState::Backfilled(snapshot)
+                            | SearchIndexState::SnapshottedAt(snapshot) => match snapshot.data {
+                                SearchIndexSnapshotData::SingleSegment(key) => {
+                                    keys.insert(key);
+                                },
+                                SearchIndexSnapshotData::MultiSegment(segments) => {
+                                    for segment in segments {
+                                        keys.insert(segment.segment_key);
+                                        keys.insert(segment.id_tracker_key);
+                                        keys.insert(segment.deleted_bitset_and_stats_key);
+                                    }
+                                },
+                                SearchIndexSnapshotData::Unknown(_) => {
+                                    anyhow::bail!("Search index has unknown snapshot data")
+                                },
+                            },
+                        },
+                        IndexConfig::Vector { on_disk_state, .. } => {
+                            for segment in on_disk_state.segments()? {
+                                keys.insert(segment.segment_key.clone());
+                                keys.insert(segment.id_tracker_key.clone());
+                                keys.insert(segment.deleted_bitset_key.clone());
+                            }
+                        },
+                    }
+                }
+            },
+        }
+        Ok(keys)
+    }
+}
+
+/// Reads system tables at a snapshot without loading them into a transaction.
+struct TableScanner<'a, RT: Runtime> {
+    database: &'a Database<RT>,
+    runtime: &'a RT,
+    snapshot_ts: RepeatableTimestamp,
+    table_mapping: TableMapping,
+    by_id_indexes: BTreeMap<TableId, IndexId>,
+}
+
+impl<'a, RT: Runtime> TableScanner<'a, RT> {
+    /// Scan tables as of `tx`'s begin timestamp.
+    async fn new(
+        database: &'a Database<RT>,
+        runtime: &'a RT,
+        tx: &mut Transaction<RT>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            database,
+            runtime,
+            snapshot_ts: tx.begin_timestamp(),
+            by_id_indexes: IndexModel::new(tx).by_id_indexes().await?,
+            table_mapping: tx.table_mapping().clone(),
+        })
+    }
+
+    /// Calls `f` with each document in every table named `table_name`, one at
+    /// a time so whole tables aren't held in memory. This includes hidden
+    /// tables that an in-progress snapshot import is writing to.
+    async fn for_each_document(
+        &self,
+        table_name: &TableName,
+        mut f: impl FnMut(ResolvedDocument) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let rate_limiter =
+            new_rate_limiter(self.runtime.clone(), Quota::per_second(1000.try_into()?));
+        let table_ids: Vec<_> = self
+            .table_mapping
+            .iter()
+            .filter(|(_, _, name)| *name == table_name)
+            .map(|(table_id, ..)| table_id)
+            .collect();
+        for table_id in table_ids {
+            let by_id = self
+                .by_id_indexes
+                .get(&table_id)
+                .ok_or_else(|| anyhow::anyhow!("no by_id index for {} found", table_id))?;
+            let table_iterator = self.database.table_iterator(self.snapshot_ts, 1000, None);
+            let stream =
+                table_iterator.stream_documents_in_table(table_id, *by_id, None, &rate_limiter);
+            pin_mut!(stream);
+            while let Some((doc, _ts)) = stream.try_next().await? {
+                f(doc)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::Arc,
+        time::SystemTime,
+    };
+
+    use bytes::Bytes;
+    use common::{
+        knobs::STORAGE_GC_GRACE_PERIOD,
+        runtime::Runtime,
+        types::{
+            ObjectKey,
+            TableName,
+        },
+    };
+    use database::{
+        test_helpers::DbFixtures,
+        Database,
+        ImportFacingModel,
+        TableModel,
+        UserFacingModel,
+    };
+    use events::usage::NoOpUsageEventLogger;
+    use file_storage::{
+        FileStorage,
+        TransactionalFileStorage,
+    };
+    use futures::stream;
+    use keybroker::Identity;
+    use maplit::btreeset;
+    use model::{
+        file_storage::FileStorageId,
+        test_helpers::DbFixturesWithModel,
+    };
+    use runtime::testing::TestRuntime;
+    use storage::{
+        LocalDirStorage,
+        Storage,
+        StorageUseCase,
+        Upload,
+    };
+    use usage_tracking::UsageCounter;
+    use value::{
+        assert_obj,
+        ConvexValue,
+    };
+
+    use super::{
+        StorageGarbageCollector,
+        TableScanner,
+    };
+
+    struct GcFixture {
+        gc: StorageGarbageCollector<TestRuntime>,
+        storage: Arc<dyn Storage>,
+        referenced: ObjectKey,
+        orphaned: ObjectKey,
+    }
+
+    /// Stores a file through file storage, whose object is referenced by
+    /// `_file_storage`, and uploads an object that nothing refers to.
+    async fn setup_gc(rt: &TestRuntime) -> anyhow::Result<GcFixture> {
+        let database = DbFixtures::new(rt).await?.with_model().await?.db;
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage = FileStorage {
+            database: database.clone(),
+            transactional_file_storage: TransactionalFileStorage::new(
+                rt.clone(),
+                storage.clone(),
+                "http://127.0.0.1:8000".into(),
+            ),
+        };
+        let id = file_storage
+            .store_file(
+                None,
+                None,
+                stream::iter([Ok(b"referenced".to_vec())]),
+                None,
+                &UsageCounter::new(Arc::new(NoOpUsageEventLogger)),
+            )
+            .await?;
+        let mut tx = database.begin(Identity::system()).await?;
+        let referenced = file_storage
+            .transactional_file_storage
+            .get_file_entry(&mut tx, FileStorageId::DocumentId(id))
+            .await?
+            .unwrap()
+            .storage_key;
+
+        let mut upload = storage.start_upload().await?;
+        upload.write(Bytes::from_static(b"orphaned")).await?;
+        let orphaned = upload.complete().await?;
+
+        let gc = StorageGarbageCollector::new(
+            rt.clone(),
+            database,
+            vec![(StorageUseCase::Files, storage.clone())],
+        );
+        Ok(GcFixture {
+            gc,
+            storage,
+            referenced,
+            orphaned,
+        })
+    }
+
+    /// Move the test runtime's clock past the grace period of objects written
+    /// to disk just now.
+    fn advance_past_grace_period(rt: &TestRuntime) {
+        let behind = SystemTime::now()
+            .duration_since(rt.system_time())
+            .unwrap_or_default();
+        rt.advance_time(behind + *STORAGE_GC_GRACE_PERIOD * 2);
+    }
+
+    async fn object_keys(storage: &Arc<dyn Storage>) -> anyhow::Result<BTreeSet<ObjectKey>> {
+        Ok(storage
+            .list_objects()
+            .await?
+            .into_iter()
+            .map(|object| object.key)
+            .collect())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_referenced_object_keys(rt: TestRuntime) -> anyhow::Result<()> {
+        let fixture = setup_gc(&rt).await?;
+        let referenced = fixture
+            .gc
+            .referenced_object_keys(StorageUseCase::Files)
+            .await?;
+        assert_eq!(referenced, btreeset! { fixture.referenced });
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_collect_keeps_recent_objects(rt: TestRuntime) -> anyhow::Result<()> {
+        let fixture = setup_gc(&rt).await?;
+        rt.advance_time(*STORAGE_GC_GRACE_PERIOD / 2);
+        let reports = fixture.gc.collect(false).await?;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].num_objects, 2);
+        assert!(reports[0].orphaned_objects.is_empty());
+        assert_eq!(
+            object_keys(&fixture.storage).await?,
+            btreeset! { fixture.referenced, fixture.orphaned }
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_collect_dry_run(rt: TestRuntime) -> anyhow::Result<()> {
+        let fixture = setup_gc(&rt).await?;
+        advance_past_grace_period(&rt);
+        let reports = fixture.gc.collect(true).await?;
+        assert_eq!(reports[0].orphaned_objects, vec![fixture.orphaned.clone()]);
+        assert_eq!(reports[0].orphaned_bytes, 8);
+        assert!(!reports[0].deleted);
+        assert_eq!(
+            object_keys(&fixture.storage).await?,
+            btreeset! { fixture.referenced, fixture.orphaned }
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_collect_deletes_orphans(rt: TestRuntime) -> anyhow::Result<()> {
+        let fixture = setup_gc(&rt).await?;
+        advance_past_grace_period(&rt);
+        let reports = fixture.gc.collect(false).await?;
+        assert_eq!(reports[0].orphaned_objects, vec![fixture.orphaned]);
+        assert!(reports[0].deleted);
+        assert_eq!(
+            object_keys(&fixture.storage).await?,
+            btreeset! { fixture.referenced }
+        );
+
+        // Nothing is left to collect.
+        let reports = fixture.gc.collect(false).await?;
+        assert!(reports[0].orphaned_objects.is_empty());
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_table_scanner_includes_hidden_tables(rt: TestRuntime) -> anyhow::Result<()> {
+        let database: Database<TestRuntime> = DbFixtures::new(&rt).await?.with_model().await?.db;
+        let table_name: TableName = "messages".parse()?;
+        let mut tx = database.begin(Identity::system()).await?;
+        UserFacingModel::new(&mut tx)
+            .insert(table_name.clone(), assert_obj!("text" => "active"))
+            .await?;
+        database.commit(tx).await?;
+
+        // Start importing into a hidden table that will replace `messages`.
+        let mut tx = database.begin(Identity::system()).await?;
+        let hidden_table_id = TableModel::new(&mut tx)
+            .insert_table_for_import(&table_name, None, &btreeset! { table_name.clone() })
+            .await?;
+        let mut table_mapping_for_schema = tx.table_mapping().clone();
+        table_mapping_for_schema.insert(
+            hidden_table_id.table_id,
+            hidden_table_id.table_number,
+            table_name.clone(),
+        );
+        ImportFacingModel::new(&mut tx)
+            .insert(
+                hidden_table_id,
+                &table_name,
+                assert_obj!("text" => "importing"),
+                &table_mapping_for_schema,
+            )
+            .await?;
+        database.commit(tx).await?;
+
+        let mut tx = database.begin(Identity::system()).await?;
+        let scanner = TableScanner::new(&database, &rt, &mut tx).await?;
+        let texts: BTreeSet<_> = scanner
+            .documents(&table_name)
+            .await?
+            .into_iter()
+            .map(|doc| doc.value().0.get("text").cloned())
+            .collect();
+        assert_eq!(
+            texts,
+            btreeset! {
+                Some(ConvexValue::try_from("active".to_string())?),
+                Some(ConvexValue::try_from("importing".to_string())?),
+            }
+        );
+        assert!(scanner.documents(&"empty".parse()?).await?.is_empty());
+        Ok(())
+    }
+}
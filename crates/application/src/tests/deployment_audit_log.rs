@@ -0,0 +1,160 @@
+use common::types::MemberId;
+use database::TestFacingModel;
+use keybroker::{
+    AdminIdentity,
+    Identity,
+};
+use model::deployment_audit_log::{
+    types::DeploymentAuditLogEvent,
+    DeploymentAuditLogFilter,
+};
+use runtime::testing::TestRuntime;
+use value::{
+    obj,
+    TableName,
+};
+
+use crate::{
+    test_helpers::ApplicationTestExt,
+    Application,
+};
+
+fn admin_identity(member_id: u64) -> Identity {
+    Identity::InstanceAdmin(AdminIdentity::new_for_test_only(
+        "test".to_string(),
+        MemberId(member_id),
+    ))
+}
+
+#[convex_macro::test_runtime]
+async fn test_delete_tables_is_audit_logged(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    let table_name: TableName = "messages".parse()?;
+    let mut tx = application.begin(Identity::system()).await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, obj!("text" => "hello")?)
+        .await?;
+    application.commit_test(tx).await?;
+
+    application
+        .delete_tables(&admin_identity(1), vec![table_name.clone()])
+        .await?;
+
+    let (entries, _) = application
+        .list_deployment_audit_log(
+            admin_identity(1),
+            DeploymentAuditLogFilter {
+                action: Some("delete_tables".to_string()),
+                ..Default::default()
+            },
+            None,
+            10,
+        )
+        .await?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].member_id, Some(MemberId(1)));
+    must_let::must_let!(
+        let DeploymentAuditLogEvent::DeleteTables {
+            table_names,
+            document_count,
+        } = &entries[0].event
+    );
+    assert_eq!(table_names, &vec![table_name]);
+    assert_eq!(*document_count, 1);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_list_deployment_audit_log(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    for member_id in [1, 2, 1] {
+        let tx = application.begin(admin_identity(member_id)).await?;
+        application
+            .commit_with_audit_log_events(tx, vec![DeploymentAuditLogEvent::ClearTables], "test")
+            .await?;
+    }
+
+    // Filter by member, paging one entry at a time.
+    let filter = DeploymentAuditLogFilter {
+        member_id: Some(MemberId(1)),
+        ..Default::default()
+    };
+    let mut entries = vec![];
+    let mut cursor = None;
+    loop {
+        let (page, next_cursor) = application
+            .list_deployment_audit_log(admin_identity(1), filter.clone(), cursor, 1)
+            .await?;
+        entries.extend(page);
+        match next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => break,
+        }
+    }
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].creation_time() > entries[1].creation_time());
+
+    // Filter by time range.
+    let end_ms = entries[1].creation_time().map(f64::from);
+    let (older, _) = application
+        .list_deployment_audit_log(
+            admin_identity(1),
+            DeploymentAuditLogFilter {
+                end_ms,
+                ..Default::default()
+            },
+            None,
+            10,
+        )
+        .await?;
+    assert_eq!(older.len(), 1);
+    assert_eq!(older[0].id(), entries[1].id());
+
+    // Only admins can read the audit log.
+    let result = application
+        .list_deployment_audit_log(
+            Identity::Unknown,
+            DeploymentAuditLogFilter::default(),
+            None,
+            10,
+        )
+        .await;
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_list_deployment_audit_log_same_creation_time(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    // Transactions that begin at the same timestamp create entries with the
+    // same creation time.
+    let tx1 = application.begin(admin_identity(1)).await?;
+    let tx2 = application.begin(admin_identity(2)).await?;
+    for tx in [tx1, tx2] {
+        application
+            .commit_with_audit_log_events(tx, vec![DeploymentAuditLogEvent::ClearTables], "test")
+            .await?;
+    }
+
+    let mut entries = vec![];
+    let mut cursor = None;
+    loop {
+        let (page, next_cursor) = application
+            .list_deployment_audit_log(
+                admin_identity(1),
+                DeploymentAuditLogFilter::default(),
+                cursor,
+                1,
+            )
+            .await?;
+        entries.extend(page);
+        match next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => break,
+        }
+    }
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].creation_time(), entries[1].creation_time());
+    assert_ne!(entries[0].id(), entries[1].id());
+    Ok(())
+}
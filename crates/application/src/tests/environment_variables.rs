@@ -1,12 +1,19 @@
+use common::types::MemberId;
 use errors::ErrorMetadataAnyhowExt;
-use keybroker::Identity;
-use model::environment_variables::{
-    types::{
-        EnvVarName,
-        EnvVarValue,
-        EnvironmentVariable,
+use keybroker::{
+    AdminIdentity,
+    Identity,
+};
+use model::{
+    deployment_audit_log::DeploymentAuditLogFilter,
+    environment_variables::{
+        types::{
+            EnvVarName,
+            EnvVarValue,
+            EnvironmentVariable,
+        },
+        EnvironmentVariablesModel,
     },
-    EnvironmentVariablesModel,
 };
 use runtime::testing::TestRuntime;
 
@@ -72,7 +79,7 @@ async fn test_env_variable_delete_and_create(rt: TestRuntime) -> anyhow::Result<
         .await?;
 
     EnvironmentVariablesModel::new(&mut tx)
-        .get(&name)
+        .get(&name, application.key_broker())
         .await?
         .unwrap()
         .id();
@@ -88,7 +95,7 @@ async fn test_env_variable_delete_and_create(rt: TestRuntime) -> anyhow::Result<
         .await?;
 
     let after = EnvironmentVariablesModel::new(&mut tx)
-        .get(&name)
+        .get(&name, application.key_broker())
         .await?
         .unwrap()
         .into_value();
@@ -194,3 +201,55 @@ async fn test_env_variable_cannot_set_system_name(rt: TestRuntime) -> anyhow::Re
 
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_reveal_environment_variable(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let name: EnvVarName = "secret".parse()?;
+    let value: EnvVarValue = "hunter2".parse()?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    application
+        .create_environment_variables(
+            &mut tx,
+            vec![EnvironmentVariable::new(name.clone(), value.clone())],
+        )
+        .await?;
+    application.commit_test(tx).await?;
+
+    let admin = Identity::InstanceAdmin(AdminIdentity::new_for_test_only(
+        "test".to_string(),
+        MemberId(1),
+    ));
+    let revealed = application
+        .reveal_environment_variable(admin.clone(), name.clone())
+        .await?;
+    assert_eq!(revealed, value);
+
+    let (entries, _) = application
+        .list_deployment_audit_log(
+            admin.clone(),
+            DeploymentAuditLogFilter {
+                action: Some("reveal_environment_variable".to_string()),
+                ..Default::default()
+            },
+            None,
+            10,
+        )
+        .await?;
+    assert_eq!(entries.len(), 1);
+
+    let error = application
+        .reveal_environment_variable(admin, "missing".parse()?)
+        .await
+        .unwrap_err();
+    assert_eq!(error.short_msg(), "EnvironmentVariableNotFound");
+
+    let error = application
+        .reveal_environment_variable(Identity::Unknown, name)
+        .await
+        .unwrap_err();
+    assert!(error.is_forbidden());
+    Ok(())
+}
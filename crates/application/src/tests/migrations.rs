@@ -0,0 +1,258 @@
+use anyhow::Context;
+use common::{
+    bootstrap_model::schema::SchemaState,
+    db_schema,
+    object_validator,
+    schemas::{
+        validator::{
+            FieldValidator,
+            Validator,
+        },
+        DocumentSchema,
+    },
+};
+use database::{
+    SchemaModel,
+    TestFacingModel,
+};
+use keybroker::Identity;
+use model::migrations::{
+    types::{
+        Migration,
+        MigrationState,
+    },
+    MigrationModel,
+};
+use runtime::testing::TestRuntime;
+use sync_types::{
+    CanonicalizedUdfPath,
+    UdfPath,
+};
+use value::{
+    assert_obj,
+    ConvexObject,
+    ConvexValue,
+    ResolvedDocumentId,
+};
+
+use crate::{
+    test_helpers::{
+        ApplicationTestExt,
+        OBJECTS_TABLE,
+    },
+    Application,
+};
+
+const MIGRATION_NAME: &str = "markMigrated";
+
+fn udf_path() -> anyhow::Result<CanonicalizedUdfPath> {
+    Ok("migrations:markMigrated".parse::<UdfPath>()?.canonicalize())
+}
+
+async fn insert_objects(
+    application: &Application<TestRuntime>,
+    objects: Vec<ConvexObject>,
+) -> anyhow::Result<Vec<ResolvedDocumentId>> {
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut ids = vec![];
+    for object in objects {
+        ids.push(
+            TestFacingModel::new(&mut tx)
+                .insert(&OBJECTS_TABLE, object)
+                .await?,
+        );
+    }
+    application.commit_test(tx).await?;
+    Ok(ids)
+}
+
+async fn is_migrated(
+    application: &Application<TestRuntime>,
+    id: ResolvedDocumentId,
+) -> anyhow::Result<bool> {
+    let mut tx = application.begin(Identity::system()).await?;
+    let doc = tx.get(id).await?.context("Document not found")?;
+    Ok(doc.value().get("migrated") == Some(&ConvexValue::from(true)))
+}
+
+/// Waits for the migration worker to move the migration out of `Running`.
+async fn wait_for_migration(application: &Application<TestRuntime>) -> anyhow::Result<Migration> {
+    loop {
+        let mut tx = application.begin(Identity::system()).await?;
+        let migration = MigrationModel::new(&mut tx)
+            .get_by_name(MIGRATION_NAME)
+            .await?
+            .context("Migration not found")?
+            .into_value();
+        if migration.state != MigrationState::Running {
+            return Ok(migration);
+        }
+        let subscription = application.database().subscribe(tx.into_token()?).await?;
+        subscription.wait_for_invalidation().await;
+    }
+}
+
+#[convex_macro::test_runtime]
+async fn test_migration_runs_in_batches(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let ids = insert_objects(
+        &application,
+        vec![
+            assert_obj!("field" => 1.),
+            assert_obj!("field" => 2., "migrated" => true),
+            assert_obj!("field" => 3.),
+            assert_obj!("field" => 4.),
+            assert_obj!("field" => 5.),
+        ],
+    )
+    .await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    MigrationModel::new(&mut tx)
+        .start(
+            MIGRATION_NAME.to_string(),
+            OBJECTS_TABLE.clone(),
+            udf_path()?,
+            false,
+            2,
+        )
+        .await?;
+    application.commit_test(tx).await?;
+
+    let migration = wait_for_migration(&application).await?;
+    assert_eq!(migration.state, MigrationState::Completed);
+    assert_eq!(migration.num_processed, 5);
+    // The document that was already migrated is left as it was.
+    assert_eq!(migration.num_changed, 4);
+    assert_eq!(migration.cursor, Some(ids[4].into()));
+    for id in ids {
+        assert!(is_migrated(&application, id).await?);
+    }
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_migration_resumes_from_cursor(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let ids = insert_objects(
+        &application,
+        (1..=4).map(|i| assert_obj!("field" => i as f64)).collect(),
+    )
+    .await?;
+
+    // Start the migration as if a previous worker had already processed the
+    // first batch before the backend restarted.
+    let mut tx = application.begin(Identity::system()).await?;
+    let mut model = MigrationModel::new(&mut tx);
+    let id = model
+        .start(
+            MIGRATION_NAME.to_string(),
+            OBJECTS_TABLE.clone(),
+            udf_path()?,
+            false,
+            2,
+        )
+        .await?;
+    model.record_progress(id, ids[1].into(), 2, 2).await?;
+    application.commit_test(tx).await?;
+
+    let migration = wait_for_migration(&application).await?;
+    assert_eq!(migration.state, MigrationState::Completed);
+    assert_eq!(migration.num_processed, 4);
+    assert_eq!(migration.num_changed, 4);
+    // Documents before the cursor aren't processed again.
+    assert!(!is_migrated(&application, ids[0]).await?);
+    assert!(!is_migrated(&application, ids[1]).await?);
+    assert!(is_migrated(&application, ids[2]).await?);
+    assert!(is_migrated(&application, ids[3]).await?);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_migration_dry_run(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let ids = insert_objects(
+        &application,
+        vec![
+            assert_obj!("field" => 1.),
+            assert_obj!("field" => 2., "migrated" => true),
+            assert_obj!("field" => 3.),
+        ],
+    )
+    .await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    MigrationModel::new(&mut tx)
+        .start(
+            MIGRATION_NAME.to_string(),
+            OBJECTS_TABLE.clone(),
+            udf_path()?,
+            true,
+            2,
+        )
+        .await?;
+    application.commit_test(tx).await?;
+
+    let migration = wait_for_migration(&application).await?;
+    assert_eq!(migration.state, MigrationState::Completed);
+    assert_eq!(migration.num_processed, 3);
+    assert_eq!(migration.num_changed, 2);
+    // Dry runs roll back every batch.
+    assert!(!is_migrated(&application, ids[0]).await?);
+    assert!(is_migrated(&application, ids[1]).await?);
+    assert!(!is_migrated(&application, ids[2]).await?);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_schema_validation_waits_for_migration(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    insert_objects(
+        &application,
+        (1..=3).map(|i| assert_obj!("field" => i as f64)).collect(),
+    )
+    .await?;
+
+    // None of the existing documents match the schema until the migration
+    // backfills `migrated`.
+    let db_schema = db_schema!(
+        OBJECTS_TABLE.clone() => DocumentSchema::Union(vec![object_validator!(
+            "field" => FieldValidator::required_field_type(Validator::Float64),
+            "migrated" => FieldValidator::required_field_type(Validator::Boolean),
+        )])
+    );
+    let mut tx = application.begin(Identity::system()).await?;
+    MigrationModel::new(&mut tx)
+        .start(
+            MIGRATION_NAME.to_string(),
+            OBJECTS_TABLE.clone(),
+            udf_path()?,
+            false,
+            1,
+        )
+        .await?;
+    let (schema_id, _) = SchemaModel::new(&mut tx).submit_pending(db_schema).await?;
+    application.commit_test(tx).await?;
+
+    let migration = wait_for_migration(&application).await?;
+    assert_eq!(migration.state, MigrationState::Completed);
+    loop {
+        let mut tx = application.begin(Identity::system()).await?;
+        let mut model = SchemaModel::new(&mut tx);
+        if model.get_by_state(SchemaState::Pending).await?.is_none() {
+            let (validated_id, _) = model
+                .get_by_state(SchemaState::Validated)
+                .await?
+                .context("Schema wasn't validated")?;
+            assert_eq!(validated_id, schema_id);
+            break;
+        }
+        let subscription = application.database().subscribe(tx.into_token()?).await?;
+        subscription.wait_for_invalidation().await;
+    }
+    Ok(())
+}
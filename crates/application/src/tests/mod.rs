@@ -1,11 +1,14 @@
 mod analyze;
 mod auth_config;
 mod cron_jobs;
+mod deployment_audit_log;
 mod environment_variables;
+mod migrations;
 mod mutation;
 mod occ_retries;
 mod scheduled_jobs;
 mod schema;
+mod slow_queries;
 mod source_package;
 
 const NODE_SOURCE: &str = r#"
@@ -0,0 +1,78 @@
+use common::{
+    pause::PauseClient,
+    types::{
+        AllowedVisibility,
+        FunctionCaller,
+    },
+    RequestId,
+};
+use keybroker::Identity;
+use runtime::testing::TestRuntime;
+use serde_json::json;
+
+use crate::{
+    test_helpers::ApplicationTestExt,
+    Application,
+};
+
+#[convex_macro::test_runtime]
+async fn test_slow_query_log(rt: TestRuntime) -> anyhow::Result<()> {
+    std::env::set_var("SLOW_QUERY_LOG_ENABLED", "true");
+    std::env::set_var("SLOW_QUERY_DOCUMENTS_SCANNED_THRESHOLD", "2");
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let list_all_objects = || {
+        application.read_only_udf(
+            RequestId::new(),
+            "basic:listAllObjects".parse().unwrap(),
+            vec![json!({})],
+            Identity::system(),
+            AllowedVisibility::PublicOnly,
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+        )
+    };
+    application
+        .mutation_udf(
+            RequestId::new(),
+            "basic:insertObject".parse()?,
+            vec![json!({"an": "object"})],
+            Identity::system(),
+            None,
+            AllowedVisibility::PublicOnly,
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+            PauseClient::new(),
+        )
+        .await??;
+    // Queries under the thresholds aren't logged.
+    assert!(list_all_objects().await?.result.is_ok());
+    assert!(application.slow_queries().is_empty());
+
+    application
+        .mutation_udf(
+            RequestId::new(),
+            "basic:insertObject".parse()?,
+            vec![json!({"an": "object"})],
+            Identity::system(),
+            None,
+            AllowedVisibility::PublicOnly,
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+            PauseClient::new(),
+        )
+        .await??;
+    // Queries run by the function runner land in the backend's log.
+    assert!(list_all_objects().await?.result.is_ok());
+    let slow_queries = application.slow_queries();
+    let slow_query = slow_queries.last().expect("Query wasn't logged");
+    assert!(slow_query.udf_path.contains("listAllObjects"));
+    assert!(slow_query.explanation.is_full_table_scan());
+    assert_eq!(slow_query.explanation.documents_returned, 2);
+    assert!(slow_query.explanation.documents_scanned >= 2);
+    Ok(())
+}
@@ -0,0 +1,446 @@
+use std::time::Duration;
+
+use common::{
+    backoff::Backoff,
+    bootstrap_model::schema::SchemaState,
+    errors::report_error,
+    knobs::{
+        TTL_DELETE_BATCH_SIZE,
+        TTL_WORKER_INTERVAL,
+    },
+    pause::PauseClient,
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    schemas::{
+        TableDefinition,
+        TableTtl,
+    },
+    types::IndexName,
+};
+use database::{
+    Database,
+    IndexModel,
+    ResolvedQuery,
+    SchemaModel,
+    Transaction,
+    UserFacingModel,
+};
+use futures::Future;
+use keybroker::Identity;
+use model::backend_state::{
+    types::BackendState,
+    BackendStateModel,
+};
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    ConvexValue,
+    FieldPath,
+};
+
+use crate::{
+    function_log::FunctionExecutionLog,
+    metrics::{
+        log_ttl_documents_deleted,
+        log_worker_starting,
+        ttl_worker_run_timer,
+    },
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(900); // 15 minutes
+
+/// Deletes documents that are past their table's TTL, as configured in the
+/// active schema.
+pub struct TtlWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    function_log: FunctionExecutionLog<RT>,
+}
+
+impl<RT: Runtime> TtlWorker<RT> {
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        function_log: FunctionExecutionLog<RT>,
+    ) -> Self {
+        Self {
+            runtime,
+            database,
+            function_log,
+        }
+    }
+
+    pub fn start(self) -> impl Future<Output = ()> + Send {
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        async move {
+            loop {
+                self.runtime.wait(*TTL_WORKER_INTERVAL).await;
+                let _status = log_worker_starting("TtlWorker");
+                match self.delete_expired().await {
+                    Ok(num_deleted) => {
+                        backoff.reset();
+                        if num_deleted > 0 {
+                            tracing::info!("Deleted {num_deleted} documents past their TTL");
+                        }
+                    },
+                    Err(e) => {
+                        report_error(&mut e.context("TtlWorker died"));
+                        let delay = self.runtime.with_rng(|rng| backoff.fail(rng));
+                        self.runtime.wait(delay).await;
+                    },
+                }
+            }
+        }
+    }
+
+    /// Deletes every expired document in tables with a TTL, returning how many
+    /// were deleted. Stops early if the backend isn't running.
+    pub async fn delete_expired(&self) -> anyhow::Result<usize> {
+        let timer = ttl_worker_run_timer();
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let Some((_, schema)) = SchemaModel::new(&mut tx)
+            .get_by_state(SchemaState::Active)
+            .await?
+        else {
+            timer.finish();
+            return Ok(0);
+        };
+        let mut num_deleted = 0;
+        for (table_name, table_definition) in &schema.tables {
+            let (Some(ttl), Some(index)) = (&table_definition.ttl, table_definition.ttl_index())
+            else {
+                continue;
+            };
+            let index_name = IndexName::new(table_name.clone(), index.index_descriptor.clone())?;
+            // The index is still backfilling if the schema was just pushed.
+            if IndexModel::new(&mut tx)
+                .enabled_index_metadata(&index_name)?
+                .is_none()
+            {
+                continue;
+            }
+            match self
+                .delete_expired_in_table(table_definition, ttl, &index_name)
+                .await?
+            {
+                Some(num_deleted_in_table) => num_deleted += num_deleted_in_table,
+                None => break,
+            }
+        }
+        timer.finish();
+        Ok(num_deleted)
+    }
+
+    /// Deletes expired documents in batches until there are none left,
+    /// returning `None` if the backend stopped running in between.
+    async fn delete_expired_in_table(
+        &self,
+        table_definition: &TableDefinition,
+        ttl: &TableTtl,
+        index_name: &IndexName,
+    ) -> anyhow::Result<Option<usize>> {
+        let mut num_deleted = 0;
+        loop {
+            let start = self.runtime.monotonic_now();
+            let now_ms = self.runtime.unix_timestamp().as_ms_since_epoch()?;
+            let cutoff_ms = now_ms.saturating_sub(ttl.duration.as_millis() as u64) as f64;
+            let (_, batch, _) = self
+                .database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "ttl_worker",
+                    |tx| {
+                        Self::delete_batch(
+                            tx,
+                            index_name.clone(),
+                            ttl.field.clone(),
+                            cutoff_ms,
+                            *TTL_DELETE_BATCH_SIZE,
+                        )
+                        .into()
+                    },
+                )
+                .await?;
+            let Some(num_deleted_in_batch) = batch else {
+                return Ok(None);
+            };
+            if num_deleted_in_batch > 0 {
+                log_ttl_documents_deleted(num_deleted_in_batch);
+                self.function_log.log_ttl_deletions(
+                    table_definition.table_name.clone(),
+                    num_deleted_in_batch,
+                    start.elapsed(),
+                );
+            }
+            num_deleted += num_deleted_in_batch;
+            if num_deleted_in_batch < *TTL_DELETE_BATCH_SIZE {
+                return Ok(Some(num_deleted));
+            }
+        }
+    }
+
+    /// Deletes up to `max_documents` documents whose `field` is at or before
+    /// `cutoff_ms`. Returns `None` without deleting anything if the backend
+    /// is paused or disabled.
+    async fn delete_batch(
+        tx: &mut Transaction<RT>,
+        index_name: IndexName,
+        field: FieldPath,
+        cutoff_ms: f64,
+        max_documents: usize,
+    ) -> anyhow::Result<Option<usize>> {
+        // Reading the backend state in the same transaction means pausing the
+        // backend conflicts with an in-progress batch.
+        match BackendStateModel::new(tx).get_backend_state().await? {
+            BackendState::Running => {},
+            BackendState::Paused | BackendState::Disabled => return Ok(None),
+        }
+        // Only float64 values can expire, so skip past missing fields, nulls
+        // and int64s, which all sort before them.
+        let query = Query::index_range(IndexRange {
+            index_name,
+            range: vec![
+                IndexRangeExpression::Gte(field.clone(), ConvexValue::from(f64::NEG_INFINITY)),
+                IndexRangeExpression::Lte(field, ConvexValue::from(cutoff_ms)),
+            ],
+            order: Order::Asc,
+        })
+        .limit(max_documents);
+        let mut query_stream = ResolvedQuery::new(tx, query)?;
+        let mut expired = vec![];
+        while let Some(document) = query_stream.next(tx, None).await? {
+            expired.push(document.id());
+        }
+        for id in &expired {
+            UserFacingModel::new(tx).delete((*id).into()).await?;
+        }
+        Ok(Some(expired.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::{
+        bootstrap_model::index::IndexMetadata,
+        log_streaming::NoopLogSender,
+        persistence::NoopRetentionValidator,
+        runtime::Runtime,
+        schemas::DatabaseSchema,
+        types::{
+            IndexName,
+            TableName,
+        },
+    };
+    use database::{
+        test_helpers::DbFixtures,
+        Database,
+        IndexModel,
+        IndexWorker,
+        SchemaModel,
+        TestFacingModel,
+    };
+    use keybroker::Identity;
+    use model::{
+        backend_state::{
+            types::BackendState,
+            BackendStateModel,
+        },
+        test_helpers::DbFixturesWithModel,
+    };
+    use runtime::testing::TestRuntime;
+    use serde_json::json;
+    use value::{
+        assert_obj,
+        ConvexValue,
+        ResolvedDocumentId,
+    };
+
+    use super::TtlWorker;
+    use crate::function_log::FunctionExecutionLog;
+
+    const HOUR_MS: f64 = 60. * 60. * 1000.;
+
+    /// Sets up a `sessions` table whose documents expire an hour after their
+    /// `lastSeen` timestamp.
+    async fn setup_ttl_worker(
+        rt: &TestRuntime,
+    ) -> anyhow::Result<(TtlWorker<TestRuntime>, Database<TestRuntime>)> {
+        let DbFixtures { db, tp, .. } = DbFixtures::new_with_model(rt).await?;
+        let table_name: TableName = "sessions".parse()?;
+        let index_name = IndexName::new(table_name.clone(), "by_last_seen".parse()?)?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let begin_ts = tx.begin_timestamp();
+        IndexModel::new(&mut tx)
+            .add_application_index(IndexMetadata::new_backfilling(
+                *begin_ts,
+                index_name.clone(),
+                vec!["lastSeen".parse()?].try_into()?,
+            ))
+            .await?;
+        db.commit(tx).await?;
+        IndexWorker::new_terminating(rt.clone(), tp, Arc::new(NoopRetentionValidator), db.clone())
+            .await?;
+
+        let schema = DatabaseSchema::try_from(json!({
+            "tables": [
+                {
+                    "tableName": "sessions",
+                    "documentType": null,
+                    "indexes": [
+                        {
+                            "indexDescriptor": "by_last_seen",
+                            "fields": ["lastSeen"],
+                        },
+                    ],
+                    "searchIndexes": [],
+                    "ttl": {
+                        "field": "lastSeen",
+                        "durationMs": HOUR_MS as u64,
+                    },
+                },
+            ],
+            "schemaValidation": false,
+        }))?;
+        let mut tx = db.begin(Identity::system()).await?;
+        IndexModel::new(&mut tx)
+            .enable_index_for_testing(&index_name)
+            .await?;
+        let mut model = SchemaModel::new(&mut tx);
+        let (schema_id, _) = model.submit_pending(schema).await?;
+        model.mark_validated(schema_id).await?;
+        model.mark_active(schema_id).await?;
+        db.commit(tx).await?;
+
+        let function_log =
+            FunctionExecutionLog::new(rt.clone(), db.usage_counter(), Arc::new(NoopLogSender));
+        Ok((TtlWorker::new(rt.clone(), db.clone(), function_log), db))
+    }
+
+    async fn insert_sessions(
+        db: &Database<TestRuntime>,
+        last_seen: Vec<ConvexValue>,
+    ) -> anyhow::Result<Vec<ResolvedDocumentId>> {
+        let table_name: TableName = "sessions".parse()?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut ids = vec![];
+        for last_seen in last_seen {
+            let id = TestFacingModel::new(&mut tx)
+                .insert(&table_name, assert_obj!("lastSeen" => last_seen))
+                .await?;
+            ids.push(id);
+        }
+        db.commit(tx).await?;
+        Ok(ids)
+    }
+
+    async fn exists(db: &Database<TestRuntime>, id: ResolvedDocumentId) -> anyhow::Result<bool> {
+        let mut tx = db.begin(Identity::system()).await?;
+        Ok(tx.get(id).await?.is_some())
+    }
+
+    fn now_ms(rt: &TestRuntime) -> anyhow::Result<f64> {
+        Ok(rt.unix_timestamp().as_ms_since_epoch()? as f64)
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_delete_expired(rt: TestRuntime) -> anyhow::Result<()> {
+        let (worker, db) = setup_ttl_worker(&rt).await?;
+        let now_ms = now_ms(&rt)?;
+        let expired = insert_sessions(
+            &db,
+            vec![
+                ConvexValue::from(now_ms - 3. * HOUR_MS),
+                ConvexValue::from(now_ms - 2. * HOUR_MS),
+            ],
+        )
+        .await?;
+        let not_expired = insert_sessions(
+            &db,
+            vec![
+                ConvexValue::from(now_ms - HOUR_MS / 2.),
+                // Only float64 timestamps expire.
+                ConvexValue::from((now_ms - 2. * HOUR_MS) as i64),
+                ConvexValue::Null,
+            ],
+        )
+        .await?;
+
+        assert_eq!(worker.delete_expired().await?, 2);
+        for id in expired {
+            assert!(!exists(&db, id).await?);
+        }
+        for id in &not_expired {
+            assert!(exists(&db, *id).await?);
+        }
+
+        // The fresh session expires once enough time has passed.
+        rt.advance_time(std::time::Duration::from_secs(60 * 60));
+        assert_eq!(worker.delete_expired().await?, 1);
+        assert!(!exists(&db, not_expired[0]).await?);
+        assert!(exists(&db, not_expired[1]).await?);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_delete_batch(rt: TestRuntime) -> anyhow::Result<()> {
+        let (_worker, db) = setup_ttl_worker(&rt).await?;
+        let now_ms = now_ms(&rt)?;
+        let cutoff_ms = now_ms - HOUR_MS;
+        let expired = insert_sessions(
+            &db,
+            (1..=5)
+                .map(|hours| ConvexValue::from(now_ms - (hours as f64 + 1.) * HOUR_MS))
+                .collect(),
+        )
+        .await?;
+        let fresh = insert_sessions(&db, vec![ConvexValue::from(now_ms)]).await?;
+
+        let index_name = IndexName::new("sessions".parse()?, "by_last_seen".parse()?)?;
+        let mut num_deleted = vec![];
+        loop {
+            let mut tx = db.begin(Identity::system()).await?;
+            let batch = TtlWorker::delete_batch(
+                &mut tx,
+                index_name.clone(),
+                "lastSeen".parse()?,
+                cutoff_ms,
+                2,
+            )
+            .await?
+            .unwrap();
+            db.commit(tx).await?;
+            num_deleted.push(batch);
+            if batch < 2 {
+                break;
+            }
+        }
+        assert_eq!(num_deleted, vec![2, 2, 1]);
+        for id in expired {
+            assert!(!exists(&db, id).await?);
+        }
+        assert!(exists(&db, fresh[0]).await?);
+
+        // Nothing is deleted while the backend is paused.
+        let mut tx = db.begin(Identity::system()).await?;
+        BackendStateModel::new(&mut tx)
+            .toggle_backend_state(BackendState::Paused)
+            .await?;
+        db.commit(tx).await?;
+        insert_sessions(&db, vec![ConvexValue::from(now_ms - 2. * HOUR_MS)]).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let batch =
+            TtlWorker::delete_batch(&mut tx, index_name, "lastSeen".parse()?, cutoff_ms, 2).await?;
+        assert_eq!(batch, None);
+        Ok(())
+    }
+}
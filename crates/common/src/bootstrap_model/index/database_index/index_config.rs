@@ -2,9 +2,18 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use value::ConvexObject;
 
 use super::indexed_fields::IndexedFields;
-use crate::paths::FieldPath;
+use crate::{
+    document::ResolvedDocument,
+    errors::report_error,
+    index::IndexKey,
+    json::JsonExpression,
+    paths::FieldPath,
+    query::Expression,
+    types::PersistenceVersion,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
@@ -12,12 +21,136 @@ pub struct DeveloperDatabaseIndexConfig {
     /// Ordered field(s) to index. The "unindexed" primary key ordering of
     /// documents by [`DocumentId`] is represented by an empty vector.
     pub fields: IndexedFields,
+    /// Whether at most one document may have each combination of values for
+    /// `fields`. Documents missing any of the fields are exempt.
+    pub unique: bool,
+    /// If set, only documents for which this expression evaluates to `true`
+    /// are included in the index. Restricted to comparisons between fields and
+    /// literals, see [`is_valid_index_filter`].
+    pub filter: Option<Expression>,
+    /// One of `fields` that gets an index entry per distinct element when it's
+    /// an array, so documents can be looked up by any of its elements.
+    pub multikey_field: Option<FieldPath>,
+    /// If set, a count of the documents in the index (and optionally a sum of
+    /// one of their fields) is maintained on write for every prefix of
+    /// `fields`.
+    pub aggregate: Option<IndexAggregate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct IndexAggregate {
+    /// Numeric field to sum alongside the count. Documents where it's missing
+    /// or not a number add zero.
+    pub sum_field: Option<FieldPath>,
+}
+
+impl From<IndexedFields> for DeveloperDatabaseIndexConfig {
+    fn from(fields: IndexedFields) -> Self {
+        Self {
+            fields,
+            unique: false,
+            filter: None,
+            multikey_field: None,
+            aggregate: None,
+        }
+    }
+}
+
+impl DeveloperDatabaseIndexConfig {
+    /// Whether a document with `value` belongs in the index. Fails if the
+    /// filter doesn't evaluate to a boolean, in which case writes of the
+    /// document are rejected.
+    pub fn includes(&self, value: &ConvexObject) -> anyhow::Result<bool> {
+        match &self.filter {
+            None => Ok(true),
+            Some(filter) => filter.eval(value)?.into_boolean(),
+        }
+    }
+
+    /// Position of the multikey field within `fields`, if any.
+    pub fn multikey_position(&self) -> Option<usize> {
+        let multikey_field = self.multikey_field.as_ref()?;
+        self.fields.iter().position(|field| field == multikey_field)
+    }
+
+    /// The keys of `document`'s entries in the index. There are none if it
+    /// doesn't match the filter, and more than one if it's a multikey index.
+    pub fn index_keys(
+        &self,
+        document: &ResolvedDocument,
+        persistence_version: PersistenceVersion,
+    ) -> Vec<IndexKey> {
+        // Writes of documents the filter fails on are rejected and filters
+        // that pass [`is_valid_index_filter`] can't fail, so this is a bug.
+        match self.includes(document.value()) {
+            Ok(true) => (),
+            Ok(false) => return vec![],
+            Err(mut e) => {
+                report_error(&mut e);
+                return vec![];
+            },
+        }
+        match &self.multikey_field {
+            None => vec![document.index_key(&self.fields[..], persistence_version)],
+            Some(multikey_field) => {
+                document.multikey_index_keys(&self.fields[..], multikey_field, persistence_version)
+            },
+        }
+    }
+}
+
+/// Index filters must be deterministic and cheap to evaluate on every write,
+/// so they may only compare fields with literals and combine those
+/// comparisons with `and`, `or` and `not`.
+pub fn is_valid_index_filter(filter: &Expression) -> bool {
+    match filter {
+        Expression::Eq(l, r)
+        | Expression::Neq(l, r)
+        | Expression::Lt(l, r)
+        | Expression::Lte(l, r)
+        | Expression::Gt(l, r)
+        | Expression::Gte(l, r) => matches!(
+            (&**l, &**r),
+            (Expression::Field(_), Expression::Literal(_))
+                | (Expression::Literal(_), Expression::Field(_))
+        ),
+        Expression::And(exprs) | Expression::Or(exprs) => {
+            !exprs.is_empty() && exprs.iter().all(is_valid_index_filter)
+        },
+        Expression::Not(expr) => is_valid_index_filter(expr),
+        Expression::Add(..)
+        | Expression::Sub(..)
+        | Expression::Mul(..)
+        | Expression::Div(..)
+        | Expression::Mod(..)
+        | Expression::Neg(_)
+        | Expression::Field(_)
+        | Expression::Literal(_) => false,
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SerializedDeveloperDatabaseIndexConfig {
     fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unique: Option<bool>,
+    /// JSON-encoded [`JsonExpression`]. Stored as a string since its keys
+    /// start with `$`, which isn't allowed in field names.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multikey_field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aggregate: Option<SerializedIndexAggregate>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedIndexAggregate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sum_field: Option<String>,
 }
 
 impl TryFrom<DeveloperDatabaseIndexConfig> for SerializedDeveloperDatabaseIndexConfig {
@@ -29,6 +162,15 @@ impl TryFrom<DeveloperDatabaseIndexConfig> for SerializedDeveloperDatabaseIndexC
                 .into_iter()
                 .map(String::from)
                 .collect(),
+            unique: config.unique.then_some(true),
+            filter: config
+                .filter
+                .map(|filter| serde_json::to_string(&JsonExpression::from(filter)))
+                .transpose()?,
+            multikey_field: config.multikey_field.map(String::from),
+            aggregate: config.aggregate.map(|aggregate| SerializedIndexAggregate {
+                sum_field: aggregate.sum_field.map(String::from),
+            }),
         })
     }
 }
@@ -44,6 +186,25 @@ impl TryFrom<SerializedDeveloperDatabaseIndexConfig> for DeveloperDatabaseIndexC
                 .map(|p| p.parse())
                 .collect::<anyhow::Result<Vec<FieldPath>>>()?
                 .try_into()?,
+            unique: config.unique.unwrap_or(false),
+            filter: config
+                .filter
+                .map(|filter| {
+                    Expression::try_from(serde_json::from_str::<JsonExpression>(&filter)?)
+                })
+                .transpose()?,
+            multikey_field: config
+                .multikey_field
+                .map(|field| field.parse())
+                .transpose()?,
+            aggregate: config
+                .aggregate
+                .map(|aggregate| -> anyhow::Result<_> {
+                    Ok(IndexAggregate {
+                        sum_field: aggregate.sum_field.map(|field| field.parse()).transpose()?,
+                    })
+                })
+                .transpose()?,
         })
     }
 }
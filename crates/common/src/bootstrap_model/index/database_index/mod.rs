@@ -9,8 +9,11 @@ pub use self::{
         SerializedDatabaseIndexBackfillState,
     },
     index_config::{
+        is_valid_index_filter,
         DeveloperDatabaseIndexConfig,
+        IndexAggregate,
         SerializedDeveloperDatabaseIndexConfig,
+        SerializedIndexAggregate,
     },
     index_state::{
         DatabaseIndexState,
@@ -66,11 +66,29 @@ impl<T: TableIdentifier> IndexMetadata<T> {
         index_created_lower_bound: Timestamp,
         name: GenericIndexName<T>,
         fields: IndexedFields,
+    ) -> Self {
+        Self::new_backfilling_database_index(
+            index_created_lower_bound,
+            name,
+            DeveloperDatabaseIndexConfig {
+                fields,
+                unique: false,
+                filter: None,
+                multikey_field: None,
+                aggregate: None,
+            },
+        )
+    }
+
+    pub fn new_backfilling_database_index(
+        index_created_lower_bound: Timestamp,
+        name: GenericIndexName<T>,
+        developer_config: DeveloperDatabaseIndexConfig,
     ) -> Self {
         Self {
             name,
             config: IndexConfig::Database {
-                developer_config: DeveloperDatabaseIndexConfig { fields },
+                developer_config,
                 on_disk_state: DatabaseIndexState::Backfilling(DatabaseIndexBackfillState {
                     index_created_lower_bound,
                     retention_started: false,
@@ -135,7 +153,13 @@ impl<T: TableIdentifier> IndexMetadata<T> {
         Self {
             name,
             config: IndexConfig::Database {
-                developer_config: DeveloperDatabaseIndexConfig { fields },
+                developer_config: DeveloperDatabaseIndexConfig {
+                    fields,
+                    unique: false,
+                    filter: None,
+                    multikey_field: None,
+                    aggregate: None,
+                },
                 on_disk_state: DatabaseIndexState::Enabled,
             },
         }
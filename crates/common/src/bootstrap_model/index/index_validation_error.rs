@@ -1,4 +1,5 @@
 use errors::ErrorMetadata;
+use sync_types::identifier::MAX_IDENTIFIER_LEN;
 use value::{
     TableIdentifier,
     TableName,
@@ -122,6 +123,96 @@ pub fn invalid_index_field(descriptor: &IndexDescriptor, field: &str) -> ErrorMe
         format!("In index \"{descriptor}\": Invalid index field: \"{field}\""),
     )
 }
+pub fn invalid_index_filter(descriptor: &IndexDescriptor) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidIndexFilter",
+        format!(
+            "In index \"{descriptor}\": Index filters may only compare fields with constant \
+             values, combined with `and`, `or` and `not`."
+        ),
+    )
+}
+pub fn multikey_field_not_indexed(
+    descriptor: &IndexDescriptor,
+    field: &FieldPath,
+) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidMultikeyField",
+        format!(
+            "In index \"{descriptor}\": Multikey field {field} must be one of the index's fields."
+        ),
+    )
+}
+pub fn unique_multikey_index(descriptor: &IndexDescriptor) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "UniqueMultikeyIndex",
+        format!("In index \"{descriptor}\": Multikey indexes can't be unique."),
+    )
+}
+
+pub fn multikey_aggregate_index(descriptor: &IndexDescriptor) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "MultikeyAggregateIndex",
+        format!("In index \"{descriptor}\": Multikey indexes can't have aggregates."),
+    )
+}
+
+pub fn invalid_ttl_field(table_name: &TableName, field: &str) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidTtlField",
+        format!("In table \"{table_name}\": Invalid TTL field: \"{field}\""),
+    )
+}
+pub fn ttl_index_missing(table_name: &TableName, field: &FieldPath) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "TtlIndexMissing",
+        format!(
+            "In table \"{table_name}\": A TTL on {field} needs an index starting with {field}, \
+             without a filter or multikey field."
+        ),
+    )
+}
+pub fn invalid_foreign_key_field(table_name: &TableName, field: &str) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidForeignKeyField",
+        format!(
+            "In table \"{table_name}\": Invalid foreign key field: \"{field}\". Foreign keys must \
+             be on top-level fields."
+        ),
+    )
+}
+pub fn invalid_foreign_key_table(table_name: &TableName, referenced: &str) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidForeignKeyTable",
+        format!(
+            "In table \"{table_name}\": Foreign keys must reference a user table, not \
+             \"{referenced}\"."
+        ),
+    )
+}
+pub fn foreign_key_field_too_long(table_name: &TableName, field: &FieldPath) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "ForeignKeyFieldTooLong",
+        format!(
+            "In table \"{table_name}\": The foreign key field {field} is too long. Foreign keys \
+             are indexed as \"_foreign_key_{field}\", which can be at most {MAX_IDENTIFIER_LEN} \
+             characters."
+        ),
+    )
+}
+pub fn foreign_key_set_null_not_nullable(
+    table_name: &TableName,
+    field: &FieldPath,
+) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "ForeignKeySetNullNotNullable",
+        format!(
+            "In table \"{table_name}\": The foreign key on {field} sets it to null when the \
+             referenced document is deleted, but the table's schema doesn't allow {field} to be \
+             null. Use `v.union(v.id(...), v.null())` for {field} or a different onDelete."
+        ),
+    )
+}
 
 // TODO - move elsewhere (near table names) - it's not indexing related
 pub fn invalid_table_name(table_name: &str) -> ErrorMetadata {
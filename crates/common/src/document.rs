@@ -63,7 +63,10 @@ use value::{
 use crate::value::FieldType;
 use crate::{
     floating_point::MAX_EXACT_F64_INT,
-    index::IndexKey,
+    index::{
+        multikey_index_values,
+        IndexKey,
+    },
     pii::PII,
     types::{
         PersistenceVersion,
@@ -519,6 +522,24 @@ impl ResolvedDocument {
         IndexKey::new_allow_missing(values, (*self.id()).into())
     }
 
+    /// Like [`Self::index_key`], but for a multikey index on `multikey_field`,
+    /// which has one key per distinct element when the field is an array.
+    pub fn multikey_index_keys(
+        &self,
+        fields: &[FieldPath],
+        multikey_field: &FieldPath,
+        persistence_version: PersistenceVersion,
+    ) -> Vec<IndexKey> {
+        let index_key = self.index_key(fields, persistence_version);
+        let Some(position) = fields.iter().position(|field| field == multikey_field) else {
+            return vec![index_key];
+        };
+        multikey_index_values(index_key.indexed_values().to_vec(), position)
+            .into_iter()
+            .map(|values| IndexKey::new_allow_missing(values, (*self.id()).into()))
+            .collect()
+    }
+
     /// Recreate a `Document` from an already-written value to the database.
     /// This method assumes that system-provided fields, like `_id`, have
     /// already been inserted into `value`.
@@ -724,6 +745,31 @@ impl PackedDocument {
         }
         IndexKey::new_allow_missing(values, self.id().into())
     }
+
+    /// Every key this document could have in an index on `fields`: its
+    /// regular key, plus its multikey entries for each array-valued field.
+    /// Read sets don't know which indexes are multikey, so they check writes
+    /// against all of these, which may conflict a little more than needed.
+    pub fn possible_index_keys(
+        &self,
+        fields: &[FieldPath],
+        persistence_version: PersistenceVersion,
+    ) -> Vec<IndexKey> {
+        let index_key = self.index_key(fields, persistence_version);
+        let values = index_key.indexed_values();
+        let mut index_keys = vec![];
+        for (position, value) in values.iter().enumerate() {
+            if let Some(ConvexValue::Array(_)) = value {
+                index_keys.extend(
+                    multikey_index_values(values.to_vec(), position)
+                        .into_iter()
+                        .map(|values| IndexKey::new_allow_missing(values, self.id().into())),
+                );
+            }
+        }
+        index_keys.push(index_key);
+        index_keys
+    }
 }
 
 impl HeapSize for PackedDocument {
@@ -6,7 +6,10 @@ use std::{
     net::SocketAddr,
     pin::Pin,
     str,
-    sync::LazyLock,
+    sync::{
+        Arc,
+        LazyLock,
+    },
     time::{
         Duration,
         Instant,
@@ -30,6 +33,7 @@ use axum::{
     error_handling::HandleErrorLayer,
     extract::{
         connect_info::IntoMakeServiceWithConnectInfo,
+        ConnectInfo,
         FromRequestParts,
         State,
     },
@@ -40,6 +44,7 @@ use axum::{
     response::IntoResponse,
     routing::get,
     BoxError,
+    Extension,
     RequestPartsExt,
     Router,
 };
@@ -67,7 +72,10 @@ use http::{
     HeaderMap,
     Method,
 };
-use hyper::server::conn::AddrIncoming;
+use hyper::{
+    server::conn::AddrIncoming,
+    service::make_service_fn,
+};
 use itertools::Itertools;
 use maplit::btreemap;
 use minitrace::future::FutureExt;
@@ -90,10 +98,21 @@ use tower_http::cors::{
 };
 use url::Url;
 
-use self::metrics::log_http_request;
+use self::{
+    metrics::log_http_request,
+    tls::{
+        ReloadingCertResolver,
+        TlsConfig,
+        TlsConnection,
+        TlsIncoming,
+    },
+};
 use crate::{
     errors::report_error,
-    knobs::HTTP_SERVER_TCP_BACKLOG,
+    knobs::{
+        HTTP_SERVER_TCP_BACKLOG,
+        TLS_CERT_RELOAD_INTERVAL,
+    },
     metrics::log_client_version_unsupported,
     minitrace_helpers::get_sampled_span,
     version::{
@@ -106,6 +125,7 @@ use crate::{
 
 pub mod extract;
 pub mod fetch;
+pub mod tls;
 
 const MAX_HTTP2_STREAMS: u32 = 1024;
 
@@ -599,6 +619,15 @@ impl ConvexHttpService {
         serve_http(make_svc, addr, shutdown).await
     }
 
+    pub async fn serve_tls<F: Future<Output = ()>>(
+        self,
+        addr: SocketAddr,
+        tls_config: TlsConfig,
+        shutdown: F,
+    ) -> anyhow::Result<()> {
+        serve_https(self.router, addr, tls_config, shutdown).await
+    }
+
     #[cfg(any(test, feature = "testing"))]
     pub fn new_for_test(router: Router<(), Body>) -> Self {
         Self { router }
@@ -651,6 +680,65 @@ pub async fn serve_http<F>(
 where
     F: Future<Output = ()>,
 {
+    let incoming_sockets = bind_incoming(addr)?;
+    let addr = incoming_sockets.local_addr();
+
+    tracing::info!("Listening on http://{}", addr);
+    hyper::Server::builder(incoming_sockets)
+        .http2_max_concurrent_streams(MAX_HTTP2_STREAMS)
+        .serve(service)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    tracing::info!("HTTP server shutdown complete");
+
+    Ok(())
+}
+
+/// Serves an HTTPS server using the given router, terminating TLS in-process.
+/// The certificate is reloaded from disk when it changes.
+pub async fn serve_https<F>(
+    router: Router,
+    addr: SocketAddr,
+    tls_config: TlsConfig,
+    shutdown: F,
+) -> anyhow::Result<()>
+where
+    F: Future<Output = ()>,
+{
+    let cert_resolver = Arc::new(ReloadingCertResolver::new(
+        tls_config.cert_path.clone(),
+        tls_config.key_path.clone(),
+    )?);
+    let server_config = tls_config.server_config(cert_resolver.clone())?;
+    let incoming_sockets = bind_incoming(addr)?;
+    let addr = incoming_sockets.local_addr();
+    let incoming = TlsIncoming::new(incoming_sockets, server_config);
+
+    // `into_make_service_with_connect_info` only understands plain TCP streams, so
+    // attach the connection info to each request ourselves.
+    let make_svc = make_service_fn(move |conn: &TlsConnection| {
+        let service = ServiceBuilder::new()
+            .layer(Extension(ConnectInfo(conn.remote_addr())))
+            .layer(Extension(conn.client_authentication()))
+            .service(router.clone());
+        async move { Ok::<_, Infallible>(service) }
+    });
+
+    let reload_handle = tokio::spawn(cert_resolver.reload_periodically(*TLS_CERT_RELOAD_INTERVAL));
+    tracing::info!("Listening on https://{}", addr);
+    let result = hyper::Server::builder(incoming)
+        .http2_max_concurrent_streams(MAX_HTTP2_STREAMS)
+        .serve(make_svc)
+        .with_graceful_shutdown(shutdown)
+        .await;
+    reload_handle.abort();
+    result?;
+    tracing::info!("HTTPS server shutdown complete");
+
+    Ok(())
+}
+
+fn bind_incoming(addr: SocketAddr) -> anyhow::Result<AddrIncoming> {
     // Set SO_REUSEADDR and a bounded TCP accept backlog for our server's listening
     // socket.
     let socket = TcpSocket::new_v4()?;
@@ -666,17 +754,7 @@ where
     // https://klau.si/blog/crashing-a-rust-hyper-server-with-a-denial-of-service-attack/ for more
     // details.
     incoming_sockets.set_sleep_on_errors(true);
-    let addr = incoming_sockets.local_addr();
-
-    tracing::info!("Listening on http://{}", addr);
-    hyper::Server::builder(incoming_sockets)
-        .http2_max_concurrent_streams(MAX_HTTP2_STREAMS)
-        .serve(service)
-        .with_graceful_shutdown(shutdown)
-        .await?;
-    tracing::info!("HTTP server shutdown complete");
-
-    Ok(())
+    Ok(incoming_sockets)
 }
 
 async fn client_version_state_middleware(
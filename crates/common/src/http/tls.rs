@@ -0,0 +1,532 @@
+use std::{
+    fs,
+    io,
+    net::SocketAddr,
+    path::{
+        Path,
+        PathBuf,
+    },
+    pin::Pin,
+    sync::Arc,
+    task::{
+        Context,
+        Poll,
+    },
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use anyhow::Context as _;
+use futures::{
+    future::BoxFuture,
+    stream::FuturesUnordered,
+    FutureExt,
+    StreamExt,
+};
+use hyper::server::{
+    accept::Accept,
+    conn::{
+        AddrIncoming,
+        AddrStream,
+    },
+};
+use parking_lot::Mutex;
+use rustls::{
+    server::{
+        AllowAnyAnonymousOrAuthenticatedClient,
+        ClientHello,
+        ResolvesServerCert,
+    },
+    sign::CertifiedKey,
+    Certificate,
+    PrivateKey,
+    RootCertStore,
+    ServerConfig,
+};
+use tokio::io::{
+    AsyncRead,
+    AsyncWrite,
+    ReadBuf,
+};
+use tokio_rustls::{
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+use crate::knobs::TLS_HANDSHAKE_TIMEOUT;
+
+/// Where to find the PEM-encoded certificate chain and private key for a TLS
+/// listener, and optionally a CA bundle for verifying client certificates.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// If set, clients may authenticate with a certificate signed by one of
+    /// these CAs. Connections without a certificate are still accepted; see
+    /// [`ClientAuthentication`].
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub(crate) fn server_config(
+        &self,
+        cert_resolver: Arc<ReloadingCertResolver>,
+    ) -> anyhow::Result<Arc<ServerConfig>> {
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let mut config = match self.client_ca_path {
+            Some(ref client_ca_path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in read_certs(client_ca_path)? {
+                    roots.add(&cert).with_context(|| {
+                        format!("Invalid client CA in {}", client_ca_path.display())
+                    })?;
+                }
+                builder
+                    .with_client_cert_verifier(
+                        AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed(),
+                    )
+                    .with_cert_resolver(cert_resolver)
+            },
+            None => builder
+                .with_no_client_auth()
+                .with_cert_resolver(cert_resolver),
+        };
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+}
+
+/// Request extension recording how the client authenticated its TLS
+/// connection. Absent for requests served over plain HTTP.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientAuthentication {
+    Anonymous,
+    /// The client presented a certificate signed by the configured client CA.
+    Certificate,
+}
+
+/// Serves the current certificate and key, reloading them from disk when
+/// their modification times change so certificates can be rotated without
+/// restarting the server.
+pub(crate) struct ReloadingCertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: Mutex<LoadedCert>,
+}
+
+struct LoadedCert {
+    key: Arc<CertifiedKey>,
+    modified: (SystemTime, SystemTime),
+}
+
+impl ReloadingCertResolver {
+    pub(crate) fn new(cert_path: PathBuf, key_path: PathBuf) -> anyhow::Result<Self> {
+        let current = LoadedCert::load(&cert_path, &key_path)?;
+        Ok(Self {
+            cert_path,
+            key_path,
+            current: Mutex::new(current),
+        })
+    }
+
+    /// Reload the certificate if either file changed since we last loaded it.
+    /// Returns whether a new certificate was installed.
+    pub(crate) fn reload_if_changed(&self) -> anyhow::Result<bool> {
+        let modified = (modified(&self.cert_path)?, modified(&self.key_path)?);
+        if self.current.lock().modified == modified {
+            return Ok(false);
+        }
+        let loaded = LoadedCert::load(&self.cert_path, &self.key_path)?;
+        *self.current.lock() = loaded;
+        Ok(true)
+    }
+
+    pub(crate) async fn reload_periodically(self: Arc<Self>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            match self.reload_if_changed() {
+                Ok(true) => {
+                    tracing::info!("Reloaded TLS certificate from {}", self.cert_path.display())
+                },
+                Ok(false) => (),
+                // Keep serving the old certificate. This can happen transiently while
+                // the cert and key are being replaced.
+                Err(e) => tracing::warn!("Failed to reload TLS certificate: {e:#}"),
+            }
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.lock().key.clone())
+    }
+}
+
+impl LoadedCert {
+    fn load(cert_path: &Path, key_path: &Path) -> anyhow::Result<Self> {
+        // Read the modification times first so a write racing with the load gets
+        // picked up on the next reload.
+        let modified = (modified(cert_path)?, modified(key_path)?);
+        let certs = read_certs(cert_path)?;
+        anyhow::ensure!(
+            !certs.is_empty(),
+            "No certificates found in {}",
+            cert_path.display()
+        );
+        let key = read_private_key(key_path)?;
+        let signing_key = rustls::sign::any_supported_type(&key).map_err(|e| {
+            anyhow::anyhow!("Unsupported private key in {}: {e}", key_path.display())
+        })?;
+        Ok(Self {
+            key: Arc::new(CertifiedKey::new(certs, signing_key)),
+            modified,
+        })
+    }
+}
+
+fn modified(path: &Path) -> anyhow::Result<SystemTime> {
+    let metadata =
+        fs::metadata(path).with_context(|| format!("Failed to stat {}", path.display()))?;
+    Ok(metadata.modified()?)
+}
+
+fn read_certs(path: &Path) -> anyhow::Result<Vec<Certificate>> {
+    let file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut io::BufReader::new(file))
+        .with_context(|| format!("Invalid PEM in {}", path.display()))?;
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_private_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let file =
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = io::BufReader::new(file);
+    loop {
+        match rustls_pemfile::read_one(&mut reader)
+            .with_context(|| format!("Invalid PEM in {}", path.display()))?
+        {
+            Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => return Ok(PrivateKey(key)),
+            Some(_) => continue,
+            None => anyhow::bail!("No private key found in {}", path.display()),
+        }
+    }
+}
+
+type Handshake = BoxFuture<'static, (SocketAddr, Result<io::Result<TlsStream<AddrStream>>, ()>)>;
+
+/// Accepts TCP connections and completes their TLS handshakes concurrently,
+/// yielding connections to hyper as their handshakes finish.
+pub(crate) struct TlsIncoming {
+    incoming: AddrIncoming,
+    acceptor: TlsAcceptor,
+    handshakes: FuturesUnordered<Handshake>,
+}
+
+impl TlsIncoming {
+    pub(crate) fn new(incoming: AddrIncoming, config: Arc<ServerConfig>) -> Self {
+        Self {
+            incoming,
+            acceptor: TlsAcceptor::from(config),
+            handshakes: FuturesUnordered::new(),
+        }
+    }
+}
+
+impl Accept for TlsIncoming {
+    type Conn = TlsConnection;
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<TlsConnection>>> {
+        let this = self.get_mut();
+        loop {
+            match Pin::new(&mut this.incoming).poll_accept(cx) {
+                Poll::Ready(Some(Ok(stream))) => {
+                    let remote_addr = stream.remote_addr();
+                    let accept = this.acceptor.accept(stream);
+                    this.handshakes.push(
+                        async move {
+                            let result = tokio::time::timeout(*TLS_HANDSHAKE_TIMEOUT, accept)
+                                .await
+                                .map_err(|_| ());
+                            (remote_addr, result)
+                        }
+                        .boxed(),
+                    );
+                },
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => break,
+            }
+        }
+        loop {
+            match this.handshakes.poll_next_unpin(cx) {
+                Poll::Ready(Some((remote_addr, Ok(Ok(stream))))) => {
+                    return Poll::Ready(Some(Ok(TlsConnection {
+                        stream,
+                        remote_addr,
+                    })));
+                },
+                Poll::Ready(Some((remote_addr, Ok(Err(e))))) => {
+                    tracing::debug!("TLS handshake with {remote_addr} failed: {e}");
+                },
+                Poll::Ready(Some((remote_addr, Err(())))) => {
+                    tracing::debug!("TLS handshake with {remote_addr} timed out");
+                },
+                // No handshakes in flight: we'll be woken by `incoming`.
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+pub(crate) struct TlsConnection {
+    stream: TlsStream<AddrStream>,
+    remote_addr: SocketAddr,
+}
+
+impl TlsConnection {
+    pub(crate) fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    pub(crate) fn client_authentication(&self) -> ClientAuthentication {
+        // The verifier rejects certificates that don't chain to the client CA, so
+        // any certificate on an established connection has been verified.
+        match self.stream.get_ref().1.peer_certificates() {
+            Some(certs) if !certs.is_empty() => ClientAuthentication::Certificate,
+            _ => ClientAuthentication::Anonymous,
+        }
+    }
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        fs,
+        net::SocketAddr,
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::Arc,
+        time::Duration,
+    };
+
+    use hyper::{
+        client::conn as client_conn,
+        server::conn::AddrIncoming,
+        service::{
+            make_service_fn,
+            service_fn,
+        },
+        Body,
+        Request,
+        Response,
+        Server,
+    };
+    use rustls::{
+        ClientConfig,
+        RootCertStore,
+        ServerName,
+    };
+    use tempfile::TempDir;
+    use tokio::net::TcpStream;
+    use tokio_rustls::TlsConnector;
+
+    use super::{
+        modified,
+        read_certs,
+        read_private_key,
+        ReloadingCertResolver,
+        TlsConfig,
+        TlsConnection,
+        TlsIncoming,
+    };
+
+    // P-256 certificates issued by a throwaway test CA, valid for 100 years.
+    // `server_rotated` is a second certificate for `localhost` and `client` is
+    // only valid for client authentication.
+    const CA_CERT: &str = include_str!("../../testdata/tls/ca.pem");
+    const SERVER_CERT: &str = include_str!("../../testdata/tls/server.pem");
+    const SERVER_KEY: &str = include_str!("../../testdata/tls/server.key");
+    const ROTATED_CERT: &str = include_str!("../../testdata/tls/server_rotated.pem");
+    const ROTATED_KEY: &str = include_str!("../../testdata/tls/server_rotated.key");
+    const CLIENT_CERT: &str = include_str!("../../testdata/tls/client.pem");
+    const CLIENT_KEY: &str = include_str!("../../testdata/tls/client.key");
+
+    /// Writes `contents` to `path`, making sure its modification time changes
+    /// even on filesystems with coarse timestamps.
+    fn rewrite(path: &Path, contents: &str) -> anyhow::Result<()> {
+        let previous = modified(path).ok();
+        loop {
+            fs::write(path, contents)?;
+            if Some(modified(path)?) != previous {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn write_fixture(dir: &TempDir, name: &str, contents: &str) -> anyhow::Result<PathBuf> {
+        let path = dir.path().join(name);
+        rewrite(&path, contents)?;
+        Ok(path)
+    }
+
+    fn current_cert(resolver: &ReloadingCertResolver) -> Vec<rustls::Certificate> {
+        resolver.current.lock().key.cert.clone()
+    }
+
+    #[test]
+    fn test_reload_if_changed() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let cert_path = write_fixture(&dir, "cert.pem", SERVER_CERT)?;
+        let key_path = write_fixture(&dir, "key.pem", SERVER_KEY)?;
+        let resolver = ReloadingCertResolver::new(cert_path.clone(), key_path.clone())?;
+        assert_eq!(current_cert(&resolver), read_certs(&cert_path)?);
+        assert!(!resolver.reload_if_changed()?);
+
+        // A half-written rotation fails to load and keeps the old certificate.
+        rewrite(&cert_path, ROTATED_CERT)?;
+        rewrite(&key_path, "")?;
+        assert!(resolver.reload_if_changed().is_err());
+        assert_ne!(current_cert(&resolver), read_certs(&cert_path)?);
+
+        rewrite(&key_path, ROTATED_KEY)?;
+        assert!(resolver.reload_if_changed()?);
+        assert_eq!(current_cert(&resolver), read_certs(&cert_path)?);
+        assert!(!resolver.reload_if_changed()?);
+        Ok(())
+    }
+
+    /// Serves the client authentication of each connection as the response
+    /// body.
+    async fn serve_client_authentication(dir: &TempDir) -> anyhow::Result<SocketAddr> {
+        let tls_config = TlsConfig {
+            cert_path: write_fixture(dir, "cert.pem", SERVER_CERT)?,
+            key_path: write_fixture(dir, "key.pem", SERVER_KEY)?,
+            client_ca_path: Some(write_fixture(dir, "ca.pem", CA_CERT)?),
+        };
+        let resolver = Arc::new(ReloadingCertResolver::new(
+            tls_config.cert_path.clone(),
+            tls_config.key_path.clone(),
+        )?);
+        let server_config = tls_config.server_config(resolver)?;
+        let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse()?)?;
+        let addr = incoming.local_addr();
+        let make_svc = make_service_fn(|conn: &TlsConnection| {
+            let client_authentication = conn.client_authentication();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_: Request<Body>| async move {
+                    Ok::<_, Infallible>(Response::new(Body::from(format!(
+                        "{client_authentication:?}"
+                    ))))
+                }))
+            }
+        });
+        tokio::spawn(Server::builder(TlsIncoming::new(incoming, server_config)).serve(make_svc));
+        Ok(addr)
+    }
+
+    async fn request(
+        addr: SocketAddr,
+        client_cert: Option<(&str, &str)>,
+    ) -> anyhow::Result<String> {
+        let dir = TempDir::new()?;
+        let mut roots = RootCertStore::empty();
+        for cert in read_certs(&write_fixture(&dir, "ca.pem", CA_CERT)?)? {
+            roots.add(&cert)?;
+        }
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let config = match client_cert {
+            Some((cert, key)) => builder.with_client_auth_cert(
+                read_certs(&write_fixture(&dir, "client.pem", cert)?)?,
+                read_private_key(&write_fixture(&dir, "client.key", key)?)?,
+            )?,
+            None => builder.with_no_client_auth(),
+        };
+        let stream = TlsConnector::from(Arc::new(config))
+            .connect(
+                ServerName::try_from("localhost")?,
+                TcpStream::connect(addr).await?,
+            )
+            .await?;
+        let (mut sender, connection) = client_conn::handshake(stream).await?;
+        tokio::spawn(connection);
+        let response = sender
+            .send_request(Request::get("/").body(Body::empty())?)
+            .await?;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok(String::from_utf8(body.to_vec())?)
+    }
+
+    #[tokio::test]
+    async fn test_client_authentication() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let addr = serve_client_authentication(&dir).await?;
+
+        assert_eq!(request(addr, None).await?, "Anonymous");
+        assert_eq!(
+            request(addr, Some((CLIENT_CERT, CLIENT_KEY))).await?,
+            "Certificate"
+        );
+        // The server certificate chains to the client CA but isn't valid for
+        // client authentication, so the handshake fails.
+        assert!(request(addr, Some((SERVER_CERT, SERVER_KEY)))
+            .await
+            .is_err());
+        Ok(())
+    }
+}
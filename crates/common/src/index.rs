@@ -1,6 +1,7 @@
 use std::{
     borrow::Borrow,
     cmp::Ordering,
+    collections::BTreeSet,
 };
 
 use derive_more::Deref;
@@ -170,6 +171,27 @@ impl IndexKey {
     }
 }
 
+/// Expands the indexed values of a document for a multikey index, where the
+/// field at `multikey_position` has one index entry per distinct element when
+/// it's an array. An empty array has no entries.
+pub fn multikey_index_values(
+    values: Vec<Option<ConvexValue>>,
+    multikey_position: usize,
+) -> Vec<Vec<Option<ConvexValue>>> {
+    let elements: BTreeSet<ConvexValue> = match values.get(multikey_position) {
+        Some(Some(ConvexValue::Array(array))) => array.iter().cloned().collect(),
+        _ => return vec![values],
+    };
+    elements
+        .into_iter()
+        .map(|element| {
+            let mut element_values = values.clone();
+            element_values[multikey_position] = Some(element);
+            element_values
+        })
+        .collect()
+}
+
 impl From<IndexKey> for (Vec<Option<ConvexValue>>, DeveloperDocumentId) {
     fn from(k: IndexKey) -> Self {
         let mut values = k.values_with_id;
@@ -293,6 +293,14 @@ impl From<QuerySource> for JsonQuerySource {
     }
 }
 
+impl TryFrom<QuerySource> for JsonValue {
+    type Error = anyhow::Error;
+
+    fn try_from(source: QuerySource) -> Result<Self, Self::Error> {
+        Ok(serde_json::to_value(JsonQuerySource::from(source))?)
+    }
+}
+
 impl TryFrom<JsonValue> for Query {
     type Error = anyhow::Error;
 
@@ -165,6 +165,14 @@ pub static DOCUMENTS_IN_MEMORY: LazyLock<usize> =
 pub static HTTP_SERVER_TCP_BACKLOG: LazyLock<u32> =
     LazyLock::new(|| env_config("HTTP_SERVER_TCP_BACKLOG", 256));
 
+/// How often to check the TLS certificate and key files for changes.
+pub static TLS_CERT_RELOAD_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("TLS_CERT_RELOAD_INTERVAL_SECS", 60)));
+
+/// How long a client has to complete the TLS handshake after connecting.
+pub static TLS_HANDSHAKE_TIMEOUT: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("TLS_HANDSHAKE_TIMEOUT_SECS", 10)));
+
 /// The max concurrent of concurrent HTTP requests. This also limits Node.js
 /// action callbacks concurrency since those go over http.
 pub static HTTP_SERVER_MAX_CONCURRENT_REQUESTS: LazyLock<usize> =
@@ -737,6 +745,33 @@ pub static MAX_CONCURRENT_ACTION_OPS: LazyLock<usize> =
 pub static SYNC_MAX_SEND_TRANSITION_COUNT: LazyLock<usize> =
     LazyLock::new(|| env_config("SYNC_MAX_SEND_TRANSITION_COUNT", 2));
 
+/// Send query results as diffs against the previous result to sync clients
+/// that support them, when the diff is smaller than the new result.
+pub static SYNC_QUERY_DIFFS_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("SYNC_QUERY_DIFFS_ENABLED", true));
+
+/// Query results larger than this are always sent in full and aren't kept to
+/// diff the next result against, which bounds the memory each subscription
+/// holds on to.
+pub static SYNC_QUERY_DIFF_MAX_VALUE_BYTES: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_QUERY_DIFF_MAX_VALUE_BYTES", 1 << 20));
+
+/// Negotiate permessage-deflate compression on the sync WebSocket with
+/// clients that offer it. Experimental, since it rewrites WebSocket frames
+/// itself rather than relying on the WebSocket library.
+pub static SYNC_WS_COMPRESSION_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("SYNC_WS_COMPRESSION_ENABLED", false));
+
+/// Base-2 log of the window the backend compresses sync messages with, from 9
+/// to 15. Each compressed connection holds a compressor with a window of this
+/// size.
+pub static SYNC_WS_COMPRESSION_WINDOW_BITS: LazyLock<u8> =
+    LazyLock::new(|| env_config("SYNC_WS_COMPRESSION_WINDOW_BITS", 15));
+
+/// Sync messages smaller than this are sent uncompressed.
+pub static SYNC_WS_COMPRESSION_THRESHOLD_BYTES: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_WS_COMPRESSION_THRESHOLD_BYTES", 1024));
+
 /// Max Axiom sink attributes. This is a knob just in case a user actually hits
 /// the limit but has an Enterprise Axiom plan that lets them use more than the
 /// limit we've configured.
@@ -928,6 +963,12 @@ pub static MAX_FUNRUN_REQUEST_MESSAGE_SIZE: LazyLock<usize> =
 pub static MAX_FUNRUN_RESPONSE_MESSAGE_SIZE: LazyLock<usize> =
     LazyLock::new(|| env_config("MAX_FUNRUN_RESPONSE_MESSAGE_SIZE", 1 << 25)); // 32 MiB
 
+/// The maximum size for Searchlight request and response messages. Text query
+/// requests carry the memory index's shortlisted terms and responses carry
+/// term positions for every candidate.
+pub static MAX_SEARCHLIGHT_MESSAGE_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("MAX_SEARCHLIGHT_MESSAGE_SIZE", 1 << 25)); // 32 MiB
+
 /// The maximum size for Backend HTTP and GRPC action callbacks. This is 8MiB
 /// for path and args, plus a buffer for the smaller fields This should also be
 /// enough for vector and text search callbacks.
@@ -986,3 +1027,89 @@ pub static MAX_PUSH_BYTES: LazyLock<usize> =
 /// Percentage of request traces that should sampled
 pub static REQUEST_TRACE_SAMPLE_CONFIG: LazyLock<SamplingConfig> =
     LazyLock::new(|| env_config("REQUEST_TRACE_SAMPLE_CONFIG", SamplingConfig::default()));
+
+/// If set, files in `_storage` are deduplicated by sha256: uploads with the
+/// same contents as an existing file share its storage object, which is only
+/// deleted once no file references it.
+pub static FILE_STORAGE_DEDUPLICATION: LazyLock<bool> =
+    LazyLock::new(|| env_config("FILE_STORAGE_DEDUPLICATION", false));
+
+/// Max number of unreferenced file storage blobs to delete per transaction.
+pub static FILE_STORAGE_BLOB_DELETE_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("FILE_STORAGE_BLOB_DELETE_BATCH_SIZE", 100));
+
+/// How often to look for storage objects that no metadata refers to.
+pub static STORAGE_GC_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("STORAGE_GC_INTERVAL_SECS", 6 * 60 * 60)));
+
+/// Unreferenced storage objects younger than this are never deleted, since
+/// they may belong to an upload whose metadata hasn't been committed yet.
+pub static STORAGE_GC_GRACE_PERIOD: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("STORAGE_GC_GRACE_PERIOD_SECS", 24 * 60 * 60)));
+
+/// If set, the storage garbage collector only reports unreferenced objects
+/// instead of deleting them.
+pub static STORAGE_GC_DRY_RUN: LazyLock<bool> =
+    LazyLock::new(|| env_config("STORAGE_GC_DRY_RUN", false));
+
+/// How long a resumable file upload session can be used after it's created.
+/// Keep this shorter than `STORAGE_GC_GRACE_PERIOD`, since the storage garbage
+/// collector can't tell which objects belong to unfinished uploads.
+pub static FILE_UPLOAD_SESSION_TTL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("FILE_UPLOAD_SESSION_TTL_SECS", 12 * 60 * 60)));
+
+/// Max size of a single part of a resumable file upload.
+pub static FILE_UPLOAD_MAX_PART_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("FILE_UPLOAD_MAX_PART_SIZE", 64 << 20));
+
+/// Max number of expired upload sessions to delete when starting a new one.
+pub static FILE_UPLOAD_SESSION_DELETE_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("FILE_UPLOAD_SESSION_DELETE_BATCH_SIZE", 10));
+
+/// How often to look for documents past their table's TTL.
+pub static TTL_WORKER_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("TTL_WORKER_INTERVAL_SECS", 60)));
+
+/// Max number of expired documents to delete in a single transaction.
+pub static TTL_DELETE_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("TTL_DELETE_BATCH_SIZE", 256));
+
+/// Default number of documents a data migration processes in a single
+/// transaction, if the migration doesn't specify one.
+pub static MIGRATION_DEFAULT_BATCH_SIZE: LazyLock<u32> =
+    LazyLock::new(|| env_config("MIGRATION_DEFAULT_BATCH_SIZE", 100));
+
+/// Upper bound on a data migration's batch size, to keep each batch's
+/// transaction within limits.
+pub static MIGRATION_MAX_BATCH_SIZE: LazyLock<u32> =
+    LazyLock::new(|| env_config("MIGRATION_MAX_BATCH_SIZE", 1000));
+
+/// Whether to keep a log of slow queries run by functions, which can be read
+/// through the admin API.
+pub static SLOW_QUERY_LOG_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("SLOW_QUERY_LOG_ENABLED", false));
+
+/// Queries that spend at least this long fetching documents are logged as
+/// slow.
+pub static SLOW_QUERY_THRESHOLD: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_millis(env_config("SLOW_QUERY_THRESHOLD_MS", 500)));
+
+/// Queries that scan at least this many documents are logged as slow. Queries
+/// approaching the transaction's read limits are always logged.
+pub static SLOW_QUERY_DOCUMENTS_SCANNED_THRESHOLD: LazyLock<usize> =
+    LazyLock::new(|| env_config("SLOW_QUERY_DOCUMENTS_SCANNED_THRESHOLD", 4096));
+
+/// Number of slow queries kept in memory, dropping the oldest first.
+pub static SLOW_QUERY_LOG_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("SLOW_QUERY_LOG_SIZE", 1000));
+
+/// Number of pushes kept in the deployment history. Older deployments can no
+/// longer be rolled back to, and their module versions and source packages
+/// are deleted. Must be at least 1.
+pub static DEPLOYMENT_HISTORY_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("DEPLOYMENT_HISTORY_SIZE", 10));
+
+/// Maximum number of existing documents a push plan checks against a pending
+/// schema. Past this, the plan reports the schema's validation as unknown.
+pub static DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS: LazyLock<usize> =
+    LazyLock::new(|| env_config("DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS", 10000));
@@ -13,6 +13,7 @@ use itertools::{
     Itertools,
 };
 use pb::{
+    backend::internal_search_filter_expression::Filter as FilterProto,
     convex_cursor::IndexKey as IndexKeyProto,
     funrun::cursor::Position as PositionProto,
 };
@@ -560,6 +561,24 @@ pub enum SearchVersion {
     V2,
 }
 
+impl From<SearchVersion> for pb::backend::SearchVersion {
+    fn from(version: SearchVersion) -> Self {
+        match version {
+            SearchVersion::V1 => pb::backend::SearchVersion::V1,
+            SearchVersion::V2 => pb::backend::SearchVersion::V2,
+        }
+    }
+}
+
+impl From<pb::backend::SearchVersion> for SearchVersion {
+    fn from(version: pb::backend::SearchVersion) -> Self {
+        match version {
+            pb::backend::SearchVersion::V1 => SearchVersion::V1,
+            pb::backend::SearchVersion::V2 => SearchVersion::V2,
+        }
+    }
+}
+
 /// A query against a search index.
 ///
 /// Results are returned in relevancy order based on how well they match
@@ -622,6 +641,79 @@ impl InternalSearch {
     }
 }
 
+impl From<InternalSearch> for pb::backend::InternalSearch {
+    fn from(
+        InternalSearch {
+            index_name,
+            table_name,
+            filters,
+        }: InternalSearch,
+    ) -> Self {
+        Self {
+            index_name: Some(index_name.into()),
+            table_name: Some(table_name.to_string()),
+            filters: filters
+                .into_iter()
+                .map(|filter| match filter {
+                    InternalSearchFilterExpression::Search(field_path, s) => {
+                        pb::backend::InternalSearchFilterExpression {
+                            field_path: Some(field_path.into()),
+                            filter: Some(FilterProto::Search(s)),
+                        }
+                    },
+                    InternalSearchFilterExpression::Eq(field_path, bytes) => {
+                        pb::backend::InternalSearchFilterExpression {
+                            field_path: Some(field_path.into()),
+                            filter: Some(FilterProto::Eq(bytes)),
+                        }
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::backend::InternalSearch> for InternalSearch {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        pb::backend::InternalSearch {
+            index_name,
+            table_name,
+            filters,
+        }: pb::backend::InternalSearch,
+    ) -> anyhow::Result<Self> {
+        let filters = filters
+            .into_iter()
+            .map(
+                |pb::backend::InternalSearchFilterExpression { field_path, filter }| {
+                    let field_path = field_path
+                        .ok_or_else(|| anyhow::anyhow!("Missing field_path"))?
+                        .try_into()?;
+                    let filter = match filter.ok_or_else(|| anyhow::anyhow!("Missing filter"))? {
+                        FilterProto::Search(s) => {
+                            InternalSearchFilterExpression::Search(field_path, s)
+                        },
+                        FilterProto::Eq(bytes) => {
+                            InternalSearchFilterExpression::Eq(field_path, bytes)
+                        },
+                    };
+                    anyhow::Ok(filter)
+                },
+            )
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            index_name: index_name
+                .ok_or_else(|| anyhow::anyhow!("Missing index_name"))?
+                .try_into()?,
+            table_name: table_name
+                .ok_or_else(|| anyhow::anyhow!("Missing table_name"))?
+                .parse()?,
+            filters,
+        })
+    }
+}
+
 /// Filter field values under this size are stored as bytes. Otherwise
 /// we hash them down to 32 bytes.
 const MAX_FILTER_FIELD_LENGTH: usize = 32;
@@ -954,6 +1046,15 @@ where
 }
 
 impl Expression {
+    /// The expressions that must all hold for this one to hold, flattening
+    /// nested `And`s.
+    pub fn conjuncts(&self) -> Vec<&Expression> {
+        match self {
+            Expression::And(exprs) => exprs.iter().flat_map(|e| e.conjuncts()).collect(),
+            _ => vec![self],
+        }
+    }
+
     /// Evaluate the expression and return the result. Expression::Fields are
     /// evaluated on `environ`.
     pub fn eval(&self, environ: &ConvexObject) -> anyhow::Result<MaybeValue> {
@@ -1192,6 +1293,7 @@ mod tests {
             Cursor,
             IndexRange,
             IndexRangeExpression,
+            InternalSearch,
             MaybeValue,
         },
     };
@@ -1423,5 +1525,10 @@ mod tests {
         fn proptest_cursor_serialization(v in any::<Cursor>()) {
             assert_roundtrips::<Cursor, pb::funrun::Cursor>(v);
         }
+
+        #[test]
+        fn proptest_internal_search_serialization(v in any::<InternalSearch>()) {
+            assert_roundtrips::<InternalSearch, pb::backend::InternalSearch>(v);
+        }
     }
 }
@@ -1,8 +1,10 @@
-use std::collections::{
-    BTreeMap,
-    BTreeSet,
-    HashMap,
-    HashSet,
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+        HashSet,
+    },
+    time::Duration,
 };
 
 use anyhow::Context;
@@ -27,8 +29,11 @@ use super::{
     validator::{
         FieldValidator,
         LiteralValidator,
+        NumericBound,
         ObjectValidator,
+        Pattern,
         Validator,
+        ValidatorConstraints,
     },
     DatabaseSchema,
     DocumentSchema,
@@ -37,6 +42,11 @@ use super::{
 };
 use crate::{
     bootstrap_model::index::{
+        database_index::{
+            is_valid_index_filter,
+            IndexAggregate,
+            IndexedFields,
+        },
         index_validation_error::{
             self,
             index_not_unique,
@@ -45,11 +55,18 @@ use crate::{
         },
         vector_index::VectorDimensions,
     },
-    json::invalid_json,
+    json::{
+        invalid_json,
+        JsonExpression,
+    },
+    query::Expression,
     schemas::{
         invalid_top_level_type_in_schema,
+        ForeignKey,
+        OnDelete,
         SearchIndexSchema,
         TableDefinition,
+        TableTtl,
         MAX_INDEXES_PER_TABLE,
         MAX_SEARCH_INDEXES_PER_TABLE,
         MAX_VECTOR_INDEXES_PER_TABLE,
@@ -136,6 +153,53 @@ struct TableDefinitionJson {
     search_indexes: Option<Vec<JsonValue>>,
     vector_indexes: Option<Vec<JsonValue>>,
     document_type: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ttl: Option<TableTtlJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    foreign_keys: Vec<ForeignKeyJson>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TableTtlJson {
+    field: String,
+    duration_ms: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ForeignKeyJson {
+    field: String,
+    table: String,
+    on_delete: OnDeleteJson,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum OnDeleteJson {
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+impl From<OnDeleteJson> for OnDelete {
+    fn from(on_delete: OnDeleteJson) -> Self {
+        match on_delete {
+            OnDeleteJson::Restrict => Self::Restrict,
+            OnDeleteJson::Cascade => Self::Cascade,
+            OnDeleteJson::SetNull => Self::SetNull,
+        }
+    }
+}
+
+impl From<OnDelete> for OnDeleteJson {
+    fn from(on_delete: OnDelete) -> Self {
+        match on_delete {
+            OnDelete::Restrict => Self::Restrict,
+            OnDelete::Cascade => Self::Cascade,
+            OnDelete::SetNull => Self::SetNull,
+        }
+    }
 }
 
 // Collect the index names separately from the deduplicating map so that we can
@@ -159,21 +223,20 @@ fn parse_names_and_indexes<T: TryFrom<JsonValue, Error = anyhow::Error>>(
     .map_err(|e: anyhow::Error| e.wrap_error_message(|s| format!("In table \"{table_name}\": {s}")))
 }
 
-fn validate_unique_index_fields<T, Y: Clone + Eq + std::hash::Hash>(
+fn validate_unique_index_fields<T, Y: Eq>(
     indexes: &BTreeMap<IndexDescriptor, T>,
     unique_index_field: impl Fn(&T) -> Y,
     non_unique_error: impl Fn(&IndexDescriptor, &IndexDescriptor) -> ErrorMetadata,
 ) -> anyhow::Result<()> {
-    let index_fields: BTreeMap<_, _> = indexes
-        .iter()
-        .map(|(name, fields)| (name, unique_index_field(fields)))
-        .collect();
-
-    let mut seen: HashMap<_, &IndexDescriptor> = HashMap::new();
-    for (name, fields) in index_fields.into_iter() {
-        if let Some(other_name) = seen.insert(fields, name) {
+    // Compared pairwise since index filters aren't hashable, and there are at
+    // most a few dozen indexes per table.
+    let mut seen: Vec<(Y, &IndexDescriptor)> = vec![];
+    for (name, index) in indexes {
+        let fields = unique_index_field(index);
+        if let Some((_, other_name)) = seen.iter().find(|(other, _)| *other == fields) {
             anyhow::bail!(non_unique_error(name, other_name));
         }
+        seen.push((fields, name));
     }
     Ok(())
 }
@@ -211,9 +274,17 @@ impl TryFrom<JsonValue> for TableDefinition {
                 anyhow::bail!(index_validation_error::empty_index(&table_name, schema));
             }
         }
+        // Indexes on the same fields are distinct if their filters or multikey
+        // fields are.
         validate_unique_index_fields(
             &indexes,
-            |idx| Vec::<FieldPath>::from(idx.fields.clone()),
+            |idx| {
+                (
+                    Vec::<FieldPath>::from(idx.fields.clone()),
+                    idx.filter.clone(),
+                    idx.multikey_field.clone(),
+                )
+            },
             |index1, index2| index_not_unique(&table_name, index1, index2),
         )?;
 
@@ -269,13 +340,95 @@ impl TryFrom<JsonValue> for TableDefinition {
             }
         }
 
-        Ok(Self {
+        let ttl = j
+            .ttl
+            .map(|ttl| -> anyhow::Result<_> {
+                let field = ttl.field.parse().with_context(|| {
+                    index_validation_error::invalid_ttl_field(&table_name, &ttl.field)
+                })?;
+                Ok(TableTtl {
+                    field,
+                    duration: Duration::from_millis(ttl.duration_ms),
+                })
+            })
+            .transpose()?;
+        let foreign_keys = j
+            .foreign_keys
+            .into_iter()
+            .map(|foreign_key| -> anyhow::Result<_> {
+                let field: FieldPath = foreign_key.field.parse().with_context(|| {
+                    index_validation_error::invalid_foreign_key_field(
+                        &table_name,
+                        &foreign_key.field,
+                    )
+                })?;
+                // Setting the field to null is a top-level patch.
+                anyhow::ensure!(
+                    field.fields().len() == 1,
+                    index_validation_error::invalid_foreign_key_field(
+                        &table_name,
+                        &foreign_key.field
+                    )
+                );
+                let table: TableName = foreign_key.table.parse().with_context(|| {
+                    index_validation_error::invalid_foreign_key_table(
+                        &table_name,
+                        &foreign_key.table,
+                    )
+                })?;
+                anyhow::ensure!(
+                    !table.is_system(),
+                    index_validation_error::invalid_foreign_key_table(
+                        &table_name,
+                        &foreign_key.table
+                    )
+                );
+                Ok((
+                    field,
+                    ForeignKey {
+                        table,
+                        on_delete: foreign_key.on_delete.into(),
+                    },
+                ))
+            })
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+        let table_definition = Self {
             table_name,
             indexes,
             search_indexes,
             vector_indexes,
             document_type,
-        })
+            ttl,
+            foreign_keys,
+        };
+        if let Some(ref ttl) = table_definition.ttl {
+            anyhow::ensure!(
+                table_definition.ttl_index().is_some(),
+                index_validation_error::ttl_index_missing(&table_definition.table_name, &ttl.field)
+            );
+        }
+        for (field, foreign_key) in &table_definition.foreign_keys {
+            table_definition.foreign_key_index(field).with_context(|| {
+                index_validation_error::foreign_key_field_too_long(
+                    &table_definition.table_name,
+                    field,
+                )
+            })?;
+            // Otherwise deleting a referenced document would fail schema
+            // validation when it nulls out the field.
+            if foreign_key.on_delete == OnDelete::SetNull
+                && let Some(document_type) = &table_definition.document_type
+            {
+                anyhow::ensure!(
+                    document_type.allows_null_field(field.last()),
+                    index_validation_error::foreign_key_set_null_not_nullable(
+                        &table_definition.table_name,
+                        field
+                    )
+                );
+            }
+        }
+        Ok(table_definition)
     }
 }
 
@@ -289,6 +442,8 @@ impl TryFrom<TableDefinition> for JsonValue {
             search_indexes,
             vector_indexes,
             document_type,
+            ttl,
+            foreign_keys,
         }: TableDefinition,
     ) -> anyhow::Result<Self> {
         let table_name = String::from(table_name);
@@ -315,6 +470,18 @@ impl TryFrom<TableDefinition> for JsonValue {
             search_indexes,
             vector_indexes,
             document_type,
+            ttl: ttl.map(|ttl| TableTtlJson {
+                field: String::from(ttl.field),
+                duration_ms: ttl.duration.as_millis() as u64,
+            }),
+            foreign_keys: foreign_keys
+                .into_iter()
+                .map(|(field, foreign_key)| ForeignKeyJson {
+                    field: String::from(field),
+                    table: String::from(foreign_key.table),
+                    on_delete: foreign_key.on_delete.into(),
+                })
+                .collect(),
         })?)
     }
 }
@@ -324,6 +491,21 @@ impl TryFrom<TableDefinition> for JsonValue {
 struct IndexSchemaJson {
     index_descriptor: String,
     fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unique: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filter: Option<JsonExpression>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multikey_field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aggregate: Option<IndexAggregateJson>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexAggregateJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sum_field: Option<String>,
 }
 
 impl TryFrom<JsonValue> for IndexSchema {
@@ -332,7 +514,7 @@ impl TryFrom<JsonValue> for IndexSchema {
     fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
         let j: IndexSchemaJson = serde_json::from_value(value).with_context(invalid_json)?;
         let index_descriptor = j.index_descriptor.parse()?;
-        let fields = j
+        let fields: IndexedFields = j
             .fields
             .into_iter()
             .map(|p| {
@@ -345,9 +527,62 @@ impl TryFrom<JsonValue> for IndexSchema {
             .map_err(|e: anyhow::Error| {
                 e.wrap_error_message(|s| format!("In index \"{index_descriptor}\": {s}"))
             })?;
+        let filter = j.filter.map(Expression::try_from).transpose()?;
+        if let Some(ref filter) = filter {
+            anyhow::ensure!(
+                is_valid_index_filter(filter),
+                index_validation_error::invalid_index_filter(&index_descriptor)
+            );
+        }
+        let unique = j.unique.unwrap_or(false);
+        let multikey_field = j
+            .multikey_field
+            .map(|p| {
+                p.parse().with_context(|| {
+                    index_validation_error::invalid_index_field(&index_descriptor, &p)
+                })
+            })
+            .transpose()?;
+        if let Some(ref multikey_field) = multikey_field {
+            anyhow::ensure!(
+                fields.contains(multikey_field),
+                index_validation_error::multikey_field_not_indexed(
+                    &index_descriptor,
+                    multikey_field
+                )
+            );
+            anyhow::ensure!(
+                !unique,
+                index_validation_error::unique_multikey_index(&index_descriptor)
+            );
+        }
+        let aggregate = j
+            .aggregate
+            .map(|aggregate| -> anyhow::Result<_> {
+                let sum_field = aggregate
+                    .sum_field
+                    .map(|p| {
+                        p.parse().with_context(|| {
+                            index_validation_error::invalid_index_field(&index_descriptor, &p)
+                        })
+                    })
+                    .transpose()?;
+                Ok(IndexAggregate { sum_field })
+            })
+            .transpose()?;
+        if aggregate.is_some() {
+            anyhow::ensure!(
+                multikey_field.is_none(),
+                index_validation_error::multikey_aggregate_index(&index_descriptor)
+            );
+        }
         Ok(Self {
             index_descriptor,
             fields,
+            unique,
+            filter,
+            multikey_field,
+            aggregate,
         })
     }
 }
@@ -359,6 +594,10 @@ impl TryFrom<IndexSchema> for JsonValue {
         IndexSchema {
             index_descriptor,
             fields,
+            unique,
+            filter,
+            multikey_field,
+            aggregate,
         }: IndexSchema,
     ) -> anyhow::Result<Self> {
         let index_schema_json = IndexSchemaJson {
@@ -367,6 +606,12 @@ impl TryFrom<IndexSchema> for JsonValue {
                 .into_iter()
                 .map(String::from)
                 .collect::<Vec<_>>(),
+            unique: unique.then_some(true),
+            filter: filter.map(JsonExpression::from),
+            multikey_field: multikey_field.map(String::from),
+            aggregate: aggregate.map(|aggregate| IndexAggregateJson {
+                sum_field: aggregate.sum_field.map(String::from),
+            }),
         };
         Ok(serde_json::to_value(index_schema_json)?)
     }
@@ -690,9 +935,25 @@ impl TryFrom<ValidatorJson> for Validator {
 impl TryFrom<JsonValue> for Validator {
     type Error = anyhow::Error;
 
-    fn try_from(value: JsonValue) -> anyhow::Result<Self> {
+    fn try_from(mut value: JsonValue) -> anyhow::Result<Self> {
+        // Constraints are extra fields next to the validator's type, like
+        // `{"type": "string", "maxLength": 20}`.
+        let mut constraints = serde_json::Map::new();
+        if let JsonValue::Object(ref mut fields) = value {
+            for field in CONSTRAINT_FIELDS {
+                if let Some(constraint) = fields.remove(field) {
+                    constraints.insert(field.to_string(), constraint);
+                }
+            }
+        }
         let schema_type_json: ValidatorJson = serde_json::from_value(value)?;
-        schema_type_json.try_into()
+        let validator = schema_type_json.try_into()?;
+        if constraints.is_empty() {
+            return Ok(validator);
+        }
+        let constraints_json: ValidatorConstraintsJson =
+            serde_json::from_value(JsonValue::Object(constraints))?;
+        Validator::constrained(validator, constraints_json.try_into()?)
     }
 }
 
@@ -742,12 +1003,105 @@ impl TryFrom<Validator> for JsonValue {
                     .map(JsonValue::try_from)
                     .collect::<anyhow::Result<Vec<_>>>()?,
             },
+            Validator::Constrained(validator, constraints) => {
+                let mut json = JsonValue::try_from(*validator)?;
+                let JsonValue::Object(fields) = &mut json else {
+                    anyhow::bail!("Constrained validator didn't serialize to an object");
+                };
+                let constraints_json =
+                    serde_json::to_value(ValidatorConstraintsJson::try_from(constraints)?)?;
+                if let JsonValue::Object(constraint_fields) = constraints_json {
+                    fields.extend(constraint_fields);
+                }
+                return Ok(json);
+            },
             Validator::Any => ValidatorJson::Any,
         };
         Ok(serde_json::to_value(schema_type)?)
     }
 }
 
+const CONSTRAINT_FIELDS: [&str; 6] = ["minLength", "maxLength", "min", "max", "pattern", "integer"];
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidatorConstraintsJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_length: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<JsonValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    integer: Option<bool>,
+}
+
+impl TryFrom<ValidatorConstraintsJson> for ValidatorConstraints {
+    type Error = anyhow::Error;
+
+    fn try_from(j: ValidatorConstraintsJson) -> anyhow::Result<Self> {
+        Ok(ValidatorConstraints {
+            min_length: j.min_length,
+            max_length: j.max_length,
+            min: j.min.map(NumericBound::try_from).transpose()?,
+            max: j.max.map(NumericBound::try_from).transpose()?,
+            pattern: j.pattern.as_deref().map(Pattern::new).transpose()?,
+            integer: j.integer.unwrap_or(false),
+        })
+    }
+}
+
+impl TryFrom<ValidatorConstraints> for ValidatorConstraintsJson {
+    type Error = anyhow::Error;
+
+    fn try_from(c: ValidatorConstraints) -> anyhow::Result<Self> {
+        Ok(ValidatorConstraintsJson {
+            min_length: c.min_length,
+            max_length: c.max_length,
+            min: c.min.map(JsonValue::try_from).transpose()?,
+            max: c.max.map(JsonValue::try_from).transpose()?,
+            pattern: c.pattern.map(|p| p.as_str().to_string()),
+            integer: c.integer.then_some(true),
+        })
+    }
+}
+
+impl TryFrom<JsonValue> for NumericBound {
+    type Error = anyhow::Error;
+
+    fn try_from(v: JsonValue) -> anyhow::Result<Self> {
+        match ConvexValue::try_from(v)? {
+            ConvexValue::Float64(f) => Ok(NumericBound::Float64(f.into())),
+            ConvexValue::Int64(i) => Ok(NumericBound::Int64(i)),
+            v => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidValidatorConstraint",
+                format!("Validator bounds must be numbers or bigints, but found {v}"),
+            )),
+        }
+    }
+}
+
+impl TryFrom<NumericBound> for JsonValue {
+    type Error = anyhow::Error;
+
+    fn try_from(bound: NumericBound) -> anyhow::Result<JsonValue> {
+        let v = match bound {
+            NumericBound::Float64(f) => {
+                let f: f64 = f.into();
+                let n = serde_json::Number::from_f64(f)
+                    .ok_or_else(|| anyhow::anyhow!("Number failed to serialize from f64: {f}"))?;
+                JsonValue::Number(n)
+            },
+            NumericBound::Int64(i) => JsonValue::from(ConvexValue::Int64(i)),
+        };
+        Ok(v)
+    }
+}
+
 impl TryFrom<JsonValue> for LiteralValidator {
     type Error = anyhow::Error;
 
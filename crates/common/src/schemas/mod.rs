@@ -7,6 +7,7 @@ use std::{
     fmt::Display,
     iter,
     marker::PhantomData,
+    time::Duration,
 };
 
 use errors::ErrorMetadata;
@@ -40,7 +41,10 @@ use self::validator::{
 };
 use crate::{
     bootstrap_model::index::{
-        database_index::IndexedFields,
+        database_index::{
+            IndexAggregate,
+            IndexedFields,
+        },
         index_validation_error,
         vector_index::VectorDimensions,
         MAX_SEARCH_INDEX_FILTER_FIELDS_SIZE,
@@ -48,8 +52,10 @@ use crate::{
     },
     document::ResolvedDocument,
     paths::FieldPath,
+    query::Expression,
     types::{
         IndexDescriptor,
+        IndexName,
         TableName,
     },
 };
@@ -97,6 +103,29 @@ pub enum SchemaValidationError {
         table_in_schema: TableName,
         table_name: TableName,
     },
+
+    #[display(
+        fmt = "Unique index \"{index_descriptor}\" on table \"{table_name}\" can't be built \
+               because documents with these IDs have the same indexed values: {}",
+        "ids.iter().map(|id| id.encode()).collect::<Vec<_>>().join(\", \")"
+    )]
+    UniqueIndexViolation {
+        table_name: TableName,
+        index_descriptor: IndexDescriptor,
+        ids: Vec<DocumentIdV6>,
+    },
+
+    #[display(
+        fmt = "Field {field} of document with ID \"{}\" in table \"{table_name}\" must be null or \
+               the ID of an existing document in table \"{referenced_table}\"",
+        "id.encode()"
+    )]
+    ForeignKeyViolation {
+        table_name: TableName,
+        id: DocumentIdV6,
+        field: FieldPath,
+        referenced_table: TableName,
+    },
 }
 
 #[derive(derive_more::Display, Debug, Clone, PartialEq)]
@@ -177,6 +206,8 @@ macro_rules! db_schema {
                         search_indexes: Default::default(),
                         vector_indexes: Default::default(),
                         document_type: Some($document_schema),
+                        ttl: None,
+                        foreign_keys: Default::default(),
                     };
                     tables.insert(table_name, table_def);
                 )*
@@ -208,6 +239,8 @@ macro_rules! db_schema_not_validated {
                         search_indexes: Default::default(),
                         vector_indexes: Default::default(),
                         document_type: Some($document_schema),
+                        ttl: None,
+                        foreign_keys: Default::default(),
                     };
                     tables.insert(table_name, table_def);
                 )*
@@ -261,6 +294,8 @@ macro_rules! db_schema_with_vector_indexes {
                         search_indexes: Default::default(),
                         vector_indexes,
                         document_type: Some($document_schema),
+                        ttl: None,
+                        foreign_keys: Default::default(),
                     };
                     tables.insert(table_name, table_def);
                 )*
@@ -284,14 +319,18 @@ impl DatabaseSchema {
     where
         F: Fn(&TableName) -> Shape<C, S>,
     {
-        if !new_schema.schema_validation {
-            return Ok(BTreeSet::new());
-        }
-
         let possible_table_names: Vec<Option<&TableName>> = new_schema
             .tables
             .iter()
             .map(|(table_name, table_definition)| {
+                // Foreign keys are enforced even without schema validation, so
+                // existing documents need to reference existing documents.
+                if Self::has_new_foreign_keys(table_name, table_definition, &active_schema) {
+                    return Ok(Some(table_name));
+                }
+                if !new_schema.schema_validation {
+                    return Ok(None);
+                }
                 Self::must_revalidate_table(
                     table_name,
                     table_definition,
@@ -306,6 +345,28 @@ impl DatabaseSchema {
         Ok(possible_table_names.into_iter().flatten().collect())
     }
 
+    /// Whether `table_definition` has a foreign key that the active schema
+    /// doesn't already enforce.
+    fn has_new_foreign_keys(
+        table_name: &TableName,
+        table_definition: &TableDefinition,
+        active_schema: &Option<DatabaseSchema>,
+    ) -> bool {
+        let active_foreign_keys = active_schema
+            .as_ref()
+            .and_then(|active_schema| active_schema.tables.get(table_name))
+            .map(|active_table| &active_table.foreign_keys);
+        table_definition
+            .foreign_keys
+            .iter()
+            .any(|(field, foreign_key)| {
+                active_foreign_keys
+                    .and_then(|foreign_keys| foreign_keys.get(field))
+                    .map(|active_foreign_key| &active_foreign_key.table)
+                    != Some(&foreign_key.table)
+            })
+    }
+
     fn must_revalidate_table<C: ShapeConfig, S: ShapeCounter>(
         table_name: &TableName,
         table_definition: &TableDefinition,
@@ -535,9 +596,64 @@ pub struct TableDefinition {
     pub search_indexes: BTreeMap<IndexDescriptor, SearchIndexSchema>,
     pub vector_indexes: BTreeMap<IndexDescriptor, VectorIndexSchema>,
     pub document_type: Option<DocumentSchema>,
+    pub ttl: Option<TableTtl>,
+    pub foreign_keys: BTreeMap<FieldPath, ForeignKey>,
+}
+
+/// Documents are deleted once `duration` has passed since the time in `field`,
+/// in milliseconds since the Unix epoch like `Date.now()`. Documents where the
+/// field is missing or not a float64 never expire.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TableTtl {
+    pub field: FieldPath,
+    pub duration: Duration,
+}
+
+/// A top-level field holding the ID of a document in `table`, or null. Writes
+/// that reference a nonexistent document fail, and deleting a referenced
+/// document does what `on_delete` says.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ForeignKey {
+    pub table: TableName,
+    pub on_delete: OnDelete,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OnDelete {
+    /// Fail the delete.
+    Restrict,
+    /// Delete the referencing documents too.
+    Cascade,
+    /// Set the field to null in the referencing documents.
+    SetNull,
 }
 
 impl TableDefinition {
+    /// The index used to find expired documents for the table's TTL: a
+    /// database index starting with the TTL field that covers every document.
+    pub fn ttl_index(&self) -> Option<&IndexSchema> {
+        let ttl = self.ttl.as_ref()?;
+        self.full_index_starting_with(&ttl.field)
+    }
+
+    /// The index used to find the documents referencing a deleted document
+    /// through the foreign key on `field`. The database maintains it
+    /// alongside the developer's indexes.
+    pub fn foreign_key_index(&self, field: &FieldPath) -> anyhow::Result<IndexName> {
+        IndexName::new_reserved(
+            self.table_name.clone(),
+            IndexDescriptor::foreign_key(field)?,
+        )
+    }
+
+    fn full_index_starting_with(&self, field: &FieldPath) -> Option<&IndexSchema> {
+        self.indexes.values().find(|index| {
+            index.fields.first() == Some(field)
+                && index.filter.is_none()
+                && index.multikey_field.is_none()
+        })
+    }
+
     pub fn fields_referenced_in_indexes(
         &self,
     ) -> impl Iterator<Item = (&IndexDescriptor, &FieldPath)> {
@@ -630,6 +746,8 @@ impl proptest::arbitrary::Arbitrary for TableDefinition {
                                 .map(|i| (i.index_descriptor.clone(), i))
                                 .collect(),
                             document_type,
+                            ttl: None,
+                            foreign_keys: Default::default(),
                         })
                     } else {
                         None
@@ -644,6 +762,15 @@ impl proptest::arbitrary::Arbitrary for TableDefinition {
 pub struct IndexSchema {
     pub index_descriptor: IndexDescriptor,
     pub fields: IndexedFields,
+    pub unique: bool,
+    /// Only documents matching this filter are indexed.
+    #[cfg_attr(any(test, feature = "testing"), proptest(value = "None"))]
+    pub filter: Option<Expression>,
+    /// One of `fields` to index each array element of.
+    #[cfg_attr(any(test, feature = "testing"), proptest(value = "None"))]
+    pub multikey_field: Option<FieldPath>,
+    /// Count (and sum) maintained for every prefix of `fields`.
+    pub aggregate: Option<IndexAggregate>,
 }
 
 impl Display for IndexSchema {
@@ -771,6 +898,19 @@ impl DocumentSchema {
         Ok(())
     }
 
+    /// Returns `true` when every document matching the schema can have the
+    /// top-level `field` set to null.
+    pub fn allows_null_field(&self, field: &IdentifierFieldName) -> bool {
+        match self {
+            DocumentSchema::Any => true,
+            DocumentSchema::Union(validators) => validators.iter().all(|validator| {
+                validator.0.get(field).is_some_and(|field_validator| {
+                    Validator::Null.is_subset(&field_validator.validator)
+                })
+            }),
+        }
+    }
+
     /// Returns `true` when it is sometimes possible to have a field with the
     /// given path on the document if this table definition is enforced, or
     /// `false` when it is never possible.
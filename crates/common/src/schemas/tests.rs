@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use cmd_util::env::env_config;
 use proptest::prelude::*;
 use serde_json::{
@@ -23,6 +25,8 @@ use crate::{
         },
         DatabaseSchema,
         DocumentSchema,
+        ForeignKey,
+        OnDelete,
         Validator,
     },
     testing::assert_roundtrips,
@@ -616,6 +620,114 @@ fn test_invalid_field_name() {
     assert!(error.to_string().contains("Identifiers must start with"));
 }
 
+#[test]
+fn test_index_filter() -> anyhow::Result<()> {
+    let schema_json = |filter: JsonValue| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "messages",
+                    "documentType": null,
+                    "indexes": [
+                        {
+                            "indexDescriptor": "by_channel",
+                            "fields": ["channel"],
+                        },
+                        {
+                            "indexDescriptor": "by_channel_active",
+                            "fields": ["channel"],
+                            "filter": filter,
+                        },
+                    ],
+                    "searchIndexes": []
+                },
+            ],
+            "schemaValidation": true
+        })
+    };
+    let not_archived = json!({
+        "$and": [
+            { "$eq": [{ "$field": "archived" }, { "$literal": false }] },
+            { "$not": { "$lt": [{ "$literal": 3 }, { "$field": "priority" }] } },
+        ]
+    });
+    let schema = DatabaseSchema::try_from(schema_json(not_archived.clone()))?;
+    assert_roundtrips::<DatabaseSchema, JsonValue>(schema);
+
+    // Filters can only compare fields with literals.
+    let computed = json!({
+        "$eq": [
+            { "$add": [{ "$field": "likes" }, { "$literal": 1 }] },
+            { "$literal": 2 },
+        ]
+    });
+    let error = DatabaseSchema::try_from(schema_json(computed))
+        .expect_err("Successfully created invalid schema");
+    assert!(error.to_string().contains("Index filters may only compare"));
+
+    // Indexes on the same fields with the same filter are duplicates.
+    let mut duplicate = schema_json(not_archived.clone());
+    let indexes = duplicate["tables"][0]["indexes"].as_array_mut().unwrap();
+    indexes[0]["filter"] = not_archived;
+    let error =
+        DatabaseSchema::try_from(duplicate).expect_err("Successfully created invalid schema");
+    assert!(error.to_string().contains("have the same fields"));
+    Ok(())
+}
+
+#[test]
+fn test_multikey_index() -> anyhow::Result<()> {
+    let schema_json = |index: JsonValue| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "posts",
+                    "documentType": null,
+                    "indexes": [
+                        {
+                            "indexDescriptor": "by_author",
+                            "fields": ["author", "tags"],
+                        },
+                        index,
+                    ],
+                    "searchIndexes": []
+                },
+            ],
+            "schemaValidation": true
+        })
+    };
+    let schema = DatabaseSchema::try_from(schema_json(json!({
+        "indexDescriptor": "by_author_tag",
+        "fields": ["author", "tags"],
+        "multikeyField": "tags",
+    })))?;
+    assert_roundtrips::<DatabaseSchema, JsonValue>(schema);
+
+    // The multikey field must be indexed.
+    let error = DatabaseSchema::try_from(schema_json(json!({
+        "indexDescriptor": "by_tag",
+        "fields": ["author"],
+        "multikeyField": "tags",
+    })))
+    .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("must be one of the index's fields"));
+
+    // Multikey indexes can't be unique.
+    let error = DatabaseSchema::try_from(schema_json(json!({
+        "indexDescriptor": "by_tag",
+        "fields": ["tags"],
+        "unique": true,
+        "multikeyField": "tags",
+    })))
+    .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("Multikey indexes can't be unique"));
+    Ok(())
+}
+
 #[test]
 fn test_json_backwards_compatibility() -> anyhow::Result<()> {
     // JSON from the npm package <= 0.13.0 didn't include the `schemaValidation`
@@ -1577,3 +1689,220 @@ mod can_contain_field {
         Ok(())
     }
 }
+
+#[test]
+fn test_table_ttl() -> anyhow::Result<()> {
+    let schema_json = |index_fields: JsonValue| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "sessions",
+                    "documentType": null,
+                    "indexes": [
+                        {
+                            "indexDescriptor": "by_expiry",
+                            "fields": index_fields,
+                        },
+                    ],
+                    "searchIndexes": [],
+                    "ttl": {
+                        "field": "lastSeen",
+                        "durationMs": 3_600_000,
+                    },
+                },
+            ],
+            "schemaValidation": true
+        })
+    };
+    let schema = DatabaseSchema::try_from(schema_json(json!(["lastSeen"])))?;
+    let table_definition = schema.tables.values().next().unwrap();
+    assert_eq!(
+        table_definition.ttl.as_ref().unwrap().duration,
+        Duration::from_secs(3600)
+    );
+    assert!(table_definition.ttl_index().is_some());
+    assert_roundtrips::<DatabaseSchema, JsonValue>(schema);
+
+    // The TTL field must lead an index.
+    let error = DatabaseSchema::try_from(schema_json(json!(["userId", "lastSeen"])))
+        .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("A TTL on lastSeen needs an index"));
+    Ok(())
+}
+
+#[test]
+fn test_table_foreign_keys() -> anyhow::Result<()> {
+    let schema_json = |index_fields: JsonValue, foreign_key: JsonValue| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "messages",
+                    "documentType": null,
+                    "indexes": [
+                        {
+                            "indexDescriptor": "by_author",
+                            "fields": index_fields,
+                        },
+                    ],
+                    "searchIndexes": [],
+                    "foreignKeys": [foreign_key],
+                },
+            ],
+            "schemaValidation": true
+        })
+    };
+    let foreign_key = json!({
+        "field": "author",
+        "table": "users",
+        "onDelete": "setNull",
+    });
+    let schema = DatabaseSchema::try_from(schema_json(json!(["author"]), foreign_key.clone()))?;
+    let table_definition = schema.tables.values().next().unwrap();
+    assert_eq!(
+        table_definition.foreign_keys.get(&"author".parse()?),
+        Some(&ForeignKey {
+            table: "users".parse()?,
+            on_delete: OnDelete::SetNull,
+        })
+    );
+    assert_eq!(
+        table_definition.foreign_key_index(&"author".parse()?)?,
+        "messages._foreign_key_author".parse()?
+    );
+    assert_roundtrips::<DatabaseSchema, JsonValue>(schema);
+
+    // The database maintains the foreign key's index, so the field doesn't
+    // need to lead one of the table's indexes.
+    DatabaseSchema::try_from(schema_json(
+        json!(["channel", "author"]),
+        foreign_key.clone(),
+    ))?;
+
+    // The foreign key's index is named after the field.
+    let long_field = "a".repeat(60);
+    let error = DatabaseSchema::try_from(schema_json(
+        json!([long_field]),
+        json!({ "field": long_field, "table": "users", "onDelete": "cascade" }),
+    ))
+    .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains(&format!("The foreign key field {long_field} is too long")));
+
+    // Foreign keys are on top-level fields of user tables.
+    let error = DatabaseSchema::try_from(schema_json(
+        json!(["meta.author"]),
+        json!({ "field": "meta.author", "table": "users", "onDelete": "cascade" }),
+    ))
+    .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("Invalid foreign key field: \"meta.author\""));
+    let error = DatabaseSchema::try_from(schema_json(
+        json!(["author"]),
+        json!({ "field": "author", "table": "_storage", "onDelete": "restrict" }),
+    ))
+    .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("must reference a user table, not \"_storage\""));
+    Ok(())
+}
+
+#[test]
+fn test_set_null_foreign_key_must_be_nullable() -> anyhow::Result<()> {
+    let schema_json = |author_type: JsonValue, on_delete: &str| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "messages",
+                    "documentType": {
+                        "type": "object",
+                        "value": {
+                            "author": {
+                                "fieldType": author_type,
+                                "optional": false
+                            },
+                        }
+                    },
+                    "indexes": [
+                        {
+                            "indexDescriptor": "by_author",
+                            "fields": ["author"],
+                        },
+                    ],
+                    "searchIndexes": [],
+                    "foreignKeys": [
+                        { "field": "author", "table": "users", "onDelete": on_delete },
+                    ],
+                },
+            ],
+            "schemaValidation": true
+        })
+    };
+    let id_type = json!({ "type": "id", "tableName": "users" });
+    let nullable_id_type = json!({
+        "type": "union",
+        "value": [id_type.clone(), { "type": "null" }],
+    });
+    DatabaseSchema::try_from(schema_json(nullable_id_type, "setNull"))?;
+    // Only setNull needs the field to allow null.
+    DatabaseSchema::try_from(schema_json(id_type.clone(), "cascade"))?;
+
+    let error = DatabaseSchema::try_from(schema_json(id_type, "setNull"))
+        .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("the table's schema doesn't allow author to be null"));
+    Ok(())
+}
+
+#[test]
+fn test_index_aggregate() -> anyhow::Result<()> {
+    let schema_json = |index: JsonValue| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "orders",
+                    "documentType": null,
+                    "indexes": [index],
+                    "searchIndexes": [],
+                },
+            ],
+            "schemaValidation": true
+        })
+    };
+    let schema = DatabaseSchema::try_from(schema_json(json!({
+        "indexDescriptor": "by_customer",
+        "fields": ["customerId", "status"],
+        "aggregate": { "sumField": "total" },
+    })))?;
+    let index = schema
+        .tables
+        .values()
+        .next()
+        .unwrap()
+        .indexes
+        .values()
+        .next()
+        .unwrap();
+    assert_eq!(
+        index.aggregate.as_ref().unwrap().sum_field,
+        Some("total".parse()?)
+    );
+    assert_roundtrips::<DatabaseSchema, JsonValue>(schema);
+
+    let error = DatabaseSchema::try_from(schema_json(json!({
+        "indexDescriptor": "by_tag",
+        "fields": ["tags"],
+        "multikeyField": "tags",
+        "aggregate": {},
+    })))
+    .expect_err("Successfully created invalid schema");
+    assert!(error
+        .to_string()
+        .contains("Multikey indexes can't have aggregates"));
+    Ok(())
+}
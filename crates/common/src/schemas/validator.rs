@@ -13,6 +13,7 @@ use std::{
 use errors::ErrorMetadata;
 #[cfg(any(test, feature = "testing"))]
 use proptest::prelude::*;
+use regex::Regex;
 use serde_json::{
     Number,
     Value as JsonValue,
@@ -72,6 +73,7 @@ pub enum Validator {
     Map(Box<Validator>, Box<Validator>),
     Object(ObjectValidator),
     Union(Vec<Validator>),
+    Constrained(Box<Validator>, ValidatorConstraints),
     Any,
 }
 
@@ -94,6 +96,31 @@ impl proptest::arbitrary::Arbitrary for Validator {
             Just(Validator::Bytes),
             any::<LiteralValidator>().prop_map(Validator::Literal),
             Just(Validator::Any),
+            (0u64..8).prop_map(|max_length| Validator::Constrained(
+                Box::new(Validator::String),
+                ValidatorConstraints {
+                    max_length: Some(max_length),
+                    pattern: Some(Pattern::new("^[a-z]*$").unwrap()),
+                    ..ValidatorConstraints::default()
+                }
+            )),
+            (0i64..8, proptest::bool::ANY).prop_map(|(bound, integer)| Validator::Constrained(
+                Box::new(Validator::Float64),
+                ValidatorConstraints {
+                    min: Some(NumericBound::Float64(((-bound) as f64).into())),
+                    max: Some(NumericBound::Float64((bound as f64).into())),
+                    integer,
+                    ..ValidatorConstraints::default()
+                }
+            )),
+            (0i64..8).prop_map(|bound| Validator::Constrained(
+                Box::new(Validator::Int64),
+                ValidatorConstraints {
+                    min: Some(NumericBound::Int64(-bound)),
+                    max: Some(NumericBound::Int64(bound)),
+                    ..ValidatorConstraints::default()
+                }
+            )),
         ];
         leaf.prop_recursive(3, 8, 8, move |inner| {
             prop_oneof![
@@ -138,12 +165,28 @@ impl Display for Validator {
             Validator::Union(validators) => {
                 display_sequence(f, ["v.union(", ")"], validators.iter())
             },
+            Validator::Constrained(validator, constraints) => {
+                write!(f, "{validator}{constraints}")
+            },
             Validator::Any => write!(f, "v.any()"),
         }
     }
 }
 
 impl Validator {
+    /// Adds `constraints` to `validator`, checking that they apply to its
+    /// type.
+    pub fn constrained(
+        validator: Validator,
+        constraints: ValidatorConstraints,
+    ) -> anyhow::Result<Self> {
+        if constraints.is_empty() {
+            return Ok(validator);
+        }
+        constraints.ensure_valid_for(&validator)?;
+        Ok(Validator::Constrained(Box::new(validator), constraints))
+    }
+
     pub fn check_value(
         &self,
         value: &ConvexValue,
@@ -308,6 +351,21 @@ impl Validator {
                     context,
                 });
             },
+            (Validator::Constrained(validator, constraints), value) => {
+                validator.check_value_internal(
+                    value,
+                    all_tables_number_to_name,
+                    context.clone(),
+                )?;
+                if let Some(violation) = constraints.violation(value) {
+                    return Err(ValidationError::ConstraintViolated {
+                        value: value.clone(),
+                        violation,
+                        validator: self.clone(),
+                        context,
+                    });
+                }
+            },
             (Validator::Any, _) => return Ok(()),
             (..) => {
                 return Err(ValidationError::NoMatch {
@@ -454,6 +512,13 @@ impl Validator {
             // Identical types
             (v1, v2) if v1 == v2 => true,
 
+            // Constraints only ever narrow a validator
+            (
+                Validator::Constrained(left, left_constraints),
+                Validator::Constrained(right, right_constraints),
+            ) => left_constraints == right_constraints && left.is_subset(right),
+            (Validator::Constrained(left, _), _) => left.is_subset(superset),
+
             // Types that are subsets of other ones
             (_, Validator::Any)
             | (Validator::Literal(LiteralValidator::String(_)), Validator::String)
@@ -509,6 +574,9 @@ impl Validator {
             Validator::Union(unions) => unions
                 .iter()
                 .any(|v| v.is_string_subtype_with_string_literal()),
+            Validator::Constrained(validator, _) => {
+                validator.is_string_subtype_with_string_literal()
+            },
         }
     }
 
@@ -529,6 +597,7 @@ impl Validator {
             Validator::Union(cases) => cases
                 .iter()
                 .any(|case| case._can_contain_field(field_path_parts)),
+            Validator::Constrained(validator, _) => validator._can_contain_field(field_path_parts),
             Validator::Object(ObjectValidator(fields)) => fields
                 .get(first_part)
                 .map(|field_validator| {
@@ -559,6 +628,7 @@ impl Validator {
             },
             Validator::Any => true,
             Validator::Union(validators) => validators.iter().any(Self::is_valid_vector_validator),
+            Validator::Constrained(validator, _) => Self::is_valid_vector_validator(validator),
             _ => false,
         };
     }
@@ -573,6 +643,9 @@ impl Validator {
             Validator::Union(cases) => cases
                 .iter()
                 .any(|case| case._overlaps_with_array_float64(field_path_parts)),
+            Validator::Constrained(validator, _) => {
+                validator._overlaps_with_array_float64(field_path_parts)
+            },
             Validator::Object(ObjectValidator(fields)) => fields
                 .get(first_part)
                 .map(|field_validator| {
@@ -605,6 +678,9 @@ impl Validator {
             Validator::Set(element_validator) => {
                 element_validator.ensure_supported_for_streaming_export()
             },
+            Validator::Constrained(validator, _) => {
+                validator.ensure_supported_for_streaming_export()
+            },
             Validator::Map(key_validator, value_validator) => {
                 key_validator.ensure_supported_for_streaming_export()?;
                 value_validator.ensure_supported_for_streaming_export()
@@ -672,6 +748,8 @@ impl Validator {
                     .collect();
                 json_schemas::union(options)
             },
+            // Exports only use the types of values.
+            Validator::Constrained(validator, _) => validator.to_json_schema(value_format),
             Validator::Any => json_schemas::any(),
         };
         json_schema
@@ -685,7 +763,7 @@ impl Validator {
                     yield table_name;
                 }
             },
-            Self::Array(item) | Self::Set(item) => {
+            Self::Array(item) | Self::Set(item) | Self::Constrained(item, _) => {
                 for table_name in item.foreign_keys() {
                     yield table_name;
                 }
@@ -726,7 +804,7 @@ impl Validator {
             | Self::Literal(_)
             | Self::Any => false,
             Self::Set(_) | Self::Map(..) => true,
-            Self::Array(a) => a.has_map_or_set(),
+            Self::Array(a) | Self::Constrained(a, _) => a.has_map_or_set(),
             Self::Record(k, v) => k.has_map_or_set() || v.has_map_or_set(),
             Self::Object(o) => o.has_map_or_set(),
             Self::Union(u) => u.iter().any(|o| o.has_map_or_set()),
@@ -836,6 +914,240 @@ impl TryFrom<ConvexValue> for LiteralValidator {
     }
 }
 
+/// Restrictions on values beyond their type, like `v.string().maxLength(20)`.
+/// See [`Validator::constrained`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ValidatorConstraints {
+    /// Minimum length of a string (in Unicode code points), bytes, or array.
+    pub min_length: Option<u64>,
+    /// Maximum length of a string (in Unicode code points), bytes, or array.
+    pub max_length: Option<u64>,
+    /// Inclusive lower bound for a float64 or int64.
+    pub min: Option<NumericBound>,
+    /// Inclusive upper bound for a float64 or int64.
+    pub max: Option<NumericBound>,
+    /// Regular expression that strings must contain a match for.
+    pub pattern: Option<Pattern>,
+    /// Whether float64s must be integers.
+    pub integer: bool,
+}
+
+impl ValidatorConstraints {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    fn ensure_valid_for(&self, validator: &Validator) -> anyhow::Result<()> {
+        let invalid = |message: String| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidValidatorConstraint",
+                format!("Invalid constraint on `{validator}`: {message}"),
+            ))
+        };
+        if (self.min_length.is_some() || self.max_length.is_some())
+            && !matches!(
+                validator,
+                Validator::String | Validator::Bytes | Validator::Array(_)
+            )
+        {
+            return Err(invalid(
+                "minLength and maxLength only apply to strings, bytes, and arrays".to_string(),
+            ));
+        }
+        if let (Some(min_length), Some(max_length)) = (self.min_length, self.max_length)
+            && min_length > max_length
+        {
+            return Err(invalid(format!(
+                "minLength {min_length} is greater than maxLength {max_length}"
+            )));
+        }
+        for bound in self.min.iter().chain(&self.max) {
+            match (validator, bound) {
+                (Validator::Float64, NumericBound::Float64(f))
+                    if !f64::from(f.clone()).is_nan() => {},
+                (Validator::Int64, NumericBound::Int64(_)) => {},
+                _ => {
+                    return Err(invalid(format!(
+                        "min and max must be numbers for `v.float64()` and bigints for \
+                         `v.int64()`, but found {bound}"
+                    )))
+                },
+            }
+        }
+        if let (Some(min), Some(max)) = (&self.min, &self.max)
+            && min > max
+        {
+            return Err(invalid(format!("min {min} is greater than max {max}")));
+        }
+        if self.pattern.is_some() && validator != &Validator::String {
+            return Err(invalid("pattern only applies to strings".to_string()));
+        }
+        if self.integer && validator != &Validator::Float64 {
+            return Err(invalid("integer only applies to `v.float64()`".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Describes how `value` violates these constraints, if it does. `value`
+    /// must already match the constrained validator.
+    fn violation(&self, value: &ConvexValue) -> Option<String> {
+        let length = match value {
+            ConvexValue::String(s) => Some((s.chars().count(), "characters")),
+            ConvexValue::Bytes(b) => Some((b.len(), "bytes")),
+            ConvexValue::Array(a) => Some((a.len(), "elements")),
+            _ => None,
+        };
+        if let Some((length, unit)) = length {
+            let length = length as u64;
+            if let Some(min_length) = self.min_length
+                && length < min_length
+            {
+                return Some(format!(
+                    "Value must have at least {min_length} {unit}, but has {length}"
+                ));
+            }
+            if let Some(max_length) = self.max_length
+                && length > max_length
+            {
+                return Some(format!(
+                    "Value must have at most {max_length} {unit}, but has {length}"
+                ));
+            }
+        }
+        match value {
+            ConvexValue::Float64(f) => {
+                if let Some(NumericBound::Float64(min)) = &self.min
+                    && (f.is_nan() || *f < f64::from(min.clone()))
+                {
+                    return Some(format!("Value must be at least {min}"));
+                }
+                if let Some(NumericBound::Float64(max)) = &self.max
+                    && (f.is_nan() || *f > f64::from(max.clone()))
+                {
+                    return Some(format!("Value must be at most {max}"));
+                }
+                if self.integer && f.fract() != 0.0 {
+                    return Some("Value must be an integer".to_string());
+                }
+            },
+            ConvexValue::Int64(i) => {
+                if let Some(min @ NumericBound::Int64(min_int)) = &self.min
+                    && i < min_int
+                {
+                    return Some(format!("Value must be at least {min}"));
+                }
+                if let Some(max @ NumericBound::Int64(max_int)) = &self.max
+                    && i > max_int
+                {
+                    return Some(format!("Value must be at most {max}"));
+                }
+            },
+            ConvexValue::String(s) => {
+                if let Some(pattern) = &self.pattern
+                    && !pattern.is_match(s)
+                {
+                    return Some(format!("Value must match the pattern {pattern}"));
+                }
+            },
+            _ => {},
+        }
+        None
+    }
+}
+
+impl Display for ValidatorConstraints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(min_length) = self.min_length {
+            write!(f, ".minLength({min_length})")?;
+        }
+        if let Some(max_length) = self.max_length {
+            write!(f, ".maxLength({max_length})")?;
+        }
+        if let Some(min) = &self.min {
+            write!(f, ".min({min})")?;
+        }
+        if let Some(max) = &self.max {
+            write!(f, ".max({max})")?;
+        }
+        if let Some(pattern) = &self.pattern {
+            write!(f, ".pattern({pattern})")?;
+        }
+        if self.integer {
+            write!(f, ".integer()")?;
+        }
+        Ok(())
+    }
+}
+
+/// A `min` or `max` constraint, which is a float64 for `v.float64()` and an
+/// int64 for `v.int64()`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NumericBound {
+    Float64(TotalOrdF64),
+    Int64(i64),
+}
+
+impl Display for NumericBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumericBound::Float64(float) => write!(f, "{}", f64::from(float.clone())),
+            NumericBound::Int64(int) => write!(f, "{int}n"),
+        }
+    }
+}
+
+/// A `pattern` constraint. Like JavaScript's `RegExp.test`, strings match if
+/// they contain a match anywhere, so use `^` and `$` to match whole strings.
+#[derive(Clone, Debug)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidValidatorConstraint",
+                format!("Invalid pattern {pattern:?}: {e}"),
+            ))
+        })?;
+        Ok(Self(regex))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    fn is_match(&self, s: &str) -> bool {
+        self.0.is_match(s)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl PartialOrd for Pattern {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pattern {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let string = serde_json::to_string(self.as_str()).map_err(|_| fmt::Error)?;
+        write!(f, "{string}")
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 #[cfg_attr(
@@ -1056,6 +1368,16 @@ Validator: {validator}")]
         validator: Validator,
         context: ValidationContext,
     },
+    #[display(fmt = "{violation}.
+{context}
+Value: {value}
+Validator: {validator}")]
+    ConstraintViolated {
+        value: ConvexValue,
+        violation: String,
+        validator: Validator,
+        context: ValidationContext,
+    },
 }
 
 #[cfg(test)]
@@ -1101,9 +1423,11 @@ mod tests {
             validator::{
                 FieldValidator,
                 LiteralValidator,
+                NumericBound,
                 ObjectValidator,
                 ValidationContext,
                 ValidationError,
+                ValidatorConstraints,
             },
             DocumentSchema,
         },
@@ -1170,6 +1494,8 @@ mod tests {
                 })?;
                 value_from_validator(validator, id_generator)?
             },
+            // The arbitrary constraints all allow the values above.
+            Validator::Constrained(v, _) => value_from_validator(*v, id_generator)?,
             Validator::Any => assert_val!(null),
         };
         Ok(value)
@@ -1503,6 +1829,19 @@ mod tests {
             "v.union(v.string(), v.float64())"
         );
 
+        let constrained_validator = Validator::constrained(
+            Validator::Int64,
+            ValidatorConstraints {
+                min: Some(NumericBound::Int64(0)),
+                max: Some(NumericBound::Int64(10)),
+                ..ValidatorConstraints::default()
+            },
+        )?;
+        assert_eq!(
+            constrained_validator.to_string(),
+            "v.int64().min(0n).max(10n)"
+        );
+
         Ok(())
     }
 
@@ -1610,6 +1949,82 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_constraints() -> anyhow::Result<()> {
+        let validator = Validator::try_from(json!({
+            "type": "object",
+            "value": {
+                "email": {
+                    "fieldType": { "type": "string", "pattern": "^[^@]+@[^@]+$" },
+                    "optional": false,
+                },
+                "rating": {
+                    "fieldType": { "type": "number", "min": 0, "max": 5, "integer": true },
+                    "optional": false,
+                },
+                "tags": {
+                    "fieldType": {
+                        "type": "array",
+                        "value": { "type": "string", "minLength": 1 },
+                        "maxLength": 2,
+                    },
+                    "optional": false,
+                },
+            }
+        }))?;
+        let check = |value: ConvexValue| {
+            validator
+                .check_value(&value, &TableMapping::new(), &VirtualTableMapping::new())
+                .map_err(|e| e.to_string())
+        };
+
+        check(assert_val!({"email" => "a@b.com", "rating" => 5., "tags" => ["x", "y"]})).unwrap();
+
+        let err =
+            check(assert_val!({"email" => "nope", "rating" => 5., "tags" => ["x"]})).unwrap_err();
+        assert!(err.starts_with("Value must match the pattern \"^[^@]+@[^@]+$\""));
+        assert!(err.contains("Path: .email"));
+
+        let err = check(assert_val!({"email" => "a@b.com", "rating" => 6., "tags" => ["x"]}))
+            .unwrap_err();
+        assert!(err.starts_with("Value must be at most 5."));
+        assert!(err.contains("Path: .rating"));
+
+        let err = check(assert_val!({"email" => "a@b.com", "rating" => 2.5, "tags" => ["x"]}))
+            .unwrap_err();
+        assert!(err.starts_with("Value must be an integer."));
+
+        let err =
+            check(assert_val!({"email" => "a@b.com", "rating" => 1., "tags" => ["x", "y", "z"]}))
+                .unwrap_err();
+        assert!(err.starts_with("Value must have at most 2 elements, but has 3."));
+        assert!(err.contains("Path: .tags"));
+
+        let err = check(assert_val!({"email" => "a@b.com", "rating" => 1., "tags" => ["x", ""]}))
+            .unwrap_err();
+        assert!(err.starts_with("Value must have at least 1 characters, but has 0."));
+        assert!(err.contains("Path: .tags[1]"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_constraints() -> anyhow::Result<()> {
+        for validator_json in [
+            json!({ "type": "number", "minLength": 1 }),
+            json!({ "type": "string", "minLength": 2, "maxLength": 1 }),
+            json!({ "type": "bigint", "min": 0 }),
+            json!({ "type": "number", "min": 1, "max": 0 }),
+            json!({ "type": "bytes", "pattern": "a" }),
+            json!({ "type": "string", "pattern": "(" }),
+            json!({ "type": "bigint", "integer": true }),
+        ] {
+            must_let::must_let!(let Err(e) = Validator::try_from(validator_json));
+            assert_eq!(e.short_msg(), "InvalidValidatorConstraint");
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_ensure_supported_for_streaming_export() -> anyhow::Result<()> {
         let simple_object_validator = Validator::Object(ObjectValidator(btreemap! {
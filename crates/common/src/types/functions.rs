@@ -106,6 +106,9 @@ pub enum UdfIdentifier {
     Function(CanonicalizedUdfPath),
     Http(HttpActionRoute),
     Cli(String),
+    /// Work the backend does on its own, like deleting documents past their
+    /// table's TTL.
+    System(String),
 }
 
 impl fmt::Display for UdfIdentifier {
@@ -114,6 +117,7 @@ impl fmt::Display for UdfIdentifier {
             UdfIdentifier::Function(path) => write!(f, "{}", path),
             UdfIdentifier::Http(route) => write!(f, "{}", route.path),
             UdfIdentifier::Cli(command) => write!(f, "_cli/{command}"),
+            UdfIdentifier::System(name) => write!(f, "_system/{name}"),
         }
     }
 }
@@ -137,6 +141,10 @@ pub enum FunctionCaller {
     Action {
         parent_scheduled_job: Option<DocumentIdV6>,
     },
+    /// The system deleting documents past their table's TTL.
+    Ttl,
+    /// The system running a data migration's mutation on each document.
+    Migration,
 }
 
 impl FunctionCaller {
@@ -148,7 +156,9 @@ impl FunctionCaller {
             FunctionCaller::HttpEndpoint
             | FunctionCaller::Cron
             | FunctionCaller::Scheduler { .. }
-            | FunctionCaller::Action { .. } => None,
+            | FunctionCaller::Action { .. }
+            | FunctionCaller::Ttl
+            | FunctionCaller::Migration => None,
         }
         .cloned()
     }
@@ -159,7 +169,9 @@ impl FunctionCaller {
             | FunctionCaller::HttpApi(_)
             | FunctionCaller::Tester(_)
             | FunctionCaller::HttpEndpoint
-            | FunctionCaller::Cron => None,
+            | FunctionCaller::Cron
+            | FunctionCaller::Ttl
+            | FunctionCaller::Migration => None,
             FunctionCaller::Scheduler { job_id } => Some(*job_id),
             FunctionCaller::Action {
                 parent_scheduled_job,
@@ -174,7 +186,9 @@ impl FunctionCaller {
             | FunctionCaller::Tester(_)
             | FunctionCaller::HttpEndpoint
             | FunctionCaller::Cron
-            | FunctionCaller::Scheduler { .. } => true,
+            | FunctionCaller::Scheduler { .. }
+            | FunctionCaller::Ttl
+            | FunctionCaller::Migration => true,
             FunctionCaller::Action { .. } => false,
         }
     }
@@ -190,7 +204,9 @@ impl FunctionCaller {
             | FunctionCaller::Tester(_) => true,
             FunctionCaller::Cron
             | FunctionCaller::Scheduler { .. }
-            | FunctionCaller::Action { .. } => false,
+            | FunctionCaller::Action { .. }
+            | FunctionCaller::Ttl
+            | FunctionCaller::Migration => false,
         }
     }
 }
@@ -205,6 +221,8 @@ impl fmt::Display for FunctionCaller {
             FunctionCaller::Cron => "Cron",
             FunctionCaller::Scheduler { .. } => "Scheduler",
             FunctionCaller::Action { .. } => "Action",
+            FunctionCaller::Ttl => "Ttl",
+            FunctionCaller::Migration => "Migration",
         };
         write!(f, "{s}")
     }
@@ -13,6 +13,7 @@ use value::{
     heap_size::HeapSize,
     id_v6::VirtualTableNumberMap,
     FieldName,
+    FieldPath,
     InternalId,
     ResolvedDocumentId,
     TableId,
@@ -50,6 +51,19 @@ impl IndexDescriptor {
             || self == &*INDEX_BY_CREATION_TIME_DESCRIPTOR
             || self.0.starts_with('_')
     }
+
+    /// The index the database maintains on a foreign key field to find the
+    /// documents referencing a document, e.g., "_foreign_key_author".
+    pub fn foreign_key(field: &FieldPath) -> anyhow::Result<Self> {
+        format!("{FOREIGN_KEY_INDEX_PREFIX}{}", field.last()).parse()
+    }
+
+    /// Is this an index the database maintains on a foreign key field? These
+    /// are reserved, but they're created and dropped with the schema like
+    /// the developer's indexes.
+    pub fn is_foreign_key(&self) -> bool {
+        self.0.starts_with(FOREIGN_KEY_INDEX_PREFIX)
+    }
 }
 
 impl FromStr for IndexDescriptor {
@@ -209,6 +223,8 @@ pub static INDEX_BY_ID_DESCRIPTOR: LazyLock<IndexDescriptor> =
 pub static INDEX_BY_CREATION_TIME_DESCRIPTOR: LazyLock<IndexDescriptor> =
     LazyLock::new(|| "by_creation_time".parse().unwrap());
 
+const FOREIGN_KEY_INDEX_PREFIX: &str = "_foreign_key_";
+
 impl<T: TableIdentifier> GenericIndexName<T> {
     /// Create a new index name for the table and given descriptor,
     /// e.g., "users.by_email".
@@ -309,6 +325,12 @@ impl IndexName {
         self.table.is_system() || self.descriptor.is_reserved()
     }
 
+    /// Is this the index the database maintains for a foreign key on a user
+    /// table?
+    pub fn is_foreign_key(&self) -> bool {
+        !self.table.is_system() && self.descriptor.is_foreign_key()
+    }
+
     pub fn to_resolved(
         self,
         f: impl Fn(TableName) -> anyhow::Result<TableIdAndTableNumber>,
@@ -393,6 +415,8 @@ mod protobuf {
                 Ok(Self::by_id(table_id))
             } else if index_descriptor == *INDEX_BY_CREATION_TIME_DESCRIPTOR {
                 Ok(Self::by_creation_time(table_id))
+            } else if index_descriptor.is_foreign_key() {
+                Self::new_reserved(table_id, index_descriptor)
             } else {
                 Self::new(table_id, index_descriptor)
             }
@@ -295,7 +295,10 @@ impl ClientVersion {
     // deprecated.
     pub fn from_path_param(v: Version, path: &str) -> ClientVersion {
         Self {
-            client: if path.ends_with("/sync") || path.ends_with("/udf") {
+            client: if path.ends_with("/sync")
+                || path.ends_with("/sync/sse")
+                || path.ends_with("/udf")
+            {
                 ClientType::NPM
             } else {
                 ClientType::CLI
@@ -13,6 +13,7 @@ use std::{
 };
 
 use convex_sync_types::{
+    diff::value_hash,
     AuthenticationToken,
     CanonicalizedUdfPath,
     ClientMessage,
@@ -26,7 +27,10 @@ use convex_sync_types::{
     Timestamp,
     UdfPath,
 };
-use serde_json::json;
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
 use tokio::sync::oneshot;
 
 #[cfg(doc)]
@@ -302,6 +306,42 @@ impl RemoteQuerySet {
                     self.remote_query_set
                         .insert(query_id, FunctionResult::Value(value));
                 },
+                StateModification::QueryDiffed {
+                    query_id,
+                    diff,
+                    value_hash: expected_hash,
+                    log_lines,
+                    journal: _,
+                } => {
+                    for log_line in log_lines.0 {
+                        convex_logs!("{}", log_line);
+                    }
+                    let Some(FunctionResult::Value(base)) = self.remote_query_set.remove(&query_id)
+                    else {
+                        tracing::error!("Received a diff for query {query_id} without a value");
+                        return Err("QueryDiffWithoutBase".into());
+                    };
+                    let value = match diff.apply(JsonValue::from(base)) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            tracing::error!("Failed to apply diff for query {query_id}: {e}");
+                            return Err("QueryDiffInvalid".into());
+                        },
+                    };
+                    if value_hash(&value) != expected_hash {
+                        tracing::error!("Diffed value for query {query_id} has the wrong hash");
+                        return Err("QueryDiffHashMismatch".into());
+                    }
+                    let value = match Value::try_from(value) {
+                        Ok(value) => value,
+                        Err(e) => {
+                            tracing::error!("Diffed value for query {query_id} is invalid: {e}");
+                            return Err("QueryDiffInvalid".into());
+                        },
+                    };
+                    self.remote_query_set
+                        .insert(query_id, FunctionResult::Value(value));
+                },
                 StateModification::QueryFailed {
                     query_id,
                     error_message,
@@ -1,11 +1,22 @@
 use std::{
     collections::BTreeMap,
     convert::Infallible,
-    sync::Arc,
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::SystemTime,
 };
 
+use anyhow::Context;
 use convex_sync_types::{
+    deflate::{
+        DeflateConfig,
+        MAX_WINDOW_BITS,
+        MIN_WINDOW_BITS,
+    },
     AuthenticationToken,
+    Timestamp,
     UdfPath,
     UserIdentityAttributes,
 };
@@ -19,6 +30,10 @@ use futures::{
     SinkExt,
     StreamExt,
 };
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
 use tokio::{
     sync::broadcast,
     task::JoinHandle,
@@ -35,6 +50,7 @@ use crate::{
         QueryResults,
     },
     client::{
+        pagination::PaginatedQuerySubscription,
         subscription::{
             QuerySetSubscription,
             QuerySubscription,
@@ -48,16 +64,83 @@ use crate::{
         },
     },
     sync::{
+        sse_manager::SseManager,
         web_socket_manager::WebSocketManager,
         SyncProtocol,
     },
     value::Value,
+    ConvexError,
     FunctionResult,
 };
 
+pub mod pagination;
 pub mod subscription;
 mod worker;
 
+const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
+
+/// The snapshot [`ConvexClient::query_at`] reads at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryTimestamp {
+    /// A snapshot timestamp returned by an earlier
+    /// [`ConvexClient::query_at`], for reading several queries at the same
+    /// snapshot.
+    Snapshot(Timestamp),
+    /// The latest snapshot as of a wall-clock time.
+    WallClock(SystemTime),
+}
+
+/// How [`ConvexClient`] connects to the deployment for subscriptions,
+/// mutations and actions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncTransport {
+    /// A WebSocket to `/api/sync`.
+    #[default]
+    WebSocket,
+    /// Server-Sent Events from `/api/sync/sse`, with messages to the server
+    /// sent as HTTP requests. Use this when a proxy blocks WebSockets.
+    ServerSentEvents,
+}
+
+/// Configures how a [`ConvexClient`] connects to its deployment. Create one
+/// with [`ConvexClient::builder`].
+#[derive(Clone, Debug)]
+pub struct ConvexClientBuilder {
+    deployment_url: String,
+    transport: SyncTransport,
+    compression: Option<DeflateConfig>,
+}
+
+impl ConvexClientBuilder {
+    /// Sets the transport used for subscriptions, mutations and actions.
+    /// Defaults to [`SyncTransport::WebSocket`].
+    pub fn with_transport(mut self, transport: SyncTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Offers `permessage-deflate` compression of messages sent over the
+    /// WebSocket with `compression`, or disables it with `None`. Compression
+    /// is experimental and defaults to `None`. Ignored for
+    /// [`SyncTransport::ServerSentEvents`].
+    pub fn with_compression(mut self, compression: Option<DeflateConfig>) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Connects to the deployment.
+    pub async fn build(self) -> anyhow::Result<ConvexClient> {
+        if let Some(compression) = &self.compression {
+            anyhow::ensure!(
+                (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&compression.window_bits),
+                "window_bits must be between {MIN_WINDOW_BITS} and {MAX_WINDOW_BITS}, got {}",
+                compression.window_bits
+            );
+        }
+        ConvexClient::connect(self).await
+    }
+}
+
 /// An asynchronous client to interact with a specific project to perform
 /// mutations and manage query subscriptions using [`tokio`].
 ///
@@ -90,6 +173,11 @@ pub struct ConvexClient {
     listen_handle: Option<Arc<JoinHandle<Infallible>>>,
     request_sender: mpsc::UnboundedSender<ClientRequest>,
     watch_receiver: broadcast::Receiver<QueryResults>,
+    deployment_url: Url,
+    http_client: reqwest::Client,
+    // The last token passed to `set_auth` or `set_admin_auth`, for requests
+    // made over HTTP rather than the WebSocket.
+    auth: Arc<Mutex<AuthenticationToken>>,
 }
 
 /// Clone the [`ConvexClient`], sharing the connection and outstanding
@@ -100,6 +188,9 @@ impl Clone for ConvexClient {
             listen_handle: self.listen_handle.clone(),
             request_sender: self.request_sender.clone(),
             watch_receiver: self.watch_receiver.resubscribe(),
+            deployment_url: self.deployment_url.clone(),
+            http_client: self.http_client.clone(),
+            auth: self.auth.clone(),
         }
     }
 }
@@ -130,7 +221,66 @@ impl ConvexClient {
     /// # }
     /// ```
     pub async fn new(deployment_url: &str) -> anyhow::Result<Self> {
-        let ws_url = deployment_to_ws_url(deployment_url.try_into()?)?;
+        Self::builder(deployment_url).build().await
+    }
+
+    /// Returns a [`ConvexClientBuilder`] for configuring how the client
+    /// connects to `deployment_url`.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, DeflateConfig};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let client = ConvexClient::builder("https://cool-music-123.convex.cloud")
+    ///     .with_compression(Some(DeflateConfig {
+    ///         window_bits: 10,
+    ///         threshold: 4096,
+    ///     }))
+    ///     .build()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder(deployment_url: &str) -> ConvexClientBuilder {
+        ConvexClientBuilder {
+            deployment_url: deployment_url.to_string(),
+            transport: SyncTransport::default(),
+            compression: None,
+        }
+    }
+
+    /// Constructs a new client for communicating with `deployment_url` over
+    /// `transport`.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, SyncTransport};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let client = ConvexClient::new_with_transport(
+    ///     "https://cool-music-123.convex.cloud",
+    ///     SyncTransport::ServerSentEvents,
+    /// )
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_with_transport(
+        deployment_url: &str,
+        transport: SyncTransport,
+    ) -> anyhow::Result<Self> {
+        Self::builder(deployment_url)
+            .with_transport(transport)
+            .build()
+            .await
+    }
+
+    async fn connect(builder: ConvexClientBuilder) -> anyhow::Result<Self> {
+        let ConvexClientBuilder {
+            deployment_url,
+            transport,
+            compression,
+        } = builder;
+        let deployment_url: Url = deployment_url.as_str().try_into()?;
 
         // Channels for the `listen` background thread
         let (response_sender, response_receiver) = mpsc::channel(1);
@@ -141,19 +291,39 @@ impl ConvexClient {
 
         let base_client = BaseConvexClient::new();
 
-        let protocol = WebSocketManager::open(ws_url, response_sender).await?;
-
-        let listen_handle = tokio::spawn(worker(
-            response_receiver,
-            request_receiver,
-            watch_sender,
-            base_client,
-            protocol,
-        ));
+        let listen_handle = match transport {
+            SyncTransport::WebSocket => {
+                let ws_url = deployment_to_ws_url(deployment_url.clone())?;
+                let protocol =
+                    WebSocketManager::open_with_compression(ws_url, response_sender, compression)
+                        .await?;
+                tokio::spawn(worker(
+                    response_receiver,
+                    request_receiver,
+                    watch_sender,
+                    base_client,
+                    protocol,
+                ))
+            },
+            SyncTransport::ServerSentEvents => {
+                let sse_url = deployment_to_sse_url(deployment_url.clone())?;
+                let protocol = SseManager::open(sse_url, response_sender).await?;
+                tokio::spawn(worker(
+                    response_receiver,
+                    request_receiver,
+                    watch_sender,
+                    base_client,
+                    protocol,
+                ))
+            },
+        };
         let client = ConvexClient {
             listen_handle: Some(Arc::new(listen_handle)),
             request_sender,
             watch_receiver,
+            deployment_url,
+            http_client: reqwest::Client::new(),
+            auth: Arc::new(Mutex::new(AuthenticationToken::None)),
         };
         Ok(client)
     }
@@ -200,6 +370,52 @@ impl ConvexClient {
         Ok(res)
     }
 
+    /// Subscribe to the paginated query `name` with `args`, loading
+    /// `initial_num_items` items in the first page.
+    ///
+    /// The query function must take a `paginationOpts` argument, which the
+    /// subscription fills in, and return the result of `.paginate()`. The
+    /// returned [`PaginatedQuerySubscription`] yields the items of all loaded
+    /// pages every time they change. Call
+    /// [`PaginatedQuerySubscription::load_more`] to load another page.
+    ///
+    /// The pages are unsubscribed when the subscription is dropped.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, PaginationStatus};
+    /// # use futures::StreamExt;
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// let mut sub = client.subscribe_paginated("listMessages", maplit::btreemap!{}, 10)?;
+    /// while let Some(results) = sub.next().await {
+    ///     let results = results?;
+    ///     println!("{:?}", results.results);
+    ///     if results.status == PaginationStatus::CanLoadMore {
+    ///         sub.load_more(10);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    pub fn subscribe_paginated(
+        &mut self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+        initial_num_items: usize,
+    ) -> anyhow::Result<PaginatedQuerySubscription> {
+        let _: UdfPath = name.parse()?;
+        anyhow::ensure!(
+            !args.contains_key("paginationOpts"),
+            "paginationOpts is filled in by subscribe_paginated and can't be passed as an argument"
+        );
+        Ok(PaginatedQuerySubscription::new(
+            self.clone(),
+            name.to_string(),
+            args,
+            initial_num_items,
+        ))
+    }
+
     /// Make a oneshot request to a query `name` with `args`.
     ///
     /// Returns a [`FunctionResult`] representing the result of the query.
@@ -232,6 +448,98 @@ impl ConvexClient {
             .expect("INTERNAL BUG: Convex Client dropped prematurely."))
     }
 
+    /// Run query `name` with `args` once at a past snapshot, returning its
+    /// result along with the snapshot timestamp it read at.
+    ///
+    /// Reads at the latest snapshot if `at` is `None`. Passing the returned
+    /// timestamp back as [`QueryTimestamp::Snapshot`] reads further queries
+    /// at the same snapshot. The deployment only retains a limited window of
+    /// history, and reading before it fails. Picking a snapshot requires a
+    /// deploy key set with [`ConvexClient::set_admin_auth`], not acting as a
+    /// user.
+    ///
+    /// Unlike [`ConvexClient::query`], this is a single HTTP request rather
+    /// than a subscription on the WebSocket.
+    ///
+    /// ```no_run
+    /// # use convex::{ConvexClient, QueryTimestamp};
+    /// # use std::time::{Duration, SystemTime};
+    /// # #[tokio::main]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// let mut client = ConvexClient::new("https://cool-music-123.convex.cloud").await?;
+    /// client.set_admin_auth("deploy-key".to_string(), None).await;
+    /// let an_hour_ago = SystemTime::now() - Duration::from_secs(60 * 60);
+    /// let (messages, ts) = client.query_at("listMessages", maplit::btreemap!{
+    ///     "channel".into() => 1.into(),
+    /// }, Some(QueryTimestamp::WallClock(an_hour_ago))).await?;
+    /// let (count, _) = client.query_at("countMessages", maplit::btreemap!{},
+    ///     Some(QueryTimestamp::Snapshot(ts))).await?;
+    /// println!("{messages:?} {count:?}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn query_at(
+        &self,
+        name: &str,
+        args: BTreeMap<String, Value>,
+        at: Option<QueryTimestamp>,
+    ) -> anyhow::Result<(FunctionResult, Timestamp)> {
+        let udf_path: UdfPath = name.parse()?;
+        let mut body = json!({
+            "path": String::from(udf_path),
+            "args": JsonValue::from(Value::Object(args)),
+            "format": "convex_encoded_json",
+        });
+        match at {
+            None => (),
+            Some(QueryTimestamp::Snapshot(ts)) => body["ts"] = ts.to_string().into(),
+            Some(QueryTimestamp::WallClock(time)) => {
+                let since_epoch = time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .context("Time before 1970")?;
+                body["asOf"] = (since_epoch.as_secs_f64() * 1000.0).into();
+            },
+        }
+        let version = VERSION.unwrap_or("unknown");
+        let mut request = self
+            .http_client
+            .post(self.deployment_url.join("api/query")?)
+            .header("Convex-Client", format!("rust-{version}"))
+            .json(&body);
+        if let Some(authorization) = self.authorization_header()? {
+            request = request.header("Authorization", authorization);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await?;
+            anyhow::bail!("Query {name} failed with {status}: {text}");
+        }
+        let mut response: JsonValue = response.json().await?;
+        let ts: Timestamp = response["ts"]
+            .as_str()
+            .context("Query response is missing ts")?
+            .parse()?;
+        let result = match response["status"].as_str() {
+            Some("success") => FunctionResult::Value(response["value"].take().try_into()?),
+            Some("error") => {
+                let message = response["errorMessage"]
+                    .as_str()
+                    .context("Query response is missing errorMessage")?
+                    .to_owned();
+                match response["errorData"].take() {
+                    JsonValue::Null => FunctionResult::ErrorMessage(message),
+                    data => FunctionResult::ConvexError(ConvexError {
+                        message,
+                        data: data.try_into()?,
+                    }),
+                }
+            },
+            _ => anyhow::bail!("Unexpected query response: {response}"),
+        };
+        Ok((result, ts))
+    }
+
     /// Perform a mutation `name` with `args` and return a future
     /// containing the return value of the mutation once it completes.
     ///
@@ -346,6 +654,7 @@ impl ConvexClient {
                 Some(token) => AuthenticationToken::User(token),
             },
         };
+        self.set_http_auth(req.token.clone());
         self.request_sender
             .send(ClientRequest::Authenticate(req))
             .await
@@ -367,11 +676,34 @@ impl ConvexClient {
         let req = AuthenticateRequest {
             token: AuthenticationToken::Admin(deploy_key, acting_as),
         };
+        self.set_http_auth(req.token.clone());
         self.request_sender
             .send(ClientRequest::Authenticate(req))
             .await
             .expect("INTERNAL BUG: Worker has gone away");
     }
+
+    fn set_http_auth(&self, token: AuthenticationToken) {
+        *self.auth.lock().expect("INTERNAL BUG: Auth lock poisoned") = token;
+    }
+
+    fn authorization_header(&self) -> anyhow::Result<Option<String>> {
+        let token = self
+            .auth
+            .lock()
+            .expect("INTERNAL BUG: Auth lock poisoned")
+            .clone();
+        let header = match token {
+            AuthenticationToken::None => None,
+            AuthenticationToken::User(token) => Some(format!("Bearer {token}")),
+            AuthenticationToken::Admin(key, None) => Some(format!("Convex {key}")),
+            AuthenticationToken::Admin(key, Some(acting_as)) => {
+                let acting_as = serde_json::to_vec(&JsonValue::try_from(acting_as)?)?;
+                Some(format!("Convex {key}:{}", base64::encode(acting_as)))
+            },
+        };
+        Ok(header)
+    }
 }
 
 fn deployment_to_ws_url(mut deployment_url: Url) -> anyhow::Result<Url> {
@@ -387,15 +719,35 @@ fn deployment_to_ws_url(mut deployment_url: Url) -> anyhow::Result<Url> {
     Ok(deployment_url)
 }
 
+fn deployment_to_sse_url(mut deployment_url: Url) -> anyhow::Result<Url> {
+    let http_scheme = match deployment_url.scheme() {
+        "http" | "ws" => "http",
+        "https" | "wss" => "https",
+        scheme => anyhow::bail!("Unknown scheme {scheme}. Expected http or https."),
+    };
+    deployment_url
+        .set_scheme(http_scheme)
+        .map_err(|()| anyhow::anyhow!("Scheme not supported"))?;
+    deployment_url.set_path("api/sync/sse");
+    Ok(deployment_url)
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::{
         str::FromStr,
-        sync::Arc,
+        sync::{
+            Arc,
+            Mutex,
+        },
         time::Duration,
     };
 
     use convex_sync_types::{
+        diff::{
+            value_hash,
+            ValueDiff,
+        },
         AuthenticationToken,
         ClientMessage,
         LogLinesMessage,
@@ -410,17 +762,22 @@ pub mod tests {
     };
     use futures::{
         channel::mpsc,
+        FutureExt,
         StreamExt,
     };
     use maplit::btreemap;
     use pretty_assertions::assert_eq;
-    use serde_json::json;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
     use tokio::sync::broadcast;
 
     use super::ConvexClient;
     use crate::{
         base_client::FunctionResult,
         client::{
+            deployment_to_sse_url,
             deployment_to_ws_url,
             worker::worker,
             BaseConvexClient,
@@ -431,6 +788,9 @@ pub mod tests {
             SyncProtocol,
         },
         value::Value,
+        DeflateConfig,
+        PaginatedResults,
+        PaginationStatus,
     };
 
     impl ConvexClient {
@@ -462,6 +822,9 @@ pub mod tests {
                 listen_handle: Some(Arc::new(listen_handle)),
                 request_sender,
                 watch_receiver,
+                deployment_url: "http://test.com".parse()?,
+                http_client: reqwest::Client::new(),
+                auth: Arc::new(Mutex::new(AuthenticationToken::None)),
             };
             Ok((client, test_protocol))
         }
@@ -645,6 +1008,35 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_http_auth() -> anyhow::Result<()> {
+        let (mut client, _test_protocol) = ConvexClient::with_test_protocol().await?;
+        assert_eq!(client.authorization_header()?, None);
+
+        client.set_auth(Some("myauthtoken".into())).await;
+        assert_eq!(
+            client.authorization_header()?,
+            Some("Bearer myauthtoken".into())
+        );
+
+        client.set_admin_auth("myadminauth".into(), None).await;
+        assert_eq!(
+            client.authorization_header()?,
+            Some("Convex myadminauth".into())
+        );
+
+        let acting_as = UserIdentityAttributes {
+            name: Some("Barbara Liskov".into()),
+            ..Default::default()
+        };
+        client
+            .set_admin_auth("myadminauth".into(), Some(acting_as))
+            .await;
+        let header = client.authorization_header()?.expect("Missing header");
+        assert!(header.starts_with("Convex myadminauth:"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_client_single_subscription() -> anyhow::Result<()> {
         let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
@@ -659,6 +1051,7 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    supports_query_diffs: true,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -705,6 +1098,51 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_client_query_diff() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        let mut subscription = client.subscribe("listMessages", btreemap! {}).await?;
+        let query_id = subscription.query_id();
+        test_protocol.take_sent().await;
+
+        let old_value = Value::from(vec![Value::from("hello"), Value::from("world")]);
+        let (transition, version) =
+            fake_transition(StateVersion::initial(), vec![(query_id, old_value.clone())]);
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            subscription.next().await,
+            Some(FunctionResult::Value(old_value.clone()))
+        );
+
+        let new_value = Value::from(vec![
+            Value::from("hello"),
+            Value::from("world"),
+            Value::from("again"),
+        ]);
+        let new_json = JsonValue::from(new_value.clone());
+        test_protocol
+            .fake_server_response(ServerMessage::Transition {
+                start_version: version,
+                end_version: StateVersion {
+                    ts: version.ts.succ()?,
+                    ..version
+                },
+                modifications: vec![StateModification::QueryDiffed {
+                    query_id,
+                    diff: ValueDiff::new(&JsonValue::from(old_value), &new_json),
+                    value_hash: value_hash(&new_json),
+                    log_lines: LogLinesMessage(vec![]),
+                    journal: None,
+                }],
+            })
+            .await?;
+        assert_eq!(
+            subscription.next().await,
+            Some(FunctionResult::Value(new_value))
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_client_consistent_view_watch() -> anyhow::Result<()> {
         let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
@@ -791,6 +1229,7 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    supports_query_diffs: true,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -848,6 +1287,7 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    supports_query_diffs: true,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -898,6 +1338,418 @@ pub mod tests {
         Ok(())
     }
 
+    fn page(items: Vec<Value>, is_done: bool, continue_cursor: &str) -> Value {
+        Value::Object(btreemap! {
+            "page".to_string() => items.into(),
+            "isDone".to_string() => is_done.into(),
+            "continueCursor".to_string() => continue_cursor.into(),
+        })
+    }
+
+    fn split_page(
+        items: Vec<Value>,
+        continue_cursor: &str,
+        split_cursor: Option<&str>,
+        page_status: &str,
+    ) -> Value {
+        let Value::Object(mut fields) = page(items, false, continue_cursor) else {
+            unreachable!();
+        };
+        if let Some(split_cursor) = split_cursor {
+            fields.insert("splitCursor".to_string(), split_cursor.into());
+        }
+        fields.insert("pageStatus".to_string(), page_status.into());
+        Value::Object(fields)
+    }
+
+    /// Returns the query set modifications sent since the last call.
+    async fn sent_modifications(test_protocol: &TestProtocolManager) -> Vec<QuerySetModification> {
+        let mut sent = vec![];
+        for message in test_protocol.take_sent().await {
+            if let ClientMessage::ModifyQuerySet { modifications, .. } = message {
+                sent.extend(modifications);
+            }
+        }
+        sent
+    }
+
+    /// Returns the `paginationOpts` of the queries added since the last call,
+    /// without the pagination ID.
+    async fn added_pages(test_protocol: &TestProtocolManager) -> Vec<(QueryId, JsonValue)> {
+        let mut added = vec![];
+        for modification in sent_modifications(test_protocol).await {
+            if let QuerySetModification::Add(query) = modification {
+                let mut opts = query.args[0]["paginationOpts"].clone();
+                opts.as_object_mut().unwrap().remove("id");
+                added.push((query.query_id, opts));
+            }
+        }
+        added
+    }
+
+    /// Waits for the client to add `n` page queries and returns their
+    /// `paginationOpts` like [`added_pages`].
+    async fn wait_for_added_pages(
+        test_protocol: &TestProtocolManager,
+        n: usize,
+    ) -> Vec<(QueryId, JsonValue)> {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            let mut added = vec![];
+            while added.len() < n {
+                added.extend(added_pages(test_protocol).await);
+                tokio::task::yield_now().await;
+            }
+            added
+        })
+        .await
+        .expect("Test timed out waiting for pages to be added")
+    }
+
+    /// Finds the query in `added` with the `paginationOpts` `opts`.
+    fn page_query(added: &[(QueryId, JsonValue)], opts: JsonValue) -> QueryId {
+        added
+            .iter()
+            .find(|(_, added_opts)| *added_opts == opts)
+            .unwrap_or_else(|| panic!("No page added with {opts}"))
+            .0
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_paginated() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let mut pages = client.subscribe_paginated("listMessages", btreemap! {}, 2)?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![],
+                status: PaginationStatus::LoadingFirstPage,
+            }
+        );
+        let added = added_pages(&test_protocol).await;
+        assert_eq!(
+            added.iter().map(|(_, opts)| opts).collect::<Vec<_>>(),
+            vec![&json!({"numItems": 2.0, "cursor": null})]
+        );
+        let first_page = added[0].0;
+
+        let (transition, version) = fake_transition(
+            StateVersion::initial(),
+            vec![(first_page, page(vec![1.into(), 2.into()], false, "c1"))],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into()],
+                status: PaginationStatus::CanLoadMore,
+            }
+        );
+
+        // Loading more pins the first page to end at its current cursor.
+        assert!(pages.load_more(2));
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into()],
+                status: PaginationStatus::LoadingMore,
+            }
+        );
+        let mut added = added_pages(&test_protocol).await;
+        added.sort_by_key(|(_, opts)| opts["cursor"].to_string());
+        assert_eq!(
+            added.iter().map(|(_, opts)| opts).collect::<Vec<_>>(),
+            vec![
+                &json!({"numItems": 2.0, "cursor": "c1"}),
+                &json!({"numItems": 2.0, "cursor": null, "endCursor": "c1"}),
+            ]
+        );
+        let (second_page, pinned_page) = (added[0].0, added[1].0);
+
+        let (transition, _) = fake_transition(
+            version,
+            vec![
+                (pinned_page, page(vec![1.into(), 2.into()], false, "c1")),
+                (second_page, page(vec![3.into()], true, "c2")),
+            ],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into(), 3.into()],
+                status: PaginationStatus::Exhausted,
+            }
+        );
+        assert!(!pages.load_more(2));
+
+        // The unpinned page is unsubscribed once the pinned one replaces it.
+        test_protocol.wait_until_n_messages_sent(1).await;
+        let ClientMessage::ModifyQuerySet { modifications, .. } =
+            test_protocol.take_sent().await.remove(0)
+        else {
+            panic!("Expected ModifyQuerySet");
+        };
+        assert_eq!(
+            modifications,
+            vec![QuerySetModification::Remove {
+                query_id: first_page
+            }]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_paginated_split_required() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let mut pages = client.subscribe_paginated("listMessages", btreemap! {}, 2)?;
+        pages.next().await.unwrap()?;
+        let first_page = added_pages(&test_protocol).await[0].0;
+
+        // The page read too much, so it might be incomplete and has to be split
+        // before any of it is shown.
+        let (transition, version) = fake_transition(
+            StateVersion::initial(),
+            vec![(
+                first_page,
+                split_page(
+                    vec![1.into(), 2.into(), 3.into()],
+                    "c3",
+                    Some("s"),
+                    "SplitRequired",
+                ),
+            )],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert!(pages.next().now_or_never().is_none());
+        let added = wait_for_added_pages(&test_protocol, 2).await;
+        let first_half = page_query(
+            &added,
+            json!({"numItems": 2.0, "cursor": null, "endCursor": "s"}),
+        );
+        let second_half = page_query(
+            &added,
+            json!({"numItems": 2.0, "cursor": "s", "endCursor": "c3"}),
+        );
+
+        let (transition, _) = fake_transition(
+            version,
+            vec![
+                (first_half, page(vec![1.into(), 2.into()], false, "s")),
+                (second_half, page(vec![3.into(), 4.into()], false, "c3")),
+            ],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into(), 3.into(), 4.into()],
+                status: PaginationStatus::CanLoadMore,
+            }
+        );
+
+        // The split page is unsubscribed once both halves replace it.
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(
+            sent_modifications(&test_protocol).await,
+            vec![QuerySetModification::Remove {
+                query_id: first_page
+            }]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_paginated_split_required_without_cursor() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let mut pages = client.subscribe_paginated("listMessages", btreemap! {}, 2)?;
+        pages.next().await.unwrap()?;
+        let first_page = added_pages(&test_protocol).await[0].0;
+
+        let (transition, _) = fake_transition(
+            StateVersion::initial(),
+            vec![(
+                first_page,
+                split_page(vec![1.into(), 2.into()], "c2", None, "SplitRequired"),
+            )],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        let err = pages.next().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("splitCursor"), "{err}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_paginated_invalid_cursor_restarts() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let mut pages = client.subscribe_paginated("listMessages", btreemap! {}, 2)?;
+        pages.next().await.unwrap()?;
+        let first_page = added_pages(&test_protocol).await[0].0;
+
+        let (transition, version) = fake_transition(
+            StateVersion::initial(),
+            vec![(first_page, page(vec![1.into(), 2.into()], false, "c1"))],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into()],
+                status: PaginationStatus::CanLoadMore,
+            }
+        );
+
+        let end_version = StateVersion {
+            ts: version.ts.succ()?,
+            ..version
+        };
+        test_protocol
+            .fake_server_response(ServerMessage::Transition {
+                start_version: version,
+                end_version,
+                modifications: vec![StateModification::QueryFailed {
+                    query_id: first_page,
+                    error_message: "InvalidCursor: Tried to run a query starting from a cursor, \
+                                    but it looks like this cursor is from a different query."
+                        .to_string(),
+                    log_lines: LogLinesMessage(vec![]),
+                    journal: None,
+                    error_data: None,
+                }],
+            })
+            .await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![],
+                status: PaginationStatus::LoadingFirstPage,
+            }
+        );
+
+        // Pagination starts over from a new first page.
+        assert!(pages.next().now_or_never().is_none());
+        let added = wait_for_added_pages(&test_protocol, 1).await;
+        assert_eq!(
+            added.iter().map(|(_, opts)| opts).collect::<Vec<_>>(),
+            vec![&json!({"numItems": 2.0, "cursor": null})]
+        );
+        let restarted_page = added[0].0;
+        assert_ne!(restarted_page, first_page);
+
+        let (transition, _) = fake_transition(
+            end_version,
+            vec![(restarted_page, page(vec![3.into()], true, "c3"))],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![3.into()],
+                status: PaginationStatus::Exhausted,
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_paginated_result_before_subscription() -> anyhow::Result<()> {
+        let (mut client, mut test_protocol) = ConvexClient::with_test_protocol().await?;
+        test_protocol.take_sent().await;
+
+        let mut pages = client.subscribe_paginated("listMessages", btreemap! {}, 2)?;
+        pages.next().await.unwrap()?;
+        let ClientMessage::ModifyQuerySet { modifications, .. } =
+            test_protocol.take_sent().await.remove(0)
+        else {
+            panic!("Expected ModifyQuerySet");
+        };
+        let QuerySetModification::Add(first_query) = &modifications[0] else {
+            panic!("Expected the first page to be added");
+        };
+        let first_page = first_query.query_id;
+        let pagination_id = first_query.args[0]["paginationOpts"]["id"]
+            .as_f64()
+            .expect("paginationOpts is missing id");
+
+        let (transition, version) = fake_transition(
+            StateVersion::initial(),
+            vec![(first_page, page(vec![1.into(), 2.into()], false, "c1"))],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        pages.next().await.unwrap()?;
+
+        // Subscribe to the page that loading more will pin the first page to
+        // and give it a result, so the paginated subscription gets the result
+        // as soon as it subscribes instead of in a later transition.
+        let mut pinned = client
+            .subscribe(
+                "listMessages",
+                btreemap! {
+                    "paginationOpts".to_string() => Value::Object(btreemap! {
+                        "numItems".to_string() => Value::Float64(2.0),
+                        "cursor".to_string() => Value::Null,
+                        "endCursor".to_string() => "c1".into(),
+                        "id".to_string() => Value::Float64(pagination_id),
+                    }),
+                },
+            )
+            .await?;
+        let pinned_page = added_pages(&test_protocol).await[0].0;
+        let (transition, version) = fake_transition(
+            version,
+            vec![(pinned_page, page(vec![1.into(), 2.into()], false, "c1"))],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        pinned.next().await;
+
+        assert!(pages.load_more(2));
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into()],
+                status: PaginationStatus::LoadingMore,
+            }
+        );
+        let added = wait_for_added_pages(&test_protocol, 1).await;
+        // The pinned page is already subscribed, so only the new page is added.
+        assert_eq!(
+            added.iter().map(|(_, opts)| opts).collect::<Vec<_>>(),
+            vec![&json!({"numItems": 2.0, "cursor": "c1"})]
+        );
+        let second_page = added[0].0;
+
+        let (transition, _) = fake_transition(
+            version,
+            vec![(second_page, page(vec![3.into()], true, "c2"))],
+        );
+        test_protocol.fake_server_response(transition).await?;
+        assert_eq!(
+            pages.next().await.unwrap()?,
+            PaginatedResults {
+                results: vec![1.into(), 2.into(), 3.into()],
+                status: PaginationStatus::Exhausted,
+            }
+        );
+
+        // The pinned page replaces the first page without a transition of its
+        // own.
+        test_protocol.wait_until_n_messages_sent(1).await;
+        assert_eq!(
+            sent_modifications(&test_protocol).await,
+            vec![QuerySetModification::Remove {
+                query_id: first_page
+            }]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_deployment_url() -> anyhow::Result<()> {
         assert_eq!(
@@ -922,6 +1774,31 @@ pub mod tests {
                 .to_string(),
             "Unknown scheme ftp. Expected http or https.",
         );
+        assert_eq!(
+            deployment_to_sse_url("https://flying-shark-123.convex.cloud".parse()?)?.to_string(),
+            "https://flying-shark-123.convex.cloud/api/sync/sse",
+        );
+        assert_eq!(
+            deployment_to_sse_url("ws://flying-shark-123.convex.cloud".parse()?)?.to_string(),
+            "http://flying-shark-123.convex.cloud/api/sync/sse",
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_builder_rejects_invalid_window_bits() -> anyhow::Result<()> {
+        for window_bits in [8, 16] {
+            let err = ConvexClient::builder("https://flying-shark-123.convex.cloud")
+                .with_compression(Some(DeflateConfig {
+                    window_bits,
+                    threshold: 1024,
+                }))
+                .build()
+                .await
+                .err()
+                .expect("Invalid window_bits should be rejected");
+            assert!(err.to_string().contains("window_bits"), "{err}");
+        }
         Ok(())
     }
 }
@@ -0,0 +1,444 @@
+use std::{
+    collections::BTreeMap,
+    pin::Pin,
+    sync::atomic::{
+        AtomicU64,
+        Ordering,
+    },
+};
+
+use anyhow::Context;
+use futures::{
+    future::BoxFuture,
+    stream::FuturesUnordered,
+    task,
+    FutureExt,
+    Stream,
+    StreamExt,
+};
+
+use crate::{
+    base_client::{
+        FunctionResult,
+        QueryResults,
+    },
+    client::subscription::{
+        QuerySetSubscription,
+        QuerySubscription,
+    },
+    ConvexClient,
+    Value,
+};
+
+static NEXT_PAGINATION_ID: AtomicU64 = AtomicU64::new(1);
+
+type PageKey = u64;
+
+/// The loading state of a [`PaginatedQuerySubscription`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaginationStatus {
+    /// The first page of results is still loading.
+    LoadingFirstPage,
+    /// A page after the first is loading.
+    LoadingMore,
+    /// More results can be loaded with
+    /// [`PaginatedQuerySubscription::load_more`].
+    CanLoadMore,
+    /// All results have been loaded.
+    Exhausted,
+}
+
+/// The loaded results of a paginated query, concatenated across pages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaginatedResults {
+    /// The items of all loaded pages, in order.
+    pub results: Vec<Value>,
+    /// Whether more results are loading or can be loaded.
+    pub status: PaginationStatus,
+}
+
+/// The `paginationOpts` argument for one page.
+#[derive(Clone, Debug)]
+struct PaginationOpts {
+    num_items: usize,
+    cursor: Option<String>,
+    end_cursor: Option<String>,
+}
+
+impl PaginationOpts {
+    fn to_value(&self, id: u64) -> Value {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "numItems".to_string(),
+            Value::Float64(self.num_items as f64),
+        );
+        fields.insert("cursor".to_string(), self.cursor.clone().into());
+        if let Some(end_cursor) = &self.end_cursor {
+            fields.insert("endCursor".to_string(), end_cursor.clone().into());
+        }
+        fields.insert("id".to_string(), Value::Float64(id as f64));
+        Value::Object(fields)
+    }
+}
+
+/// A `PaginationResult` returned by a paginated query function.
+struct PageResult {
+    page: Vec<Value>,
+    is_done: bool,
+    continue_cursor: String,
+    split_cursor: Option<String>,
+    page_status: Option<String>,
+}
+
+impl TryFrom<&Value> for PageResult {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Value) -> anyhow::Result<Self> {
+        let Value::Object(fields) = value else {
+            anyhow::bail!("Paginated query didn't return an object: {value:?}");
+        };
+        let string_field = |name: &str| match fields.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(v) => Err(anyhow::anyhow!(
+                "Invalid {name} in paginated query result: {v:?}"
+            )),
+        };
+        let Some(Value::Array(page)) = fields.get("page") else {
+            anyhow::bail!("Paginated query result is missing page");
+        };
+        let Some(Value::Boolean(is_done)) = fields.get("isDone") else {
+            anyhow::bail!("Paginated query result is missing isDone");
+        };
+        Ok(PageResult {
+            page: page.clone(),
+            is_done: *is_done,
+            continue_cursor: string_field("continueCursor")?
+                .context("Paginated query result is missing continueCursor")?,
+            split_cursor: string_field("splitCursor")?,
+            page_status: string_field("pageStatus")?,
+        })
+    }
+}
+
+struct PageQuery {
+    opts: PaginationOpts,
+    /// `None` until the subscribe request completes.
+    subscription: Option<QuerySubscription>,
+    result: Option<FunctionResult>,
+}
+
+/// A subscription to a paginated query, returned by
+/// [`ConvexClient::subscribe_paginated`].
+///
+/// Implements [`Stream`]<[`anyhow::Result`]<[`PaginatedResults`]>>. A new item
+/// appears on the stream whenever the concatenated results of the loaded
+/// pages change, and all pages in an item come from the same snapshot.
+///
+/// Every loaded page stays subscribed. When more items are loaded, the
+/// previous last page is pinned to end at its current `continueCursor`, so
+/// documents inserted later show up in exactly one page instead of shifting
+/// items between pages. Pages that read too much data are split in two using
+/// the `splitCursor` the server returns.
+///
+/// If a page fails with an `InvalidCursor` error, e.g. because the query
+/// changed underneath it, pagination restarts from the first page.
+pub struct PaginatedQuerySubscription {
+    client: ConvexClient,
+    name: String,
+    args: BTreeMap<String, Value>,
+    initial_num_items: usize,
+    watch: QuerySetSubscription,
+    latest_results: Option<QueryResults>,
+
+    /// Passed as `paginationOpts.id` so this pagination session has its own
+    /// query subscriptions and journals. Changes when pagination restarts.
+    id: u64,
+    next_page_key: PageKey,
+    queries: BTreeMap<PageKey, PageQuery>,
+    /// The pages whose results we show, in order.
+    page_keys: Vec<PageKey>,
+    /// Pages being replaced by pinned or split pages. The replacements are
+    /// swapped in once all of them have results.
+    ongoing_replacements: BTreeMap<PageKey, Vec<PageKey>>,
+    subscribing: FuturesUnordered<BoxFuture<'static, (PageKey, anyhow::Result<QuerySubscription>)>>,
+
+    continue_cursor: Option<String>,
+    last_results: Option<PaginatedResults>,
+}
+
+impl PaginatedQuerySubscription {
+    pub(super) fn new(
+        client: ConvexClient,
+        name: String,
+        args: BTreeMap<String, Value>,
+        initial_num_items: usize,
+    ) -> Self {
+        let watch = client.watch_all();
+        let mut subscription = Self {
+            client,
+            name,
+            args,
+            initial_num_items,
+            watch,
+            latest_results: None,
+            id: 0,
+            next_page_key: 0,
+            queries: BTreeMap::new(),
+            page_keys: vec![],
+            ongoing_replacements: BTreeMap::new(),
+            subscribing: FuturesUnordered::new(),
+            continue_cursor: None,
+            last_results: None,
+        };
+        subscription.restart();
+        subscription
+    }
+
+    /// Loads up to `num_items` more items after the last loaded page.
+    ///
+    /// Only has an effect if the latest status is
+    /// [`PaginationStatus::CanLoadMore`], and returns whether more items are
+    /// being loaded.
+    pub fn load_more(&mut self, num_items: usize) -> bool {
+        let Some(continue_cursor) = self.continue_cursor.take() else {
+            return false;
+        };
+        // Pin the last page to end where it ends now, so items inserted after
+        // it go to the new page instead of moving the boundary between them.
+        let last_key = *self.page_keys.last().expect("Pagination always has a page");
+        let last_opts = &self.queries[&last_key].opts;
+        if last_opts.end_cursor.is_none() && !self.ongoing_replacements.contains_key(&last_key) {
+            let pinned = PaginationOpts {
+                end_cursor: Some(continue_cursor.clone()),
+                ..last_opts.clone()
+            };
+            self.replace(last_key, vec![pinned]);
+        }
+        let key = self.add_query(PaginationOpts {
+            num_items,
+            cursor: Some(continue_cursor),
+            end_cursor: None,
+        });
+        self.page_keys.push(key);
+        true
+    }
+
+    fn restart(&mut self) {
+        self.id = NEXT_PAGINATION_ID.fetch_add(1, Ordering::Relaxed);
+        // Dropping the old subscriptions unsubscribes from them.
+        self.queries.clear();
+        self.ongoing_replacements.clear();
+        self.continue_cursor = None;
+        let key = self.add_query(PaginationOpts {
+            num_items: self.initial_num_items,
+            cursor: None,
+            end_cursor: None,
+        });
+        self.page_keys = vec![key];
+    }
+
+    fn add_query(&mut self, opts: PaginationOpts) -> PageKey {
+        let key = self.next_page_key;
+        self.next_page_key += 1;
+
+        let mut args = self.args.clone();
+        args.insert("paginationOpts".to_string(), opts.to_value(self.id));
+        let mut client = self.client.clone();
+        let name = self.name.clone();
+        self.subscribing
+            .push(async move { (key, client.subscribe(&name, args).await) }.boxed());
+        self.queries.insert(
+            key,
+            PageQuery {
+                opts,
+                subscription: None,
+                result: None,
+            },
+        );
+        key
+    }
+
+    fn replace(&mut self, key: PageKey, replacements: Vec<PaginationOpts>) {
+        let keys = replacements
+            .into_iter()
+            .map(|opts| self.add_query(opts))
+            .collect();
+        self.ongoing_replacements.insert(key, keys);
+    }
+
+    fn split(&mut self, key: PageKey, split_cursor: String, continue_cursor: String) {
+        let opts = self.queries[&key].opts.clone();
+        let first = PaginationOpts {
+            end_cursor: Some(split_cursor.clone()),
+            ..opts.clone()
+        };
+        let second = PaginationOpts {
+            cursor: Some(split_cursor),
+            end_cursor: Some(continue_cursor),
+            ..opts
+        };
+        self.replace(key, vec![first, second]);
+    }
+
+    fn update_results(&mut self, results: QueryResults) {
+        for query in self.queries.values_mut() {
+            if let Some(subscription) = &query.subscription
+                && let Some(result) = results.get(subscription.id())
+            {
+                query.result = Some(result.clone());
+            }
+        }
+        self.latest_results = Some(results);
+    }
+
+    /// Swaps in replacement pages that all have results.
+    fn complete_replacements(&mut self) {
+        let completed: Vec<_> = self
+            .ongoing_replacements
+            .iter()
+            .filter(|(_, replacements)| {
+                replacements
+                    .iter()
+                    .all(|key| self.queries.get(key).is_some_and(|q| q.result.is_some()))
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        for key in completed {
+            let replacements = self
+                .ongoing_replacements
+                .remove(&key)
+                .expect("Replacement disappeared");
+            self.queries.remove(&key);
+            if let Some(i) = self.page_keys.iter().position(|k| *k == key) {
+                self.page_keys.splice(i..=i, replacements);
+            }
+        }
+    }
+
+    /// Concatenates the loaded pages, splitting any that are too large.
+    fn compute_results(&mut self) -> anyhow::Result<PaginatedResults> {
+        self.complete_replacements();
+        self.continue_cursor = None;
+
+        let mut results = vec![];
+        let mut last_page = None;
+        for (i, key) in self.page_keys.clone().into_iter().enumerate() {
+            let loading_status = if i == 0 {
+                PaginationStatus::LoadingFirstPage
+            } else {
+                PaginationStatus::LoadingMore
+            };
+            let value = match &self.queries[&key].result {
+                None => {
+                    return Ok(PaginatedResults {
+                        results,
+                        status: loading_status,
+                    });
+                },
+                Some(FunctionResult::Value(value)) => value,
+                Some(FunctionResult::ErrorMessage(message))
+                    if message.contains("InvalidCursor") =>
+                {
+                    // The paginated database query probably changed underneath
+                    // us, so our cursors no longer apply. Start over.
+                    tracing::warn!("Paginated query hit error, restarting pagination: {message}");
+                    self.restart();
+                    return Ok(PaginatedResults {
+                        results: vec![],
+                        status: PaginationStatus::LoadingFirstPage,
+                    });
+                },
+                Some(FunctionResult::ErrorMessage(message)) => anyhow::bail!("{message}"),
+                Some(FunctionResult::ConvexError(e)) => return Err(e.clone().into()),
+            };
+            let page = PageResult::try_from(value)?;
+            let split_requested = matches!(
+                page.page_status.as_deref(),
+                Some("SplitRecommended" | "SplitRequired")
+            );
+            if !self.ongoing_replacements.contains_key(&key)
+                && let Some(split_cursor) = &page.split_cursor
+                && (split_requested || page.page.len() > self.initial_num_items * 2)
+            {
+                self.split(key, split_cursor.clone(), page.continue_cursor.clone());
+            }
+            if page.page_status.as_deref() == Some("SplitRequired") {
+                // Without a split cursor we'd wait for split pages that never
+                // load.
+                anyhow::ensure!(
+                    page.split_cursor.is_some(),
+                    "Paginated query result has pageStatus SplitRequired but no splitCursor"
+                );
+                // The page might be incomplete, so stop before it until the
+                // split pages have loaded.
+                return Ok(PaginatedResults {
+                    results,
+                    status: loading_status,
+                });
+            }
+            results.extend(page.page.iter().cloned());
+            last_page = Some(page);
+        }
+
+        let last_page = last_page.expect("Pagination always has a page");
+        let status = if last_page.is_done {
+            PaginationStatus::Exhausted
+        } else {
+            self.continue_cursor = Some(last_page.continue_cursor);
+            PaginationStatus::CanLoadMore
+        };
+        Ok(PaginatedResults { results, status })
+    }
+}
+
+impl Stream for PaginatedQuerySubscription {
+    type Item = anyhow::Result<PaginatedResults>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+    ) -> task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let mut changed = false;
+            while let task::Poll::Ready(Some((key, subscription))) =
+                this.subscribing.poll_next_unpin(cx)
+            {
+                let mut subscription = match subscription {
+                    Ok(subscription) => subscription,
+                    Err(e) => return task::Poll::Ready(Some(Err(e))),
+                };
+                // Ignore subscriptions for pages we've since dropped.
+                let Some(query) = this.queries.get_mut(&key) else {
+                    continue;
+                };
+                // The result may have arrived before we got the subscription.
+                query.result = subscription.initial.take().or_else(|| {
+                    this.latest_results
+                        .as_ref()
+                        .and_then(|results| results.get(subscription.id()).cloned())
+                });
+                query.subscription = Some(subscription);
+                changed = true;
+            }
+            match this.watch.poll_next_unpin(cx) {
+                task::Poll::Ready(Some(results)) => {
+                    this.update_results(results);
+                    changed = true;
+                },
+                task::Poll::Ready(None) => return task::Poll::Ready(None),
+                task::Poll::Pending if !changed => return task::Poll::Pending,
+                task::Poll::Pending => {},
+            }
+            let results = match this.compute_results() {
+                Ok(results) => results,
+                Err(e) => return task::Poll::Ready(Some(Err(e))),
+            };
+            if this.last_results.as_ref() != Some(&results) {
+                this.last_results = Some(results.clone());
+                return task::Poll::Ready(Some(Ok(results)));
+            }
+        }
+    }
+}
@@ -51,11 +51,23 @@ pub use value::{
 
 mod client;
 pub use client::{
+    pagination::{
+        PaginatedQuerySubscription,
+        PaginatedResults,
+        PaginationStatus,
+    },
     subscription::{
         QuerySetSubscription,
         QuerySubscription,
     },
     ConvexClient,
+    ConvexClientBuilder,
+    QueryTimestamp,
+    SyncTransport,
+};
+pub use convex_sync_types::{
+    deflate::DeflateConfig,
+    Timestamp,
 };
 
 pub mod base_client;
@@ -8,6 +8,7 @@ use url::Url;
 
 use crate::value::Value;
 
+pub mod sse_manager;
 #[cfg(test)]
 pub mod testing;
 pub mod web_socket_manager;
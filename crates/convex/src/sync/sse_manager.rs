@@ -0,0 +1,421 @@
+//! A [`SyncProtocol`] over Server-Sent Events, for networks where WebSockets
+//! are blocked. Server messages arrive on a long-lived `GET /api/sync/sse`
+//! response, and client messages are sent with
+//! `POST /api/sync/sse/{connection_id}`.
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use convex_sync_types::{
+    backoff::Backoff,
+    ClientMessage,
+    SessionId,
+    Timestamp,
+};
+use futures::{
+    channel::{
+        mpsc,
+        oneshot,
+    },
+    select_biased,
+    FutureExt,
+    SinkExt,
+    StreamExt,
+};
+use reqwest::header;
+use tokio::{
+    task::JoinHandle,
+    time::{
+        Instant,
+        Interval,
+    },
+};
+use url::Url;
+use uuid::Uuid;
+
+use crate::sync::{
+    web_socket_manager::{
+        deprecation_message,
+        INITIAL_BACKOFF,
+        MAX_BACKOFF,
+        VERSION,
+    },
+    ProtocolResponse,
+    ReconnectRequest,
+    ServerMessage,
+    SyncProtocol,
+};
+
+#[derive(Debug)]
+enum SseRequest {
+    SendMessage(ClientMessage, oneshot::Sender<()>),
+    Reconnect(ReconnectRequest),
+}
+
+/// An event from a `text/event-stream` response.
+#[derive(Debug, PartialEq, Eq)]
+struct ServerEvent {
+    event: String,
+    data: String,
+}
+
+/// Incremental parser for the `text/event-stream` format. Only handles the
+/// `event` and `data` fields, which is all the sync endpoint sends.
+#[derive(Default)]
+struct EventStreamParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl EventStreamParser {
+    fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Returns the next complete event in the buffer, if there is one.
+    fn next_event(&mut self) -> anyhow::Result<Option<ServerEvent>> {
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = std::str::from_utf8(&line)
+                .context("EventStreamInvalidUtf8")?
+                .trim_end_matches(&['\r', '\n'][..]);
+            // A blank line dispatches the event, if it has any data.
+            if line.is_empty() {
+                let event = self.event.take();
+                if let Some(data) = self.data.take() {
+                    return Ok(Some(ServerEvent {
+                        event: event.unwrap_or_else(|| "message".to_string()),
+                        data,
+                    }));
+                }
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    },
+                    None => self.data = Some(value.to_string()),
+                },
+                // Comments (like the server's keep-alives), `id` and `retry`.
+                _ => {},
+            }
+        }
+        Ok(None)
+    }
+}
+
+struct SseInternal {
+    http_client: reqwest::Client,
+    events: reqwest::Response,
+    parser: EventStreamParser,
+    send_url: Url,
+    last_server_response: Instant,
+}
+
+struct SseWorker {
+    sse_url: Url,
+    http_client: reqwest::Client,
+    on_response: mpsc::Sender<ProtocolResponse>,
+    internal_receiver: mpsc::UnboundedReceiver<SseRequest>,
+    ping_ticker: Interval,
+    connection_count: u32,
+    backoff: Backoff,
+}
+
+/// Implements the sync protocol over Server-Sent Events, as a fallback for
+/// when WebSockets aren't available.
+pub struct SseManager {
+    internal_sender: mpsc::UnboundedSender<SseRequest>,
+    worker_handle: JoinHandle<anyhow::Result<()>>,
+}
+impl Drop for SseManager {
+    fn drop(&mut self) {
+        self.worker_handle.abort()
+    }
+}
+
+#[async_trait]
+impl SyncProtocol for SseManager {
+    async fn open(
+        sse_url: Url,
+        on_response: mpsc::Sender<ProtocolResponse>,
+    ) -> anyhow::Result<Self> {
+        let (internal_sender, internal_receiver) = mpsc::unbounded();
+        let worker_handle = tokio::spawn(SseWorker::run(sse_url, on_response, internal_receiver));
+
+        Ok(SseManager {
+            internal_sender,
+            worker_handle,
+        })
+    }
+
+    async fn send(&mut self, message: ClientMessage) -> anyhow::Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.internal_sender
+            .send(SseRequest::SendMessage(message, tx))
+            .await?;
+        rx.await?;
+        Ok(())
+    }
+
+    async fn reconnect(&mut self, request: ReconnectRequest) {
+        let _ = self
+            .internal_sender
+            .send(SseRequest::Reconnect(request))
+            .await;
+    }
+}
+
+impl SseWorker {
+    /// How often we check for server inactivity.
+    const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+    /// How long before lack of server response causes a timeout. The server
+    /// sends keep-alive comments, so this only fires if the stream is stuck.
+    const SERVER_INACTIVITY_THRESHOLD: Duration = Duration::from_secs(30);
+
+    async fn run(
+        sse_url: Url,
+        on_response: mpsc::Sender<ProtocolResponse>,
+        internal_receiver: mpsc::UnboundedReceiver<SseRequest>,
+    ) -> anyhow::Result<()> {
+        let mut worker = Self {
+            sse_url,
+            http_client: reqwest::Client::new(),
+            on_response,
+            internal_receiver,
+            ping_ticker: tokio::time::interval(Self::HEARTBEAT_INTERVAL),
+            connection_count: 0,
+            backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
+        };
+
+        let mut last_close_reason = "InitialConnect".to_string();
+        let mut max_observed_timestamp = None;
+        loop {
+            let e = match worker.work(last_close_reason, max_observed_timestamp).await {
+                Ok(reconnect) => {
+                    tracing::debug!("Reconnecting event stream due to {}", reconnect.reason);
+                    last_close_reason = reconnect.reason;
+                    max_observed_timestamp = reconnect.max_observed_timestamp;
+                    continue;
+                },
+                Err(e) => e,
+            };
+            worker.connection_count += 1;
+            last_close_reason = e.to_string();
+            let delay = worker.backoff.fail(&mut rand::thread_rng());
+            tracing::error!(
+                "Convex SseWorker failed: {e:?}. Backing off for {delay:?} and retrying."
+            );
+
+            // Like the WebSocket worker, let the base client coordinate the
+            // reconnect and wait for its Reconnect message, which is followed by
+            // the refreshed query set.
+            let _ = worker.on_response.send(ProtocolResponse::Failure).await;
+            loop {
+                let request = worker.internal_receiver.next().await;
+                if let Some(SseRequest::Reconnect(reconnect)) = request {
+                    max_observed_timestamp = reconnect.max_observed_timestamp;
+                    break;
+                }
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn work(
+        &mut self,
+        last_close_reason: String,
+        max_seen_transition: Option<Timestamp>,
+    ) -> anyhow::Result<ReconnectRequest> {
+        tracing::debug!("trying to connect to {}", self.sse_url);
+        let mut internal = SseInternal::new(
+            self.http_client.clone(),
+            &self.sse_url,
+            self.connection_count,
+            last_close_reason,
+            max_seen_transition,
+        )
+        .await?;
+        tracing::debug!("completed event stream connect to {}", self.sse_url);
+
+        loop {
+            select_biased! {
+                _ = self.ping_ticker.tick().fuse() => {
+                    let now = Instant::now();
+                    if now - internal.last_server_response > Self::SERVER_INACTIVITY_THRESHOLD {
+                        anyhow::bail!("InactiveServer");
+                    }
+                },
+                event = internal.next_event().fuse() => {
+                    let ServerEvent { event, data } = event?;
+                    match &event[..] {
+                        "message" => {
+                            let json: serde_json::Value = serde_json::from_str(&data).context("JsonDeserializeError")?;
+                            let server_message: ServerMessage = json.try_into()?;
+                            tracing::trace!("received message {server_message:?}");
+                            let resp = ProtocolResponse::ServerMessage(server_message);
+                            let _ = self.on_response.send(resp).await;
+                            self.backoff.reset();
+                        },
+                        // The server's equivalent of a WebSocket close frame.
+                        "close" => anyhow::bail!("{data}"),
+                        _ => tracing::debug!("received unknown event {event}"),
+                    }
+                },
+                request = self.internal_receiver.select_next_some() => {
+                    match request {
+                        SseRequest::SendMessage(message, sender) => {
+                            tracing::debug!("Sending {message:?}");
+                            internal.send(message).await?;
+                            let _ = sender.send(());
+                        },
+                        SseRequest::Reconnect(reason) => return Ok(reason),
+                    };
+                }
+            };
+        }
+    }
+}
+
+impl SseInternal {
+    async fn new(
+        http_client: reqwest::Client,
+        sse_url: &Url,
+        connection_count: u32,
+        last_close_reason: String,
+        max_observed_timestamp: Option<Timestamp>,
+    ) -> anyhow::Result<SseInternal> {
+        let version = VERSION.unwrap_or("unknown");
+        let events = http_client
+            .get(sse_url.clone())
+            .header(header::ACCEPT, "text/event-stream")
+            .header("Convex-Client", format!("rust-{version}"))
+            .send()
+            .await
+            .with_context(|| format!("Connection to {sse_url} failed"))?;
+        let status = events.status();
+        if !status.is_success() {
+            let body = events.text().await.unwrap_or_default();
+            anyhow::bail!("Connection to {sse_url} failed: {status}: {body}");
+        }
+        if let Some(msg) = deprecation_message(events.headers()) {
+            tracing::warn!("{msg}");
+        }
+
+        let mut internal = SseInternal {
+            http_client,
+            events,
+            parser: EventStreamParser::default(),
+            send_url: sse_url.clone(),
+            last_server_response: Instant::now(),
+        };
+
+        // The first event names the connection to send our messages to.
+        let ServerEvent { event, data } = internal.next_event().await?;
+        anyhow::ensure!(
+            event == "connected",
+            "Connection to {sse_url} failed: expected a connected event, got {event}"
+        );
+        internal
+            .send_url
+            .path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("Bad SSE Url"))?
+            .push(&data);
+
+        // Send an initial connect message on the new connection
+        let session_id = Uuid::new_v4();
+        let message = ClientMessage::Connect {
+            session_id: SessionId::new(session_id),
+            connection_count,
+            last_close_reason,
+            max_observed_timestamp,
+            supports_query_diffs: true,
+        };
+        internal.send(message).await?;
+
+        Ok(internal)
+    }
+
+    /// Waits for the next event from the server. Cancel safe.
+    async fn next_event(&mut self) -> anyhow::Result<ServerEvent> {
+        loop {
+            if let Some(event) = self.parser.next_event()? {
+                return Ok(event);
+            }
+            let chunk = self
+                .events
+                .chunk()
+                .await
+                .context("EventStreamConnectionError")?
+                .context("EventStreamClosed")?;
+            self.last_server_response = Instant::now();
+            self.parser.feed(&chunk);
+        }
+    }
+
+    async fn send(&mut self, message: ClientMessage) -> anyhow::Result<()> {
+        let json = serde_json::Value::try_from(message).context("JsonSerializeError")?;
+        let response = self
+            .http_client
+            .post(self.send_url.clone())
+            .json(&json)
+            .send()
+            .await
+            .context("EventStreamClosedOnSend")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("EventStreamClosedOnSend: {status}: {body}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EventStreamParser,
+        ServerEvent,
+    };
+
+    #[test]
+    fn test_event_stream_parser() -> anyhow::Result<()> {
+        let mut parser = EventStreamParser::default();
+        parser.feed(b"event: connected\ndata: abc\n\n:\n\ndata: {\"type\":");
+        assert_eq!(
+            parser.next_event()?,
+            Some(ServerEvent {
+                event: "connected".to_string(),
+                data: "abc".to_string(),
+            })
+        );
+        // Keep-alive comments and partial events don't produce events.
+        assert_eq!(parser.next_event()?, None);
+
+        parser.feed(b"\"Ping\"}\r\n\r\nevent: close\ndata: line one\ndata: line two\n\n");
+        assert_eq!(
+            parser.next_event()?,
+            Some(ServerEvent {
+                event: "message".to_string(),
+                data: "{\"type\":\"Ping\"}".to_string(),
+            })
+        );
+        assert_eq!(
+            parser.next_event()?,
+            Some(ServerEvent {
+                event: "close".to_string(),
+                data: "line one\nline two".to_string(),
+            })
+        );
+        assert_eq!(parser.next_event()?, None);
+        Ok(())
+    }
+}
@@ -81,6 +81,7 @@ impl SyncProtocol for TestProtocolManager {
                 connection_count,
                 last_close_reason: "InitialConnect".to_string(),
                 max_observed_timestamp: None,
+                supports_query_diffs: true,
             })
             .await?;
 
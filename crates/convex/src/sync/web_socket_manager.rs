@@ -4,6 +4,11 @@ use anyhow::Context;
 use async_trait::async_trait;
 use convex_sync_types::{
     backoff::Backoff,
+    deflate::{
+        DeflateConfig,
+        DeflateStream,
+        SEC_WEBSOCKET_EXTENSIONS,
+    },
     headers::{
         DEPRECATION_MSG_HEADER_NAME,
         DEPRECATION_STATE_HEADER_NAME,
@@ -22,8 +27,12 @@ use futures::{
     SinkExt,
     StreamExt,
 };
+use reqwest::{
+    header,
+    StatusCode,
+    Upgraded,
+};
 use tokio::{
-    net::TcpStream,
     task::JoinHandle,
     time::{
         Instant,
@@ -31,14 +40,17 @@ use tokio::{
     },
 };
 use tokio_tungstenite::{
-    connect_async,
     tungstenite::{
-        self,
-        client::IntoClientRequest,
+        handshake::{
+            client::generate_key,
+            derive_accept_key,
+        },
         http::HeaderMap,
-        protocol::Message,
+        protocol::{
+            Message,
+            Role,
+        },
     },
-    MaybeTlsStream,
     WebSocketStream,
 };
 use url::Url;
@@ -51,10 +63,10 @@ use crate::sync::{
     SyncProtocol,
 };
 
-const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
-const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
-const MAX_BACKOFF: Duration = Duration::from_secs(15);
-type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+pub(super) const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
+pub(super) const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+pub(super) const MAX_BACKOFF: Duration = Duration::from_secs(15);
+type WsStream = WebSocketStream<DeflateStream<Upgraded>>;
 
 #[derive(Debug)]
 enum WebSocketRequest {
@@ -73,6 +85,7 @@ struct WebSocketWorker {
     ping_ticker: Interval,
     connection_count: u32,
     backoff: Backoff,
+    compression: Option<DeflateConfig>,
 }
 
 pub struct WebSocketManager {
@@ -85,21 +98,38 @@ impl Drop for WebSocketManager {
     }
 }
 
-#[async_trait]
-impl SyncProtocol for WebSocketManager {
-    async fn open(
+impl WebSocketManager {
+    /// Opens the WebSocket like [`SyncProtocol::open`], offering
+    /// `permessage-deflate` with `compression` or not offering it at all if
+    /// `compression` is `None`.
+    pub async fn open_with_compression(
         ws_url: Url,
         on_response: mpsc::Sender<ProtocolResponse>,
+        compression: Option<DeflateConfig>,
     ) -> anyhow::Result<Self> {
         let (internal_sender, internal_receiver) = mpsc::unbounded();
-        let worker_handle =
-            tokio::spawn(WebSocketWorker::run(ws_url, on_response, internal_receiver));
+        let worker_handle = tokio::spawn(WebSocketWorker::run(
+            ws_url,
+            on_response,
+            internal_receiver,
+            compression,
+        ));
 
         Ok(WebSocketManager {
             internal_sender,
             worker_handle,
         })
     }
+}
+
+#[async_trait]
+impl SyncProtocol for WebSocketManager {
+    async fn open(
+        ws_url: Url,
+        on_response: mpsc::Sender<ProtocolResponse>,
+    ) -> anyhow::Result<Self> {
+        Self::open_with_compression(ws_url, on_response, None).await
+    }
 
     async fn send(&mut self, message: ClientMessage) -> anyhow::Result<()> {
         let (tx, rx) = oneshot::channel();
@@ -128,6 +158,7 @@ impl WebSocketWorker {
         ws_url: Url,
         on_response: mpsc::Sender<ProtocolResponse>,
         internal_receiver: mpsc::UnboundedReceiver<WebSocketRequest>,
+        compression: Option<DeflateConfig>,
     ) -> anyhow::Result<()> {
         let ping_ticker = tokio::time::interval(Self::HEARTBEAT_INTERVAL);
         let backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
@@ -139,6 +170,7 @@ impl WebSocketWorker {
             ping_ticker,
             connection_count: 0,
             backoff,
+            compression,
         };
 
         let mut last_close_reason = "InitialConnect".to_string();
@@ -199,6 +231,7 @@ impl WebSocketWorker {
         tracing::debug!("trying to {verb} to {}", self.ws_url);
         let mut internal = WebSocketInternal::new(
             self.ws_url.clone(),
+            self.compression,
             self.connection_count,
             last_close_reason,
             max_seen_transition,
@@ -264,42 +297,93 @@ impl WebSocketWorker {
     }
 }
 
-fn deprecation_message(headers: &HeaderMap) -> Option<String> {
+pub(super) fn deprecation_message(headers: &HeaderMap) -> Option<String> {
     let dep_state = headers.get(DEPRECATION_STATE_HEADER_NAME)?.to_str().ok()?;
     let msg = headers.get(DEPRECATION_MSG_HEADER_NAME)?.to_str().ok()?;
     Some(format!("{dep_state}: {msg}"))
 }
 
+/// Opens a WebSocket to `ws_url`, offering `permessage-deflate` compression
+/// with `compression` if it's set.
+///
+/// tungstenite doesn't support WebSocket extensions, so we do the handshake
+/// over a plain HTTP/1.1 upgrade and let [`DeflateStream`] handle compressed
+/// frames underneath tungstenite.
+async fn connect(ws_url: &Url, compression: Option<DeflateConfig>) -> anyhow::Result<WsStream> {
+    let mut http_url = ws_url.clone();
+    let scheme = match ws_url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        scheme => anyhow::bail!("Bad WS Url scheme: {scheme}"),
+    };
+    http_url
+        .set_scheme(scheme)
+        .map_err(|()| anyhow::anyhow!("Bad WS Url"))?;
+
+    let key = generate_key();
+    let version = VERSION.unwrap_or("unknown");
+    let client = reqwest::Client::builder().http1_only().build()?;
+    let mut request = client
+        .get(http_url)
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::UPGRADE, "websocket")
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .header(header::SEC_WEBSOCKET_KEY, &key)
+        .header("Convex-Client", format!("rust-{version}"));
+    if let Some(compression) = compression {
+        request = request.header(SEC_WEBSOCKET_EXTENSIONS, compression.client_offer());
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Connection to {ws_url} failed"))?;
+
+    let status = response.status();
+    if status != StatusCode::SWITCHING_PROTOCOLS {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Connection to {ws_url} failed: {status}: {body}");
+    }
+    let headers = response.headers();
+    let expected_accept = derive_accept_key(key.as_bytes());
+    anyhow::ensure!(
+        headers
+            .get(header::SEC_WEBSOCKET_ACCEPT)
+            .is_some_and(|accept| accept.as_bytes() == expected_accept.as_bytes()),
+        "Connection to {ws_url} failed: invalid Sec-WebSocket-Accept header"
+    );
+    let params = match (headers.get(SEC_WEBSOCKET_EXTENSIONS), compression) {
+        (Some(extensions), Some(compression)) => Some(
+            compression
+                .accept_response(extensions.to_str()?)
+                .with_context(|| format!("Connection to {ws_url} failed"))?,
+        ),
+        (Some(extensions), None) => anyhow::bail!(
+            "Connection to {ws_url} failed: server accepted extensions we didn't offer: {}",
+            extensions.to_str()?
+        ),
+        (None, _) => None,
+    };
+    if let Some(msg) = deprecation_message(headers) {
+        tracing::warn!("{msg}");
+    }
+
+    let upgraded = response
+        .upgrade()
+        .await
+        .with_context(|| format!("Connection to {ws_url} failed"))?;
+    let stream = DeflateStream::new(upgraded, params);
+    Ok(WebSocketStream::from_raw_socket(stream, Role::Client, None).await)
+}
+
 impl WebSocketInternal {
     async fn new(
         ws_url: Url,
+        compression: Option<DeflateConfig>,
         connection_count: u32,
         last_close_reason: String,
         max_observed_timestamp: Option<Timestamp>,
     ) -> anyhow::Result<WebSocketInternal> {
-        let mut request = (&ws_url).into_client_request().context("Bad WS Url")?;
-        let version = VERSION.unwrap_or("unknown");
-        request.headers_mut().insert(
-            "Convex-Client",
-            format!("rust-{version}")
-                .try_into()
-                .context("Bad version")?,
-        );
-        let (ws_stream, response) = connect_async(request).await.map_err(|e| {
-            if let tungstenite::Error::Http(ref response) = e {
-                let body = response
-                    .body()
-                    .as_deref()
-                    .map(String::from_utf8_lossy)
-                    .unwrap_or_default();
-                return anyhow::anyhow!("Connection to {ws_url} failed: {e}: {body}");
-            }
-            anyhow::anyhow!("Connection to {ws_url} failed: {e}")
-        })?;
-
-        if let Some(msg) = deprecation_message(response.headers()) {
-            tracing::warn!("{msg}");
-        }
+        let ws_stream = connect(&ws_url, compression).await?;
 
         let last_server_response = Instant::now();
         let mut internal = WebSocketInternal {
@@ -314,6 +398,7 @@ impl WebSocketInternal {
             connection_count,
             last_close_reason,
             max_observed_timestamp,
+            supports_query_diffs: true,
         };
         let msg = Message::Text(
             serde_json::Value::try_from(message)
@@ -0,0 +1,945 @@
+//! RFC 7692 `permessage-deflate` compression for the sync protocol's
+//! WebSocket.
+//!
+//! Our WebSocket library doesn't support extensions and rejects frames with
+//! reserved bits set, so [`DeflateStream`] sits between it and the upgraded
+//! connection instead. It rewrites frames as they pass through: incoming
+//! compressed messages are inflated and outgoing messages above the
+//! configured threshold are deflated, so the WebSocket library only ever sees
+//! uncompressed frames.
+//!
+//! This is experimental and off by default on both ends. Connections that
+//! didn't negotiate the extension are passed through without parsing frames
+//! at all.
+use std::{
+    cmp,
+    io,
+    pin::Pin,
+    task::{
+        ready,
+        Context,
+        Poll,
+    },
+};
+
+use flate2::{
+    Compress,
+    Compression,
+    Decompress,
+    FlushCompress,
+    FlushDecompress,
+};
+use tokio::io::{
+    AsyncRead,
+    AsyncWrite,
+    ReadBuf,
+};
+
+pub const SEC_WEBSOCKET_EXTENSIONS: &str = "Sec-WebSocket-Extensions";
+pub const PERMESSAGE_DEFLATE: &str = "permessage-deflate";
+
+/// zlib doesn't support raw deflate streams with a 256 byte window, so we
+/// decline offers that require one.
+pub const MIN_WINDOW_BITS: u8 = 9;
+pub const MAX_WINDOW_BITS: u8 = 15;
+
+/// Matches the WebSocket library's default limit on message size, which
+/// bounds how much we'll inflate a single message to.
+const MAX_MESSAGE_SIZE: usize = 64 << 20;
+/// Inflated messages are passed on in frames of at most this size, so they
+/// stay under the WebSocket library's frame size limit.
+const MAX_OUTPUT_FRAME_SIZE: usize = 1 << 20;
+const READ_CHUNK_SIZE: usize = 8192;
+const WRITE_BUFFER_LIMIT: usize = 1 << 20;
+
+/// Every sync-flushed deflate block ends with this, and RFC 7692 strips it
+/// from compressed messages.
+const DEFLATE_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+
+/// How one end of a connection compresses the messages it sends.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DeflateConfig {
+    /// Base-2 log of the LZ77 window used to compress outgoing messages,
+    /// between [`MIN_WINDOW_BITS`] and [`MAX_WINDOW_BITS`]. Smaller windows
+    /// use less memory on both ends but compress worse.
+    pub window_bits: u8,
+    /// Messages with smaller payloads than this are sent uncompressed.
+    pub threshold: usize,
+}
+
+impl Default for DeflateConfig {
+    fn default() -> Self {
+        Self {
+            window_bits: MAX_WINDOW_BITS,
+            threshold: 1024,
+        }
+    }
+}
+
+/// The negotiated extension parameters for one end of a connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DeflateParams {
+    /// Window bits for compressing outgoing messages.
+    pub window_bits: u8,
+    /// Whether to reset the compression context after every outgoing
+    /// message.
+    pub no_context_takeover: bool,
+    pub threshold: usize,
+}
+
+impl DeflateConfig {
+    /// The `Sec-WebSocket-Extensions` header a client sends to offer the
+    /// extension. We let the server pick our window size, but never ask it
+    /// to shrink its own since we always inflate with the largest window.
+    pub fn client_offer(&self) -> String {
+        format!("{PERMESSAGE_DEFLATE}; client_max_window_bits")
+    }
+
+    /// Picks the first acceptable offer from a client's
+    /// `Sec-WebSocket-Extensions` header, returning the header to respond
+    /// with and the server's parameters. Returns `None` if the client didn't
+    /// offer anything we support.
+    pub fn accept_offer(&self, header: &str) -> Option<(String, DeflateParams)> {
+        parse_extensions(header)
+            .into_iter()
+            .find_map(|(name, params)| self.accept_single_offer(name, params))
+    }
+
+    fn accept_single_offer(
+        &self,
+        name: &str,
+        params: Vec<(&str, Option<&str>)>,
+    ) -> Option<(String, DeflateParams)> {
+        if !name.eq_ignore_ascii_case(PERMESSAGE_DEFLATE) || has_duplicates(&params) {
+            return None;
+        }
+        let mut window_bits = self.window_bits;
+        let mut window_bits_offered = false;
+        let mut no_context_takeover = false;
+        for (param, value) in params {
+            match (param, value) {
+                ("server_no_context_takeover", None) => no_context_takeover = true,
+                ("client_no_context_takeover", None) => {},
+                ("server_max_window_bits", Some(value)) => {
+                    let bits = parse_window_bits(value)?;
+                    if bits < MIN_WINDOW_BITS {
+                        return None;
+                    }
+                    window_bits = cmp::min(window_bits, bits);
+                    window_bits_offered = true;
+                },
+                ("client_max_window_bits", value) => {
+                    if let Some(value) = value {
+                        parse_window_bits(value)?;
+                    }
+                },
+                _ => return None,
+            }
+        }
+        let mut response = PERMESSAGE_DEFLATE.to_string();
+        if no_context_takeover {
+            response.push_str("; server_no_context_takeover");
+        }
+        if window_bits_offered || window_bits < MAX_WINDOW_BITS {
+            response.push_str(&format!("; server_max_window_bits={window_bits}"));
+        }
+        let params = DeflateParams {
+            window_bits,
+            no_context_takeover,
+            threshold: self.threshold,
+        };
+        Some((response, params))
+    }
+
+    /// Parses the `Sec-WebSocket-Extensions` header a server accepted our
+    /// offer with into the client's parameters.
+    pub fn accept_response(&self, header: &str) -> anyhow::Result<DeflateParams> {
+        let mut extensions = parse_extensions(header);
+        anyhow::ensure!(
+            extensions.len() == 1,
+            "Server accepted unexpected extensions: {header}"
+        );
+        let (name, params) = extensions.remove(0);
+        anyhow::ensure!(
+            name.eq_ignore_ascii_case(PERMESSAGE_DEFLATE) && !has_duplicates(&params),
+            "Server accepted an invalid extension: {header}"
+        );
+        let mut window_bits = self.window_bits;
+        let mut no_context_takeover = false;
+        for (param, value) in params {
+            match (param, value) {
+                ("server_no_context_takeover", None) => {},
+                ("client_no_context_takeover", None) => no_context_takeover = true,
+                ("server_max_window_bits", Some(value)) if parse_window_bits(value).is_some() => {},
+                ("client_max_window_bits", Some(value)) => {
+                    let bits = parse_window_bits(value)
+                        .filter(|bits| *bits >= MIN_WINDOW_BITS)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Unsupported client_max_window_bits in {header}")
+                        })?;
+                    window_bits = cmp::min(window_bits, bits);
+                },
+                _ => anyhow::bail!("Server accepted an invalid extension: {header}"),
+            }
+        }
+        Ok(DeflateParams {
+            window_bits,
+            no_context_takeover,
+            threshold: self.threshold,
+        })
+    }
+}
+
+/// Splits a `Sec-WebSocket-Extensions` header into extensions and their
+/// parameters.
+fn parse_extensions(header: &str) -> Vec<(&str, Vec<(&str, Option<&str>)>)> {
+    header
+        .split(',')
+        .filter_map(|extension| {
+            let mut parts = extension.split(';').map(str::trim);
+            let name = parts.next().filter(|name| !name.is_empty())?;
+            let params = parts
+                .map(|param| match param.split_once('=') {
+                    Some((param, value)) => (param.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (param, None),
+                })
+                .collect();
+            Some((name, params))
+        })
+        .collect()
+}
+
+fn has_duplicates(params: &[(&str, Option<&str>)]) -> bool {
+    params
+        .iter()
+        .enumerate()
+        .any(|(i, (param, _))| params[..i].iter().any(|(other, _)| other == param))
+}
+
+fn parse_window_bits(value: &str) -> Option<u8> {
+    let bits: u8 = value.parse().ok()?;
+    (8..=MAX_WINDOW_BITS).contains(&bits).then_some(bits)
+}
+
+struct FrameHeader {
+    fin: bool,
+    rsv1: bool,
+    /// RSV2 and RSV3, which no extension we support uses.
+    other_rsv: bool,
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    header_len: usize,
+    payload_len: usize,
+}
+
+impl FrameHeader {
+    /// Parses the header at the start of `buf`, returning `None` if it's
+    /// incomplete.
+    fn parse(buf: &[u8]) -> io::Result<Option<Self>> {
+        let [first, second, ..] = *buf else {
+            return Ok(None);
+        };
+        let (payload_len, mut header_len) = match second & 0x7f {
+            126 => {
+                let Some(len) = buf.get(2..4) else {
+                    return Ok(None);
+                };
+                (u16::from_be_bytes(len.try_into().unwrap()) as u64, 4)
+            },
+            127 => {
+                let Some(len) = buf.get(2..10) else {
+                    return Ok(None);
+                };
+                (u64::from_be_bytes(len.try_into().unwrap()), 10)
+            },
+            len => (len as u64, 2),
+        };
+        let payload_len = usize::try_from(payload_len)
+            .ok()
+            .filter(|len| *len <= MAX_MESSAGE_SIZE)
+            .ok_or_else(|| invalid_data(format!("Frame of {payload_len} bytes is too large")))?;
+        let mask = if second & 0x80 != 0 {
+            let Some(mask) = buf.get(header_len..header_len + 4) else {
+                return Ok(None);
+            };
+            header_len += 4;
+            Some(mask.try_into().unwrap())
+        } else {
+            None
+        };
+        Ok(Some(Self {
+            fin: first & 0x80 != 0,
+            rsv1: first & 0x40 != 0,
+            other_rsv: first & 0x30 != 0,
+            opcode: first & 0x0f,
+            mask,
+            header_len,
+            payload_len,
+        }))
+    }
+
+    fn is_data(&self) -> bool {
+        self.opcode == OPCODE_TEXT || self.opcode == OPCODE_BINARY
+    }
+
+    fn is_control(&self) -> bool {
+        self.opcode & 0x08 != 0
+    }
+
+    /// Returns the frame's unmasked payload.
+    fn payload(&self, frame: &[u8]) -> Vec<u8> {
+        let mut payload = frame[self.header_len..].to_vec();
+        if let Some(mask) = self.mask {
+            apply_mask(&mut payload, mask);
+        }
+        payload
+    }
+}
+
+fn apply_mask(data: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
+
+fn write_frame(
+    out: &mut Vec<u8>,
+    fin: bool,
+    rsv1: bool,
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    payload: &[u8],
+) {
+    out.push(if fin { 0x80 } else { 0 } | if rsv1 { 0x40 } else { 0 } | opcode);
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    match payload.len() {
+        len @ 0..=125 => out.push(mask_bit | len as u8),
+        len @ 126..=0xffff => {
+            out.push(mask_bit | 126);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        },
+        len => {
+            out.push(mask_bit | 127);
+            out.extend_from_slice(&(len as u64).to_be_bytes());
+        },
+    }
+    let start = out.len();
+    out.extend_from_slice(payload);
+    if let Some(mask) = mask {
+        out.extend_from_slice(&mask);
+        out[start..].rotate_right(4);
+        apply_mask(&mut out[start + 4..], mask);
+    }
+}
+
+/// Writes an uncompressed message, split into frames the WebSocket library
+/// will accept.
+fn write_message(out: &mut Vec<u8>, opcode: u8, mask: Option<[u8; 4]>, payload: &[u8]) {
+    let num_frames = cmp::max(payload.len().div_ceil(MAX_OUTPUT_FRAME_SIZE), 1);
+    for i in 0..num_frames {
+        let start = i * MAX_OUTPUT_FRAME_SIZE;
+        let end = cmp::min(start + MAX_OUTPUT_FRAME_SIZE, payload.len());
+        let opcode = if i == 0 { opcode } else { OPCODE_CONTINUATION };
+        write_frame(
+            out,
+            i == num_frames - 1,
+            false,
+            opcode,
+            mask,
+            &payload[start..end],
+        );
+    }
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A compressed message received over several frames.
+struct PartialMessage {
+    opcode: u8,
+    mask: Option<[u8; 4]>,
+    payload: Vec<u8>,
+}
+
+/// Compression state for a connection that negotiated the extension.
+struct Compressor {
+    params: DeflateParams,
+    compress: Compress,
+    decompress: Decompress,
+}
+
+impl Compressor {
+    fn new(params: DeflateParams) -> Self {
+        Self {
+            params,
+            compress: Compress::new_with_window_bits(
+                Compression::default(),
+                false,
+                params.window_bits,
+            ),
+            // The peer's window is at most this large, and inflating with a
+            // larger window than the peer compressed with is fine.
+            decompress: Decompress::new_with_window_bits(false, MAX_WINDOW_BITS),
+        }
+    }
+
+    fn deflate(&mut self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(payload.len() / 2 + 64);
+        let mut consumed = 0;
+        loop {
+            if output.capacity() - output.len() < 64 {
+                output.reserve(cmp::max(output.capacity(), 1024));
+            }
+            let total_in = self.compress.total_in();
+            self.compress
+                .compress_vec(&payload[consumed..], &mut output, FlushCompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            consumed += (self.compress.total_in() - total_in) as usize;
+            if consumed == payload.len() && output.len() < output.capacity() {
+                break;
+            }
+        }
+        if !output.ends_with(&DEFLATE_TRAILER) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Sync flush didn't end with an empty block",
+            ));
+        }
+        output.truncate(output.len() - DEFLATE_TRAILER.len());
+        if self.params.no_context_takeover {
+            self.compress.reset();
+        }
+        Ok(output)
+    }
+
+    fn inflate(&mut self, mut payload: Vec<u8>) -> io::Result<Vec<u8>> {
+        payload.extend_from_slice(&DEFLATE_TRAILER);
+        let mut output = Vec::with_capacity(payload.len() * 4);
+        let mut consumed = 0;
+        loop {
+            if output.capacity() - output.len() < READ_CHUNK_SIZE {
+                output.reserve(cmp::max(output.capacity(), READ_CHUNK_SIZE));
+            }
+            let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+            self.decompress
+                .decompress_vec(&payload[consumed..], &mut output, FlushDecompress::Sync)
+                .map_err(|e| invalid_data(format!("Invalid compressed message: {e}")))?;
+            consumed += (self.decompress.total_in() - total_in) as usize;
+            if output.len() > MAX_MESSAGE_SIZE {
+                return Err(invalid_data("Inflated message is too large".to_string()));
+            }
+            if consumed == payload.len() && output.len() < output.capacity() {
+                return Ok(output);
+            }
+            if self.decompress.total_in() == total_in && self.decompress.total_out() == total_out {
+                return Err(invalid_data(
+                    "Compressed message has data after its end".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+struct Codec {
+    compressor: Compressor,
+    partial: Option<PartialMessage>,
+    /// Whether we're in the middle of sending a fragmented message, which we
+    /// pass through uncompressed.
+    sending_fragmented: bool,
+    /// Called with the payload size before and after compression for every
+    /// outgoing data frame.
+    observer: Option<fn(usize, usize)>,
+}
+
+impl Codec {
+    fn new(params: DeflateParams) -> Self {
+        Self {
+            compressor: Compressor::new(params),
+            partial: None,
+            sending_fragmented: false,
+            observer: None,
+        }
+    }
+
+    /// Removes the first complete frame from `buf`, returning it with its
+    /// header.
+    fn next_frame(buf: &mut Vec<u8>) -> io::Result<Option<(FrameHeader, Vec<u8>)>> {
+        let Some(header) = FrameHeader::parse(buf)? else {
+            return Ok(None);
+        };
+        let frame_len = header.header_len + header.payload_len;
+        if buf.len() < frame_len {
+            return Ok(None);
+        }
+        let frame = buf.drain(..frame_len).collect();
+        Ok(Some((header, frame)))
+    }
+
+    /// Inflates the next complete frame in `input` into `output`, returning
+    /// whether there was one.
+    fn decode(&mut self, input: &mut Vec<u8>, output: &mut Vec<u8>) -> io::Result<bool> {
+        let Some((header, frame)) = Self::next_frame(input)? else {
+            return Ok(false);
+        };
+        if header.other_rsv || header.is_control() {
+            output.extend_from_slice(&frame);
+            return Ok(true);
+        }
+        if header.is_data() && header.rsv1 {
+            if self.partial.is_some() {
+                return Err(invalid_data(
+                    "New message before the previous one finished".to_string(),
+                ));
+            }
+            self.partial = Some(PartialMessage {
+                opcode: header.opcode,
+                mask: header.mask,
+                payload: vec![],
+            });
+        }
+        let Some(partial) = &mut self.partial else {
+            // An uncompressed message.
+            output.extend_from_slice(&frame);
+            return Ok(true);
+        };
+        if header.opcode == OPCODE_CONTINUATION && header.rsv1 {
+            return Err(invalid_data("RSV1 set on a continuation frame".to_string()));
+        }
+        if header.opcode != OPCODE_CONTINUATION && !header.rsv1 {
+            return Err(invalid_data(
+                "New message before the previous one finished".to_string(),
+            ));
+        }
+        partial.payload.extend(header.payload(&frame));
+        if partial.payload.len() > MAX_MESSAGE_SIZE {
+            return Err(invalid_data("Compressed message is too large".to_string()));
+        }
+        if header.fin {
+            let partial = self.partial.take().unwrap();
+            let payload = self.compressor.inflate(partial.payload)?;
+            write_message(output, partial.opcode, partial.mask, &payload);
+        }
+        Ok(true)
+    }
+
+    /// Deflates the next complete frame in `input` into `output`, returning
+    /// whether there was one.
+    fn encode(&mut self, input: &mut Vec<u8>, output: &mut Vec<u8>) -> io::Result<bool> {
+        let Some((header, frame)) = Self::next_frame(input)? else {
+            return Ok(false);
+        };
+        let compress = header.is_data()
+            && header.fin
+            && !header.rsv1
+            && !header.other_rsv
+            && header.payload_len >= self.compressor.params.threshold;
+        if compress && !self.sending_fragmented {
+            let payload = header.payload(&frame);
+            let compressed = self.compressor.deflate(&payload)?;
+            if let Some(observer) = self.observer {
+                observer(payload.len(), compressed.len());
+            }
+            write_frame(output, true, true, header.opcode, header.mask, &compressed);
+        } else {
+            if !header.is_control() {
+                if let Some(observer) = self.observer {
+                    observer(header.payload_len, header.payload_len);
+                }
+                self.sending_fragmented = !header.fin;
+            }
+            output.extend_from_slice(&frame);
+        }
+        Ok(true)
+    }
+}
+
+/// Wraps an upgraded WebSocket connection, applying `permessage-deflate` to
+/// the frames passing through it. Without negotiated parameters, reads and
+/// writes go straight to the connection.
+pub struct DeflateStream<S> {
+    inner: S,
+    /// `None` if the connection didn't negotiate compression.
+    codec: Option<Codec>,
+    /// Bytes read from `inner` that don't form a complete frame yet.
+    read_buf: Vec<u8>,
+    /// Decoded bytes waiting to be read.
+    read_output: Vec<u8>,
+    read_eof: bool,
+    /// Bytes written to us that don't form a complete frame yet.
+    write_buf: Vec<u8>,
+    /// Encoded bytes waiting to be written to `inner`.
+    write_output: Vec<u8>,
+}
+
+impl<S> DeflateStream<S> {
+    pub fn new(inner: S, params: Option<DeflateParams>) -> Self {
+        Self {
+            inner,
+            codec: params.map(Codec::new),
+            read_buf: vec![],
+            read_output: vec![],
+            read_eof: false,
+            write_buf: vec![],
+            write_output: vec![],
+        }
+    }
+
+    /// Calls `observer` with the payload size before and after compression
+    /// for every data frame sent, if the connection is compressed.
+    pub fn with_observer(mut self, observer: fn(usize, usize)) -> Self {
+        if let Some(codec) = &mut self.codec {
+            codec.observer = Some(observer);
+        }
+        self
+    }
+
+    pub fn params(&self) -> Option<DeflateParams> {
+        self.codec.as_ref().map(|codec| codec.compressor.params)
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for DeflateStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let Some(codec) = &mut this.codec else {
+            return Pin::new(&mut this.inner).poll_read(cx, buf);
+        };
+        loop {
+            if !this.read_output.is_empty() {
+                let n = cmp::min(buf.remaining(), this.read_output.len());
+                buf.put_slice(&this.read_output[..n]);
+                this.read_output.drain(..n);
+                return Poll::Ready(Ok(()));
+            }
+            if codec.decode(&mut this.read_buf, &mut this.read_output)? {
+                continue;
+            }
+            if this.read_eof {
+                // Pass on any truncated frame so the WebSocket library sees
+                // the connection end where it did.
+                if this.read_buf.is_empty() {
+                    return Poll::Ready(Ok(()));
+                }
+                this.read_output.append(&mut this.read_buf);
+                continue;
+            }
+            let mut chunk = [0; READ_CHUNK_SIZE];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf))?;
+            if chunk_buf.filled().is_empty() {
+                this.read_eof = true;
+            } else {
+                this.read_buf.extend_from_slice(chunk_buf.filled());
+            }
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> DeflateStream<S> {
+    fn poll_write_output(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_output.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.write_output))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_output.drain(..n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for DeflateStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.codec.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, data);
+        }
+        if this.write_output.len() >= WRITE_BUFFER_LIMIT {
+            ready!(this.poll_write_output(cx))?;
+        }
+        this.write_buf.extend_from_slice(data);
+        let codec = this.codec.as_mut().unwrap();
+        while codec.encode(&mut this.write_buf, &mut this.write_output)? {}
+        Poll::Ready(Ok(data.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_output(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.write_output.append(&mut this.write_buf);
+        ready!(this.poll_write_output(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use tokio::io::{
+        AsyncReadExt,
+        AsyncWriteExt,
+    };
+
+    use super::{
+        write_frame,
+        Codec,
+        Compressor,
+        DeflateConfig,
+        DeflateParams,
+        DeflateStream,
+        FrameHeader,
+        MAX_WINDOW_BITS,
+        MIN_WINDOW_BITS,
+        OPCODE_CONTINUATION,
+        OPCODE_TEXT,
+    };
+
+    fn params(threshold: usize) -> DeflateParams {
+        DeflateParams {
+            window_bits: MAX_WINDOW_BITS,
+            no_context_takeover: false,
+            threshold,
+        }
+    }
+
+    fn text_frame(mask: Option<[u8; 4]>, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![];
+        write_frame(&mut frame, true, false, OPCODE_TEXT, mask, payload);
+        frame
+    }
+
+    #[test]
+    fn test_accept_offer() {
+        let config = DeflateConfig::default();
+        let (response, params) = config
+            .accept_offer("permessage-deflate; client_max_window_bits")
+            .unwrap();
+        assert_eq!(response, "permessage-deflate");
+        assert_eq!(params.window_bits, MAX_WINDOW_BITS);
+        assert!(!params.no_context_takeover);
+
+        let (response, params) = config
+            .accept_offer(
+                "permessage-deflate; server_max_window_bits=8, permessage-deflate; \
+                 server_max_window_bits=\"10\"; server_no_context_takeover",
+            )
+            .unwrap();
+        assert_eq!(
+            response,
+            "permessage-deflate; server_no_context_takeover; server_max_window_bits=10"
+        );
+        assert_eq!(params.window_bits, 10);
+        assert!(params.no_context_takeover);
+
+        assert_eq!(config.accept_offer("x-webkit-deflate-frame"), None);
+        assert_eq!(config.accept_offer("permessage-deflate; unknown"), None);
+        assert_eq!(
+            config.accept_offer("permessage-deflate; client_max_window_bits=16"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_accept_response() -> anyhow::Result<()> {
+        let config = DeflateConfig::default();
+        let (response, _) = config.accept_offer(&config.client_offer()).unwrap();
+        assert_eq!(
+            config.accept_response(&response)?.window_bits,
+            MAX_WINDOW_BITS
+        );
+
+        let params = config.accept_response(
+            "permessage-deflate; client_max_window_bits=11; client_no_context_takeover",
+        )?;
+        assert_eq!(params.window_bits, 11);
+        assert!(params.no_context_takeover);
+
+        assert!(config.accept_response("permessage-deflate; bogus").is_err());
+        assert!(config
+            .accept_response("permessage-deflate, permessage-deflate")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_roundtrip() -> anyhow::Result<()> {
+        let mut sender = Codec::new(params(16));
+        let mut receiver = Codec::new(params(16));
+        let mask = Some([1, 2, 3, 4]);
+        // Send the same message twice so the second one refers back to the
+        // first through the shared compression context.
+        let payload = "the quick brown fox jumps over the lazy dog ".repeat(20);
+        for _ in 0..2 {
+            let frame = text_frame(mask, payload.as_bytes());
+            let mut input = frame.clone();
+            let mut compressed = vec![];
+            assert!(sender.encode(&mut input, &mut compressed)?);
+            assert!(input.is_empty());
+            assert!(compressed.len() < frame.len());
+            assert_eq!(compressed[0] & 0x40, 0x40);
+
+            let mut decoded = vec![];
+            assert!(receiver.decode(&mut compressed, &mut decoded)?);
+            assert_eq!(decoded, frame);
+        }
+
+        // Small messages are sent as is.
+        let frame = text_frame(mask, b"hi");
+        let mut output = vec![];
+        sender.encode(&mut frame.clone(), &mut output)?;
+        assert_eq!(output, frame);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_fragmented_message() -> anyhow::Result<()> {
+        let mut receiver = Codec::new(params(0));
+        let payload = "abcabcabcabc".repeat(10);
+        let compressed = Compressor::new(params(0)).deflate(payload.as_bytes())?;
+        let (first, second) = compressed.split_at(compressed.len() / 2);
+
+        let mut input = vec![];
+        write_frame(&mut input, false, true, OPCODE_TEXT, None, first);
+        // Control frames may be interleaved with a fragmented message.
+        write_frame(&mut input, true, false, 0x9, None, b"ping");
+        write_frame(&mut input, true, false, OPCODE_CONTINUATION, None, second);
+
+        let mut output = vec![];
+        while receiver.decode(&mut input, &mut output)? {}
+        let mut expected = vec![];
+        write_frame(&mut expected, true, false, 0x9, None, b"ping");
+        expected.extend(text_frame(None, payload.as_bytes()));
+        assert_eq!(output, expected);
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+
+        #[test]
+        fn proptest_frame_header_roundtrips(
+            fin in any::<bool>(),
+            rsv1 in any::<bool>(),
+            opcode in 0..16u8,
+            mask in any::<Option<[u8; 4]>>(),
+            payload in prop::collection::vec(any::<u8>(), 0..70000),
+        ) {
+            let mut frame = vec![];
+            write_frame(&mut frame, fin, rsv1, opcode, mask, &payload);
+            let header = FrameHeader::parse(&frame).unwrap().unwrap();
+            assert_eq!(header.fin, fin);
+            assert_eq!(header.rsv1, rsv1);
+            assert!(!header.other_rsv);
+            assert_eq!(header.opcode, opcode);
+            assert_eq!(header.mask, mask);
+            assert_eq!(header.header_len + header.payload_len, frame.len());
+            assert_eq!(header.payload(&frame), payload);
+            // A truncated header is incomplete rather than invalid.
+            for len in 0..header.header_len {
+                assert!(FrameHeader::parse(&frame[..len]).unwrap().is_none());
+            }
+        }
+
+        #[test]
+        fn proptest_decode_arbitrary_bytes(
+            mut input in prop::collection::vec(any::<u8>(), 0..4096),
+        ) {
+            // Whatever the peer sends is either decoded or rejected.
+            let mut receiver = Codec::new(params(0));
+            let mut output = vec![];
+            while let Ok(true) = receiver.decode(&mut input, &mut output) {}
+        }
+
+        #[test]
+        fn proptest_codec_roundtrips(
+            messages in prop::collection::vec(
+                (any::<Option<[u8; 4]>>(), prop::collection::vec(any::<u8>(), 0..2048)),
+                1..8,
+            ),
+            window_bits in MIN_WINDOW_BITS..=MAX_WINDOW_BITS,
+            no_context_takeover in any::<bool>(),
+            threshold in 0..1024usize,
+        ) {
+            let params = DeflateParams {
+                window_bits,
+                no_context_takeover,
+                threshold,
+            };
+            let mut sender = Codec::new(params);
+            let mut receiver = Codec::new(params);
+            for (mask, payload) in messages {
+                let frame = text_frame(mask, &payload);
+                let mut input = frame.clone();
+                let mut encoded = vec![];
+                assert!(sender.encode(&mut input, &mut encoded).unwrap());
+                assert!(input.is_empty());
+                let mut decoded = vec![];
+                while receiver.decode(&mut encoded, &mut decoded).unwrap() {}
+                assert!(encoded.is_empty());
+                assert_eq!(decoded, frame);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_roundtrip() -> anyhow::Result<()> {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = DeflateStream::new(client, Some(params(0)));
+        let mut server = DeflateStream::new(server, Some(params(0)));
+
+        let frame = text_frame(Some([9, 8, 7, 6]), "hello ".repeat(100).as_bytes());
+        let expected = frame.clone();
+        let write = tokio::spawn(async move {
+            client.write_all(&frame).await?;
+            client.flush().await?;
+            client.shutdown().await?;
+            anyhow::Ok(())
+        });
+        let mut received = vec![];
+        server.read_to_end(&mut received).await?;
+        write.await??;
+        assert_eq!(received, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_passthrough() -> anyhow::Result<()> {
+        // Without compression, bytes pass through untouched, even if they
+        // aren't valid frames.
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = DeflateStream::new(client, None);
+        let mut server = DeflateStream::new(server, None);
+        let bytes = vec![0xff; 1000];
+        let expected = bytes.clone();
+        let write = tokio::spawn(async move {
+            client.write_all(&bytes).await?;
+            client.shutdown().await?;
+            anyhow::Ok(())
+        });
+        let mut received = vec![];
+        server.read_to_end(&mut received).await?;
+        write.await??;
+        assert_eq!(received, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+//! Structural diffs between query results, which let the sync protocol send
+//! only what changed in a query's result instead of the whole value.
+//!
+//! Diffs are computed between the JSON encodings of two values, so they're
+//! independent of the value type used on either end of the protocol. The
+//! server sends a [`value_hash`] of the new value alongside each diff, which
+//! the client checks after applying it.
+use std::{
+    collections::BTreeMap,
+    mem,
+};
+
+use anyhow::Context;
+use serde_json::{
+    json,
+    Map as JsonMap,
+    Value as JsonValue,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValueDiff {
+    /// The value didn't change.
+    Unchanged,
+    /// Replace the value entirely.
+    Replace(JsonValue),
+    /// Patch an object, applying the diffs in `set` to its fields (adding
+    /// fields that are missing) and removing the fields in `remove`.
+    Object {
+        set: BTreeMap<String, ValueDiff>,
+        remove: Vec<String>,
+    },
+    /// Patch an array, keeping its first `prefix` and last `suffix` elements
+    /// and replacing the elements in between with `items`. Item diffs other
+    /// than `Replace` patch the old element at the same offset after the
+    /// prefix.
+    Array {
+        prefix: usize,
+        suffix: usize,
+        items: Vec<ValueDiff>,
+    },
+}
+
+impl ValueDiff {
+    /// Computes a diff that turns `old` into `new`.
+    pub fn new(old: &JsonValue, new: &JsonValue) -> Self {
+        if old == new {
+            return ValueDiff::Unchanged;
+        }
+        match (old, new) {
+            (JsonValue::Object(old), JsonValue::Object(new)) => {
+                let set = new
+                    .iter()
+                    .filter_map(|(field, new_value)| {
+                        let diff = match old.get(field) {
+                            Some(old_value) => ValueDiff::new(old_value, new_value),
+                            None => ValueDiff::Replace(new_value.clone()),
+                        };
+                        (diff != ValueDiff::Unchanged).then(|| (field.clone(), diff))
+                    })
+                    .collect();
+                let remove = old
+                    .keys()
+                    .filter(|field| !new.contains_key(*field))
+                    .cloned()
+                    .collect();
+                ValueDiff::Object { set, remove }
+            },
+            (JsonValue::Array(old), JsonValue::Array(new)) => {
+                let prefix = old
+                    .iter()
+                    .zip(new.iter())
+                    .take_while(|(old, new)| old == new)
+                    .count();
+                let max_suffix = old.len().min(new.len()) - prefix;
+                let suffix = old
+                    .iter()
+                    .rev()
+                    .zip(new.iter().rev())
+                    .take(max_suffix)
+                    .take_while(|(old, new)| old == new)
+                    .count();
+                let old_items = &old[prefix..old.len() - suffix];
+                let new_items = &new[prefix..new.len() - suffix];
+                // Only patch elements in place if none were inserted or removed,
+                // since otherwise they no longer line up.
+                let items = if old_items.len() == new_items.len() {
+                    old_items
+                        .iter()
+                        .zip(new_items)
+                        .map(|(old, new)| ValueDiff::new(old, new))
+                        .collect()
+                } else {
+                    new_items.iter().cloned().map(ValueDiff::Replace).collect()
+                };
+                ValueDiff::Array {
+                    prefix,
+                    suffix,
+                    items,
+                }
+            },
+            _ => ValueDiff::Replace(new.clone()),
+        }
+    }
+
+    /// Returns a diff from `old` to `new` if sending it is cheaper than
+    /// sending `new` itself.
+    pub fn new_if_smaller(old: &JsonValue, new: &JsonValue) -> Option<Self> {
+        let diff = ValueDiff::new(old, new);
+        if matches!(diff, ValueDiff::Replace(_)) {
+            return None;
+        }
+        let diff_len = JsonValue::from(diff.clone()).to_string().len();
+        (diff_len < new.to_string().len()).then_some(diff)
+    }
+
+    /// Applies the diff to `base`, returning the new value.
+    pub fn apply(self, base: JsonValue) -> anyhow::Result<JsonValue> {
+        let result = match (self, base) {
+            (ValueDiff::Unchanged, base) => base,
+            (ValueDiff::Replace(value), _) => value,
+            (ValueDiff::Object { set, remove }, JsonValue::Object(mut object)) => {
+                for field in remove {
+                    object
+                        .remove(&field)
+                        .with_context(|| format!("Removing missing field {field}"))?;
+                }
+                for (field, diff) in set {
+                    match object.get_mut(&field) {
+                        Some(value) => *value = diff.apply(mem::take(value))?,
+                        None => {
+                            let ValueDiff::Replace(value) = diff else {
+                                anyhow::bail!("Patching missing field {field}");
+                            };
+                            object.insert(field, value);
+                        },
+                    }
+                }
+                JsonValue::Object(object)
+            },
+            (
+                ValueDiff::Array {
+                    prefix,
+                    suffix,
+                    items,
+                },
+                JsonValue::Array(mut array),
+            ) => {
+                anyhow::ensure!(
+                    prefix + suffix <= array.len(),
+                    "Array of length {} is too short to keep {prefix} + {suffix} elements",
+                    array.len()
+                );
+                let suffix_items = array.split_off(array.len() - suffix);
+                let mut old_items = array.split_off(prefix).into_iter();
+                for diff in items {
+                    let item = match (diff, old_items.next()) {
+                        (ValueDiff::Replace(value), _) => value,
+                        (diff, Some(old_item)) => diff.apply(old_item)?,
+                        (_, None) => anyhow::bail!("Patching missing array element"),
+                    };
+                    array.push(item);
+                }
+                array.extend(suffix_items);
+                JsonValue::Array(array)
+            },
+            (diff, base) => anyhow::bail!("Can't apply {diff:?} to {base}"),
+        };
+        Ok(result)
+    }
+}
+
+impl From<ValueDiff> for JsonValue {
+    fn from(diff: ValueDiff) -> Self {
+        match diff {
+            ValueDiff::Unchanged => json!({}),
+            ValueDiff::Replace(value) => json!({ "v": value }),
+            ValueDiff::Object { set, remove } => {
+                let set: JsonMap<_, _> = set
+                    .into_iter()
+                    .map(|(field, diff)| (field, JsonValue::from(diff)))
+                    .collect();
+                let mut result = json!({ "o": set });
+                if !remove.is_empty() {
+                    result["r"] = json!(remove);
+                }
+                result
+            },
+            ValueDiff::Array {
+                prefix,
+                suffix,
+                items,
+            } => json!({
+                "a": items.into_iter().map(JsonValue::from).collect::<Vec<_>>(),
+                "p": prefix,
+                "s": suffix,
+            }),
+        }
+    }
+}
+
+impl TryFrom<JsonValue> for ValueDiff {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonValue) -> anyhow::Result<Self> {
+        let JsonValue::Object(mut fields) = value else {
+            anyhow::bail!("Value diff must be an object");
+        };
+        let diff = if let Some(value) = fields.remove("v") {
+            ValueDiff::Replace(value)
+        } else if let Some(set) = fields.remove("o") {
+            let JsonValue::Object(set) = set else {
+                anyhow::bail!("Object diff fields must be an object");
+            };
+            ValueDiff::Object {
+                set: set
+                    .into_iter()
+                    .map(|(field, diff)| Ok((field, diff.try_into()?)))
+                    .collect::<anyhow::Result<_>>()?,
+                remove: match fields.remove("r") {
+                    Some(remove) => serde_json::from_value(remove)?,
+                    None => vec![],
+                },
+            }
+        } else if let Some(items) = fields.remove("a") {
+            let items: Vec<JsonValue> = serde_json::from_value(items)?;
+            ValueDiff::Array {
+                prefix: serde_json::from_value(fields.remove("p").context("Missing prefix")?)?,
+                suffix: serde_json::from_value(fields.remove("s").context("Missing suffix")?)?,
+                items: items
+                    .into_iter()
+                    .map(ValueDiff::try_from)
+                    .collect::<anyhow::Result<_>>()?,
+            }
+        } else {
+            ValueDiff::Unchanged
+        };
+        anyhow::ensure!(
+            fields.is_empty(),
+            "Unexpected fields in value diff: {:?}",
+            fields.keys().collect::<Vec<_>>()
+        );
+        Ok(diff)
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl proptest::arbitrary::Arbitrary for ValueDiff {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+        (crate::testing::arb_json(), crate::testing::arb_json())
+            .prop_map(|(old, new)| ValueDiff::new(&old, &new))
+            .boxed()
+    }
+}
+
+/// Hashes a JSON value independently of its objects' field order, so the
+/// client can check that applying a diff produced the server's value.
+pub fn value_hash(value: &JsonValue) -> String {
+    let mut hasher = Sha256::new();
+    hash_value(&mut hasher, value);
+    base64::encode(hasher.finalize())
+}
+
+fn hash_value(hasher: &mut Sha256, value: &JsonValue) {
+    match value {
+        JsonValue::Null => hasher.update([0]),
+        JsonValue::Bool(b) => hasher.update([1, *b as u8]),
+        JsonValue::Number(n) => {
+            hasher.update([2]);
+            hash_str(hasher, &n.to_string());
+        },
+        JsonValue::String(s) => {
+            hasher.update([3]);
+            hash_str(hasher, s);
+        },
+        JsonValue::Array(array) => {
+            hasher.update([4]);
+            hasher.update((array.len() as u64).to_le_bytes());
+            for item in array {
+                hash_value(hasher, item);
+            }
+        },
+        JsonValue::Object(object) => {
+            hasher.update([5]);
+            hasher.update((object.len() as u64).to_le_bytes());
+            let sorted: BTreeMap<_, _> = object.iter().collect();
+            for (field, value) in sorted {
+                hash_str(hasher, field);
+                hash_value(hasher, value);
+            }
+        },
+    }
+}
+
+fn hash_str(hasher: &mut Sha256, s: &str) {
+    // Write the length first so strings sharing a prefix don't collide.
+    hasher.update((s.len() as u64).to_le_bytes());
+    hasher.update(s.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use super::{
+        value_hash,
+        ValueDiff,
+    };
+    use crate::testing::{
+        arb_json,
+        assert_roundtrips,
+    };
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+
+        #[test]
+        fn proptest_diff_applies(old in arb_json(), new in arb_json()) {
+            let diff = ValueDiff::new(&old, &new);
+            let applied = diff.apply(old).unwrap();
+            assert_eq!(value_hash(&applied), value_hash(&new));
+            assert_eq!(applied, new);
+        }
+
+        #[test]
+        fn proptest_diff_roundtrips(diff in any::<ValueDiff>()) {
+            assert_roundtrips::<ValueDiff, JsonValue>(diff);
+        }
+    }
+
+    #[test]
+    fn test_diff_single_array_element() -> anyhow::Result<()> {
+        let old: Vec<_> = (0..1000).map(|i| json!({ "_id": i, "count": 0 })).collect();
+        let mut new = old.clone();
+        new[500] = json!({ "_id": 500, "count": 1 });
+        let (old, new) = (JsonValue::Array(old), JsonValue::Array(new));
+        let diff = ValueDiff::new_if_smaller(&old, &new).unwrap();
+        assert_eq!(
+            JsonValue::from(diff.clone()),
+            json!({ "a": [{ "o": { "count": { "v": 1 } } }], "p": 500, "s": 499 })
+        );
+        assert_eq!(diff.apply(old)?, new);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_insert_at_front() -> anyhow::Result<()> {
+        let old = json!([{ "_id": 1 }, { "_id": 2 }, { "_id": 3 }]);
+        let new = json!([{ "_id": 0 }, { "_id": 1 }, { "_id": 2 }, { "_id": 3 }]);
+        let diff = ValueDiff::new(&old, &new);
+        assert_eq!(
+            diff,
+            ValueDiff::Array {
+                prefix: 0,
+                suffix: 3,
+                items: vec![ValueDiff::Replace(json!({ "_id": 0 }))],
+            }
+        );
+        assert_eq!(diff.apply(old)?, new);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_larger_than_value() {
+        assert_eq!(ValueDiff::new_if_smaller(&json!([1]), &json!([2])), None);
+        assert_eq!(ValueDiff::new_if_smaller(&json!(1), &json!("a")), None);
+    }
+
+    #[test]
+    fn test_value_hash_ignores_field_order() {
+        assert_eq!(
+            value_hash(&json!({ "a": 1, "b": [true, null] })),
+            value_hash(&json!({ "b": [true, null], "a": 1 })),
+        );
+        assert_ne!(
+            value_hash(&json!(["ab", "c"])),
+            value_hash(&json!(["a", "bc"]))
+        );
+    }
+}
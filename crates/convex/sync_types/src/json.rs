@@ -163,6 +163,10 @@ enum ClientMessageJson {
         #[serde(default)]
         #[serde(skip_serializing_if = "Option::is_none")]
         max_observed_timestamp: Option<String>,
+
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        supports_query_diffs: Option<bool>,
     },
     #[serde(rename_all = "camelCase")]
     ModifyQuerySet {
@@ -205,11 +209,13 @@ impl TryFrom<ClientMessage> for JsonValue {
                 connection_count,
                 last_close_reason,
                 max_observed_timestamp,
+                supports_query_diffs,
             } => ClientMessageJson::Connect {
                 session_id: format!("{}", session_id.as_hyphenated()),
                 connection_count,
                 last_close_reason: Some(last_close_reason),
                 max_observed_timestamp: max_observed_timestamp.map(|ts| u64_to_string(ts.into())),
+                supports_query_diffs: supports_query_diffs.then_some(true),
             },
             ClientMessage::ModifyQuerySet {
                 base_version,
@@ -285,6 +291,7 @@ impl TryFrom<JsonValue> for ClientMessage {
                 connection_count,
                 last_close_reason,
                 max_observed_timestamp,
+                supports_query_diffs,
             } => ClientMessage::Connect {
                 session_id: session_id.parse()?,
                 connection_count,
@@ -294,6 +301,7 @@ impl TryFrom<JsonValue> for ClientMessage {
                     .transpose()?
                     .map(Timestamp::try_from)
                     .transpose()?,
+                supports_query_diffs: supports_query_diffs.unwrap_or(false),
             },
             ClientMessageJson::ModifyQuerySet {
                 base_version,
@@ -403,6 +411,20 @@ impl<V: Into<JsonValue>> From<StateModification<V>> for JsonValue {
                     "journal": journal
                 })
             },
+            StateModification::QueryDiffed {
+                query_id,
+                diff,
+                value_hash,
+                log_lines,
+                journal,
+            } => json!({
+                "type": "QueryDiffed",
+                "queryId": query_id,
+                "diff": JsonValue::from(diff),
+                "valueHash": value_hash,
+                "logLines": log_lines,
+                "journal": journal
+            }),
             StateModification::QueryFailed {
                 query_id,
                 error_message,
@@ -447,6 +469,14 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 journal: SerializedQueryJournal,
             },
             #[serde(rename_all = "camelCase")]
+            QueryDiffed {
+                query_id: QueryId,
+                diff: JsonValue,
+                value_hash: String,
+                log_lines: LogLinesMessage,
+                journal: SerializedQueryJournal,
+            },
+            #[serde(rename_all = "camelCase")]
             QueryFailed {
                 query_id: QueryId,
                 error_message: String,
@@ -471,6 +501,19 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 log_lines,
                 journal,
             },
+            StateModificationJson::QueryDiffed {
+                query_id,
+                diff,
+                value_hash,
+                log_lines,
+                journal,
+            } => StateModification::QueryDiffed {
+                query_id,
+                diff: diff.try_into()?,
+                value_hash,
+                log_lines,
+                journal,
+            },
             StateModificationJson::QueryFailed {
                 query_id,
                 error_message,
@@ -1,4 +1,6 @@
 pub mod backoff;
+pub mod deflate;
+pub mod diff;
 pub mod headers;
 pub mod identifier;
 pub mod json;
@@ -10,6 +12,7 @@ pub mod types;
 pub mod udf_path;
 
 pub use crate::{
+    diff::ValueDiff,
     module_path::{
         CanonicalizedModulePath,
         ModulePath,
@@ -17,6 +17,7 @@ use serde_json::Value as JsonValue;
 use uuid::Uuid;
 
 use crate::{
+    diff::ValueDiff,
     Timestamp,
     UdfPath,
 };
@@ -91,6 +92,8 @@ pub enum ClientMessage {
         connection_count: u32,
         last_close_reason: String,
         max_observed_timestamp: Option<Timestamp>,
+        /// Whether the client can apply `StateModification::QueryDiffed`.
+        supports_query_diffs: bool,
     },
     ModifyQuerySet {
         base_version: QuerySetVersion,
@@ -237,6 +240,17 @@ pub enum StateModification<V> {
         log_lines: LogLinesMessage,
         journal: SerializedQueryJournal,
     },
+    /// Sent instead of `QueryUpdated` to clients that support query diffs,
+    /// when diffing against the last value sent for the query is smaller than
+    /// the new value.
+    QueryDiffed {
+        query_id: QueryId,
+        diff: ValueDiff,
+        /// `diff::value_hash` of the new value.
+        value_hash: String,
+        log_lines: LogLinesMessage,
+        journal: SerializedQueryJournal,
+    },
     QueryFailed {
         query_id: QueryId,
         error_message: String,
@@ -112,7 +112,11 @@ impl<'a, RT: Runtime> ImportFacingModel<'a, RT> {
         SchemaModel::new(self.tx)
             .enforce_with_table_mapping(&document, table_mapping_for_schema)
             .await?;
-        self.tx.apply_validated_write(id, None, Some(document))?;
+        self.tx
+            .apply_validated_write(id, None, Some(document.clone()))?;
+        self.tx
+            .update_index_aggregates(None, Some(&document))
+            .await?;
 
         Ok(id.into())
     }
@@ -116,12 +116,20 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
             self.tx.identity().is_admin() || self.tx.identity().is_system(),
             unauthorized_error("add_index")
         );
+        // Foreign key indexes are created with the schema, but they're
+        // maintained by the database and don't count towards the limit.
+        if index.name.is_foreign_key() {
+            return self._add_index(index).await;
+        }
         anyhow::ensure!(!index.name.is_system_owned(), "Can't change system indexes");
         let num_user_indexes_on_table = self
             .get_application_indexes()
             .await?
             .into_iter()
-            .filter(|application_index| application_index.name.table() == index.name.table())
+            .filter(|application_index| {
+                application_index.name.table() == index.name.table()
+                    && !application_index.name.is_foreign_key()
+            })
             .count();
         anyhow::ensure!(
             num_user_indexes_on_table < MAX_INDEXES_PER_TABLE,
@@ -321,10 +329,16 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
             // Collect the database indexes.
             for (index_descriptor, index_schema) in &table_schema.indexes {
                 let index_name = IndexName::new(table_name.clone(), index_descriptor.clone())?;
-                indexes_in_schema.push(IndexMetadata::new_backfilling(
+                indexes_in_schema.push(IndexMetadata::new_backfilling_database_index(
                     *self.tx.begin_timestamp(),
                     index_name.clone(),
-                    index_schema.fields.clone(),
+                    DeveloperDatabaseIndexConfig {
+                        fields: index_schema.fields.clone(),
+                        unique: index_schema.unique,
+                        filter: index_schema.filter.clone(),
+                        multikey_field: index_schema.multikey_field.clone(),
+                        aggregate: index_schema.aggregate.clone(),
+                    },
                 ))
             }
 
@@ -346,6 +360,21 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                     index_schema.filter_fields.clone(),
                 ));
             }
+
+            // Collect the indexes the database maintains for foreign keys.
+            for field in table_schema.foreign_keys.keys() {
+                indexes_in_schema.push(IndexMetadata::new_backfilling_database_index(
+                    *self.tx.begin_timestamp(),
+                    table_schema.foreign_key_index(field)?,
+                    DeveloperDatabaseIndexConfig {
+                        fields: vec![field.clone()].try_into()?,
+                        unique: false,
+                        filter: None,
+                        multikey_field: None,
+                        aggregate: None,
+                    },
+                ));
+            }
         }
 
         let mut diff = IndexDiff::default();
@@ -383,7 +412,7 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
 
         for (name, mut indexes) in remaining_indexes {
             anyhow::ensure!(
-                !name.is_system_owned(),
+                !name.is_system_owned() || name.is_foreign_key(),
                 "Preparing to drop a system index: {:?}",
                 name,
             );
@@ -487,6 +516,16 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
         stable_index_name: &StableIndexName,
         printable_index_name: &IndexName,
     ) -> anyhow::Result<IndexedFields> {
+        Ok(self
+            .database_index_config(stable_index_name, printable_index_name)?
+            .fields)
+    }
+
+    pub fn database_index_config(
+        &mut self,
+        stable_index_name: &StableIndexName,
+        printable_index_name: &IndexName,
+    ) -> anyhow::Result<DeveloperDatabaseIndexConfig> {
         let resolved_index_name = match stable_index_name {
             StableIndexName::Physical(index_name) => index_name,
             StableIndexName::Virtual(_, index_name) => index_name,
@@ -496,9 +535,8 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
             self.require_enabled_index_metadata(printable_index_name, resolved_index_name)?;
         match metadata.config.clone() {
             IndexConfig::Database {
-                developer_config: DeveloperDatabaseIndexConfig { fields },
-                ..
-            } => Ok(fields),
+                developer_config, ..
+            } => Ok(developer_config),
             _ => anyhow::bail!(index_not_a_database_index_error(printable_index_name)),
         }
     }
@@ -790,12 +828,20 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                 // by_id and by_creation_time already created.
                 continue;
             }
-            let index_name = TabletIndexName::new(target_table, index.name.descriptor().clone())?;
+            let descriptor = index.name.descriptor().clone();
+            let index_name = if descriptor.is_foreign_key() {
+                TabletIndexName::new_reserved(target_table, descriptor)?
+            } else {
+                TabletIndexName::new(target_table, descriptor)?
+            };
             let metadata = match index.into_value().config {
                 IndexConfig::Database {
-                    developer_config: DeveloperDatabaseIndexConfig { fields },
-                    ..
-                } => IndexMetadata::new_backfilling(*self.tx.begin_timestamp(), index_name, fields),
+                    developer_config, ..
+                } => IndexMetadata::new_backfilling_database_index(
+                    *self.tx.begin_timestamp(),
+                    index_name,
+                    developer_config,
+                ),
                 IndexConfig::Search {
                     developer_config:
                         DeveloperSearchIndexConfig {
@@ -842,7 +888,10 @@ impl IndexCategory {
         index: &ParsedDocument<TabletIndexMetadata>,
         table_mapping: &TableMapping,
     ) -> bool {
-        let is_system = index.name.descriptor().is_reserved()
+        // Foreign key indexes on user tables come and go with the schema, so
+        // they're listed with the application's indexes.
+        let is_system = (index.name.descriptor().is_reserved()
+            && !index.name.descriptor().is_foreign_key())
             || table_mapping.is_system_table_id(*index.name.table());
         let is_active = table_mapping.is_active(*index.name.table());
         is_active
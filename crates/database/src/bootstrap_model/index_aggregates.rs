@@ -0,0 +1,446 @@
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::LazyLock,
+};
+
+use common::{
+    bootstrap_model::index::database_index::DeveloperDatabaseIndexConfig,
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::{
+        IndexId,
+        IndexName,
+    },
+};
+use value::{
+    obj,
+    values_to_bytes,
+    ConvexObject,
+    ConvexValue,
+    FieldPath,
+    InternalId,
+    TableName,
+};
+
+use crate::{
+    defaults::{
+        system_index,
+        SystemIndex,
+        SystemTable,
+    },
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+
+pub static INDEX_AGGREGATES_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_index_aggregates"
+        .parse()
+        .expect("_index_aggregates is an invalid table name")
+});
+
+static INDEX_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "indexId".parse().expect("Invalid built-in field"));
+
+static PREFIX_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "prefix".parse().expect("Invalid built-in field"));
+
+pub static INDEX_AGGREGATES_BY_INDEX_AND_PREFIX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&INDEX_AGGREGATES_TABLE, "by_index_and_prefix"));
+
+pub struct IndexAggregatesTable;
+impl SystemTable for IndexAggregatesTable {
+    fn table_name(&self) -> &'static TableName {
+        &INDEX_AGGREGATES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: INDEX_AGGREGATES_BY_INDEX_AND_PREFIX.clone(),
+            fields: vec![INDEX_ID_FIELD.clone(), PREFIX_FIELD.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<IndexAggregateRecord>::try_from(document).map(|_| ())
+    }
+}
+
+/// The number of documents in an index matching a prefix of its fields, and
+/// the sum of the index's `sum_field` over them. The count is exact, but
+/// adding and subtracting non-integer values can leave rounding error in the
+/// sum, so a prefix's totals are reset once its count returns to zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IndexAggregateTotals {
+    pub count: i64,
+    pub sum: f64,
+}
+
+impl IndexAggregateTotals {
+    pub fn add(&mut self, other: IndexAggregateTotals) {
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.count == 0 && self.sum == 0.0
+    }
+
+    /// Whether no documents match, in which case the sum is zero regardless
+    /// of any rounding error it accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// A row of `_index_aggregates`. An index's totals are only kept up to date
+/// once it has a `Status` record, so writes read that record to decide
+/// whether to update them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexAggregateRecord {
+    /// Written when the index worker starts counting the index's existing
+    /// documents, with `backfilled` set once it has finished.
+    Status { index_id: IndexId, backfilled: bool },
+    /// Totals for the documents whose values for the index's first fields
+    /// encode to `prefix`.
+    Totals {
+        index_id: IndexId,
+        prefix: Vec<u8>,
+        totals: IndexAggregateTotals,
+    },
+}
+
+impl IndexAggregateRecord {
+    pub fn index_id(&self) -> IndexId {
+        match self {
+            IndexAggregateRecord::Status { index_id, .. }
+            | IndexAggregateRecord::Totals { index_id, .. } => *index_id,
+        }
+    }
+}
+
+impl TryFrom<IndexAggregateRecord> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(value: IndexAggregateRecord) -> Result<Self, Self::Error> {
+        match value {
+            IndexAggregateRecord::Status {
+                index_id,
+                backfilled,
+            } => obj!(
+                "indexId" => ConvexValue::String(index_id.to_string().try_into()?),
+                "prefix" => ConvexValue::Null,
+                "backfilled" => ConvexValue::Boolean(backfilled),
+            ),
+            IndexAggregateRecord::Totals {
+                index_id,
+                prefix,
+                totals,
+            } => obj!(
+                "indexId" => ConvexValue::String(index_id.to_string().try_into()?),
+                "prefix" => ConvexValue::try_from(prefix)?,
+                "count" => ConvexValue::Int64(totals.count),
+                "sum" => ConvexValue::Float64(totals.sum),
+            ),
+        }
+    }
+}
+
+impl TryFrom<ConvexObject> for IndexAggregateRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<_, _> = value.into();
+        let index_id = match fields.remove("indexId") {
+            Some(ConvexValue::String(index_id)) => InternalId::from_str(&index_id)?,
+            _ => anyhow::bail!("Missing or invalid `indexId` field for IndexAggregateRecord"),
+        };
+        let record = match fields.remove("prefix") {
+            Some(ConvexValue::Null) => {
+                let backfilled = match fields.remove("backfilled") {
+                    Some(ConvexValue::Boolean(backfilled)) => backfilled,
+                    _ => anyhow::bail!(
+                        "Missing or invalid `backfilled` field for IndexAggregateRecord"
+                    ),
+                };
+                IndexAggregateRecord::Status {
+                    index_id,
+                    backfilled,
+                }
+            },
+            Some(ConvexValue::Bytes(prefix)) => {
+                let count = match fields.remove("count") {
+                    Some(ConvexValue::Int64(count)) => count,
+                    _ => anyhow::bail!("Missing or invalid `count` field for IndexAggregateRecord"),
+                };
+                let sum = match fields.remove("sum") {
+                    Some(ConvexValue::Float64(sum)) => sum,
+                    _ => anyhow::bail!("Missing or invalid `sum` field for IndexAggregateRecord"),
+                };
+                IndexAggregateRecord::Totals {
+                    index_id,
+                    prefix: prefix.into(),
+                    totals: IndexAggregateTotals { count, sum },
+                }
+            },
+            _ => anyhow::bail!("Missing or invalid `prefix` field for IndexAggregateRecord"),
+        };
+        Ok(record)
+    }
+}
+
+/// Add a document's contribution to the totals for every prefix of the
+/// index's fields, negated if `sign` is -1. Documents outside a partial index
+/// don't contribute, and neither do non-numeric values of the summed field.
+pub(crate) fn accumulate_index_aggregates(
+    developer_config: &DeveloperDatabaseIndexConfig,
+    document: &ResolvedDocument,
+    sign: i64,
+    totals: &mut BTreeMap<Vec<u8>, IndexAggregateTotals>,
+) -> anyhow::Result<()> {
+    if !developer_config.includes(document.value())? {
+        return Ok(());
+    }
+    let values: Vec<_> = developer_config
+        .fields
+        .iter()
+        .map(|field| document.value().get_path(field).cloned())
+        .collect();
+    let sum_field = developer_config
+        .aggregate
+        .as_ref()
+        .and_then(|aggregate| aggregate.sum_field.as_ref());
+    let addend = match sum_field.and_then(|field| document.value().get_path(field)) {
+        Some(ConvexValue::Float64(f)) => *f,
+        Some(ConvexValue::Int64(i)) => *i as f64,
+        _ => 0.0,
+    };
+    for len in 1..=values.len() {
+        totals
+            .entry(values_to_bytes(&values[..len]))
+            .or_default()
+            .add(IndexAggregateTotals {
+                count: sign,
+                sum: sign as f64 * addend,
+            });
+    }
+    Ok(())
+}
+
+pub struct IndexAggregateModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> IndexAggregateModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Returns `None` if writes aren't maintaining the index's totals yet,
+    /// and otherwise whether the existing documents have been counted.
+    pub async fn status(&mut self, index_id: IndexId) -> anyhow::Result<Option<bool>> {
+        let Some(document) = self.get(index_id, ConvexValue::Null).await? else {
+            return Ok(None);
+        };
+        match IndexAggregateRecord::try_from(document.into_value().0)? {
+            IndexAggregateRecord::Status { backfilled, .. } => Ok(Some(backfilled)),
+            IndexAggregateRecord::Totals { .. } => {
+                anyhow::bail!("Expected a status record for index {index_id}")
+            },
+        }
+    }
+
+    /// The totals for documents whose values for the index's first fields
+    /// encode to `prefix`. This adds the record to the read set, so the
+    /// transaction is invalidated by any write that changes them.
+    pub async fn totals(
+        &mut self,
+        index_id: IndexId,
+        prefix: Vec<u8>,
+    ) -> anyhow::Result<IndexAggregateTotals> {
+        let Some(document) = self.get(index_id, ConvexValue::try_from(prefix)?).await? else {
+            return Ok(IndexAggregateTotals::default());
+        };
+        match IndexAggregateRecord::try_from(document.into_value().0)? {
+            IndexAggregateRecord::Totals { totals, .. } => Ok(totals),
+            IndexAggregateRecord::Status { .. } => {
+                anyhow::bail!("Expected a totals record for index {index_id}")
+            },
+        }
+    }
+
+    /// Add `delta` to the totals for `prefix`, deleting the record once no
+    /// documents match it. This runs as part of every write to an aggregated
+    /// table, so it writes the record directly rather than going through
+    /// `SystemMetadataModel`.
+    pub(crate) async fn add_to_totals(
+        &mut self,
+        index_id: IndexId,
+        prefix: Vec<u8>,
+        delta: IndexAggregateTotals,
+    ) -> anyhow::Result<()> {
+        let existing = self
+            .get(index_id, ConvexValue::try_from(prefix.clone())?)
+            .await?;
+        let mut totals = match &existing {
+            Some(document) => match IndexAggregateRecord::try_from(document.value().0.clone())? {
+                IndexAggregateRecord::Totals { totals, .. } => totals,
+                IndexAggregateRecord::Status { .. } => {
+                    anyhow::bail!("Expected a totals record for index {index_id}")
+                },
+            },
+            None => IndexAggregateTotals::default(),
+        };
+        totals.add(delta);
+        let new_value: ConvexObject = IndexAggregateRecord::Totals {
+            index_id,
+            prefix,
+            totals,
+        }
+        .try_into()?;
+        match existing {
+            Some(document) if totals.is_empty() => {
+                self.tx
+                    .apply_validated_write(*document.id(), Some(document), None)?;
+            },
+            Some(document) => {
+                let new_document = document.replace_value(new_value)?;
+                self.tx.apply_validated_write(
+                    *new_document.id(),
+                    Some(document),
+                    Some(new_document),
+                )?;
+            },
+            None if totals.is_empty() => {},
+            None => {
+                let table_id = self.tx.table_mapping().id(&INDEX_AGGREGATES_TABLE)?;
+                let id = self.tx.id_generator.generate(&table_id);
+                let creation_time = self.tx.next_creation_time.increment()?;
+                let document = ResolvedDocument::new(id, creation_time, new_value)?;
+                self.tx.apply_validated_write(id, None, Some(document))?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Start maintaining the index's totals on write. Writes that raced with
+    /// this conflict on the status record, so every write after it is
+    /// counted.
+    pub async fn start_backfill(&mut self, index_id: IndexId) -> anyhow::Result<()> {
+        SystemMetadataModel::new(self.tx)
+            .insert(
+                &INDEX_AGGREGATES_TABLE,
+                IndexAggregateRecord::Status {
+                    index_id,
+                    backfilled: false,
+                }
+                .try_into()?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn finish_backfill(&mut self, index_id: IndexId) -> anyhow::Result<()> {
+        let document = self
+            .get(index_id, ConvexValue::Null)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Missing status record for index {index_id}"))?;
+        SystemMetadataModel::new(self.tx)
+            .replace(
+                *document.id(),
+                IndexAggregateRecord::Status {
+                    index_id,
+                    backfilled: true,
+                }
+                .try_into()?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Delete up to `max_records` of the index's records, starting with its
+    /// status so writes stop updating the rest. Returns how many were deleted.
+    pub async fn delete_records(
+        &mut self,
+        index_id: IndexId,
+        max_records: usize,
+    ) -> anyhow::Result<usize> {
+        let query = Query::index_range(IndexRange {
+            index_name: INDEX_AGGREGATES_BY_INDEX_AND_PREFIX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                INDEX_ID_FIELD.clone(),
+                ConvexValue::String(index_id.to_string().try_into()?).into(),
+            )],
+            order: Order::Asc,
+        })
+        .limit(max_records);
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        let mut ids = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            ids.push(*document.id());
+        }
+        for id in &ids {
+            SystemMetadataModel::new(self.tx).delete(*id).await?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Returns the ids of the indexes that have records, one index lookup per
+    /// index, so dropped indexes' records can be cleaned up.
+    pub async fn indexes_with_records(&mut self) -> anyhow::Result<Vec<IndexId>> {
+        let mut index_ids = vec![];
+        loop {
+            let range = match index_ids.last() {
+                Some(index_id) => vec![IndexRangeExpression::Gt(
+                    INDEX_ID_FIELD.clone(),
+                    ConvexValue::String(index_id.to_string().try_into()?),
+                )],
+                None => vec![],
+            };
+            let query = Query::index_range(IndexRange {
+                index_name: INDEX_AGGREGATES_BY_INDEX_AND_PREFIX.clone(),
+                range,
+                order: Order::Asc,
+            })
+            .limit(1);
+            let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+            let Some(document) = query_stream.next(self.tx, None).await? else {
+                return Ok(index_ids);
+            };
+            let record = IndexAggregateRecord::try_from(document.into_value().0)?;
+            index_ids.push(record.index_id());
+        }
+    }
+
+    async fn get(
+        &mut self,
+        index_id: IndexId,
+        prefix: ConvexValue,
+    ) -> anyhow::Result<Option<ResolvedDocument>> {
+        let query = Query::index_range(IndexRange {
+            index_name: INDEX_AGGREGATES_BY_INDEX_AND_PREFIX.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    INDEX_ID_FIELD.clone(),
+                    ConvexValue::String(index_id.to_string().try_into()?).into(),
+                ),
+                IndexRangeExpression::Eq(PREFIX_FIELD.clone(), prefix.into()),
+            ],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        query_stream.next(self.tx, None).await
+    }
+}
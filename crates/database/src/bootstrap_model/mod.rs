@@ -5,6 +5,7 @@
 pub mod defaults;
 pub mod import_facing;
 pub mod index;
+pub mod index_aggregates;
 pub mod index_workers;
 pub mod schema;
 pub mod system_metadata;
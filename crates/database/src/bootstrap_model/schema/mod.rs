@@ -2,6 +2,11 @@
 mod tests;
 
 use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    iter,
     sync::LazyLock,
     time::Duration,
 };
@@ -26,13 +31,17 @@ use common::{
     runtime::Runtime,
     schemas::{
         DatabaseSchema,
+        OnDelete,
         SchemaValidationError,
     },
     types::IndexName,
 };
 use errors::ErrorMetadata;
 use value::{
+    id_v6::DocumentIdV6,
     val,
+    ConvexValue,
+    FieldName,
     FieldPath,
     ResolvedDocumentId,
     TableMapping,
@@ -46,6 +55,7 @@ use crate::{
         SystemTable,
     },
     patch_value,
+    IndexModel,
     ResolvedQuery,
     SystemMetadataModel,
     TableModel,
@@ -81,6 +91,15 @@ impl SystemTable for SchemasTable {
     }
 }
 
+/// The writes that go with deleting a document referenced by foreign keys.
+#[derive(Default)]
+pub(crate) struct ForeignKeyDeletes {
+    /// Documents deleted by cascading foreign keys.
+    pub cascade: Vec<ResolvedDocument>,
+    /// Documents with set-null foreign keys nulled out, before and after.
+    pub set_null: Vec<(ResolvedDocument, ResolvedDocument)>,
+}
+
 pub struct SchemaModel<'a, RT: Runtime> {
     tx: &'a mut Transaction<RT>,
 }
@@ -93,7 +112,282 @@ impl<'a, RT: Runtime> SchemaModel<'a, RT> {
     pub async fn enforce(&mut self, document: &ResolvedDocument) -> anyhow::Result<()> {
         let schema_table_mapping = self.tx.table_mapping().clone();
         self.enforce_with_table_mapping(document, &schema_table_mapping)
-            .await
+            .await?;
+        self.enforce_foreign_keys(document).await
+    }
+
+    /// Checks that the foreign key fields of a new or updated document
+    /// reference existing documents. Reading the referenced documents puts
+    /// them in the read set, so a concurrent delete of one of them conflicts
+    /// with this transaction. Like other schema violations, a violation of a
+    /// pending or validated schema's foreign keys fails that schema instead.
+    async fn enforce_foreign_keys(&mut self, document: &ResolvedDocument) -> anyhow::Result<()> {
+        if self.tx.is_system(document.table().table_number) {
+            return Ok(());
+        }
+        let table_name = self
+            .tx
+            .table_mapping()
+            .tablet_name(document.table().table_id)?;
+        if let Some((_id, active_schema)) = self.get_by_state(SchemaState::Active).await?
+            && let Some(schema_error) = self
+                .check_foreign_keys(&active_schema, document, &table_name)
+                .await?
+        {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "ForeignKeyViolation",
+                schema_error.to_string()
+            ));
+        }
+        let pending_schema = self.get_by_state(SchemaState::Pending).await?;
+        let validated_schema = self.get_by_state(SchemaState::Validated).await?;
+        match (pending_schema, validated_schema) {
+            (None, None) => {},
+            (Some((id, in_progress_schema)), None) | (None, Some((id, in_progress_schema))) => {
+                if let Some(schema_error) = self
+                    .check_foreign_keys(&in_progress_schema, document, &table_name)
+                    .await?
+                {
+                    self.mark_failed(id, schema_error).await?;
+                }
+            },
+            (Some(_), Some(_)) => {
+                anyhow::bail!("Invalid schema state: both pending and validated schemas exist")
+            },
+        }
+        Ok(())
+    }
+
+    async fn check_foreign_keys(
+        &mut self,
+        schema: &DatabaseSchema,
+        document: &ResolvedDocument,
+        table_name: &TableName,
+    ) -> anyhow::Result<Option<SchemaValidationError>> {
+        let Some(table_definition) = schema.tables.get(table_name) else {
+            return Ok(None);
+        };
+        for (field, foreign_key) in &table_definition.foreign_keys {
+            if !self
+                .references_existing_document(document.value().get_path(field), &foreign_key.table)
+                .await?
+            {
+                return Ok(Some(SchemaValidationError::ForeignKeyViolation {
+                    table_name: table_name.clone(),
+                    id: (*document.id()).into(),
+                    field: field.clone(),
+                    referenced_table: foreign_key.table.clone(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether `value`, the value of a foreign key field, is missing, null, or
+    /// the ID of an existing document in `table`.
+    pub async fn references_existing_document(
+        &mut self,
+        value: Option<&ConvexValue>,
+        table: &TableName,
+    ) -> anyhow::Result<bool> {
+        let referenced_id = match value {
+            None | Some(ConvexValue::Null) => return Ok(true),
+            Some(ConvexValue::String(s)) => DocumentIdV6::decode(s).ok(),
+            Some(_) => None,
+        };
+        let referenced_id = referenced_id
+            .and_then(|id| id.map_table(self.tx.table_mapping().inject_table_id()).ok())
+            .filter(|id| {
+                self.tx
+                    .table_mapping()
+                    .tablet_name(id.table().table_id)
+                    .is_ok_and(|name| &name == table)
+            });
+        match referenced_id {
+            Some(id) => Ok(self.tx.get(id).await?.is_some()),
+            None => Ok(false),
+        }
+    }
+
+    /// Finds the writes that go with deleting `document`, following the
+    /// `onDelete` behavior of the foreign keys that reference it. Fails
+    /// without writing anything if a restrict foreign key references a
+    /// document that isn't deleted too. The referencing documents are found
+    /// with the foreign keys' indexes, so documents that start referencing a
+    /// deleted document concurrently conflict with this transaction.
+    ///
+    /// A pending or validated schema fails if the delete leaves one of its
+    /// foreign keys referencing a deleted document.
+    pub(crate) async fn foreign_key_deletes(
+        &mut self,
+        document: &ResolvedDocument,
+    ) -> anyhow::Result<ForeignKeyDeletes> {
+        let mut deletes = ForeignKeyDeletes::default();
+        if self.tx.is_system(document.table().table_number) {
+            return Ok(deletes);
+        }
+        let mut deleted_ids = BTreeSet::from([*document.id()]);
+        let mut set_null: BTreeMap<ResolvedDocumentId, (ResolvedDocument, Vec<FieldPath>)> =
+            BTreeMap::new();
+        if let Some((_id, active_schema)) = self.get_by_state(SchemaState::Active).await? {
+            let mut restricted = vec![];
+            let mut to_visit = vec![document.clone()];
+            while let Some(deleted) = to_visit.pop() {
+                let table_name = self
+                    .tx
+                    .table_mapping()
+                    .tablet_name(deleted.table().table_id)?;
+                let deleted_id = DocumentIdV6::from(*deleted.id());
+                for (referencing_table, table_definition) in &active_schema.tables {
+                    for (field, foreign_key) in &table_definition.foreign_keys {
+                        if foreign_key.table != table_name {
+                            continue;
+                        }
+                        let index_name = table_definition.foreign_key_index(field)?;
+                        for referencing in self
+                            .referencing_documents(index_name, field, deleted_id)
+                            .await?
+                        {
+                            let referencing_id = *referencing.id();
+                            match foreign_key.on_delete {
+                                OnDelete::Restrict => restricted.push((
+                                    referencing_id,
+                                    format!(
+                                        "Can't delete document {} in table \"{table_name}\" \
+                                         because field {field} of document {} in table \
+                                         \"{referencing_table}\" references it",
+                                        deleted_id.encode(),
+                                        DocumentIdV6::from(referencing_id).encode(),
+                                    ),
+                                )),
+                                OnDelete::Cascade => {
+                                    if deleted_ids.insert(referencing_id) {
+                                        deletes.cascade.push(referencing.clone());
+                                        to_visit.push(referencing);
+                                    }
+                                },
+                                OnDelete::SetNull => {
+                                    set_null
+                                        .entry(referencing_id)
+                                        .or_insert_with(|| (referencing, vec![]))
+                                        .1
+                                        .push(field.clone());
+                                },
+                            }
+                        }
+                    }
+                }
+            }
+            // References from documents that are deleted too don't restrict.
+            if let Some((_, message)) = restricted
+                .into_iter()
+                .find(|(id, _)| !deleted_ids.contains(id))
+            {
+                anyhow::bail!(ErrorMetadata::bad_request("ForeignKeyViolation", message));
+            }
+        }
+        set_null.retain(|id, _| !deleted_ids.contains(id));
+        self.enforce_in_progress_foreign_key_deletes(document, &deletes, &set_null)
+            .await?;
+        for (_id, (old_document, fields)) in set_null {
+            let mut value: BTreeMap<FieldName, ConvexValue> =
+                old_document.value().clone().into_value().into();
+            for field in fields {
+                value.insert(field.last().clone().into(), ConvexValue::Null);
+            }
+            let new_document = old_document.replace_value(value.try_into()?)?;
+            self.enforce(&new_document).await?;
+            deletes.set_null.push((old_document, new_document));
+        }
+        Ok(deletes)
+    }
+
+    /// Fails the pending or validated schema if one of its foreign keys
+    /// references a document deleted along with `document`, unless the
+    /// referencing document is deleted or has the field nulled out too. Until
+    /// a foreign key's index is enabled, its metadata is read instead, and
+    /// the schema worker validates existing documents once it's enabled.
+    async fn enforce_in_progress_foreign_key_deletes(
+        &mut self,
+        document: &ResolvedDocument,
+        deletes: &ForeignKeyDeletes,
+        set_null: &BTreeMap<ResolvedDocumentId, (ResolvedDocument, Vec<FieldPath>)>,
+    ) -> anyhow::Result<()> {
+        let pending_schema = self.get_by_state(SchemaState::Pending).await?;
+        let validated_schema = self.get_by_state(SchemaState::Validated).await?;
+        let (id, in_progress_schema) = match (pending_schema, validated_schema) {
+            (None, None) => return Ok(()),
+            (Some(in_progress), None) | (None, Some(in_progress)) => in_progress,
+            (Some(_), Some(_)) => {
+                anyhow::bail!("Invalid schema state: both pending and validated schemas exist")
+            },
+        };
+        let deleted: Vec<_> = iter::once(document).chain(&deletes.cascade).collect();
+        let deleted_ids: BTreeSet<_> = deleted.iter().map(|deleted| *deleted.id()).collect();
+        for deleted in deleted {
+            let table_name = self
+                .tx
+                .table_mapping()
+                .tablet_name(deleted.table().table_id)?;
+            for (referencing_table, table_definition) in &in_progress_schema.tables {
+                for (field, foreign_key) in &table_definition.foreign_keys {
+                    if foreign_key.table != table_name {
+                        continue;
+                    }
+                    let index_name = table_definition.foreign_key_index(field)?;
+                    if IndexModel::new(self.tx)
+                        .enabled_index_metadata(&index_name)?
+                        .is_none()
+                    {
+                        continue;
+                    }
+                    let dangling = self
+                        .referencing_documents(index_name, field, (*deleted.id()).into())
+                        .await?
+                        .into_iter()
+                        .find(|referencing| {
+                            !deleted_ids.contains(referencing.id())
+                                && !set_null
+                                    .get(referencing.id())
+                                    .is_some_and(|(_, fields)| fields.contains(field))
+                        });
+                    if let Some(referencing) = dangling {
+                        let schema_error = SchemaValidationError::ForeignKeyViolation {
+                            table_name: referencing_table.clone(),
+                            id: (*referencing.id()).into(),
+                            field: field.clone(),
+                            referenced_table: table_name,
+                        };
+                        return self.mark_failed(id, schema_error).await;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The documents whose foreign key on `field` references `referenced_id`,
+    /// read with the foreign key's index.
+    async fn referencing_documents(
+        &mut self,
+        index_name: IndexName,
+        field: &FieldPath,
+        referenced_id: DocumentIdV6,
+    ) -> anyhow::Result<Vec<ResolvedDocument>> {
+        let index_range = IndexRange {
+            index_name,
+            range: vec![IndexRangeExpression::Eq(
+                field.clone(),
+                ConvexValue::from(referenced_id).into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(self.tx, Query::index_range(index_range))?;
+        let mut referencing = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            referencing.push(document);
+        }
+        Ok(referencing)
     }
 
     pub async fn enforce_table_deletion(
@@ -370,6 +664,8 @@ impl<'a, RT: Runtime> SchemaModel<'a, RT> {
                     SchemaValidationError::ReferencedTableCannotBeDeleted {
                         table_name, ..
                     } => table_name,
+                    SchemaValidationError::UniqueIndexViolation { table_name, .. } => table_name,
+                    SchemaValidationError::ForeignKeyViolation { table_name, .. } => table_name,
                 };
                 SystemMetadataModel::new(self.tx)
                     .patch(
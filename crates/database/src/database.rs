@@ -33,7 +33,11 @@ use cmd_util::env::env_config;
 use common::{
     bootstrap_model::{
         index::{
-            database_index::IndexedFields,
+            database_index::{
+                DeveloperDatabaseIndexConfig,
+                IndexedFields,
+            },
+            IndexConfig,
             IndexMetadata,
             TabletIndexMetadata,
             INDEX_TABLE,
@@ -178,6 +182,7 @@ use crate::{
     },
     retention::LeaderRetentionManager,
     search_and_vector_bootstrap::SearchAndVectorIndexBootstrapWorker,
+    slow_query_log::SlowQueryLog,
     snapshot_manager::{
         Snapshot,
         SnapshotManager,
@@ -263,6 +268,7 @@ pub struct Database<RT: Runtime> {
     pub search_storage: Arc<OnceLock<Arc<dyn Storage>>>,
     usage_counter: UsageCounter,
     virtual_system_mapping: VirtualSystemMapping,
+    slow_query_log: SlowQueryLog,
     pub bootstrap_metadata: BootstrapMetadata,
 }
 
@@ -865,6 +871,7 @@ impl<RT: Runtime> Database<RT> {
             search_storage: Arc::new(OnceLock::new()),
             usage_counter,
             virtual_system_mapping,
+            slow_query_log: SlowQueryLog::new(),
             bootstrap_metadata,
         };
 
@@ -1461,6 +1468,7 @@ impl<RT: Runtime> Database<RT> {
             usage_tracker,
             Arc::new(self.retention_manager.clone()),
             self.virtual_system_mapping.clone(),
+            self.slow_query_log.clone(),
         );
         Ok(tx)
     }
@@ -1764,9 +1772,11 @@ impl<RT: Runtime> Database<RT> {
         let table_mapping = snapshot.table_mapping().clone();
 
         let mut document_storage_by_table = BTreeMap::new();
+        let mut summaries_by_table = BTreeMap::new();
         for (table_name, summary) in snapshot.iter_user_table_summaries() {
             let table_size = summary.total_size_rounded() as usize;
-            document_storage_by_table.insert(table_name, (table_size, 0));
+            document_storage_by_table.insert(table_name.clone(), (table_size, 0));
+            summaries_by_table.insert(table_name, summary);
         }
 
         // TODO: We are currently using document size * index count as a rough
@@ -1784,8 +1794,31 @@ impl<RT: Runtime> Database<RT> {
                 let (document_size, index_size) = *document_storage_by_table
                     .get(&table_name)
                     .expect("Index on a nonexistent table");
+                // Multikey indexes have an entry per array element, so scale by
+                // the estimated number of entries per document.
+                let entries_size = match &index.config {
+                    IndexConfig::Database {
+                        developer_config:
+                            DeveloperDatabaseIndexConfig {
+                                multikey_field: Some(multikey_field),
+                                ..
+                            },
+                        ..
+                    } => {
+                        let summary = summaries_by_table[&table_name];
+                        let num_documents = summary.num_values();
+                        if num_documents == 0 {
+                            0
+                        } else {
+                            let num_entries = summary.multikey_index_entries(multikey_field);
+                            (document_size as u128 * num_entries as u128 / num_documents as u128)
+                                as usize
+                        }
+                    },
+                    _ => document_size,
+                };
                 document_storage_by_table
-                    .insert(table_name, (document_size, index_size + document_size));
+                    .insert(table_name, (document_size, index_size + entries_size));
             }
         }
 
@@ -1796,6 +1829,10 @@ impl<RT: Runtime> Database<RT> {
         self.usage_counter.clone()
     }
 
+    pub fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.slow_query_log
+    }
+
     pub fn write_log_size(&self) -> usize {
         self.log.heap_size()
     }
@@ -14,15 +14,18 @@ use std::{
 
 use common::{
     backoff::Backoff,
-    bootstrap_model::index::{
-        database_index::{
-            DatabaseIndexState,
-            IndexedFields,
+    bootstrap_model::{
+        index::{
+            database_index::{
+                DatabaseIndexState,
+                DeveloperDatabaseIndexConfig,
+            },
+            IndexConfig,
+            IndexMetadata,
+            TabletIndexMetadata,
+            INDEX_TABLE,
         },
-        IndexConfig,
-        IndexMetadata,
-        TabletIndexMetadata,
-        INDEX_TABLE,
+        schema::SchemaState,
     },
     document::{
         ParsedDocument,
@@ -35,6 +38,7 @@ use common::{
         INDEX_BACKFILL_CHUNK_SIZE,
         INDEX_WORKERS_INITIAL_BACKOFF,
     },
+    pause::PauseClient,
     persistence::{
         ConflictStrategy,
         Persistence,
@@ -59,6 +63,7 @@ use common::{
         RuntimeInstant,
         SpawnHandle,
     },
+    schemas::SchemaValidationError,
     types::{
         DatabaseIndexUpdate,
         IndexId,
@@ -69,6 +74,8 @@ use common::{
         Timestamp,
     },
     value::{
+        id_v6::DocumentIdV6,
+        ConvexValue,
         ResolvedDocumentId,
         TableId,
         TableMapping,
@@ -93,9 +100,11 @@ use maplit::{
     btreeset,
 };
 use tracing::log;
+use usage_tracking::FunctionUsageTracker;
 use value::InternalDocumentId;
 
 use crate::{
+    bootstrap_model::index_aggregates::accumulate_index_aggregates,
     metrics::{
         log_index_backfilled,
         log_num_indexes_to_backfill,
@@ -103,13 +112,22 @@ use crate::{
     },
     retention::LeaderRetentionManager,
     Database,
+    IndexAggregateModel,
+    IndexAggregateTotals,
+    IndexModel,
     ResolvedQuery,
+    SchemaModel,
     SystemMetadataModel,
     TableIterator,
+    Transaction,
 };
 
 const MAX_BACKOFF: Duration = Duration::from_secs(30);
 
+/// How many conflicting documents to report when a unique index can't be
+/// backfilled.
+const MAX_REPORTED_UNIQUE_CONFLICTS: usize = 20;
+
 static ENTRIES_PER_SECOND: LazyLock<NonZeroU32> = LazyLock::new(|| {
     NonZeroU32::new(
         (*INDEX_BACKFILL_CHUNK_RATE * *INDEX_BACKFILL_CHUNK_SIZE)
@@ -300,6 +318,10 @@ impl<RT: Runtime> IndexWorker<RT> {
                     }
                 }
             }
+            let live_index_ids: BTreeSet<_> =
+                index_documents.keys().map(|id| id.internal_id()).collect();
+            self.delete_dropped_index_aggregates(&live_index_ids)
+                .await?;
             let num_to_backfill = to_backfill.len();
             log::info!(
                 "{num_to_backfill} database indexes to backfill @ {}",
@@ -364,14 +386,19 @@ impl<RT: Runtime> IndexWorker<RT> {
         }
 
         // Run retention.
-        let (backfill_begin_ts, index_name, indexed_fields) =
-            self.begin_retention(index_id).await?;
+        let (backfill_begin_ts, index_name, index_config) = self.begin_retention(index_id).await?;
         log::info!("Started running retention for index {}", index_name);
         self.index_writer
-            .run_retention(index_id, backfill_begin_ts, index_name, indexed_fields)
+            .run_retention(index_id, backfill_begin_ts, index_name, index_config)
             .await?;
 
-        self.finish_backfill(index_id).await?;
+        let conflicts = self.unique_index_conflicts(index_id).await?;
+        if conflicts.is_empty() {
+            self.backfill_aggregates(index_id).await?;
+            self.finish_backfill(index_id).await?;
+        } else {
+            self.fail_unique_backfill(index_id, conflicts).await?;
+        }
         Ok(())
     }
 
@@ -418,7 +445,7 @@ impl<RT: Runtime> IndexWorker<RT> {
     async fn begin_retention(
         &mut self,
         index_id: IndexId,
-    ) -> anyhow::Result<(Timestamp, TabletIndexName, IndexedFields)> {
+    ) -> anyhow::Result<(Timestamp, TabletIndexName, DeveloperDatabaseIndexConfig)> {
         let mut tx = self.database.begin(Identity::system()).await?;
         let index_table_id = tx.bootstrap_tables().index_id;
 
@@ -432,7 +459,7 @@ impl<RT: Runtime> IndexWorker<RT> {
         // the state to still be `Backfilling` here. If this assertion fails, we
         // somehow raced with another `IndexWorker`(!) or don't actually have the
         // database lease (!).
-        let (index_ts, indexed_fields) = match &mut index_metadata.config {
+        let (index_ts, index_config) = match &mut index_metadata.config {
             IndexConfig::Database {
                 on_disk_state,
                 developer_config,
@@ -445,10 +472,7 @@ impl<RT: Runtime> IndexWorker<RT> {
                 };
 
                 state.retention_started = true;
-                (
-                    state.index_created_lower_bound,
-                    developer_config.fields.clone(),
-                )
+                (state.index_created_lower_bound, developer_config.clone())
             },
             _ => anyhow::bail!(
                 "IndexWorker attempted to backfill an index {index_metadata:?} which wasn't a \
@@ -464,7 +488,282 @@ impl<RT: Runtime> IndexWorker<RT> {
             .commit_with_write_source(tx, "index_worker_start_retention")
             .await?;
 
-        Ok((index_ts, name, indexed_fields))
+        Ok((index_ts, name, index_config))
+    }
+
+    /// For a unique index, returns the IDs of documents that have the same
+    /// indexed values as another document, stopping after
+    /// `MAX_REPORTED_UNIQUE_CONFLICTS`. This is empty for other indexes.
+    ///
+    /// Writes are checked against the index from when retention starts, so
+    /// together with this scan every document is checked, including ones
+    /// written before the index is enabled on push.
+    async fn unique_index_conflicts(
+        &mut self,
+        index_id: IndexId,
+    ) -> anyhow::Result<Vec<ResolvedDocumentId>> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let index_table_id = tx.bootstrap_tables().index_id;
+        let index_doc = tx
+            .get(ResolvedDocumentId::new(index_table_id, index_id))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Index {index_id:?} no longer exists"))?;
+        let index_metadata = TabletIndexMetadata::from_document(index_doc)?;
+        let IndexConfig::Database {
+            developer_config, ..
+        } = &index_metadata.config
+        else {
+            return Ok(vec![]);
+        };
+        if !developer_config.unique {
+            return Ok(vec![]);
+        }
+        let fields = &developer_config.fields;
+
+        let rate_limiter =
+            new_rate_limiter(self.runtime.clone(), Quota::per_second(*ENTRIES_PER_SECOND));
+        let table_iterator = self.database.table_iterator(
+            self.database.now_ts_for_reads(),
+            *INDEX_BACKFILL_CHUNK_SIZE,
+            None,
+        );
+        let stream = table_iterator.stream_documents_in_table_by_index(
+            *index_metadata.name.table(),
+            index_id,
+            fields.clone(),
+            None,
+            &rate_limiter,
+        );
+        pin_mut!(stream);
+        // Documents are in index order, so equal values are adjacent.
+        let mut conflicts = BTreeSet::new();
+        let mut previous: Option<(Vec<Option<ConvexValue>>, ResolvedDocumentId)> = None;
+        while let Some((_, _, doc)) = stream.try_next().await? {
+            if !developer_config.includes(doc.value())? {
+                continue;
+            }
+            let values: Vec<_> = fields
+                .iter()
+                .map(|field| doc.value().get_path(field).cloned())
+                .collect();
+            // Documents missing an indexed field are exempt from uniqueness.
+            if values.iter().any(Option::is_none) {
+                continue;
+            }
+            if let Some((previous_values, previous_id)) = &previous
+                && *previous_values == values
+            {
+                conflicts.insert(*previous_id);
+                conflicts.insert(*doc.id());
+                if conflicts.len() >= MAX_REPORTED_UNIQUE_CONFLICTS {
+                    break;
+                }
+            }
+            previous = Some((values, *doc.id()));
+        }
+        Ok(conflicts.into_iter().collect())
+    }
+
+    /// For an index with an aggregate, count its existing documents into
+    /// `_index_aggregates`. Starting the backfill makes every later write
+    /// update the totals, so this only needs to count the documents in the
+    /// index as of that commit.
+    async fn backfill_aggregates(&mut self, index_id: IndexId) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let index_table_id = tx.bootstrap_tables().index_id;
+        let index_doc = tx
+            .get(ResolvedDocumentId::new(index_table_id, index_id))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Index {index_id:?} no longer exists"))?;
+        let index_metadata = TabletIndexMetadata::from_document(index_doc)?;
+        let IndexConfig::Database {
+            developer_config, ..
+        } = &index_metadata.config
+        else {
+            return Ok(());
+        };
+        if developer_config.aggregate.is_none() {
+            return Ok(());
+        }
+        match IndexAggregateModel::new(&mut tx).status(index_id).await? {
+            Some(true) => return Ok(()),
+            // A previous attempt was interrupted partway through counting, so
+            // throw away its totals and start over.
+            Some(false) => {
+                loop {
+                    let mut tx = self.database.begin(Identity::system()).await?;
+                    let num_deleted = IndexAggregateModel::new(&mut tx)
+                        .delete_records(index_id, *INDEX_BACKFILL_CHUNK_SIZE)
+                        .await?;
+                    self.database
+                        .commit_with_write_source(tx, "index_worker_reset_aggregates")
+                        .await?;
+                    if num_deleted < *INDEX_BACKFILL_CHUNK_SIZE {
+                        break;
+                    }
+                }
+                tx = self.database.begin(Identity::system()).await?;
+                anyhow::ensure!(
+                    IndexAggregateModel::new(&mut tx)
+                        .status(index_id)
+                        .await?
+                        .is_none(),
+                    "Aggregates for index {index_id} were written during reset"
+                );
+            },
+            None => {},
+        }
+        log::info!(
+            "Starting aggregate backfill of index {}",
+            index_metadata.name
+        );
+        IndexAggregateModel::new(&mut tx)
+            .start_backfill(index_id)
+            .await?;
+        let start_ts = self
+            .database
+            .commit_with_write_source(tx, "index_worker_start_aggregate_backfill")
+            .await?;
+        let snapshot_ts = self.database.now_ts_for_reads().prior_ts(start_ts)?;
+
+        let rate_limiter =
+            new_rate_limiter(self.runtime.clone(), Quota::per_second(*ENTRIES_PER_SECOND));
+        let table_iterator =
+            self.database
+                .table_iterator(snapshot_ts, *INDEX_BACKFILL_CHUNK_SIZE, None);
+        let stream = table_iterator.stream_documents_in_table_by_index(
+            *index_metadata.name.table(),
+            index_id,
+            developer_config.fields.clone(),
+            None,
+            &rate_limiter,
+        );
+        pin_mut!(stream);
+        // Totals are added to what's already recorded, so a prefix can be
+        // flushed more than once.
+        let mut totals = BTreeMap::new();
+        while let Some((_, _, doc)) = stream.try_next().await? {
+            accumulate_index_aggregates(developer_config, &doc, 1, &mut totals)?;
+            if totals.len() >= *INDEX_BACKFILL_CHUNK_SIZE {
+                self.add_aggregate_totals(index_id, std::mem::take(&mut totals))
+                    .await?;
+            }
+        }
+        self.add_aggregate_totals(index_id, totals).await?;
+
+        let mut tx = self.database.begin(Identity::system()).await?;
+        IndexAggregateModel::new(&mut tx)
+            .finish_backfill(index_id)
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "index_worker_finish_aggregate_backfill")
+            .await?;
+        Ok(())
+    }
+
+    /// Delete the `_index_aggregates` records of indexes that have been
+    /// dropped. Writes stop updating them as soon as the index is gone, so
+    /// this can happen in batches after the fact. It uses its own
+    /// transactions so the deletions don't wake the worker's subscription.
+    async fn delete_dropped_index_aggregates(
+        &self,
+        live_index_ids: &BTreeSet<IndexId>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let dropped: Vec<_> = IndexAggregateModel::new(&mut tx)
+            .indexes_with_records()
+            .await?
+            .into_iter()
+            .filter(|index_id| !live_index_ids.contains(index_id))
+            .collect();
+        for index_id in dropped {
+            log::info!("Deleting aggregates of dropped index {index_id}");
+            loop {
+                let mut tx = self.database.begin(Identity::system()).await?;
+                let num_deleted = IndexAggregateModel::new(&mut tx)
+                    .delete_records(index_id, *INDEX_BACKFILL_CHUNK_SIZE)
+                    .await?;
+                self.database
+                    .commit_with_write_source(tx, "index_worker_delete_dropped_aggregates")
+                    .await?;
+                if num_deleted < *INDEX_BACKFILL_CHUNK_SIZE {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn add_aggregate_totals(
+        &self,
+        index_id: IndexId,
+        totals: BTreeMap<Vec<u8>, IndexAggregateTotals>,
+    ) -> anyhow::Result<()> {
+        if totals.is_empty() {
+            return Ok(());
+        }
+        // Concurrent writes update the same records, so retry on conflicts.
+        self.database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "index_worker_backfill_aggregates",
+                |tx| Self::add_aggregate_totals_inner(tx, index_id, totals.clone()).into(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn add_aggregate_totals_inner(
+        tx: &mut Transaction<RT>,
+        index_id: IndexId,
+        totals: BTreeMap<Vec<u8>, IndexAggregateTotals>,
+    ) -> anyhow::Result<()> {
+        for (prefix, delta) in totals {
+            IndexAggregateModel::new(tx)
+                .add_to_totals(index_id, prefix, delta)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Drop a unique index whose existing documents violate the constraint and
+    /// fail the in-progress schema that added it, so pushing it reports the
+    /// conflicting documents. Pushing the index again retries the backfill.
+    async fn fail_unique_backfill(
+        &mut self,
+        index_id: IndexId,
+        conflicts: Vec<ResolvedDocumentId>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let index_table_id = tx.bootstrap_tables().index_id;
+        let full_index_id = ResolvedDocumentId::new(index_table_id, index_id);
+        let index_doc = tx
+            .get(full_index_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Index {index_id:?} no longer exists"))?;
+        let index_metadata = TabletIndexMetadata::from_document(index_doc)?;
+        let error = SchemaValidationError::UniqueIndexViolation {
+            table_name: tx
+                .table_mapping()
+                .tablet_name(*index_metadata.name.table())?,
+            index_descriptor: index_metadata.name.descriptor().clone(),
+            ids: conflicts.into_iter().map(DocumentIdV6::from).collect(),
+        };
+        log::warn!("Failed backfill of index {}: {error}", index_metadata.name);
+
+        IndexModel::new(&mut tx).drop_index(full_index_id).await?;
+        let mut schema_model = SchemaModel::new(&mut tx);
+        for state in [SchemaState::Pending, SchemaState::Validated] {
+            if let Some((schema_id, _)) = schema_model.get_by_state(state).await? {
+                schema_model.mark_failed(schema_id, error.clone()).await?;
+            }
+        }
+        self.database
+            .commit_with_write_source(tx, "index_worker_fail_unique_backfill")
+            .await?;
+        Ok(())
     }
 
     async fn finish_backfill(&mut self, index_id: IndexId) -> anyhow::Result<()> {
@@ -919,10 +1218,10 @@ impl<RT: Runtime> IndexWriter<RT> {
         index_id: IndexId,
         backfill_begin_ts: Timestamp,
         index_name: TabletIndexName,
-        indexed_fields: IndexedFields,
+        index_config: DeveloperDatabaseIndexConfig,
     ) -> anyhow::Result<()> {
         let min_snapshot_ts = self.retention_validator.min_snapshot_ts().await?;
-        let all_indexes = btreemap! { index_id => (index_name, indexed_fields) };
+        let all_indexes = btreemap! { index_id => (index_name, index_config) };
         // TODO(lee) add checkpointing.
         LeaderRetentionManager::delete_all_no_checkpoint(
             backfill_begin_ts,
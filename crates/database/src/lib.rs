@@ -31,6 +31,7 @@ pub mod query;
 mod reads;
 mod retention;
 mod search_and_vector_bootstrap;
+mod slow_query_log;
 mod snapshot_manager;
 mod stack_traces;
 pub mod subscription;
@@ -68,6 +69,10 @@ pub use reads::{
     OVER_LIMIT_HELP,
 };
 pub use search_index_worker::flusher::SearchIndexFlusher;
+pub use slow_query_log::{
+    SlowQuery,
+    SlowQueryLog,
+};
 pub use table_registry::TableRegistry;
 pub use token::{
     SerializedToken,
@@ -102,6 +107,12 @@ pub use self::{
             IndexTable,
             LegacyIndexDiff,
         },
+        index_aggregates::{
+            IndexAggregateModel,
+            IndexAggregateTotals,
+            IndexAggregatesTable,
+            INDEX_AGGREGATES_TABLE,
+        },
         index_workers::{
             IndexWorkerMetadataTable,
             INDEX_DOC_ID_INDEX,
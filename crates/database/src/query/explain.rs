@@ -0,0 +1,30 @@
+use common::{
+    query::QuerySource,
+    types::IndexName,
+};
+
+/// How a query has executed so far, as reported by
+/// [`CompiledQuery::explain`](super::CompiledQuery::explain).
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryExplanation {
+    /// The source as written, before it's compiled to an index range.
+    pub source: QuerySource,
+    /// The index walked. Full table scans walk `by_creation_time`.
+    pub index_name: IndexName,
+    /// Index entries, or search results, read from the source.
+    pub documents_scanned: usize,
+    /// Documents read from the source but excluded by a `filter`.
+    pub documents_filtered_out: usize,
+    /// Documents the query has returned.
+    pub documents_returned: usize,
+    pub bytes_read: usize,
+    /// The smallest `limit` applied to the query.
+    pub limit: Option<usize>,
+    pub is_approaching_data_limit: bool,
+}
+
+impl QueryExplanation {
+    pub fn is_full_table_scan(&self) -> bool {
+        matches!(self.source, QuerySource::FullTableScan(_))
+    }
+}
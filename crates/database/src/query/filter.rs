@@ -9,6 +9,7 @@ use common::{
 
 use super::{
     IndexRangeResponse,
+    QueryExplanation,
     QueryNode,
     QueryStream,
     QueryStreamNext,
@@ -25,11 +26,16 @@ const FILTER_QUERY_PREFETCH: usize = 100;
 pub(super) struct Filter<T: QueryType> {
     inner: QueryNode<T>,
     expr: Expression,
+    rows_filtered_out: usize,
 }
 
 impl<T: QueryType> Filter<T> {
     pub fn new(inner: QueryNode<T>, expr: Expression) -> Self {
-        Self { inner, expr }
+        Self {
+            inner,
+            expr,
+            rows_filtered_out: 0,
+        }
     }
 }
 
@@ -47,6 +53,11 @@ impl<T: QueryType> QueryStream<T> for Filter<T> {
         self.inner.is_approaching_data_limit()
     }
 
+    fn explain(&self, explanation: &mut QueryExplanation) {
+        self.inner.explain(explanation);
+        explanation.documents_filtered_out += self.rows_filtered_out;
+    }
+
     async fn next<RT: Runtime>(
         &mut self,
         tx: &mut Transaction<RT>,
@@ -65,6 +76,7 @@ impl<T: QueryType> QueryStream<T> for Filter<T> {
             if self.expr.eval(&value)?.into_boolean()? {
                 return Ok(QueryStreamNext::Ready(Some((document, write_timestamp))));
             }
+            self.rows_filtered_out += 1;
         }
     }
 
@@ -5,8 +5,15 @@ use std::{
 
 use async_trait::async_trait;
 use common::{
-    document::GenericDocument,
-    index::IndexKeyBytes,
+    bootstrap_model::index::database_index::IndexedFields,
+    document::{
+        GenericDocument,
+        ID_FIELD_PATH,
+    },
+    index::{
+        multikey_index_values,
+        IndexKeyBytes,
+    },
     interval::Interval,
     knobs::{
         TRANSACTION_MAX_READ_SIZE_BYTES,
@@ -24,11 +31,16 @@ use common::{
     },
     version::Version,
 };
+use value::{
+    values_to_bytes,
+    ConvexObject,
+};
 
 use super::{
     query_scanned_too_many_documents_error,
     query_scanned_too_much_data,
     IndexRangeResponse,
+    QueryExplanation,
     QueryStream,
     QueryStreamNext,
     QueryType,
@@ -78,6 +90,46 @@ pub struct IndexRange<T: QueryType> {
     soft_maximum_rows_read: usize,
     soft_maximum_bytes_read: usize,
     version: Option<Version>,
+    /// Set for multikey indexes, where a document may have several entries
+    /// in the interval but should only be returned once.
+    multikey_dedup: Option<MultikeyDedup>,
+}
+
+struct MultikeyDedup {
+    fields: IndexedFields,
+    multikey_position: usize,
+    interval: Interval,
+}
+
+impl MultikeyDedup {
+    /// Whether `index_key` is `value`'s first entry in the interval, in the
+    /// order it's walked. Only yielding a document at its first entry keeps
+    /// results deduplicated across pages and cursors.
+    fn is_first_entry(
+        &self,
+        index_key: &IndexKeyBytes,
+        value: &ConvexObject,
+        order: Order,
+    ) -> bool {
+        let values = self
+            .fields
+            .iter()
+            .map(|field| value.get_path(field).cloned())
+            .collect();
+        let id = value.get_path(&ID_FIELD_PATH).cloned();
+        let keys_in_interval = multikey_index_values(values, self.multikey_position)
+            .into_iter()
+            .map(|mut values| {
+                values.push(id.clone());
+                IndexKeyBytes(values_to_bytes(&values))
+            })
+            .filter(|key| self.interval.contains(key));
+        let first_key = match order {
+            Order::Asc => keys_in_interval.min(),
+            Order::Desc => keys_in_interval.max(),
+        };
+        first_key.as_ref() == Some(index_key)
+    }
 }
 
 impl<T: QueryType> IndexRange<T> {
@@ -138,9 +190,26 @@ impl<T: QueryType> IndexRange<T> {
                     .min(*TRANSACTION_MAX_READ_SIZE_BYTES),
             ),
             version,
+            multikey_dedup: None,
         }
     }
 
+    /// Deduplicates results from a multikey index on `fields`, where `interval`
+    /// is the full interval passed to [`Self::new`].
+    pub fn with_multikey_dedup(
+        mut self,
+        fields: IndexedFields,
+        multikey_position: usize,
+        interval: Interval,
+    ) -> Self {
+        self.multikey_dedup = Some(MultikeyDedup {
+            fields,
+            multikey_position,
+            interval,
+        });
+        self
+    }
+
     fn start_next<RT: Runtime>(
         &mut self,
         tx: &mut Transaction<RT>,
@@ -161,12 +230,19 @@ impl<T: QueryType> IndexRange<T> {
             return Err(query_scanned_too_much_data(self.returned_bytes).into());
         }
 
-        if let Some((index_position, v, timestamp)) = self.page.pop_front() {
+        while let Some((index_position, v, timestamp)) = self.page.pop_front() {
             let index_bytes = index_position.len();
+            let is_duplicate = self
+                .multikey_dedup
+                .as_ref()
+                .is_some_and(|dedup| !dedup.is_first_entry(&index_position, v.value(), self.order));
             if let Some(intermediate_cursors) = &mut self.intermediate_cursors {
                 intermediate_cursors.push(CursorPosition::After(index_position.clone()));
             }
             self.cursor_interval.curr_exclusive = Some(CursorPosition::After(index_position));
+            if is_duplicate {
+                continue;
+            }
             self.returned_results += 1;
             T::record_read_document(tx, &v, self.printable_index_name.table())?;
             // Database bandwidth for index reads
@@ -252,6 +328,11 @@ impl<T: QueryType> QueryStream<T> for IndexRange<T> {
             || self.returned_bytes > self.soft_maximum_bytes_read
     }
 
+    fn explain(&self, explanation: &mut QueryExplanation) {
+        explanation.documents_scanned += self.rows_read;
+        explanation.bytes_read += self.returned_bytes;
+    }
+
     async fn next<RT: Runtime>(
         &mut self,
         tx: &mut Transaction<RT>,
@@ -8,6 +8,7 @@ use common::{
 
 use super::{
     IndexRangeResponse,
+    QueryExplanation,
     QueryNode,
     QueryStream,
     QueryStreamNext,
@@ -47,6 +48,14 @@ impl<T: QueryType> QueryStream<T> for Limit<T> {
         self.inner.is_approaching_data_limit()
     }
 
+    fn explain(&self, explanation: &mut QueryExplanation) {
+        self.inner.explain(explanation);
+        explanation.limit = Some(match explanation.limit {
+            Some(limit) => limit.min(self.limit),
+            None => self.limit,
+        });
+    }
+
     async fn next<RT: Runtime>(
         &mut self,
         tx: &mut Transaction<RT>,
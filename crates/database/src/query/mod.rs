@@ -1,6 +1,7 @@
 use std::{
     collections::BTreeMap,
     marker::PhantomData,
+    time::Duration,
 };
 
 use anyhow::Context;
@@ -16,12 +17,16 @@ use common::{
     query::{
         Cursor,
         CursorPosition,
+        Expression,
         Query,
         QueryFingerprint,
         QueryOperator,
         QuerySource,
     },
-    runtime::Runtime,
+    runtime::{
+        Runtime,
+        RuntimeInstant,
+    },
     types::{
         IndexName,
         WriteTimestamp,
@@ -49,17 +54,20 @@ use self::{
     search_query::SearchQuery,
 };
 use crate::{
+    slow_query_log::SlowQueryLogger,
     transaction::IndexRangeRequest,
     IndexModel,
     Transaction,
     UserFacingModel,
 };
 
+mod explain;
 mod filter;
 mod index_range;
 mod limit;
 mod search_query;
 
+pub use explain::QueryExplanation;
 pub use index_range::soft_data_limit;
 
 // Even in the presence of large prefetch hints, we should never fetch too much
@@ -93,6 +101,9 @@ trait QueryStream<T: QueryType>: Send {
     /// instead.
     fn is_approaching_data_limit(&self) -> bool;
 
+    /// Add what this stage has read and dropped so far to `explanation`.
+    fn explain(&self, explanation: &mut QueryExplanation);
+
     /// Pull a value out from the query pipeline. The query has completed after
     /// returning `None`, and `.next()` should not be called again. If this
     /// method returns an error, it is safe to retry calling `.next()`, but
@@ -226,6 +237,12 @@ pub struct CompiledQuery<RT: Runtime, T: QueryType> {
     root: QueryNode<T>,
     query_fingerprint: QueryFingerprint,
     end_cursor: Option<Cursor>,
+    /// For `explain`.
+    source: QuerySource,
+    index_name: IndexName,
+    documents_returned: usize,
+    fetch_time: Duration,
+    slow_query_logger: Option<SlowQueryLogger>,
     _marker: PhantomData<(RT, T)>,
 }
 
@@ -313,6 +330,7 @@ impl<RT: Runtime, T: QueryType> CompiledQuery<RT, T> {
         version: Option<Version>,
         table_filter: TableFilter,
     ) -> anyhow::Result<Self> {
+        let source = query.source.clone();
         let index_name = match query.source {
             QuerySource::FullTableScan(ref full_table_scan) => {
                 let table_name = full_table_scan.table_name.clone();
@@ -327,10 +345,17 @@ impl<RT: Runtime, T: QueryType> CompiledQuery<RT, T> {
             QuerySource::Search(ref search) => search.index_name.clone(),
         };
         let stable_index_name = IndexModel::new(tx).stable_index_name(&index_name, table_filter)?;
+        let mut multikey_position = None;
         let indexed_fields = match query.source {
             QuerySource::FullTableScan(_) => IndexedFields::creation_time(),
             QuerySource::IndexRange(_) => {
-                IndexModel::new(tx).indexed_fields(&stable_index_name, &index_name)?
+                let developer_config =
+                    IndexModel::new(tx).database_index_config(&stable_index_name, &index_name)?;
+                if let Some(ref index_filter) = developer_config.filter {
+                    check_partial_index_filter(&index_name, index_filter, &query.operators)?;
+                }
+                multikey_position = developer_config.multikey_position();
+                developer_config.fields
             },
             QuerySource::Search(_) => {
                 // Hack! Search indexes don't have any concept of indexed fields.
@@ -364,7 +389,7 @@ impl<RT: Runtime, T: QueryType> CompiledQuery<RT, T> {
         let mut cur_node = match query.source {
             QuerySource::FullTableScan(full_table_scan) => QueryNode::IndexRange(IndexRange::new(
                 stable_index_name,
-                index_name,
+                index_name.clone(),
                 Interval::all(),
                 full_table_scan.order,
                 cursor_interval,
@@ -378,18 +403,27 @@ impl<RT: Runtime, T: QueryType> CompiledQuery<RT, T> {
                 let virtual_table_mapping = tx.virtual_table_mapping().clone();
                 let virtual_table_number_map = stable_index_name
                     .virtual_table_number_map(tx.table_mapping(), &virtual_table_mapping)?;
-                let interval = index_range.compile(indexed_fields, virtual_table_number_map)?;
-                QueryNode::IndexRange(IndexRange::new(
+                let interval =
+                    index_range.compile(indexed_fields.clone(), virtual_table_number_map)?;
+                let mut index_range = IndexRange::new(
                     stable_index_name,
-                    index_name,
-                    interval,
+                    index_name.clone(),
+                    interval.clone(),
                     order,
                     cursor_interval,
                     maximum_rows_read,
                     maximum_bytes_read,
                     should_compute_split_cursor,
                     version,
-                ))
+                );
+                if let Some(multikey_position) = multikey_position {
+                    index_range = index_range.with_multikey_dedup(
+                        indexed_fields,
+                        multikey_position,
+                        interval,
+                    );
+                }
+                QueryNode::IndexRange(index_range)
             },
             QuerySource::Search(search) => {
                 QueryNode::Search(SearchQuery::new(search, cursor_interval, version))
@@ -412,6 +446,11 @@ impl<RT: Runtime, T: QueryType> CompiledQuery<RT, T> {
             root: cur_node,
             query_fingerprint: fingerprint,
             end_cursor,
+            source,
+            index_name,
+            documents_returned: 0,
+            fetch_time: Duration::ZERO,
+            slow_query_logger: tx.slow_query_logger(),
             _marker: PhantomData,
         })
     }
@@ -512,6 +551,31 @@ impl<RT: Runtime, T: QueryType> CompiledQuery<RT, T> {
     pub fn is_approaching_data_limit(&self) -> bool {
         self.root.is_approaching_data_limit()
     }
+
+    /// Describe how the query has executed so far: its source and index, and
+    /// how many documents it has scanned, filtered out and returned.
+    pub fn explain(&self) -> QueryExplanation {
+        let mut explanation = QueryExplanation {
+            source: self.source.clone(),
+            index_name: self.index_name.clone(),
+            documents_scanned: 0,
+            documents_filtered_out: 0,
+            documents_returned: self.documents_returned,
+            bytes_read: 0,
+            limit: None,
+            is_approaching_data_limit: self.is_approaching_data_limit(),
+        };
+        self.root.explain(&mut explanation);
+        explanation
+    }
+}
+
+impl<RT: Runtime, T: QueryType> Drop for CompiledQuery<RT, T> {
+    fn drop(&mut self) {
+        if let Some(slow_query_logger) = self.slow_query_logger.take() {
+            slow_query_logger.log_if_slow(&self.query_fingerprint, self.explain(), self.fetch_time);
+        }
+    }
 }
 
 pub async fn query_batch_next<RT: Runtime, T: QueryType>(
@@ -524,12 +588,18 @@ pub async fn query_batch_next<RT: Runtime, T: QueryType>(
     // Accumulate fetch (IO) requests and perform them all in a batch.
     // Call `feed` on the queries with the responses from the fetch requests.
     // Repeat until all queries have returned Ready from `next`.
+    //
+    // Each query's fetch time covers its own `next` and `feed` calls and the
+    // batched fetches it waited on, but not the time spent on other queries.
     let mut results = BTreeMap::new();
     while !batch.is_empty() {
         let mut batch_to_feed = BTreeMap::new();
         let mut requests = BTreeMap::new();
         for (batch_key, (query, prefetch_hint)) in batch {
-            match query.root.next(tx, prefetch_hint).await {
+            let start = tx.runtime().monotonic_now();
+            let result = query.root.next(tx, prefetch_hint).await;
+            query.fetch_time += start.elapsed();
+            match result {
                 Err(e) => {
                     results.insert(batch_key, Err(e));
                 },
@@ -538,19 +608,26 @@ pub async fn query_batch_next<RT: Runtime, T: QueryType>(
                     batch_to_feed.insert(batch_key, (query, prefetch_hint));
                 },
                 Ok(QueryStreamNext::Ready(result)) => {
+                    if result.is_some() {
+                        query.documents_returned += 1;
+                    }
                     results.insert(batch_key, Ok(result));
                 },
             }
         }
+        let fetch_start = tx.runtime().monotonic_now();
         let mut responses = T::index_range_batch(tx, requests).await;
+        let fetch_time = fetch_start.elapsed();
         let mut next_batch = BTreeMap::new();
         for (batch_key, (query, prefetch_hint)) in batch_to_feed {
+            let start = tx.runtime().monotonic_now();
             let result: anyhow::Result<_> = try {
                 let index_range_responses = responses
                     .remove(&batch_key)
                     .context("batch_key missing")??;
                 query.root.feed(index_range_responses)?;
             };
+            query.fetch_time += fetch_time + start.elapsed();
             match result {
                 Err(e) => {
                     results.insert(batch_key, Err(e));
@@ -602,6 +679,15 @@ impl<T: QueryType> QueryStream<T> for QueryNode<T> {
         }
     }
 
+    fn explain(&self, explanation: &mut QueryExplanation) {
+        match self {
+            Self::IndexRange(r) => r.explain(explanation),
+            Self::Search(r) => r.explain(explanation),
+            Self::Filter(r) => r.explain(explanation),
+            Self::Limit(r) => r.explain(explanation),
+        }
+    }
+
     async fn next<RT: Runtime>(
         &mut self,
         tx: &mut Transaction<RT>,
@@ -625,6 +711,37 @@ impl<T: QueryType> QueryStream<T> for QueryNode<T> {
     }
 }
 
+/// A partial index only contains documents matching its filter, so it can
+/// only answer queries that apply the same filter before any limit.
+fn check_partial_index_filter(
+    index_name: &IndexName,
+    index_filter: &Expression,
+    operators: &[QueryOperator],
+) -> anyhow::Result<()> {
+    let query_conjuncts: Vec<&Expression> = operators
+        .iter()
+        .map_while(|operator| match operator {
+            QueryOperator::Filter(expr) => Some(expr),
+            QueryOperator::Limit(_) => None,
+        })
+        .flat_map(|expr| expr.conjuncts())
+        .collect();
+    anyhow::ensure!(
+        index_filter
+            .conjuncts()
+            .into_iter()
+            .all(|conjunct| query_conjuncts.contains(&conjunct)),
+        ErrorMetadata::bad_request(
+            "PartialIndexFilterRequired",
+            format!(
+                "Index {index_name} is a partial index, so queries using it must filter on the \
+                 index's filter before any limit."
+            ),
+        )
+    );
+    Ok(())
+}
+
 /// Return a system limit for reading too many documents in a query
 fn query_scanned_too_many_documents_error(num_documents: usize) -> ErrorMetadata {
     ErrorMetadata::pagination_limit(
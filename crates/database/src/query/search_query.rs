@@ -28,6 +28,7 @@ use super::{
         CursorInterval,
     },
     IndexRangeResponse,
+    QueryExplanation,
     QueryStream,
     QueryStreamNext,
     QueryType,
@@ -134,6 +135,13 @@ impl<T: QueryType> QueryStream<T> for SearchQuery<T> {
             .map_or(false, |results| results.is_approaching_data_limit())
     }
 
+    fn explain(&self, explanation: &mut QueryExplanation) {
+        if let Some(results) = &self.results {
+            explanation.documents_scanned += results.next_index;
+            explanation.bytes_read += results.bytes_read;
+        }
+    }
+
     async fn next<RT: Runtime>(
         &mut self,
         tx: &mut Transaction<RT>,
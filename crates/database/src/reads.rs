@@ -27,18 +27,8 @@ use common::{
 };
 use errors::ErrorMetadata;
 use pb::{
-    convex_token::{
-        search_text_query_term::Term::{
-            Exact as ExactProto,
-            Fuzzy as FuzzyProto,
-        },
-        FieldPath as FieldPathProto,
-        SearchExactTextTerm as SearchExactTextTermProto,
-        SearchFuzzyTextTerm as SearchFuzzyTextTermProto,
-        SearchTextQueryTerm as SearchTextQueryTermProto,
-    },
+    convex_token::FieldPath as FieldPathProto,
     funrun::{
-        FilterConditionRead as FilterConditionReadProto,
         IndexReads as IndexReadsProto,
         ReadSet as ReadSetProto,
         SearchQueryReads as SearchQueryReadsProto,
@@ -46,11 +36,8 @@ use pb::{
     },
 };
 use search::{
-    query::TextQueryTerm,
-    FilterConditionRead,
     QueryReads,
     QueryReads as SearchQueryReads,
-    TextQueryTermRead,
 };
 use usage_tracking::FunctionUsageTracker;
 use value::{
@@ -136,52 +123,10 @@ impl From<ReadSet> for ReadSetProto {
             .collect::<Vec<IndexReadsProto>>();
         let search = search
             .into_iter()
-            .map(
-                |(
-                    index_name,
-                    QueryReads {
-                        text_queries,
-                        filter_conditions,
-                        ..
-                    },
-                )| {
-                    let text_queries = text_queries
-                        .into_iter()
-                        .map(|text_query_term| SearchTextQueryTermProto {
-                            field_path: Some(text_query_term.field_path.clone().into()),
-                            term: Some(match text_query_term.term {
-                                TextQueryTerm::Exact(token) => {
-                                    ExactProto(SearchExactTextTermProto { token })
-                                },
-                                TextQueryTerm::Fuzzy {
-                                    token,
-                                    max_distance,
-                                    prefix,
-                                } => FuzzyProto(SearchFuzzyTextTermProto {
-                                    token,
-                                    max_distance: (*max_distance).into(),
-                                    prefix,
-                                }),
-                            }),
-                        })
-                        .collect();
-                    SearchQueryReadsProto {
-                        index_name: Some(index_name.into()),
-                        text_queries,
-                        filter_conditions: filter_conditions
-                            .into_iter()
-                            .map(|filter_condition| match filter_condition {
-                                FilterConditionRead::Must(field_path, filter_value) => {
-                                    FilterConditionReadProto {
-                                        field_path: Some(field_path.into()),
-                                        filter_value: Some(filter_value),
-                                    }
-                                },
-                            })
-                            .collect(),
-                    }
-                },
-            )
+            .map(|(index_name, reads)| SearchQueryReadsProto {
+                index_name: Some(index_name.into()),
+                ..reads.into()
+            })
             .collect::<Vec<SearchQueryReadsProto>>();
         Self { indexed, search }
     }
@@ -218,57 +163,15 @@ impl TryFrom<ReadSetProto> for ReadSet {
             .into();
         let search = search
             .into_iter()
-            .map(
-                |SearchQueryReadsProto {
-                     index_name,
-                     text_queries,
-                     filter_conditions,
-                 }| {
-                    let k = index_name
-                        .ok_or_else(|| anyhow::anyhow!("Missing index_name"))?
-                        .try_into()?;
-                    let text_queries = text_queries
-                        .into_iter()
-                        .map(|SearchTextQueryTermProto { field_path, term }| {
-                            let field_path = field_path
-                                .ok_or_else(|| anyhow::anyhow!("Missing field_path"))?
-                                .try_into()?;
-                            let term = match term.ok_or_else(|| anyhow::anyhow!("Missing term!"))? {
-                                ExactProto(exact) => TextQueryTerm::Exact(exact.token),
-                                FuzzyProto(fuzzy) => TextQueryTerm::Fuzzy {
-                                    token: fuzzy.token,
-                                    max_distance: u8::try_from(fuzzy.max_distance)?.try_into()?,
-                                    prefix: fuzzy.prefix,
-                                },
-                            };
-                            anyhow::Ok(TextQueryTermRead::new(field_path, term))
-                        })
-                        .try_collect::<Vec<_>>()?
-                        .into();
-
-                    let filter_conditions = filter_conditions
-                        .into_iter()
-                        .map(
-                            |FilterConditionReadProto {
-                                 field_path,
-                                 filter_value,
-                             }| {
-                                let q = FilterConditionRead::Must(
-                                    field_path
-                                        .ok_or_else(|| anyhow::anyhow!("Missing field_path"))?
-                                        .try_into()?,
-                                    filter_value
-                                        .ok_or_else(|| anyhow::anyhow!("Missing filter_value"))?,
-                                );
-                                anyhow::Ok::<FilterConditionRead>(q)
-                            },
-                        )
-                        .try_collect::<Vec<_>>()?
-                        .into();
-                    let v = QueryReads::new(text_queries, filter_conditions);
-                    Ok((k, v))
-                },
-            )
+            .map(|mut reads| {
+                let k = reads
+                    .index_name
+                    .take()
+                    .ok_or_else(|| anyhow::anyhow!("Missing index_name"))?
+                    .try_into()?;
+                let v = QueryReads::try_from(reads)?;
+                anyhow::Ok((k, v))
+            })
             .collect::<anyhow::Result<BTreeMap<_, _>>>()?
             .into();
         Ok(Self { indexed, search })
@@ -317,8 +220,14 @@ impl ReadSet {
         ) in self.indexed.iter()
         {
             if *index.table() == document.table().table_id {
-                let index_key = document.index_key(fields, persistence_version).into_bytes();
-                if intervals.contains(&index_key) {
+                // Reads don't know whether the index is multikey, so check every
+                // key the document could have in it.
+                let index_keys = document.possible_index_keys(fields, persistence_version);
+                if let Some(index_key) = index_keys
+                    .into_iter()
+                    .map(|key| key.into_bytes())
+                    .find(|key| intervals.contains(key))
+                {
                     let stack_traces = stack_traces.as_ref().map(|st| {
                         st.iter()
                             .filter_map(|(interval, trace)| {
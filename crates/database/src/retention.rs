@@ -6,6 +6,7 @@ use std::{
     collections::{
         hash_map::DefaultHasher,
         BTreeMap,
+        BTreeSet,
     },
     hash::{
         Hash,
@@ -22,7 +23,7 @@ use common::{
     bootstrap_model::index::{
         database_index::{
             DatabaseIndexState,
-            IndexedFields,
+            DeveloperDatabaseIndexConfig,
         },
         IndexConfig,
         IndexMetadata,
@@ -511,7 +512,7 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
         reader: RepeatablePersistence,
         cursor: Timestamp,
         min_snapshot_ts: Timestamp,
-        all_indexes: &BTreeMap<IndexId, (GenericIndexName<TableId>, IndexedFields)>,
+        all_indexes: &BTreeMap<IndexId, (GenericIndexName<TableId>, DeveloperDatabaseIndexConfig)>,
         persistence_version: PersistenceVersion,
     ) {
         tracing::trace!(
@@ -526,9 +527,9 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
                 let chunk = chunk?.to_vec();
                 let mut entries_to_delete = vec![];
                 // Prev revs are the documents we are deleting.
-                // Each prev rev has 1 or 2 index entries to delete per index -- one entry at
-                // the prev rev's ts, and a tombstone at the current rev's ts if
-                // the document was deleted or its index key changed.
+                // Each prev rev has 1 or 2 index entries to delete per index key -- one
+                // entry at the prev rev's ts, and a tombstone at the current rev's ts if
+                // the document was deleted or no longer has that index key.
                 let prev_revs = reader_
                     .previous_revisions(chunk.iter().map(|(ts, id, _)| (*id, *ts)).collect())
                     .await?;
@@ -553,44 +554,51 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
                         continue;
                     };
                     log_retention_scanned_document(maybe_doc.is_none(), true);
-                    for (index_id, (_, index_fields)) in all_indexes
+                    for (index_id, (_, index_config)) in all_indexes
                         .iter()
                         .filter(|(_, (index, _))| *index.table() == *id.table())
                     {
-                        let index_key = prev_rev
-                            .index_key(index_fields, persistence_version)
-                            .into_bytes();
-                        let key_sha256 = Sha256::hash(&index_key);
-                        let key = SplitKey::new(index_key.clone().0);
-                        log_retention_expired_index_entry(false, false);
-                        entries_to_delete.push(IndexEntry {
-                            index_id: *index_id,
-                            key_prefix: key.prefix.clone(),
-                            key_suffix: key.suffix.clone(),
-                            key_sha256: key_sha256.to_vec(),
-                            ts: *prev_rev_ts,
-                            deleted: false,
-                        });
-                        match maybe_doc.as_ref() {
-                            Some(doc) => {
-                                let next_index_key = doc
-                                    .index_key(index_fields, persistence_version)
-                                    .into_bytes();
-                                if index_key == next_index_key {
-                                    continue;
-                                }
-                                log_retention_expired_index_entry(true, true);
-                            },
-                            None => log_retention_expired_index_entry(true, false),
+                        // Partial and multikey indexes may have any number of entries
+                        // per revision, so compare the full sets of keys.
+                        let next_index_keys: BTreeSet<_> = match maybe_doc.as_ref() {
+                            Some(doc) => index_config
+                                .index_keys(doc, persistence_version)
+                                .into_iter()
+                                .map(|key| key.into_bytes())
+                                .collect(),
+                            None => BTreeSet::new(),
+                        };
+                        for index_key in index_config.index_keys(prev_rev, persistence_version) {
+                            let index_key = index_key.into_bytes();
+                            let key_sha256 = Sha256::hash(&index_key);
+                            let key = SplitKey::new(index_key.clone().0);
+                            log_retention_expired_index_entry(false, false);
+                            entries_to_delete.push(IndexEntry {
+                                index_id: *index_id,
+                                key_prefix: key.prefix.clone(),
+                                key_suffix: key.suffix.clone(),
+                                key_sha256: key_sha256.to_vec(),
+                                ts: *prev_rev_ts,
+                                deleted: false,
+                            });
+                            match maybe_doc.as_ref() {
+                                Some(_) => {
+                                    if next_index_keys.contains(&index_key) {
+                                        continue;
+                                    }
+                                    log_retention_expired_index_entry(true, true);
+                                },
+                                None => log_retention_expired_index_entry(true, false),
+                            }
+                            entries_to_delete.push(IndexEntry {
+                                index_id: *index_id,
+                                key_prefix: key.prefix,
+                                key_suffix: key.suffix,
+                                key_sha256: key_sha256.to_vec(),
+                                ts,
+                                deleted: true,
+                            });
                         }
-                        entries_to_delete.push(IndexEntry {
-                            index_id: *index_id,
-                            key_prefix: key.prefix,
-                            key_suffix: key.suffix,
-                            key_sha256: key_sha256.to_vec(),
-                            ts,
-                            deleted: true,
-                        });
                     }
                 }
                 anyhow::Ok(entries_to_delete)
@@ -615,7 +623,7 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
         persistence: Arc<dyn Persistence>,
         rt: &RT,
         cursor: Timestamp,
-        all_indexes: &BTreeMap<IndexId, (GenericIndexName<TableId>, IndexedFields)>,
+        all_indexes: &BTreeMap<IndexId, (GenericIndexName<TableId>, DeveloperDatabaseIndexConfig)>,
         retention_validator: Arc<dyn RetentionValidator>,
     ) -> anyhow::Result<(Timestamp, usize)> {
         if !*RETENTION_DELETES_ENABLED || min_snapshot_ts == Timestamp::MIN {
@@ -682,7 +690,7 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
         min_snapshot_ts: Timestamp,
         persistence: Arc<dyn Persistence>,
         rt: &RT,
-        all_indexes: &BTreeMap<IndexId, (GenericIndexName<TableId>, IndexedFields)>,
+        all_indexes: &BTreeMap<IndexId, (GenericIndexName<TableId>, DeveloperDatabaseIndexConfig)>,
         retention_validator: Arc<dyn RetentionValidator>,
     ) -> anyhow::Result<()> {
         while cursor_ts.succ()? < min_snapshot_ts {
@@ -1008,7 +1016,10 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
         bounds_reader: Reader<SnapshotBounds>,
         rt: RT,
         persistence: Arc<dyn Persistence>,
-        mut all_indexes: BTreeMap<IndexId, (GenericIndexName<TableId>, IndexedFields)>,
+        mut all_indexes: BTreeMap<
+            IndexId,
+            (GenericIndexName<TableId>, DeveloperDatabaseIndexConfig),
+        >,
         index_table_id: TableIdAndTableNumber,
         mut index_cursor: Timestamp,
         retention_validator: Arc<dyn RetentionValidator>,
@@ -1286,7 +1297,10 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
 
     fn accumulate_index_document(
         maybe_doc: Option<ResolvedDocument>,
-        all_indexes: &mut BTreeMap<IndexId, (GenericIndexName<TableId>, IndexedFields)>,
+        all_indexes: &mut BTreeMap<
+            IndexId,
+            (GenericIndexName<TableId>, DeveloperDatabaseIndexConfig),
+        >,
         index_table_id: TableIdAndTableNumber,
     ) -> anyhow::Result<()> {
         let Some(doc) = maybe_doc else {
@@ -1317,13 +1331,16 @@ impl<RT: Runtime> LeaderRetentionManager<RT> {
             }
         }
 
-        all_indexes.insert(index_id, (index.name, developer_config.fields));
+        all_indexes.insert(index_id, (index.name, developer_config));
         Ok(())
     }
 
     async fn accumulate_indexes(
         persistence: &dyn Persistence,
-        all_indexes: &mut BTreeMap<IndexId, (GenericIndexName<TableId>, IndexedFields)>,
+        all_indexes: &mut BTreeMap<
+            IndexId,
+            (GenericIndexName<TableId>, DeveloperDatabaseIndexConfig),
+        >,
         cursor: &mut Timestamp,
         latest_ts: RepeatableTimestamp,
         index_table_id: TableIdAndTableNumber,
@@ -1721,8 +1738,8 @@ mod tests {
         let reader = RepeatablePersistence::new(reader, repeatable_ts, retention_validator.clone());
 
         let all_indexes = btreemap!(
-            by_id_index_id => (GenericIndexName::by_id(table_id), IndexedFields::by_id()),
-            by_val_index_id => (GenericIndexName::new(table_id, "by_val".parse()?)?, IndexedFields::try_from(vec!["value".parse()?])?),
+            by_id_index_id => (GenericIndexName::by_id(table_id), IndexedFields::by_id().into()),
+            by_val_index_id => (GenericIndexName::new(table_id, "by_val".parse()?)?, IndexedFields::try_from(vec!["value".parse()?])?.into()),
         );
         let expired_stream = LeaderRetentionManager::<TestRuntime>::expired_index_entries(
             reader,
@@ -0,0 +1,95 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    knobs::{
+        SLOW_QUERY_DOCUMENTS_SCANNED_THRESHOLD,
+        SLOW_QUERY_LOG_SIZE,
+        SLOW_QUERY_THRESHOLD,
+    },
+    query::QueryFingerprint,
+    runtime::UnixTimestamp,
+};
+use parking_lot::Mutex;
+
+use crate::query::QueryExplanation;
+
+/// A query that took too long or read too much, as configured by the
+/// `SLOW_QUERY_*` knobs.
+#[derive(Clone, Debug)]
+pub struct SlowQuery {
+    pub udf_path: String,
+    pub query_fingerprint: QueryFingerprint,
+    pub explanation: QueryExplanation,
+    /// Time spent fetching the query's documents, not including time the
+    /// function spent between reads.
+    pub fetch_time: Duration,
+    pub started_at: UnixTimestamp,
+}
+
+/// The most recent slow queries run by functions, kept in memory.
+#[derive(Clone, Default)]
+pub struct SlowQueryLog {
+    queries: Arc<Mutex<VecDeque<SlowQuery>>>,
+}
+
+impl SlowQueryLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The logged queries, oldest first.
+    pub fn queries(&self) -> Vec<SlowQuery> {
+        self.queries.lock().iter().cloned().collect()
+    }
+
+    fn record(&self, query: SlowQuery) {
+        let mut queries = self.queries.lock();
+        while queries.len() >= *SLOW_QUERY_LOG_SIZE {
+            queries.pop_front();
+        }
+        queries.push_back(query);
+    }
+}
+
+/// Logs a single query to the [`SlowQueryLog`] when it finishes, if it was
+/// slow.
+pub(crate) struct SlowQueryLogger {
+    log: SlowQueryLog,
+    udf_path: String,
+    started_at: UnixTimestamp,
+}
+
+impl SlowQueryLogger {
+    pub(crate) fn new(log: SlowQueryLog, udf_path: String, started_at: UnixTimestamp) -> Self {
+        Self {
+            log,
+            udf_path,
+            started_at,
+        }
+    }
+
+    pub(crate) fn log_if_slow(
+        self,
+        query_fingerprint: &QueryFingerprint,
+        explanation: QueryExplanation,
+        fetch_time: Duration,
+    ) {
+        let is_slow = fetch_time >= *SLOW_QUERY_THRESHOLD
+            || explanation.documents_scanned >= *SLOW_QUERY_DOCUMENTS_SCANNED_THRESHOLD
+            || explanation.is_approaching_data_limit;
+        if !is_slow {
+            return;
+        }
+        self.log.record(SlowQuery {
+            udf_path: self.udf_path,
+            query_fingerprint: query_fingerprint.clone(),
+            explanation,
+            fetch_time,
+            started_at: self.started_at,
+        });
+    }
+}
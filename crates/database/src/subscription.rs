@@ -340,9 +340,10 @@ impl SubscriptionManager {
     ) {
         for (index, (fields, range_map)) in &self.subscriptions.indexed {
             if *index.table() == document.table().table_id {
-                let index_key = document.index_key(fields, persistence_version);
-                for subscriber_id in range_map.query(index_key.into_bytes()) {
-                    to_notify.insert(subscriber_id);
+                for index_key in document.possible_index_keys(fields, persistence_version) {
+                    for subscriber_id in range_map.query(index_key.into_bytes()) {
+                        to_notify.insert(subscriber_id);
+                    }
                 }
             }
         }
@@ -37,6 +37,8 @@ use common::{
     },
     value::{
         ConvexObject,
+        FieldPath,
+        IdentifierFieldName,
         JsonInteger,
         Size,
         TableId,
@@ -127,6 +129,13 @@ impl TableSummary {
         &self.inferred_type
     }
 
+    /// Estimates how many entries a multikey index on `field` has: one per
+    /// array element, and one per document where the field isn't an array.
+    /// Duplicate elements within an array are counted more than once.
+    pub fn multikey_index_entries(&self, field: &FieldPath) -> u64 {
+        multikey_index_entries(&self.inferred_type, field.fields())
+    }
+
     pub fn insert(&self, object: &ConvexObject) -> Self {
         let total_size = self.total_size + object.size() as i64;
         let total_size_rounded =
@@ -162,6 +171,28 @@ impl TableSummary {
     }
 }
 
+fn multikey_index_entries(
+    shape: &CountedShape<ProdConfigWithOptionalFields>,
+    path: &[IdentifierFieldName],
+) -> u64 {
+    match (shape.variant(), path) {
+        (ShapeEnum::Union(union), _) => union
+            .iter()
+            .map(|variant| multikey_index_entries(variant, path))
+            .sum(),
+        (ShapeEnum::Array(array), []) => *array.element().num_values(),
+        (ShapeEnum::Object(object), [first, rest @ ..]) => match object.get(first) {
+            // Documents missing the field have a single entry.
+            Some(field) => {
+                multikey_index_entries(&field.value_shape, rest) + shape.num_values()
+                    - field.value_shape.num_values()
+            },
+            None => *shape.num_values(),
+        },
+        _ => *shape.num_values(),
+    }
+}
+
 impl From<&TableSummary> for JsonValue {
     fn from(summary: &TableSummary) -> Self {
         json!({
@@ -10,25 +10,34 @@ use std::{
 };
 
 use ::usage_tracking::FunctionUsageTracker;
+use anyhow::Context;
 use common::{
     assert_obj,
-    bootstrap_model::index::{
-        database_index::{
-            DeveloperDatabaseIndexConfig,
-            IndexedFields,
+    bootstrap_model::{
+        index::{
+            database_index::{
+                DeveloperDatabaseIndexConfig,
+                IndexAggregate,
+                IndexedFields,
+            },
+            IndexConfig,
+            IndexMetadata,
         },
-        IndexConfig,
-        IndexMetadata,
+        schema::SchemaState,
     },
     db_schema,
     document::{
         CreationTime,
         ResolvedDocument,
     },
+    interval::Interval,
     maybe_val,
     object_validator,
     pause::PauseClient,
-    persistence::NoopRetentionValidator,
+    persistence::{
+        NoopRetentionValidator,
+        Persistence,
+    },
     query::{
         Expression,
         FullTableScan,
@@ -46,7 +55,9 @@ use common::{
         },
         DatabaseSchema,
         DocumentSchema,
+        ForeignKey,
         IndexSchema,
+        OnDelete,
         TableDefinition,
         MAX_INDEXES_PER_TABLE,
     },
@@ -67,6 +78,10 @@ use errors::{
 };
 use imbl::OrdSet;
 use keybroker::Identity;
+use maplit::{
+    btreemap,
+    btreeset,
+};
 use must_let::must_let;
 use pb::funrun::BootstrapMetadata as BootstrapMetadataProto;
 use pretty_assertions::assert_eq;
@@ -86,12 +101,14 @@ use value::{
 };
 
 use crate::{
+    defaults::SystemTable,
     index_worker::{
         IndexSelector,
         IndexWriter,
     },
     query::{
         CompiledQuery,
+        IndexRangeResponse,
         Resolved,
         TableFilter,
     },
@@ -105,11 +122,15 @@ use crate::{
         DbFixtures,
         DbFixturesArgs,
     },
+    transaction::IndexRangeRequest,
     write_log::WriteSource,
     BootstrapMetadata,
     Database,
     DatabaseSnapshot,
     ImportFacingModel,
+    IndexAggregateModel,
+    IndexAggregateTotals,
+    IndexAggregatesTable,
     IndexModel,
     IndexWorker,
     ResolvedQuery as CompiledResolvedQuery,
@@ -119,6 +140,7 @@ use crate::{
     TestFacingModel,
     Transaction,
     UserFacingModel,
+    INDEX_AGGREGATES_TABLE,
 };
 
 mod randomized_search_tests;
@@ -245,6 +267,10 @@ async fn test_build_indexes(rt: TestRuntime) -> anyhow::Result<()> {
         IndexSchema {
             index_descriptor: index_name1.descriptor().clone(),
             fields: vec![str::parse("a")?, str::parse("b")?].try_into()?,
+            unique: false,
+            filter: None,
+            multikey_field: None,
+            aggregate: None,
         },
     );
     indexes.insert(
@@ -252,6 +278,10 @@ async fn test_build_indexes(rt: TestRuntime) -> anyhow::Result<()> {
         IndexSchema {
             index_descriptor: index_name2.descriptor().clone(),
             fields: vec![str::parse("c")?, str::parse("d")?].try_into()?,
+            unique: false,
+            filter: None,
+            multikey_field: None,
+            aggregate: None,
         },
     );
 
@@ -264,6 +294,8 @@ async fn test_build_indexes(rt: TestRuntime) -> anyhow::Result<()> {
             search_indexes: BTreeMap::new(),
             vector_indexes: BTreeMap::new(),
             document_type: None,
+            ttl: None,
+            foreign_keys: BTreeMap::new(),
         },
     );
     let schema = DatabaseSchema {
@@ -299,6 +331,10 @@ async fn test_build_indexes(rt: TestRuntime) -> anyhow::Result<()> {
         IndexSchema {
             index_descriptor: index_name2.descriptor().clone(),
             fields: vec![str::parse("c")?].try_into()?,
+            unique: false,
+            filter: None,
+            multikey_field: None,
+            aggregate: None,
         },
     );
     indexes.insert(
@@ -306,6 +342,10 @@ async fn test_build_indexes(rt: TestRuntime) -> anyhow::Result<()> {
         IndexSchema {
             index_descriptor: index_name3.descriptor().clone(),
             fields: vec![str::parse("e")?, str::parse("f")?].try_into()?,
+            unique: false,
+            filter: None,
+            multikey_field: None,
+            aggregate: None,
         },
     );
 
@@ -318,6 +358,8 @@ async fn test_build_indexes(rt: TestRuntime) -> anyhow::Result<()> {
             search_indexes: BTreeMap::new(),
             vector_indexes: BTreeMap::new(),
             document_type: None,
+            ttl: None,
+            foreign_keys: BTreeMap::new(),
         },
     );
     let schema = DatabaseSchema {
@@ -370,7 +412,7 @@ fn get_pending_index_fields(
         .pending_index_metadata(index_name)?
         .expect("index should exist");
     must_let!(let IndexConfig::Database { developer_config, .. } = &index_c_d.config);
-    must_let!(let DeveloperDatabaseIndexConfig { fields } = developer_config);
+    must_let!(let DeveloperDatabaseIndexConfig { fields, .. } = developer_config);
     Ok(fields.clone())
 }
 
@@ -597,6 +639,58 @@ async fn test_query_limit(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_query_explain(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = new_test_database(rt).await;
+    let mut tx = database.begin(Identity::system()).await?;
+    for channel in ["eng", "general", "eng", "eng"] {
+        TestFacingModel::new(&mut tx)
+            .insert(
+                &"messages".parse()?,
+                assert_obj!(
+                    "channel" => channel,
+                    "text" => "hello",
+                ),
+            )
+            .await?;
+    }
+    database.commit(tx).await?;
+
+    let query = Query {
+        source: QuerySource::FullTableScan(FullTableScan {
+            table_name: "messages".parse()?,
+            order: Order::Asc,
+        }),
+        operators: vec![
+            QueryOperator::Filter(Expression::Eq(
+                Box::new(Expression::Literal(maybe_val!("eng"))),
+                Box::new(Expression::Field("channel".parse()?)),
+            )),
+            QueryOperator::Limit(2),
+        ],
+    };
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut query_stream = CompiledResolvedQuery::new(&mut tx, query)?;
+    while query_stream
+        .next(&mut tx, Some(TEST_PREFETCH_HINT))
+        .await?
+        .is_some()
+    {}
+    let explanation = query_stream.explain();
+    assert!(explanation.is_full_table_scan());
+    assert_eq!(
+        explanation.index_name,
+        IndexName::by_creation_time("messages".parse()?)
+    );
+    assert_eq!(explanation.documents_returned, 2);
+    assert_eq!(explanation.documents_filtered_out, 1);
+    assert!(explanation.documents_scanned >= 3);
+    assert!(explanation.bytes_read > 0);
+    assert_eq!(explanation.limit, Some(2));
+    assert!(!explanation.is_approaching_data_limit);
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_full_table_scan_order(rt: TestRuntime) -> anyhow::Result<()> {
     let database = new_test_database(rt).await;
@@ -1669,6 +1763,761 @@ async fn test_index_backfill(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn add_unique_email_index(
+    rt: TestRuntime,
+    db: &Database<TestRuntime>,
+    tp: Arc<dyn Persistence>,
+    index_name: &IndexName,
+) -> anyhow::Result<()> {
+    let mut tx = db.begin_system().await?;
+    let begin_ts = tx.begin_timestamp();
+    IndexModel::new(&mut tx)
+        .add_application_index(IndexMetadata::new_backfilling_database_index(
+            *begin_ts,
+            index_name.clone(),
+            DeveloperDatabaseIndexConfig {
+                fields: vec![str::parse("email")?].try_into()?,
+                unique: true,
+                filter: None,
+                multikey_field: None,
+                aggregate: None,
+            },
+        ))
+        .await?;
+    db.commit(tx).await?;
+
+    let retention_validator = Arc::new(NoopRetentionValidator);
+    IndexWorker::new_terminating(rt, tp, retention_validator, db.clone()).await?;
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_unique_index_rejects_duplicates(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("users")?;
+    let index_name = IndexName::new(table_name.clone(), "by_email".parse()?)?;
+    add_unique_email_index(rt, &db, tp, &index_name).await?;
+    let mut tx = db.begin_system().await?;
+    IndexModel::new(&mut tx)
+        .enable_index_for_testing(&index_name)
+        .await?;
+    db.commit(tx).await?;
+
+    let mut tx = db.begin_system().await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "a@example.com"))
+        .await?;
+    let other_id = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "b@example.com"))
+        .await?;
+    // Documents without the indexed field don't conflict.
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!())
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!())
+        .await?;
+    // The check sees writes earlier in the same transaction.
+    let err = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "a@example.com"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "UniqueIndexViolation");
+    db.commit(tx).await?;
+
+    let mut tx = db.begin_system().await?;
+    let err = UserFacingModel::new(&mut tx)
+        .replace(other_id.into(), assert_obj!("email" => "a@example.com"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "UniqueIndexViolation");
+    // Replacing a document with its own values is fine.
+    UserFacingModel::new(&mut tx)
+        .replace(other_id.into(), assert_obj!("email" => "b@example.com"))
+        .await?;
+    db.commit(tx).await?;
+
+    // Concurrent inserts of the same value conflict.
+    let mut tx1 = db.begin_system().await?;
+    TestFacingModel::new(&mut tx1)
+        .insert(&table_name, assert_obj!("email" => "c@example.com"))
+        .await?;
+    let mut tx2 = db.begin_system().await?;
+    TestFacingModel::new(&mut tx2)
+        .insert(&table_name, assert_obj!("email" => "c@example.com"))
+        .await?;
+    db.commit(tx1).await?;
+    must_let!(let Err(e) = db.commit(tx2).await);
+    assert!(e.is_occ());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_unique_index_enforced_before_enabled(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("users")?;
+    let index_name = IndexName::new(table_name.clone(), "by_email".parse()?)?;
+    let mut tx = db.begin_system().await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "a@example.com"))
+        .await?;
+    db.commit(tx).await?;
+
+    // The index is backfilled without conflicts but not enabled yet, so a
+    // duplicate written now would otherwise be missed.
+    add_unique_email_index(rt, &db, tp, &index_name).await?;
+    let mut tx = db.begin_system().await?;
+    assert!(IndexModel::new(&mut tx)
+        .pending_index_metadata(&index_name)?
+        .is_some());
+    let err = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "a@example.com"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "UniqueIndexViolation");
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "b@example.com"))
+        .await?;
+    db.commit(tx).await?;
+
+    // Concurrent inserts of the same value conflict.
+    let mut tx1 = db.begin_system().await?;
+    TestFacingModel::new(&mut tx1)
+        .insert(&table_name, assert_obj!("email" => "c@example.com"))
+        .await?;
+    let mut tx2 = db.begin_system().await?;
+    TestFacingModel::new(&mut tx2)
+        .insert(&table_name, assert_obj!("email" => "c@example.com"))
+        .await?;
+    db.commit(tx1).await?;
+    must_let!(let Err(e) = db.commit(tx2).await);
+    assert!(e.is_occ());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_foreign_keys(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let users: TableName = str::parse("users")?;
+    let messages: TableName = str::parse("messages")?;
+    let fields = [
+        ("author", OnDelete::Restrict),
+        ("editor", OnDelete::Cascade),
+        ("reviewer", OnDelete::SetNull),
+    ];
+    let mut foreign_keys = BTreeMap::new();
+    for (field, on_delete) in fields {
+        foreign_keys.insert(
+            str::parse(field)?,
+            ForeignKey {
+                table: users.clone(),
+                on_delete,
+            },
+        );
+    }
+    let schema = DatabaseSchema {
+        tables: btreemap! {
+            messages.clone() => TableDefinition {
+                table_name: messages.clone(),
+                indexes: BTreeMap::new(),
+                search_indexes: BTreeMap::new(),
+                vector_indexes: BTreeMap::new(),
+                document_type: None,
+                ttl: None,
+                foreign_keys,
+            },
+        },
+        schema_validation: true,
+    };
+    // The database maintains an index for each foreign key.
+    let mut tx = db.begin_system().await?;
+    IndexModel::new(&mut tx).build_indexes(&schema).await?;
+    db.commit(tx).await?;
+    IndexWorker::new_terminating(rt, tp, Arc::new(NoopRetentionValidator), db.clone()).await?;
+
+    let mut tx = db.begin_system().await?;
+    let mut model = SchemaModel::new(&mut tx);
+    let (schema_id, _) = model.submit_pending(schema).await?;
+    model.mark_validated(schema_id).await?;
+    model.mark_active(schema_id).await?;
+    db.commit(tx).await?;
+
+    let mut tx = db.begin_system().await?;
+    let alice = UserFacingModel::new(&mut tx)
+        .insert(users.clone(), assert_obj!())
+        .await?;
+    let bob = UserFacingModel::new(&mut tx)
+        .insert(users.clone(), assert_obj!())
+        .await?;
+    // References must be null or point to an existing document in the table.
+    let message = UserFacingModel::new(&mut tx)
+        .insert(
+            messages.clone(),
+            assert_obj!("author" => alice, "editor" => bob, "reviewer" => bob),
+        )
+        .await?;
+    let nulls = UserFacingModel::new(&mut tx)
+        .insert(messages.clone(), assert_obj!("author" => null))
+        .await?;
+    let err = UserFacingModel::new(&mut tx)
+        .patch(nulls, assert_obj!("author" => message).into())
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "ForeignKeyViolation");
+    let err = UserFacingModel::new(&mut tx)
+        .patch(nulls, assert_obj!("author" => "not an ID").into())
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "ForeignKeyViolation");
+    db.commit(tx).await?;
+
+    // A referenced document can't be deleted with a restrict foreign key.
+    let mut tx = db.begin_system().await?;
+    let err = UserFacingModel::new(&mut tx)
+        .delete(alice)
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "ForeignKeyViolation");
+
+    // Deleting bob deletes the message bob edited and sets the reviewer of
+    // the other message to null.
+    let carol = UserFacingModel::new(&mut tx)
+        .insert(users.clone(), assert_obj!())
+        .await?;
+    let reviewed = UserFacingModel::new(&mut tx)
+        .insert(
+            messages.clone(),
+            assert_obj!("editor" => carol, "reviewer" => bob),
+        )
+        .await?;
+    UserFacingModel::new(&mut tx).delete(bob).await?;
+    assert!(UserFacingModel::new(&mut tx)
+        .get(message, None)
+        .await?
+        .is_none());
+    let reviewed = UserFacingModel::new(&mut tx)
+        .get(reviewed, None)
+        .await?
+        .context("reviewed message was deleted")?;
+    assert_eq!(reviewed.value().get("reviewer"), Some(&ConvexValue::Null));
+    db.commit(tx).await?;
+
+    // Inserting a reference conflicts with a concurrent delete of the
+    // referenced document.
+    let mut tx1 = db.begin_system().await?;
+    UserFacingModel::new(&mut tx1)
+        .insert(messages.clone(), assert_obj!("editor" => carol))
+        .await?;
+    let mut tx2 = db.begin_system().await?;
+    UserFacingModel::new(&mut tx2).delete(carol).await?;
+    db.commit(tx2).await?;
+    must_let!(let Err(e) = db.commit(tx1).await);
+    assert!(e.is_occ());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_pending_schema_foreign_keys(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let users: TableName = str::parse("users")?;
+    let messages: TableName = str::parse("messages")?;
+    let schema = DatabaseSchema {
+        tables: btreemap! {
+            messages.clone() => TableDefinition {
+                table_name: messages.clone(),
+                indexes: BTreeMap::new(),
+                search_indexes: BTreeMap::new(),
+                vector_indexes: BTreeMap::new(),
+                document_type: None,
+                ttl: None,
+                foreign_keys: btreemap! {
+                    str::parse("author")? => ForeignKey {
+                        table: users.clone(),
+                        on_delete: OnDelete::Cascade,
+                    },
+                },
+            },
+        },
+        schema_validation: true,
+    };
+    let mut tx = db.begin_system().await?;
+    IndexModel::new(&mut tx).build_indexes(&schema).await?;
+    db.commit(tx).await?;
+    IndexWorker::new_terminating(rt, tp, Arc::new(NoopRetentionValidator), db.clone()).await?;
+
+    let mut tx = db.begin_system().await?;
+    let alice = UserFacingModel::new(&mut tx)
+        .insert(users.clone(), assert_obj!())
+        .await?;
+    let message = UserFacingModel::new(&mut tx)
+        .insert(messages.clone(), assert_obj!("author" => alice))
+        .await?;
+    SchemaModel::new(&mut tx)
+        .submit_pending(schema.clone())
+        .await?;
+    db.commit(tx).await?;
+
+    // A pending schema's foreign keys don't apply to deletes yet, but leaving
+    // a dangling reference fails the schema.
+    let mut tx = db.begin_system().await?;
+    UserFacingModel::new(&mut tx).delete(alice).await?;
+    assert!(UserFacingModel::new(&mut tx)
+        .get(message, None)
+        .await?
+        .is_some());
+    assert!(SchemaModel::new(&mut tx)
+        .get_by_state(SchemaState::Pending)
+        .await?
+        .is_none());
+    db.commit(tx).await?;
+
+    // So does writing a reference to a nonexistent document.
+    let mut tx = db.begin_system().await?;
+    UserFacingModel::new(&mut tx)
+        .patch(message, assert_obj!("author" => null).into())
+        .await?;
+    SchemaModel::new(&mut tx).submit_pending(schema).await?;
+    UserFacingModel::new(&mut tx)
+        .insert(messages.clone(), assert_obj!("author" => alice))
+        .await?;
+    assert!(SchemaModel::new(&mut tx)
+        .get_by_state(SchemaState::Pending)
+        .await?
+        .is_none());
+    db.commit(tx).await?;
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_unique_index_backfill_reports_conflicts(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("users")?;
+    let mut tx = db.begin_system().await?;
+    let id1 = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "a@example.com"))
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "b@example.com"))
+        .await?;
+    let id2 = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("email" => "a@example.com"))
+        .await?;
+    let (schema_id, _) = SchemaModel::new(&mut tx)
+        .submit_pending(DatabaseSchema::default())
+        .await?;
+    db.commit(tx).await?;
+
+    let index_name = IndexName::new(table_name, "by_email".parse()?)?;
+    add_unique_email_index(rt, &db, tp, &index_name).await?;
+
+    let mut tx = db.begin_system().await?;
+    assert!(IndexModel::new(&mut tx)
+        .pending_index_metadata(&index_name)?
+        .is_none());
+    let err = SchemaModel::new(&mut tx)
+        .get_validated_or_active(schema_id)
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "SchemaAlreadyFailed");
+    for id in [id1, id2] {
+        let id = DocumentIdV6::from(id).encode();
+        assert!(err.to_string().contains(&id), "{err} doesn't mention {id}");
+    }
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_partial_index(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("messages")?;
+    let index_name = IndexName::new(table_name.clone(), "by_channel_active".parse()?)?;
+    let not_archived = Expression::Eq(
+        Box::new(Expression::Field("archived".parse()?)),
+        Box::new(Expression::Literal(maybe_val!(false))),
+    );
+
+    // Backfill only indexes documents matching the filter.
+    let mut tx = db.begin_system().await?;
+    let active = TestFacingModel::new(&mut tx)
+        .insert(
+            &table_name,
+            assert_obj!("channel" => "eng", "archived" => false),
+        )
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(
+            &table_name,
+            assert_obj!("channel" => "eng", "archived" => true),
+        )
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("channel" => "eng"))
+        .await?;
+    let begin_ts = tx.begin_timestamp();
+    IndexModel::new(&mut tx)
+        .add_application_index(IndexMetadata::new_backfilling_database_index(
+            *begin_ts,
+            index_name.clone(),
+            DeveloperDatabaseIndexConfig {
+                fields: vec![str::parse("channel")?].try_into()?,
+                unique: false,
+                filter: Some(not_archived.clone()),
+                multikey_field: None,
+                aggregate: None,
+            },
+        ))
+        .await?;
+    db.commit(tx).await?;
+    let retention_validator = Arc::new(NoopRetentionValidator);
+    IndexWorker::new_terminating(rt, tp, retention_validator, db.clone()).await?;
+    let mut tx = db.begin_system().await?;
+    IndexModel::new(&mut tx)
+        .enable_index_for_testing(&index_name)
+        .await?;
+    db.commit(tx).await?;
+
+    // Writes move documents in and out of the index.
+    let mut tx = db.begin_system().await?;
+    let unarchived = TestFacingModel::new(&mut tx)
+        .insert(
+            &table_name,
+            assert_obj!("channel" => "eng", "archived" => false),
+        )
+        .await?;
+    UserFacingModel::new(&mut tx)
+        .patch(active.into(), assert_obj!("archived" => true).into())
+        .await?;
+    db.commit(tx).await?;
+
+    let mut tx = db.begin_system().await?;
+    let stable_index_name = IndexModel::new(&mut tx)
+        .stable_index_name(&index_name, TableFilter::IncludePrivateSystemTables)?;
+    let IndexRangeResponse { page, .. } = tx
+        .index_range_batch(btreemap! { 0 => IndexRangeRequest {
+            stable_index_name,
+            interval: Interval::all(),
+            order: Order::Asc,
+            max_rows: 100,
+            version: None,
+        }})
+        .await
+        .remove(&0)
+        .context("missing result")??;
+    let indexed: Vec<_> = page.into_iter().map(|(_, doc, _)| doc.id()).collect();
+    assert_eq!(indexed, vec![unarchived]);
+
+    // Queries must state the index's filter to use it.
+    let query = |operators| Query {
+        source: QuerySource::IndexRange(IndexRange {
+            index_name: index_name.clone(),
+            range: vec![],
+            order: Order::Asc,
+        }),
+        operators,
+    };
+    let err = run_query(db.clone(), query(vec![])).await.unwrap_err();
+    assert_eq!(err.short_msg(), "PartialIndexFilterRequired");
+    let err = run_query(
+        db.clone(),
+        query(vec![
+            QueryOperator::Limit(1),
+            QueryOperator::Filter(not_archived.clone()),
+        ]),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.short_msg(), "PartialIndexFilterRequired");
+    let results = run_query(
+        db.clone(),
+        query(vec![QueryOperator::Filter(Expression::And(vec![
+            Expression::Eq(
+                Box::new(Expression::Field("channel".parse()?)),
+                Box::new(Expression::Literal(maybe_val!("eng"))),
+            ),
+            not_archived,
+        ]))]),
+    )
+    .await?;
+    let ids: Vec<_> = results.into_iter().map(|doc| doc.id()).collect();
+    assert_eq!(ids, vec![unarchived]);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_multikey_index(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("posts")?;
+    let index_name = IndexName::new(table_name.clone(), "by_tags".parse()?)?;
+
+    // Backfill adds an entry per distinct element, and none for empty arrays.
+    let mut tx = db.begin_system().await?;
+    let ab = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("tags" => ["a", "b"]))
+        .await?;
+    let bcb = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("tags" => ["b", "c", "b"]))
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(
+            &table_name,
+            assert_obj!("tags" => ConvexValue::Array(array![])),
+        )
+        .await?;
+    let begin_ts = tx.begin_timestamp();
+    IndexModel::new(&mut tx)
+        .add_application_index(IndexMetadata::new_backfilling_database_index(
+            *begin_ts,
+            index_name.clone(),
+            DeveloperDatabaseIndexConfig {
+                fields: vec![str::parse("tags")?].try_into()?,
+                unique: false,
+                filter: None,
+                multikey_field: Some(str::parse("tags")?),
+                aggregate: None,
+            },
+        ))
+        .await?;
+    db.commit(tx).await?;
+    let retention_validator = Arc::new(NoopRetentionValidator);
+    IndexWorker::new_terminating(rt, tp, retention_validator, db.clone()).await?;
+    let mut tx = db.begin_system().await?;
+    IndexModel::new(&mut tx)
+        .enable_index_for_testing(&index_name)
+        .await?;
+    db.commit(tx).await?;
+
+    // Non-array values are indexed as-is.
+    let mut tx = db.begin_system().await?;
+    let b = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("tags" => "b"))
+        .await?;
+    db.commit(tx).await?;
+
+    let mut tx = db.begin_system().await?;
+    let stable_index_name = IndexModel::new(&mut tx)
+        .stable_index_name(&index_name, TableFilter::IncludePrivateSystemTables)?;
+    let IndexRangeResponse { page, .. } = tx
+        .index_range_batch(btreemap! { 0 => IndexRangeRequest {
+            stable_index_name,
+            interval: Interval::all(),
+            order: Order::Asc,
+            max_rows: 100,
+            version: None,
+        }})
+        .await
+        .remove(&0)
+        .context("missing result")??;
+    assert_eq!(page.len(), 5);
+
+    let query = |range, order| Query {
+        source: QuerySource::IndexRange(IndexRange {
+            index_name: index_name.clone(),
+            range,
+            order,
+        }),
+        operators: vec![],
+    };
+    let query_ids = |range, order| {
+        let query = query(range, order);
+        let db = db.clone();
+        async move {
+            let results = run_query(db, query).await?;
+            anyhow::Ok(results.into_iter().map(|doc| doc.id()).collect::<Vec<_>>())
+        }
+    };
+    let has_b = || {
+        vec![IndexRangeExpression::Eq(
+            "tags".parse().unwrap(),
+            maybe_val!("b"),
+        )]
+    };
+    let ids = query_ids(has_b(), Order::Asc).await?;
+    assert_eq!(
+        ids.iter().copied().collect::<BTreeSet<_>>(),
+        btreeset! {ab, bcb, b}
+    );
+    assert_eq!(ids.len(), 3);
+
+    // Scanning the whole index returns each document once, in either order.
+    for order in [Order::Asc, Order::Desc] {
+        let ids = query_ids(vec![], order).await?;
+        assert_eq!(ids.len(), 3);
+        assert_eq!(
+            ids.into_iter().collect::<BTreeSet<_>>(),
+            btreeset! {ab, bcb, b}
+        );
+    }
+
+    // Removing an element removes its entry.
+    let mut tx = db.begin_system().await?;
+    UserFacingModel::new(&mut tx)
+        .patch(bcb.into(), assert_obj!("tags" => ["c"]).into())
+        .await?;
+    db.commit(tx).await?;
+    let ids = query_ids(has_b(), Order::Asc).await?;
+    assert_eq!(ids.into_iter().collect::<BTreeSet<_>>(), btreeset! {ab, b});
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_index_aggregate(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("orders")?;
+    let index_name = IndexName::new(table_name.clone(), "by_customer".parse()?)?;
+
+    let mut tx = db.begin_system().await?;
+    tx.create_system_table_testing(&INDEX_AGGREGATES_TABLE, None)
+        .await?;
+    for index in IndexAggregatesTable.indexes() {
+        IndexModel::new(&mut tx)
+            .add_system_index(IndexMetadata::new_enabled(index.name, index.fields))
+            .await?;
+    }
+    db.commit(tx).await?;
+
+    // Backfill counts the documents that already exist.
+    let mut tx = db.begin_system().await?;
+    let first = TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("customer" => "a", "total" => 3.0))
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("customer" => "a", "total" => 4.0))
+        .await?;
+    TestFacingModel::new(&mut tx)
+        .insert(
+            &table_name,
+            assert_obj!("customer" => "b", "total" => "n/a"),
+        )
+        .await?;
+    let begin_ts = tx.begin_timestamp();
+    IndexModel::new(&mut tx)
+        .add_application_index(IndexMetadata::new_backfilling_database_index(
+            *begin_ts,
+            index_name.clone(),
+            DeveloperDatabaseIndexConfig {
+                fields: vec![str::parse("customer")?].try_into()?,
+                unique: false,
+                filter: None,
+                multikey_field: None,
+                aggregate: Some(IndexAggregate {
+                    sum_field: Some(str::parse("total")?),
+                }),
+            },
+        ))
+        .await?;
+    db.commit(tx).await?;
+    let retention_validator = Arc::new(NoopRetentionValidator);
+    IndexWorker::new_terminating(rt.clone(), tp.clone(), retention_validator, db.clone()).await?;
+    let mut tx = db.begin_system().await?;
+    IndexModel::new(&mut tx)
+        .enable_index_for_testing(&index_name)
+        .await?;
+    db.commit(tx).await?;
+
+    let customer_range = |customer: &str| IndexRange {
+        index_name: index_name.clone(),
+        range: vec![IndexRangeExpression::Eq(
+            "customer".parse().unwrap(),
+            maybe_val!(customer),
+        )],
+        order: Order::Asc,
+    };
+    let mut tx = db.begin_system().await?;
+    assert_eq!(
+        tx.index_aggregate(customer_range("a")).await?,
+        IndexAggregateTotals { count: 2, sum: 7.0 }
+    );
+    // Non-numeric values of the summed field count as zero.
+    assert_eq!(
+        tx.index_aggregate(customer_range("b")).await?,
+        IndexAggregateTotals { count: 1, sum: 0.0 }
+    );
+
+    // Writes keep the totals up to date.
+    let mut tx = db.begin_system().await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("customer" => "a", "total" => 1.0))
+        .await?;
+    UserFacingModel::new(&mut tx)
+        .patch(first.into(), assert_obj!("customer" => "b").into())
+        .await?;
+    db.commit(tx).await?;
+    let mut tx = db.begin_system().await?;
+    assert_eq!(
+        tx.index_aggregate(customer_range("a")).await?,
+        IndexAggregateTotals { count: 2, sum: 5.0 }
+    );
+    assert_eq!(
+        tx.index_aggregate(customer_range("b")).await?,
+        IndexAggregateTotals { count: 2, sum: 3.0 }
+    );
+    UserFacingModel::new(&mut tx).delete(first.into()).await?;
+    assert_eq!(
+        tx.index_aggregate(customer_range("b")).await?,
+        IndexAggregateTotals { count: 1, sum: 0.0 }
+    );
+    assert_eq!(
+        tx.index_aggregate(customer_range("c")).await?,
+        IndexAggregateTotals::default()
+    );
+
+    // Only equalities on the index's fields are supported.
+    let err = tx
+        .index_aggregate(IndexRange {
+            index_name: index_name.clone(),
+            range: vec![IndexRangeExpression::Gt(
+                "customer".parse()?,
+                maybe_val!("a"),
+            )],
+            order: Order::Asc,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "InvalidIndexAggregateRange");
+
+    // Once no documents match, the totals are gone even if subtracting the
+    // sum left rounding error behind.
+    let mut tx = db.begin_system().await?;
+    let mut ids = vec![];
+    for total in [0.1, 0.2] {
+        ids.push(
+            TestFacingModel::new(&mut tx)
+                .insert(
+                    &table_name,
+                    assert_obj!("customer" => "d", "total" => total),
+                )
+                .await?,
+        );
+    }
+    db.commit(tx).await?;
+    let mut tx = db.begin_system().await?;
+    for id in ids {
+        UserFacingModel::new(&mut tx).delete(id.into()).await?;
+    }
+    db.commit(tx).await?;
+    let mut tx = db.begin_system().await?;
+    assert_eq!(
+        tx.index_aggregate(customer_range("d")).await?,
+        IndexAggregateTotals::default()
+    );
+
+    // Dropping the index deletes its totals.
+    let index_id = IndexModel::new(&mut tx)
+        .enabled_index_metadata(&index_name)?
+        .unwrap()
+        .id();
+    IndexModel::new(&mut tx).drop_index(index_id).await?;
+    db.commit(tx).await?;
+    let retention_validator = Arc::new(NoopRetentionValidator);
+    IndexWorker::new_terminating(rt, tp, retention_validator, db.clone()).await?;
+    let mut tx = db.begin_system().await?;
+    assert!(IndexAggregateModel::new(&mut tx)
+        .indexes_with_records()
+        .await?
+        .is_empty());
+    Ok(())
+}
+
 // Same as test_index_backfill but writing the index with IndexWriter directly.
 #[convex_macro::test_runtime]
 async fn test_index_write(rt: TestRuntime) -> anyhow::Result<()> {
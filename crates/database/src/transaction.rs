@@ -16,7 +16,11 @@ use async_trait::async_trait;
 use common::{
     bootstrap_model::{
         index::{
-            database_index::IndexedFields,
+            database_index::{
+                DatabaseIndexState,
+                IndexedFields,
+            },
+            IndexConfig,
             IndexMetadata,
             INDEX_TABLE,
         },
@@ -35,14 +39,21 @@ use common::{
         IndexKey,
         IndexKeyBytes,
     },
-    interval::Interval,
+    interval::{
+        BinaryKey,
+        Interval,
+        Start,
+    },
     knobs::{
         SEARCH_INDEX_SIZE_HARD_LIMIT,
+        SLOW_QUERY_LOG_ENABLED,
         VECTOR_INDEX_SIZE_HARD_LIMIT,
     },
     persistence::RetentionValidator,
     query::{
         CursorPosition,
+        IndexRange,
+        IndexRangeExpression,
         Order,
         Search,
         SearchVersion,
@@ -74,6 +85,7 @@ use common::{
 use errors::ErrorMetadata;
 use indexing::backend_in_memory_indexes::{
     BatchKey,
+    IndexVersion,
     RangeRequest,
 };
 use keybroker::{
@@ -88,6 +100,7 @@ use sync_types::{
 };
 use usage_tracking::FunctionUsageTracker;
 use value::{
+    values_to_bytes,
     TableId,
     TableIdAndTableNumber,
     TableNumber,
@@ -96,6 +109,7 @@ use value::{
 use crate::{
     bootstrap_model::{
         defaults::BootstrapTableIds,
+        index_aggregates::accumulate_index_aggregates,
         table::{
             NUM_RESERVED_LEGACY_TABLE_NUMBERS,
             NUM_RESERVED_SYSTEM_TABLE_NUMBERS,
@@ -111,6 +125,10 @@ use crate::{
         TableFilter,
     },
     reads::TransactionReadSet,
+    slow_query_log::{
+        SlowQueryLog,
+        SlowQueryLogger,
+    },
     snapshot_manager::{
         Snapshot,
         SnapshotManager,
@@ -124,6 +142,8 @@ use crate::{
         TransactionWriteSize,
         Writes,
     },
+    IndexAggregateModel,
+    IndexAggregateTotals,
     IndexModel,
     ReadSet,
     SchemaModel,
@@ -168,6 +188,11 @@ pub struct Transaction<RT: Runtime> {
     pub usage_tracker: FunctionUsageTracker,
     pub(crate) virtual_system_mapping: VirtualSystemMapping,
 
+    slow_query_log: SlowQueryLog,
+    /// The function running this transaction, if its slow queries should be
+    /// logged.
+    slow_query_udf_path: Option<String>,
+
     #[cfg(any(test, feature = "testing"))]
     index_size_override: Option<usize>,
 }
@@ -198,6 +223,7 @@ impl<RT: Runtime> Transaction<RT> {
         usage_tracker: FunctionUsageTracker,
         retention_validator: Arc<dyn RetentionValidator>,
         virtual_system_mapping: VirtualSystemMapping,
+        slow_query_log: SlowQueryLog,
     ) -> Self {
         Self {
             identity,
@@ -215,11 +241,32 @@ impl<RT: Runtime> Transaction<RT> {
             retention_validator,
             usage_tracker,
             virtual_system_mapping,
+            slow_query_log,
+            slow_query_udf_path: None,
             #[cfg(any(test, feature = "testing"))]
             index_size_override: None,
         }
     }
 
+    /// Log queries in this transaction that exceed the slow query thresholds,
+    /// attributing them to `udf_path`. Does nothing unless
+    /// `SLOW_QUERY_LOG_ENABLED` is set.
+    pub fn enable_slow_query_log(&mut self, udf_path: String) {
+        self.slow_query_udf_path = Some(udf_path);
+    }
+
+    pub(crate) fn slow_query_logger(&self) -> Option<SlowQueryLogger> {
+        if !*SLOW_QUERY_LOG_ENABLED {
+            return None;
+        }
+        let udf_path = self.slow_query_udf_path.clone()?;
+        Some(SlowQueryLogger::new(
+            self.slow_query_log.clone(),
+            udf_path,
+            self.runtime.unix_timestamp(),
+        ))
+    }
+
     pub fn persistence_version(&self) -> PersistenceVersion {
         self.index.index_registry().persistence_version()
     }
@@ -502,8 +549,11 @@ impl<RT: Runtime> Transaction<RT> {
             old_document.replace_value(patched_value)?
         };
         SchemaModel::new(self).enforce(&new_document).await?;
+        self.enforce_unique_indexes(&new_document).await?;
 
-        self.apply_validated_write(id, Some(old_document), Some(new_document.clone()))?;
+        self.apply_validated_write(id, Some(old_document.clone()), Some(new_document.clone()))?;
+        self.update_index_aggregates(Some(&old_document), Some(&new_document))
+            .await?;
         Ok(new_document)
     }
 
@@ -531,12 +581,15 @@ impl<RT: Runtime> Transaction<RT> {
         let new_document = old_document.replace_value(value)?;
 
         SchemaModel::new(self).enforce(&new_document).await?;
+        self.enforce_unique_indexes(&new_document).await?;
 
         self.apply_validated_write(
             *new_document.id(),
-            Some(old_document),
+            Some(old_document.clone()),
             Some(new_document.clone()),
         )?;
+        self.update_index_aggregates(Some(&old_document), Some(&new_document))
+            .await?;
         Ok(new_document)
     }
 
@@ -554,7 +607,28 @@ impl<RT: Runtime> Transaction<RT> {
                     format!("Delete on nonexistent document ID {id}"),
                 ))?;
 
+        let foreign_key_deletes = SchemaModel::new(self)
+            .foreign_key_deletes(&document)
+            .await?;
+        for (_, new_document) in &foreign_key_deletes.set_null {
+            self.enforce_unique_indexes(new_document).await?;
+        }
+
         self.apply_validated_write(*document.id(), Some(document.clone()), None)?;
+        self.update_index_aggregates(Some(&document), None).await?;
+        for cascaded in foreign_key_deletes.cascade {
+            self.apply_validated_write(*cascaded.id(), Some(cascaded.clone()), None)?;
+            self.update_index_aggregates(Some(&cascaded), None).await?;
+        }
+        for (old_document, new_document) in foreign_key_deletes.set_null {
+            self.apply_validated_write(
+                *new_document.id(),
+                Some(old_document.clone()),
+                Some(new_document.clone()),
+            )?;
+            self.update_index_aggregates(Some(&old_document), Some(&new_document))
+                .await?;
+        }
         Ok(document)
     }
 
@@ -570,6 +644,55 @@ impl<RT: Runtime> Transaction<RT> {
         TableModel::new(self).count(system_table).await
     }
 
+    /// Returns the totals of an aggregate index over the documents whose first
+    /// fields equal the values in `index_range`, which may only contain
+    /// equalities. Like reading the documents, this is invalidated by writes
+    /// that change the totals.
+    #[minitrace::trace]
+    #[convex_macro::instrument_future]
+    pub async fn index_aggregate(
+        &mut self,
+        index_range: IndexRange,
+    ) -> anyhow::Result<IndexAggregateTotals> {
+        let index_name = index_range.index_name.clone();
+        let stable_index_name = IndexModel::new(self)
+            .stable_index_name(&index_name, TableFilter::ExcludePrivateSystemTables)?;
+        let developer_config =
+            IndexModel::new(self).database_index_config(&stable_index_name, &index_name)?;
+        let StableIndexName::Physical(tablet_index_name) = stable_index_name else {
+            anyhow::bail!(index_aggregate_missing_error(&index_name));
+        };
+        anyhow::ensure!(
+            developer_config.aggregate.is_some(),
+            index_aggregate_missing_error(&index_name)
+        );
+        let num_fields = developer_config.fields.len();
+        anyhow::ensure!(
+            (1..=num_fields).contains(&index_range.range.len())
+                && index_range
+                    .range
+                    .iter()
+                    .all(|expression| matches!(expression, IndexRangeExpression::Eq(..))),
+            ErrorMetadata::bad_request(
+                "InvalidIndexAggregateRange",
+                format!(
+                    "Aggregates over {index_name} need `eq` comparisons on between 1 and \
+                     {num_fields} of its first fields."
+                ),
+            )
+        );
+        let Start::Included(prefix) = index_range.compile(developer_config.fields, None)?.start;
+        let index_id = self
+            .index
+            .index_registry()
+            .get_enabled(&tablet_index_name)
+            .context("Index should be enabled")?
+            .id();
+        IndexAggregateModel::new(self)
+            .totals(index_id, prefix.into())
+            .await
+    }
+
     pub fn into_token(self) -> anyhow::Result<Token> {
         if !self.is_readonly() {
             anyhow::bail!("Transaction isn't readonly");
@@ -823,6 +946,163 @@ impl<RT: Runtime> Transaction<RT> {
         batch_result
     }
 
+    /// Check that no other document has the same values as `document` in any
+    /// of its table's unique indexes. The ranges checked are added to the read
+    /// set, so a concurrent write of a conflicting document causes an OCC
+    /// conflict.
+    ///
+    /// Pending unique indexes are checked too once the index worker has
+    /// finished backfilling them, since it only looks for existing conflicts
+    /// once, before the index is enabled. Their state is read before that as
+    /// well, so a write racing with the end of the backfill conflicts with it
+    /// instead of going unchecked.
+    async fn enforce_unique_indexes(&mut self, document: &ResolvedDocument) -> anyhow::Result<()> {
+        let unique_indexes = self
+            .index
+            .index_registry()
+            .unique_indexes_by_table(&document.table().table_id);
+        if unique_indexes.is_empty() {
+            return Ok(());
+        }
+        let mut enabled_ranges = BTreeMap::new();
+        let mut pending_ranges = vec![];
+        for (i, index) in unique_indexes.into_iter().enumerate() {
+            let IndexConfig::Database {
+                developer_config,
+                on_disk_state,
+            } = &index.metadata.config
+            else {
+                continue;
+            };
+            let version = match on_disk_state {
+                DatabaseIndexState::Enabled => IndexVersion::Enabled,
+                DatabaseIndexState::Backfilled => IndexVersion::Pending,
+                DatabaseIndexState::Backfilling(state) if state.retention_started => {
+                    IndexVersion::Pending
+                },
+                DatabaseIndexState::Backfilling(_) => {
+                    self.index
+                        .record_index_metadata_read(&mut self.reads, &index);
+                    continue;
+                },
+            };
+            // Uniqueness only applies to documents in the index.
+            if !developer_config.includes(document.value())? {
+                continue;
+            }
+            let values: Vec<_> = developer_config
+                .fields
+                .iter()
+                .map(|field| document.value().get_path(field).cloned())
+                .collect();
+            // Documents missing an indexed field are exempt from uniqueness.
+            if values.iter().any(Option::is_none) {
+                continue;
+            }
+            let index_name = index.name();
+            let printable_index_name = index_name
+                .clone()
+                .map_table(&self.table_mapping().tablet_to_name())?;
+            let range = RangeRequest {
+                index_name,
+                printable_index_name,
+                interval: Interval::prefix(BinaryKey::from(values_to_bytes(&values))),
+                order: Order::Asc,
+                // One other document is enough to violate the constraint.
+                max_size: 2,
+            };
+            match version {
+                IndexVersion::Enabled => {
+                    enabled_ranges.insert(i, range);
+                },
+                IndexVersion::Pending => pending_ranges.push(range),
+            }
+        }
+        let mut pages = vec![];
+        for range in pending_ranges {
+            let printable_index_name = range.printable_index_name.clone();
+            let page = self.index.pending_range(&mut self.reads, range).await?;
+            pages.push((printable_index_name, page));
+        }
+        let printable_index_names: BTreeMap<_, _> = enabled_ranges
+            .iter()
+            .map(|(i, range)| (*i, range.printable_index_name.clone()))
+            .collect();
+        let mut results = self
+            .index
+            .range_batch(&mut self.reads, enabled_ranges)
+            .await;
+        for (i, index_name) in printable_index_names {
+            let IndexRangeResponse { page, .. } =
+                results.remove(&i).context("expected result")??;
+            pages.push((index_name, page));
+        }
+        for (index_name, page) in pages {
+            if let Some((_, existing, _)) =
+                page.iter().find(|(_, doc, _)| doc.id() != document.id())
+            {
+                anyhow::bail!(ErrorMetadata::bad_request(
+                    "UniqueIndexViolation",
+                    format!(
+                        "Document {} has the same values as document {} in unique index {}",
+                        DocumentIdV6::from(*document.id()).encode(),
+                        DocumentIdV6::from(*existing.id()).encode(),
+                        index_name,
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the totals of the table's aggregate indexes for a write. Indexes
+    /// whose totals the index worker hasn't started backfilling are skipped,
+    /// but their status is still read, so a backfill that starts concurrently
+    /// conflicts with this transaction instead of missing the write.
+    pub(crate) async fn update_index_aggregates(
+        &mut self,
+        old_document: Option<&ResolvedDocument>,
+        new_document: Option<&ResolvedDocument>,
+    ) -> anyhow::Result<()> {
+        let Some(table_id) = old_document
+            .or(new_document)
+            .map(|document| document.table().table_id)
+        else {
+            return Ok(());
+        };
+        let aggregate_indexes = self
+            .index
+            .index_registry()
+            .aggregate_indexes_by_table(&table_id);
+        for (index_id, developer_config) in aggregate_indexes {
+            if IndexAggregateModel::new(self)
+                .status(index_id)
+                .await?
+                .is_none()
+            {
+                continue;
+            }
+            let mut deltas: BTreeMap<Vec<u8>, IndexAggregateTotals> = BTreeMap::new();
+            for (document, sign) in [(old_document, -1), (new_document, 1)] {
+                let Some(document) = document else {
+                    continue;
+                };
+                accumulate_index_aggregates(&developer_config, document, sign, &mut deltas)?;
+            }
+            for (prefix, delta) in deltas {
+                // Replacing a document without changing its indexed or summed
+                // values leaves the totals as they were.
+                if delta.is_zero() {
+                    continue;
+                }
+                IndexAggregateModel::new(self)
+                    .add_to_totals(index_id, prefix, delta)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     /// Apply a validated write to the [Transaction], updating the
     /// [IndexRegistry] and [TableRegistry]. Validated means the write
     /// has already been checked for schema enforcement.
@@ -898,8 +1178,10 @@ impl<RT: Runtime> Transaction<RT> {
         document: ResolvedDocument,
     ) -> anyhow::Result<ResolvedDocumentId> {
         SchemaModel::new(self).enforce(&document).await?;
+        self.enforce_unique_indexes(&document).await?;
         let document_id = *document.id();
-        self.apply_validated_write(document_id, None, Some(document))?;
+        self.apply_validated_write(document_id, None, Some(document.clone()))?;
+        self.update_index_aggregates(None, Some(&document)).await?;
         Ok(document_id)
     }
 
@@ -1199,3 +1481,10 @@ impl FinalTransaction {
         Ok(())
     }
 }
+
+fn index_aggregate_missing_error(name: &IndexName) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "IndexAggregateMissing",
+        format!("Index {name} doesn't maintain aggregates. Pass `aggregate` when defining it."),
+    )
+}
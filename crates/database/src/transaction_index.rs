@@ -48,6 +48,7 @@ use indexing::{
         index_not_a_database_index_error,
         BatchKey,
         DatabaseIndexSnapshot,
+        IndexVersion,
         RangeRequest,
     },
     index_registry::{
@@ -129,6 +130,7 @@ impl TransactionIndex {
     async fn range_no_deps(
         &mut self,
         ranges: BTreeMap<BatchKey, RangeRequest>,
+        version: IndexVersion,
     ) -> BTreeMap<
         BatchKey,
         anyhow::Result<(
@@ -137,7 +139,7 @@ impl TransactionIndex {
         )>,
     > {
         let snapshot = &mut self.database_index_snapshot;
-        let mut snapshot_results = snapshot.range_batch(ranges.clone()).await;
+        let mut snapshot_results = snapshot.range_batch(ranges.clone(), version).await;
 
         let batch_size = ranges.len();
         let mut results = BTreeMap::new();
@@ -150,14 +152,19 @@ impl TransactionIndex {
                 let mut snapshot_it = snapshot_result_vec.into_iter();
                 let index_registry = &self.index_registry;
                 let database_index_updates = &self.database_index_updates;
-                let pending_it = match index_registry.require_enabled(
-                    &range_request.index_name,
-                    &range_request.printable_index_name,
-                ) {
-                    Ok(index) => database_index_updates.get(&index.id()),
-                    // Range queries on missing tables are allowed for system provided indexes.
-                    Err(_) if range_request.index_name.is_by_id_or_creation_time() => None,
-                    Err(e) => Err(e)?,
+                let pending_it = match version {
+                    IndexVersion::Enabled => match index_registry.require_enabled(
+                        &range_request.index_name,
+                        &range_request.printable_index_name,
+                    ) {
+                        Ok(index) => database_index_updates.get(&index.id()),
+                        // Range queries on missing tables are allowed for system provided indexes.
+                        Err(_) if range_request.index_name.is_by_id_or_creation_time() => None,
+                        Err(e) => Err(e)?,
+                    },
+                    IndexVersion::Pending => index_registry
+                        .get_pending(&range_request.index_name)
+                        .and_then(|index| database_index_updates.get(&index.id())),
                 }
                 .map(|pending| pending.range(&range_request.interval))
                 .into_iter()
@@ -304,7 +311,7 @@ impl TransactionIndex {
                     match self.require_enabled(reads, index_name, printable_index_name) {
                         Ok(index) => match index.metadata().config.clone() {
                             IndexConfig::Database {
-                                developer_config: DeveloperDatabaseIndexConfig { fields },
+                                developer_config: DeveloperDatabaseIndexConfig { fields, .. },
                                 ..
                             } => fields,
                             _ => Err(index_not_a_database_index_error(printable_index_name))?,
@@ -328,6 +335,7 @@ impl TransactionIndex {
                 // We use max_rows as size hint. We might receive more or less
                 // due to pending deletes or inserts in the transaction.
                 ranges_to_fetch.clone(),
+                IndexVersion::Enabled,
             )
             .await;
 
@@ -410,6 +418,47 @@ impl TransactionIndex {
             .context("batch_key missing")?
     }
 
+    /// Returns the documents in `interval` of the pending version of
+    /// `index_name`, which applications can't query yet. The whole interval is
+    /// recorded as read, so use a small interval.
+    pub async fn pending_range(
+        &mut self,
+        reads: &mut TransactionReadSet,
+        range_request: RangeRequest,
+    ) -> anyhow::Result<Vec<(IndexKeyBytes, ResolvedDocument, WriteTimestamp)>> {
+        let index = self
+            .index_registry
+            .get_pending(&range_request.index_name)
+            .cloned()
+            .with_context(|| format!("Missing pending index {:?}", range_request.index_name))?;
+        self.record_interval(reads, Some(&index));
+        let IndexConfig::Database {
+            developer_config: DeveloperDatabaseIndexConfig { fields, .. },
+            ..
+        } = index.metadata().config.clone()
+        else {
+            anyhow::bail!(index_not_a_database_index_error(
+                &range_request.printable_index_name
+            ));
+        };
+        let (documents, _) = self
+            .range_no_deps(
+                btreemap! { 0 => range_request.clone() },
+                IndexVersion::Pending,
+            )
+            .await
+            .remove(&0)
+            .context("batch_key missing")??;
+        reads.record_indexed_directly(range_request.index_name, fields, range_request.interval)?;
+        Ok(documents)
+    }
+
+    /// Record that the transaction depends on the metadata of `index`, so
+    /// that it conflicts with a concurrent change to the index's state.
+    pub fn record_index_metadata_read(&self, reads: &mut TransactionReadSet, index: &Index) {
+        self.record_interval(reads, Some(index));
+    }
+
     #[minitrace::trace]
     pub async fn preload_index_range(
         &mut self,
@@ -433,13 +482,16 @@ impl TransactionIndex {
         let mut preloaded = BTreeMap::new();
         while !remaining_interval.is_empty() {
             let (documents, cursor) = self
-                .range_no_deps(btreemap! { 0 => RangeRequest {
-                    index_name: tablet_index_name.clone(),
-                    printable_index_name: printable_index_name.clone(),
-                    interval: remaining_interval,
-                    order: Order::Asc,
-                    max_size: DEFAULT_PAGE_SIZE,
-                }})
+                .range_no_deps(
+                    btreemap! { 0 => RangeRequest {
+                        index_name: tablet_index_name.clone(),
+                        printable_index_name: printable_index_name.clone(),
+                        interval: remaining_interval,
+                        order: Order::Asc,
+                        max_size: DEFAULT_PAGE_SIZE,
+                    }},
+                    IndexVersion::Enabled,
+                )
                 .await
                 .remove(&0)
                 .context("batch_key missing")??;
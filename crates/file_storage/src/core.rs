@@ -7,6 +7,10 @@ use std::{
 use anyhow::Context;
 use bytes::Bytes;
 use common::{
+    knobs::{
+        FILE_STORAGE_BLOB_DELETE_BATCH_SIZE,
+        FILE_STORAGE_DEDUPLICATION,
+    },
     runtime::{
         Runtime,
         UnixTimestamp,
@@ -76,9 +80,16 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
             rt,
             storage,
             convex_origin,
+            deduplicate: *FILE_STORAGE_DEDUPLICATION,
         }
     }
 
+    /// Override the `FILE_STORAGE_DEDUPLICATION` knob.
+    pub fn with_deduplication(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
     pub fn generate_upload_url(
         &self,
         key_broker: &KeyBroker,
@@ -90,6 +101,21 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
         Ok(format!("{origin}/api/storage/upload?token={token}"))
     }
 
+    /// Like `generate_upload_url`, but for starting a resumable upload session
+    /// whose parts can be uploaded separately.
+    pub fn generate_resumable_upload_url(
+        &self,
+        key_broker: &KeyBroker,
+        issued_ts: UnixTimestamp,
+    ) -> anyhow::Result<String> {
+        let token = key_broker.issue_store_file_authorization(&self.rt, issued_ts)?;
+        let origin = &self.convex_origin;
+
+        Ok(format!(
+            "{origin}/api/storage/upload/resumable?token={token}"
+        ))
+    }
+
     pub async fn get_url(
         &self,
         tx: &mut Transaction<RT>,
@@ -341,8 +367,9 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
     pub async fn store_file_entry(
         &self,
         tx: &mut Transaction<RT>,
-        entry: FileStorageEntry,
+        mut entry: FileStorageEntry,
     ) -> anyhow::Result<DocumentIdV6> {
+        self.reference_blob(tx, &mut entry).await?;
         let table_mapping = tx.table_mapping().clone();
         let system_doc_id = FileStorageModel::new(tx).store_file(entry).await?;
         let virtual_id = tx
@@ -355,9 +382,53 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
 
         Ok(virtual_id)
     }
+
+    /// If deduplication is enabled, point `entry` at an existing object with
+    /// the same contents, if any, and take a reference on it. Must be called
+    /// in the same transaction that inserts `entry` into `_file_storage`.
+    pub async fn reference_blob(
+        &self,
+        tx: &mut Transaction<RT>,
+        entry: &mut FileStorageEntry,
+    ) -> anyhow::Result<()> {
+        if !self.deduplicate {
+            return Ok(());
+        }
+        if FileStorageModel::new(tx).reference_blob(entry).await? {
+            metrics::log_file_deduplicated();
+        }
+        Ok(())
+    }
 }
 
 impl<RT: Runtime> FileStorage<RT> {
+    /// Delete a batch of blobs that are no longer referenced by any file,
+    /// along with their storage objects. Returns the number of blobs deleted.
+    pub async fn delete_unreferenced_blobs(&self) -> anyhow::Result<usize> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let blobs = FileStorageModel::new(&mut tx)
+            .unreferenced_blobs(*FILE_STORAGE_BLOB_DELETE_BATCH_SIZE)
+            .await?;
+        let mut storage_keys = Vec::with_capacity(blobs.len());
+        for blob in blobs {
+            storage_keys.push(blob.storage_key.clone());
+            FileStorageModel::new(&mut tx).delete_blob(blob).await?;
+        }
+        // Only delete the objects once the blobs are gone, so a concurrent
+        // upload with the same contents can't pick up a deleted object.
+        self.database
+            .commit_with_write_source(tx, "file_storage_delete_unreferenced_blobs")
+            .await?;
+        for storage_key in &storage_keys {
+            self.transactional_file_storage
+                .storage
+                .delete_object(storage_key)
+                .await?;
+        }
+        metrics::log_blobs_deleted(storage_keys.len());
+        Ok(storage_keys.len())
+    }
+
     pub async fn store_file(
         &self,
         content_length: Option<ContentLength>,
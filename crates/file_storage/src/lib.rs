@@ -21,6 +21,9 @@ mod core;
 mod metrics;
 #[cfg(test)]
 mod tests;
+mod upload_session;
+
+pub use self::upload_session::FileUploadProgress;
 
 pub struct FileStream {
     pub sha256: Sha256Digest,
@@ -47,6 +50,8 @@ pub struct TransactionalFileStorage<RT: Runtime> {
     rt: RT,
     storage: Arc<dyn Storage>,
     convex_origin: ConvexOrigin,
+    /// Whether files with identical contents share a storage object.
+    deduplicate: bool,
 }
 
 pub struct FileMetadata {
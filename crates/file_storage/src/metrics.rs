@@ -1,5 +1,7 @@
 use metrics::{
+    log_counter,
     log_distribution_with_labels,
+    register_convex_counter,
     register_convex_histogram,
     MetricLabel,
     StatusTimer,
@@ -46,3 +48,19 @@ pub fn log_get_file_chunk_size(size_bytes: u64, get_file_type: GetFileType) {
         vec![get_file_type.tag()],
     );
 }
+
+register_convex_counter!(
+    FILE_STORAGE_DEDUPLICATED_TOTAL,
+    "Number of stored files that reused an existing object with the same contents"
+);
+pub fn log_file_deduplicated() {
+    log_counter(&FILE_STORAGE_DEDUPLICATED_TOTAL, 1);
+}
+
+register_convex_counter!(
+    FILE_STORAGE_BLOBS_DELETED_TOTAL,
+    "Number of unreferenced file storage objects deleted"
+);
+pub fn log_blobs_deleted(count: usize) {
+    log_counter(&FILE_STORAGE_BLOBS_DELETED_TOTAL, count as u64);
+}
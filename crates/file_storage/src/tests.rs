@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use bytes::Bytes;
 use common::{
     runtime::Runtime,
     sha256::Sha256,
@@ -19,16 +20,24 @@ use events::usage::NoOpUsageEventLogger;
 use futures::stream;
 use keybroker::Identity;
 use model::{
-    file_storage::FileStorageId,
+    file_storage::{
+        FileStorageId,
+        FileStorageModel,
+    },
     test_helpers::DbFixturesWithModel,
 };
 use runtime::testing::TestRuntime;
-use storage::LocalDirStorage;
+use storage::{
+    LocalDirStorage,
+    Storage,
+};
 use usage_tracking::UsageCounter;
 
 use super::FileStorage;
 use crate::TransactionalFileStorage;
 
+const TOKEN: &str = "store-file-token";
+
 fn setup_file_storage(
     rt: TestRuntime,
     database: &Database<TestRuntime>,
@@ -82,3 +91,230 @@ async fn test_store_file_sha_mismatch(rt: TestRuntime) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_deduplicated_files_share_object(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new(&rt).await?.with_model().await?.db;
+    let storage = Arc::new(LocalDirStorage::new(rt.clone())?);
+    let file_storage = FileStorage {
+        database: database.clone(),
+        transactional_file_storage: TransactionalFileStorage::new(
+            rt,
+            storage.clone(),
+            "http://127.0.0.1:8000".into(),
+        )
+        .with_deduplication(true),
+    };
+    let usage_tracker = UsageCounter::new(Arc::new(NoOpUsageEventLogger));
+
+    let mut ids = vec![];
+    for _ in 0..2 {
+        let id = file_storage
+            .store_file(
+                None,
+                None,
+                stream::iter([Ok(b"attachment".to_vec())]),
+                None,
+                &usage_tracker,
+            )
+            .await?;
+        ids.push(FileStorageId::DocumentId(id));
+    }
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut entries = vec![];
+    for id in &ids {
+        entries.push(
+            file_storage
+                .transactional_file_storage
+                .get_file_entry(&mut tx, id.clone())
+                .await?
+                .unwrap(),
+        );
+    }
+    let storage_key = entries[0].storage_key.clone();
+    assert_eq!(entries[1].storage_key, storage_key);
+    assert_eq!(
+        FileStorageModel::new(&mut tx)
+            .unreferenced_blobs(10)
+            .await?
+            .len(),
+        1
+    );
+
+    // The second upload's object is redundant, but the shared one is kept.
+    assert_eq!(file_storage.delete_unreferenced_blobs().await?, 1);
+    assert!(storage.get_object_attributes(&storage_key).await?.is_some());
+
+    // Deleting one file keeps the object alive for the other.
+    let mut tx = database.begin(Identity::system()).await?;
+    file_storage
+        .transactional_file_storage
+        .delete(&mut tx, ids[0].clone())
+        .await?;
+    database.commit(tx).await?;
+    assert_eq!(file_storage.delete_unreferenced_blobs().await?, 0);
+    assert!(storage.get_object_attributes(&storage_key).await?.is_some());
+
+    // Once the last reference is gone, the object is deleted.
+    let mut tx = database.begin(Identity::system()).await?;
+    file_storage
+        .transactional_file_storage
+        .delete(&mut tx, ids[1].clone())
+        .await?;
+    database.commit(tx).await?;
+    assert_eq!(file_storage.delete_unreferenced_blobs().await?, 1);
+    assert!(storage.get_object_attributes(&storage_key).await?.is_none());
+
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_resumable_upload(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new(&rt).await?.with_model().await?.db;
+    let file_storage = setup_file_storage(rt, &database)?;
+    let usage_tracker = UsageCounter::new(Arc::new(NoOpUsageEventLogger));
+
+    let upload_id = file_storage
+        .start_upload_session(TOKEN, None)
+        .await?
+        .upload_id;
+    let progress = file_storage
+        .upload_session_part(TOKEN, &upload_id, 0, Bytes::from_static(b"hello "))
+        .await?;
+    assert_eq!(progress.offset, 6);
+
+    // The session can only be used with the token it was started with.
+    let err: ErrorMetadata = file_storage
+        .upload_session_part("other-token", &upload_id, 6, Bytes::from_static(b"world"))
+        .await
+        .unwrap_err()
+        .downcast()?;
+    assert_eq!(err.short_msg, "UploadSessionNotFound");
+    assert!(file_storage
+        .get_upload_session("other-token", &upload_id)
+        .await
+        .is_err());
+
+    // A retried part at a stale offset is rejected.
+    let err: ErrorMetadata = file_storage
+        .upload_session_part(TOKEN, &upload_id, 0, Bytes::from_static(b"hello "))
+        .await
+        .unwrap_err()
+        .downcast()?;
+    assert_eq!(err.short_msg, "UploadOffsetMismatch");
+
+    file_storage
+        .upload_session_part(TOKEN, &upload_id, 6, Bytes::from_static(b"world"))
+        .await?;
+    assert_eq!(
+        file_storage
+            .get_upload_session(TOKEN, &upload_id)
+            .await?
+            .offset,
+        11
+    );
+
+    let id = file_storage
+        .finish_upload_session(
+            TOKEN,
+            &upload_id,
+            Sha256::hash(b"hello world"),
+            &usage_tracker,
+        )
+        .await?;
+    let mut tx = database.begin(Identity::system()).await?;
+    let entry = file_storage
+        .transactional_file_storage
+        .get_file_entry(&mut tx, FileStorageId::DocumentId(id))
+        .await?
+        .unwrap();
+    assert_eq!(entry.size, 11);
+
+    // The session is gone once it's finished.
+    let err: ErrorMetadata = file_storage
+        .get_upload_session(TOKEN, &upload_id)
+        .await
+        .unwrap_err()
+        .downcast()?;
+    assert_eq!(err.code, ErrorCode::NotFound);
+
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_resumable_upload_concurrent_parts(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new(&rt).await?.with_model().await?.db;
+    let file_storage = setup_file_storage(rt, &database)?;
+    let usage_tracker = UsageCounter::new(Arc::new(NoOpUsageEventLogger));
+
+    // Two attempts at the same offset, e.g. a client retrying a part before
+    // the first attempt finished. Exactly one of them is kept.
+    let upload_id = file_storage
+        .start_upload_session(TOKEN, None)
+        .await?
+        .upload_id;
+    let (first, second) = futures::join!(
+        file_storage.upload_session_part(TOKEN, &upload_id, 0, Bytes::from_static(b"hello ")),
+        file_storage.upload_session_part(TOKEN, &upload_id, 0, Bytes::from_static(b"HELLO ")),
+    );
+    let (winner, err): (&[u8], _) = match (first, second) {
+        (Ok(_), Err(e)) => (b"hello ", e),
+        (Err(e), Ok(_)) => (b"HELLO ", e),
+        (first, second) => panic!(
+            "Expected exactly one part to be kept, got {} and {}",
+            first.is_ok(),
+            second.is_ok()
+        ),
+    };
+    let err: ErrorMetadata = err.downcast()?;
+    assert_eq!(err.short_msg, "UploadOffsetMismatch");
+
+    file_storage
+        .upload_session_part(TOKEN, &upload_id, 6, Bytes::from_static(b"world"))
+        .await?;
+    let expected = [winner, b"world"].concat();
+    let id = file_storage
+        .finish_upload_session(TOKEN, &upload_id, Sha256::hash(&expected), &usage_tracker)
+        .await?;
+    let mut tx = database.begin(Identity::system()).await?;
+    let entry = file_storage
+        .transactional_file_storage
+        .get_file_entry(&mut tx, FileStorageId::DocumentId(id))
+        .await?
+        .unwrap();
+    assert_eq!(entry.size, 11);
+
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_resumable_upload_sha_mismatch(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new(&rt).await?.with_model().await?.db;
+    let file_storage = setup_file_storage(rt, &database)?;
+    let usage_tracker = UsageCounter::new(Arc::new(NoOpUsageEventLogger));
+
+    let upload_id = file_storage
+        .start_upload_session(TOKEN, None)
+        .await?
+        .upload_id;
+    file_storage
+        .upload_session_part(TOKEN, &upload_id, 0, Bytes::from_static(b"hello"))
+        .await?;
+    let err: ErrorMetadata = file_storage
+        .finish_upload_session(
+            TOKEN,
+            &upload_id,
+            Sha256::hash(b"Wrong thing"),
+            &usage_tracker,
+        )
+        .await
+        .unwrap_err()
+        .downcast()?;
+    assert_eq!(err.short_msg, "Sha256Mismatch");
+    assert!(file_storage
+        .get_upload_session(TOKEN, &upload_id)
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,406 @@
+//! Resumable uploads into file storage. The client starts a session, uploads
+//! parts in order, and can ask for the current offset to resume after a
+//! dropped connection. Finishing the session verifies the sha256 of the whole
+//! file and stores it in `_file_storage`. Sessions are bound to the
+//! authorization token they were started with, which every later request for
+//! the session must present.
+
+use bytes::Bytes;
+use common::{
+    document::ParsedDocument,
+    knobs::{
+        FILE_UPLOAD_MAX_PART_SIZE,
+        FILE_UPLOAD_SESSION_DELETE_BATCH_SIZE,
+        FILE_UPLOAD_SESSION_TTL,
+    },
+    pause::PauseClient,
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    sha256::{
+        Sha256,
+        Sha256Digest,
+    },
+    types::ObjectKey,
+};
+use database::Transaction;
+use errors::ErrorMetadata;
+use futures::TryStreamExt;
+use headers::ContentType;
+use keybroker::Identity;
+use model::file_storage::{
+    types::{
+        FileStorageEntry,
+        FileUploadSession,
+        StorageUuid,
+    },
+    FileStorageModel,
+};
+use storage::{
+    ClientDrivenUploadPartToken,
+    ClientDrivenUploadToken,
+    StorageExt,
+};
+use usage_tracking::{
+    FunctionUsageTracker,
+    StorageCallTracker,
+    StorageUsageTracker,
+};
+use value::id_v6::DocumentIdV6;
+
+use crate::FileStorage;
+
+/// How much of a resumable upload has been received.
+pub struct FileUploadProgress {
+    pub upload_id: String,
+    /// Number of bytes received so far, which is where the next part starts.
+    pub offset: u64,
+    pub expiration_ts: UnixTimestamp,
+}
+
+impl From<FileUploadSession> for FileUploadProgress {
+    fn from(session: FileUploadSession) -> Self {
+        Self {
+            upload_id: session.upload_id,
+            offset: session.offset as u64,
+            expiration_ts: UnixTimestamp::from_nanos(session.expiration_ts as u64),
+        }
+    }
+}
+
+impl<RT: Runtime> FileStorage<RT> {
+    pub async fn start_upload_session(
+        &self,
+        authorization: &str,
+        content_type: Option<ContentType>,
+    ) -> anyhow::Result<FileUploadProgress> {
+        let rt = &self.transactional_file_storage.rt;
+        let upload_token = self
+            .transactional_file_storage
+            .storage
+            .start_client_driven_upload()
+            .await?;
+        let now = rt.unix_timestamp();
+        let session = FileUploadSession {
+            upload_id: rt.new_uuid_v4().to_string(),
+            authorization_sha256: Sha256::hash(authorization.as_bytes()),
+            upload_token: upload_token.0,
+            part_tokens: vec![],
+            next_part_number: 1,
+            offset: 0,
+            content_type: content_type.map(|ct| ct.to_string()),
+            expiration_ts: (now + *FILE_UPLOAD_SESSION_TTL).as_nanos().try_into()?,
+        };
+
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let mut model = FileStorageModel::new(&mut tx);
+        // Abandoned sessions are never finished, so clean up a few of them
+        // whenever a session starts. Their objects are left for the storage
+        // garbage collector.
+        let expired = model
+            .expired_upload_sessions(
+                now.as_nanos().try_into()?,
+                *FILE_UPLOAD_SESSION_DELETE_BATCH_SIZE,
+            )
+            .await?;
+        for session in expired {
+            model.delete_upload_session(session.id()).await?;
+        }
+        model.insert_upload_session(session.clone()).await?;
+        self.database
+            .commit_with_write_source(tx, "file_storage_start_upload_session")
+            .await?;
+        Ok(session.into())
+    }
+
+    pub async fn get_upload_session(
+        &self,
+        authorization: &str,
+        upload_id: &str,
+    ) -> anyhow::Result<FileUploadProgress> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let session = self
+            .live_upload_session(&mut tx, authorization, upload_id)
+            .await?;
+        Ok(session.into_value().into())
+    }
+
+    /// Append `part` to the upload, which must start at `offset`, the number
+    /// of bytes uploaded so far. If several parts are uploaded at the same
+    /// offset, only the first to finish is kept and the rest are rejected.
+    pub async fn upload_session_part(
+        &self,
+        authorization: &str,
+        upload_id: &str,
+        offset: u64,
+        part: Bytes,
+    ) -> anyhow::Result<FileUploadProgress> {
+        anyhow::ensure!(
+            part.len() <= *FILE_UPLOAD_MAX_PART_SIZE,
+            ErrorMetadata::bad_request(
+                "UploadPartTooLarge",
+                format!(
+                    "Upload parts can be at most {} bytes, but this part is {} bytes",
+                    *FILE_UPLOAD_MAX_PART_SIZE,
+                    part.len()
+                ),
+            )
+        );
+        let authorization_sha256 = Sha256::hash(authorization.as_bytes());
+        // Claim a part number before uploading so that concurrent attempts at
+        // the same offset, e.g. a client retrying a part it thinks failed,
+        // never write to the same part.
+        let now = self.now()?;
+        let (_, (upload_token, part_number), _) = self
+            .database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "file_storage_claim_upload_part",
+                |tx| {
+                    Self::claim_upload_part(
+                        tx,
+                        now,
+                        authorization_sha256.clone(),
+                        upload_id.to_string(),
+                        offset,
+                    )
+                    .into()
+                },
+            )
+            .await?;
+        let part_len = part.len() as i64;
+        let part_token = self
+            .transactional_file_storage
+            .storage
+            .upload_part(ClientDrivenUploadToken(upload_token), part_number, part)
+            .await?;
+
+        // Record the part in a new transaction, since the upload may have been
+        // slow. If another attempt was recorded at this offset in the meantime
+        // this one is rejected, and its part is never included in the file.
+        let now = self.now()?;
+        let (_, session, _) = self
+            .database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "file_storage_upload_session_part",
+                |tx| {
+                    Self::record_upload_part(
+                        tx,
+                        now,
+                        authorization_sha256.clone(),
+                        upload_id.to_string(),
+                        offset,
+                        part_token.0.clone(),
+                        part_len,
+                    )
+                    .into()
+                },
+            )
+            .await?;
+        Ok(session.into())
+    }
+
+    /// Complete the upload and store it as a file, as long as its contents
+    /// match `expected_sha256`. The session can't be used afterwards, even if
+    /// the check fails.
+    pub async fn finish_upload_session(
+        &self,
+        authorization: &str,
+        upload_id: &str,
+        expected_sha256: Sha256Digest,
+        usage_tracker: &dyn StorageUsageTracker,
+    ) -> anyhow::Result<DocumentIdV6> {
+        let storage = &self.transactional_file_storage.storage;
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let session = self
+            .live_upload_session(&mut tx, authorization, upload_id)
+            .await?;
+        let storage_key = storage
+            .finish_client_driven_upload(
+                ClientDrivenUploadToken(session.upload_token.clone()),
+                session
+                    .part_tokens
+                    .iter()
+                    .cloned()
+                    .map(ClientDrivenUploadPartToken)
+                    .collect(),
+            )
+            .await?;
+        // Parts may arrive over many connections, so hash the finished object
+        // rather than the parts.
+        let (size, actual_sha256) = self.hash_object(&storage_key).await?;
+
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let session = self
+            .live_upload_session(&mut tx, authorization, upload_id)
+            .await?;
+        let id = session.id();
+        let session = session.into_value();
+        FileStorageModel::new(&mut tx)
+            .delete_upload_session(id)
+            .await?;
+        if size != session.offset || actual_sha256 != expected_sha256 {
+            self.database
+                .commit_with_write_source(tx, "file_storage_finish_upload_session")
+                .await?;
+            storage.delete_object(&storage_key).await?;
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "Sha256Mismatch",
+                format!(
+                    "Sha256 mismatch. Expected: {} Actual: {}",
+                    expected_sha256.as_base64(),
+                    actual_sha256.as_base64()
+                ),
+            ));
+        }
+        let entry = FileStorageEntry {
+            storage_id: StorageUuid::from(self.transactional_file_storage.rt.new_uuid_v4()),
+            storage_key,
+            sha256: actual_sha256,
+            size,
+            content_type: session.content_type,
+        };
+        let virtual_id = self
+            .transactional_file_storage
+            .store_file_entry(&mut tx, entry)
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "file_storage_finish_upload_session")
+            .await?;
+
+        usage_tracker
+            .track_storage_call("store")
+            .track_storage_ingress_size(size as u64);
+        Ok(virtual_id)
+    }
+
+    async fn claim_upload_part(
+        tx: &mut Transaction<RT>,
+        now: i64,
+        authorization_sha256: Sha256Digest,
+        upload_id: String,
+        offset: u64,
+    ) -> anyhow::Result<(String, u16)> {
+        let session =
+            Self::live_upload_session_at(tx, now, &authorization_sha256, &upload_id).await?;
+        check_offset(&session, offset)?;
+        let id = session.id();
+        let mut session = session.into_value();
+        let part_number: u16 = session.next_part_number.try_into().map_err(|_| {
+            ErrorMetadata::bad_request(
+                "TooManyUploadParts",
+                format!("Uploads can have at most {} parts", u16::MAX),
+            )
+        })?;
+        session.next_part_number += 1;
+        let upload_token = session.upload_token.clone();
+        FileStorageModel::new(tx)
+            .replace_upload_session(id, session)
+            .await?;
+        Ok((upload_token, part_number))
+    }
+
+    async fn record_upload_part(
+        tx: &mut Transaction<RT>,
+        now: i64,
+        authorization_sha256: Sha256Digest,
+        upload_id: String,
+        offset: u64,
+        part_token: String,
+        part_len: i64,
+    ) -> anyhow::Result<FileUploadSession> {
+        let session =
+            Self::live_upload_session_at(tx, now, &authorization_sha256, &upload_id).await?;
+        check_offset(&session, offset)?;
+        let id = session.id();
+        let mut session = session.into_value();
+        session.part_tokens.push(part_token);
+        session.offset += part_len;
+        FileStorageModel::new(tx)
+            .replace_upload_session(id, session.clone())
+            .await?;
+        Ok(session)
+    }
+
+    fn now(&self) -> anyhow::Result<i64> {
+        Ok(self
+            .transactional_file_storage
+            .rt
+            .unix_timestamp()
+            .as_nanos()
+            .try_into()?)
+    }
+
+    async fn live_upload_session(
+        &self,
+        tx: &mut Transaction<RT>,
+        authorization: &str,
+        upload_id: &str,
+    ) -> anyhow::Result<ParsedDocument<FileUploadSession>> {
+        let authorization_sha256 = Sha256::hash(authorization.as_bytes());
+        Self::live_upload_session_at(tx, self.now()?, &authorization_sha256, upload_id).await
+    }
+
+    /// Sessions started with a different token are reported as missing, so
+    /// their ids can't be probed for.
+    async fn live_upload_session_at(
+        tx: &mut Transaction<RT>,
+        now: i64,
+        authorization_sha256: &Sha256Digest,
+        upload_id: &str,
+    ) -> anyhow::Result<ParsedDocument<FileUploadSession>> {
+        match FileStorageModel::new(tx)
+            .get_upload_session(upload_id)
+            .await?
+        {
+            Some(session)
+                if session.expiration_ts > now
+                    && session.authorization_sha256 == *authorization_sha256 =>
+            {
+                Ok(session)
+            },
+            _ => anyhow::bail!(ErrorMetadata::not_found(
+                "UploadSessionNotFound",
+                format!("Upload session {upload_id} doesn't exist or has expired"),
+            )),
+        }
+    }
+
+    async fn hash_object(&self, storage_key: &ObjectKey) -> anyhow::Result<(i64, Sha256Digest)> {
+        let mut stream = self
+            .transactional_file_storage
+            .storage
+            .get(storage_key)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Finished upload {storage_key:?} is missing"))?
+            .stream;
+        let mut hasher = Sha256::new();
+        let mut size = 0;
+        while let Some(chunk) = stream.try_next().await? {
+            hasher.update(&chunk);
+            size += chunk.len() as i64;
+        }
+        Ok((size, hasher.finalize()))
+    }
+}
+
+fn check_offset(session: &FileUploadSession, offset: u64) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        session.offset as u64 == offset,
+        ErrorMetadata::bad_request(
+            "UploadOffsetMismatch",
+            format!(
+                "Part starts at offset {offset}, but the upload has {} bytes. Resume from there \
+                 instead.",
+                session.offset
+            ),
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,430 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use common::{
+    document::DocumentUpdate,
+    execution_context::ExecutionContext,
+    index::IndexKeyBytes,
+    knobs::{
+        MAX_BACKEND_ACTION_CALLBACKS_REQUEST_SIZE,
+        MAX_BACKEND_ACTION_CALLBACKS_RESPONSE_SIZE,
+    },
+    minitrace_helpers::EncodedSpan,
+    query::{
+        InternalSearch,
+        SearchVersion,
+    },
+    runtime::UnixTimestamp,
+    types::RepeatableTimestamp,
+};
+use database::{
+    TableCountSnapshot,
+    TransactionSearchSnapshot,
+};
+use indexing::index_registry::Index;
+use isolate::{
+    ActionCallbacks,
+    FunctionResult,
+};
+use keybroker::{
+    Identity,
+    InstanceSecret,
+    KeyBroker,
+};
+use minitrace::collector::SpanContext;
+use model::file_storage::{
+    types::FileStorageEntry,
+    FileStorageId,
+};
+use pb::{
+    backend::{
+        backend_client::BackendClient as BackendGrpcClient,
+        CancelJobRequest,
+        ExecuteActionRequest,
+        ExecuteMutationRequest,
+        ExecuteQueryRequest,
+        RequestHeader,
+        RevisionWithKey,
+        ScheduleJobRequest,
+        StorageDeleteRequest,
+        StorageGetFileEntryRequest,
+        StorageGetUrlRequest,
+        StorageStoreFileEntryRequest,
+        TableCountAtTsRequest,
+        TextSearchAtTsRequest,
+        VectorSearchRequest,
+    },
+    error_metadata::ErrorMetadataStatusExt,
+    searchlight::CandidateRevision as CandidateRevisionProto,
+};
+use search::QueryResults;
+use serde_json::Value as JsonValue;
+use sync_types::UdfPath;
+use tonic::transport::{
+    Channel,
+    Endpoint,
+};
+use usage_tracking::FunctionUsageStats;
+use value::{
+    id_v6::DocumentIdV6,
+    TableId,
+};
+use vector::PublicVectorSearchQueryResult;
+
+use crate::proto::{
+    path_and_args_to_proto,
+    vector_result_from_proto,
+};
+
+/// Client for the backend's `Backend` gRPC service, used by a Funrun server to
+/// call back into the backend that sent it a function.
+#[derive(Clone)]
+pub(crate) struct BackendClient {
+    instance_name: String,
+    system_key: String,
+    client: BackendGrpcClient<Channel>,
+}
+
+impl BackendClient {
+    pub(crate) fn new(
+        instance_name: String,
+        instance_secret: InstanceSecret,
+        backend_grpc_addr: String,
+    ) -> anyhow::Result<Self> {
+        let system_key = KeyBroker::new(&instance_name, instance_secret)?
+            .issue_system_key()
+            .to_string();
+        let channel = Endpoint::from_shared(backend_grpc_addr)?.connect_lazy();
+        let client = BackendGrpcClient::new(channel)
+            .max_encoding_message_size(*MAX_BACKEND_ACTION_CALLBACKS_REQUEST_SIZE)
+            .max_decoding_message_size(*MAX_BACKEND_ACTION_CALLBACKS_RESPONSE_SIZE);
+        Ok(Self {
+            instance_name,
+            system_key,
+            client,
+        })
+    }
+
+    /// Table counts and text searches for a query or mutation have to be
+    /// served at the function's snapshot.
+    pub(crate) fn snapshot(&self, ts: RepeatableTimestamp) -> BackendSnapshot {
+        BackendSnapshot {
+            client: self.clone(),
+            ts,
+        }
+    }
+
+    fn header(&self) -> Option<RequestHeader> {
+        Some(RequestHeader {
+            instance_name: Some(self.instance_name.clone()),
+            system_key: Some(self.system_key.clone()),
+        })
+    }
+}
+
+fn encoded_parent_trace() -> Option<String> {
+    EncodedSpan::from_parent(SpanContext::current_local_parent()).0
+}
+
+#[async_trait]
+impl ActionCallbacks for BackendClient {
+    async fn execute_query(
+        &self,
+        identity: Identity,
+        name: UdfPath,
+        args: Vec<JsonValue>,
+        block_logging: bool,
+        context: ExecutionContext,
+    ) -> anyhow::Result<FunctionResult> {
+        let request = ExecuteQueryRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            path_and_args: Some(path_and_args_to_proto(name, args)?),
+            block_logging: Some(block_logging),
+            execution_context: Some(context.into()),
+            encoded_parent_trace: encoded_parent_trace(),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute_query(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response.result.context("Missing result")?.try_into()
+    }
+
+    async fn execute_mutation(
+        &self,
+        identity: Identity,
+        name: UdfPath,
+        args: Vec<JsonValue>,
+        block_logging: bool,
+        context: ExecutionContext,
+    ) -> anyhow::Result<FunctionResult> {
+        let request = ExecuteMutationRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            path_and_args: Some(path_and_args_to_proto(name, args)?),
+            block_logging: Some(block_logging),
+            execution_context: Some(context.into()),
+            encoded_parent_trace: encoded_parent_trace(),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute_mutation(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response.result.context("Missing result")?.try_into()
+    }
+
+    async fn execute_action(
+        &self,
+        identity: Identity,
+        name: UdfPath,
+        args: Vec<JsonValue>,
+        block_logging: bool,
+        context: ExecutionContext,
+    ) -> anyhow::Result<FunctionResult> {
+        let request = ExecuteActionRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            path_and_args: Some(path_and_args_to_proto(name, args)?),
+            block_logging: Some(block_logging),
+            execution_context: Some(context.into()),
+            encoded_parent_trace: encoded_parent_trace(),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute_action(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response.result.context("Missing result")?.try_into()
+    }
+
+    async fn storage_get_url(
+        &self,
+        identity: Identity,
+        storage_id: FileStorageId,
+    ) -> anyhow::Result<Option<String>> {
+        let request = StorageGetUrlRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            storage_id: Some(storage_id.into()),
+        };
+        let response = self
+            .client
+            .clone()
+            .storage_get_url(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        Ok(response.url)
+    }
+
+    async fn storage_delete(
+        &self,
+        identity: Identity,
+        storage_id: FileStorageId,
+    ) -> anyhow::Result<()> {
+        let request = StorageDeleteRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            storage_id: Some(storage_id.into()),
+        };
+        self.client
+            .clone()
+            .storage_delete(request)
+            .await
+            .map_err(|status| status.into_anyhow())?;
+        Ok(())
+    }
+
+    async fn storage_get_file_entry(
+        &self,
+        identity: Identity,
+        storage_id: FileStorageId,
+    ) -> anyhow::Result<Option<FileStorageEntry>> {
+        let request = StorageGetFileEntryRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            storage_id: Some(storage_id.into()),
+        };
+        let response = self
+            .client
+            .clone()
+            .storage_get_file_entry(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response.entry.map(FileStorageEntry::try_from).transpose()
+    }
+
+    async fn storage_store_file_entry(
+        &self,
+        identity: Identity,
+        entry: FileStorageEntry,
+    ) -> anyhow::Result<DocumentIdV6> {
+        let request = StorageStoreFileEntryRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            entry: Some(entry.into()),
+        };
+        let response = self
+            .client
+            .clone()
+            .storage_store_file_entry(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        Ok(DocumentIdV6::decode(
+            &response.document_id.context("Missing document_id")?,
+        )?)
+    }
+
+    async fn schedule_job(
+        &self,
+        identity: Identity,
+        udf_path: UdfPath,
+        udf_args: Vec<JsonValue>,
+        scheduled_ts: UnixTimestamp,
+        context: ExecutionContext,
+    ) -> anyhow::Result<DocumentIdV6> {
+        let request = ScheduleJobRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            path_and_args: Some(path_and_args_to_proto(udf_path, udf_args)?),
+            scheduled_ts: Some(scheduled_ts.as_secs_f64()),
+            execution_context: Some(context.into()),
+        };
+        let response = self
+            .client
+            .clone()
+            .schedule_job(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        Ok(DocumentIdV6::decode(&response.id.context("Missing id")?)?)
+    }
+
+    async fn cancel_job(&self, identity: Identity, virtual_id: DocumentIdV6) -> anyhow::Result<()> {
+        let request = CancelJobRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            id: Some(virtual_id.encode()),
+        };
+        self.client
+            .clone()
+            .cancel_job(request)
+            .await
+            .map_err(|status| status.into_anyhow())?;
+        Ok(())
+    }
+
+    async fn vector_search(
+        &self,
+        identity: Identity,
+        query: JsonValue,
+    ) -> anyhow::Result<(Vec<PublicVectorSearchQueryResult>, FunctionUsageStats)> {
+        let request = VectorSearchRequest {
+            header: self.header(),
+            identity: Some(identity.into()),
+            query_json: Some(serde_json::to_vec(&query)?),
+        };
+        let response = self
+            .client
+            .clone()
+            .vector_search(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        let results = response
+            .results
+            .into_iter()
+            .map(vector_result_from_proto)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let usage_stats = response
+            .usage_stats
+            .context("Missing usage_stats")?
+            .try_into()?;
+        Ok((results, usage_stats))
+    }
+}
+
+/// A [`BackendClient`] pinned to a timestamp, implementing the snapshot traits
+/// a Funrun transaction needs for table counts and text search.
+pub(crate) struct BackendSnapshot {
+    client: BackendClient,
+    ts: RepeatableTimestamp,
+}
+
+#[async_trait]
+impl TableCountSnapshot for BackendSnapshot {
+    async fn count(&self, table: TableId) -> anyhow::Result<u64> {
+        let request = TableCountAtTsRequest {
+            header: self.client.header(),
+            ts: Some((*self.ts).into()),
+            table_id: Some(table.0.into()),
+        };
+        let response = self
+            .client
+            .client
+            .clone()
+            .table_count_at_ts(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response.count.context("Missing count")
+    }
+}
+
+#[async_trait]
+impl TransactionSearchSnapshot for BackendSnapshot {
+    async fn search(
+        &self,
+        index: &Index,
+        search: &InternalSearch,
+        version: SearchVersion,
+        pending_updates: &Vec<DocumentUpdate>,
+    ) -> anyhow::Result<QueryResults> {
+        let request = TextSearchAtTsRequest {
+            header: self.client.header(),
+            ts: Some((*self.ts).into()),
+            index_id: Some(index.id.into()),
+            query: None,
+            printable_index_name: Some(search.printable_index_name()?.to_string()),
+            pending_updates: pending_updates
+                .iter()
+                .cloned()
+                .map(|update| update.try_into())
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            search: Some(search.clone().into()),
+            version: Some(pb::backend::SearchVersion::from(version).into()),
+        };
+        let response = self
+            .client
+            .client
+            .clone()
+            .text_search_at_ts(request)
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        let revisions_with_keys = response
+            .revisions_with_keys
+            .into_iter()
+            .map(|RevisionWithKey { revision, key }| {
+                let revision: CandidateRevisionProto = revision.context("Missing revision")?;
+                let key = IndexKeyBytes(key.context("Missing key")?);
+                anyhow::Ok((revision.try_into()?, key))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let reads = response.reads.context("Missing reads")?.try_into()?;
+        Ok(QueryResults {
+            revisions_with_keys,
+            reads,
+        })
+    }
+}
@@ -0,0 +1,852 @@
+use std::sync::{
+    Arc,
+    Weak,
+};
+
+use anyhow::Context;
+use common::{
+    document::DocumentUpdate,
+    knobs::{
+        MAX_BACKEND_ACTION_CALLBACKS_REQUEST_SIZE,
+        MAX_BACKEND_ACTION_CALLBACKS_RESPONSE_SIZE,
+    },
+    minitrace_helpers::{
+        initialize_root_from_parent,
+        EncodedSpan,
+    },
+    query::{
+        InternalSearch,
+        SearchVersion,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+};
+use database::{
+    shutdown_error,
+    Database,
+    SearchIndexManagerSnapshot,
+    TableCountSnapshot,
+    TransactionSearchSnapshot,
+};
+use errors::ErrorMetadata;
+use isolate::ActionCallbacks;
+use keybroker::KeyBroker;
+use minitrace::future::FutureExt;
+use parking_lot::RwLock;
+use pb::{
+    backend::{
+        backend_server::{
+            Backend,
+            BackendServer,
+        },
+        CancelJobRequest,
+        CancelJobResponse,
+        ExecuteActionRequest,
+        ExecuteActionResponse,
+        ExecuteMutationRequest,
+        ExecuteMutationResponse,
+        ExecuteQueryRequest,
+        ExecuteQueryResponse,
+        RequestHeader,
+        RevisionWithKey,
+        ScheduleJobRequest,
+        ScheduleJobResponse,
+        StorageDeleteRequest,
+        StorageDeleteResponse,
+        StorageGetFileEntryRequest,
+        StorageGetFileEntryResponse,
+        StorageGetUrlRequest,
+        StorageGetUrlResponse,
+        StorageStoreFileEntryRequest,
+        StorageStoreFileEntryResponse,
+        TableCountAtTsRequest,
+        TableCountAtTsResponse,
+        TextSearchAtTsRequest,
+        TextSearchAtTsResponse,
+        VectorSearchRequest,
+        VectorSearchResponse,
+    },
+    error_metadata::ErrorMetadataStatusExt,
+};
+use search::QueryResults;
+use sync_types::Timestamp;
+use tonic::{
+    Request,
+    Response,
+    Status,
+};
+use value::{
+    id_v6::DocumentIdV6,
+    InternalId,
+    TableId,
+};
+
+use crate::proto::{
+    path_and_args_from_proto,
+    vector_result_to_proto,
+};
+
+pub(crate) type ActionCallbacksSlot = Arc<RwLock<Option<Weak<dyn ActionCallbacks>>>>;
+
+/// Serves the `Backend` gRPC service so Funrun servers can run action
+/// callbacks, table counts and text searches against this backend.
+///
+/// Every request must carry a system key issued from the instance secret, and
+/// admin identities in requests must carry valid admin keys.
+pub struct BackendService<RT: Runtime> {
+    instance_name: String,
+    key_broker: KeyBroker,
+    database: Database<RT>,
+    action_callbacks: ActionCallbacksSlot,
+}
+
+impl<RT: Runtime> BackendService<RT> {
+    pub(crate) fn new(
+        instance_name: String,
+        key_broker: KeyBroker,
+        database: Database<RT>,
+        action_callbacks: ActionCallbacksSlot,
+    ) -> Self {
+        Self {
+            instance_name,
+            key_broker,
+            database,
+            action_callbacks,
+        }
+    }
+
+    pub fn into_server(self) -> BackendServer<Self> {
+        BackendServer::new(self)
+            .max_decoding_message_size(*MAX_BACKEND_ACTION_CALLBACKS_REQUEST_SIZE)
+            .max_encoding_message_size(*MAX_BACKEND_ACTION_CALLBACKS_RESPONSE_SIZE)
+    }
+
+    fn validate_header(&self, header: Option<RequestHeader>) -> anyhow::Result<()> {
+        let RequestHeader {
+            instance_name,
+            system_key,
+        } = header.context("Missing header")?;
+        let instance_name = instance_name.context("Missing instance_name")?;
+        anyhow::ensure!(
+            instance_name == self.instance_name,
+            "Request for {instance_name} sent to {}",
+            self.instance_name
+        );
+        let is_system = system_key
+            .and_then(|key| self.key_broker.check_admin_key(&key).ok())
+            .is_some_and(|identity| identity.is_system());
+        anyhow::ensure!(
+            is_system,
+            ErrorMetadata::unauthenticated(
+                "InvalidSystemKey",
+                "Backend requests must be authenticated with a system key"
+            )
+        );
+        Ok(())
+    }
+
+    fn action_callbacks(&self) -> anyhow::Result<Arc<dyn ActionCallbacks>> {
+        self.action_callbacks
+            .read()
+            .clone()
+            .context("Action callbacks not set")?
+            .upgrade()
+            .context(shutdown_error())
+    }
+
+    fn snapshot(&self, ts: Option<u64>) -> anyhow::Result<database::Snapshot> {
+        let ts = Timestamp::try_from(ts.context("Missing ts")?)?;
+        let ts = self.database.now_ts_for_reads().prior_ts(ts)?;
+        self.database.snapshot(ts)
+    }
+
+    async fn text_search_at_ts_inner(
+        &self,
+        TextSearchAtTsRequest {
+            header,
+            ts,
+            index_id,
+            pending_updates,
+            search,
+            version,
+            ..
+        }: TextSearchAtTsRequest,
+    ) -> anyhow::Result<TextSearchAtTsResponse> {
+        self.validate_header(header)?;
+        let snapshot = self.snapshot(ts)?;
+        let index_id = InternalId::try_from(index_id.context("Missing index_id")?)?;
+        let index = snapshot
+            .index_registry
+            .enabled_index_by_index_id(&index_id)
+            .cloned()
+            .context("Search index is not enabled")?;
+        let search = InternalSearch::try_from(search.context("Missing search")?)?;
+        let version = SearchVersion::from(pb::backend::SearchVersion::try_from(
+            version.context("Missing version")?,
+        )?);
+        let pending_updates = pending_updates
+            .into_iter()
+            .map(DocumentUpdate::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let search_snapshot = SearchIndexManagerSnapshot::new(
+            snapshot.index_registry,
+            snapshot.search_indexes,
+            self.database.searcher.clone(),
+            self.database.search_storage.clone(),
+        );
+        let QueryResults {
+            revisions_with_keys,
+            reads,
+        } = search_snapshot
+            .search(&index, &search, version, &pending_updates)
+            .await?;
+        Ok(TextSearchAtTsResponse {
+            revisions_with_keys: revisions_with_keys
+                .into_iter()
+                .map(|(revision, key)| RevisionWithKey {
+                    revision: Some(revision.into()),
+                    key: Some(key.0),
+                })
+                .collect(),
+            reads: Some(reads.into()),
+        })
+    }
+
+    async fn table_count_at_ts_inner(
+        &self,
+        TableCountAtTsRequest {
+            header,
+            ts,
+            table_id,
+        }: TableCountAtTsRequest,
+    ) -> anyhow::Result<TableCountAtTsResponse> {
+        self.validate_header(header)?;
+        let snapshot = self.snapshot(ts)?;
+        let table_id = TableId(InternalId::try_from(table_id.context("Missing table_id")?)?);
+        let count = snapshot.table_summaries.count(table_id).await?;
+        Ok(TableCountAtTsResponse { count: Some(count) })
+    }
+
+    async fn execute_query_inner(
+        &self,
+        ExecuteQueryRequest {
+            header,
+            identity,
+            path_and_args,
+            block_logging,
+            execution_context,
+            ..
+        }: ExecuteQueryRequest,
+    ) -> anyhow::Result<ExecuteQueryResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let (path, args) =
+            path_and_args_from_proto(path_and_args.context("Missing path_and_args")?)?;
+        let context = execution_context
+            .context("Missing execution_context")?
+            .try_into()?;
+        let result = self
+            .action_callbacks()?
+            .execute_query(
+                identity,
+                path,
+                args,
+                block_logging.unwrap_or(false),
+                context,
+            )
+            .await?;
+        Ok(ExecuteQueryResponse {
+            result: Some(result.try_into()?),
+        })
+    }
+
+    async fn execute_mutation_inner(
+        &self,
+        ExecuteMutationRequest {
+            header,
+            identity,
+            path_and_args,
+            block_logging,
+            execution_context,
+            ..
+        }: ExecuteMutationRequest,
+    ) -> anyhow::Result<ExecuteMutationResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let (path, args) =
+            path_and_args_from_proto(path_and_args.context("Missing path_and_args")?)?;
+        let context = execution_context
+            .context("Missing execution_context")?
+            .try_into()?;
+        let result = self
+            .action_callbacks()?
+            .execute_mutation(
+                identity,
+                path,
+                args,
+                block_logging.unwrap_or(false),
+                context,
+            )
+            .await?;
+        Ok(ExecuteMutationResponse {
+            result: Some(result.try_into()?),
+        })
+    }
+
+    async fn execute_action_inner(
+        &self,
+        ExecuteActionRequest {
+            header,
+            identity,
+            path_and_args,
+            block_logging,
+            execution_context,
+            ..
+        }: ExecuteActionRequest,
+    ) -> anyhow::Result<ExecuteActionResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let (path, args) =
+            path_and_args_from_proto(path_and_args.context("Missing path_and_args")?)?;
+        let context = execution_context
+            .context("Missing execution_context")?
+            .try_into()?;
+        let result = self
+            .action_callbacks()?
+            .execute_action(
+                identity,
+                path,
+                args,
+                block_logging.unwrap_or(false),
+                context,
+            )
+            .await?;
+        Ok(ExecuteActionResponse {
+            result: Some(result.try_into()?),
+        })
+    }
+
+    async fn storage_get_url_inner(
+        &self,
+        StorageGetUrlRequest {
+            header,
+            identity,
+            storage_id,
+        }: StorageGetUrlRequest,
+    ) -> anyhow::Result<StorageGetUrlResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let storage_id = storage_id.context("Missing storage_id")?.try_into()?;
+        let url = self
+            .action_callbacks()?
+            .storage_get_url(identity, storage_id)
+            .await?;
+        Ok(StorageGetUrlResponse { url })
+    }
+
+    async fn storage_get_file_entry_inner(
+        &self,
+        StorageGetFileEntryRequest {
+            header,
+            identity,
+            storage_id,
+        }: StorageGetFileEntryRequest,
+    ) -> anyhow::Result<StorageGetFileEntryResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let storage_id = storage_id.context("Missing storage_id")?.try_into()?;
+        let entry = self
+            .action_callbacks()?
+            .storage_get_file_entry(identity, storage_id)
+            .await?;
+        Ok(StorageGetFileEntryResponse {
+            entry: entry.map(|entry| entry.into()),
+        })
+    }
+
+    async fn storage_store_file_entry_inner(
+        &self,
+        StorageStoreFileEntryRequest {
+            header,
+            identity,
+            entry,
+        }: StorageStoreFileEntryRequest,
+    ) -> anyhow::Result<StorageStoreFileEntryResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let entry = entry.context("Missing entry")?.try_into()?;
+        let document_id = self
+            .action_callbacks()?
+            .storage_store_file_entry(identity, entry)
+            .await?;
+        Ok(StorageStoreFileEntryResponse {
+            document_id: Some(document_id.encode()),
+        })
+    }
+
+    async fn storage_delete_inner(
+        &self,
+        StorageDeleteRequest {
+            header,
+            identity,
+            storage_id,
+        }: StorageDeleteRequest,
+    ) -> anyhow::Result<StorageDeleteResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let storage_id = storage_id.context("Missing storage_id")?.try_into()?;
+        self.action_callbacks()?
+            .storage_delete(identity, storage_id)
+            .await?;
+        Ok(StorageDeleteResponse {})
+    }
+
+    async fn schedule_job_inner(
+        &self,
+        ScheduleJobRequest {
+            header,
+            identity,
+            path_and_args,
+            scheduled_ts,
+            execution_context,
+        }: ScheduleJobRequest,
+    ) -> anyhow::Result<ScheduleJobResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let (path, args) =
+            path_and_args_from_proto(path_and_args.context("Missing path_and_args")?)?;
+        let scheduled_ts =
+            UnixTimestamp::from_secs_f64(scheduled_ts.context("Missing scheduled_ts")?);
+        let context = execution_context
+            .context("Missing execution_context")?
+            .try_into()?;
+        let id = self
+            .action_callbacks()?
+            .schedule_job(identity, path, args, scheduled_ts, context)
+            .await?;
+        Ok(ScheduleJobResponse {
+            id: Some(id.encode()),
+        })
+    }
+
+    async fn cancel_job_inner(
+        &self,
+        CancelJobRequest {
+            header,
+            identity,
+            id,
+        }: CancelJobRequest,
+    ) -> anyhow::Result<CancelJobResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let id = DocumentIdV6::decode(&id.context("Missing id")?)?;
+        self.action_callbacks()?.cancel_job(identity, id).await?;
+        Ok(CancelJobResponse {})
+    }
+
+    async fn vector_search_inner(
+        &self,
+        VectorSearchRequest {
+            header,
+            identity,
+            query_json,
+        }: VectorSearchRequest,
+    ) -> anyhow::Result<VectorSearchResponse> {
+        self.validate_header(header)?;
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let query = serde_json::from_slice(&query_json.context("Missing query_json")?)?;
+        let (results, usage_stats) = self
+            .action_callbacks()?
+            .vector_search(identity, query)
+            .await?;
+        Ok(VectorSearchResponse {
+            results: results.into_iter().map(vector_result_to_proto).collect(),
+            usage_stats: Some(usage_stats.into()),
+        })
+    }
+}
+
+fn into_response<T>(result: anyhow::Result<T>) -> Result<Response<T>, Status> {
+    result.map(Response::new).map_err(Status::from_anyhow)
+}
+
+#[tonic::async_trait]
+impl<RT: Runtime> Backend for BackendService<RT> {
+    async fn text_search_at_ts(
+        &self,
+        request: Request<TextSearchAtTsRequest>,
+    ) -> Result<Response<TextSearchAtTsResponse>, Status> {
+        into_response(self.text_search_at_ts_inner(request.into_inner()).await)
+    }
+
+    async fn table_count_at_ts(
+        &self,
+        request: Request<TableCountAtTsRequest>,
+    ) -> Result<Response<TableCountAtTsResponse>, Status> {
+        into_response(self.table_count_at_ts_inner(request.into_inner()).await)
+    }
+
+    async fn execute_query(
+        &self,
+        request: Request<ExecuteQueryRequest>,
+    ) -> Result<Response<ExecuteQueryResponse>, Status> {
+        let request = request.into_inner();
+        let root = initialize_root_from_parent(
+            "BackendService::execute_query",
+            EncodedSpan(request.encoded_parent_trace.clone()),
+        );
+        into_response(self.execute_query_inner(request).in_span(root).await)
+    }
+
+    async fn execute_mutation(
+        &self,
+        request: Request<ExecuteMutationRequest>,
+    ) -> Result<Response<ExecuteMutationResponse>, Status> {
+        let request = request.into_inner();
+        let root = initialize_root_from_parent(
+            "BackendService::execute_mutation",
+            EncodedSpan(request.encoded_parent_trace.clone()),
+        );
+        into_response(self.execute_mutation_inner(request).in_span(root).await)
+    }
+
+    async fn execute_action(
+        &self,
+        request: Request<ExecuteActionRequest>,
+    ) -> Result<Response<ExecuteActionResponse>, Status> {
+        let request = request.into_inner();
+        let root = initialize_root_from_parent(
+            "BackendService::execute_action",
+            EncodedSpan(request.encoded_parent_trace.clone()),
+        );
+        into_response(self.execute_action_inner(request).in_span(root).await)
+    }
+
+    async fn storage_get_url(
+        &self,
+        request: Request<StorageGetUrlRequest>,
+    ) -> Result<Response<StorageGetUrlResponse>, Status> {
+        into_response(self.storage_get_url_inner(request.into_inner()).await)
+    }
+
+    async fn storage_get_file_entry(
+        &self,
+        request: Request<StorageGetFileEntryRequest>,
+    ) -> Result<Response<StorageGetFileEntryResponse>, Status> {
+        into_response(
+            self.storage_get_file_entry_inner(request.into_inner())
+                .await,
+        )
+    }
+
+    async fn storage_store_file_entry(
+        &self,
+        request: Request<StorageStoreFileEntryRequest>,
+    ) -> Result<Response<StorageStoreFileEntryResponse>, Status> {
+        into_response(
+            self.storage_store_file_entry_inner(request.into_inner())
+                .await,
+        )
+    }
+
+    async fn storage_delete(
+        &self,
+        request: Request<StorageDeleteRequest>,
+    ) -> Result<Response<StorageDeleteResponse>, Status> {
+        into_response(self.storage_delete_inner(request.into_inner()).await)
+    }
+
+    async fn schedule_job(
+        &self,
+        request: Request<ScheduleJobRequest>,
+    ) -> Result<Response<ScheduleJobResponse>, Status> {
+        into_response(self.schedule_job_inner(request.into_inner()).await)
+    }
+
+    async fn cancel_job(
+        &self,
+        request: Request<CancelJobRequest>,
+    ) -> Result<Response<CancelJobResponse>, Status> {
+        into_response(self.cancel_job_inner(request.into_inner()).await)
+    }
+
+    async fn vector_search(
+        &self,
+        request: Request<VectorSearchRequest>,
+    ) -> Result<Response<VectorSearchResponse>, Status> {
+        into_response(self.vector_search_inner(request.into_inner()).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use common::{
+        execution_context::ExecutionContext,
+        http::serve_grpc,
+        runtime::{
+            Runtime,
+            UnixTimestamp,
+        },
+    };
+    use database::test_helpers::DbFixtures;
+    use errors::ErrorMetadataAnyhowExt;
+    use futures::channel::oneshot;
+    use isolate::{
+        ActionCallbacks,
+        FunctionResult,
+    };
+    use keybroker::{
+        Identity,
+        InstanceSecret,
+        KeyBroker,
+        DEV_INSTANCE_NAME,
+        DEV_SECRET,
+    };
+    use model::file_storage::{
+        types::FileStorageEntry,
+        FileStorageId,
+    };
+    use parking_lot::RwLock;
+    use runtime::prod::ProdRuntime;
+    use serde_json::Value as JsonValue;
+    use sync_types::UdfPath;
+    use usage_tracking::FunctionUsageStats;
+    use value::{
+        id_v6::DocumentIdV6,
+        ConvexValue,
+    };
+    use vector::PublicVectorSearchQueryResult;
+
+    use crate::{
+        backend_client::BackendClient,
+        backend_service::BackendService,
+    };
+
+    /// Answers calls with values naming the call, so a test can tell they
+    /// made it across.
+    struct EchoActionCallbacks;
+
+    impl EchoActionCallbacks {
+        fn result(identity: Identity, name: &str) -> anyhow::Result<FunctionResult> {
+            anyhow::ensure!(identity.is_system(), "Expected a system identity");
+            Ok(FunctionResult {
+                result: Ok(ConvexValue::try_from(name.to_string())?),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ActionCallbacks for EchoActionCallbacks {
+        async fn execute_query(
+            &self,
+            identity: Identity,
+            name: UdfPath,
+            _args: Vec<JsonValue>,
+            _block_logging: bool,
+            _context: ExecutionContext,
+        ) -> anyhow::Result<FunctionResult> {
+            Self::result(identity, &format!("query {name}"))
+        }
+
+        async fn execute_mutation(
+            &self,
+            identity: Identity,
+            name: UdfPath,
+            _args: Vec<JsonValue>,
+            _block_logging: bool,
+            _context: ExecutionContext,
+        ) -> anyhow::Result<FunctionResult> {
+            Self::result(identity, &format!("mutation {name}"))
+        }
+
+        async fn execute_action(
+            &self,
+            _identity: Identity,
+            _name: UdfPath,
+            _args: Vec<JsonValue>,
+            _block_logging: bool,
+            _context: ExecutionContext,
+        ) -> anyhow::Result<FunctionResult> {
+            unimplemented!()
+        }
+
+        async fn storage_get_url(
+            &self,
+            identity: Identity,
+            _storage_id: FileStorageId,
+        ) -> anyhow::Result<Option<String>> {
+            anyhow::ensure!(identity.is_system(), "Expected a system identity");
+            Ok(Some("https://storage.example/file".to_string()))
+        }
+
+        async fn storage_delete(
+            &self,
+            _identity: Identity,
+            _storage_id: FileStorageId,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn storage_get_file_entry(
+            &self,
+            _identity: Identity,
+            _storage_id: FileStorageId,
+        ) -> anyhow::Result<Option<FileStorageEntry>> {
+            unimplemented!()
+        }
+
+        async fn storage_store_file_entry(
+            &self,
+            _identity: Identity,
+            _entry: FileStorageEntry,
+        ) -> anyhow::Result<DocumentIdV6> {
+            unimplemented!()
+        }
+
+        async fn schedule_job(
+            &self,
+            _identity: Identity,
+            _udf_path: UdfPath,
+            _udf_args: Vec<JsonValue>,
+            _scheduled_ts: UnixTimestamp,
+            _context: ExecutionContext,
+        ) -> anyhow::Result<DocumentIdV6> {
+            unimplemented!()
+        }
+
+        async fn cancel_job(
+            &self,
+            _identity: Identity,
+            _virtual_id: DocumentIdV6,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn vector_search(
+            &self,
+            _identity: Identity,
+            _query: JsonValue,
+        ) -> anyhow::Result<(Vec<PublicVectorSearchQueryResult>, FunctionUsageStats)> {
+            unimplemented!()
+        }
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_backend_service_round_trip(rt: ProdRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new(&rt).await?;
+        let instance_secret = InstanceSecret::try_from(DEV_SECRET)?;
+        let action_callbacks: Arc<dyn ActionCallbacks> = Arc::new(EchoActionCallbacks);
+        let service = BackendService::new(
+            DEV_INSTANCE_NAME.to_string(),
+            KeyBroker::new(DEV_INSTANCE_NAME, instance_secret)?,
+            db,
+            Arc::new(RwLock::new(Some(Arc::downgrade(&action_callbacks)))),
+        );
+        let port = portpicker::pick_unused_port().expect("No ports free");
+        let addr = format!("127.0.0.1:{port}").parse()?;
+        let (_shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        rt.spawn("backend_grpc_server", async move {
+            let shutdown = async move {
+                let _ = shutdown_rx.await;
+            };
+            serve_grpc(service.into_server(), addr, shutdown)
+                .await
+                .expect("Backend gRPC server failed");
+        });
+        let backend_url = format!("http://{addr}");
+        let client = BackendClient::new(
+            DEV_INSTANCE_NAME.to_string(),
+            instance_secret,
+            backend_url.clone(),
+        )?;
+        let path: UdfPath = "messages:list".parse()?;
+
+        // It can take a moment for the server to start listening.
+        let mut attempts = 0;
+        let result = loop {
+            match client
+                .execute_query(
+                    Identity::system(),
+                    path.clone(),
+                    vec![],
+                    false,
+                    ExecutionContext::new_for_test(),
+                )
+                .await
+            {
+                Ok(result) => break result,
+                Err(e) if attempts < 100 => {
+                    tracing::info!("Backend gRPC server not ready: {e:#}");
+                    attempts += 1;
+                    rt.wait(Duration::from_millis(10)).await;
+                },
+                Err(e) => return Err(e),
+            }
+        };
+        assert_eq!(
+            result.result.unwrap(),
+            ConvexValue::try_from("query messages:list".to_string())?
+        );
+
+        let result = client
+            .execute_mutation(
+                Identity::system(),
+                path,
+                vec![],
+                false,
+                ExecutionContext::new_for_test(),
+            )
+            .await?;
+        assert_eq!(
+            result.result.unwrap(),
+            ConvexValue::try_from("mutation messages:list".to_string())?
+        );
+
+        let storage_id = FileStorageId::LegacyStorageId(rt.new_uuid_v4().into());
+        let url = client
+            .storage_get_url(Identity::system(), storage_id.clone())
+            .await?;
+        assert_eq!(url.as_deref(), Some("https://storage.example/file"));
+
+        // A runner that doesn't have the instance secret can't call in.
+        let client = BackendClient::new(
+            DEV_INSTANCE_NAME.to_string(),
+            InstanceSecret::random(),
+            backend_url,
+        )?;
+        let err = client
+            .storage_get_url(Identity::system(), storage_id)
+            .await
+            .unwrap_err();
+        assert!(err.is_unauthenticated(), "{err:?}");
+        Ok(())
+    }
+}
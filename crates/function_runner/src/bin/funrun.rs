@@ -0,0 +1,182 @@
+//! Standalone Funrun server, running functions for a backend started with
+//! `--funrun-url`.
+//!
+//! Funrun reads the backend's SQLite database and runs functions as any
+//! identity, so it only serves requests authenticated with a system key
+//! issued from the backend's instance secret.
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use clap::Parser;
+use cmd_util::env::config_service;
+use common::{
+    errors::MainError,
+    http::{
+        fetch::ProxiedFetchClient,
+        serve_grpc,
+    },
+    knobs::FUNRUN_SCHEDULER_MAX_PERCENT_PER_CLIENT,
+    persistence::Persistence,
+    version::COMPILED_REVISION,
+};
+use function_runner::{
+    funrun_service::FunrunService,
+    server::{
+        FunctionRunnerCore,
+        InstanceStorage,
+    },
+};
+use futures::FutureExt;
+use keybroker::{
+    InstanceSecret,
+    DEV_INSTANCE_NAME,
+    DEV_SECRET,
+};
+use runtime::prod::ProdRuntime;
+use sqlite::SqlitePersistence;
+use storage::{
+    LocalDirStorage,
+    StorageUseCase,
+};
+use tokio::signal;
+use url::Url;
+
+#[derive(Parser, Clone)]
+#[clap(version = COMPILED_REVISION, author = "Convex, Inc. <no-reply@convex.dev>")]
+struct FunrunConfig {
+    /// File path for the backend's SQLite database, opened read-only
+    #[clap(default_value = "convex_local_backend.sqlite3")]
+    db_spec: String,
+
+    /// The backend's local storage directory
+    #[clap(long, default_value = "convex_local_storage")]
+    local_storage: String,
+
+    /// Instance name of the backend, which must match its `--instance-name`
+    #[clap(long, requires = "instance_secret")]
+    instance_name: Option<String>,
+
+    /// Instance secret of the backend, used to authenticate its requests
+    #[clap(long, requires = "instance_name")]
+    instance_secret: Option<String>,
+
+    /// URL of the backend's gRPC service that Funrun calls back into
+    #[clap(long, default_value = "http://127.0.0.1:3212")]
+    backend_grpc_url: String,
+
+    /// Host interface to bind to
+    #[clap(short, long, default_value = "127.0.0.1")]
+    interface: ::std::net::Ipv4Addr,
+
+    /// Host port to serve the Funrun gRPC service on
+    #[clap(short, long, default_value = "3213")]
+    port: u16,
+
+    #[clap(long)]
+    convex_http_proxy: Option<Url>,
+}
+
+impl std::fmt::Debug for FunrunConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FunrunConfig")
+            .field("db_spec", &self.db_spec)
+            .field("local_storage", &self.local_storage)
+            .field("instance_name", &self.instance_name)
+            .field("backend_grpc_url", &self.backend_grpc_url)
+            .field("interface", &self.interface)
+            .field("port", &self.port)
+            .field("convex_http_proxy", &self.convex_http_proxy)
+            .finish()
+    }
+}
+
+impl FunrunConfig {
+    fn name(&self) -> String {
+        self.instance_name
+            .clone()
+            .unwrap_or(DEV_INSTANCE_NAME.to_owned())
+    }
+
+    fn secret(&self) -> anyhow::Result<InstanceSecret> {
+        InstanceSecret::try_from(
+            self.instance_secret
+                .clone()
+                .unwrap_or(DEV_SECRET.to_owned())
+                .as_str(),
+        )
+    }
+}
+
+fn main() -> Result<(), MainError> {
+    tracing::info!("Starting Funrun");
+    let _guard = config_service();
+    let config = FunrunConfig::parse();
+    tracing::info!("Starting with config {:?}", config);
+
+    sodiumoxide::init().map_err(|()| anyhow!("sodiumoxide initialization failed"))?;
+
+    let tokio = ProdRuntime::init_tokio()?;
+    let runtime = ProdRuntime::new(&tokio);
+
+    let runtime_ = runtime.clone();
+    let server_future = async move {
+        run_server(runtime_, config).await?;
+        Ok(())
+    };
+
+    runtime.block_on("main", server_future)
+}
+
+async fn run_server(runtime: ProdRuntime, config: FunrunConfig) -> anyhow::Result<()> {
+    let persistence = SqlitePersistence::new(&config.db_spec, true)?;
+    let storage = InstanceStorage {
+        files_storage: Arc::new(LocalDirStorage::for_use_case(
+            runtime.clone(),
+            &config.local_storage,
+            StorageUseCase::Files,
+        )?),
+        modules_storage: Arc::new(LocalDirStorage::for_use_case(
+            runtime.clone(),
+            &config.local_storage,
+            StorageUseCase::Modules,
+        )?),
+    };
+    let core = FunctionRunnerCore::new(
+        runtime.clone(),
+        storage,
+        *FUNRUN_SCHEDULER_MAX_PERCENT_PER_CLIENT,
+    )
+    .await?;
+    let fetch_client = Arc::new(ProxiedFetchClient::new(
+        config.convex_http_proxy.clone(),
+        "funrun".to_string(),
+    ));
+    let service = FunrunService::new(
+        runtime,
+        core,
+        config.name(),
+        config.secret()?,
+        config.backend_grpc_url.clone(),
+        persistence.reader(),
+        fetch_client,
+    )?;
+
+    let shutdown = signal::ctrl_c().map(|r| {
+        if let Err(e) = r {
+            tracing::error!("Failed to listen for Ctrl-C: {e}");
+        }
+        tracing::info!("Received Ctrl-C signal!");
+    });
+    serve_grpc(
+        service.clone().into_server(),
+        (config.interface.octets(), config.port).into(),
+        shutdown,
+    )
+    .await?;
+
+    tracing::info!("Shutting down function runner...");
+    service.shutdown().await?;
+    tracing::info!("Server successfully shut down.");
+    Ok(())
+}
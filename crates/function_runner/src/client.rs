@@ -0,0 +1,319 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use common::{
+    execution_context::ExecutionContext,
+    knobs::{
+        FUNRUN_CLIENT_MAX_RETRIES,
+        MAX_FUNRUN_REQUEST_MESSAGE_SIZE,
+        MAX_FUNRUN_RESPONSE_MESSAGE_SIZE,
+    },
+    log_lines::LogLine,
+    minitrace_helpers::EncodedSpan,
+    query_journal::QueryJournal,
+    runtime::Runtime,
+    types::{
+        ConvexOrigin,
+        IndexId,
+        RepeatableTimestamp,
+        UdfType,
+    },
+};
+use database::Database;
+use errors::ErrorMetadataAnyhowExt;
+use futures::channel::mpsc;
+use isolate::{
+    ActionCallbacks,
+    FunctionOutcome,
+    ValidatedUdfPathAndArgs,
+};
+use keybroker::{
+    Identity,
+    InstanceSecret,
+    KeyBroker,
+};
+use minitrace::collector::SpanContext;
+use model::environment_variables::types::{
+    EnvVarName,
+    EnvVarValue,
+};
+use parking_lot::RwLock;
+use pb::{
+    error_metadata::ErrorMetadataStatusExt,
+    funrun::{
+        funrun_client::FunrunClient,
+        funrun_response::Inner as FunrunResponseInner,
+        FunctionCompleteResponse,
+        FunrunRequest,
+        FunrunResponse,
+        InMemoryIndexLastModified,
+        LogLineResponse,
+        UdfType as UdfTypeProto,
+    },
+};
+use sync_types::{
+    backoff::Backoff,
+    Timestamp,
+};
+use tonic::{
+    transport::{
+        Channel,
+        Endpoint,
+    },
+    Streaming,
+};
+use usage_tracking::FunctionUsageStats;
+
+use crate::{
+    backend_service::{
+        ActionCallbacksSlot,
+        BackendService,
+    },
+    metrics::log_funrun_client_retry,
+    server::validate_run_function_result,
+    FunctionFinalTransaction,
+    FunctionRunner,
+    FunctionWrites,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(50);
+const MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+/// A [`FunctionRunner`] that runs functions on a remote Funrun server.
+///
+/// Requests are authenticated with a system key, so the Funrun server must be
+/// started with this backend's instance secret. It calls back into this
+/// backend through the `Backend` gRPC service returned by
+/// [`GrpcFunctionRunner::backend_service`].
+pub struct GrpcFunctionRunner<RT: Runtime> {
+    rt: RT,
+    client: FunrunClient<Channel>,
+
+    // Static information about the backend.
+    instance_name: String,
+    key_broker: KeyBroker,
+    system_key: String,
+    convex_origin: ConvexOrigin,
+    database: Database<RT>,
+    // Use Weak reference to avoid reference cycle between GrpcFunctionRunner
+    // and ApplicationFunctionRunner.
+    action_callbacks: ActionCallbacksSlot,
+}
+
+impl<RT: Runtime> GrpcFunctionRunner<RT> {
+    pub fn new(
+        rt: RT,
+        funrun_url: String,
+        instance_name: String,
+        instance_secret: InstanceSecret,
+        convex_origin: ConvexOrigin,
+        database: Database<RT>,
+    ) -> anyhow::Result<Self> {
+        let channel = Endpoint::from_shared(funrun_url)?.connect_lazy();
+        let client = FunrunClient::new(channel)
+            .max_encoding_message_size(*MAX_FUNRUN_REQUEST_MESSAGE_SIZE)
+            .max_decoding_message_size(*MAX_FUNRUN_RESPONSE_MESSAGE_SIZE);
+        let key_broker = KeyBroker::new(&instance_name, instance_secret)?;
+        let system_key = key_broker.issue_system_key().to_string();
+        Ok(Self {
+            rt,
+            client,
+            instance_name,
+            key_broker,
+            system_key,
+            convex_origin,
+            database,
+            action_callbacks: Arc::new(RwLock::new(None)),
+        })
+    }
+
+    /// The `Backend` service Funrun calls back into while running this
+    /// runner's functions.
+    pub fn backend_service(&self) -> BackendService<RT> {
+        BackendService::new(
+            self.instance_name.clone(),
+            self.key_broker.clone(),
+            self.database.clone(),
+            self.action_callbacks.clone(),
+        )
+    }
+
+    fn request(
+        &self,
+        path_and_args: ValidatedUdfPathAndArgs,
+        udf_type: UdfType,
+        identity: Identity,
+        ts: RepeatableTimestamp,
+        existing_writes: FunctionWrites,
+        journal: QueryJournal,
+        system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
+        in_memory_index_last_modified: BTreeMap<IndexId, Timestamp>,
+        context: ExecutionContext,
+    ) -> anyhow::Result<FunrunRequest> {
+        Ok(FunrunRequest {
+            instance_name: Some(self.instance_name.clone()),
+            system_key: Some(self.system_key.clone()),
+            db_cluster_name: None,
+            convex_origin: Some(self.convex_origin.to_string()),
+            path_and_args: Some(path_and_args.try_into()?),
+            udf_type: UdfTypeProto::from(udf_type).into(),
+            identity: Some(identity.into()),
+            existing_writes: Some(existing_writes.try_into()?),
+            journal: Some(journal.into()),
+            system_env_vars: system_env_vars
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            in_memory_index_last_modified: in_memory_index_last_modified
+                .into_iter()
+                .map(|(index_id, ts)| InMemoryIndexLastModified {
+                    index_id: Some(index_id.into()),
+                    last_modified: Some(SystemTime::from(ts).into()),
+                })
+                .collect(),
+            bootstrap_metadata: Some(self.database.bootstrap_metadata.clone().into()),
+            context: Some(context.into()),
+            repeatable_ts: Some(ts.into()),
+            encoded_parent_trace: EncodedSpan::from_parent(SpanContext::current_local_parent()).0,
+        })
+    }
+
+    /// Reads the response stream, forwarding log lines until the function
+    /// completes.
+    async fn receive_responses(
+        mut responses: Streaming<FunrunResponse>,
+        log_line_sender: &Option<mpsc::UnboundedSender<LogLine>>,
+    ) -> anyhow::Result<FunctionCompleteResponse> {
+        while let Some(FunrunResponse { inner }) = responses
+            .message()
+            .await
+            .map_err(|status| status.into_anyhow())?
+        {
+            match inner.context("Missing funrun response")? {
+                FunrunResponseInner::LogLines(LogLineResponse { log_lines }) => {
+                    let log_line_sender = log_line_sender
+                        .as_ref()
+                        .context("Received log lines without a log line sender")?;
+                    for log_line in log_lines {
+                        // The receiver may have gone away if the caller stopped
+                        // waiting for this function.
+                        let _ = log_line_sender.unbounded_send(log_line.try_into()?);
+                    }
+                },
+                FunrunResponseInner::FunctionComplete(complete) => return Ok(complete),
+            }
+        }
+        anyhow::bail!("Funrun response stream ended before the function completed")
+    }
+
+    /// Whether an error from Funrun is known to have happened before the
+    /// function did anything observable.
+    fn is_retryable(udf_type: UdfType, error: &anyhow::Error) -> bool {
+        if error.is_rejected_before_execution() {
+            return true;
+        }
+        // Queries and mutations have no side effects outside of the
+        // transaction we return, so it's safe to retry them whenever Funrun
+        // was unreachable.
+        let unavailable = error
+            .downcast_ref::<tonic::Status>()
+            .is_some_and(|status| status.code() == tonic::Code::Unavailable);
+        unavailable && matches!(udf_type, UdfType::Query | UdfType::Mutation)
+    }
+}
+
+#[async_trait]
+impl<RT: Runtime> FunctionRunner<RT> for GrpcFunctionRunner<RT> {
+    #[minitrace::trace]
+    async fn run_function(
+        &self,
+        path_and_args: ValidatedUdfPathAndArgs,
+        udf_type: UdfType,
+        identity: Identity,
+        ts: RepeatableTimestamp,
+        existing_writes: FunctionWrites,
+        journal: QueryJournal,
+        log_line_sender: Option<mpsc::UnboundedSender<LogLine>>,
+        system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
+        in_memory_index_last_modified: BTreeMap<IndexId, Timestamp>,
+        context: ExecutionContext,
+    ) -> anyhow::Result<(
+        Option<FunctionFinalTransaction>,
+        FunctionOutcome,
+        FunctionUsageStats,
+    )> {
+        let request = self.request(
+            path_and_args.clone(),
+            udf_type,
+            identity.clone(),
+            ts,
+            existing_writes,
+            journal,
+            system_env_vars,
+            in_memory_index_last_modified,
+            context,
+        )?;
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        // NOTE: Funrun doesn't check retention, so it is important that we do
+        // not surface any errors or results until after we call
+        // `validate_run_function_result` below.
+        let result = loop {
+            let result = match self
+                .client
+                .clone()
+                .run_function_no_retention_check(request.clone())
+                .await
+            {
+                Ok(responses) => {
+                    Self::receive_responses(responses.into_inner(), &log_line_sender).await
+                },
+                Err(status) => Err(status.into_anyhow()),
+            };
+            match result {
+                Err(e)
+                    if Self::is_retryable(udf_type, &e)
+                        && (backoff.failures() as usize) < *FUNRUN_CLIENT_MAX_RETRIES =>
+                {
+                    let delay = self.rt.with_rng(|rng| backoff.fail(rng));
+                    tracing::warn!(
+                        "Retrying {udf_type} on Funrun after {}ms: {e:#}",
+                        delay.as_millis()
+                    );
+                    log_funrun_client_retry(udf_type);
+                    self.rt.wait(delay).await;
+                },
+                result => break result,
+            }
+        };
+        validate_run_function_result(udf_type, *ts, self.database.retention_validator()).await?;
+        let FunctionCompleteResponse {
+            transaction,
+            function_outcome,
+            usage_stats,
+        } = result?;
+        let transaction = transaction
+            .map(FunctionFinalTransaction::try_from)
+            .transpose()?;
+        let outcome = FunctionOutcome::from_proto(
+            function_outcome.context("Missing function_outcome")?,
+            path_and_args,
+            identity.into(),
+        )?;
+        let usage_stats = usage_stats.context("Missing usage_stats")?.try_into()?;
+        Ok((transaction, outcome, usage_stats))
+    }
+
+    /// This fn should be called on startup. All `run_function` calls will fail
+    /// if actions callbacks are not set.
+    fn set_action_callbacks(&self, action_callbacks: Arc<dyn ActionCallbacks>) {
+        *self.action_callbacks.write() = Some(Arc::downgrade(&action_callbacks));
+    }
+}
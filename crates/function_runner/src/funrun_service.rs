@@ -0,0 +1,319 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use common::{
+    http::fetch::FetchClient,
+    knobs::{
+        MAX_FUNRUN_REQUEST_MESSAGE_SIZE,
+        MAX_FUNRUN_RESPONSE_MESSAGE_SIZE,
+    },
+    log_lines::LogLine,
+    minitrace_helpers::{
+        initialize_root_from_parent,
+        EncodedSpan,
+    },
+    persistence::PersistenceReader,
+    runtime::Runtime,
+    types::{
+        ConvexOrigin,
+        IndexId,
+        RepeatableTimestamp,
+        UdfType,
+    },
+};
+use database::{
+    shutdown_error,
+    SlowQueryLog,
+};
+use errors::ErrorMetadata;
+use futures::{
+    channel::{
+        mpsc,
+        oneshot,
+    },
+    stream::{
+        self,
+        BoxStream,
+    },
+    StreamExt,
+};
+use isolate::ValidatedUdfPathAndArgs;
+use keybroker::{
+    InstanceSecret,
+    KeyBroker,
+};
+use minitrace::future::FutureExt;
+use model::environment_variables::types::{
+    EnvVarName,
+    EnvVarValue,
+};
+use pb::{
+    error_metadata::ErrorMetadataStatusExt,
+    funrun::{
+        funrun_response::Inner as FunrunResponseInner,
+        funrun_server::{
+            Funrun,
+            FunrunServer,
+        },
+        FunctionCompleteResponse,
+        FunrunRequest,
+        FunrunResponse,
+        InMemoryIndexLastModified,
+        LogLineResponse,
+        UdfType as UdfTypeProto,
+    },
+};
+use sync_types::Timestamp;
+use tonic::{
+    Request,
+    Response,
+    Status,
+};
+
+use crate::{
+    backend_client::BackendClient,
+    server::{
+        FunctionRunnerCore,
+        StorageForInstance,
+    },
+};
+
+/// Serves the `Funrun` gRPC service, running functions on behalf of a remote
+/// backend with a [`FunctionRunnerCore`].
+///
+/// Every request must carry a system key issued from the backend's instance
+/// secret, which Funrun is started with. Documents are read directly through
+/// `persistence_reader`, while table counts, text search and action callbacks
+/// go back to the backend's `Backend` service at `backend_grpc_addr`.
+pub struct FunrunService<RT: Runtime, S: StorageForInstance<RT>> {
+    rt: RT,
+    core: Arc<FunctionRunnerCore<RT, S>>,
+    instance_name: String,
+    instance_secret: InstanceSecret,
+    key_broker: KeyBroker,
+    backend: BackendClient,
+    persistence_reader: Arc<dyn PersistenceReader>,
+    fetch_client: Arc<dyn FetchClient>,
+}
+
+impl<RT: Runtime, S: StorageForInstance<RT>> Clone for FunrunService<RT, S> {
+    fn clone(&self) -> Self {
+        Self {
+            rt: self.rt.clone(),
+            core: self.core.clone(),
+            instance_name: self.instance_name.clone(),
+            instance_secret: self.instance_secret,
+            key_broker: self.key_broker.clone(),
+            backend: self.backend.clone(),
+            persistence_reader: self.persistence_reader.clone(),
+            fetch_client: self.fetch_client.clone(),
+        }
+    }
+}
+
+impl<RT: Runtime, S: StorageForInstance<RT>> FunrunService<RT, S> {
+    pub fn new(
+        rt: RT,
+        core: FunctionRunnerCore<RT, S>,
+        instance_name: String,
+        instance_secret: InstanceSecret,
+        backend_grpc_addr: String,
+        persistence_reader: Arc<dyn PersistenceReader>,
+        fetch_client: Arc<dyn FetchClient>,
+    ) -> anyhow::Result<Self> {
+        let key_broker = KeyBroker::new(&instance_name, instance_secret)?;
+        let backend =
+            BackendClient::new(instance_name.clone(), instance_secret, backend_grpc_addr)?;
+        Ok(Self {
+            rt,
+            core: Arc::new(core),
+            instance_name,
+            instance_secret,
+            key_broker,
+            backend,
+            persistence_reader,
+            fetch_client,
+        })
+    }
+
+    pub fn into_server(self) -> FunrunServer<Self> {
+        FunrunServer::new(self)
+            .max_decoding_message_size(*MAX_FUNRUN_REQUEST_MESSAGE_SIZE)
+            .max_encoding_message_size(*MAX_FUNRUN_RESPONSE_MESSAGE_SIZE)
+    }
+
+    pub async fn shutdown(&self) -> anyhow::Result<()> {
+        self.core.shutdown().await
+    }
+
+    fn validate_request(
+        &self,
+        instance_name: Option<String>,
+        system_key: Option<String>,
+    ) -> anyhow::Result<()> {
+        let is_system = system_key
+            .and_then(|key| self.key_broker.check_admin_key(&key).ok())
+            .is_some_and(|identity| identity.is_system());
+        anyhow::ensure!(
+            is_system,
+            ErrorMetadata::unauthenticated(
+                "InvalidSystemKey",
+                "Funrun requests must be authenticated with a system key"
+            )
+        );
+        let instance_name = instance_name.context("Missing instance_name")?;
+        anyhow::ensure!(
+            instance_name == self.instance_name,
+            "Request for {instance_name} sent to {}",
+            self.instance_name
+        );
+        Ok(())
+    }
+
+    async fn run_function(
+        &self,
+        FunrunRequest {
+            instance_name,
+            system_key,
+            convex_origin,
+            path_and_args,
+            udf_type,
+            identity,
+            existing_writes,
+            journal,
+            system_env_vars,
+            in_memory_index_last_modified,
+            bootstrap_metadata,
+            context,
+            repeatable_ts,
+            ..
+        }: FunrunRequest,
+        log_line_sender: mpsc::UnboundedSender<LogLine>,
+    ) -> anyhow::Result<FunctionCompleteResponse> {
+        self.validate_request(instance_name, system_key)?;
+        let convex_origin = ConvexOrigin::from(convex_origin.context("Missing convex_origin")?);
+        let path_and_args =
+            ValidatedUdfPathAndArgs::from_proto(path_and_args.context("Missing path_and_args")?)?;
+        let udf_type = UdfType::from(UdfTypeProto::try_from(udf_type)?);
+        let identity = self
+            .key_broker
+            .check_identity_proto(identity.context("Missing identity")?)?;
+        let ts = RepeatableTimestamp::try_from(repeatable_ts.context("Missing repeatable_ts")?)?;
+        let existing_writes = existing_writes
+            .context("Missing existing_writes")?
+            .try_into()?;
+        let journal = journal.context("Missing journal")?.try_into()?;
+        let system_env_vars = system_env_vars
+            .into_iter()
+            .map(|(name, value)| anyhow::Ok((name.parse()?, value.parse()?)))
+            .collect::<anyhow::Result<BTreeMap<EnvVarName, EnvVarValue>>>()?;
+        let in_memory_index_last_modified = in_memory_index_last_modified
+            .into_iter()
+            .map(
+                |InMemoryIndexLastModified {
+                     index_id,
+                     last_modified,
+                 }| {
+                    let index_id = IndexId::try_from(index_id.context("Missing index_id")?)?;
+                    let last_modified: SystemTime =
+                        last_modified.context("Missing last_modified")?.try_into()?;
+                    anyhow::Ok((index_id, Timestamp::try_from(last_modified)?))
+                },
+            )
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+        let bootstrap_metadata = bootstrap_metadata
+            .context("Missing bootstrap_metadata")?
+            .try_into()?;
+        let context = context.context("Missing context")?.try_into()?;
+        // Only actions stream their log lines back before completing.
+        let log_line_sender = (udf_type == UdfType::Action).then_some(log_line_sender);
+        let snapshot = Arc::new(self.backend.snapshot(ts));
+
+        let (transaction, outcome, usage_stats) = self
+            .core
+            .run_function_no_retention_check(
+                self.instance_name.clone(),
+                self.instance_secret,
+                self.persistence_reader.clone(),
+                convex_origin,
+                bootstrap_metadata,
+                snapshot.clone(),
+                snapshot,
+                Arc::new(self.backend.clone()),
+                self.fetch_client.clone(),
+                log_line_sender,
+                path_and_args,
+                udf_type,
+                identity,
+                ts,
+                existing_writes,
+                journal,
+                system_env_vars,
+                in_memory_index_last_modified,
+                context,
+                // The backend's slow query log lives in its own process, so
+                // slow queries run here aren't reported to it.
+                SlowQueryLog::new(),
+            )
+            .await?;
+        Ok(FunctionCompleteResponse {
+            transaction: transaction.map(|tx| tx.try_into()).transpose()?,
+            function_outcome: Some(outcome.try_into()?),
+            usage_stats: Some(usage_stats.into()),
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl<RT: Runtime, S: StorageForInstance<RT>> Funrun for FunrunService<RT, S> {
+    type RunFunctionNoRetentionCheckStream = BoxStream<'static, Result<FunrunResponse, Status>>;
+
+    async fn run_function_no_retention_check(
+        &self,
+        request: Request<FunrunRequest>,
+    ) -> Result<Response<Self::RunFunctionNoRetentionCheckStream>, Status> {
+        let request = request.into_inner();
+        let root = initialize_root_from_parent(
+            "FunrunService::run_function",
+            EncodedSpan(request.encoded_parent_trace.clone()),
+        );
+        let (log_line_sender, log_line_receiver) = mpsc::unbounded();
+        let (result_sender, result_receiver) = oneshot::channel();
+        // Run the function in its own task so it keeps making progress while
+        // log lines are being streamed to the client.
+        let service = self.clone();
+        self.rt.spawn(
+            "funrun_run_function",
+            async move {
+                let result = service.run_function(request, log_line_sender).await;
+                let _ = result_sender.send(result);
+            }
+            .in_span(root),
+        );
+        // The log line sender is dropped once the function completes, so every
+        // log line is sent before the final response.
+        let log_lines = log_line_receiver.map(|log_line| {
+            Ok(FunrunResponse {
+                inner: Some(FunrunResponseInner::LogLines(LogLineResponse {
+                    log_lines: vec![log_line.into()],
+                })),
+            })
+        });
+        let function_complete = async move {
+            result_receiver
+                .await
+                .map_err(|_| shutdown_error())
+                .and_then(|result| result)
+                .map(|complete| FunrunResponse {
+                    inner: Some(FunrunResponseInner::FunctionComplete(complete)),
+                })
+                .map_err(Status::from_anyhow)
+        };
+        let responses = log_lines.chain(stream::once(function_complete));
+        Ok(Response::new(responses.boxed()))
+    }
+}
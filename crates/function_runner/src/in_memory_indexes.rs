@@ -41,6 +41,7 @@ use common::{
 use database::{
     BootstrapMetadata,
     DatabaseSnapshot,
+    SlowQueryLog,
     TableCountSnapshot,
     TableRegistry,
     Transaction,
@@ -101,6 +102,7 @@ pub struct TransactionIngredients<RT: Runtime> {
     pub retention_validator: Arc<dyn RetentionValidator>,
     pub virtual_system_mapping: VirtualSystemMapping,
     pub usage_tracker: FunctionUsageTracker,
+    pub slow_query_log: SlowQueryLog,
 }
 
 impl<RT: Runtime> TryFrom<TransactionIngredients<RT>> for Transaction<RT> {
@@ -120,6 +122,7 @@ impl<RT: Runtime> TryFrom<TransactionIngredients<RT>> for Transaction<RT> {
             retention_validator,
             virtual_system_mapping,
             usage_tracker,
+            slow_query_log,
         }: TransactionIngredients<RT>,
     ) -> Result<Self, Self::Error> {
         let id_generator = TransactionIdGenerator::new(&rt)?;
@@ -140,6 +143,7 @@ impl<RT: Runtime> TryFrom<TransactionIngredients<RT>> for Transaction<RT> {
             usage_tracker,
             retention_validator,
             virtual_system_mapping,
+            slow_query_log,
         );
         let updates = existing_writes.updates;
         tx.merge_writes(updates, existing_writes.generated_ids)?;
@@ -357,6 +361,7 @@ impl<RT: Runtime> InMemoryIndexCache<RT> {
         search_index_snapshot: Arc<dyn TransactionSearchSnapshot>,
         usage_tracker: FunctionUsageTracker,
         retention_validator: Arc<dyn RetentionValidator>,
+        slow_query_log: SlowQueryLog,
     ) -> anyhow::Result<TransactionIngredients<RT>> {
         let _timer = begin_tx_timer();
         for (index_id, last_modified) in &in_memory_index_last_modified {
@@ -393,6 +398,7 @@ impl<RT: Runtime> InMemoryIndexCache<RT> {
             retention_validator,
             virtual_system_mapping: virtual_system_mapping(),
             usage_tracker,
+            slow_query_log,
         };
         Ok(transaction_ingredients)
     }
@@ -51,6 +51,10 @@ use value::{
     TableNumber,
 };
 
+mod backend_client;
+pub mod backend_service;
+pub mod client;
+pub mod funrun_service;
 mod in_memory_indexes;
 mod isolate_worker;
 mod metrics;
@@ -78,9 +82,9 @@ pub trait FunctionRunner<RT: Runtime>: Send + Sync + 'static {
         FunctionUsageStats,
     )>;
 
-    /// Set the action callbacks. Only used for InProcessFunctionRunner to break
-    /// a reference cycle between ApplicationFunctionRunner and dyn
-    /// FunctionRunner.
+    /// Set the action callbacks. Used by InProcessFunctionRunner and
+    /// GrpcFunctionRunner to break a reference cycle between
+    /// ApplicationFunctionRunner and dyn FunctionRunner.
     fn set_action_callbacks(&self, action_callbacks: Arc<dyn ActionCallbacks>);
 }
 
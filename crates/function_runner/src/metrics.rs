@@ -1,3 +1,4 @@
+use common::types::UdfType;
 use metrics::{
     log_counter_with_labels,
     log_distribution_with_labels,
@@ -63,3 +64,16 @@ register_convex_histogram!(
 pub fn begin_tx_timer() -> Timer<VMHistogram> {
     Timer::new(&FUNCTION_RUNNER_BEGIN_TX_SECONDS)
 }
+
+register_convex_counter!(
+    FUNRUN_CLIENT_RETRIES_TOTAL,
+    "Number of times a function was retried on Funrun",
+    &["udf_type"]
+);
+pub fn log_funrun_client_retry(udf_type: UdfType) {
+    log_counter_with_labels(
+        &FUNRUN_CLIENT_RETRIES_TOTAL,
+        1,
+        vec![udf_type.metric_label()],
+    );
+}
@@ -8,15 +8,23 @@ use std::{
 
 use anyhow::Context;
 use common::document::DocumentUpdate;
-use pb::funrun::{
-    FunrunFinalTransaction as FunrunFinalTransactionProto,
-    FunrunReads as FunrunReadsProto,
-    Writes as FunrunWritesProto,
+use pb::{
+    backend::PublicVectorQueryResult as PublicVectorQueryResultProto,
+    common::PathAndArgs as PathAndArgsProto,
+    funrun::{
+        FunrunFinalTransaction as FunrunFinalTransactionProto,
+        FunrunReads as FunrunReadsProto,
+        Writes as FunrunWritesProto,
+    },
 };
+use serde_json::Value as JsonValue;
+use sync_types::UdfPath;
 use value::{
+    id_v6::DocumentIdV6,
     ResolvedDocumentId,
     TableNumber,
 };
+use vector::PublicVectorSearchQueryResult;
 
 use super::{
     FunctionFinalTransaction,
@@ -165,6 +173,45 @@ impl TryFrom<FunrunWritesProto> for FunctionWrites {
     }
 }
 
+/// Action callbacks pass an unvalidated path and argument list, so they can't
+/// go through the `ValidatedUdfPathAndArgs` conversion.
+pub(crate) fn path_and_args_to_proto(
+    path: UdfPath,
+    args: Vec<JsonValue>,
+) -> anyhow::Result<PathAndArgsProto> {
+    Ok(PathAndArgsProto {
+        path: Some(path.to_string()),
+        args: Some(serde_json::to_vec(&args)?),
+        npm_version: None,
+    })
+}
+
+pub(crate) fn path_and_args_from_proto(
+    PathAndArgsProto { path, args, .. }: PathAndArgsProto,
+) -> anyhow::Result<(UdfPath, Vec<JsonValue>)> {
+    let path = path.context("Missing path")?.parse()?;
+    let args = serde_json::from_slice(&args.context("Missing args")?)?;
+    Ok((path, args))
+}
+
+pub(crate) fn vector_result_to_proto(
+    PublicVectorSearchQueryResult { score, id }: PublicVectorSearchQueryResult,
+) -> PublicVectorQueryResultProto {
+    PublicVectorQueryResultProto {
+        score: Some(score),
+        document_id: Some(id.encode()),
+    }
+}
+
+pub(crate) fn vector_result_from_proto(
+    PublicVectorQueryResultProto { score, document_id }: PublicVectorQueryResultProto,
+) -> anyhow::Result<PublicVectorSearchQueryResult> {
+    Ok(PublicVectorSearchQueryResult {
+        score: score.context("Missing score")?,
+        id: DocumentIdV6::decode(&document_id.context("Missing document_id")?)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use cmd_util::env::env_config;
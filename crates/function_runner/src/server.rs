@@ -47,6 +47,7 @@ use database::{
     Database,
     FollowerRetentionManager,
     SearchIndexManagerSnapshot,
+    SlowQueryLog,
     TableCountSnapshot,
     Transaction,
     TransactionSearchSnapshot,
@@ -292,6 +293,7 @@ impl<RT: Runtime, S: StorageForInstance<RT>> FunctionRunnerCore<RT, S> {
         table_count_snapshot: Arc<dyn TableCountSnapshot>,
         search_index_snapshot: Arc<dyn TransactionSearchSnapshot>,
         retention_validator: Arc<dyn RetentionValidator>,
+        slow_query_log: SlowQueryLog,
     ) -> anyhow::Result<Transaction<RT>> {
         let usage_tracker = FunctionUsageTracker::new();
         let transaction_ingredients = self
@@ -308,6 +310,7 @@ impl<RT: Runtime, S: StorageForInstance<RT>> FunctionRunnerCore<RT, S> {
                 search_index_snapshot,
                 usage_tracker.clone(),
                 retention_validator,
+                slow_query_log,
             )
             .await?;
         let transaction = transaction_ingredients.clone().try_into()?;
@@ -341,6 +344,7 @@ impl<RT: Runtime, S: StorageForInstance<RT>> FunctionRunnerCore<RT, S> {
         system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
         in_memory_index_last_modified: BTreeMap<IndexId, Timestamp>,
         context: ExecutionContext,
+        slow_query_log: SlowQueryLog,
     ) -> anyhow::Result<(
         Option<FunctionFinalTransaction>,
         FunctionOutcome,
@@ -370,6 +374,7 @@ impl<RT: Runtime, S: StorageForInstance<RT>> FunctionRunnerCore<RT, S> {
                 search_index_snapshot,
                 usage_tracker.clone(),
                 retention_validator,
+                slow_query_log,
             )
             .await?;
         let mut transaction = transaction_ingredients.clone().try_into()?;
@@ -560,6 +565,7 @@ impl<RT: Runtime> FunctionRunner<RT> for InProcessFunctionRunner<RT> {
                 system_env_vars,
                 in_memory_index_last_modified,
                 context,
+                self.database.slow_query_log().clone(),
             )
             .await;
         validate_run_function_result(udf_type, *ts, self.database.retention_validator()).await?;
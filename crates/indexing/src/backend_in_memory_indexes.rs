@@ -359,6 +359,7 @@ impl DatabaseIndexSnapshot {
     async fn start_range_fetch(
         &self,
         range_request: RangeRequest,
+        version: IndexVersion,
     ) -> anyhow::Result<
         // Ok means we have a result immediately, Err means we need to fetch.
         Result<
@@ -369,21 +370,30 @@ impl DatabaseIndexSnapshot {
             (IndexId, RangeRequest, Vec<DatabaseIndexSnapshotCacheResult>),
         >,
     > {
-        let index = match self.index_registry.require_enabled(
-            &range_request.index_name,
-            &range_request.printable_index_name,
-        ) {
-            Ok(index) => index,
-            // Allow default system defined indexes on all tables other than the _index
-            // table.
-            Err(_)
-                if range_request.index_name.table()
-                    != &self.index_registry.index_table().table_id
-                    && range_request.index_name.is_by_id_or_creation_time() =>
-            {
-                return Ok(Ok((vec![], CursorPosition::End)));
+        let index = match version {
+            IndexVersion::Enabled => match self.index_registry.require_enabled(
+                &range_request.index_name,
+                &range_request.printable_index_name,
+            ) {
+                Ok(index) => index,
+                // Allow default system defined indexes on all tables other than the _index
+                // table.
+                Err(_)
+                    if range_request.index_name.table()
+                        != &self.index_registry.index_table().table_id
+                        && range_request.index_name.is_by_id_or_creation_time() =>
+                {
+                    return Ok(Ok((vec![], CursorPosition::End)));
+                },
+                Err(e) => anyhow::bail!(e),
+            },
+            // An index created after this snapshot has no entries in it.
+            IndexVersion::Pending => {
+                match self.index_registry.get_pending(&range_request.index_name) {
+                    Some(index) => index.clone(),
+                    None => return Ok(Ok((vec![], CursorPosition::End))),
+                }
             },
-            Err(e) => anyhow::bail!(e),
         };
 
         // Check that the index is indeed a database index.
@@ -396,8 +406,9 @@ impl DatabaseIndexSnapshot {
             anyhow::bail!(err);
         };
         anyhow::ensure!(
-            *on_disk_state == DatabaseIndexState::Enabled,
-            "Index returned from `require_enabled` but not enabled?"
+            (*on_disk_state == DatabaseIndexState::Enabled) == (version == IndexVersion::Enabled),
+            "Index {:?} is {on_disk_state:?}, but {version:?} was requested",
+            range_request.index_name
         );
 
         // Now that we know it's a database index, serve it from the pinned
@@ -427,6 +438,7 @@ impl DatabaseIndexSnapshot {
     pub async fn range_batch(
         &mut self,
         range_requests: BTreeMap<BatchKey, RangeRequest>,
+        version: IndexVersion,
     ) -> BTreeMap<
         BatchKey,
         anyhow::Result<(
@@ -439,7 +451,7 @@ impl DatabaseIndexSnapshot {
         let mut results = BTreeMap::new();
 
         for (batch_key, range_request) in range_requests {
-            let result = self.start_range_fetch(range_request).await;
+            let result = self.start_range_fetch(range_request, version).await;
             match result {
                 Err(e) => {
                     results.insert(batch_key, Err(e));
@@ -565,13 +577,16 @@ impl DatabaseIndexSnapshot {
         // We call next() twice due to the verification below.
         let max_size = 2;
         let (stream, cursor) = self
-            .range_batch(btreemap! { 0 => RangeRequest {
-                index_name,
-                printable_index_name,
-                interval: range,
-                order: Order::Asc,
-                max_size,
-            }})
+            .range_batch(
+                btreemap! { 0 => RangeRequest {
+                    index_name,
+                    printable_index_name,
+                    interval: range,
+                    order: Order::Asc,
+                    max_size,
+                }},
+                IndexVersion::Enabled,
+            )
             .await
             .remove(&0)
             .context("batch_key missing")??;
@@ -984,6 +999,16 @@ mod cache_tests {
 
 pub type BatchKey = usize;
 
+/// Which version of an index a range request reads. An index name can have
+/// both an enabled version and a pending one, e.g. while a changed index is
+/// backfilled. Applications can only query enabled indexes, but the database
+/// reads pending ones to enforce their constraints on writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexVersion {
+    Enabled,
+    Pending,
+}
+
 #[derive(Debug, Clone)]
 pub struct RangeRequest {
     pub index_name: TabletIndexName,
@@ -49,6 +49,7 @@ use imbl::{
 use itertools::Itertools;
 use value::{
     InternalId,
+    ResolvedDocumentId,
     TableId,
     TableIdAndTableNumber,
     TableMapping,
@@ -149,11 +150,36 @@ impl IndexRegistry {
         deletion: Option<&ResolvedDocument>,
         insertion: Option<&ResolvedDocument>,
     ) -> anyhow::Result<()> {
+        if let Some(document) = insertion {
+            self.check_index_filters(document)?;
+        }
         self.verify_update(deletion, insertion)?;
         self.apply_verified_update(deletion, insertion);
         Ok(())
     }
 
+    /// Rejects a document that one of its table's partial indexes can't
+    /// evaluate its filter on, rather than silently leaving it out of the
+    /// index.
+    fn check_index_filters(&self, document: &ResolvedDocument) -> anyhow::Result<()> {
+        for index in self.indexes_by_table(&document.table().table_id) {
+            let IndexConfig::Database {
+                developer_config, ..
+            } = &index.metadata.config
+            else {
+                continue;
+            };
+            if let Err(e) = developer_config.includes(document.value()) {
+                anyhow::bail!(index_filter_error(
+                    index.name().descriptor(),
+                    document.id(),
+                    e
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn index_keys<'a>(
         &'a self,
         document: &'a ResolvedDocument,
@@ -162,14 +188,16 @@ impl IndexRegistry {
             for index in self.indexes_by_table(&document.table().table_id) {
                 // Only yield fields from database indexes.
                 if let IndexConfig::Database {
-                    developer_config: DeveloperDatabaseIndexConfig { fields },
+                    developer_config,
                     on_disk_state: _,
                 } = &index.metadata.config
                 {
-                    yield (
-                        index,
-                        document.index_key(&fields[..], self.persistence_version()),
-                    );
+                    // Partial indexes leave out documents that don't match their
+                    // filter and multikey indexes have an entry per array
+                    // element, so a document has zero or more keys per index.
+                    for key in developer_config.index_keys(document, self.persistence_version()) {
+                        yield (index, key);
+                    }
                 }
             }
         })
@@ -464,6 +492,40 @@ impl IndexRegistry {
             .filter(|index| index.metadata.is_vector_index())
     }
 
+    /// Unique database indexes on the given table, both enabled and pending.
+    pub fn unique_indexes_by_table(&self, table_id: &TableId) -> Vec<Index> {
+        self.indexes_by_table(table_id)
+            .filter(|index| {
+                matches!(
+                    &index.metadata.config,
+                    IndexConfig::Database {
+                        developer_config,
+                        ..
+                    } if developer_config.unique
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Enabled and pending database indexes on the given table that maintain
+    /// aggregates, along with their configs.
+    pub fn aggregate_indexes_by_table(
+        &self,
+        table_id: &TableId,
+    ) -> Vec<(IndexId, DeveloperDatabaseIndexConfig)> {
+        self.indexes_by_table(table_id)
+            .filter_map(|index| match &index.metadata.config {
+                IndexConfig::Database {
+                    developer_config, ..
+                } if developer_config.aggregate.is_some() => {
+                    Some((index.id(), developer_config.clone()))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Returns both enabled and pending indexes for the given table.
     ///
     /// Multiple Indexes with a given name will be returned if an index is
@@ -624,6 +686,17 @@ pub fn index_backfilling_error(name: &IndexName) -> ErrorMetadata {
     )
 }
 
+pub fn index_filter_error(
+    descriptor: &IndexDescriptor,
+    id: ResolvedDocumentId,
+    e: anyhow::Error,
+) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "IndexFilterError",
+        format!("Failed to evaluate the filter of index {descriptor} on document {id}: {e}"),
+    )
+}
+
 pub fn index_not_found_error(name: &IndexName) -> ErrorMetadata {
     ErrorMetadata::bad_request("IndexNotFoundError", format!("Index {name} not found."))
 }
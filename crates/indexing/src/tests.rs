@@ -264,7 +264,7 @@ fn test_metadata_change_index() -> anyhow::Result<()> {
         .contains("Can't modify developer index config for existing indexes"));
     let current_metadata = index_registry.enabled_index_metadata(&by_name).unwrap();
     must_let!(let IndexConfig::Database { developer_config, .. } = &current_metadata.config);
-    must_let!(let DeveloperDatabaseIndexConfig { fields } = developer_config);
+    must_let!(let DeveloperDatabaseIndexConfig { fields, .. } = developer_config);
     assert_eq!(*fields, vec!["name".parse()?].try_into()?,);
 
     // Changing which table the index is indexing is not allowed.
@@ -302,7 +302,7 @@ fn test_metadata_change_index() -> anyhow::Result<()> {
     let current_metadata = index_registry.enabled_index_metadata(&by_name).unwrap();
     must_let!(
         let IndexConfig::Database {
-            developer_config: DeveloperDatabaseIndexConfig { fields },
+            developer_config: DeveloperDatabaseIndexConfig { fields, .. },
             ..
         } = &current_metadata.config
     );
@@ -355,7 +355,7 @@ fn test_second_pending_index_for_name_fails() -> anyhow::Result<()> {
     );
     let current_index = index_registry.get_pending(&by_name).unwrap();
     must_let!(let IndexConfig::Database { developer_config, .. } = &current_index.metadata.config);
-    must_let!(let DeveloperDatabaseIndexConfig { fields } = developer_config);
+    must_let!(let DeveloperDatabaseIndexConfig { fields, .. } = developer_config);
     assert_eq!(*fields, vec!["name".parse()?].try_into()?,);
 
     Ok(())
@@ -227,12 +227,26 @@ impl<RT: Runtime> TaskExecutor<RT> {
 
     async fn async_syscall_storageGenerateUploadUrl(
         &self,
-        _args: JsonValue,
+        args: JsonValue,
     ) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct GenerateUploadUrlArgs {
+            #[serde(default)]
+            resumable: bool,
+        }
+        let GenerateUploadUrlArgs { resumable } =
+            with_argument_error("storage.generateUploadUrl", || {
+                Ok(serde_json::from_value(args)?)
+            })?;
         let issued_ts = self.rt.unix_timestamp();
-        let postUrl = self
-            .file_storage
-            .generate_upload_url(&self.key_broker, issued_ts)?;
+        let postUrl = if resumable {
+            self.file_storage
+                .generate_resumable_upload_url(&self.key_broker, issued_ts)?
+        } else {
+            self.file_storage
+                .generate_upload_url(&self.key_broker, issued_ts)?
+        };
         Ok(serde_json::to_value(postUrl)?)
     }
 
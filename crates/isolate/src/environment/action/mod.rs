@@ -224,7 +224,7 @@ impl<RT: Runtime> ActionEnvironment<RT> {
             action_callbacks,
             fetch_client,
             module_loader: module_loader.clone(),
-            key_broker,
+            key_broker: key_broker.clone(),
             task_order: Default::default(),
             task_retval_sender,
             usage_tracker: transaction.usage_tracker.clone(),
@@ -243,7 +243,13 @@ impl<RT: Runtime> ActionEnvironment<RT> {
             task_responses,
             running_tasks: Some(running_tasks),
             task_promise_resolvers: BTreeMap::new(),
-            phase: ActionPhase::new(rt.clone(), transaction, module_loader, system_env_vars),
+            phase: ActionPhase::new(
+                rt.clone(),
+                transaction,
+                module_loader,
+                key_broker,
+                system_env_vars,
+            ),
             syscall_trace,
             heap_stats,
         }
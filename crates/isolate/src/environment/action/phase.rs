@@ -13,6 +13,7 @@ use common::{
 };
 use database::Transaction;
 use errors::ErrorMetadata;
+use keybroker::KeyBroker;
 use model::{
     environment_variables::{
         types::{
@@ -67,6 +68,7 @@ enum ActionPreloaded<RT: Runtime> {
     Created {
         tx: Transaction<RT>,
         module_loader: Arc<dyn ModuleLoader<RT>>,
+        key_broker: KeyBroker,
         system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
     },
     Preloading,
@@ -83,6 +85,7 @@ impl<RT: Runtime> ActionPhase<RT> {
         rt: RT,
         tx: Transaction<RT>,
         module_loader: Arc<dyn ModuleLoader<RT>>,
+        key_broker: KeyBroker,
         system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
     ) -> Self {
         Self {
@@ -91,6 +94,7 @@ impl<RT: Runtime> ActionPhase<RT> {
             preloaded: ActionPreloaded::Created {
                 tx,
                 module_loader,
+                key_broker,
                 system_env_vars,
             },
         }
@@ -107,6 +111,7 @@ impl<RT: Runtime> ActionPhase<RT> {
         let ActionPreloaded::Created {
             mut tx,
             module_loader,
+            key_broker,
             system_env_vars,
         } = preloaded
         else {
@@ -147,7 +152,7 @@ impl<RT: Runtime> ActionPhase<RT> {
         let user_env_vars = with_release_permit(
             timeout,
             permit_slot,
-            EnvironmentVariablesModel::new(&mut tx).get_all(),
+            EnvironmentVariablesModel::new(&mut tx).get_all(&key_broker),
         )
         .await?;
         env_vars.extend(user_env_vars);
@@ -14,6 +14,7 @@ use common::{
         Cursor,
         CursorPosition,
         Query,
+        QuerySource,
     },
     query_journal::QueryJournal,
     runtime::{
@@ -263,7 +264,7 @@ pub trait AsyncSyscallProvider<RT: Runtime> {
         scheduled_ts: UnixTimestamp,
     ) -> anyhow::Result<(UdfPath, ConvexArray)>;
 
-    fn file_storage_generate_upload_url(&self) -> anyhow::Result<String>;
+    fn file_storage_generate_upload_url(&self, resumable: bool) -> anyhow::Result<String>;
     async fn file_storage_get_url_batch(
         &mut self,
         storage_ids: BTreeMap<BatchKey, FileStorageId>,
@@ -352,11 +353,15 @@ impl<RT: Runtime> AsyncSyscallProvider<RT> for DatabaseUdfEnvironment<RT> {
         .await
     }
 
-    fn file_storage_generate_upload_url(&self) -> anyhow::Result<String> {
+    fn file_storage_generate_upload_url(&self, resumable: bool) -> anyhow::Result<String> {
         let issued_ts = self.phase.unix_timestamp()?;
-        let post_url = self
-            .file_storage
-            .generate_upload_url(&self.key_broker, issued_ts)?;
+        let post_url = if resumable {
+            self.file_storage
+                .generate_resumable_upload_url(&self.key_broker, issued_ts)?
+        } else {
+            self.file_storage
+                .generate_upload_url(&self.key_broker, issued_ts)?
+        };
         Ok(post_url)
     }
 
@@ -474,11 +479,21 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
         struct CountArgs {
-            table: String,
+            table: Option<String>,
+            query: Option<JsonValue>,
+            aggregate: Option<String>,
+        }
+        let args: CountArgs =
+            with_argument_error("db.count", || Ok(serde_json::from_value(args)?))?;
+        if let Some(query) = args.query {
+            return Self::index_aggregate(provider, query, args.aggregate).await;
         }
         let table = with_argument_error("db.count", || {
-            let args: CountArgs = serde_json::from_value(args)?;
-            args.table.parse().context(ArgName("table"))
+            args.table
+                .context("Missing table")
+                .context(ArgName("table"))?
+                .parse()
+                .context(ArgName("table"))
         })?;
         let tx = provider.tx()?;
         let result = tx.count(&table).await?;
@@ -490,6 +505,43 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
         Ok(ConvexValue::from(result).into())
     }
 
+    /// `count()` or `sum()` on a `withIndex` query, answered from the totals
+    /// the index maintains rather than by reading the documents.
+    async fn index_aggregate(
+        provider: &mut P,
+        query: JsonValue,
+        aggregate: Option<String>,
+    ) -> anyhow::Result<JsonValue> {
+        let (index_range, is_sum) = with_argument_error("query.count", || {
+            let query = Query::try_from(query).context(ArgName("query"))?;
+            let QuerySource::IndexRange(index_range) = query.source else {
+                anyhow::bail!("Only `withIndex` queries can be counted or summed");
+            };
+            anyhow::ensure!(
+                query.operators.is_empty(),
+                "Queries with `filter` or `take` can't be counted or summed"
+            );
+            let is_sum = match aggregate.as_deref() {
+                None | Some("count") => false,
+                Some("sum") => true,
+                Some(aggregate) => {
+                    return Err(anyhow::anyhow!("Unknown aggregate {aggregate}")
+                        .context(ArgName("aggregate")));
+                },
+            };
+            Ok((index_range, is_sum))
+        })?;
+        let tx = provider.tx()?;
+        let totals = tx.index_aggregate(index_range).await?;
+        let result = if is_sum {
+            totals.sum
+        } else {
+            // Return as f64, which converts to number type in Javascript.
+            totals.count as f64
+        };
+        Ok(ConvexValue::from(result).into())
+    }
+
     #[convex_macro::instrument_future]
     async fn get_user_identity(provider: &mut P, _args: JsonValue) -> anyhow::Result<JsonValue> {
         // TODO: Somehow make the Transaction aware of the dependency on the user.
@@ -505,9 +557,19 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
     #[convex_macro::instrument_future]
     async fn storage_generate_upload_url(
         provider: &mut P,
-        _args: JsonValue,
+        args: JsonValue,
     ) -> anyhow::Result<JsonValue> {
-        let post_url = provider.file_storage_generate_upload_url()?;
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct GenerateUploadUrlArgs {
+            #[serde(default)]
+            resumable: bool,
+        }
+        let GenerateUploadUrlArgs { resumable } =
+            with_argument_error("storage.generateUploadUrl", || {
+                Ok(serde_json::from_value(args)?)
+            })?;
+        let post_url = provider.file_storage_generate_upload_url(resumable)?;
         Ok(serde_json::to_value(post_url)?)
     }
 
@@ -337,13 +337,14 @@ impl<RT: Runtime> DatabaseUdfEnvironment<RT> {
             path_and_args,
             udf_type,
             identity,
-            transaction,
+            mut transaction,
             journal,
             context,
         }: UdfRequest<RT>,
     ) -> Self {
         let persistence_version = transaction.persistence_version();
         let (udf_path, arguments, udf_server_version) = path_and_args.consume();
+        transaction.enable_slow_query_log(udf_path.to_string());
         Self {
             rt: rt.clone(),
             udf_type,
@@ -352,7 +353,13 @@ impl<RT: Runtime> DatabaseUdfEnvironment<RT> {
             identity,
             udf_server_version,
 
-            phase: UdfPhase::new(transaction, rt, module_loader.clone(), system_env_vars),
+            phase: UdfPhase::new(
+                transaction,
+                rt,
+                module_loader.clone(),
+                key_broker.clone(),
+                system_env_vars,
+            ),
             module_loader,
             file_storage,
 
@@ -22,6 +22,7 @@ use database::{
     Transaction,
 };
 use errors::ErrorMetadata;
+use keybroker::KeyBroker;
 use model::{
     environment_variables::{
         types::{
@@ -62,6 +63,7 @@ pub struct UdfPhase<RT: Runtime> {
     tx: Transaction<RT>,
     pub rt: RT,
     module_loader: Arc<dyn ModuleLoader<RT>>,
+    key_broker: KeyBroker,
     system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
     preloaded: UdfPreloaded,
 }
@@ -82,6 +84,7 @@ impl<RT: Runtime> UdfPhase<RT> {
         tx: Transaction<RT>,
         rt: RT,
         module_loader: Arc<dyn ModuleLoader<RT>>,
+        key_broker: KeyBroker,
         system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
     ) -> Self {
         Self {
@@ -89,6 +92,7 @@ impl<RT: Runtime> UdfPhase<RT> {
             tx,
             rt,
             module_loader,
+            key_broker,
             system_env_vars,
             preloaded: UdfPreloaded::Created,
         }
@@ -119,7 +123,7 @@ impl<RT: Runtime> UdfPhase<RT> {
         let env_vars = with_release_permit(
             timeout,
             permit_slot,
-            EnvironmentVariablesModel::new(&mut self.tx).preload(),
+            EnvironmentVariablesModel::new(&mut self.tx).preload(&self.key_broker),
         )
         .await?;
 
@@ -217,7 +221,7 @@ impl<RT: Runtime> UdfPhase<RT> {
             anyhow::bail!("Phase not initialized");
         };
         if let Some(var) = env_vars.get(&mut self.tx, &name)? {
-            return Ok(Some(var.clone()));
+            return Ok(Some(var));
         }
         Ok(self.system_env_vars.get(&name).cloned())
     }
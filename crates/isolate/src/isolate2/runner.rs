@@ -879,7 +879,7 @@ impl<'a, RT: Runtime> AsyncSyscallProvider<RT> for Isolate2SyscallProvider<'a, R
         .await
     }
 
-    fn file_storage_generate_upload_url(&self) -> anyhow::Result<String> {
+    fn file_storage_generate_upload_url(&self, _resumable: bool) -> anyhow::Result<String> {
         todo!()
     }
 
@@ -968,6 +968,7 @@ pub async fn run_isolate_v2_udf<RT: Runtime>(
     query_journal: QueryJournal,
 ) -> anyhow::Result<(Transaction<RT>, UdfOutcome)> {
     initialize_v8();
+    tx.enable_slow_query_log(path_and_args.udf_path().to_string());
 
     let semaphore = Arc::new(Semaphore::new(8));
     let user_timeout = Duration::from_secs(5);
@@ -990,7 +991,9 @@ pub async fn run_isolate_v2_udf<RT: Runtime>(
             .map(|c| c.import_phase_unix_timestamp)
             .context("Missing import phase unix timestamp")?,
     };
-    let env_vars = EnvironmentVariablesModel::new(&mut tx).preload().await?;
+    let env_vars = EnvironmentVariablesModel::new(&mut tx)
+        .preload(&key_broker)
+        .await?;
 
     // TODO: This unconditionally takes a table mapping dep.
     let shared = UdfShared::new(
@@ -20,7 +20,7 @@ async fn test_get_environment_variable(rt: TestRuntime) -> anyhow::Result<()> {
     let environment_variable =
         EnvironmentVariable::new("TEST_NAME".parse()?, "TEST_VALUE".parse()?);
     EnvironmentVariablesModel::new(&mut tx)
-        .create(environment_variable, &HashSet::new())
+        .create(environment_variable, &HashSet::new(), &t.key_broker)
         .await?;
     t.database.commit(tx).await?;
     let value = t
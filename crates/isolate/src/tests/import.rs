@@ -46,7 +46,7 @@ async fn test_dynamic_import_load_failure(rt: TestRuntime) -> anyhow::Result<()>
     let environment_variable =
         EnvironmentVariable::new("FAIL_MODULE_LOAD".parse()?, "fail".parse()?);
     EnvironmentVariablesModel::new(&mut tx)
-        .create(environment_variable, &HashSet::new())
+        .create(environment_variable, &HashSet::new(), &t.key_broker)
         .await?;
     t.database.commit(tx).await?;
 
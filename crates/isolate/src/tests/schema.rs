@@ -262,7 +262,9 @@ async fn test_eval_schema(rt: TestRuntime) -> anyhow::Result<()> {
                     "union" => FieldValidator::required_field_type(Validator::Union(vec![Validator::String, Validator::Float64])),
                     "object" => FieldValidator::required_field_type(Validator::Object(object_validator!("a" => FieldValidator::optional_field_type(Validator::Any))))
                   )
-                ]))
+                ])),
+                ttl: None,
+                foreign_keys: btreemap!(),
             },
             name2.clone() => TableDefinition {
                 table_name: name2,
@@ -270,15 +272,25 @@ async fn test_eval_schema(rt: TestRuntime) -> anyhow::Result<()> {
                     by_email.clone() => IndexSchema {
                         index_descriptor: by_email,
                         fields: vec!["email".parse()?].try_into()?,
+                        unique: false,
+                        filter: None,
+                        multikey_field: None,
+                        aggregate: None,
                     },
                     by_creation_deleted.clone() => IndexSchema {
                         index_descriptor: by_creation_deleted,
                         fields: vec!["creation".parse()?, "deleted".parse()?].try_into()?,
+                        unique: false,
+                        filter: None,
+                        multikey_field: None,
+                        aggregate: None,
                     },
                 ),
                 search_indexes: btreemap!(),
                 vector_indexes: btreemap!(),
                 document_type: None,
+                ttl: None,
+                foreign_keys: btreemap!(),
             },
             name3.clone() => TableDefinition {
               table_name: name3,
@@ -292,6 +304,8 @@ async fn test_eval_schema(rt: TestRuntime) -> anyhow::Result<()> {
                },
                vector_indexes: btreemap!(),
                document_type: None,
+               ttl: None,
+               foreign_keys: btreemap!(),
           }
         ),
         schema_validation: true,
@@ -19,7 +19,7 @@ async fn test_system_udf(rt: TestRuntime) -> anyhow::Result<()> {
     let mut tx = t.database.begin(Identity::system()).await?;
     let environment_variable = EnvironmentVariable::new("A".parse()?, "B".parse()?);
     EnvironmentVariablesModel::new(&mut tx)
-        .create(environment_variable, &HashSet::new())
+        .create(environment_variable, &HashSet::new(), &t.key_broker)
         .await?;
     t.database.commit(tx).await?;
 
@@ -30,7 +30,7 @@ async fn test_system_udf(rt: TestRuntime) -> anyhow::Result<()> {
     )
     .await?);
 
-    // return environment variable
+    // return environment variable, with its value redacted
     must_let!(let ConvexValue::Object(obj) = t.query(
         "_system/cli/queryEnvironmentVariables:get",
         assert_obj!("name" => "A".to_string()),
@@ -38,8 +38,8 @@ async fn test_system_udf(rt: TestRuntime) -> anyhow::Result<()> {
     .await?);
     must_let!(let Some(ConvexValue::String(name)) = ConvexObject::get(&obj, "name"));
     assert_eq!(name.to_string(), "A");
-    must_let!(let Some(ConvexValue::String(value)) = ConvexObject::get(&obj, "value"));
-    assert_eq!(value.to_string(), "B");
+    assert!(ConvexObject::get(&obj, "value").is_none());
+    assert!(ConvexObject::get(&obj, "encryptedValue").is_none());
 
     // calling with empty argument fails
     let error = t
@@ -23,12 +23,14 @@ use common::{
         Runtime,
         UnixTimestamp,
     },
+    sha256::Sha256,
     types::{
         format_admin_key,
         remove_type_prefix_from_instance_name,
         split_admin_key,
         ActionCallbackToken,
         AdminKey,
+        EnvVarValue,
         MemberId,
         PersistenceVersion,
     },
@@ -83,7 +85,10 @@ use crate::{
         log_actions_token_expired,
         log_store_file_auth_expired,
     },
-    secret::InstanceSecret,
+    secret::{
+        InstanceSecret,
+        Secret,
+    },
 };
 
 const ACTION_KEY_VERSION: u8 = 1;
@@ -91,6 +96,11 @@ const ADMIN_KEY_VERSION: u8 = 1;
 const CURSOR_VERSION: u8 = 7;
 const STORE_FILE_AUTHZ_VERSION: u8 = 1;
 const QUERY_JOURNAL_VERSION: u8 = 7;
+const ENV_VAR_VERSION: u8 = 1;
+
+// Mixed into the instance secret to derive the key for environment variable
+// values, which are stored at rest rather than handed out to clients.
+const ENV_VAR_KEY_LABEL: &[u8] = b"convex-environment-variables";
 
 // Max delay from transaction start time -> key being issued that is tolerable.
 const MAX_TS_DELAY: Duration = Duration::from_secs(15);
@@ -99,6 +109,7 @@ const MAX_TS_DELAY: Duration = Duration::from_secs(15);
 pub struct KeyBroker {
     instance_name: String,
     encryptor: Encryptor,
+    env_var_encryptor: Encryptor,
 }
 
 // This enum encodes a successful authentication decision, and its nontrivial
@@ -533,6 +544,13 @@ pub struct StoreFileAuthorization(String);
 #[derive(Debug, derive_more::Display)]
 pub struct GetFileAuthorization(String);
 
+fn derive_secret(secret: &InstanceSecret, label: &[u8]) -> anyhow::Result<Secret> {
+    let mut hasher = Sha256::new();
+    hasher.update(label);
+    hasher.update(secret.as_bytes());
+    Secret::try_from(hasher.finalize().to_vec())
+}
+
 pub fn cursor_parse_error() -> ErrorMetadata {
     ErrorMetadata::bad_request("InvalidCursor", "Failed to parse cursor")
 }
@@ -542,6 +560,7 @@ impl KeyBroker {
         Ok(Self {
             instance_name: instance_name.to_owned(),
             encryptor: Encryptor::new(instance_secret)?,
+            env_var_encryptor: Encryptor::new(derive_secret(&instance_secret, ENV_VAR_KEY_LABEL)?)?,
         })
     }
 
@@ -638,6 +657,27 @@ impl KeyBroker {
         })
     }
 
+    /// Decodes an identity sent by a peer that authenticated with a system
+    /// key, checking that admin identities carry a valid admin key for this
+    /// instance. User identities were already checked by the peer when their
+    /// tokens were first presented.
+    pub fn check_identity_proto(
+        &self,
+        msg: pb::convex_identity::Identity,
+    ) -> anyhow::Result<Identity> {
+        let identity = Identity::from_proto_unchecked(msg)?;
+        if let Identity::InstanceAdmin(admin_identity) | Identity::ActingUser(admin_identity, _) =
+            &identity
+        {
+            let checked = self.check_admin_key(&admin_identity.key)?;
+            anyhow::ensure!(
+                checked.member_id() == Some(admin_identity.member_id),
+                "Admin identity doesn't match its admin key"
+            );
+        }
+        Ok(identity)
+    }
+
     pub fn check_store_file_authorization<RT: Runtime>(
         &self,
         rt: &RT,
@@ -783,6 +823,21 @@ impl KeyBroker {
         }
     }
 
+    /// Encrypts an environment variable's value to be stored in
+    /// `_environment_variables`.
+    pub fn encrypt_environment_variable(&self, value: &EnvVarValue) -> String {
+        self.env_var_encryptor
+            .encode_proto(ENV_VAR_VERSION, value.to_string())
+    }
+
+    pub fn decrypt_environment_variable(&self, encrypted: &str) -> anyhow::Result<EnvVarValue> {
+        let value: String = self
+            .env_var_encryptor
+            .decode_proto(ENV_VAR_VERSION, encrypted)
+            .context("Couldn't decrypt environment variable")?;
+        value.parse()
+    }
+
     pub fn issue_action_token(&self) -> ActionCallbackToken {
         let now = SystemTime::now();
         let since_epoch = now
@@ -866,10 +921,12 @@ mod tests {
         AdminKey,
         KeyBroker,
         ADMIN_KEY_VERSION,
+        ENV_VAR_VERSION,
     };
     use crate::{
         AdminIdentity,
         Identity,
+        InstanceSecret,
     };
 
     #[test]
@@ -882,6 +939,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_environment_variable_encryption() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let value = "hunter2".parse()?;
+        let encrypted = kb.encrypt_environment_variable(&value);
+        assert!(!encrypted.contains("hunter2"));
+        assert_eq!(kb.decrypt_environment_variable(&encrypted)?, value);
+
+        // Values are encrypted with a different key than tokens.
+        let other = KeyBroker::new("carnitas", InstanceSecret::random())?;
+        assert!(other.decrypt_environment_variable(&encrypted).is_err());
+        assert!(kb
+            .encryptor
+            .decode_proto::<String>(ENV_VAR_VERSION, &encrypted)
+            .is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_system_keys() -> anyhow::Result<()> {
         let kb = KeyBroker::dev();
@@ -892,6 +967,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_check_identity_proto() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let admin = kb.check_admin_key(&kb.issue_admin_key(MemberId(0)).to_string())?;
+        assert_eq!(kb.check_identity_proto(admin.clone().into())?, admin);
+        assert!(kb
+            .check_identity_proto(Identity::system().into())?
+            .is_system());
+
+        // Admin identities with forged keys are rejected.
+        let forged = AdminIdentity::new_for_test_only(kb.instance_name.clone(), MemberId(0));
+        assert!(kb
+            .check_identity_proto(Identity::InstanceAdmin(forged).into())
+            .is_err());
+        // So are admin keys for other instances.
+        let other = KeyBroker::new("carnitas", InstanceSecret::random())?;
+        let other_admin = other.check_admin_key(&other.issue_admin_key(MemberId(0)).to_string())?;
+        assert!(kb.check_identity_proto(other_admin.into()).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_admin_keys_with_prefix() -> anyhow::Result<()> {
         let kb = KeyBroker::dev();
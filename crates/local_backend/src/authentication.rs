@@ -8,12 +8,18 @@ use anyhow::{
 use async_trait::async_trait;
 use authentication::extract_bearer_token;
 use axum::{
-    extract::FromRequestParts,
+    body::Body,
+    extract::{
+        FromRequestParts,
+        State,
+    },
+    response::IntoResponse,
     RequestPartsExt,
 };
 use common::{
     http::{
         extract::Query,
+        tls::ClientAuthentication,
         HttpResponseError,
     },
     runtime::Runtime,
@@ -193,14 +199,68 @@ fn strip_prefix_ignore_case<'a>(string: &'a str, prefix: &str) -> Option<&'a str
 
 #[cfg(test)]
 mod tests {
+    use axum::headers::authorization::Credentials;
+    use common::http::{
+        tls::ClientAuthentication,
+        HttpError,
+    };
     use errors::ErrorMetadataAnyhowExt;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use hyper::Body;
     use keybroker::testing::TestUserIdentity;
+    use runtime::prod::ProdRuntime;
     use sync_types::{
         AuthenticationToken,
         UserIdentityAttributes,
     };
+    use tower::ServiceExt;
 
     use super::extract_admin_key;
+    use crate::{
+        router::router,
+        test_helpers::setup_backend_for_test,
+    };
+
+    #[convex_macro::prod_rt_test]
+    async fn test_admin_routes_require_client_certificate(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let mut st = backend.st.clone();
+        st.require_admin_client_cert = true;
+        let router = router(st).await;
+        let request = |client_authentication: Option<ClientAuthentication>| {
+            let mut req = Request::builder()
+                .uri("/api/slow_queries")
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())?;
+            if let Some(client_authentication) = client_authentication {
+                req.extensions_mut().insert(client_authentication);
+            }
+            anyhow::Ok(req)
+        };
+
+        // Plain HTTP and TLS connections without a client certificate are rejected
+        // even with a valid admin key.
+        for client_authentication in [None, Some(ClientAuthentication::Anonymous)] {
+            let response = router
+                .clone()
+                .oneshot(request(client_authentication)?)
+                .await?;
+            let error = HttpError::from_response(response).await;
+            assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+            assert_eq!(error.error_code(), "ClientCertificateRequired");
+        }
+
+        let response = router
+            .oneshot(request(Some(ClientAuthentication::Certificate))?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(())
+    }
+
     #[test]
     fn test_extracts_admin_key() -> anyhow::Result<()> {
         // Check that we don't panic no matter how short the admin key is
@@ -253,3 +313,23 @@ mod tests {
         Ok(())
     }
 }
+
+/// Rejects requests that didn't arrive over a TLS connection authenticated with
+/// a client certificate, if the backend was configured with a client CA.
+pub async fn require_client_certificate_middleware(
+    State(st): State<LocalAppState>,
+    req: http::request::Request<Body>,
+    next: axum::middleware::Next<Body>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    if st.require_admin_client_cert
+        && req.extensions().get::<ClientAuthentication>()
+            != Some(&ClientAuthentication::Certificate)
+    {
+        return Err(anyhow!(ErrorMetadata::forbidden(
+            "ClientCertificateRequired",
+            "This endpoint requires a TLS client certificate signed by the configured client CA.",
+        ))
+        .into());
+    }
+    Ok(next.run(req).await)
+}
@@ -1,10 +1,12 @@
 use std::{
     fmt,
     path::PathBuf,
+    time::Duration,
 };
 
 use clap::Parser;
 use common::{
+    http::tls::TlsConfig,
     types::{
         ConvexOrigin,
         ConvexSite,
@@ -17,6 +19,7 @@ use keybroker::{
     DEV_INSTANCE_NAME,
     DEV_SECRET,
 };
+use node_executor::NodeSandboxConfig;
 use url::Url;
 
 #[derive(Parser, Clone)]
@@ -56,6 +59,65 @@ pub struct LocalConfig {
     /// Which directory should local storage use
     #[clap(long, default_value = "convex_local_storage")]
     local_storage: String,
+
+    /// PEM certificate chain for serving HTTPS on both the API and HTTP
+    /// Actions ports. Reloaded when the file changes.
+    #[clap(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key matching `tls_cert`
+    #[clap(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// PEM CA bundle for client certificates. When set, admin routes require a
+    /// client certificate signed by one of these CAs.
+    #[clap(long, requires = "tls_cert")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    /// Heap limit in megabytes for each Node action process
+    #[clap(long)]
+    pub node_action_memory_limit_mb: Option<u64>,
+
+    /// CPU time limit in seconds for each Node action process
+    #[clap(long)]
+    pub node_action_cpu_limit_secs: Option<u64>,
+
+    /// Maximum number of open file descriptors for each Node action process
+    #[clap(long)]
+    pub node_action_open_files_limit: Option<u64>,
+
+    /// Directory under which each Node action invocation gets its own
+    /// scratch directory, removed when the invocation finishes
+    #[clap(long)]
+    pub node_action_tmp_dir: Option<PathBuf>,
+
+    /// Run Node actions in their own network namespace, so they can only
+    /// reach the backend and `convex_http_proxy`. Linux only, and needs
+    /// unprivileged user namespaces.
+    #[clap(long, requires = "convex_http_proxy")]
+    pub node_action_network_isolation: bool,
+
+    /// URL of an out-of-process Funrun server to run functions on, e.g.
+    /// `http://127.0.0.1:3213`. Funrun must be started with this backend's
+    /// instance name and secret. Functions run in process when unset.
+    #[clap(long)]
+    pub funrun_url: Option<String>,
+
+    /// Host interface for the gRPC service Funrun calls back into. Only
+    /// trusted function runners should be able to reach it.
+    #[clap(long, default_value = "127.0.0.1", requires = "funrun_url")]
+    backend_grpc_interface: ::std::net::Ipv4Addr,
+
+    /// Host port for the gRPC service Funrun calls back into
+    #[clap(long, default_value = "3212", requires = "funrun_url")]
+    backend_grpc_port: u16,
+
+    /// URL of an out-of-process Searchlight server to run text and vector
+    /// searches on, e.g. `http://127.0.0.1:3214`. Searches run in process
+    /// when unset. Searchlight must be started with this backend's instance
+    /// name and secret, and be able to read its local storage directory.
+    #[clap(long)]
+    pub searchlight_url: Option<String>,
 }
 
 impl fmt::Debug for LocalConfig {
@@ -64,6 +126,8 @@ impl fmt::Debug for LocalConfig {
             .field("convex_origin", &self.convex_origin)
             .field("convex_site", &self.convex_site)
             .field("instance_name", &self.instance_name)
+            .field("funrun_url", &self.funrun_url)
+            .field("searchlight_url", &self.searchlight_url)
             .finish()
     }
 }
@@ -77,16 +141,36 @@ impl LocalConfig {
         Some((self.interface.octets(), self.site_proxy_port))
     }
 
+    pub fn backend_grpc_bind_address(&self) -> ([u8; 4], u16) {
+        (self.backend_grpc_interface.octets(), self.backend_grpc_port)
+    }
+
     pub fn convex_origin_url(&self) -> ConvexOrigin {
         self.convex_origin
             .clone()
-            .unwrap_or(format!("http://127.0.0.1:{}", self.port).into())
+            .unwrap_or(format!("{}://127.0.0.1:{}", self.scheme(), self.port).into())
     }
 
     pub fn convex_site_url(&self) -> ConvexSite {
         self.convex_site
             .clone()
-            .unwrap_or(format!("http://127.0.0.1:{}", self.site_proxy_port).into())
+            .unwrap_or(format!("{}://127.0.0.1:{}", self.scheme(), self.site_proxy_port).into())
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.tls_cert.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    pub fn tls_config(&self) -> Option<TlsConfig> {
+        Some(TlsConfig {
+            cert_path: self.tls_cert.clone()?,
+            key_path: self.tls_key.clone()?,
+            client_ca_path: self.tls_client_ca.clone(),
+        })
     }
 
     pub fn name(&self) -> String {
@@ -114,6 +198,21 @@ impl LocalConfig {
         self.local_storage.clone().into()
     }
 
+    pub fn node_sandbox_config(&self) -> NodeSandboxConfig {
+        NodeSandboxConfig {
+            memory_limit_mb: self.node_action_memory_limit_mb,
+            cpu_time_limit: self.node_action_cpu_limit_secs.map(Duration::from_secs),
+            open_files_limit: self.node_action_open_files_limit,
+            tmp_dir_root: self.node_action_tmp_dir.clone(),
+            network_isolation: self.node_action_network_isolation,
+            network_proxy: if self.node_action_network_isolation {
+                self.convex_http_proxy.clone()
+            } else {
+                None
+            },
+        }
+    }
+
     #[cfg(test)]
     pub fn new_for_test() -> anyhow::Result<Self> {
         use anyhow::Context;
@@ -1,3 +1,4 @@
+use anyhow::Context;
 use application::valid_identifier::ValidIdentifier;
 use axum::{
     debug_handler,
@@ -9,17 +10,24 @@ use common::{
         extract::Json,
         HttpResponseError,
     },
+    query::Query,
     shapes::{
         dashboard_shape_json,
         reduced::ReducedShape,
     },
 };
-use database::IndexModel;
+use database::{
+    query::QueryExplanation,
+    IndexModel,
+    SlowQuery,
+};
+use errors::ErrorMetadata;
 use http::StatusCode;
 use serde::{
     Deserialize,
     Serialize,
 };
+use serde_json::Value as JsonValue;
 use value::TableName;
 
 use crate::{
@@ -96,3 +104,275 @@ pub async fn get_indexes(
             .collect::<anyhow::Result<_>>()?,
     }))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectStorageGarbageArgs {
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageGcUseCaseResponse {
+    use_case: String,
+    num_objects: usize,
+    orphaned_objects: Vec<String>,
+    orphaned_bytes: u64,
+    deleted: bool,
+    skipped_reason: Option<String>,
+}
+
+#[debug_handler]
+pub async fn collect_storage_garbage(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CollectStorageGarbageArgs { dry_run }): Json<CollectStorageGarbageArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let reports = st
+        .application
+        .collect_storage_garbage(identity, dry_run)
+        .await?;
+    let response: Vec<_> = reports
+        .into_iter()
+        .map(|report| StorageGcUseCaseResponse {
+            use_case: report.use_case.to_string(),
+            num_objects: report.num_objects,
+            orphaned_objects: report
+                .orphaned_objects
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            orphaned_bytes: report.orphaned_bytes,
+            deleted: report.deleted,
+            skipped_reason: report.skipped_reason,
+        })
+        .collect();
+    Ok(Json(response))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainQueryArgs {
+    query: JsonValue,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryExplanationResponse {
+    source: JsonValue,
+    index_name: String,
+    full_table_scan: bool,
+    documents_scanned: usize,
+    documents_filtered_out: usize,
+    documents_returned: usize,
+    bytes_read: usize,
+    limit: Option<usize>,
+    is_approaching_data_limit: bool,
+}
+
+impl TryFrom<QueryExplanation> for QueryExplanationResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(explanation: QueryExplanation) -> anyhow::Result<Self> {
+        Ok(Self {
+            full_table_scan: explanation.is_full_table_scan(),
+            source: JsonValue::try_from(explanation.source)?,
+            index_name: explanation.index_name.to_string(),
+            documents_scanned: explanation.documents_scanned,
+            documents_filtered_out: explanation.documents_filtered_out,
+            documents_returned: explanation.documents_returned,
+            bytes_read: explanation.bytes_read,
+            limit: explanation.limit,
+            is_approaching_data_limit: explanation.is_approaching_data_limit,
+        })
+    }
+}
+
+/// Runs a query to completion and reports how it executed.
+#[debug_handler]
+pub async fn explain_query(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(ExplainQueryArgs { query }): Json<ExplainQueryArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let query = Query::try_from(query).context(ErrorMetadata::bad_request(
+        "InvalidQuery",
+        "The query to explain is malformed.",
+    ))?;
+    let explanation = st.application.explain_query(identity, query).await?;
+    Ok(Json(QueryExplanationResponse::try_from(explanation)?))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlowQueryResponse {
+    udf_path: String,
+    query_fingerprint: String,
+    explanation: QueryExplanationResponse,
+    fetch_time_ms: u128,
+    started_at_ms: u64,
+}
+
+impl TryFrom<SlowQuery> for SlowQueryResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(query: SlowQuery) -> anyhow::Result<Self> {
+        Ok(Self {
+            udf_path: query.udf_path,
+            query_fingerprint: hex::encode(query.query_fingerprint),
+            explanation: query.explanation.try_into()?,
+            fetch_time_ms: query.fetch_time.as_millis(),
+            started_at_ms: query.started_at.as_ms_since_epoch()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlowQueriesResponse {
+    queries: Vec<SlowQueryResponse>,
+}
+
+#[debug_handler]
+pub async fn slow_queries(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let queries = st
+        .application
+        .slow_queries()
+        .into_iter()
+        .map(SlowQueryResponse::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(SlowQueriesResponse { queries }))
+}
+
+#[cfg(test)]
+mod tests {
+    use application::test_helpers::ApplicationTestExt;
+    use axum::headers::authorization::Credentials;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use hyper::Body;
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::{
+        setup_backend_for_test,
+        TestLocalBackend,
+    };
+
+    async fn insert_object(backend: &TestLocalBackend) -> anyhow::Result<()> {
+        let body = json!({"path": "basic:insertObject", "args": {"an": "object"}});
+        let req = Request::builder()
+            .uri("/api/mutation")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&body)?))?;
+        backend.expect_success(req).await
+    }
+
+    fn explain_query_request(
+        backend: &TestLocalBackend,
+        query: JsonValue,
+    ) -> anyhow::Result<Request<Body>> {
+        Ok(Request::builder()
+            .uri("/api/explain_query")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(Body::from(serde_json::to_vec(&json!({ "query": query }))?))?)
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_explain_query(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        for _ in 0..3 {
+            insert_object(&backend).await?;
+        }
+
+        let query = json!({
+            "source": { "type": "FullTableScan", "tableName": "objects", "order": "asc" },
+            "operators": [{ "limit": 2 }],
+        });
+        let req = explain_query_request(&backend, query.clone())?;
+        let explanation: JsonValue = backend.expect_success_and_result(req).await?;
+        assert_eq!(explanation["indexName"], "objects.by_creation_time");
+        assert_eq!(explanation["fullTableScan"], true);
+        assert_eq!(explanation["documentsReturned"], 2);
+        assert_eq!(explanation["documentsFilteredOut"], 0);
+        assert_eq!(explanation["limit"], 2);
+        assert_eq!(explanation["isApproachingDataLimit"], false);
+
+        let req = explain_query_request(&backend, json!({ "source": "objects" }))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidQuery")
+            .await?;
+
+        // Explaining queries is only for admins.
+        let req = Request::builder()
+            .uri("/api/explain_query")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({ "query": query }))?))?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "BadDeployKey")
+            .await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_slow_queries(rt: ProdRuntime) -> anyhow::Result<()> {
+        std::env::set_var("SLOW_QUERY_LOG_ENABLED", "true");
+        std::env::set_var("SLOW_QUERY_DOCUMENTS_SCANNED_THRESHOLD", "2");
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        for _ in 0..3 {
+            insert_object(&backend).await?;
+        }
+        let body = json!({"path": "basic:listAllObjects", "args": {}});
+        let req = Request::builder()
+            .uri("/api/query")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&body)?))?;
+        backend.expect_success(req).await?;
+
+        let req = Request::builder()
+            .uri("/api/slow_queries")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(Body::empty())?;
+        let response: JsonValue = backend.expect_success_and_result(req).await?;
+        let queries = response["queries"].as_array().unwrap();
+        let slow_query = queries
+            .iter()
+            .find(|query| {
+                query["udfPath"]
+                    .as_str()
+                    .is_some_and(|path| path.contains("listAllObjects"))
+            })
+            .expect("Query wasn't logged");
+        assert_eq!(slow_query["explanation"]["documentsReturned"], 3);
+        assert_eq!(slow_query["explanation"]["fullTableScan"], true);
+        assert!(slow_query["queryFingerprint"].is_string());
+
+        let req = Request::builder()
+            .uri("/api/slow_queries")
+            .method("GET")
+            .body(Body::empty())?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "BadDeployKey")
+            .await?;
+        Ok(())
+    }
+}
@@ -14,6 +14,8 @@ use application::{
     Application,
     ApplyConfigArgs,
     ConfigMetadataAndSchema,
+    DeployPlan,
+    SchemaValidationEstimate,
 };
 use axum::{
     debug_handler,
@@ -21,6 +23,7 @@ use axum::{
     response::IntoResponse,
 };
 use common::{
+    document::ParsedDocument,
     http::{
         extract::Json,
         HttpResponseError,
@@ -28,7 +31,10 @@ use common::{
     runtime::Runtime,
     schemas::DatabaseSchema,
     sha256::Sha256,
-    types::NodeDependency,
+    types::{
+        ModuleEnvironment,
+        NodeDependency,
+    },
     version::Version,
 };
 use database::OccRetryStats;
@@ -48,6 +54,7 @@ use model::{
         },
         ConfigModel,
     },
+    deployment_history::types::Deployment,
     modules::module_versions::{
         AnalyzedModule,
         ModuleSource,
@@ -67,11 +74,15 @@ use serde::{
 };
 use serde_json::Value as JsonValue;
 use sync_types::CanonicalizedModulePath;
-use value::ConvexObject;
+use value::{
+    id_v6::DocumentIdV6,
+    ConvexObject,
+};
 
 use crate::{
     admin::must_be_admin_from_keybroker,
     parse::parse_module_path,
+    schema::SchemaStateJson,
     EmptyResponse,
     LocalAppState,
 };
@@ -169,6 +180,9 @@ pub struct ConfigJson {
     // Used in CLI >= future
     #[allow(dead_code)]
     pub bundled_module_infos: Option<Vec<BundledModuleInfoJson>>,
+    // Analyze the push and return what it would change without applying it.
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 pub struct ConfigStats {
@@ -365,23 +379,141 @@ pub async fn get_config_hashes(
     }))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackDeploymentRequest {
+    pub admin_key: String,
+    pub deployment_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentJson {
+    pub id: String,
+    pub deployed_at: f64,
+    pub source_package_id: Option<String>,
+    pub modules: Vec<String>,
+    pub udf_server_version: String,
+    pub schema_id: Option<String>,
+}
+
+impl TryFrom<ParsedDocument<Deployment>> for DeploymentJson {
+    type Error = anyhow::Error;
+
+    fn try_from(deployment: ParsedDocument<Deployment>) -> anyhow::Result<Self> {
+        let id = DocumentIdV6::from(deployment.id()).encode();
+        let deployed_at = deployment
+            .creation_time()
+            .context("Deployment is missing a creation time")?
+            .into();
+        let deployment = deployment.into_value();
+        Ok(Self {
+            id,
+            deployed_at,
+            source_package_id: deployment
+                .source_package_id
+                .map(|id| DocumentIdV6::from(id).encode()),
+            modules: deployment
+                .modules
+                .into_iter()
+                .map(|module| String::from(module.path))
+                .collect(),
+            udf_server_version: deployment.udf_config.server_version.to_string(),
+            schema_id: deployment.schema_id.map(|id| id.encode()),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeploymentsResponse {
+    pub deployments: Vec<DeploymentJson>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackDeploymentResponse {
+    pub config_diff: JsonValue,
+}
+
+/// Lists the retained deployments that can be rolled back to, newest first.
+#[debug_handler]
+pub async fn list_deployments(
+    State(st): State<LocalAppState>,
+    Json(req): Json<GetConfigRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let identity = must_be_admin_from_keybroker(
+        st.application.key_broker(),
+        Some(st.instance_name.clone()),
+        req.admin_key,
+    )?;
+    let deployments = st
+        .application
+        .list_deployments(identity)
+        .await?
+        .into_iter()
+        .map(DeploymentJson::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListDeploymentsResponse { deployments }))
+}
+
+#[debug_handler]
+pub async fn rollback_deployment(
+    State(st): State<LocalAppState>,
+    Json(req): Json<RollbackDeploymentRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let identity = must_be_admin_from_keybroker(
+        st.application.key_broker(),
+        Some(st.instance_name.clone()),
+        req.admin_key,
+    )?;
+    let config_diff = st
+        .application
+        .rollback_config(identity, req.deployment_id)
+        .await?;
+    Ok(Json(RollbackDeploymentResponse {
+        config_diff: ConvexObject::try_from(config_diff)?.into(),
+    }))
+}
+
 #[debug_handler]
 pub async fn push_config(
     State(st): State<LocalAppState>,
     Json(req): Json<ConfigJson>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
+    if req.dry_run {
+        let plan = plan_push_config_handler(&st.application, req)
+            .await
+            .map_err(|e| {
+                e.wrap_error_message(|msg| format!("Hit an error while planning the push:\n{msg}"))
+            })?;
+        return Ok(Json(plan).into_response());
+    }
     push_config_handler(&st.application, req)
         .await
         .map_err(|e| e.wrap_error_message(|msg| format!("Hit an error while pushing:\n{msg}")))?;
 
-    Ok(Json(EmptyResponse {}))
+    Ok(Json(EmptyResponse {}).into_response())
+}
+
+/// A push that has been uploaded and analyzed, ready to be applied.
+struct PreparedPush {
+    identity: Identity,
+    apply_config_args: ApplyConfigArgs,
+    /// Node.js modules left unanalyzed because a dry run doesn't upload the
+    /// source package they're analyzed from.
+    unanalyzed_modules: Vec<CanonicalizedModulePath>,
+    build_external_deps_time: Duration,
+    upload_source_package_time: Duration,
+    analyze_time: Duration,
 }
 
 #[minitrace::trace]
-pub async fn push_config_handler(
+async fn prepare_push(
     application: &Application<ProdRuntime>,
     config: ConfigJson,
-) -> anyhow::Result<(Identity, PushAnalytics, PushMetrics)> {
+    dry_run: bool,
+) -> anyhow::Result<PreparedPush> {
     let modules: Vec<ModuleConfig> = config
         .modules
         .into_iter()
@@ -397,32 +529,37 @@ pub async fn push_config_handler(
     )?;
 
     let begin_build_external_deps = Instant::now();
-    // Upload external node dependencies separately
-    let external_deps_id_and_pkg = if let Some(deps) = config.node_dependencies
-        && !deps.is_empty()
-    {
-        let deps: Vec<_> = deps.into_iter().map(NodeDependency::from).collect();
-        Some(application.build_external_node_deps(deps).await?)
+    let (source_package, end_build_external_deps) = if dry_run {
+        (None, begin_build_external_deps)
     } else {
-        None
+        // Upload external node dependencies separately
+        let external_deps_id_and_pkg = if let Some(deps) = config.node_dependencies
+            && !deps.is_empty()
+        {
+            let deps: Vec<_> = deps.into_iter().map(NodeDependency::from).collect();
+            Some(application.build_external_node_deps(deps).await?)
+        } else {
+            None
+        };
+        let end_build_external_deps = Instant::now();
+        let external_deps_pkg_size = external_deps_id_and_pkg
+            .as_ref()
+            .map(|(_, pkg)| pkg.package_size)
+            .unwrap_or(PackageSize::default());
+
+        let source_package = application
+            .upload_package(&modules, external_deps_id_and_pkg)
+            .await?;
+        // Verify that we have not exceeded the max zipped or unzipped file size
+        let combined_pkg_size = source_package
+            .as_ref()
+            .map(|pkg| pkg.package_size)
+            .unwrap_or(PackageSize::default())
+            + external_deps_pkg_size;
+        combined_pkg_size.verify_size()?;
+        (source_package, end_build_external_deps)
     };
-    let end_build_external_deps = Instant::now();
-    let external_deps_pkg_size = external_deps_id_and_pkg
-        .as_ref()
-        .map(|(_, pkg)| pkg.package_size)
-        .unwrap_or(PackageSize::default());
-
-    let source_package = application
-        .upload_package(&modules, external_deps_id_and_pkg)
-        .await?;
     let end_upload_source_package = Instant::now();
-    // Verify that we have not exceeded the max zipped or unzipped file size
-    let combined_pkg_size = source_package
-        .as_ref()
-        .map(|pkg| pkg.package_size)
-        .unwrap_or(PackageSize::default())
-        + external_deps_pkg_size;
-    combined_pkg_size.verify_size()?;
 
     let udf_config = UdfConfig {
         server_version: udf_server_version,
@@ -431,15 +568,64 @@ pub async fn push_config_handler(
         import_phase_unix_timestamp: application.runtime().unix_timestamp(),
     };
     let begin_analyze = Instant::now();
+    let (unanalyzed_modules, modules_to_analyze): (Vec<_>, Vec<_>) = if dry_run {
+        modules
+            .iter()
+            .cloned()
+            .partition(|module| module.environment == ModuleEnvironment::Node)
+    } else {
+        (vec![], modules.clone())
+    };
     // Run analyze to make sure the new modules are valid.
-    let (auth_module, analyze_result) = analyze_modules_with_auth_config(
+    let (auth_module, mut analyze_results) = analyze_modules_with_auth_config(
         application,
         udf_config.clone(),
-        modules.clone(),
+        modules_to_analyze,
         source_package.clone(),
     )
     .await?;
+    let unanalyzed_modules: Vec<_> = unanalyzed_modules
+        .into_iter()
+        .map(|module| module.path.canonicalize())
+        .collect();
+    for path in &unanalyzed_modules {
+        analyze_results.insert(path.clone(), AnalyzedModule::default());
+    }
     let end_analyze = Instant::now();
+    Ok(PreparedPush {
+        identity,
+        apply_config_args: ApplyConfigArgs {
+            auth_module,
+            config_file: config.config,
+            schema_id: config.schema_id,
+            modules,
+            udf_config,
+            source_package,
+            analyze_results,
+        },
+        unanalyzed_modules,
+        build_external_deps_time: end_build_external_deps - begin_build_external_deps,
+        upload_source_package_time: end_upload_source_package - end_build_external_deps,
+        analyze_time: end_analyze - begin_analyze,
+    })
+}
+
+#[minitrace::trace]
+pub async fn push_config_handler(
+    application: &Application<ProdRuntime>,
+    config: ConfigJson,
+) -> anyhow::Result<(Identity, PushAnalytics, PushMetrics)> {
+    let PreparedPush {
+        identity,
+        apply_config_args,
+        build_external_deps_time,
+        upload_source_package_time,
+        analyze_time,
+        ..
+    } = prepare_push(application, config, false).await?;
+    let modules = apply_config_args.modules.clone();
+    let udf_server_version = apply_config_args.udf_config.server_version.clone();
+    let analyze_results = apply_config_args.analyze_results.clone();
     let (
         ConfigMetadataAndSchema {
             config_metadata,
@@ -447,18 +633,7 @@ pub async fn push_config_handler(
         },
         occ_stats,
     ) = application
-        .apply_config_with_retries(
-            identity.clone(),
-            ApplyConfigArgs {
-                auth_module,
-                config_file: config.config,
-                schema_id: config.schema_id,
-                modules: modules.clone(),
-                udf_config: udf_config.clone(),
-                source_package,
-                analyze_results: analyze_result.clone(),
-            },
-        )
+        .apply_config_with_retries(identity.clone(), apply_config_args)
         .await?;
 
     Ok((
@@ -466,19 +641,94 @@ pub async fn push_config_handler(
         PushAnalytics {
             config: config_metadata,
             modules,
-            udf_server_version: udf_config.server_version,
-            analyze_results: analyze_result,
+            udf_server_version,
+            analyze_results,
             schema,
         },
         PushMetrics {
-            build_external_deps_time: end_build_external_deps - begin_build_external_deps,
-            upload_source_package_time: end_upload_source_package - end_build_external_deps,
-            analyze_time: end_analyze - begin_analyze,
+            build_external_deps_time,
+            upload_source_package_time,
+            analyze_time,
             occ_stats,
         },
     ))
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexBackfillJson {
+    pub index_name: String,
+    pub num_documents: u64,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SchemaValidationJson {
+    Passed,
+    Failed {
+        error: String,
+    },
+    /// Too many existing documents to check them all in a dry run.
+    Unknown,
+}
+
+impl From<SchemaValidationEstimate> for SchemaValidationJson {
+    fn from(value: SchemaValidationEstimate) -> Self {
+        match value {
+            SchemaValidationEstimate::Passed => Self::Passed,
+            SchemaValidationEstimate::Failed(error) => Self::Failed {
+                error: error.to_string(),
+            },
+            SchemaValidationEstimate::Unknown => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployPlanJson {
+    pub config_diff: JsonValue,
+    pub schema_state: Option<SchemaStateJson>,
+    pub schema_validation: Option<SchemaValidationJson>,
+    pub index_backfills: Vec<IndexBackfillJson>,
+    pub unanalyzed_modules: Vec<String>,
+}
+
+/// Analyzes the pushed modules and returns what applying them would change,
+/// without uploading or committing anything. Node.js modules aren't analyzed,
+/// since that needs an uploaded source package.
+#[minitrace::trace]
+pub async fn plan_push_config_handler(
+    application: &Application<ProdRuntime>,
+    config: ConfigJson,
+) -> anyhow::Result<DeployPlanJson> {
+    let PreparedPush {
+        identity,
+        apply_config_args,
+        unanalyzed_modules,
+        ..
+    } = prepare_push(application, config, true).await?;
+    let DeployPlan {
+        config_diff,
+        schema_state,
+        schema_validation,
+        index_backfills,
+    } = application.plan_config(identity, apply_config_args).await?;
+    Ok(DeployPlanJson {
+        config_diff: ConvexObject::try_from(config_diff)?.into(),
+        schema_state: schema_state.map(SchemaStateJson::from),
+        schema_validation: schema_validation.map(SchemaValidationJson::from),
+        index_backfills: index_backfills
+            .into_iter()
+            .map(|backfill| IndexBackfillJson {
+                index_name: backfill.index_name.to_string(),
+                num_documents: backfill.num_documents,
+            })
+            .collect(),
+        unanalyzed_modules: unanalyzed_modules.into_iter().map(String::from).collect(),
+    })
+}
+
 #[minitrace::trace]
 async fn analyze_modules_with_auth_config(
     application: &Application<ProdRuntime>,
@@ -557,3 +807,150 @@ pub async fn analyze_modules(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::headers::authorization::Credentials;
+    use common::{
+        assert_obj,
+        bootstrap_model::schema::SchemaState,
+        db_schema,
+        object_validator,
+        schemas::{
+            validator::{
+                FieldValidator,
+                Validator,
+            },
+            DocumentSchema,
+        },
+    };
+    use database::{
+        SchemaModel,
+        TestFacingModel,
+    };
+    use http::Request;
+    use hyper::Body;
+    use keybroker::Identity;
+    use model::{
+        modules::ModuleModel,
+        source_packages::SourcePackageModel,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+    use value::{
+        id_v6::DocumentIdV6,
+        ResolvedDocumentId,
+    };
+
+    use crate::test_helpers::{
+        setup_backend_for_test,
+        TestLocalBackend,
+    };
+
+    /// Inserts a message that doesn't match the schema from
+    /// `submit_messages_schema`.
+    async fn insert_invalid_message(backend: &TestLocalBackend) -> anyhow::Result<()> {
+        let mut tx = backend.st.application.begin(Identity::system()).await?;
+        TestFacingModel::new(&mut tx)
+            .insert(&"messages".parse()?, assert_obj!("body" => "hello"))
+            .await?;
+        backend.st.application.commit_test(tx).await?;
+        Ok(())
+    }
+
+    async fn submit_messages_schema(
+        backend: &TestLocalBackend,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let schema = db_schema!(
+            "messages" => DocumentSchema::Union(vec![object_validator!(
+                "body" => FieldValidator::required_field_type(Validator::Int64)
+            )])
+        );
+        let mut tx = backend.st.application.begin(Identity::system()).await?;
+        let (schema_id, _) = SchemaModel::new(&mut tx).submit_pending(schema).await?;
+        backend.st.application.commit_test(tx).await?;
+        Ok(schema_id)
+    }
+
+    fn dry_run_request(
+        backend: &TestLocalBackend,
+        schema_id: ResolvedDocumentId,
+    ) -> anyhow::Result<Request<Body>> {
+        let header = backend.admin_auth_header.0.encode();
+        let admin_key = header
+            .to_str()?
+            .strip_prefix("Convex ")
+            .expect("Admin key header should start with \"Convex \"");
+        let body = json!({
+            "config": { "functions": "convex/" },
+            "modules": [
+                {
+                    "path": "messages.js",
+                    "source": "export const limit = 10;",
+                    "environment": "isolate",
+                },
+                {
+                    "path": "actions.js",
+                    "source": "export const limit = 10;",
+                    "environment": "node",
+                },
+            ],
+            "adminKey": admin_key,
+            "udfServerVersion": "1000.0.0",
+            "schemaId": DocumentIdV6::from(schema_id).encode(),
+            "nodeDependencies": [{ "name": "left-pad", "version": "1.3.0" }],
+            "dryRun": true,
+        });
+        Ok(Request::builder()
+            .uri("/api/push_config")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&body)?))?)
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_push_dry_run(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        insert_invalid_message(&backend).await?;
+        let schema_id = submit_messages_schema(&backend).await?;
+
+        let req = dry_run_request(&backend, schema_id)?;
+        let plan: JsonValue = backend.expect_success_and_result(req).await?;
+        assert_eq!(plan["schemaState"]["state"], "pending");
+        assert_eq!(plan["schemaValidation"]["status"], "failed");
+        assert_eq!(plan["unanalyzedModules"], json!(["actions.js"]));
+
+        // Nothing was uploaded or committed.
+        let mut tx = backend.st.application.begin(Identity::system()).await?;
+        assert!(ModuleModel::new(&mut tx)
+            .get_all_metadata()
+            .await?
+            .is_empty());
+        assert!(SourcePackageModel::new(&mut tx)
+            .get_latest()
+            .await?
+            .is_none());
+        let (pending_id, _) = SchemaModel::new(&mut tx)
+            .get_by_state(SchemaState::Pending)
+            .await?
+            .expect("Schema should still be pending");
+        assert_eq!(pending_id, schema_id);
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_push_dry_run_too_many_documents(rt: ProdRuntime) -> anyhow::Result<()> {
+        std::env::set_var("DEPLOY_PLAN_MAX_VALIDATED_DOCUMENTS", "0");
+        let backend = setup_backend_for_test(rt).await?;
+        insert_invalid_message(&backend).await?;
+        let schema_id = submit_messages_schema(&backend).await?;
+
+        let req = dry_run_request(&backend, schema_id)?;
+        let plan: JsonValue = backend.expect_success_and_result(req).await?;
+        assert_eq!(plan["schemaValidation"]["status"], "unknown");
+        Ok(())
+    }
+}
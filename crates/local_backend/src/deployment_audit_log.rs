@@ -0,0 +1,130 @@
+use anyhow::Context;
+use axum::{
+    debug_handler,
+    extract::{
+        Query,
+        State,
+    },
+    response::IntoResponse,
+};
+use common::{
+    document::ParsedDocument,
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+    types::MemberId,
+};
+use errors::ErrorMetadata;
+use model::deployment_audit_log::{
+    types::DeploymentAuditLog,
+    DeploymentAuditLogFilter,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use value::id_v6::DocumentIdV6;
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeploymentAuditLogArgs {
+    /// Only return events with this action, e.g. `delete_tables`.
+    action: Option<String>,
+    member_id: Option<u64>,
+    start_ms: Option<f64>,
+    end_ms: Option<f64>,
+    /// The `cursor` from the previous page, requested with the same filters.
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeploymentAuditLogEntryJson {
+    id: String,
+    timestamp: f64,
+    member_id: Option<u64>,
+    action: String,
+    action_metadata: JsonValue,
+}
+
+impl TryFrom<ParsedDocument<DeploymentAuditLog>> for DeploymentAuditLogEntryJson {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: ParsedDocument<DeploymentAuditLog>) -> anyhow::Result<Self> {
+        let id = DocumentIdV6::from(entry.id()).encode();
+        let timestamp = entry
+            .creation_time()
+            .context("Audit log entry is missing a creation time")?
+            .into();
+        let DeploymentAuditLog { event, member_id } = entry.into_value();
+        let action = event.action().to_string();
+        let mut fields = serde_json::Map::try_from(event)?;
+        let action_metadata = fields.remove("actionMetadata").unwrap_or(JsonValue::Null);
+        Ok(Self {
+            id,
+            timestamp,
+            member_id: member_id.map(u64::from),
+            action,
+            action_metadata,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListDeploymentAuditLogResponse {
+    entries: Vec<DeploymentAuditLogEntryJson>,
+    /// Pass back as `cursor` to get the next page. `null` once there are no
+    /// older entries. It's opaque and only valid with the same filters.
+    cursor: Option<String>,
+}
+
+fn page_size(limit: Option<usize>) -> anyhow::Result<usize> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    anyhow::ensure!(
+        limit > 0 && limit <= MAX_PAGE_SIZE,
+        ErrorMetadata::bad_request(
+            "InvalidLimit",
+            format!("limit must be between 1 and {MAX_PAGE_SIZE}"),
+        )
+    );
+    Ok(limit)
+}
+
+/// Lists deployment audit log entries, newest first.
+#[debug_handler]
+pub async fn list_deployment_audit_log(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<ListDeploymentAuditLogArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let limit = page_size(args.limit)?;
+    let filter = DeploymentAuditLogFilter {
+        action: args.action,
+        member_id: args.member_id.map(MemberId),
+        start_ms: args.start_ms,
+        end_ms: args.end_ms,
+    };
+    let (entries, cursor) = st
+        .application
+        .list_deployment_audit_log(identity, filter, args.cursor, limit)
+        .await?;
+    let entries = entries
+        .into_iter()
+        .map(DeploymentAuditLogEntryJson::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListDeploymentAuditLogResponse { entries, cursor }))
+}
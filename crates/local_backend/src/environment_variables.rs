@@ -13,7 +13,10 @@ use model::environment_variables::types::{
     EnvVarValue,
     EnvironmentVariable,
 };
-use serde::Deserialize;
+use serde::{
+    Deserialize,
+    Serialize,
+};
 
 use crate::{
     admin::must_be_admin,
@@ -77,6 +80,36 @@ pub async fn update_environment_variables(
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+pub struct RevealEnvVarRequest {
+    name: String,
+}
+
+#[derive(Serialize)]
+pub struct RevealEnvVarResponse {
+    name: String,
+    value: String,
+}
+
+/// Returns the decrypted value of one environment variable. Listings of
+/// `_environment_variables` only include names.
+pub async fn reveal_environment_variable(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RevealEnvVarRequest { name }): Json<RevealEnvVarRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let name: EnvVarName = name.parse()?;
+    let value = st
+        .application
+        .reveal_environment_variable(identity, name.clone())
+        .await?;
+    Ok(Json(RevealEnvVarResponse {
+        name: name.to_string(),
+        value: value.to_string(),
+    }))
+}
+
 fn validate_env_var(name: &String, value: &String) -> anyhow::Result<EnvironmentVariable> {
     let name: EnvVarName = name.parse()?;
     let value: EnvVarValue = value.parse()?;
@@ -125,7 +158,9 @@ mod tests {
         backend: &TestLocalBackend,
     ) -> anyhow::Result<BTreeMap<EnvVarName, EnvVarValue>> {
         let mut tx = backend.st.application.begin(Identity::system()).await?;
-        let envs = EnvironmentVariablesModel::new(&mut tx).get_all().await?;
+        let envs = EnvironmentVariablesModel::new(&mut tx)
+            .get_all(backend.st.application.key_broker())
+            .await?;
         Ok(envs)
     }
 
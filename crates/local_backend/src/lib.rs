@@ -3,6 +3,7 @@
 #![feature(let_chains)]
 #![feature(try_blocks)]
 #![feature(iterator_try_collect)]
+#![feature(unwrap_infallible)]
 
 use std::{
     sync::{
@@ -23,12 +24,14 @@ use application::{
 use common::{
     http::{
         fetch::ProxiedFetchClient,
+        serve_grpc,
         RouteMapper,
     },
     knobs::ACTION_USER_TIMEOUT,
     log_streaming::NoopLogSender,
     pause::PauseClient,
     persistence::Persistence,
+    runtime::Runtime,
     types::{
         ConvexOrigin,
         ConvexSite,
@@ -45,6 +48,7 @@ use file_storage::{
     TransactionalFileStorage,
 };
 use function_runner::{
+    client::GrpcFunctionRunner,
     server::{
         InProcessFunctionRunner,
         InstanceStorage,
@@ -61,21 +65,28 @@ use node_executor::{
 };
 use runtime::prod::ProdRuntime;
 use search::{
-    searcher::InProcessSearcher,
+    searcher::{
+        InProcessSearcher,
+        SearchlightClient,
+    },
     Searcher,
 };
 use serde::Serialize;
 
+use crate::subs::sse::SseConnections;
+
 pub mod admin;
 pub mod authentication;
 pub mod config;
 pub mod custom_headers;
 pub mod dashboard;
 pub mod deploy_config;
+pub mod deployment_audit_log;
 pub mod environment_variables;
 pub mod http_actions;
 pub mod import;
 pub mod logs;
+pub mod migrations;
 pub mod node_action_callbacks;
 pub mod parse;
 pub mod proxy;
@@ -102,7 +113,11 @@ pub struct LocalAppState {
     pub application: Application<ProdRuntime>,
     // Number of sync protocol workers.
     pub live_ws_count: Arc<AtomicU64>,
+    // Open Server-Sent Events sync connections, for routing client messages.
+    pub sse_connections: SseConnections,
     pub zombify_rx: async_broadcast::Receiver<()>,
+    // Whether admin routes require a TLS client certificate.
+    pub require_admin_client_cert: bool,
 }
 
 impl LocalAppState {
@@ -121,7 +136,9 @@ impl Clone for LocalAppState {
             instance_name: self.instance_name.clone(),
             application: self.application.clone(),
             live_ws_count: self.live_ws_count.clone(),
+            sse_connections: self.sse_connections.clone(),
             zombify_rx: self.zombify_rx.clone(),
+            require_admin_client_cert: self.require_admin_client_cert,
         }
     }
 }
@@ -137,7 +154,13 @@ pub async fn make_app(
     preempt_tx: ShutdownSignal,
 ) -> anyhow::Result<LocalAppState> {
     let key_broker = config.key_broker()?;
-    let searcher: Arc<dyn Searcher> = Arc::new(InProcessSearcher::new(runtime.clone()).await?);
+    let searcher: Arc<dyn Searcher> = match config.searchlight_url {
+        Some(ref searchlight_url) => Arc::new(SearchlightClient::new(
+            searchlight_url.clone(),
+            key_broker.issue_system_key(),
+        )?),
+        None => Arc::new(InProcessSearcher::new(runtime.clone()).await?),
+    };
     let database = Database::load(
         persistence.clone(),
         runtime.clone(),
@@ -186,7 +209,10 @@ pub async fn make_app(
     };
 
     let node_process_timeout = *ACTION_USER_TIMEOUT + Duration::from_secs(5);
-    let node_executor = Arc::new(LocalNodeExecutor::new(node_process_timeout)?);
+    let node_executor = Arc::new(LocalNodeExecutor::new_with_sandbox(
+        node_process_timeout,
+        config.node_sandbox_config(),
+    )?);
     let actions = Actions::new(
         node_executor,
         config.convex_origin_url(),
@@ -203,22 +229,48 @@ pub async fn make_app(
         config.convex_http_proxy.clone(),
         config.name(),
     ));
-    let function_runner: Arc<dyn FunctionRunner<ProdRuntime>> = Arc::new(
-        InProcessFunctionRunner::new(
-            config.name().clone(),
-            config.secret()?,
-            config.convex_origin_url(),
-            runtime.clone(),
-            persistence.reader(),
-            InstanceStorage {
-                files_storage: files_storage.clone(),
-                modules_storage: modules_storage.clone(),
-            },
-            database.clone(),
-            fetch_client.clone(),
-        )
-        .await?,
-    );
+    let function_runner: Arc<dyn FunctionRunner<ProdRuntime>> = match config.funrun_url {
+        Some(ref funrun_url) => {
+            let function_runner = GrpcFunctionRunner::new(
+                runtime.clone(),
+                funrun_url.clone(),
+                config.name(),
+                config.secret()?,
+                config.convex_origin_url(),
+                database.clone(),
+            )?;
+            // Funrun calls back into this service for table counts, text
+            // search and action callbacks.
+            let backend_server = function_runner.backend_service().into_server();
+            let mut shutdown_rx = zombify_rx.clone();
+            let backend_grpc_addr = config.backend_grpc_bind_address().into();
+            runtime.spawn("backend_grpc_server", async move {
+                let shutdown = async move {
+                    let _ = shutdown_rx.recv().await;
+                };
+                if let Err(e) = serve_grpc(backend_server, backend_grpc_addr, shutdown).await {
+                    tracing::error!("Backend gRPC server failed: {e:#}");
+                }
+            });
+            Arc::new(function_runner)
+        },
+        None => Arc::new(
+            InProcessFunctionRunner::new(
+                config.name().clone(),
+                config.secret()?,
+                config.convex_origin_url(),
+                runtime.clone(),
+                persistence.reader(),
+                InstanceStorage {
+                    files_storage: files_storage.clone(),
+                    modules_storage: modules_storage.clone(),
+                },
+                database.clone(),
+                fetch_client.clone(),
+            )
+            .await?,
+        ),
+    };
     let application = Application::new(
         runtime.clone(),
         database.clone(),
@@ -255,7 +307,9 @@ pub async fn make_app(
         instance_name,
         application,
         live_ws_count: Arc::new(AtomicU64::new(0)),
+        sse_connections: SseConnections::default(),
         zombify_rx,
+        require_admin_client_cert: config.tls_client_ca.is_some(),
     };
 
     Ok(app_state)
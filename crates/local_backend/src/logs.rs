@@ -19,6 +19,7 @@ use common::{
         ExtractClientVersion,
         HttpResponseError,
     },
+    types::UdfIdentifier,
     version::ClientType,
     RequestId,
 };
@@ -249,6 +250,18 @@ fn execution_to_json(
                 execution_id: execution.context.execution_id.to_string(),
             }
         },
+        UdfParams::System { name } => FunctionExecutionJson::Completion {
+            udf_type: execution.udf_type.to_string(),
+            identifier: UdfIdentifier::System(name).to_string(),
+            log_lines: vec![],
+            timestamp: execution.unix_timestamp.as_secs_f64(),
+            cached_result: execution.cached_result,
+            execution_time: execution.execution_time,
+            success: None,
+            error: None,
+            request_id: execution.context.request_id.to_string(),
+            execution_id: execution.context.execution_id.to_string(),
+        },
         UdfParams::Http { result, identifier } => {
             let identifier: String = identifier.to_string();
             let (success, error) = match result {
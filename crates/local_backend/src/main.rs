@@ -87,18 +87,27 @@ async fn run_server_inner(runtime: ProdRuntime, config: LocalConfig) -> anyhow::
     let router = router(st.clone()).await;
     let mut shutdown_rx_ = shutdown_rx.clone();
     let http_service = ConvexHttpService::new(
-        router,
+        router.clone(),
         SERVER_VERSION_STR.to_string(),
         MAX_CONCURRENT_REQUESTS,
         Duration::from_secs(125),
         BackendRouteMapper,
     );
-    let serve_http_future = http_service.serve(config.http_bind_address().into(), async move {
+    let http_shutdown = async move {
         let _ = shutdown_rx_.recv().await;
-    });
+    };
+    let serve_http_future = match config.tls_config() {
+        Some(tls_config) => http_service
+            .serve_tls(config.http_bind_address().into(), tls_config, http_shutdown)
+            .left_future(),
+        None => http_service
+            .serve(config.http_bind_address().into(), http_shutdown)
+            .right_future(),
+    };
     let proxy_future = dev_site_proxy(
         config.site_bind_address(),
-        config.convex_origin_url(),
+        router,
+        config.tls_config(),
         shutdown_rx,
     );
 
@@ -0,0 +1,223 @@
+use axum::{
+    debug_handler,
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    document::ParsedDocument,
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+    knobs::{
+        MIGRATION_DEFAULT_BATCH_SIZE,
+        MIGRATION_MAX_BATCH_SIZE,
+    },
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use model::migrations::{
+    types::{
+        Migration,
+        MigrationState,
+    },
+    MigrationModel,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::TableName;
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    parse::parse_udf_path,
+    LocalAppState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartMigrationRequest {
+    pub name: String,
+    pub table: String,
+    /// Path to a mutation that takes a single document as its argument.
+    pub udf_path: String,
+    pub dry_run: Option<bool>,
+    pub batch_size: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationNameRequest {
+    pub name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MigrationJson {
+    name: String,
+    table: String,
+    udf_path: String,
+    dry_run: bool,
+    batch_size: u32,
+    state: &'static str,
+    error: Option<String>,
+    num_processed: u64,
+    /// For dry runs, how many documents would have changed.
+    num_changed: u64,
+}
+
+impl From<ParsedDocument<Migration>> for MigrationJson {
+    fn from(migration: ParsedDocument<Migration>) -> Self {
+        let migration = migration.into_value();
+        let state = migration.state.as_str();
+        let error = match migration.state {
+            MigrationState::Failed { error } => Some(error),
+            _ => None,
+        };
+        Self {
+            name: migration.name,
+            table: String::from(migration.table_name),
+            udf_path: String::from(migration.udf_path),
+            dry_run: migration.dry_run,
+            batch_size: migration.batch_size,
+            state,
+            error,
+            num_processed: migration.num_processed,
+            num_changed: migration.num_changed,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListMigrationsResponse {
+    migrations: Vec<MigrationJson>,
+}
+
+fn batch_size(batch_size: Option<u32>) -> anyhow::Result<u32> {
+    let batch_size = batch_size.unwrap_or(*MIGRATION_DEFAULT_BATCH_SIZE);
+    anyhow::ensure!(
+        batch_size > 0 && batch_size <= *MIGRATION_MAX_BATCH_SIZE,
+        ErrorMetadata::bad_request(
+            "InvalidBatchSize",
+            format!(
+                "batchSize must be between 1 and {}",
+                *MIGRATION_MAX_BATCH_SIZE
+            ),
+        )
+    );
+    Ok(batch_size)
+}
+
+/// Starts a migration that runs `udfPath` on every document in `table`.
+#[debug_handler]
+pub async fn start_migration(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(req): Json<StartMigrationRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let table_name: TableName = req.table.parse().map_err(|e: anyhow::Error| {
+        e.context(ErrorMetadata::bad_request(
+            "InvalidTableName",
+            format!("{} is not a valid table name", req.table),
+        ))
+    })?;
+    let udf_path = parse_udf_path(&req.udf_path)?.canonicalize();
+    let dry_run = req.dry_run.unwrap_or(false);
+    let batch_size = batch_size(req.batch_size)?;
+    st.application
+        .execute_with_audit_log_events_and_occ_retries(identity, "start_migration", |tx| {
+            async {
+                MigrationModel::new(tx)
+                    .start(
+                        req.name.clone(),
+                        table_name.clone(),
+                        udf_path.clone(),
+                        dry_run,
+                        batch_size,
+                    )
+                    .await?;
+                Ok(((), vec![]))
+            }
+            .into()
+        })
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[debug_handler]
+pub async fn pause_migration(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(MigrationNameRequest { name }): Json<MigrationNameRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    st.application
+        .execute_with_audit_log_events_and_occ_retries(identity, "pause_migration", |tx| {
+            async {
+                MigrationModel::new(tx).pause(&name).await?;
+                Ok(((), vec![]))
+            }
+            .into()
+        })
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[debug_handler]
+pub async fn resume_migration(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(MigrationNameRequest { name }): Json<MigrationNameRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    st.application
+        .execute_with_audit_log_events_and_occ_retries(identity, "resume_migration", |tx| {
+            async {
+                MigrationModel::new(tx).resume(&name).await?;
+                Ok(((), vec![]))
+            }
+            .into()
+        })
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[debug_handler]
+pub async fn cancel_migration(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(MigrationNameRequest { name }): Json<MigrationNameRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    st.application
+        .execute_with_audit_log_events_and_occ_retries(identity, "cancel_migration", |tx| {
+            async {
+                MigrationModel::new(tx).cancel(&name).await?;
+                Ok(((), vec![]))
+            }
+            .into()
+        })
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+/// Lists every migration with its progress.
+#[debug_handler]
+pub async fn list_migrations(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let mut tx = st.application.begin(identity).await?;
+    let migrations = MigrationModel::new(&mut tx)
+        .list()
+        .await?
+        .into_iter()
+        .map(MigrationJson::from)
+        .collect();
+    Ok(Json(ListMigrationsResponse { migrations }))
+}
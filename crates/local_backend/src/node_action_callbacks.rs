@@ -21,7 +21,10 @@ use common::{
         ExecutionId,
     },
     http::{
-        extract::Json,
+        extract::{
+            Json,
+            Query,
+        },
         ExtractClientVersion,
         HttpResponseError,
     },
@@ -317,11 +320,22 @@ pub async fn vector_search(
     Ok(Json(json!({ "results": results })))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateUploadUrlParams {
+    #[serde(default)]
+    resumable: bool,
+}
+
 #[debug_handler]
 pub async fn storage_generate_upload_url(
     State(st): State<LocalAppState>,
+    Query(GenerateUploadUrlParams { resumable }): Query<GenerateUploadUrlParams>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
-    let url = st.application.storage_generate_upload_url().await?;
+    let url = st
+        .application
+        .storage_generate_upload_url(resumable)
+        .await?;
     Ok(Json(json!({ "url": url })))
 }
 
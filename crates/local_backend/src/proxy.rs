@@ -9,21 +9,23 @@ use axum::{
     routing::get,
     Router,
 };
-use common::{
-    http::{
-        ConvexHttpService,
-        HttpResponseError,
-        NoopRouteMapper,
-    },
-    types::ConvexOrigin,
+use common::http::{
+    tls::TlsConfig,
+    ConvexHttpService,
+    HttpResponseError,
+    NoopRouteMapper,
 };
 use http::Request;
 use hyper::Body;
+use tower::ServiceExt;
+
+use crate::MAX_CONCURRENT_REQUESTS;
 
-/// Routes HTTP actions to the main webserver
+/// Routes HTTP actions to the main webserver's router
 pub async fn dev_site_proxy(
     site_bind_addr: Option<([u8; 4], u16)>,
-    origin: ConvexOrigin,
+    backend_router: Router,
+    tls_config: Option<TlsConfig>,
     mut shutdown_rx: async_broadcast::Receiver<()>,
 ) -> anyhow::Result<()> {
     let Some(addr) = site_bind_addr else {
@@ -31,16 +33,15 @@ pub async fn dev_site_proxy(
     };
     tracing::info!("Starting dev site proxy at {:?}...", SocketAddr::from(addr));
 
+    // Dispatch in-process rather than over the network so the proxy works the
+    // same whether or not the main listener terminates TLS.
     async fn proxy_method(
-        State(st): State<ConvexOrigin>,
+        State(backend_router): State<Router>,
         mut request: Request<Body>,
     ) -> Result<impl IntoResponse, HttpResponseError> {
-        let new_uri = format!("{}/http{}", st, request.uri());
+        let new_uri = format!("/http{}", request.uri());
         *request.uri_mut() = new_uri.parse().map_err(anyhow::Error::new)?;
-        let resp = hyper::Client::new()
-            .request(request)
-            .await
-            .map_err(anyhow::Error::new)?;
+        let resp = backend_router.oneshot(request).await.into_ok();
         Ok(resp)
     }
 
@@ -53,19 +54,22 @@ pub async fn dev_site_proxy(
     let router = Router::new()
         .route("/*rest", proxy_handler.clone())
         .route("/", proxy_handler)
-        .with_state(origin);
+        .with_state(backend_router);
 
     let service = ConvexHttpService::new(
         Router::new().fallback_service(router),
         "unknown".to_string(),
-        4,
+        MAX_CONCURRENT_REQUESTS,
         Duration::from_secs(125),
         NoopRouteMapper,
     );
-    let proxy_server = service.serve(addr.into(), async move {
+    let shutdown = async move {
         let _ = shutdown_rx.recv().await;
         tracing::info!("Shut down proxy");
-    });
-    proxy_server.await?;
+    };
+    match tls_config {
+        Some(tls_config) => service.serve_tls(addr.into(), tls_config, shutdown).await?,
+        None => service.serve(addr.into(), shutdown).await?,
+    }
     Ok(())
 }
@@ -1,3 +1,8 @@
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
 use anyhow::{
     anyhow,
     Context,
@@ -29,11 +34,13 @@ use common::{
 };
 use errors::ErrorMetadata;
 use isolate::UdfArgsJson;
+use keybroker::Identity;
 use serde::{
     Deserialize,
     Serialize,
 };
 use serde_json::Value as JsonValue;
+use sync_types::Timestamp;
 use value::{
     export::ValueFormat,
     ConvexValue,
@@ -55,11 +62,30 @@ pub struct UdfPostRequest {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UdfArgsQuery {
     path: String,
     args: UdfArgsJson,
 
     format: Option<String>,
+
+    /// Snapshot timestamp to read at, as returned in the `ts` of an earlier
+    /// query response.
+    ts: Option<String>,
+    /// Wall-clock time to read at, in milliseconds since the Unix epoch.
+    as_of: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryPostRequest {
+    path: String,
+    args: UdfArgsJson,
+
+    format: Option<String>,
+
+    ts: Option<String>,
+    as_of: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -85,6 +111,15 @@ pub enum UdfResponse {
     },
 }
 
+/// Response to a query, along with the snapshot timestamp it read at. Passing
+/// that timestamp back as `ts` reads at the same snapshot.
+#[derive(Serialize)]
+pub struct QueryResponse {
+    #[serde(flatten)]
+    response: UdfResponse,
+    ts: String,
+}
+
 impl UdfResponse {
     pub fn nested_error(
         error: RedactedJsError,
@@ -176,6 +211,43 @@ pub async fn public_function_post(
     Ok(Json(response))
 }
 
+/// Resolves the timestamp a query should read at: the latest snapshot unless
+/// the caller asked for a snapshot timestamp (`ts`) or a wall-clock time
+/// (`asOf`) within the retention window.
+///
+/// Reading at an earlier snapshot can show documents that have since been
+/// deleted or changed, so only admins may pick the timestamp.
+async fn query_read_ts(
+    st: &LocalAppState,
+    identity: &Identity,
+    ts: Option<String>,
+    as_of: Option<f64>,
+) -> anyhow::Result<Timestamp> {
+    if (ts.is_some() || as_of.is_some()) && !(identity.is_admin() || identity.is_system()) {
+        anyhow::bail!(bad_admin_key_error(Some(st.instance_name.clone())));
+    }
+    let ts = match (ts, as_of) {
+        (None, None) => return Ok(*st.application.now_ts_for_reads()),
+        (Some(_), Some(_)) => anyhow::bail!(ErrorMetadata::bad_request(
+            "ConflictingTimestamps",
+            "Only one of `ts` and `asOf` may be provided",
+        )),
+        (Some(ts), None) => ts.parse().context(ErrorMetadata::bad_request(
+            "InvalidTimestamp",
+            format!("Invalid snapshot timestamp: {ts}"),
+        ))?,
+        (None, Some(as_of)) => Duration::try_from_secs_f64(as_of / 1000.0)
+            .ok()
+            .and_then(|since_epoch| SystemTime::UNIX_EPOCH.checked_add(since_epoch))
+            .and_then(|system_time| Timestamp::try_from(system_time).ok())
+            .context(ErrorMetadata::bad_request(
+                "InvalidTimestamp",
+                format!("Invalid `asOf` time: {as_of}"),
+            ))?,
+    };
+    st.application.validate_read_ts(ts).await
+}
+
 pub fn export_value(
     value: ConvexValue,
     value_format: Option<ValueFormat>,
@@ -212,13 +284,16 @@ pub async fn public_query_get(
         format!("Failed to parse Convex function path: {}", req.path),
     ))?;
     let args = req.args.into_arg_vec();
+    let ts = query_read_ts(&st, &identity, req.ts, req.as_of).await?;
     let udf_return = st
         .application
-        .read_only_udf(
+        .read_only_udf_at_ts(
             request_id,
             udf_path,
             args,
             identity,
+            ts,
+            None,
             AllowedVisibility::PublicOnly,
             FunctionCaller::HttpApi(client_version.clone()),
         )
@@ -233,7 +308,10 @@ pub async fn public_query_get(
             UdfResponse::error(error, udf_return.log_lines, value_format, client_version)?
         },
     };
-    Ok(Json(response))
+    Ok(Json(QueryResponse {
+        response,
+        ts: udf_return.ts.to_string(),
+    }))
 }
 
 #[minitrace::trace(properties = { "udf_type": "query"})]
@@ -242,19 +320,22 @@ pub async fn public_query_post(
     ExtractRequestId(request_id): ExtractRequestId,
     ExtractIdentity(identity): ExtractIdentity,
     ExtractClientVersion(client_version): ExtractClientVersion,
-    Json(req): Json<UdfPostRequest>,
+    Json(req): Json<QueryPostRequest>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let udf_path = req.path.parse().context(ErrorMetadata::bad_request(
         "InvalidConvexFunction",
         format!("Failed to parse Convex function path: {}", req.path),
     ))?;
+    let ts = query_read_ts(&st, &identity, req.ts, req.as_of).await?;
     let udf_return = st
         .application
-        .read_only_udf(
+        .read_only_udf_at_ts(
             request_id,
             udf_path,
             req.args.into_arg_vec(),
             identity,
+            ts,
+            None,
             AllowedVisibility::PublicOnly,
             FunctionCaller::HttpApi(client_version.clone()),
         )
@@ -269,17 +350,27 @@ pub async fn public_query_post(
             UdfResponse::error(error, udf_return.log_lines, value_format, client_version)?
         },
     };
-    Ok(Json(response))
+    Ok(Json(QueryResponse {
+        response,
+        ts: udf_return.ts.to_string(),
+    }))
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct QueryBatchArgs {
     queries: Vec<UdfPostRequest>,
+
+    /// All queries in the batch read at the same snapshot, which may be
+    /// pinned with `ts` or `asOf` as for a single query.
+    ts: Option<String>,
+    as_of: Option<f64>,
 }
 
 #[derive(Serialize)]
 pub struct QueryBatchResponse {
     results: Vec<UdfResponse>,
+    ts: String,
 }
 
 pub async fn public_query_batch_post(
@@ -290,7 +381,7 @@ pub async fn public_query_batch_post(
     Json(req_batch): Json<QueryBatchArgs>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let mut results = vec![];
-    let ts = *st.application.now_ts_for_reads();
+    let ts = query_read_ts(&st, &identity, req_batch.ts, req_batch.as_of).await?;
     for req in req_batch.queries {
         let value_format = req.format.as_ref().map(|f| f.parse()).transpose()?;
         let udf_path = parse_udf_path(&req.path)?;
@@ -321,7 +412,10 @@ pub async fn public_query_batch_post(
         };
         results.push(response);
     }
-    Ok(Json(QueryBatchResponse { results }))
+    Ok(Json(QueryBatchResponse {
+        results,
+        ts: ts.to_string(),
+    }))
 }
 
 #[minitrace::trace(properties = { "udf_type": "mutation"})]
@@ -401,6 +495,7 @@ pub async fn public_action_post(
 #[cfg(test)]
 mod tests {
     use application::test_helpers::ApplicationTestExt;
+    use axum::headers::authorization::Credentials;
     use http::{
         Request,
         StatusCode,
@@ -412,7 +507,10 @@ mod tests {
         Value as JsonValue,
     };
 
-    use crate::test_helpers::setup_backend_for_test;
+    use crate::test_helpers::{
+        setup_backend_for_test,
+        TestLocalBackend,
+    };
 
     async fn http_format_tester(
         rt: ProdRuntime,
@@ -439,7 +537,14 @@ mod tests {
             .body(body)?;
         match expected {
             Ok(expected) => {
-                let result: JsonValue = backend.expect_success_and_result(req).await?;
+                let mut result: JsonValue = backend.expect_success_and_result(req).await?;
+                if uri == "/api/query" {
+                    // Queries also return the snapshot timestamp they read at.
+                    let ts = result
+                        .as_object_mut()
+                        .and_then(|result| result.remove("ts"));
+                    assert!(ts.is_some_and(|ts| ts.is_string()));
+                }
                 assert_eq!(
                     result,
                     json!({
@@ -560,4 +665,158 @@ mod tests {
         )
         .await
     }
+
+    fn post_request(uri: &str, body: JsonValue) -> anyhow::Result<Request<Body>> {
+        Ok(Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&body)?))?)
+    }
+
+    fn query_request(body: JsonValue) -> anyhow::Result<Request<Body>> {
+        post_request("/api/query", body)
+    }
+
+    fn admin_post_request(
+        backend: &TestLocalBackend,
+        uri: &str,
+        body: JsonValue,
+    ) -> anyhow::Result<Request<Body>> {
+        let mut req = post_request(uri, body)?;
+        req.headers_mut()
+            .insert("Authorization", backend.admin_auth_header.0.encode());
+        Ok(req)
+    }
+
+    fn admin_query_request(
+        backend: &TestLocalBackend,
+        body: JsonValue,
+    ) -> anyhow::Result<Request<Body>> {
+        admin_post_request(backend, "/api/query", body)
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_http_query_at_ts(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        let count = json!({
+            "path": "basic:count",
+            "args": {},
+            "format": "json",
+        });
+        let before: JsonValue = backend
+            .expect_success_and_result(query_request(count.clone())?)
+            .await?;
+        assert_eq!(before["value"], json!(0));
+        let ts = before["ts"].as_str().expect("Missing ts").to_owned();
+
+        let insert = post_request(
+            "/api/mutation",
+            json!({
+                "path": "basic:insertObject",
+                "args": {"field": "value"},
+                "format": "json",
+            }),
+        )?;
+        backend.expect_success(insert).await?;
+
+        let after: JsonValue = backend
+            .expect_success_and_result(query_request(count.clone())?)
+            .await?;
+        assert_eq!(after["value"], json!(1));
+
+        let mut count_at_ts = count;
+        count_at_ts["ts"] = ts.clone().into();
+        let at_ts: JsonValue = backend
+            .expect_success_and_result(admin_query_request(&backend, count_at_ts)?)
+            .await?;
+        assert_eq!(at_ts["value"], json!(0));
+        assert_eq!(at_ts["ts"], json!(ts));
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_http_query_ts_in_future(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        let req = admin_query_request(
+            &backend,
+            json!({
+                "path": "basic:count",
+                "args": {},
+                "format": "json",
+                "ts": (i64::MAX as u64).to_string(),
+            }),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "TimestampInFuture")
+            .await
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_http_query_conflicting_timestamps(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        let req = admin_query_request(
+            &backend,
+            json!({
+                "path": "basic:count",
+                "args": {},
+                "format": "json",
+                "ts": "1",
+                "asOf": 1.0,
+            }),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "ConflictingTimestamps")
+            .await
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_http_query_at_ts_requires_admin_key(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        let count: JsonValue = backend
+            .expect_success_and_result(query_request(json!({
+                "path": "basic:count",
+                "args": {},
+                "format": "json",
+            }))?)
+            .await?;
+        let ts = count["ts"].as_str().expect("Missing ts").to_owned();
+
+        let req = query_request(json!({
+            "path": "basic:count",
+            "args": {},
+            "format": "json",
+            "ts": ts.clone(),
+        }))?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "BadDeployKey")
+            .await?;
+        let req = query_request(json!({
+            "path": "basic:count",
+            "args": {},
+            "format": "json",
+            "asOf": 1.0,
+        }))?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "BadDeployKey")
+            .await?;
+
+        let batch = json!({
+            "queries": [{"path": "basic:count", "args": {}, "format": "json"}],
+            "ts": ts,
+        });
+        let req = post_request("/api/query_batch", batch.clone())?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "BadDeployKey")
+            .await?;
+        let result: JsonValue = backend
+            .expect_success_and_result(admin_post_request(&backend, "/api/query_batch", batch)?)
+            .await?;
+        assert_eq!(result["results"][0]["value"], json!(0));
+        Ok(())
+    }
 }
@@ -16,6 +16,7 @@ use common::{
         CONVEX_CLIENT_HEADER,
     },
     knobs::{
+        FILE_UPLOAD_MAX_PART_SIZE,
         MAX_BACKEND_ACTION_CALLBACKS_REQUEST_SIZE,
         MAX_BACKEND_PUBLIC_API_REQUEST_SIZE,
         MAX_PUSH_BYTES,
@@ -42,17 +43,27 @@ use tower_http::{
 };
 
 use crate::{
+    authentication::require_client_certificate_middleware,
     dashboard::{
+        collect_storage_garbage,
         delete_tables,
+        explain_query,
         get_indexes,
         shapes2,
+        slow_queries,
     },
     deploy_config::{
         get_config,
         get_config_hashes,
+        list_deployments,
         push_config,
+        rollback_deployment,
+    },
+    deployment_audit_log::list_deployment_audit_log,
+    environment_variables::{
+        reveal_environment_variable,
+        update_environment_variables,
     },
-    environment_variables::update_environment_variables,
     http_actions::http_action_handler,
     import::{
         import,
@@ -66,6 +77,13 @@ use crate::{
         stream_function_logs,
         stream_udf_execution,
     },
+    migrations::{
+        cancel_migration,
+        list_migrations,
+        pause_migration,
+        resume_migration,
+        start_migration,
+    },
     node_action_callbacks::{
         action_callbacks_middleware,
         cancel_developer_job,
@@ -102,10 +120,18 @@ use crate::{
         request_zip_export,
     },
     storage::{
+        storage_finish_upload_session,
         storage_get,
+        storage_get_upload_session,
+        storage_start_upload_session,
         storage_upload,
+        storage_upload_session_part,
     },
     subs::{
+        sse::{
+            sync_sse,
+            sync_sse_send,
+        },
         sync,
         sync_client_version_url,
     },
@@ -116,7 +142,8 @@ pub async fn router(st: LocalAppState) -> Router {
     let browser_routes = Router::new()
         // Called by the browser (and optionally authenticated by a cookie or `Authorization`
         // header). Passes version in the URL because websockets can't do it in header.
-        .route("/:client_version/sync", get(sync_client_version_url));
+        .route("/:client_version/sync", get(sync_client_version_url))
+        .route("/:client_version/sync/sse", get(sync_sse));
 
     let dashboard_routes = Router::new()
         // Scheduled jobs routes
@@ -124,10 +151,21 @@ pub async fn router(st: LocalAppState) -> Router {
         .route("/cancel_job", post(cancel_job))
         // Environment variable routes
         .route("/update_environment_variables", post(update_environment_variables))
+        .route("/reveal_environment_variable", post(reveal_environment_variable))
         // Administrative routes for the dashboard
         .route("/shapes2", get(shapes2))
         .route("/get_indexes", get(get_indexes))
         .route("/delete_tables", post(delete_tables))
+        .route("/collect_storage_garbage", post(collect_storage_garbage))
+        .route("/explain_query", post(explain_query))
+        .route("/slow_queries", get(slow_queries))
+        .route("/deployment_audit_log", get(list_deployment_audit_log))
+        // Data migration routes
+        .route("/migrations", get(list_migrations))
+        .route("/start_migration", post(start_migration))
+        .route("/pause_migration", post(pause_migration))
+        .route("/resume_migration", post(resume_migration))
+        .route("/cancel_migration", post(cancel_migration))
         // Metrics routes
         .route("/app_metrics/stream_udf_execution", get(stream_udf_execution))
         .route("/app_metrics/stream_function_logs", get(stream_function_logs))
@@ -146,6 +184,8 @@ pub async fn router(st: LocalAppState) -> Router {
         )
         .route("/get_config", post(get_config))
         .route("/get_config_hashes", post(get_config_hashes))
+        .route("/list_deployments", post(list_deployments))
+        .route("/rollback_deployment", post(rollback_deployment))
         .route("/schema_state/:schema_id", get(schema_state))
         .route("/stream_udf_execution", get(stream_udf_execution))
         .route("/stream_function_logs", get(stream_function_logs))
@@ -158,13 +198,22 @@ pub async fn router(st: LocalAppState) -> Router {
         .route("/request/zip", post(request_zip_export))
         .route("/zip/:snapshot_ts", get(get_zip_export));
 
-    let api_routes = Router::new()
-        .merge(browser_routes)
+    // Routes that take an admin key additionally require a TLS client certificate
+    // when mutual TLS is configured.
+    let admin_routes = Router::new()
         .merge(cli_routes)
         .merge(dashboard_routes)
+        .nest("/export", snapshot_export_routes)
+        .layer(axum::middleware::from_fn_with_state(
+            st.clone(),
+            require_client_certificate_middleware,
+        ));
+
+    let api_routes = Router::new()
+        .merge(browser_routes)
+        .merge(admin_routes)
         .merge(public_api_routes())
         .nest("/actions", action_callback_routes(st.clone()))
-        .nest("/export", snapshot_export_routes)
         .nest("/storage", storage_api_routes());
 
     Router::new()
@@ -180,6 +229,8 @@ pub async fn router(st: LocalAppState) -> Router {
 pub fn public_api_routes() -> Router<LocalAppState> {
     Router::new()
         .route("/sync", get(sync))
+        .route("/sync/sse", get(sync_sse))
+        .route("/sync/sse/:connection_id", post(sync_sse_send))
         .route("/query", get(public_query_get))
         .route("/query", post(public_query_post))
         .route("/query_batch", post(public_query_batch_post))
@@ -192,6 +243,17 @@ pub fn public_api_routes() -> Router<LocalAppState> {
 pub fn storage_api_routes() -> Router<LocalAppState> {
     Router::new()
         .route("/upload", post(storage_upload))
+        .route("/upload/resumable", post(storage_start_upload_session))
+        .route(
+            "/upload/resumable/:upload_id",
+            get(storage_get_upload_session)
+                .put(storage_upload_session_part)
+                .layer(DefaultBodyLimit::max(*FILE_UPLOAD_MAX_PART_SIZE)),
+        )
+        .route(
+            "/upload/resumable/:upload_id/finish",
+            post(storage_finish_upload_session),
+        )
         .route("/:storage_id", get(storage_get))
 }
 
@@ -108,6 +108,9 @@ pub struct IndexMetadataResponse {
     // Either an array of fields (`string[]`) for a database index or an object of
     // `{ searchField: string, filterFields: string }` for a search index.
     fields: JsonValue,
+    // Only set for unique database indexes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unique: Option<bool>,
     backfill: BackfillResponse,
 }
 
@@ -119,7 +122,7 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
         let name = meta.name.descriptor().to_string();
         Ok(match meta.config {
             IndexConfig::Database {
-                developer_config: DeveloperDatabaseIndexConfig { fields },
+                developer_config: DeveloperDatabaseIndexConfig { fields, unique, .. },
                 on_disk_state,
             } => {
                 let backfill_state = match on_disk_state {
@@ -138,6 +141,7 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                     table,
                     name,
                     fields: JsonValue::from(ConvexValue::try_from(fields)?),
+                    unique: unique.then_some(true),
                     backfill: BackfillResponse {
                         state: backfill_state,
                     },
@@ -168,6 +172,7 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                         "searchField":  String::from(search_field),
                         "filterFields": filter_fields.into_iter().map(String::from).collect::<Vec<_>>()
                     }),
+                    unique: None,
                     backfill: BackfillResponse {
                         state: backfill_state,
                     },
@@ -196,6 +201,7 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                         "vectorField": String::from(vector_field),
                         "filterFields": filter_fields.into_iter().map(String::from).collect::<Vec<_>>()
                     }),
+                    unique: None,
                     backfill: BackfillResponse {
                         state: backfill_state,
                     },
@@ -321,7 +327,7 @@ struct SchemaStateResponse {
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "state")]
-enum SchemaStateJson {
+pub enum SchemaStateJson {
     Pending,
     Validated,
     Active,
@@ -5,7 +5,10 @@ use std::{
 
 use anyhow::Context;
 use axum::{
-    body::StreamBody,
+    body::{
+        Bytes,
+        StreamBody,
+    },
     debug_handler,
     extract::{
         rejection::{
@@ -44,6 +47,7 @@ use errors::ErrorMetadata;
 use file_storage::{
     FileRangeStream,
     FileStream,
+    FileUploadProgress,
 };
 use futures::StreamExt;
 use http::StatusCode;
@@ -112,6 +116,112 @@ pub async fn storage_upload(
     }))
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadSessionResponse {
+    upload_id: String,
+    offset: u64,
+    expires_at: u64,
+}
+
+impl TryFrom<FileUploadProgress> for UploadSessionResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(progress: FileUploadProgress) -> anyhow::Result<Self> {
+        Ok(Self {
+            upload_id: progress.upload_id,
+            offset: progress.offset,
+            expires_at: progress.expiration_ts.as_ms_since_epoch()?,
+        })
+    }
+}
+
+/// Start a resumable upload. The file's content type is taken from this
+/// request, and parts are then uploaded with `storage_upload_session_part`.
+/// The session is bound to `token`, which the other session endpoints must be
+/// passed too. It's only checked for expiry here, so uploads can outlive it.
+#[debug_handler]
+pub async fn storage_start_upload_session(
+    State(st): State<LocalAppState>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    content_type: Result<TypedHeader<ContentType>, TypedHeaderRejection>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    st.application.key_broker().check_store_file_authorization(
+        &st.application.runtime(),
+        &token,
+        STORE_FILE_AUTHORIZATION_VALIDITY,
+    )?;
+    let content_type = map_header_err(content_type)?;
+    let progress = st
+        .application
+        .start_file_upload_session(&token, content_type)
+        .await?;
+    Ok(Json(UploadSessionResponse::try_from(progress)?))
+}
+
+#[debug_handler]
+pub async fn storage_get_upload_session(
+    State(st): State<LocalAppState>,
+    Path(upload_id): Path<String>,
+    Query(QueryParams { token }): Query<QueryParams>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let progress = st
+        .application
+        .get_file_upload_session(&token, &upload_id)
+        .await?;
+    Ok(Json(UploadSessionResponse::try_from(progress)?))
+}
+
+#[derive(Deserialize)]
+pub struct UploadPartParams {
+    token: String,
+    offset: u64,
+}
+
+#[debug_handler]
+pub async fn storage_upload_session_part(
+    State(st): State<LocalAppState>,
+    Path(upload_id): Path<String>,
+    Query(UploadPartParams { token, offset }): Query<UploadPartParams>,
+    body: Bytes,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let progress = st
+        .application
+        .upload_file_part(&token, &upload_id, offset, body)
+        .await?;
+    Ok(Json(UploadSessionResponse::try_from(progress)?))
+}
+
+/// Finish a resumable upload. The sha256 of the whole file must be passed in
+/// the `Digest` header.
+#[debug_handler]
+pub async fn storage_finish_upload_session(
+    State(st): State<LocalAppState>,
+    Path(upload_id): Path<String>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    sha256: Result<TypedHeader<DigestHeader>, TypedHeaderRejection>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let sha256 = map_header_err(sha256)?
+        .map(|dh| dh.0)
+        .context(ErrorMetadata::bad_request(
+            "MissingDigest",
+            "Finishing a resumable upload requires a sha256 Digest header",
+        ))?;
+    let storage_id = st
+        .application
+        .finish_file_upload_session(&token, &upload_id, sha256)
+        .await?;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Response {
+        storage_id: String,
+    }
+    Ok(Json(Response {
+        storage_id: storage_id.to_string(),
+    }))
+}
+
 #[debug_handler]
 pub async fn storage_get(
     State(st): State<LocalAppState>,
@@ -184,3 +294,165 @@ pub async fn storage_get(
     )
         .into_response())
 }
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        runtime::Runtime,
+        sha256::Sha256,
+    };
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use hyper::Body;
+    use runtime::prod::ProdRuntime;
+    use serde_json::Value as JsonValue;
+
+    use crate::test_helpers::{
+        setup_backend_for_test,
+        TestLocalBackend,
+    };
+
+    /// Returns the session's upload id and the token it was started with.
+    async fn start_upload(backend: &TestLocalBackend) -> anyhow::Result<(String, String)> {
+        let rt = backend.st.application.runtime();
+        let token = backend
+            .st
+            .application
+            .key_broker()
+            .issue_store_file_authorization(&rt, rt.unix_timestamp())?;
+        let req = Request::builder()
+            .uri(format!("/api/storage/upload/resumable?token={token}"))
+            .method("POST")
+            .header("Content-Type", "text/plain")
+            .body(Body::empty())?;
+        let session: JsonValue = backend.expect_success_and_result(req).await?;
+        assert_eq!(session["offset"], 0);
+        Ok((
+            session["uploadId"].as_str().unwrap().to_string(),
+            token.to_string(),
+        ))
+    }
+
+    fn upload_part(
+        upload_id: &str,
+        token: &str,
+        offset: u64,
+        part: &'static [u8],
+    ) -> anyhow::Result<Request<Body>> {
+        Ok(Request::builder()
+            .uri(format!(
+                "/api/storage/upload/resumable/{upload_id}?token={token}&offset={offset}"
+            ))
+            .method("PUT")
+            .body(Body::from(part))?)
+    }
+
+    fn finish_upload(
+        upload_id: &str,
+        token: &str,
+        contents: &[u8],
+    ) -> anyhow::Result<Request<Body>> {
+        Ok(Request::builder()
+            .uri(format!(
+                "/api/storage/upload/resumable/{upload_id}/finish?token={token}"
+            ))
+            .method("POST")
+            .header(
+                "Digest",
+                format!("sha-256={}", Sha256::hash(contents).as_base64()),
+            )
+            .body(Body::empty())?)
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_resumable_upload(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let (upload_id, token) = start_upload(&backend).await?;
+
+        let progress: JsonValue = backend
+            .expect_success_and_result(upload_part(&upload_id, &token, 0, b"hello ")?)
+            .await?;
+        assert_eq!(progress["offset"], 6);
+
+        // Retrying a part that was already received is rejected, and the
+        // client should resume from the session's offset instead.
+        backend
+            .expect_error(
+                upload_part(&upload_id, &token, 0, b"HELLO ")?,
+                StatusCode::BAD_REQUEST,
+                "UploadOffsetMismatch",
+            )
+            .await?;
+        let req = Request::builder()
+            .uri(format!(
+                "/api/storage/upload/resumable/{upload_id}?token={token}"
+            ))
+            .method("GET")
+            .body(Body::empty())?;
+        let progress: JsonValue = backend.expect_success_and_result(req).await?;
+        assert_eq!(progress["offset"], 6);
+
+        backend
+            .expect_success(upload_part(&upload_id, &token, 6, b"world")?)
+            .await?;
+        let result: JsonValue = backend
+            .expect_success_and_result(finish_upload(&upload_id, &token, b"hello world")?)
+            .await?;
+        assert!(result["storageId"].is_string());
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_resumable_upload_errors(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let (upload_id, token) = start_upload(&backend).await?;
+        // Knowing the upload id isn't enough without the session's token.
+        let (_, other_token) = start_upload(&backend).await?;
+        backend
+            .expect_error(
+                upload_part(&upload_id, &other_token, 0, b"hello")?,
+                StatusCode::NOT_FOUND,
+                "UploadSessionNotFound",
+            )
+            .await?;
+        backend
+            .expect_error(
+                upload_part(&upload_id, &token, 3, b"hello")?,
+                StatusCode::BAD_REQUEST,
+                "UploadOffsetMismatch",
+            )
+            .await?;
+        backend
+            .expect_success(upload_part(&upload_id, &token, 0, b"hello")?)
+            .await?;
+
+        let req = Request::builder()
+            .uri(format!(
+                "/api/storage/upload/resumable/{upload_id}/finish?token={token}"
+            ))
+            .method("POST")
+            .body(Body::empty())?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "MissingDigest")
+            .await?;
+        backend
+            .expect_error(
+                finish_upload(&upload_id, &token, b"goodbye")?,
+                StatusCode::BAD_REQUEST,
+                "Sha256Mismatch",
+            )
+            .await?;
+
+        // The session is gone after a failed finish.
+        backend
+            .expect_error(
+                finish_upload(&upload_id, &token, b"hello")?,
+                StatusCode::NOT_FOUND,
+                "UploadSessionNotFound",
+            )
+            .await?;
+        Ok(())
+    }
+}
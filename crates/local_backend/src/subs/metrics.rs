@@ -66,21 +66,50 @@ register_convex_counter!(
     &["endpoint"]
 );
 pub fn log_websocket_message_out(message: &ServerMessage, delay: Duration) {
-    let endpoint = match message {
+    let labels = vec![MetricLabel::new("endpoint", message_endpoint(message))];
+    log_distribution_with_labels(
+        &BACKEND_WS_SEND_DELAY_SECONDS,
+        delay.as_secs_f64(),
+        labels.clone(),
+    );
+    log_counter_with_labels(&BACKEND_WS_OUT_TOTAL, 1, labels);
+}
+
+fn message_endpoint(message: &ServerMessage) -> &'static str {
+    match message {
         ServerMessage::Transition { .. } => "Transition",
         ServerMessage::MutationResponse { .. } => "MutationResponse",
         ServerMessage::ActionResponse { .. } => "ActionResponse",
         ServerMessage::AuthError { .. } => "AuthError",
         ServerMessage::FatalError { .. } => "FatalError",
         ServerMessage::Ping { .. } => "Ping",
-    };
-    let labels = vec![MetricLabel::new("endpoint", endpoint)];
-    log_distribution_with_labels(
-        &BACKEND_WS_SEND_DELAY_SECONDS,
-        delay.as_secs_f64(),
-        labels.clone(),
+    }
+}
+
+register_convex_counter!(
+    BACKEND_WS_COMPRESSION_NEGOTIATED_TOTAL,
+    "Number of websockets, labeled by whether they negotiated permessage-deflate",
+    &["negotiated"]
+);
+pub fn log_websocket_compression_negotiated(negotiated: bool) {
+    let labels = vec![MetricLabel::new("negotiated", negotiated.to_string())];
+    log_counter_with_labels(&BACKEND_WS_COMPRESSION_NEGOTIATED_TOTAL, 1, labels);
+}
+
+register_convex_counter!(
+    BACKEND_WS_OUT_UNCOMPRESSED_BYTES_TOTAL,
+    "Payload bytes of outgoing websocket messages before compression"
+);
+register_convex_counter!(
+    BACKEND_WS_OUT_COMPRESSED_BYTES_TOTAL,
+    "Payload bytes of outgoing websocket messages as sent, after any compression"
+);
+pub fn log_websocket_bytes_out(uncompressed: usize, compressed: usize) {
+    log_counter(
+        &BACKEND_WS_OUT_UNCOMPRESSED_BYTES_TOTAL,
+        uncompressed as u64,
     );
-    log_counter_with_labels(&BACKEND_WS_OUT_TOTAL, 1, labels);
+    log_counter(&BACKEND_WS_OUT_COMPRESSED_BYTES_TOTAL, compressed as u64);
 }
 
 register_convex_counter!(
@@ -120,3 +149,56 @@ register_convex_counter!(pub WEBSOCKET_CONNECTION_RESET_TOTAL, "Number of websoc
 pub fn log_websocket_connection_reset() {
     log_counter(&WEBSOCKET_CONNECTION_RESET_TOTAL, 1)
 }
+
+register_convex_gauge!(
+    SYNC_PROTOCOL_SSE_TOTAL,
+    "Number of Server-Sent Events sync connections to a backend",
+);
+pub fn log_sync_protocol_sse_total(count: usize) {
+    log_gauge(&SYNC_PROTOCOL_SSE_TOTAL, count as f64);
+}
+
+register_convex_counter!(
+    BACKEND_SSE_IN_TOTAL,
+    "Count of sync messages received over Server-Sent Events connections"
+);
+pub fn log_sse_message_in() {
+    log_counter(&BACKEND_SSE_IN_TOTAL, 1);
+}
+
+register_convex_histogram!(
+    BACKEND_SSE_SEND_DELAY_SECONDS,
+    "Delay between generating a message in the sync worker and sending it as a Server-Sent Event.",
+    &["endpoint"]
+);
+register_convex_counter!(
+    BACKEND_SSE_OUT_TOTAL,
+    "Count of outgoing sync messages sent as Server-Sent Events",
+    &["endpoint"]
+);
+pub fn log_sse_message_out(message: &ServerMessage, delay: Duration) {
+    let labels = vec![MetricLabel::new("endpoint", message_endpoint(message))];
+    log_distribution_with_labels(
+        &BACKEND_SSE_SEND_DELAY_SECONDS,
+        delay.as_secs_f64(),
+        labels.clone(),
+    );
+    log_counter_with_labels(&BACKEND_SSE_OUT_TOTAL, 1, labels);
+}
+
+register_convex_counter!(
+    BACKEND_SSE_CLOSED_TOTAL,
+    "Number of times a Server-Sent Events sync connection was closed"
+);
+pub fn log_sse_closed() {
+    log_counter(&BACKEND_SSE_CLOSED_TOTAL, 1);
+}
+
+register_convex_counter!(
+    BACKEND_SSE_SERVER_ERROR_TOTAL,
+    "Count of Server-Sent Events sync server errors",
+    &["type"]
+);
+pub fn log_sse_server_error(tag: MetricLabel) {
+    log_counter_with_labels(&BACKEND_SSE_SERVER_ERROR_TOTAL, 1, vec![tag]);
+}
@@ -1,10 +1,12 @@
 use std::{
+    future::Future,
     sync::{
         atomic::{
             AtomicU64,
             Ordering,
         },
         Arc,
+        LazyLock,
     },
     time::{
         Duration,
@@ -18,16 +20,15 @@ use ::errors::{
 };
 use anyhow::Context as _;
 use axum::{
-    extract::{
-        ws::{
-            CloseFrame,
-            Message,
-            WebSocket,
-            WebSocketUpgrade,
-        },
-        State,
+    body::{
+        boxed,
+        Body,
+    },
+    extract::State,
+    response::{
+        IntoResponse,
+        Response,
     },
-    response::IntoResponse,
 };
 use common::{
     errors::{
@@ -38,6 +39,11 @@ use common::{
         ExtractClientVersion,
         HttpResponseError,
     },
+    knobs::{
+        SYNC_WS_COMPRESSION_ENABLED,
+        SYNC_WS_COMPRESSION_THRESHOLD_BYTES,
+        SYNC_WS_COMPRESSION_WINDOW_BITS,
+    },
     runtime::Runtime,
     version::{
         ClientType,
@@ -53,6 +59,17 @@ use futures::{
     SinkExt,
     StreamExt,
 };
+use http::{
+    header,
+    HeaderMap,
+    Method,
+    Request,
+    StatusCode,
+};
+use hyper::upgrade::{
+    OnUpgrade,
+    Upgraded,
+};
 use parking_lot::Mutex;
 use runtime::prod::ProdRuntime;
 use serde_json::Value as JsonValue;
@@ -62,15 +79,33 @@ use sync::{
     SyncWorker,
     SyncWorkerConfig,
 };
-use sync_types::IdentityVersion;
+use sync_types::{
+    deflate::{
+        DeflateConfig,
+        DeflateParams,
+        DeflateStream,
+        MAX_WINDOW_BITS,
+        MIN_WINDOW_BITS,
+    },
+    IdentityVersion,
+};
+use tokio_tungstenite::WebSocketStream;
+use tungstenite::{
+    handshake::derive_accept_key,
+    protocol::Role,
+    Message,
+};
 
 mod metrics;
+pub mod sse;
 
 use metrics::{
     log_sync_protocol_websockets_total,
+    log_websocket_bytes_out,
     log_websocket_client_timeout,
     log_websocket_closed,
     log_websocket_closed_error_not_reported,
+    log_websocket_compression_negotiated,
     log_websocket_connection_reset,
     log_websocket_message_in,
     log_websocket_message_out,
@@ -87,6 +122,108 @@ const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 /// How long before lack of client response causes a timeout.
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(120);
 
+static DEFLATE_CONFIG: LazyLock<DeflateConfig> = LazyLock::new(|| DeflateConfig {
+    window_bits: (*SYNC_WS_COMPRESSION_WINDOW_BITS).clamp(MIN_WINDOW_BITS, MAX_WINDOW_BITS),
+    threshold: *SYNC_WS_COMPRESSION_THRESHOLD_BYTES,
+});
+
+type SyncSocket = WebSocketStream<DeflateStream<Upgraded>>;
+
+/// The WebSocket handshake for the sync protocol. We handle it ourselves
+/// instead of using axum's `WebSocketUpgrade` so we can negotiate
+/// `permessage-deflate`, which axum and tungstenite don't support.
+struct SyncSocketUpgrade {
+    on_upgrade: OnUpgrade,
+    accept_key: String,
+    /// The negotiated `Sec-WebSocket-Extensions` response header and our
+    /// parameters, if the client offered compression we support.
+    deflate: Option<(String, DeflateParams)>,
+}
+
+fn header_has_token(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .any(|value| value.trim().eq_ignore_ascii_case(token))
+        })
+}
+
+impl SyncSocketUpgrade {
+    fn new(request: &mut Request<Body>) -> anyhow::Result<Self> {
+        let headers = request.headers();
+        anyhow::ensure!(
+            request.method() == Method::GET
+                && header_has_token(headers, header::CONNECTION, "upgrade")
+                && header_has_token(headers, header::UPGRADE, "websocket")
+                && header_has_token(headers, header::SEC_WEBSOCKET_VERSION, "13"),
+            ErrorMetadata::bad_request(
+                "InvalidWebSocketUpgrade",
+                "Expected a WebSocket upgrade request",
+            )
+        );
+        let accept_key = headers
+            .get(header::SEC_WEBSOCKET_KEY)
+            .map(|key| derive_accept_key(key.as_bytes()))
+            .context(ErrorMetadata::bad_request(
+                "InvalidWebSocketUpgrade",
+                "Missing Sec-WebSocket-Key header",
+            ))?;
+        let deflate = if *SYNC_WS_COMPRESSION_ENABLED {
+            headers
+                .get(header::SEC_WEBSOCKET_EXTENSIONS)
+                .and_then(|offer| offer.to_str().ok())
+                .and_then(|offer| DEFLATE_CONFIG.accept_offer(offer))
+        } else {
+            None
+        };
+        Ok(Self {
+            on_upgrade: hyper::upgrade::on(request),
+            accept_key,
+            deflate,
+        })
+    }
+
+    /// Responds to the handshake and runs `callback` on the socket, in a task
+    /// spawned on `rt`, once the connection is upgraded.
+    fn on_upgrade<F, Fut>(self, rt: &ProdRuntime, callback: F) -> anyhow::Result<Response>
+    where
+        F: FnOnce(SyncSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        log_websocket_compression_negotiated(self.deflate.is_some());
+        let mut response = Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_ACCEPT, self.accept_key);
+        let params = match self.deflate {
+            Some((extensions, params)) => {
+                response = response.header(header::SEC_WEBSOCKET_EXTENSIONS, extensions);
+                Some(params)
+            },
+            None => None,
+        };
+        let on_upgrade = self.on_upgrade;
+        rt.spawn("sync_socket", async move {
+            let upgraded = match on_upgrade.await {
+                Ok(upgraded) => upgraded,
+                Err(e) => {
+                    tracing::debug!("Failed to upgrade sync WebSocket: {e}");
+                    return;
+                },
+            };
+            let stream =
+                DeflateStream::new(upgraded, params).with_observer(log_websocket_bytes_out);
+            let socket = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+            callback(socket).await
+        });
+        Ok(response.body(boxed(Body::empty()))?)
+    }
+}
+
 struct SyncSocketDropToken {
     counter_ref: Arc<AtomicU64>,
 }
@@ -122,7 +259,7 @@ impl Drop for SyncSocketDropToken {
 async fn run_sync_socket(
     st: LocalAppState,
     config: SyncWorkerConfig,
-    socket: WebSocket,
+    socket: SyncSocket,
     sentry_scope: sentry::Scope,
 ) {
     let _drop_token = SyncSocketDropToken::new(st.live_ws_count.clone());
@@ -238,28 +375,7 @@ async fn run_sync_socket(
         Ok(..) => None,
         Err(err) => {
             let mut err = err.last_second_classification();
-            // Send a message on the WebSocket before closing it if the sync
-            // worker failed with a "4xx" type error. In this case the client will
-            // assume the error is its fault and not retry.
-            let final_message = err.downcast_ref::<ErrorMetadata>().and_then(|em| {
-                // Special case unauthenticated errors, which want to know the sync worker's
-                // base version.
-                if em.is_unauthenticated() {
-                    let message = ServerMessage::AuthError {
-                        error_message: em.to_string(),
-                        base_version: identity_version,
-                    };
-                    Some(message)
-                }
-                // Otherwise, send a `FatalError` message if it's a user error (not to be retried)
-                else if em.is_deterministic_user_error() {
-                    Some(ServerMessage::FatalError {
-                        error_message: em.to_string(),
-                    })
-                } else {
-                    None
-                }
-            });
+            let final_message = final_server_message(&err, identity_version);
             // Only do a best-effort send of the final application message.
             if let Some(final_message) = final_message {
                 let r: anyhow::Result<_> = try {
@@ -278,12 +394,7 @@ async fn run_sync_socket(
             if let Some(label) = err.metric_server_error_label() {
                 log_websocket_server_error(label);
             }
-            // Convert from tungstenite::Message to axum::Message
-            let close_frame = err.close_frame().map(|cf| CloseFrame {
-                code: cf.code.into(),
-                reason: cf.reason,
-            });
-            Some(Message::Close(close_frame))
+            Some(Message::Close(err.close_frame()))
         },
     };
     // Similarly, only do a best effort send of the close message.
@@ -311,9 +422,38 @@ async fn run_sync_socket(
     log_websocket_closed();
 }
 
+/// The message to send to the client before closing the connection if the
+/// sync worker failed with a "4xx" type error. In this case the client will
+/// assume the error is its fault and not retry.
+fn final_server_message(
+    err: &anyhow::Error,
+    identity_version: Option<IdentityVersion>,
+) -> Option<ServerMessage> {
+    let em = err.downcast_ref::<ErrorMetadata>()?;
+    // Special case unauthenticated errors, which want to know the sync worker's
+    // base version.
+    if em.is_unauthenticated() {
+        Some(ServerMessage::AuthError {
+            error_message: em.to_string(),
+            base_version: identity_version,
+        })
+    }
+    // Otherwise, send a `FatalError` message if it's a user error (not to be retried)
+    else if em.is_deterministic_user_error() {
+        Some(ServerMessage::FatalError {
+            error_message: em.to_string(),
+        })
+    } else {
+        None
+    }
+}
+
 fn new_sync_worker_config(client_version: ClientVersion) -> anyhow::Result<SyncWorkerConfig> {
     match client_version.client() {
-        ClientType::NPM => Ok(SyncWorkerConfig { client_version }),
+        ClientType::NPM => Ok(SyncWorkerConfig {
+            client_version,
+            ..SyncWorkerConfig::default()
+        }),
         ClientType::Rust | ClientType::Unrecognized(_) => Ok(SyncWorkerConfig::default()),
         ClientType::CLI
         | ClientType::Python
@@ -332,34 +472,37 @@ fn new_sync_worker_config(client_version: ClientVersion) -> anyhow::Result<SyncW
 pub async fn sync_client_version_url(
     State(st): State<LocalAppState>,
     ExtractClientVersion(client_version): ExtractClientVersion,
-    ws: WebSocketUpgrade,
+    mut request: Request<Body>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let config = new_sync_worker_config(client_version)?;
+    let ws = SyncSocketUpgrade::new(&mut request)?;
     // Make a copy of the Sentry scope, which contains the request metadata.
     let sentry_scope = sentry::configure_scope(move |s| s.clone());
 
     let upgrade_timer = websocket_upgrade_timer();
-    Ok(ws.on_upgrade(move |ws: WebSocket| {
+    let rt = st.application.runtime();
+    Ok(ws.on_upgrade(&rt, move |ws: SyncSocket| {
         upgrade_timer.finish();
         run_sync_socket(st, config, ws, sentry_scope)
-    }))
+    })?)
 }
 
 pub async fn sync(
     State(st): State<LocalAppState>,
     ExtractClientVersion(client_version): ExtractClientVersion,
-    ws: WebSocketUpgrade,
+    mut request: Request<Body>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let config = new_sync_worker_config(client_version)?;
+    let ws = SyncSocketUpgrade::new(&mut request)?;
     // Make a copy of the Sentry scope, which contains the request metadata.
     let sentry_scope = sentry::configure_scope(move |s| s.clone());
 
     let upgrade_timer = websocket_upgrade_timer();
-    Ok(ws.on_upgrade(move |ws: WebSocket| {
+    let rt = st.application.runtime();
+    Ok(ws.on_upgrade(&rt, move |ws: SyncSocket| {
         upgrade_timer.finish();
-        let monitor = ProdRuntime::task_monitor("sync_socket");
-        monitor.instrument(run_sync_socket(st, config, ws, sentry_scope))
-    }))
+        run_sync_socket(st, config, ws, sentry_scope)
+    })?)
 }
 
 #[cfg(test)]
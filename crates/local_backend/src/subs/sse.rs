@@ -0,0 +1,366 @@
+//! Server-Sent Events transport for the sync protocol, for clients behind
+//! proxies that don't allow WebSockets.
+//!
+//! The client opens a long-lived `GET /api/sync/sse` response, which starts
+//! with a `connected` event carrying a connection ID and then has one
+//! `message` event per [`ServerMessage`]. The client sends its
+//! `ClientMessage`s with `POST /api/sync/sse/:connection_id`. Both halves
+//! feed the same [`SyncWorker`] as the WebSocket transport, so sessions,
+//! reconnects and `maxObservedTimestamp` work the same way.
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
+
+use axum::{
+    extract::State,
+    response::{
+        sse::{
+            Event,
+            KeepAlive,
+            Sse,
+        },
+        IntoResponse,
+    },
+};
+use common::{
+    errors::report_error,
+    http::{
+        extract::{
+            Json,
+            Path,
+        },
+        ExtractClientVersion,
+        HttpResponseError,
+    },
+    runtime::Runtime,
+};
+use errors::{
+    ErrorMetadata,
+    ErrorMetadataAnyhowExt,
+};
+use futures::{
+    channel::{
+        mpsc,
+        oneshot,
+    },
+    future,
+    stream,
+    StreamExt,
+};
+use parking_lot::Mutex;
+use runtime::prod::{
+    ProdInstant,
+    ProdRuntime,
+};
+use serde_json::Value as JsonValue;
+use sync::{
+    worker::{
+        measurable_unbounded_channel,
+        SingleFlightSender,
+    },
+    ServerMessage,
+    SyncWorker,
+    SyncWorkerConfig,
+};
+use sync_types::ClientMessage;
+use uuid::Uuid;
+
+use super::{
+    final_server_message,
+    metrics::{
+        log_sse_closed,
+        log_sse_message_in,
+        log_sse_message_out,
+        log_sse_server_error,
+        log_sync_protocol_sse_total,
+    },
+    new_sync_worker_config,
+    HEARTBEAT_INTERVAL,
+};
+use crate::{
+    EmptyResponse,
+    LocalAppState,
+};
+
+type ClientSender = mpsc::UnboundedSender<(ClientMessage, ProdInstant)>;
+
+/// Channels to the sync workers of open SSE connections, by connection ID.
+#[derive(Clone, Default)]
+pub struct SseConnections {
+    inner: Arc<Mutex<HashMap<Uuid, ClientSender>>>,
+}
+
+impl SseConnections {
+    fn register(&self, sender: ClientSender) -> SseConnectionGuard {
+        let connection_id = Uuid::new_v4();
+        {
+            let mut inner = self.inner.lock();
+            inner.insert(connection_id, sender);
+            log_sync_protocol_sse_total(inner.len());
+        }
+        SseConnectionGuard {
+            connections: self.clone(),
+            connection_id,
+        }
+    }
+
+    fn sender(&self, connection_id: &Uuid) -> Option<ClientSender> {
+        self.inner.lock().get(connection_id).cloned()
+    }
+}
+
+/// Unregisters a connection when its event stream is dropped, e.g. because
+/// the client went away. This closes the sync worker's input, which shuts it
+/// down cleanly.
+struct SseConnectionGuard {
+    connections: SseConnections,
+    connection_id: Uuid,
+}
+
+impl Drop for SseConnectionGuard {
+    fn drop(&mut self) {
+        let mut inner = self.connections.inner.lock();
+        inner.remove(&self.connection_id);
+        log_sync_protocol_sse_total(inner.len());
+    }
+}
+
+fn message_event(message: ServerMessage) -> anyhow::Result<Event> {
+    let serialized = serde_json::to_string(&JsonValue::from(message))?;
+    Ok(Event::default().data(serialized))
+}
+
+async fn run_sync_worker(
+    st: LocalAppState,
+    config: SyncWorkerConfig,
+    client_rx: mpsc::UnboundedReceiver<(ClientMessage, ProdInstant)>,
+    server_tx: SingleFlightSender<ProdRuntime>,
+    close_tx: oneshot::Sender<Vec<anyhow::Result<Event>>>,
+    sentry_scope: sentry::Scope,
+) {
+    let mut sync_worker = SyncWorker::new(st.application.clone(), config, client_rx, server_tx);
+    let result = sync_worker.go().await;
+    let identity_version = sync_worker.identity_version();
+    // Dropping the worker ends the stream of messages, after which the client
+    // gets the closing events.
+    drop(sync_worker);
+
+    let mut close_events = vec![];
+    if let Err(err) = result {
+        let mut err = err.last_second_classification();
+        if let Some(final_message) = final_server_message(&err, Some(identity_version)) {
+            close_events.push(message_event(final_message));
+        }
+        sentry::with_scope(|s| *s = sentry_scope, || report_error(&mut err));
+        if let Some(label) = err.metric_server_error_label() {
+            log_sse_server_error(label);
+        }
+        // Like a WebSocket close frame, tell the client why we're closing the
+        // connection so it can pass the reason along when it reconnects.
+        if let Some(close_frame) = err.close_frame() {
+            close_events.push(Ok(Event::default().event("close").data(close_frame.reason)));
+        }
+    }
+    // The client may have already disconnected.
+    _ = close_tx.send(close_events);
+    log_sse_closed();
+}
+
+pub async fn sync_sse(
+    State(st): State<LocalAppState>,
+    ExtractClientVersion(client_version): ExtractClientVersion,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let config = new_sync_worker_config(client_version)?;
+    // Make a copy of the Sentry scope, which contains the request metadata.
+    let sentry_scope = sentry::configure_scope(move |s| s.clone());
+
+    let (client_tx, client_rx) = mpsc::unbounded();
+    let guard = st.sse_connections.register(client_tx);
+    let (server_tx, server_rx) = measurable_unbounded_channel();
+    let (close_tx, close_rx) = oneshot::channel();
+
+    let connected = Event::default()
+        .event("connected")
+        .data(guard.connection_id.to_string());
+    let rt = st.application.runtime();
+    rt.spawn(
+        "sync_sse",
+        run_sync_worker(st, config, client_rx, server_tx, close_tx, sentry_scope),
+    );
+
+    // The guard lives as long as the response body.
+    let messages = stream::unfold((server_rx, guard), move |(mut server_rx, guard)| {
+        let rt = rt.clone();
+        async move {
+            let (message, send_time) = server_rx.next().await?;
+            log_sse_message_out(&message, rt.monotonic_now() - send_time);
+            Some((message_event(message), (server_rx, guard)))
+        }
+    });
+    let close = close_rx
+        .into_stream()
+        .flat_map(|events| stream::iter(events.unwrap_or_default()));
+    let events = stream::once(future::ready(Ok(connected)))
+        .chain(messages)
+        .chain(close);
+
+    Ok((
+        // Ask reverse proxies like nginx not to buffer the stream.
+        [("X-Accel-Buffering", "no")],
+        Sse::new(events).keep_alive(KeepAlive::new().interval(HEARTBEAT_INTERVAL)),
+    ))
+}
+
+pub async fn sync_sse_send(
+    State(st): State<LocalAppState>,
+    Path(connection_id): Path<Uuid>,
+    Json(body): Json<JsonValue>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let message: ClientMessage = body.try_into().map_err(|e| {
+        anyhow::anyhow!(ErrorMetadata::bad_request(
+            "SyncMessageInvalidJson",
+            format!("Received invalid sync message: {e}"),
+        ))
+    })?;
+    let sent = st.sse_connections.sender(&connection_id).is_some_and(|tx| {
+        tx.unbounded_send((message, st.application.runtime().monotonic_now()))
+            .is_ok()
+    });
+    if !sent {
+        return Err(anyhow::anyhow!(ErrorMetadata::not_found(
+            "SyncConnectionNotFound",
+            format!("Sync connection {connection_id} not found or already closed"),
+        ))
+        .into());
+    }
+    log_sse_message_in();
+    Ok(Json(EmptyResponse {}))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::BoxBody;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use hyper::body::{
+        Body,
+        HttpBody,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    /// Reads the next event from an SSE response, skipping keep-alive
+    /// comments, and returns its type and data.
+    async fn next_event(body: &mut BoxBody, buf: &mut String) -> anyhow::Result<(String, String)> {
+        loop {
+            if let Some(end) = buf.find("\n\n") {
+                let raw: String = buf.drain(..end + 2).collect();
+                let mut event = "message".to_string();
+                let mut data = vec![];
+                for line in raw.lines() {
+                    if let Some(value) = line.strip_prefix("event: ") {
+                        event = value.to_string();
+                    } else if let Some(value) = line.strip_prefix("data: ") {
+                        data.push(value);
+                    }
+                }
+                if data.is_empty() {
+                    continue;
+                }
+                return Ok((event, data.join("\n")));
+            }
+            let chunk = body
+                .data()
+                .await
+                .ok_or_else(|| anyhow::anyhow!("Event stream ended"))??;
+            buf.push_str(std::str::from_utf8(&chunk)?);
+        }
+    }
+
+    fn post_message(connection_id: &str, message: JsonValue) -> anyhow::Result<Request<Body>> {
+        Ok(Request::builder()
+            .uri(format!("/api/sync/sse/{connection_id}"))
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&message)?))?)
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_sync_sse(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+
+        let req = Request::builder()
+            .uri("/api/sync/sse")
+            .method("GET")
+            .body(Body::empty())?;
+        let response = backend.request(req).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body();
+        let mut buf = String::new();
+
+        let (event, connection_id) = next_event(&mut body, &mut buf).await?;
+        assert_eq!(event, "connected");
+
+        let connect = json!({
+            "type": "Connect",
+            "sessionId": "00000000-0000-0000-0000-000000000000",
+            "connectionCount": 0,
+        });
+        backend
+            .expect_success(post_message(&connection_id, connect)?)
+            .await?;
+        let modify_query_set = json!({
+            "type": "ModifyQuerySet",
+            "baseVersion": 0,
+            "newVersion": 1,
+            "modifications": [{
+                "type": "Add",
+                "queryId": 0,
+                "udfPath": "basic:count",
+                "args": [{}],
+            }],
+        });
+        backend
+            .expect_success(post_message(&connection_id, modify_query_set)?)
+            .await?;
+
+        let transition = loop {
+            let (event, data) = next_event(&mut body, &mut buf).await?;
+            assert_eq!(event, "message");
+            let message: JsonValue = serde_json::from_str(&data)?;
+            if message["type"] == "Transition" {
+                break message;
+            }
+        };
+        let modification = &transition["modifications"][0];
+        assert_eq!(modification["type"], "QueryUpdated");
+        assert_eq!(modification["value"], json!(0));
+
+        // Closing the stream closes the connection.
+        drop(body);
+        backend
+            .expect_error(
+                post_message(
+                    &connection_id,
+                    json!({
+                        "type": "Event",
+                        "eventType": "test",
+                        "event": {},
+                    }),
+                )?,
+                StatusCode::NOT_FOUND,
+                "SyncConnectionNotFound",
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -4,7 +4,10 @@ use std::{
 };
 
 use anyhow::Context;
-use axum::headers::Authorization;
+use axum::{
+    headers::Authorization,
+    response::Response,
+};
 use common::{
     http::{
         ConvexHttpService,
@@ -101,6 +104,13 @@ impl TestLocalBackend {
         Ok(())
     }
 
+    /// Sends a request and returns the response as is, e.g. to read a
+    /// streaming body.
+    pub async fn request(&self, req: Request<hyper::Body>) -> anyhow::Result<Response> {
+        tracing::info!("Sending req {req:?}");
+        Ok(self.app.router().clone().oneshot(req).await?)
+    }
+
     pub async fn expect_error(
         &self,
         req: Request<hyper::Body>,
@@ -106,6 +106,40 @@ async fn get_index_diff_with_one_existing_index_that_is_removed_returns_dropped_
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn get_index_diff_with_one_existing_index_made_unique_returns_mutated_index(
+    rt: TestRuntime,
+) -> anyhow::Result<()> {
+    let mut tx = new_tx(rt).await?;
+
+    let index_name = "index";
+    let table_name = "table";
+    let schema_with_index = db_schema_with_indexes!(table_name => [(index_name, vec!["a"])]);
+
+    IndexModel::new(&mut tx)
+        .build_indexes(&schema_with_index)
+        .await?;
+
+    let mut schema_with_unique_index = schema_with_index.clone();
+    for table in schema_with_unique_index.tables.values_mut() {
+        for index in table.indexes.values_mut() {
+            index.unique = true;
+        }
+    }
+
+    let diff = IndexModel::new(&mut tx)
+        .get_index_diff(&schema_with_unique_index.tables)
+        .await?;
+
+    expect_diff!(
+        diff;
+        added: [(table_name, index_name, vec!["a"])],
+        dropped: [(table_name, index_name, vec!["a"])]
+    );
+
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn get_index_diff_with_one_existing_index_when_table_is_removed_returns_dropped_index(
     rt: TestRuntime,
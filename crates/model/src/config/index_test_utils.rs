@@ -92,6 +92,10 @@ macro_rules! db_schema_with_indexes {
                             common::schemas::IndexSchema {
                                 index_descriptor: index_name.descriptor().clone(),
                                 fields: field_paths.try_into()?,
+                                unique: false,
+                                filter: None,
+                                multikey_field: None,
+                                aggregate: None,
                             },
                         );
                     )*
@@ -101,6 +105,8 @@ macro_rules! db_schema_with_indexes {
                         search_indexes: Default::default(),
                         vector_indexes: Default::default(),
                         document_type: None,
+                        ttl: None,
+                        foreign_keys: Default::default(),
                     };
                     tables.insert(table_name, table_def);
                 )*
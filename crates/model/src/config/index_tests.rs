@@ -90,6 +90,8 @@ macro_rules! db_schema_with_search_indexes {
                         search_indexes,
                         vector_indexes: Default::default(),
                         document_type: None,
+                        ttl: None,
+                        foreign_keys: Default::default(),
                     };
                     tables.insert(table_name, table_def);
                 )*
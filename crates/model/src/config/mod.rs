@@ -19,6 +19,7 @@ use std::collections::{
 use anyhow::Context;
 use common::{
     bootstrap_model::schema::SchemaState,
+    knobs::DEPLOYMENT_HISTORY_SIZE,
     runtime::Runtime,
     schemas::DatabaseSchema,
 };
@@ -29,9 +30,11 @@ use database::{
     SchemaModel,
     Transaction,
 };
+use errors::ErrorMetadata;
 use sync_types::CanonicalizedModulePath;
 use value::{
     heap_size::WithHeapSize,
+    id_v6::DocumentIdV6,
     ResolvedDocumentId,
 };
 
@@ -53,12 +56,16 @@ use crate::{
         },
         CronModel,
     },
+    deployment_history::DeploymentHistoryModel,
     modules::{
         module_versions::AnalyzedModule,
         ModuleModel,
     },
     source_packages::{
-        types::SourcePackage,
+        types::{
+            SourcePackage,
+            SourcePackageId,
+        },
         SourcePackageModel,
     },
     udf_config::{
@@ -83,7 +90,7 @@ impl<'a, RT: Runtime> ConfigModel<'a, RT> {
         modules: Vec<ModuleConfig>,
         new_config: UdfConfig,
         source_package: Option<SourcePackage>,
-        mut analyze_results: BTreeMap<CanonicalizedModulePath, AnalyzedModule>,
+        analyze_results: BTreeMap<CanonicalizedModulePath, AnalyzedModule>,
         schema_id: Option<ResolvedDocumentId>,
     ) -> anyhow::Result<(ConfigDiff, Option<DatabaseSchema>)> {
         // TODO: Move this check up to `Application`.
@@ -99,7 +106,102 @@ impl<'a, RT: Runtime> ConfigModel<'a, RT> {
             },
             None => None,
         };
+        self.apply_with_source_package_id(
+            config,
+            modules,
+            new_config,
+            source_package_id,
+            analyze_results,
+            schema_id,
+        )
+        .await
+    }
+
+    /// Restore the modules and config pushed in a retained deployment. The
+    /// deployment's schema must still be the active schema, since rolling
+    /// back can't revalidate existing documents against an older schema.
+    pub async fn rollback(&mut self, deployment_id: DocumentIdV6) -> anyhow::Result<ConfigDiff> {
+        if !(self.tx.identity().is_admin() || self.tx.identity().is_system()) {
+            anyhow::bail!(unauthorized_error("rollback_config"));
+        }
+        let Some(deployment) = DeploymentHistoryModel::new(self.tx)
+            .get(deployment_id)
+            .await?
+        else {
+            anyhow::bail!(ErrorMetadata::not_found(
+                "DeploymentNotFound",
+                format!(
+                    "Deployment {} not found. Only the last {} deployments can be rolled back to.",
+                    deployment_id.encode(),
+                    *DEPLOYMENT_HISTORY_SIZE
+                ),
+            ));
+        };
+        let deployment = deployment.into_value();
+
+        let schema_id = deployment
+            .schema_id
+            .map(|id| id.to_resolved(&self.tx.table_mapping().inject_table_id()))
+            .transpose()?;
+        let active_schema_id = SchemaModel::new(self.tx)
+            .get_by_state(SchemaState::Active)
+            .await?
+            .map(|(id, _schema)| id);
+        if schema_id != active_schema_id {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "RollbackSchemaIncompatible",
+                "The schema has changed since this deployment was pushed, so its functions may \
+                 not match the documents in your tables. Push the schema and functions you want \
+                 with `npx convex deploy` instead.",
+            ));
+        }
+
+        let mut modules = vec![];
+        let mut analyze_results = BTreeMap::new();
+        for module in deployment.modules {
+            let module_id = module
+                .module_id
+                .to_resolved(&self.tx.table_mapping().inject_table_id())?;
+            let module_version = ModuleModel::new(self.tx)
+                .get_version(module_id, module.version)
+                .await?
+                .into_value();
+            if let Some(analyze_result) = module_version.analyze_result {
+                analyze_results.insert(module.path.clone(), analyze_result);
+            }
+            modules.push(ModuleConfig {
+                path: module.path.into(),
+                source: module_version.source,
+                source_map: module_version.source_map,
+                environment: module_version.environment,
+            });
+        }
+        let config = ConfigMetadata {
+            functions: deployment.functions,
+            auth_info: deployment.auth_info,
+        };
+        let (config_diff, _schema) = self
+            .apply_with_source_package_id(
+                config,
+                modules,
+                deployment.udf_config,
+                deployment.source_package_id,
+                analyze_results,
+                schema_id,
+            )
+            .await?;
+        Ok(config_diff)
+    }
 
+    async fn apply_with_source_package_id(
+        &mut self,
+        config: ConfigMetadata,
+        modules: Vec<ModuleConfig>,
+        new_config: UdfConfig,
+        source_package_id: Option<SourcePackageId>,
+        mut analyze_results: BTreeMap<CanonicalizedModulePath, AnalyzedModule>,
+        schema_id: Option<ResolvedDocumentId>,
+    ) -> anyhow::Result<(ConfigDiff, Option<DatabaseSchema>)> {
         // TODO(tom) allow for possibility of crons in multiple files
         let crons_js: CanonicalizedModulePath = "crons.js".parse()?;
         let new_crons: WithHeapSize<BTreeMap<CronIdentifier, CronSpec>> =
@@ -186,6 +288,9 @@ impl<'a, RT: Runtime> ConfigModel<'a, RT> {
         let mut added_modules: BTreeSet<CanonicalizedModulePath> = BTreeSet::new();
 
         // Add new modules.
+        let retained_versions = DeploymentHistoryModel::new(self.tx)
+            .retained_module_versions()
+            .await?;
         let mut remaining_modules: BTreeMap<CanonicalizedModulePath, ModuleConfig> =
             ModuleModel::new(self.tx).get_application_modules().await?;
         for module in modules {
@@ -212,6 +317,7 @@ impl<'a, RT: Runtime> ConfigModel<'a, RT> {
                     module.source_map,
                     analyze_result,
                     module.environment,
+                    &retained_versions,
                 )
                 .await?;
         }
@@ -219,13 +325,20 @@ impl<'a, RT: Runtime> ConfigModel<'a, RT> {
         let mut removed_modules: BTreeSet<CanonicalizedModulePath> = BTreeSet::new();
         for (module_path, _) in remaining_modules {
             removed_modules.insert(module_path.clone());
-            ModuleModel::new(self.tx).delete(module_path).await?;
+            ModuleModel::new(self.tx)
+                .delete(module_path, &retained_versions)
+                .await?;
         }
         let module_diff = ModuleDiff::new(added_modules, removed_modules);
 
         // Update auth info.
-        let auth_diff = AuthInfoModel::new(self.tx).put(config.auth_info).await?;
-        let udf_server_version_diff = UdfConfigModel::new(self.tx).set(new_config).await?;
+        let auth_diff = AuthInfoModel::new(self.tx)
+            .put(config.auth_info.clone())
+            .await?;
+        let udf_server_version_diff = UdfConfigModel::new(self.tx).set(new_config.clone()).await?;
+        DeploymentHistoryModel::new(self.tx)
+            .record(source_package_id, config, new_config, schema_id)
+            .await?;
         let config_diff = ConfigDiff {
             module_diff,
             auth_diff,
@@ -16,10 +16,15 @@ use database::{
     test_helpers::DbFixtures,
     SchemaModel,
 };
+use errors::ErrorMetadataAnyhowExt;
 use keybroker::Identity;
 use maplit::btreemap;
 use runtime::testing::TestRuntime;
-use value::heap_size::WithHeapSize;
+use sync_types::CanonicalizedModulePath;
+use value::{
+    heap_size::WithHeapSize,
+    id_v6::DocumentIdV6,
+};
 
 use crate::{
     auth::AuthInfoModel,
@@ -30,6 +35,7 @@ use crate::{
         },
         ConfigModel,
     },
+    deployment_history::DeploymentHistoryModel,
     modules::module_versions::AnalyzedModule,
     test_helpers::DbFixturesWithModel,
     udf_config::types::UdfConfig,
@@ -286,3 +292,127 @@ async fn test_schema_in_deployment_audit_log(rt: TestRuntime) -> anyhow::Result<
 
     Ok(())
 }
+
+fn analyze_results(modules: &[ModuleConfig]) -> BTreeMap<CanonicalizedModulePath, AnalyzedModule> {
+    modules
+        .iter()
+        .map(|module| {
+            (
+                module.path.clone().canonicalize(),
+                AnalyzedModule {
+                    functions: WithHeapSize::default(),
+                    http_routes: None,
+                    cron_specs: None,
+                    source_mapped: None,
+                },
+            )
+        })
+        .collect()
+}
+
+#[convex_macro::test_runtime]
+async fn test_rollback(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new(&rt.clone()).await?.with_model().await?.db;
+    let module_v1 = ModuleConfig {
+        path: "a.js".parse()?,
+        source: "// v1".to_string(),
+        source_map: None,
+        environment: ModuleEnvironment::Isolate,
+    };
+    let module_v2 = ModuleConfig {
+        source: "// v2".to_string(),
+        ..module_v1.clone()
+    };
+    let module_b = ModuleConfig {
+        path: "b.js".parse()?,
+        source: "// b".to_string(),
+        ..module_v1.clone()
+    };
+    for modules in [
+        vec![module_v1.clone()],
+        vec![module_v2.clone(), module_b.clone()],
+    ] {
+        let mut tx = database.begin(Identity::system()).await?;
+        let analyze_results = analyze_results(&modules);
+        ConfigModel::new(&mut tx)
+            .apply(
+                ConfigMetadata::test_example(),
+                modules,
+                UdfConfig::new_for_test(&rt, "1000.0.0".parse()?),
+                None,
+                analyze_results,
+                None,
+            )
+            .await?;
+        database.commit(tx).await?;
+    }
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let deployments = DeploymentHistoryModel::new(&mut tx).list().await?;
+    assert_eq!(deployments.len(), 2);
+    let first_deployment_id = DocumentIdV6::from(deployments[1].id());
+    let config_diff = ConfigModel::new(&mut tx)
+        .rollback(first_deployment_id)
+        .await?;
+    assert!(config_diff.schema_diff.is_none());
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let (_, modules_read, _) = ConfigModel::new(&mut tx).get().await?;
+    assert_eq!(modules_read, vec![module_v1]);
+    assert_eq!(DeploymentHistoryModel::new(&mut tx).list().await?.len(), 3);
+
+    // The rolled back deployment can still be restored.
+    let second_deployment_id = DocumentIdV6::from(deployments[0].id());
+    ConfigModel::new(&mut tx)
+        .rollback(second_deployment_id)
+        .await?;
+    let (_, modules_read, _) = ConfigModel::new(&mut tx).get().await?;
+    assert_eq!(modules_read, vec![module_v2, module_b]);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_rollback_refuses_changed_schema(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new(&rt.clone()).await?.with_model().await?.db;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    ConfigModel::new(&mut tx)
+        .apply(
+            ConfigMetadata::test_example(),
+            vec![],
+            UdfConfig::new_for_test(&rt, "1000.0.0".parse()?),
+            None,
+            btreemap! {},
+            None,
+        )
+        .await?;
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut model = SchemaModel::new(&mut tx);
+    let (schema_id, _) = model
+        .submit_pending(db_schema!("table1" => DocumentSchema::Any))
+        .await?;
+    model.mark_validated(schema_id).await?;
+    ConfigModel::new(&mut tx)
+        .apply(
+            ConfigMetadata::test_example(),
+            vec![],
+            UdfConfig::new_for_test(&rt, "1000.0.0".parse()?),
+            None,
+            btreemap! {},
+            Some(schema_id),
+        )
+        .await?;
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let deployments = DeploymentHistoryModel::new(&mut tx).list().await?;
+    let err = ConfigModel::new(&mut tx)
+        .rollback(DocumentIdV6::from(deployments[1].id()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "RollbackSchemaIncompatible");
+    Ok(())
+}
@@ -4,18 +4,32 @@ use common::{
     document::{
         ParsedDocument,
         ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
     },
     obj,
+    query::{
+        Cursor,
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
     runtime::Runtime,
-    types::MemberId,
+    types::{
+        IndexName,
+        MemberId,
+    },
 };
 use database::{
+    query::TableFilter,
     unauthorized_error,
+    ResolvedQuery,
     SystemMetadataModel,
     Transaction,
 };
 use value::{
     ConvexObject,
+    ConvexValue,
     FieldPath,
     ResolvedDocumentId,
     TableName,
@@ -23,7 +37,10 @@ use value::{
 
 pub mod types;
 
-use types::DeploymentAuditLogEvent;
+use types::{
+    DeploymentAuditLog,
+    DeploymentAuditLogEvent,
+};
 
 use crate::{
     SystemIndex,
@@ -54,6 +71,39 @@ impl SystemTable for DeploymentAuditLogsTable {
     }
 }
 
+/// The most entries read to fill one page of [`DeploymentAuditLogModel::list`],
+/// so that selective filters over a long history stay within transaction
+/// limits.
+const MAX_AUDIT_LOG_ENTRIES_SCANNED: usize = 1024;
+
+/// Restricts the entries returned by [`DeploymentAuditLogModel::list`]. Unset
+/// fields match every entry.
+#[derive(Debug, Clone, Default)]
+pub struct DeploymentAuditLogFilter {
+    pub action: Option<String>,
+    pub member_id: Option<MemberId>,
+    /// Inclusive lower bound on the entry's creation time, in milliseconds.
+    pub start_ms: Option<f64>,
+    /// Inclusive upper bound on the entry's creation time, in milliseconds.
+    pub end_ms: Option<f64>,
+}
+
+impl DeploymentAuditLogFilter {
+    fn matches(&self, entry: &DeploymentAuditLog) -> bool {
+        if let Some(action) = &self.action
+            && entry.event.action() != action
+        {
+            return false;
+        }
+        if let Some(member_id) = self.member_id
+            && entry.member_id != Some(member_id)
+        {
+            return false;
+        }
+        true
+    }
+}
+
 pub struct DeploymentAuditLogModel<'a, RT: Runtime> {
     tx: &'a mut Transaction<RT>,
 }
@@ -103,6 +153,67 @@ impl<'a, RT: Runtime> DeploymentAuditLogModel<'a, RT> {
         Ok(deployment_audit_log_ids)
     }
 
+    /// Returns up to `limit` entries matching `filter`, newest first, after
+    /// `cursor` if it's set. Also returns the cursor to pass in for the next
+    /// page, or `None` if there are no more entries. Pages may be short when
+    /// few entries match the filter.
+    ///
+    /// The cursor is a position in the `by_creation_time` index, which orders
+    /// entries created in the same millisecond by ID, so the next page
+    /// resumes right after the last entry scanned.
+    pub async fn list(
+        &mut self,
+        filter: &DeploymentAuditLogFilter,
+        cursor: Option<Cursor>,
+        limit: usize,
+    ) -> anyhow::Result<(Vec<ParsedDocument<DeploymentAuditLog>>, Option<Cursor>)> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("list_deployment_audit_log"));
+        }
+        let mut range = vec![];
+        if let Some(start_ms) = filter.start_ms {
+            range.push(IndexRangeExpression::Gte(
+                CREATION_TIME_FIELD_PATH.clone(),
+                ConvexValue::from(start_ms),
+            ));
+        }
+        if let Some(end_ms) = filter.end_ms {
+            range.push(IndexRangeExpression::Lte(
+                CREATION_TIME_FIELD_PATH.clone(),
+                ConvexValue::from(end_ms),
+            ));
+        }
+        let query = Query::index_range(IndexRange {
+            index_name: IndexName::by_creation_time(DEPLOYMENT_AUDIT_LOG_TABLE.clone()),
+            range,
+            order: Order::Desc,
+        });
+        let mut query_stream = ResolvedQuery::new_bounded(
+            self.tx,
+            query,
+            cursor,
+            None,
+            None,
+            None,
+            false,
+            None,
+            TableFilter::IncludePrivateSystemTables,
+        )?;
+        let mut entries = vec![];
+        let mut scanned = 0;
+        while entries.len() < limit && scanned < MAX_AUDIT_LOG_ENTRIES_SCANNED {
+            let Some(document) = query_stream.next(self.tx, None).await? else {
+                return Ok((entries, None));
+            };
+            scanned += 1;
+            let entry: ParsedDocument<DeploymentAuditLog> = document.try_into()?;
+            if filter.matches(&entry) {
+                entries.push(entry);
+            }
+        }
+        Ok((entries, query_stream.cursor()))
+    }
+
     #[cfg(any(test, feature = "testing"))]
     pub async fn insert_single(
         &mut self,
@@ -4,6 +4,7 @@ use std::{
     sync::LazyLock,
 };
 
+use anyhow::Context;
 use common::{
     bootstrap_model::index::DeveloperIndexConfig,
     log_streaming::{
@@ -11,13 +12,17 @@ use common::{
         StructuredLogEvent,
     },
     runtime::UnixTimestamp,
-    types::IndexName,
+    types::{
+        IndexName,
+        MemberId,
+    },
 };
 use database::LegacyIndexDiff;
 #[cfg(any(test, feature = "testing"))]
 use proptest::prelude::*;
 use serde_json::Value as JsonValue;
 use value::{
+    id_v6::DocumentIdV6,
     obj,
     remove_int64,
     remove_object,
@@ -33,6 +38,7 @@ use crate::{
     backend_state::types::BackendState,
     config::types::ConfigDiff,
     environment_variables::types::EnvVarName,
+    exports::types::ExportFormat,
     snapshot_imports::types::{
         ImportFormat,
         ImportMode,
@@ -64,9 +70,16 @@ pub enum DeploymentAuditLogEvent {
         previous_name: EnvVarName,
         name: EnvVarName,
     },
+    RevealEnvironmentVariable {
+        name: EnvVarName,
+    },
     PushConfig {
         config_diff: ConfigDiff,
     },
+    RollbackConfig {
+        deployment_id: DocumentIdV6,
+        config_diff: ConfigDiff,
+    },
     BuildIndexes {
         #[cfg_attr(
             any(test, feature = "testing"),
@@ -94,6 +107,22 @@ pub enum DeploymentAuditLogEvent {
         import_mode: ImportMode,
         import_format: ImportFormat,
     },
+    RequestImport {
+        import_id: DocumentIdV6,
+        import_mode: ImportMode,
+        import_format: ImportFormat,
+    },
+    RequestExport {
+        export_format: ExportFormat,
+    },
+    DeleteTables {
+        table_names: Vec<TableName>,
+        document_count: u64,
+    },
+    CancelAllJobs {
+        udf_path: Option<String>,
+        job_count: u64,
+    },
 }
 
 impl From<LegacyIndexDiff> for DeploymentAuditLogEvent {
@@ -137,11 +166,19 @@ impl DeploymentAuditLogEvent {
             DeploymentAuditLogEvent::ReplaceEnvironmentVariable { .. } => {
                 "replace_environment_variable"
             },
+            DeploymentAuditLogEvent::RevealEnvironmentVariable { .. } => {
+                "reveal_environment_variable"
+            },
             DeploymentAuditLogEvent::PushConfig { .. } => "push_config",
+            DeploymentAuditLogEvent::RollbackConfig { .. } => "rollback_config",
             DeploymentAuditLogEvent::BuildIndexes { .. } => "build_indexes",
             DeploymentAuditLogEvent::ChangeDeploymentState { .. } => "change_deployment_state",
             DeploymentAuditLogEvent::SnapshotImport { .. } => "snapshot_import",
             DeploymentAuditLogEvent::ClearTables => "clear_tables",
+            DeploymentAuditLogEvent::RequestImport { .. } => "request_import",
+            DeploymentAuditLogEvent::RequestExport { .. } => "request_export",
+            DeploymentAuditLogEvent::DeleteTables { .. } => "delete_tables",
+            DeploymentAuditLogEvent::CancelAllJobs { .. } => "cancel_all_jobs",
         }
     }
 
@@ -149,7 +186,8 @@ impl DeploymentAuditLogEvent {
         match self {
             DeploymentAuditLogEvent::CreateEnvironmentVariable { name }
             | DeploymentAuditLogEvent::UpdateEnvironmentVariable { name }
-            | DeploymentAuditLogEvent::DeleteEnvironmentVariable { name } => {
+            | DeploymentAuditLogEvent::DeleteEnvironmentVariable { name }
+            | DeploymentAuditLogEvent::RevealEnvironmentVariable { name } => {
                 obj!("variable_name" => name.to_string())
             },
             DeploymentAuditLogEvent::ReplaceEnvironmentVariable {
@@ -161,6 +199,11 @@ impl DeploymentAuditLogEvent {
             DeploymentAuditLogEvent::PushConfig { config_diff } => {
                 ConvexObject::try_from(config_diff)
             },
+            DeploymentAuditLogEvent::RollbackConfig {
+                deployment_id,
+                config_diff,
+            } => ConvexObject::try_from(config_diff)?
+                .shallow_merge(obj!("deployment_id" => deployment_id.encode())?),
             DeploymentAuditLogEvent::BuildIndexes {
                 added_indexes,
                 removed_indexes,
@@ -226,6 +269,47 @@ impl DeploymentAuditLogEvent {
                 )
             },
             DeploymentAuditLogEvent::ClearTables => obj!(),
+            DeploymentAuditLogEvent::RequestImport {
+                import_id,
+                import_mode,
+                import_format,
+            } => {
+                obj!(
+                    "import_id" => import_id.encode(),
+                    "import_mode" => import_mode.to_string(),
+                    "import_format" => ConvexValue::Object(import_format.try_into()?)
+                )
+            },
+            DeploymentAuditLogEvent::RequestExport { export_format } => {
+                obj!("export_format" => ConvexValue::try_from(export_format)?)
+            },
+            DeploymentAuditLogEvent::DeleteTables {
+                table_names,
+                document_count,
+            } => {
+                let table_names: Vec<_> = table_names
+                    .into_iter()
+                    .map(|table_name| {
+                        anyhow::Ok(ConvexValue::String(table_name.to_string().try_into()?))
+                    })
+                    .try_collect()?;
+                obj!(
+                    "table_names" => table_names,
+                    "document_count" => document_count as i64
+                )
+            },
+            DeploymentAuditLogEvent::CancelAllJobs {
+                udf_path,
+                job_count,
+            } => {
+                obj!(
+                    "udf_path" => match udf_path {
+                        Some(udf_path) => ConvexValue::try_from(udf_path)?,
+                        None => ConvexValue::Null,
+                    },
+                    "job_count" => job_count as i64
+                )
+            },
         }
     }
 
@@ -288,9 +372,16 @@ impl TryFrom<ConvexObject> for DeploymentAuditLogEvent {
                 previous_name: remove_string(&mut fields, "previous_variable_name")?.parse()?,
                 name: remove_string(&mut fields, "variable_name")?.parse()?,
             },
+            "reveal_environment_variable" => DeploymentAuditLogEvent::RevealEnvironmentVariable {
+                name: remove_string(&mut fields, "variable_name")?.parse()?,
+            },
             "push_config" => DeploymentAuditLogEvent::PushConfig {
                 config_diff: ConvexObject::try_from(fields)?.try_into()?,
             },
+            "rollback_config" => DeploymentAuditLogEvent::RollbackConfig {
+                deployment_id: DocumentIdV6::decode(&remove_string(&mut fields, "deployment_id")?)?,
+                config_diff: ConvexObject::try_from(fields)?.try_into()?,
+            },
             "build_indexes" => {
                 let added_indexes = remove_vec(&mut fields, "added_indexes")?
                     .into_iter()
@@ -322,12 +413,66 @@ impl TryFrom<ConvexObject> for DeploymentAuditLogEvent {
                     import_format: remove_object(&mut fields, "import_format")?,
                 }
             },
+            "request_import" => DeploymentAuditLogEvent::RequestImport {
+                import_id: DocumentIdV6::decode(&remove_string(&mut fields, "import_id")?)?,
+                import_mode: remove_string(&mut fields, "import_mode")?.parse()?,
+                import_format: remove_object(&mut fields, "import_format")?,
+            },
+            "request_export" => DeploymentAuditLogEvent::RequestExport {
+                export_format: fields
+                    .remove("export_format")
+                    .context("Missing export_format")?
+                    .try_into()?,
+            },
+            "delete_tables" => {
+                let table_names = remove_vec_of_strings(&mut fields, "table_names")?
+                    .iter()
+                    .map(|s| TableName::from_str(s))
+                    .try_collect()?;
+                DeploymentAuditLogEvent::DeleteTables {
+                    table_names,
+                    document_count: remove_int64(&mut fields, "document_count")? as u64,
+                }
+            },
+            "cancel_all_jobs" => DeploymentAuditLogEvent::CancelAllJobs {
+                udf_path: match fields.remove("udf_path") {
+                    Some(ConvexValue::String(udf_path)) => Some(udf_path.into()),
+                    None | Some(ConvexValue::Null) => None,
+                    v => anyhow::bail!("Invalid udf_path for cancel_all_jobs: {v:?}"),
+                },
+                job_count: remove_int64(&mut fields, "job_count")? as u64,
+            },
             _ => anyhow::bail!("action {action} unrecognized"),
         };
         Ok(event)
     }
 }
 
+/// An event as stored in `_deployment_audit_log`, along with the member who
+/// caused it.
+#[derive(Debug, Clone)]
+pub struct DeploymentAuditLog {
+    pub event: DeploymentAuditLogEvent,
+    /// `None` for events caused by the system or by a deploy key.
+    pub member_id: Option<MemberId>,
+}
+
+impl TryFrom<ConvexObject> for DeploymentAuditLog {
+    type Error = anyhow::Error;
+
+    fn try_from(obj: ConvexObject) -> anyhow::Result<Self> {
+        let member_id = match obj.get("member_id") {
+            Some(ConvexValue::Int64(member_id)) => Some(MemberId(*member_id as u64)),
+            None | Some(ConvexValue::Null) => None,
+            v => anyhow::bail!("Invalid member_id for DeploymentAuditLog: {v:?}"),
+        };
+        Ok(Self {
+            event: obj.try_into()?,
+            member_id,
+        })
+    }
+}
+
 impl TryFrom<DeploymentAuditLogEvent> for serde_json::Map<String, JsonValue> {
     type Error = anyhow::Error;
 
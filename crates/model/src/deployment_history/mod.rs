@@ -0,0 +1,231 @@
+use std::{
+    collections::BTreeSet,
+    sync::LazyLock,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    knobs::DEPLOYMENT_HISTORY_SIZE,
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    id_v6::DocumentIdV6,
+    ResolvedDocumentId,
+    TableName,
+};
+
+use crate::{
+    config::types::ConfigMetadata,
+    deployment_history::types::{
+        DeployedModule,
+        Deployment,
+    },
+    modules::{
+        module_versions::ModuleVersion,
+        types::ModuleMetadata,
+        ModuleModel,
+    },
+    source_packages::{
+        types::SourcePackageId,
+        SourcePackageModel,
+    },
+    udf_config::types::UdfConfig,
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+/// Module versions referenced by retained deployments.
+#[derive(Default)]
+pub struct RetainedModuleVersions(BTreeSet<(DocumentIdV6, ModuleVersion)>);
+
+impl RetainedModuleVersions {
+    pub fn contains(&self, module_id: ResolvedDocumentId, version: ModuleVersion) -> bool {
+        self.0.contains(&(DocumentIdV6::from(module_id), version))
+    }
+}
+
+pub static DEPLOYMENT_HISTORY_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_deployment_history"
+        .parse()
+        .expect("Invalid built-in deployment history table")
+});
+
+pub struct DeploymentHistoryTable;
+impl SystemTable for DeploymentHistoryTable {
+    fn table_name(&self) -> &'static TableName {
+        &DEPLOYMENT_HISTORY_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<Deployment>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct DeploymentHistoryModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> DeploymentHistoryModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Record the currently pushed modules and config as the newest
+    /// deployment, forgetting the oldest deployments past
+    /// `DEPLOYMENT_HISTORY_SIZE`.
+    pub async fn record(
+        &mut self,
+        source_package_id: Option<SourcePackageId>,
+        config: ConfigMetadata,
+        udf_config: UdfConfig,
+        schema_id: Option<ResolvedDocumentId>,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let modules = ModuleModel::new(self.tx)
+            .get_all_metadata()
+            .await?
+            .into_iter()
+            .filter(|metadata| !metadata.path.is_system())
+            .map(|metadata| DeployedModule {
+                module_id: metadata.id().into(),
+                path: metadata.path.clone(),
+                version: metadata.latest_version,
+            })
+            .collect();
+        let deployment = Deployment {
+            source_package_id,
+            modules,
+            udf_config,
+            functions: config.functions,
+            auth_info: config.auth_info,
+            schema_id: schema_id.map(DocumentIdV6::from),
+        };
+        let id = SystemMetadataModel::new(self.tx)
+            .insert(&DEPLOYMENT_HISTORY_TABLE, deployment.try_into()?)
+            .await?;
+        self.prune().await?;
+        Ok(id)
+    }
+
+    /// Returns the retained deployments, newest first.
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<Deployment>>> {
+        let query = Query::full_table_scan(DEPLOYMENT_HISTORY_TABLE.clone(), Order::Desc);
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        let mut deployments = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            deployments.push(document.try_into()?);
+        }
+        Ok(deployments)
+    }
+
+    pub async fn get(
+        &mut self,
+        id: DocumentIdV6,
+    ) -> anyhow::Result<Option<ParsedDocument<Deployment>>> {
+        let table_number = self
+            .tx
+            .table_mapping()
+            .id(&DEPLOYMENT_HISTORY_TABLE)?
+            .table_number;
+        if *id.table() != table_number {
+            return Ok(None);
+        }
+        let id = id.to_resolved(&self.tx.table_mapping().inject_table_id())?;
+        self.tx.get(id).await?.map(TryInto::try_into).transpose()
+    }
+
+    /// The module versions that retained deployments can still be rolled
+    /// back to, which must be kept after being replaced. Load this once and
+    /// reuse it when replacing many modules.
+    pub async fn retained_module_versions(&mut self) -> anyhow::Result<RetainedModuleVersions> {
+        let versions = self
+            .list()
+            .await?
+            .iter()
+            .flat_map(|deployment| deployment.modules.iter())
+            .map(|module| (module.module_id, module.version))
+            .collect();
+        Ok(RetainedModuleVersions(versions))
+    }
+
+    /// Delete the deployments past `DEPLOYMENT_HISTORY_SIZE`, along with the
+    /// replaced module versions and source packages only they referenced.
+    async fn prune(&mut self) -> anyhow::Result<()> {
+        let mut deployments = self.list().await?;
+        let retained_count = (*DEPLOYMENT_HISTORY_SIZE).max(1);
+        if deployments.len() <= retained_count {
+            return Ok(());
+        }
+        let evicted = deployments.split_off(retained_count);
+
+        let retained_modules: BTreeSet<_> = deployments
+            .iter()
+            .flat_map(|deployment| deployment.modules.iter())
+            .map(|module| (module.module_id, module.version))
+            .collect();
+        let retained_source_packages: BTreeSet<_> = deployments
+            .iter()
+            .filter_map(|deployment| deployment.source_package_id)
+            .collect();
+        let mut unreferenced_modules = BTreeSet::new();
+        let mut unreferenced_source_packages = BTreeSet::new();
+        for deployment in evicted {
+            SystemMetadataModel::new(self.tx)
+                .delete(deployment.id())
+                .await?;
+            let deployment = deployment.into_value();
+            for module in deployment.modules {
+                let key = (module.module_id, module.version);
+                if !retained_modules.contains(&key) {
+                    unreferenced_modules.insert(key);
+                }
+            }
+            if let Some(source_package_id) = deployment.source_package_id
+                && !retained_source_packages.contains(&source_package_id)
+            {
+                unreferenced_source_packages.insert(source_package_id);
+            }
+        }
+
+        for (module_id, version) in unreferenced_modules {
+            let module_id = module_id.to_resolved(&self.tx.table_mapping().inject_table_id())?;
+            // The module's latest version is still in use.
+            if let Some(document) = self.tx.get(module_id).await? {
+                let metadata: ParsedDocument<ModuleMetadata> = document.try_into()?;
+                if metadata.latest_version == version {
+                    continue;
+                }
+            }
+            let module_version_id = ModuleModel::new(self.tx)
+                .get_version(module_id, version)
+                .await?
+                .id();
+            SystemMetadataModel::new(self.tx)
+                .delete(module_version_id)
+                .await?;
+        }
+        for source_package_id in unreferenced_source_packages {
+            SourcePackageModel::new(self.tx)
+                .delete(source_package_id)
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+use common::{
+    auth::AuthInfo,
+    obj,
+};
+use sync_types::{
+    CanonicalizedModulePath,
+    ModulePath,
+};
+use value::{
+    id_v6::DocumentIdV6,
+    remove_int64,
+    remove_object,
+    remove_string,
+    remove_vec,
+    ConvexObject,
+    ConvexValue,
+};
+
+use crate::{
+    auth::types::AuthInfoPersisted,
+    modules::module_versions::ModuleVersion,
+    source_packages::types::SourcePackageId,
+    udf_config::types::UdfConfig,
+};
+
+/// A module as it was pushed in a [`Deployment`]. Module versions referenced
+/// by a retained deployment are kept around after they are replaced, so the
+/// deployment can be rolled back to.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct DeployedModule {
+    pub path: CanonicalizedModulePath,
+    pub module_id: DocumentIdV6,
+    pub version: ModuleVersion,
+}
+
+impl TryFrom<DeployedModule> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(module: DeployedModule) -> anyhow::Result<Self> {
+        obj!(
+            "path" => String::from(module.path),
+            "moduleId" => module.module_id,
+            "version" => module.version,
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for DeployedModule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> anyhow::Result<Self> {
+        let mut fields = BTreeMap::from(value);
+        let module_id = match fields.remove("moduleId") {
+            Some(module_id) => module_id.try_into()?,
+            v => anyhow::bail!("Invalid moduleId field for DeployedModule: {v:?}"),
+        };
+        Ok(Self {
+            path: remove_string(&mut fields, "path")?
+                .parse::<ModulePath>()?
+                .canonicalize(),
+            module_id,
+            version: remove_int64(&mut fields, "version")?,
+        })
+    }
+}
+
+/// A single push of functions and config, stored in `_deployment_history`.
+#[derive(Debug, Clone)]
+pub struct Deployment {
+    pub source_package_id: Option<SourcePackageId>,
+    pub modules: Vec<DeployedModule>,
+    pub udf_config: UdfConfig,
+    /// The local directory on the client containing modules.
+    pub functions: String,
+    pub auth_info: Vec<AuthInfo>,
+    pub schema_id: Option<DocumentIdV6>,
+}
+
+impl TryFrom<Deployment> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(deployment: Deployment) -> anyhow::Result<Self> {
+        let modules = deployment
+            .modules
+            .into_iter()
+            .map(|module| anyhow::Ok(ConvexValue::Object(module.try_into()?)))
+            .try_collect::<Vec<_>>()?;
+        let auth_info = deployment
+            .auth_info
+            .into_iter()
+            .map(|info| anyhow::Ok(ConvexValue::Object(AuthInfoPersisted(info).try_into()?)))
+            .try_collect::<Vec<_>>()?;
+        obj!(
+            "sourcePackageId" => deployment
+                .source_package_id
+                .map(ConvexValue::from)
+                .unwrap_or(ConvexValue::Null),
+            "modules" => modules,
+            "udfConfig" => ConvexObject::try_from(deployment.udf_config)?,
+            "functions" => deployment.functions,
+            "authInfo" => auth_info,
+            "schemaId" => deployment
+                .schema_id
+                .map(ConvexValue::from)
+                .unwrap_or(ConvexValue::Null),
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for Deployment {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> anyhow::Result<Self> {
+        let mut fields = BTreeMap::from(value);
+        let source_package_id = match fields.remove("sourcePackageId") {
+            Some(ConvexValue::Null) | None => None,
+            Some(id) => Some(id.try_into()?),
+        };
+        let modules = remove_vec(&mut fields, "modules")?
+            .into_iter()
+            .map(|value| DeployedModule::try_from(ConvexObject::try_from(value)?))
+            .try_collect()?;
+        let auth_info = remove_vec(&mut fields, "authInfo")?
+            .into_iter()
+            .map(|value| {
+                let AuthInfoPersisted(info) = ConvexObject::try_from(value)?.try_into()?;
+                anyhow::Ok(info)
+            })
+            .try_collect()?;
+        let schema_id = match fields.remove("schemaId") {
+            Some(ConvexValue::Null) | None => None,
+            Some(id) => Some(id.try_into()?),
+        };
+        Ok(Self {
+            source_package_id,
+            modules,
+            udf_config: remove_object(&mut fields, "udfConfig")?,
+            functions: remove_string(&mut fields, "functions")?,
+            auth_info,
+            schema_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::testing::assert_roundtrips;
+    use proptest::prelude::*;
+    use value::ConvexObject;
+
+    use super::DeployedModule;
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+        #[test]
+        fn test_deployed_module_roundtrips(v in any::<DeployedModule>()) {
+            assert_roundtrips::<DeployedModule, ConvexObject>(v);
+        }
+    }
+}
@@ -35,6 +35,7 @@ use database::{
     Transaction,
 };
 use errors::ErrorMetadata;
+use keybroker::KeyBroker;
 use value::{
     ConvexValue,
     FieldPath,
@@ -48,6 +49,7 @@ use crate::{
         EnvVarName,
         EnvVarValue,
         EnvironmentVariable,
+        PersistedEnvVarValue,
         PersistedEnvironmentVariable,
     },
     SystemIndex,
@@ -91,6 +93,7 @@ pub struct EnvironmentVariablesModel<'a, RT: Runtime> {
 
 pub struct PreloadedEnvironmentVariables {
     range: PreloadedIndexRange,
+    key_broker: KeyBroker,
 }
 
 impl PreloadedEnvironmentVariables {
@@ -104,9 +107,8 @@ impl PreloadedEnvironmentVariables {
             return Ok(None);
         };
         let doc: ParsedDocument<PersistedEnvironmentVariable> = doc.clone().try_into()?;
-        let var = doc.into_value().0;
-        anyhow::ensure!(var.name() == name, "Invalid environment variable");
-        Ok(Some(var.into_value()))
+        anyhow::ensure!(&doc.name == name, "Invalid environment variable");
+        Ok(Some(doc.value.decrypt(&self.key_broker)?))
     }
 }
 
@@ -115,50 +117,88 @@ impl<'a, RT: Runtime> EnvironmentVariablesModel<'a, RT> {
         Self { tx }
     }
 
-    pub async fn preload(&mut self) -> anyhow::Result<PreloadedEnvironmentVariables> {
+    pub async fn preload(
+        &mut self,
+        key_broker: &KeyBroker,
+    ) -> anyhow::Result<PreloadedEnvironmentVariables> {
         let range = self
             .tx
             .preload_index_range(&ENVIRONMENT_VARIABLES_INDEX_BY_NAME, &Interval::all())
             .await?;
-        Ok(PreloadedEnvironmentVariables { range })
+        Ok(PreloadedEnvironmentVariables {
+            range,
+            key_broker: key_broker.clone(),
+        })
     }
 
-    pub async fn get(
+    /// Gets the stored variable without decrypting its value.
+    pub async fn get_persisted(
         &mut self,
         name: &EnvVarName,
-    ) -> anyhow::Result<Option<ParsedDocument<EnvironmentVariable>>> {
+    ) -> anyhow::Result<Option<ParsedDocument<PersistedEnvironmentVariable>>> {
         let query = value_query_from_env_var(name)?;
         let mut query_stream = ResolvedQuery::new(self.tx, query)?;
         query_stream
             .expect_at_most_one(self.tx)
             .await?
-            .map(|doc| {
-                let doc: ParsedDocument<PersistedEnvironmentVariable> = doc.try_into()?;
-                doc.map(|doc| Ok(doc.0))
-            })
+            .map(ParsedDocument::try_from)
             .transpose()
     }
 
-    pub async fn get_by_id_legacy(
+    pub async fn get(
+        &mut self,
+        name: &EnvVarName,
+        key_broker: &KeyBroker,
+    ) -> anyhow::Result<Option<ParsedDocument<EnvironmentVariable>>> {
+        self.get_persisted(name)
+            .await?
+            .map(|doc| doc.map(|env_var| env_var.decrypt(key_broker)))
+            .transpose()
+    }
+
+    pub async fn get_name_by_id_legacy(
         &mut self,
         id: ResolvedDocumentId,
-    ) -> anyhow::Result<Option<EnvironmentVariable>> {
+    ) -> anyhow::Result<Option<EnvVarName>> {
         let Some(doc) = self.tx.get(id).await? else {
             return Ok(None);
         };
         let persisted: ParsedDocument<PersistedEnvironmentVariable> = doc.try_into()?;
-        Ok(Some(persisted.into_value().0))
+        Ok(Some(persisted.into_value().name))
     }
 
-    #[minitrace::trace]
-    pub async fn get_all(&mut self) -> anyhow::Result<BTreeMap<EnvVarName, EnvVarValue>> {
+    async fn get_all_persisted(
+        &mut self,
+    ) -> anyhow::Result<Vec<ParsedDocument<PersistedEnvironmentVariable>>> {
         let query = Query::full_table_scan(ENVIRONMENT_VARIABLES_TABLE.clone(), Order::Asc);
         let mut query_stream = ResolvedQuery::new(self.tx, query)?;
-        let mut environment_variables = BTreeMap::new();
+        let mut environment_variables = vec![];
         while let Some(doc) = query_stream.next(self.tx, None).await? {
-            let env_var: ParsedDocument<PersistedEnvironmentVariable> = doc.try_into()?;
+            environment_variables.push(doc.try_into()?);
+        }
+        Ok(environment_variables)
+    }
+
+    /// Returns the names of all environment variables, without decrypting
+    /// their values.
+    pub async fn get_all_names(&mut self) -> anyhow::Result<Vec<EnvVarName>> {
+        Ok(self
+            .get_all_persisted()
+            .await?
+            .into_iter()
+            .map(|env_var| env_var.into_value().name)
+            .collect())
+    }
+
+    #[minitrace::trace]
+    pub async fn get_all(
+        &mut self,
+        key_broker: &KeyBroker,
+    ) -> anyhow::Result<BTreeMap<EnvVarName, EnvVarValue>> {
+        let mut environment_variables = BTreeMap::new();
+        for env_var in self.get_all_persisted().await? {
             let old_value = environment_variables
-                .insert(env_var.0.name().to_owned(), env_var.0.value().to_owned());
+                .insert(env_var.name.clone(), env_var.value.decrypt(key_broker)?);
             anyhow::ensure!(old_value.is_none(), "Duplicate environment variable");
         }
         Ok(environment_variables)
@@ -168,6 +208,7 @@ impl<'a, RT: Runtime> EnvironmentVariablesModel<'a, RT> {
         &mut self,
         env_var: EnvironmentVariable,
         forbidden_names: &HashSet<EnvVarName>,
+        key_broker: &KeyBroker,
     ) -> anyhow::Result<ResolvedDocumentId> {
         if forbidden_names.contains(env_var.name()) {
             anyhow::bail!(env_var_name_forbidden(env_var.name()));
@@ -175,26 +216,53 @@ impl<'a, RT: Runtime> EnvironmentVariablesModel<'a, RT> {
         SystemMetadataModel::new(self.tx)
             .insert(
                 &ENVIRONMENT_VARIABLES_TABLE,
-                PersistedEnvironmentVariable(env_var).try_into()?,
+                PersistedEnvironmentVariable::encrypt(&env_var, key_broker).try_into()?,
             )
             .await
     }
 
-    pub async fn delete(
-        &mut self,
-        name: &EnvVarName,
-    ) -> anyhow::Result<Option<EnvironmentVariable>> {
-        let Some(doc) = self.get(name).await? else {
-            return Ok(None);
+    /// Deletes the variable, returning whether it existed.
+    pub async fn delete(&mut self, name: &EnvVarName) -> anyhow::Result<bool> {
+        let Some(doc) = self.get_persisted(name).await? else {
+            return Ok(false);
         };
-        let document = SystemMetadataModel::new(self.tx).delete(doc.id()).await?;
-        let env_var: ParsedDocument<PersistedEnvironmentVariable> = document.try_into()?;
-        Ok(Some(env_var.into_value().0))
+        SystemMetadataModel::new(self.tx).delete(doc.id()).await?;
+        Ok(true)
+    }
+
+    /// Encrypts the values stored before encryption at rest, returning how many
+    /// were encrypted.
+    ///
+    /// This only writes new, encrypted revisions. The plaintext revisions stay
+    /// in the document log until retention deletes them, which happens after
+    /// `DOCUMENT_RETENTION_DELAY` and never while `DOCUMENT_RETENTION_DRY_RUN`
+    /// is set, and in any snapshot export or persistence backup taken before.
+    /// Rotate secrets that were stored in plaintext if that matters.
+    pub async fn encrypt_plaintext_values(
+        &mut self,
+        key_broker: &KeyBroker,
+    ) -> anyhow::Result<usize> {
+        let mut count = 0;
+        for env_var in self.get_all_persisted().await? {
+            let PersistedEnvVarValue::Plaintext(value) = &env_var.value else {
+                continue;
+            };
+            let encrypted = PersistedEnvironmentVariable {
+                name: env_var.name.clone(),
+                value: PersistedEnvVarValue::encrypt(value, key_broker),
+            };
+            SystemMetadataModel::new(self.tx)
+                .replace(env_var.id(), encrypted.try_into()?)
+                .await?;
+            count += 1;
+        }
+        Ok(count)
     }
 
     pub async fn edit(
         &mut self,
         changes: HashMap<ResolvedDocumentId, EnvironmentVariable>,
+        key_broker: &KeyBroker,
     ) -> anyhow::Result<Vec<DeploymentAuditLogEvent>> {
         let mut audit_events = vec![];
 
@@ -221,7 +289,7 @@ impl<'a, RT: Runtime> EnvironmentVariablesModel<'a, RT> {
             })?;
 
             // Ensure there is no conflict with an environment variable not in this change
-            let maybe_env_var_with_name = self.get(&new_env_var_name).await?;
+            let maybe_env_var_with_name = self.get_persisted(&new_env_var_name).await?;
             if let Some(env_var_with_name) = maybe_env_var_with_name
                 && !changed_env_vars_ids.contains(&env_var_with_name.id())
             {
@@ -231,13 +299,14 @@ impl<'a, RT: Runtime> EnvironmentVariablesModel<'a, RT> {
             SystemMetadataModel::new(self.tx)
                 .replace(
                     id,
-                    PersistedEnvironmentVariable(environment_variable).try_into()?,
+                    PersistedEnvironmentVariable::encrypt(&environment_variable, key_broker)
+                        .try_into()?,
                 )
                 .await?;
 
             let previous_env_var: ParsedDocument<PersistedEnvironmentVariable> =
                 document.try_into()?;
-            previous_env_vars.insert(id, previous_env_var.into_value().0);
+            previous_env_vars.insert(id, previous_env_var.decrypt(key_broker)?);
         }
 
         for (id, previous_env_var) in previous_env_vars {
@@ -289,31 +358,95 @@ mod tests {
         EnvVarValue,
         EnvironmentVariable,
     };
-    use database::test_helpers::DbFixtures;
+    use database::{
+        test_helpers::DbFixtures,
+        SystemMetadataModel,
+    };
+    use keybroker::KeyBroker;
     use maplit::btreemap;
     use runtime::testing::TestRuntime;
 
     use crate::{
-        environment_variables::EnvironmentVariablesModel,
+        environment_variables::{
+            types::{
+                PersistedEnvVarValue,
+                PersistedEnvironmentVariable,
+            },
+            EnvironmentVariablesModel,
+            ENVIRONMENT_VARIABLES_TABLE,
+        },
         test_helpers::DbFixturesWithModel,
     };
 
     #[convex_macro::test_runtime]
     async fn test_create_get(rt: TestRuntime) -> anyhow::Result<()> {
         let database = DbFixtures::new(&rt.clone()).await?.with_model().await?.db;
+        let key_broker = KeyBroker::dev();
         let mut tx = database.begin_system().await?;
         let mut env_model = EnvironmentVariablesModel::new(&mut tx);
         let name: EnvVarName = "hello".parse()?;
         let value: EnvVarValue = "world".parse()?;
         let env_var = EnvironmentVariable::new(name.clone(), value.clone());
-        env_model.create(env_var.clone(), &HashSet::new()).await?;
-        assert_eq!(env_model.get(&name).await?.unwrap().into_value(), env_var);
+        env_model
+            .create(env_var.clone(), &HashSet::new(), &key_broker)
+            .await?;
+        assert_eq!(
+            env_model
+                .get(&name, &key_broker)
+                .await?
+                .unwrap()
+                .into_value(),
+            env_var
+        );
+
+        // The value isn't stored in plaintext.
+        let persisted = env_model.get_persisted(&name).await?.unwrap();
+        let PersistedEnvVarValue::Encrypted(encrypted) = &persisted.value else {
+            panic!("Expected an encrypted value, got {persisted:?}");
+        };
+        assert!(!encrypted.contains("world"));
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_encrypt_plaintext_values(rt: TestRuntime) -> anyhow::Result<()> {
+        let database = DbFixtures::new(&rt.clone()).await?.with_model().await?.db;
+        let key_broker = KeyBroker::dev();
+        let mut tx = database.begin_system().await?;
+        let name: EnvVarName = "hello".parse()?;
+        let value: EnvVarValue = "world".parse()?;
+        let plaintext = PersistedEnvironmentVariable {
+            name: name.clone(),
+            value: PersistedEnvVarValue::Plaintext(value.clone()),
+        };
+        SystemMetadataModel::new(&mut tx)
+            .insert(&ENVIRONMENT_VARIABLES_TABLE, plaintext.try_into()?)
+            .await?;
+
+        let mut env_model = EnvironmentVariablesModel::new(&mut tx);
+        // Plaintext values are readable before they're encrypted.
+        assert_eq!(
+            env_model.get_all(&key_broker).await?,
+            btreemap! { name.clone() => value.clone() }
+        );
+        assert_eq!(env_model.encrypt_plaintext_values(&key_broker).await?, 1);
+        assert_eq!(env_model.encrypt_plaintext_values(&key_broker).await?, 0);
+        let persisted = env_model.get_persisted(&name).await?.unwrap();
+        assert!(matches!(
+            persisted.value,
+            PersistedEnvVarValue::Encrypted(_)
+        ));
+        assert_eq!(
+            env_model.get_all(&key_broker).await?,
+            btreemap! { name => value }
+        );
         Ok(())
     }
 
     #[convex_macro::test_runtime]
     async fn test_preload(rt: TestRuntime) -> anyhow::Result<()> {
         let database = DbFixtures::new(&rt.clone()).await?.with_model().await?.db;
+        let key_broker = KeyBroker::dev();
 
         let env_vars: BTreeMap<EnvVarName, EnvVarValue> = btreemap! {
             "hello".parse()? => "world".parse()?,
@@ -324,7 +457,7 @@ mod tests {
             for (name, value) in &env_vars {
                 let env_var = EnvironmentVariable::new(name.clone(), value.clone());
                 EnvironmentVariablesModel::new(&mut create_tx)
-                    .create(env_var.clone(), &HashSet::new())
+                    .create(env_var.clone(), &HashSet::new(), &key_broker)
                     .await?;
             }
             database.commit(create_tx).await?;
@@ -339,7 +472,7 @@ mod tests {
             let preload_token = {
                 let mut preload_tx = database.begin_system().await?;
                 let preloaded = EnvironmentVariablesModel::new(&mut preload_tx)
-                    .preload()
+                    .preload(&key_broker)
                     .await?;
                 for name in names {
                     let name = name.parse()?;
@@ -356,7 +489,7 @@ mod tests {
                     let name = name.parse()?;
                     assert_eq!(
                         EnvironmentVariablesModel::new(&mut regular_tx)
-                            .get(&name)
+                            .get(&name, &key_broker)
                             .await?
                             .map(|doc| doc.into_value().value),
                         env_vars.get(&name).cloned()
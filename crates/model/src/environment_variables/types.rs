@@ -5,25 +5,72 @@ pub use common::types::{
     EnvVarValue,
     EnvironmentVariable,
 };
+use keybroker::KeyBroker;
 use value::{
     obj,
     ConvexObject,
     ConvexValue,
 };
 
+/// An environment variable's value as stored in `_environment_variables`.
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
-pub struct PersistedEnvironmentVariable(pub EnvironmentVariable);
+pub enum PersistedEnvVarValue {
+    /// Written before values were encrypted at rest. These are encrypted when
+    /// the backend starts.
+    Plaintext(EnvVarValue),
+    /// Encrypted with `KeyBroker::encrypt_environment_variable`.
+    Encrypted(String),
+}
+
+impl PersistedEnvVarValue {
+    pub fn encrypt(value: &EnvVarValue, key_broker: &KeyBroker) -> Self {
+        Self::Encrypted(key_broker.encrypt_environment_variable(value))
+    }
+
+    pub fn decrypt(&self, key_broker: &KeyBroker) -> anyhow::Result<EnvVarValue> {
+        match self {
+            Self::Plaintext(value) => Ok(value.clone()),
+            Self::Encrypted(encrypted) => key_broker.decrypt_environment_variable(encrypted),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct PersistedEnvironmentVariable {
+    pub name: EnvVarName,
+    pub value: PersistedEnvVarValue,
+}
+
+impl PersistedEnvironmentVariable {
+    pub fn encrypt(env_var: &EnvironmentVariable, key_broker: &KeyBroker) -> Self {
+        Self {
+            name: env_var.name().clone(),
+            value: PersistedEnvVarValue::encrypt(env_var.value(), key_broker),
+        }
+    }
+
+    pub fn decrypt(&self, key_broker: &KeyBroker) -> anyhow::Result<EnvironmentVariable> {
+        Ok(EnvironmentVariable::new(
+            self.name.clone(),
+            self.value.decrypt(key_broker)?,
+        ))
+    }
+}
 
 impl TryFrom<PersistedEnvironmentVariable> for ConvexObject {
     type Error = anyhow::Error;
 
     fn try_from(
-        PersistedEnvironmentVariable(
-            EnvironmentVariable { name, value }
-        ): PersistedEnvironmentVariable,
+        PersistedEnvironmentVariable { name, value }: PersistedEnvironmentVariable,
     ) -> anyhow::Result<ConvexObject> {
-        obj!("name" => name.0, "value" => value.0)
+        match value {
+            PersistedEnvVarValue::Plaintext(value) => obj!("name" => name.0, "value" => value.0),
+            PersistedEnvVarValue::Encrypted(encrypted) => {
+                obj!("name" => name.0, "encryptedValue" => encrypted)
+            },
+        }
     }
 }
 
@@ -36,27 +83,36 @@ impl TryFrom<ConvexObject> for PersistedEnvironmentVariable {
             Some(ConvexValue::String(s)) => s.into(),
             v => anyhow::bail!("Invalid name field for EnvironmentVariable: {v:?}"),
         };
-        let value: String = match fields.remove("value") {
-            Some(ConvexValue::String(s)) => s.into(),
-            v => anyhow::bail!("Invalid value field for EnvironmentVariable: {v:?}"),
+        let value = match (fields.remove("value"), fields.remove("encryptedValue")) {
+            (Some(ConvexValue::String(s)), None) => {
+                PersistedEnvVarValue::Plaintext(String::from(s).parse()?)
+            },
+            (None, Some(ConvexValue::String(s))) => PersistedEnvVarValue::Encrypted(s.into()),
+            (value, encrypted_value) => anyhow::bail!(
+                "Invalid value fields for EnvironmentVariable: {value:?}, {encrypted_value:?}"
+            ),
         };
-        Ok(Self(EnvironmentVariable {
+        Ok(Self {
             name: name.parse()?,
-            value: value.parse()?,
-        }))
+            value,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
 
+    use keybroker::KeyBroker;
     use proptest::prelude::*;
     use value::{
         testing::assert_roundtrips,
         ConvexObject,
     };
 
-    use super::PersistedEnvironmentVariable;
+    use super::{
+        EnvironmentVariable,
+        PersistedEnvironmentVariable,
+    };
 
     proptest! {
         #![proptest_config(
@@ -66,5 +122,12 @@ mod tests {
         fn test_env_var_to_object_roundtrip(e in any::<PersistedEnvironmentVariable>()) {
             assert_roundtrips::<PersistedEnvironmentVariable, ConvexObject>(e);
         }
+
+        #[test]
+        fn test_env_var_encryption_roundtrip(e in any::<EnvironmentVariable>()) {
+            let key_broker = KeyBroker::dev();
+            let persisted = PersistedEnvironmentVariable::encrypt(&e, &key_broker);
+            prop_assert_eq!(persisted.decrypt(&key_broker).unwrap(), e);
+        }
     }
 }
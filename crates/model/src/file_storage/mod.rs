@@ -1,5 +1,6 @@
 use std::{
     collections::BTreeMap,
+    mem,
     str::FromStr,
     sync::{
         Arc,
@@ -23,6 +24,7 @@ use common::{
     types::{
         GenericIndexName,
         IndexName,
+        ObjectKey,
     },
 };
 use database::{
@@ -47,6 +49,7 @@ use pb::storage::{
 };
 use value::{
     id_v6::DocumentIdV6,
+    sha256::Sha256Digest,
     ConvexValue,
     FieldPath,
     ResolvedDocumentId,
@@ -56,7 +59,9 @@ use value::{
 use self::virtual_table::FileStorageDocMapper;
 use crate::{
     file_storage::types::{
+        FileStorageBlob,
         FileStorageEntry,
+        FileUploadSession,
         StorageUuid,
     },
     SystemIndex,
@@ -93,6 +98,36 @@ static FILE_STORAGE_ID_FIELD: LazyLock<FieldPath> =
 pub static FILE_STORAGE_ID_INDEX: LazyLock<IndexName> =
     LazyLock::new(|| system_index(&FILE_STORAGE_TABLE, "by_storage_id"));
 
+pub static FILE_STORAGE_BLOBS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_file_storage_blobs"
+        .parse()
+        .expect("invalid built-in file storage blobs table")
+});
+
+static BLOB_SHA256_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "sha256".parse().expect("invalid sha256 field"));
+static BLOB_REF_COUNT_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "refCount".parse().expect("invalid refCount field"));
+pub static FILE_STORAGE_BLOBS_BY_SHA256_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&FILE_STORAGE_BLOBS_TABLE, "by_sha256"));
+pub static FILE_STORAGE_BLOBS_BY_REF_COUNT_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&FILE_STORAGE_BLOBS_TABLE, "by_ref_count"));
+
+pub static FILE_UPLOAD_SESSIONS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_file_upload_sessions"
+        .parse()
+        .expect("invalid built-in file upload sessions table")
+});
+
+static UPLOAD_SESSION_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "uploadId".parse().expect("invalid uploadId field"));
+static UPLOAD_SESSION_EXPIRATION_TS_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "expirationTs".parse().expect("invalid expirationTs field"));
+pub static FILE_UPLOAD_SESSIONS_BY_UPLOAD_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&FILE_UPLOAD_SESSIONS_TABLE, "by_upload_id"));
+pub static FILE_UPLOAD_SESSIONS_BY_EXPIRATION_TS_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&FILE_UPLOAD_SESSIONS_TABLE, "by_expiration_ts"));
+
 pub struct FileStorageTable;
 impl SystemTable for FileStorageTable {
     fn table_name(&self) -> &'static TableName {
@@ -130,6 +165,56 @@ impl SystemTable for FileStorageTable {
     }
 }
 
+pub struct FileStorageBlobsTable;
+impl SystemTable for FileStorageBlobsTable {
+    fn table_name(&self) -> &'static TableName {
+        &FILE_STORAGE_BLOBS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: FILE_STORAGE_BLOBS_BY_SHA256_INDEX.clone(),
+                fields: vec![BLOB_SHA256_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: FILE_STORAGE_BLOBS_BY_REF_COUNT_INDEX.clone(),
+                fields: vec![BLOB_REF_COUNT_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<FileStorageBlob>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct FileUploadSessionsTable;
+impl SystemTable for FileUploadSessionsTable {
+    fn table_name(&self) -> &'static TableName {
+        &FILE_UPLOAD_SESSIONS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: FILE_UPLOAD_SESSIONS_BY_UPLOAD_ID_INDEX.clone(),
+                fields: vec![UPLOAD_SESSION_ID_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: FILE_UPLOAD_SESSIONS_BY_EXPIRATION_TS_INDEX.clone(),
+                fields: vec![UPLOAD_SESSION_EXPIRATION_TS_FIELD.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<FileUploadSession>::try_from(document).map(|_| ())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, derive_more::Display)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum FileStorageId {
@@ -320,7 +405,240 @@ impl<'a, RT: Runtime> FileStorageModel<'a, RT> {
         SystemMetadataModel::new(self.tx)
             .delete(document_id)
             .await?;
-        Ok(Some(entry.into_value()))
+        let entry = entry.into_value();
+        // Files stored with deduplication enabled hold a reference on their
+        // blob. Release it even if deduplication has since been turned off.
+        self.release_blob(&entry).await?;
+        Ok(Some(entry))
+    }
+
+    /// Take a reference on the blob holding `entry`'s contents, pointing
+    /// `entry` at an existing object with the same sha256 if there is one.
+    /// Otherwise `entry`'s object becomes the blob for its contents. Returns
+    /// whether `entry` was redirected to an existing object.
+    ///
+    /// When `entry` is redirected, the object it was uploaded to is recorded
+    /// as an unreferenced blob so the blob worker deletes it once this
+    /// transaction commits.
+    pub async fn reference_blob(&mut self, entry: &mut FileStorageEntry) -> anyhow::Result<bool> {
+        let mut blobs = self.blobs_for_sha256(&entry.sha256).await?;
+        if blobs.is_empty() {
+            self.insert_blob(entry.storage_key.clone(), entry.sha256.clone(), 1)
+                .await?;
+            return Ok(false);
+        }
+        // Prefer a blob that's still referenced, but revive an unreferenced one
+        // rather than keep a second copy of the same contents.
+        let position = blobs
+            .iter()
+            .position(|blob| blob.ref_count > 0)
+            .unwrap_or(0);
+        let blob = blobs.swap_remove(position);
+        let uploaded_key = mem::replace(&mut entry.storage_key, blob.storage_key.clone());
+        self.add_blob_references(blob, 1).await?;
+        let redirected = uploaded_key != entry.storage_key;
+        if redirected {
+            self.insert_blob(uploaded_key, entry.sha256.clone(), 0)
+                .await?;
+        }
+        Ok(redirected)
+    }
+
+    async fn release_blob(&mut self, entry: &FileStorageEntry) -> anyhow::Result<()> {
+        let blob = self
+            .blobs_for_sha256(&entry.sha256)
+            .await?
+            .into_iter()
+            .find(|blob| blob.storage_key == entry.storage_key);
+        if let Some(blob) = blob {
+            self.add_blob_references(blob, -1).await?;
+        }
+        Ok(())
+    }
+
+    /// Release the blob references held by every `_file_storage` entry, e.g.
+    /// because a snapshot import is replacing the whole table in this
+    /// transaction.
+    pub async fn release_all_blobs(&mut self) -> anyhow::Result<()> {
+        // Skip scanning `_file_storage` if deduplication was never enabled.
+        let blobs_query = Query::full_table_scan(FILE_STORAGE_BLOBS_TABLE.clone(), Order::Asc);
+        if ResolvedQuery::new(self.tx, blobs_query)?
+            .next(self.tx, None)
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+        let query = Query::full_table_scan(FILE_STORAGE_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            let entry: ParsedDocument<FileStorageEntry> = document.try_into()?;
+            self.release_blob(&entry).await?;
+        }
+        Ok(())
+    }
+
+    /// Blobs that no `_file_storage` entry references, whose objects can be
+    /// deleted.
+    pub async fn unreferenced_blobs(
+        &mut self,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<FileStorageBlob>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: FILE_STORAGE_BLOBS_BY_REF_COUNT_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                BLOB_REF_COUNT_FIELD.clone(),
+                ConvexValue::from(0i64).into(),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        let mut blobs = vec![];
+        while blobs.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            blobs.push(document.try_into()?);
+        }
+        Ok(blobs)
+    }
+
+    pub async fn delete_blob(
+        &mut self,
+        blob: ParsedDocument<FileStorageBlob>,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            blob.ref_count == 0,
+            "Deleting blob {:?} with {} references",
+            blob.storage_key,
+            blob.ref_count
+        );
+        SystemMetadataModel::new(self.tx).delete(blob.id()).await?;
+        Ok(())
+    }
+
+    async fn blobs_for_sha256(
+        &mut self,
+        sha256: &Sha256Digest,
+    ) -> anyhow::Result<Vec<ParsedDocument<FileStorageBlob>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: FILE_STORAGE_BLOBS_BY_SHA256_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                BLOB_SHA256_FIELD.clone(),
+                ConvexValue::try_from(sha256.clone())?.into(),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        let mut blobs = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            blobs.push(document.try_into()?);
+        }
+        Ok(blobs)
+    }
+
+    async fn insert_blob(
+        &mut self,
+        storage_key: ObjectKey,
+        sha256: Sha256Digest,
+        ref_count: i64,
+    ) -> anyhow::Result<()> {
+        let blob = FileStorageBlob {
+            storage_key,
+            sha256,
+            ref_count,
+        };
+        SystemMetadataModel::new(self.tx)
+            .insert_metadata(&FILE_STORAGE_BLOBS_TABLE, blob.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_blob_references(
+        &mut self,
+        blob: ParsedDocument<FileStorageBlob>,
+        delta: i64,
+    ) -> anyhow::Result<()> {
+        let id = blob.id();
+        let mut blob = blob.into_value();
+        blob.ref_count += delta;
+        anyhow::ensure!(
+            blob.ref_count >= 0,
+            "Blob {:?} has a negative reference count",
+            blob.storage_key
+        );
+        SystemMetadataModel::new(self.tx)
+            .replace(id, blob.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn insert_upload_session(
+        &mut self,
+        session: FileUploadSession,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        SystemMetadataModel::new(self.tx)
+            .insert_metadata(&FILE_UPLOAD_SESSIONS_TABLE, session.try_into()?)
+            .await
+    }
+
+    pub async fn get_upload_session(
+        &mut self,
+        upload_id: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<FileUploadSession>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: FILE_UPLOAD_SESSIONS_BY_UPLOAD_ID_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                UPLOAD_SESSION_ID_FIELD.clone(),
+                ConvexValue::try_from(upload_id.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        query_stream
+            .next(self.tx, None)
+            .await?
+            .map(|doc| doc.try_into())
+            .transpose()
+    }
+
+    pub async fn replace_upload_session(
+        &mut self,
+        id: ResolvedDocumentId,
+        session: FileUploadSession,
+    ) -> anyhow::Result<()> {
+        SystemMetadataModel::new(self.tx)
+            .replace(id, session.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_upload_session(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
+        SystemMetadataModel::new(self.tx).delete(id).await?;
+        Ok(())
+    }
+
+    /// Upload sessions that expired before `now_nanos`, oldest first.
+    pub async fn expired_upload_sessions(
+        &mut self,
+        now_nanos: i64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<FileUploadSession>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: FILE_UPLOAD_SESSIONS_BY_EXPIRATION_TS_INDEX.clone(),
+            range: vec![IndexRangeExpression::Lt(
+                UPLOAD_SESSION_EXPIRATION_TS_FIELD.clone(),
+                ConvexValue::from(now_nanos),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        let mut sessions = vec![];
+        while sessions.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            sessions.push(document.try_into()?);
+        }
+        Ok(sessions)
     }
 
     pub async fn get_total_storage_count(&mut self) -> anyhow::Result<u64> {
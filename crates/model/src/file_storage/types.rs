@@ -125,6 +125,192 @@ impl From<FileStorageEntry> for FileStorageEntryProto {
     }
 }
 
+/// A content-addressed object in file storage, shared by every
+/// `_file_storage` entry with the same sha256. Only used when file storage
+/// deduplication is enabled.
+///
+/// Reference counts can drift upwards, e.g. when a snapshot import replaces
+/// `_storage` without releasing the old entries' references. That leaks the
+/// object but never deletes one that is still referenced.
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileStorageBlob {
+    pub storage_key: ObjectKey,
+    pub sha256: Sha256Digest,
+    // Number of `_file_storage` entries pointing at `storage_key`. Blobs with
+    // no references are deleted, along with their objects, by the blob
+    // worker.
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "0..=1000i64"))]
+    pub ref_count: i64,
+}
+
+impl TryFrom<FileStorageBlob> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        FileStorageBlob {
+            storage_key,
+            sha256,
+            ref_count,
+        }: FileStorageBlob,
+    ) -> Result<Self, Self::Error> {
+        let storage_key: String = storage_key.into();
+        obj!(
+            "storageKey" => storage_key,
+            "sha256" => sha256,
+            "refCount" => ref_count,
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for FileStorageBlob {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut object_fields: BTreeMap<_, _> = value.into();
+        let storage_key = match object_fields.remove("storageKey") {
+            Some(ConvexValue::String(key)) => String::from(key).try_into()?,
+            _ => anyhow::bail!("Missing 'storageKey' in {object_fields:?}"),
+        };
+        let sha256 = match object_fields.remove("sha256") {
+            Some(sha256) => sha256.try_into()?,
+            _ => anyhow::bail!("Missing 'sha256' in {object_fields:?}"),
+        };
+        let ref_count = match object_fields.remove("refCount") {
+            Some(ConvexValue::Int64(ref_count)) if ref_count >= 0 => ref_count,
+            _ => anyhow::bail!("Missing or invalid 'refCount' in {object_fields:?}"),
+        };
+        Ok(Self {
+            storage_key,
+            sha256,
+            ref_count,
+        })
+    }
+}
+
+/// A resumable upload into file storage that hasn't been finished yet. Parts
+/// are appended in order, and the upload becomes a `_file_storage` entry once
+/// the client finishes it.
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileUploadSession {
+    // Random id handed to the client to refer to the session.
+    pub upload_id: String,
+    // Sha256 of the store file authorization token the session was started
+    // with. Every later request for the session must present the same token,
+    // so knowing the upload id alone isn't enough to use it.
+    pub authorization_sha256: Sha256Digest,
+    // Opaque token from `Storage::start_client_driven_upload`.
+    pub upload_token: String,
+    // Tokens from `Storage::upload_part` for the parts received so far, in
+    // order.
+    pub part_tokens: Vec<String>,
+    // Part number for the next part upload to claim. Every attempt to upload a
+    // part claims its own number, so concurrent attempts at the same offset
+    // can't overwrite each other's data.
+    pub next_part_number: i64,
+    // Number of bytes uploaded so far, i.e. the offset of the next part.
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "0..=i64::MAX"))]
+    pub offset: i64,
+    pub content_type: Option<String>,
+    // Unix timestamp in nanoseconds after which the session can't be used.
+    pub expiration_ts: i64,
+}
+
+impl TryFrom<FileUploadSession> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        FileUploadSession {
+            upload_id,
+            authorization_sha256,
+            upload_token,
+            part_tokens,
+            next_part_number,
+            offset,
+            content_type,
+            expiration_ts,
+        }: FileUploadSession,
+    ) -> Result<Self, Self::Error> {
+        obj!(
+            "uploadId" => upload_id,
+            "authorizationSha256" => authorization_sha256,
+            "uploadToken" => upload_token,
+            "partTokens" => ConvexValue::Array(
+                part_tokens
+                    .into_iter()
+                    .map(ConvexValue::try_from)
+                    .collect::<Result<Vec<_>, anyhow::Error>>()?
+                    .try_into()?
+            ),
+            "nextPartNumber" => next_part_number,
+            "offset" => offset,
+            "contentType" => match content_type {
+                None => ConvexValue::Null,
+                Some(ct) => ct.try_into()?,
+            },
+            "expirationTs" => expiration_ts,
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for FileUploadSession {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut object_fields: BTreeMap<_, _> = value.into();
+        let upload_id = match object_fields.remove("uploadId") {
+            Some(ConvexValue::String(id)) => String::from(id),
+            _ => anyhow::bail!("Missing 'uploadId' in {object_fields:?}"),
+        };
+        let authorization_sha256 = match object_fields.remove("authorizationSha256") {
+            Some(sha256) => sha256.try_into()?,
+            _ => anyhow::bail!("Missing 'authorizationSha256' in {object_fields:?}"),
+        };
+        let upload_token = match object_fields.remove("uploadToken") {
+            Some(ConvexValue::String(token)) => String::from(token),
+            _ => anyhow::bail!("Missing 'uploadToken' in {object_fields:?}"),
+        };
+        let part_tokens = match object_fields.remove("partTokens") {
+            Some(ConvexValue::Array(arr)) => arr
+                .into_iter()
+                .map(|token| match token {
+                    ConvexValue::String(token) => Ok(String::from(token)),
+                    _ => anyhow::bail!("Invalid part token {token:?}"),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            _ => anyhow::bail!("Missing 'partTokens' in {object_fields:?}"),
+        };
+        let next_part_number = match object_fields.remove("nextPartNumber") {
+            Some(ConvexValue::Int64(part_number)) => part_number,
+            _ => anyhow::bail!("Missing 'nextPartNumber' in {object_fields:?}"),
+        };
+        let offset = match object_fields.remove("offset") {
+            Some(ConvexValue::Int64(offset)) if offset >= 0 => offset,
+            _ => anyhow::bail!("Missing or invalid 'offset' in {object_fields:?}"),
+        };
+        let content_type = match object_fields.remove("contentType") {
+            None | Some(ConvexValue::Null) => None,
+            Some(ConvexValue::String(ct)) => Some(String::from(ct)),
+            _ => anyhow::bail!("Invalid 'contentType' in {object_fields:?}"),
+        };
+        let expiration_ts = match object_fields.remove("expirationTs") {
+            Some(ConvexValue::Int64(ts)) => ts,
+            _ => anyhow::bail!("Missing 'expirationTs' in {object_fields:?}"),
+        };
+        Ok(Self {
+            upload_id,
+            authorization_sha256,
+            upload_token,
+            part_tokens,
+            next_part_number,
+            offset,
+            content_type,
+            expiration_ts,
+        })
+    }
+}
+
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, derive_more::Display)]
 pub struct StorageUuid(
@@ -179,7 +365,9 @@ mod tests {
     };
 
     use super::{
+        FileStorageBlob,
         FileStorageEntry,
+        FileUploadSession,
         StorageUuid,
     };
 
@@ -198,6 +386,16 @@ mod tests {
             assert_roundtrips::<FileStorageEntry, FileStorageEntryProto>(v);
         }
 
+        #[test]
+        fn test_storage_blob_roundtrip(v in any::<FileStorageBlob>()) {
+            assert_roundtrips::<FileStorageBlob, ConvexObject>(v);
+        }
+
+        #[test]
+        fn test_upload_session_roundtrip(v in any::<FileUploadSession>()) {
+            assert_roundtrips::<FileUploadSession, ConvexObject>(v);
+        }
+
         #[test]
         fn test_storage_roundtrip(v in any::<StorageUuid>()) {
             assert_roundtrips::<StorageUuid, ConvexValue>(v);
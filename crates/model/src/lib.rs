@@ -54,6 +54,7 @@ pub use database::defaults::{
 };
 use database::{
     Database,
+    IndexAggregatesTable,
     IndexModel,
     IndexTable,
     IndexWorkerMetadataTable,
@@ -82,10 +83,16 @@ use crate::{
         CronJobsTable,
     },
     deployment_audit_log::DeploymentAuditLogsTable,
+    deployment_history::DeploymentHistoryTable,
     environment_variables::EnvironmentVariablesTable,
     exports::ExportsTable,
     external_packages::ExternalPackagesTable,
-    file_storage::FileStorageTable,
+    file_storage::{
+        FileStorageBlobsTable,
+        FileStorageTable,
+        FileUploadSessionsTable,
+    },
+    migrations::MigrationsTable,
     modules::{
         ModuleVersionsTable,
         ModulesTable,
@@ -102,10 +109,12 @@ pub mod backend_state;
 pub mod config;
 pub mod cron_jobs;
 pub mod deployment_audit_log;
+pub mod deployment_history;
 pub mod environment_variables;
 pub mod exports;
 pub mod external_packages;
 pub mod file_storage;
+pub mod migrations;
 pub mod modules;
 pub mod scheduled_jobs;
 pub mod session_requests;
@@ -145,9 +154,14 @@ enum DefaultTableNumber {
     FileStorageVirtual = 28,
     SnapshotImports = 29,
     IndexWorkerMetadata = 30,
+    FileStorageBlobs = 31,
+    FileUploadSessions = 32,
+    IndexAggregates = 33,
+    DeploymentHistory = 34,
+    Migrations = 35,
     // Keep this number and your user name up to date. The number makes it easy to know
     // what to use next. The username on the same line detects merge conflicts
-    // Next Number - 31 - sam
+    // Next Number - 36 - agent
 }
 
 impl From<DefaultTableNumber> for TableNumber {
@@ -184,6 +198,11 @@ impl From<DefaultTableNumber> for TableName {
             DefaultTableNumber::FileStorageVirtual => &*FILE_STORAGE_VIRTUAL_TABLE,
             DefaultTableNumber::SnapshotImports => SnapshotImportsTable.table_name(),
             DefaultTableNumber::IndexWorkerMetadata => IndexWorkerMetadataTable.table_name(),
+            DefaultTableNumber::FileStorageBlobs => FileStorageBlobsTable.table_name(),
+            DefaultTableNumber::FileUploadSessions => FileUploadSessionsTable.table_name(),
+            DefaultTableNumber::IndexAggregates => IndexAggregatesTable.table_name(),
+            DefaultTableNumber::DeploymentHistory => DeploymentHistoryTable.table_name(),
+            DefaultTableNumber::Migrations => MigrationsTable.table_name(),
         }
         .clone()
     }
@@ -298,12 +317,17 @@ pub fn app_system_tables() -> Vec<&'static dyn SystemTable> {
         &SourcePackagesTable,
         &SessionRequestsTable,
         &FileStorageTable,
+        &FileStorageBlobsTable,
+        &FileUploadSessionsTable,
         &ScheduledJobsTable,
         &CronJobsTable,
         &CronJobLogsTable,
         &BackendStateTable,
         &ExportsTable,
         &SnapshotImportsTable,
+        &IndexAggregatesTable,
+        &DeploymentHistoryTable,
+        &MigrationsTable,
     ]
 }
 
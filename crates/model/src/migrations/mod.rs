@@ -0,0 +1,375 @@
+use std::sync::LazyLock;
+
+use anyhow::Context;
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    patch_value,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    id_v6::DocumentIdV6,
+    ConvexValue,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+};
+
+use self::types::{
+    Migration,
+    MigrationState,
+};
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static MIGRATIONS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_migrations"
+        .parse()
+        .expect("Invalid built-in migrations table")
+});
+
+pub static MIGRATIONS_INDEX_BY_NAME: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&MIGRATIONS_TABLE, "by_name"));
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("invalid name field"));
+
+pub struct MigrationsTable;
+impl SystemTable for MigrationsTable {
+    fn table_name(&self) -> &'static TableName {
+        &MIGRATIONS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: MIGRATIONS_INDEX_BY_NAME.clone(),
+            fields: vec![NAME_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<Migration>::try_from(document).map(|_| ())
+    }
+}
+
+fn migration_not_found(name: &str) -> ErrorMetadata {
+    ErrorMetadata::not_found(
+        "MigrationNotFound",
+        format!("Migration {name} doesn't exist"),
+    )
+}
+
+pub struct MigrationModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> MigrationModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        id: ResolvedDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<Migration>>> {
+        anyhow::ensure!(self
+            .tx
+            .table_mapping()
+            .number_matches_name(id.table().table_number, MigrationsTable.table_name()));
+        match self.tx.get(id).await? {
+            None => Ok(None),
+            Some(doc) => Ok(Some(doc.try_into()?)),
+        }
+    }
+
+    pub async fn get_by_name(
+        &mut self,
+        name: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<Migration>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: MIGRATIONS_INDEX_BY_NAME.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                NAME_FIELD.clone(),
+                ConvexValue::try_from(name)?.into(),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Returns every migration, ordered by name.
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<Migration>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: MIGRATIONS_INDEX_BY_NAME.clone(),
+            range: vec![],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, query)?;
+        let mut migrations = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            migrations.push(doc.try_into()?);
+        }
+        Ok(migrations)
+    }
+
+    /// Starts migrating `table_name` with the mutation at `udf_path`. A
+    /// finished migration with the same name is restarted from the beginning
+    /// of the table.
+    pub async fn start(
+        &mut self,
+        name: String,
+        table_name: TableName,
+        udf_path: CanonicalizedUdfPath,
+        dry_run: bool,
+        batch_size: u32,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        if table_name.is_system() || !self.tx.table_mapping().name_exists(&table_name) {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "TableNotFound",
+                format!("Table {table_name} doesn't exist"),
+            ));
+        }
+        anyhow::ensure!(
+            batch_size > 0,
+            ErrorMetadata::bad_request("InvalidBatchSize", "batchSize must be positive")
+        );
+        let migration = Migration {
+            name: name.clone(),
+            table_name,
+            udf_path,
+            dry_run,
+            batch_size,
+            state: MigrationState::Running,
+            cursor: None,
+            num_processed: 0,
+            num_changed: 0,
+        };
+        match self.get_by_name(&name).await? {
+            Some(existing) if existing.is_active() => {
+                anyhow::bail!(ErrorMetadata::bad_request(
+                    "MigrationAlreadyRunning",
+                    format!(
+                        "Migration {name} is already {}. Cancel it before starting it again.",
+                        existing.state.as_str()
+                    ),
+                ))
+            },
+            Some(existing) => {
+                SystemMetadataModel::new(self.tx)
+                    .replace(existing.id(), migration.try_into()?)
+                    .await?;
+                Ok(existing.id())
+            },
+            None => {
+                SystemMetadataModel::new(self.tx)
+                    .insert(&MIGRATIONS_TABLE, migration.try_into()?)
+                    .await
+            },
+        }
+    }
+
+    async fn update_state(&mut self, name: &str, new_state: MigrationState) -> anyhow::Result<()> {
+        let migration = self
+            .get_by_name(name)
+            .await?
+            .context(migration_not_found(name))?;
+        match (&migration.state, &new_state) {
+            (MigrationState::Running, MigrationState::Paused)
+            | (MigrationState::Paused, MigrationState::Running)
+            | (MigrationState::Running | MigrationState::Paused, MigrationState::Canceled) => {},
+            (current_state, _) => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidMigrationState",
+                format!(
+                    "Migration {name} is {} and can't be {}",
+                    current_state.as_str(),
+                    new_state.as_str()
+                ),
+            )),
+        }
+        self.patch_state(migration.id(), new_state).await
+    }
+
+    async fn patch_state(
+        &mut self,
+        id: ResolvedDocumentId,
+        state: MigrationState,
+    ) -> anyhow::Result<()> {
+        SystemMetadataModel::new(self.tx)
+            .patch(
+                id,
+                patch_value!("state" => Some(ConvexValue::Object(state.try_into()?)))?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn pause(&mut self, name: &str) -> anyhow::Result<()> {
+        self.update_state(name, MigrationState::Paused).await
+    }
+
+    pub async fn resume(&mut self, name: &str) -> anyhow::Result<()> {
+        self.update_state(name, MigrationState::Running).await
+    }
+
+    pub async fn cancel(&mut self, name: &str) -> anyhow::Result<()> {
+        self.update_state(name, MigrationState::Canceled).await
+    }
+
+    /// Records a processed batch ending at `cursor`.
+    pub async fn record_progress(
+        &mut self,
+        id: ResolvedDocumentId,
+        cursor: DocumentIdV6,
+        num_processed: u64,
+        num_changed: u64,
+    ) -> anyhow::Result<()> {
+        let migration = self.get(id).await?.context("Migration not found")?;
+        anyhow::ensure!(
+            migration.state == MigrationState::Running,
+            "Recording progress for migration {} in state {:?}",
+            migration.name,
+            migration.state
+        );
+        SystemMetadataModel::new(self.tx)
+            .patch(
+                id,
+                patch_value!(
+                    "cursor" => Some(ConvexValue::from(cursor)),
+                    "numProcessed" => Some(
+                        ConvexValue::from((migration.num_processed + num_processed) as i64)
+                    ),
+                    "numChanged" => Some(
+                        ConvexValue::from((migration.num_changed + num_changed) as i64)
+                    )
+                )?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn complete(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
+        self.patch_state(id, MigrationState::Completed).await
+    }
+
+    pub async fn fail(&mut self, id: ResolvedDocumentId, error: String) -> anyhow::Result<()> {
+        self.patch_state(id, MigrationState::Failed { error }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::assert_obj;
+    use database::{
+        test_helpers::DbFixtures,
+        UserFacingModel,
+    };
+    use errors::ErrorMetadataAnyhowExt;
+    use runtime::testing::TestRuntime;
+    use value::TableName;
+
+    use crate::{
+        migrations::{
+            types::MigrationState,
+            MigrationModel,
+        },
+        test_helpers::DbFixturesWithModel,
+    };
+
+    #[convex_macro::test_runtime]
+    async fn test_migration_state_transitions(rt: TestRuntime) -> anyhow::Result<()> {
+        let db = DbFixtures::new(&rt).await?.with_model().await?.db;
+        let mut tx = db.begin_system().await?;
+        let table_name: TableName = "messages".parse()?;
+        UserFacingModel::new(&mut tx)
+            .insert(table_name.clone(), assert_obj!())
+            .await?;
+        let mut model = MigrationModel::new(&mut tx);
+        let err = model
+            .start(
+                "backfill".to_string(),
+                "missing".parse()?,
+                "migrations:backfill".parse()?,
+                false,
+                10,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "TableNotFound");
+
+        model
+            .start(
+                "backfill".to_string(),
+                table_name.clone(),
+                "migrations:backfill".parse()?,
+                false,
+                10,
+            )
+            .await?;
+        let err = model
+            .start(
+                "backfill".to_string(),
+                table_name.clone(),
+                "migrations:backfill".parse()?,
+                false,
+                10,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "MigrationAlreadyRunning");
+
+        model.pause("backfill").await?;
+        let err = model.pause("backfill").await.unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidMigrationState");
+        model.resume("backfill").await?;
+        model.cancel("backfill").await?;
+        let migration = model.get_by_name("backfill").await?.unwrap();
+        assert_eq!(migration.state, MigrationState::Canceled);
+        let err = model.resume("backfill").await.unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidMigrationState");
+
+        // A finished migration can be started again from the beginning.
+        let id = model
+            .start(
+                "backfill".to_string(),
+                table_name,
+                "migrations:backfill".parse()?,
+                true,
+                10,
+            )
+            .await?;
+        assert_eq!(id, migration.id());
+        let migration = model.get(id).await?.unwrap();
+        assert_eq!(migration.state, MigrationState::Running);
+        assert!(migration.dry_run);
+        assert_eq!(migration.cursor, None);
+
+        let err = model.cancel("nonexistent").await.unwrap_err();
+        assert_eq!(err.short_msg(), "MigrationNotFound");
+        Ok(())
+    }
+}
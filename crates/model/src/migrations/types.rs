@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use common::obj;
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    id_v6::DocumentIdV6,
+    remove_boolean,
+    remove_int64,
+    remove_object,
+    remove_string,
+    ConvexObject,
+    ConvexValue,
+    TableName,
+};
+
+/// A batched data migration, stored in `_migrations`. The migration worker
+/// pages through `table_name` in id order and runs `udf_path` on each
+/// document, with the document as its argument.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct Migration {
+    pub name: String,
+    pub table_name: TableName,
+    /// Mutation that transforms a single document.
+    pub udf_path: CanonicalizedUdfPath,
+    /// Roll back every batch instead of committing it, only counting how many
+    /// documents would change.
+    pub dry_run: bool,
+    pub batch_size: u32,
+    pub state: MigrationState,
+    /// The last document processed, so the migration can resume after it.
+    pub cursor: Option<DocumentIdV6>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub num_processed: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub num_changed: u64,
+}
+
+impl Migration {
+    /// Whether the migration still has documents left to process.
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, MigrationState::Running | MigrationState::Paused)
+    }
+}
+
+/// `Running` and `Paused` migrations can be paused, resumed and canceled by
+/// the developer. The worker moves `Running` migrations to `Completed` once
+/// the whole table has been processed, or to `Failed` if the mutation throws.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum MigrationState {
+    Running,
+    Paused,
+    Completed,
+    Canceled,
+    Failed { error: String },
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationState::Running => "running",
+            MigrationState::Paused => "paused",
+            MigrationState::Completed => "completed",
+            MigrationState::Canceled => "canceled",
+            MigrationState::Failed { .. } => "failed",
+        }
+    }
+}
+
+impl TryFrom<MigrationState> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(state: MigrationState) -> anyhow::Result<Self> {
+        match state {
+            MigrationState::Failed { error } => obj!(
+                "state" => "failed",
+                "error" => error,
+            ),
+            state => obj!("state" => state.as_str()),
+        }
+    }
+}
+
+impl TryFrom<ConvexObject> for MigrationState {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> anyhow::Result<Self> {
+        let mut fields = BTreeMap::from(value);
+        let state = match &*remove_string(&mut fields, "state")? {
+            "running" => MigrationState::Running,
+            "paused" => MigrationState::Paused,
+            "completed" => MigrationState::Completed,
+            "canceled" => MigrationState::Canceled,
+            "failed" => MigrationState::Failed {
+                error: remove_string(&mut fields, "error")?,
+            },
+            state => anyhow::bail!("Invalid migration state {state}"),
+        };
+        Ok(state)
+    }
+}
+
+impl TryFrom<Migration> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(migration: Migration) -> anyhow::Result<Self> {
+        obj!(
+            "name" => migration.name,
+            "table" => String::from(migration.table_name),
+            "udfPath" => String::from(migration.udf_path),
+            "dryRun" => migration.dry_run,
+            "batchSize" => migration.batch_size as i64,
+            "state" => ConvexObject::try_from(migration.state)?,
+            "cursor" => migration
+                .cursor
+                .map(ConvexValue::from)
+                .unwrap_or(ConvexValue::Null),
+            "numProcessed" => migration.num_processed as i64,
+            "numChanged" => migration.num_changed as i64,
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for Migration {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> anyhow::Result<Self> {
+        let mut fields = BTreeMap::from(value);
+        let cursor = match fields.remove("cursor") {
+            Some(ConvexValue::Null) | None => None,
+            Some(cursor) => Some(cursor.try_into()?),
+        };
+        Ok(Self {
+            name: remove_string(&mut fields, "name")?,
+            table_name: remove_string(&mut fields, "table")?.parse()?,
+            udf_path: remove_string(&mut fields, "udfPath")?.parse()?,
+            dry_run: remove_boolean(&mut fields, "dryRun")?,
+            batch_size: remove_int64(&mut fields, "batchSize")?.try_into()?,
+            state: remove_object(&mut fields, "state")?,
+            cursor,
+            num_processed: remove_int64(&mut fields, "numProcessed")?.try_into()?,
+            num_changed: remove_int64(&mut fields, "numChanged")?.try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::testing::assert_roundtrips;
+    use proptest::prelude::*;
+    use value::ConvexObject;
+
+    use super::Migration;
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+        #[test]
+        fn test_migration_roundtrips(v in any::<Migration>()) {
+            assert_roundtrips::<Migration, ConvexObject>(v);
+        }
+    }
+}
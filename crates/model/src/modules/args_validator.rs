@@ -115,8 +115,18 @@ impl TryFrom<ArgsValidator> for JsonValue {
 #[cfg(test)]
 mod tests {
     use proptest::prelude::*;
-    use serde_json::Value as JsonValue;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
     use sync_types::testing::assert_roundtrips;
+    use value::{
+        array,
+        assert_obj,
+        ConvexValue,
+        TableMapping,
+        VirtualTableMapping,
+    };
 
     use crate::modules::args_validator::ArgsValidator;
 
@@ -129,4 +139,26 @@ mod tests {
             assert_roundtrips::<ArgsValidator, JsonValue>(v);
         }
     }
+
+    #[test]
+    fn test_check_args_constraints() -> anyhow::Result<()> {
+        let validator = ArgsValidator::try_from(json!({
+            "type": "object",
+            "value": {
+                "rating": {
+                    "fieldType": { "type": "number", "min": 0, "max": 5 },
+                    "optional": false,
+                },
+            }
+        }))?;
+        let check = |rating: f64| {
+            let args = array![ConvexValue::Object(assert_obj!("rating" => rating))]?;
+            validator.check_args(&args, &TableMapping::new(), &VirtualTableMapping::new())
+        };
+        assert!(check(3.)?.is_none());
+        let error = check(7.)?.expect("Expected a validation error");
+        assert!(error.message.starts_with("Value must be at most 5."));
+        assert!(error.message.contains("Path: .rating"));
+        Ok(())
+    }
 }
@@ -64,6 +64,7 @@ use self::{
 };
 use crate::{
     config::types::ModuleConfig,
+    deployment_history::RetainedModuleVersions,
     source_packages::types::SourcePackageId,
     SystemIndex,
     SystemTable,
@@ -247,7 +248,8 @@ impl<'a, RT: Runtime> ModuleModel<'a, RT> {
         Ok(Some(module_metadata))
     }
 
-    /// Put a module's source at a given path.
+    /// Put a module's source at a given path. The replaced version is kept if
+    /// it's in `retained_versions`.
     pub async fn put(
         &mut self,
         path: CanonicalizedModulePath,
@@ -256,6 +258,7 @@ impl<'a, RT: Runtime> ModuleModel<'a, RT> {
         source_map: Option<SourceMap>,
         analyze_result: Option<AnalyzedModule>,
         environment: ModuleEnvironment,
+        retained_versions: &RetainedModuleVersions,
     ) -> anyhow::Result<()> {
         if !(self.tx.identity().is_admin() || self.tx.identity().is_system()) {
             anyhow::bail!(unauthorized_error("put_module"));
@@ -279,14 +282,17 @@ impl<'a, RT: Runtime> ModuleModel<'a, RT> {
                     .replace(module_metadata.id(), new_metadata.try_into()?)
                     .await?;
 
-                // Delete the old module version since it has no more references.
-                let previous_version_id = self
-                    .get_version(module_metadata.id(), previous_version)
-                    .await?
-                    .id();
-                SystemMetadataModel::new(self.tx)
-                    .delete(previous_version_id)
-                    .await?;
+                // Delete the old module version unless a retained deployment
+                // still references it.
+                if !retained_versions.contains(module_metadata.id(), previous_version) {
+                    let previous_version_id = self
+                        .get_version(module_metadata.id(), previous_version)
+                        .await?
+                        .id();
+                    SystemMetadataModel::new(self.tx)
+                        .delete(previous_version_id)
+                        .await?;
+                }
 
                 (module_metadata.id(), latest_version)
             },
@@ -334,7 +340,12 @@ impl<'a, RT: Runtime> ModuleModel<'a, RT> {
     }
 
     /// Delete a module, making it inaccessible for subsequent transactions.
-    pub async fn delete(&mut self, path: CanonicalizedModulePath) -> anyhow::Result<()> {
+    /// Its latest version is kept if it's in `retained_versions`.
+    pub async fn delete(
+        &mut self,
+        path: CanonicalizedModulePath,
+        retained_versions: &RetainedModuleVersions,
+    ) -> anyhow::Result<()> {
         if !(self.tx.identity().is_admin() || self.tx.identity().is_system()) {
             anyhow::bail!(unauthorized_error("delete_module"));
         }
@@ -342,13 +353,16 @@ impl<'a, RT: Runtime> ModuleModel<'a, RT> {
             let module_id = module_metadata.id();
             SystemMetadataModel::new(self.tx).delete(module_id).await?;
 
-            // Delete the module version since it has no more references.
-            let module_version = self
-                .get_version(module_id, module_metadata.latest_version)
-                .await?;
-            SystemMetadataModel::new(self.tx)
-                .delete(module_version.id())
-                .await?;
+            // Delete the module version unless a retained deployment still
+            // references it.
+            if !retained_versions.contains(module_id, module_metadata.latest_version) {
+                let module_version = self
+                    .get_version(module_id, module_metadata.latest_version)
+                    .await?;
+                SystemMetadataModel::new(self.tx)
+                    .delete(module_version.id())
+                    .await?;
+            }
         }
         Ok(())
     }
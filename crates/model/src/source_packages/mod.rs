@@ -80,6 +80,17 @@ impl<'a, RT: Runtime> SourcePackageModel<'a, RT> {
             .try_into()
     }
 
+    /// Delete a source package that's no longer referenced, letting storage
+    /// garbage collection reclaim its package.
+    pub async fn delete(&mut self, source_package_id: SourcePackageId) -> anyhow::Result<()> {
+        let id: DocumentIdV6 = source_package_id.into();
+        let document_id = id.to_resolved(&self.tx.table_mapping().inject_table_id())?;
+        SystemMetadataModel::new(self.tx)
+            .delete(document_id)
+            .await?;
+        Ok(())
+    }
+
     pub async fn get_latest(&mut self) -> anyhow::Result<Option<ParsedDocument<SourcePackage>>> {
         let mut source_package_ids = vec![];
 
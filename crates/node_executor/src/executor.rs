@@ -40,8 +40,6 @@ use model::{
     environment_variables::types::{
         EnvVarName,
         EnvVarValue,
-        EnvironmentVariable,
-        PersistedEnvironmentVariable,
     },
     modules::{
         args_validator::ArgsValidator,
@@ -72,21 +70,23 @@ use sync_types::{
 use value::{
     base64,
     heap_size::WithHeapSize,
-    ConvexObject,
     ConvexValue,
 };
 
-use crate::metrics::{
-    log_download_time,
-    log_external_deps_size_bytes_total,
-    log_function_execution,
-    log_import_time,
-    log_node_source_map_missing,
-    log_node_source_map_token_lookup_failed,
-    log_overhead,
-    log_total_executor_time,
-    log_udf_time,
-    node_executor,
+use crate::{
+    metrics::{
+        log_download_time,
+        log_external_deps_size_bytes_total,
+        log_function_execution,
+        log_import_time,
+        log_node_source_map_missing,
+        log_node_source_map_token_lookup_failed,
+        log_overhead,
+        log_total_executor_time,
+        log_udf_time,
+        node_executor,
+    },
+    sandbox::ResourceLimitViolation,
 };
 
 pub fn error_response_json(message: &str) -> JsonValue {
@@ -118,6 +118,9 @@ pub struct InvokeResponse {
     pub response: JsonValue,
     pub memory_used_in_mb: u64,
     pub aws_request_id: Option<String>,
+    /// Set if the executor killed the process for exceeding a sandbox limit.
+    /// The `response` is then an error response describing the violation.
+    pub resource_limit_violation: Option<ResourceLimitViolation>,
 }
 
 #[derive(Clone)]
@@ -213,6 +216,7 @@ impl Actions {
             response,
             memory_used_in_mb,
             aws_request_id,
+            resource_limit_violation,
         } = self.executor.invoke(request, log_line_sender).await?;
         let execute_result = ExecuteResponse::try_from(response.clone()).map_err(|e| {
             anyhow::anyhow!(
@@ -275,6 +279,7 @@ impl Actions {
             result,
             syscall_trace,
             memory_used_in_mb,
+            resource_limit_violation,
         })
     }
 
@@ -290,6 +295,7 @@ impl Actions {
             response,
             memory_used_in_mb: _,
             aws_request_id,
+            resource_limit_violation: _,
         } = self.executor.invoke(request, log_line_sender).await?;
         let response: BuildDepsResponse =
             serde_json::from_value(response.clone()).map_err(|e| {
@@ -350,6 +356,7 @@ impl Actions {
             response,
             memory_used_in_mb: _,
             aws_request_id,
+            resource_limit_violation: _,
         } = self.executor.invoke(request, log_line_sender).await?;
         let response: AnalyzeResponse = serde_json::from_value(response.clone()).map_err(|e| {
             anyhow::anyhow!(
@@ -496,13 +503,8 @@ impl TryFrom<ExecutorRequest> for JsonValue {
                 let environment_variables: Vec<JsonValue> = r
                     .environment_variables
                     .into_iter()
-                    .map(|(name, value)| {
-                        ConvexObject::try_from(PersistedEnvironmentVariable(
-                            EnvironmentVariable::new(name, value),
-                        ))
-                        .map(JsonValue::from)
-                    })
-                    .collect::<anyhow::Result<_>>()?;
+                    .map(|(name, value)| json!({ "name": name.0, "value": value.0 }))
+                    .collect();
                 let (udf_path, args, npm_version) = r.path_and_args.consume();
 
                 json!({
@@ -529,13 +531,8 @@ impl TryFrom<ExecutorRequest> for JsonValue {
                 let environment_variables: Vec<JsonValue> = r
                     .environment_variables
                     .into_iter()
-                    .map(|(name, value)| {
-                        ConvexObject::try_from(PersistedEnvironmentVariable(
-                            EnvironmentVariable::new(name, value),
-                        ))
-                        .map(JsonValue::from)
-                    })
-                    .collect::<anyhow::Result<_>>()?;
+                    .map(|(name, value)| json!({ "name": name.0, "value": value.0 }))
+                    .collect();
                 json!({
                     "type": "analyze",
                     "sourcePackage": JsonValue::from(r.source_package),
@@ -651,6 +648,8 @@ pub struct NodeActionOutcome {
     pub result: Result<ConvexValue, JsError>,
     pub syscall_trace: SyscallTrace,
     pub memory_used_in_mb: u64,
+    /// Set if `result` is an error because the action exceeded a sandbox limit.
+    pub resource_limit_violation: Option<ResourceLimitViolation>,
 }
 
 fn duration_from_millis_float(t: f64) -> Duration {
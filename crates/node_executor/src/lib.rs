@@ -11,6 +11,7 @@
 mod executor;
 pub mod local;
 mod metrics;
+mod sandbox;
 pub mod source_package;
 
 pub use crate::executor::{
@@ -29,3 +30,7 @@ pub use crate::executor::{
     SourcePackage,
     EXECUTE_TIMEOUT_RESPONSE_JSON,
 };
+pub use crate::sandbox::{
+    NodeSandboxConfig,
+    ResourceLimitViolation,
+};
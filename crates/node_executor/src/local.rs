@@ -9,6 +9,7 @@ use async_trait::async_trait;
 use common::log_lines::LogLine;
 use futures::{
     channel::mpsc,
+    pin_mut,
     select_biased,
     FutureExt,
     StreamExt,
@@ -22,13 +23,21 @@ use tokio_process_stream::{
     ProcessLineStream,
 };
 
-use crate::executor::{
-    parse_streamed_response,
-    ExecutorRequest,
-    InvokeResponse,
-    NodeExecutor,
-    ResponsePart,
-    EXECUTE_TIMEOUT_RESPONSE_JSON,
+use crate::{
+    executor::{
+        error_response_json,
+        parse_streamed_response,
+        ExecutorRequest,
+        InvokeResponse,
+        NodeExecutor,
+        ResponsePart,
+        EXECUTE_TIMEOUT_RESPONSE_JSON,
+    },
+    metrics::log_resource_limit_violation,
+    sandbox::{
+        NodeSandboxConfig,
+        StderrTail,
+    },
 };
 
 /// Always use node version specified in .nvmrc for lambda execution, even if
@@ -40,10 +49,18 @@ pub struct LocalNodeExecutor {
     source_path: PathBuf,
     node_path: String,
     node_process_timeout: Duration,
+    sandbox: NodeSandboxConfig,
 }
 
 impl LocalNodeExecutor {
     pub fn new(node_process_timeout: Duration) -> anyhow::Result<Self> {
+        Self::new_with_sandbox(node_process_timeout, NodeSandboxConfig::default())
+    }
+
+    pub fn new_with_sandbox(
+        node_process_timeout: Duration,
+        sandbox: NodeSandboxConfig,
+    ) -> anyhow::Result<Self> {
         // Write the source of local.cjs to a temp file.
         let source_dir = TempDir::new()?;
         let (source, source_map) =
@@ -76,6 +93,7 @@ impl LocalNodeExecutor {
             source_path,
             node_path,
             node_process_timeout,
+            sandbox,
         })
     }
 
@@ -107,6 +125,20 @@ impl NodeExecutor for LocalNodeExecutor {
         request: ExecutorRequest,
         log_line_sender: mpsc::UnboundedSender<LogLine>,
     ) -> anyhow::Result<InvokeResponse> {
+        let mut request = request;
+        let backend_address = match request {
+            ExecutorRequest::Execute {
+                ref mut backend_address,
+                ..
+            } => {
+                let original = backend_address.to_string();
+                if self.sandbox.network_isolation {
+                    *backend_address = self.sandbox.isolated_backend_address(&original)?.into();
+                }
+                Some(original)
+            },
+            ExecutorRequest::Analyze(_) | ExecutorRequest::BuildDeps(_) => None,
+        };
         let request = JsonValue::try_from(request)?;
         self.check_version().await?;
         let request = serde_json::to_string(&request)?;
@@ -117,14 +149,21 @@ impl NodeExecutor for LocalNodeExecutor {
             &request,
         );
         let mut _cmd = TokioCommand::new(&self.node_path);
+        // Keep the scratch directory alive until the process exits.
+        let mut sandboxed = self.sandbox.apply(&mut _cmd, backend_address.as_deref())?;
         let cmd = _cmd
+            .args(self.sandbox.node_args())
             .arg(&self.source_path)
             .arg("--request")
             .arg(request)
             .kill_on_drop(true);
         let mut result_values = vec![];
+        let mut stderr = StderrTail::default();
+        let mut resource_limit_violation = None;
 
         let mut procstream = ProcessLineStream::try_from(cmd)?.fuse();
+        let relay = sandboxed.relay()?.fuse();
+        pin_mut!(relay);
 
         let response = loop {
             select_biased! {
@@ -142,17 +181,25 @@ impl NodeExecutor for LocalNodeExecutor {
                             }
                         },
                         Item::Done(status) => {
-                            anyhow::ensure!(status?.success(), "Local process did not exit successfully");
+                            let status = status?;
+                            if let Some(violation) = self.sandbox.classify_exit(&status, &stderr) {
+                                log_resource_limit_violation(&violation);
+                                let response = error_response_json(&violation.to_string());
+                                resource_limit_violation = Some(violation);
+                                break response;
+                            }
+                            anyhow::ensure!(status.success(), "Local process did not exit successfully");
                             anyhow::ensure!(result_values.len() <= 1, "Received more than one result from lambda response");
                             let value = result_values.pop().ok_or_else(|| anyhow::anyhow!("Received no result from lambda response"))?;
                             break value;
                         }
-                        Item::Stderr(_) => ()
+                        Item::Stderr(line) => stderr.push(line),
                     }
                 },
                 _ = tokio::time::sleep(self.node_process_timeout).fuse() => {
                     break EXECUTE_TIMEOUT_RESPONSE_JSON.clone();
                 },
+                () = relay => {},
             }
         };
         Ok(InvokeResponse {
@@ -160,6 +207,7 @@ impl NodeExecutor for LocalNodeExecutor {
             // constant is good enough for measuring local executor
             memory_used_in_mb: 512,
             aws_request_id: None,
+            resource_limit_violation,
         })
     }
 
@@ -245,6 +293,8 @@ mod tests {
         Actions,
         AnalyzeRequest,
         ExecuteRequest,
+        NodeSandboxConfig,
+        ResourceLimitViolation,
         SourcePackage,
     };
 
@@ -760,6 +810,107 @@ mod tests {
         Ok(())
     }
 
+    #[convex_macro::prod_rt_test]
+    async fn test_cpu_time_limit(rt: ProdRuntime) -> anyhow::Result<()> {
+        let storage = Arc::new(LocalDirStorage::new(rt)?);
+        let sandbox = NodeSandboxConfig {
+            cpu_time_limit: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        // Leave plenty of wall clock time, since a loaded machine may take a
+        // while to spend one second of CPU time.
+        let actions = Actions::new(
+            Arc::new(LocalNodeExecutor::new_with_sandbox(
+                Duration::from_secs(120),
+                sandbox,
+            )?),
+            TEST_BACKEND_ADDRESS.into(),
+            Duration::from_secs(60),
+        );
+        let source_package = upload_modules(storage.clone(), TEST_SOURCE.clone()).await?;
+        let source_maps = BTreeMap::new();
+        let path_and_args = ValidatedUdfPathAndArgs::new_for_tests(
+            "node_actions.js:workHardForAnHour".parse()?,
+            array![],
+            VERSION.clone(),
+        );
+        let (response, _log_lines) = execute(
+            &actions,
+            execute_request(path_and_args, source_package),
+            &source_maps,
+        )
+        .await?;
+        // The busy loop should exhaust the CPU rlimit before either timeout.
+        assert_eq!(
+            response.resource_limit_violation,
+            Some(ResourceLimitViolation::CpuTime {
+                limit: Duration::from_secs(1)
+            })
+        );
+        assert_eq!(
+            &response.result.unwrap_err().message[..],
+            "Node action exceeded its CPU time limit of 1s."
+        );
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[convex_macro::prod_rt_test]
+    async fn test_network_isolation(rt: ProdRuntime) -> anyhow::Result<()> {
+        let storage = Arc::new(LocalDirStorage::new(rt)?);
+        let sandbox = NodeSandboxConfig {
+            network_isolation: true,
+            ..Default::default()
+        };
+        let actions = Actions::new(
+            Arc::new(LocalNodeExecutor::new_with_sandbox(
+                TEST_NODE_PROCESS_TIMEOUT,
+                sandbox,
+            )?),
+            TEST_BACKEND_ADDRESS.into(),
+            TEST_USER_TIMEOUT,
+        );
+        let source_package = upload_modules(storage.clone(), TEST_SOURCE.clone()).await?;
+        let source_maps = BTreeMap::new();
+        // The namespace has no route out, so a direct fetch fails right away.
+        let path_and_args = ValidatedUdfPathAndArgs::new_for_tests(
+            "node_actions.js:fetchUrl".parse()?,
+            create_args(assert_obj!("url" => "http://1.1.1.1/"))?,
+            VERSION.clone(),
+        );
+        let (response, _log_lines) = execute(
+            &actions,
+            execute_request(path_and_args, source_package.clone()),
+            &source_maps,
+        )
+        .await?;
+        let error = response.result.unwrap_err();
+        assert!(
+            error.message.contains("fetch failed"),
+            "Unexpected error: {}",
+            error.message
+        );
+        assert_eq!(response.resource_limit_violation, None);
+
+        // Actions still run normally.
+        let path_and_args = ValidatedUdfPathAndArgs::new_for_tests(
+            "node_actions.js:addNumbers".parse()?,
+            create_args(
+                assert_obj!("numbers" => ConvexValue::Array(array![2f64.into(), 3f64.into()]?)),
+            )?,
+            VERSION.clone(),
+        );
+        let (response, _log_lines) = execute(
+            &actions,
+            execute_request(path_and_args, source_package),
+            &source_maps,
+        )
+        .await?;
+        assert_eq!(response.result?, ConvexValue::from(5.));
+        Ok(())
+    }
+
     #[convex_macro::prod_rt_test]
     async fn test_deadlock(rt: ProdRuntime) -> anyhow::Result<()> {
         let storage = Arc::new(LocalDirStorage::new(rt)?);
@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use metrics::{
     log_counter,
+    log_counter_with_labels,
     log_distribution,
     log_distribution_with_labels,
     register_convex_counter,
@@ -11,6 +12,8 @@ use metrics::{
 };
 use model::source_packages::types::PackageSize;
 
+use crate::sandbox::ResourceLimitViolation;
+
 register_convex_histogram!(
     NODE_EXECUTOR_TOTAL_SECONDS,
     "Duration of Node executor",
@@ -118,3 +121,21 @@ pub fn log_external_deps_size_bytes_total(pkg_size: PackageSize) {
         vec![unzipped_label],
     );
 }
+
+register_convex_counter!(
+    NODE_EXECUTOR_RESOURCE_LIMIT_VIOLATIONS_TOTAL,
+    "Number of Node processes killed for exceeding a sandbox limit",
+    &["limit"]
+);
+pub fn log_resource_limit_violation(violation: &ResourceLimitViolation) {
+    let limit = match violation {
+        ResourceLimitViolation::Memory { .. } => "memory",
+        ResourceLimitViolation::CpuTime { .. } => "cpu_time",
+        ResourceLimitViolation::OpenFiles { .. } => "open_files",
+    };
+    log_counter_with_labels(
+        &NODE_EXECUTOR_RESOURCE_LIMIT_VIOLATIONS_TOTAL,
+        1,
+        vec![MetricLabel::new("limit", limit)],
+    );
+}
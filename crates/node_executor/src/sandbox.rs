@@ -0,0 +1,726 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    io,
+    os::unix::{
+        net::UnixStream,
+        process::ExitStatusExt,
+    },
+    path::PathBuf,
+    process::ExitStatus,
+    time::Duration,
+};
+
+use futures::{
+    future,
+    stream,
+    Future,
+    StreamExt,
+};
+use tempfile::TempDir;
+use tokio::{
+    net::{
+        TcpListener,
+        TcpStream,
+    },
+    process::Command as TokioCommand,
+};
+use url::{
+    Host,
+    Url,
+};
+
+/// V8 reserves far more virtual address space than it ever commits, so the
+/// address space rlimit leaves this much headroom over the heap limit. The
+/// precise enforcement comes from `--max-old-space-size`.
+const ADDRESS_SPACE_HEADROOM_MB: u64 = 4096;
+
+/// Number of trailing stderr lines we keep around to classify abnormal exits.
+const MAX_STDERR_LINES: usize = 64;
+
+/// Port the HTTP proxy is relayed to inside an isolated network namespace.
+const ISOLATED_PROXY_PORT: u16 = 3128;
+
+/// Environment variables that tell local.cjs how to reach the outside from an
+/// isolated network namespace: the backend hostname to resolve to the
+/// loopback interface, and the proxy to send all other `fetch` requests to.
+const ISOLATED_BACKEND_HOST_ENV: &str = "CONVEX_ISOLATED_BACKEND_HOST";
+const ISOLATED_PROXY_ENV: &str = "CONVEX_ISOLATED_PROXY";
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type RlimitResource = libc::c_int;
+
+/// Per-invocation resource limits for Node processes spawned by the
+/// [`crate::local::LocalNodeExecutor`]. Every limit is optional, and the
+/// default config leaves the process unrestricted.
+#[derive(Clone, Debug, Default)]
+pub struct NodeSandboxConfig {
+    /// Maximum V8 heap size, in megabytes.
+    pub memory_limit_mb: Option<u64>,
+    /// Maximum CPU time the process may consume.
+    pub cpu_time_limit: Option<Duration>,
+    /// Maximum number of open file descriptors.
+    pub open_files_limit: Option<u64>,
+    /// If set, each invocation gets a fresh scratch directory under this path
+    /// as its working directory and `TMPDIR`. It is removed when the
+    /// invocation finishes.
+    pub tmp_dir_root: Option<PathBuf>,
+    /// If set, Node actions run in their own network namespace with only a
+    /// loopback interface, so they can't connect to anything directly. The
+    /// executor relays connections to the backend and to `network_proxy`
+    /// into the namespace. Requires unprivileged user namespaces (Linux only),
+    /// and source packages in local storage, since `file://` URLs are the
+    /// only ones the process can still download.
+    pub network_isolation: bool,
+    /// Proxy that isolated processes send `fetch` requests through, also
+    /// advertised through `HTTP_PROXY` and `HTTPS_PROXY` for other clients.
+    /// It's the only way out of the namespace.
+    pub network_proxy: Option<Url>,
+}
+
+/// The parts of a sandbox that must live as long as the process.
+pub(crate) struct SandboxedProcess {
+    _scratch_dir: Option<TempDir>,
+    relay: Option<PendingRelay>,
+}
+
+/// The executor's end of the socket over which an isolated process sends the
+/// listeners it opened in its network namespace, one per `targets` entry.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct PendingRelay {
+    socket: UnixStream,
+    targets: Vec<String>,
+}
+
+impl NodeSandboxConfig {
+    /// Node flags that must precede the script path on the command line.
+    pub(crate) fn node_args(&self) -> Vec<String> {
+        self.memory_limit_mb
+            .map(|mb| format!("--max-old-space-size={mb}"))
+            .into_iter()
+            .collect()
+    }
+
+    /// The address an isolated process reaches the backend at. Either way
+    /// it connects to the backend's port on the namespace's loopback
+    /// interface. Plain-http loopback addresses are rewritten to `127.0.0.1`,
+    /// which keeps URLs that the backend hands out for `127.0.0.1` working.
+    /// Other addresses keep their hostname, which the process resolves to the
+    /// loopback interface, so TLS still verifies the backend's certificate.
+    pub(crate) fn isolated_backend_address(&self, backend_address: &str) -> anyhow::Result<String> {
+        let mut url = Url::parse(backend_address)?;
+        let port = backend_port(&url)?;
+        if url.scheme() == "http" && is_loopback(&url) {
+            url.set_host(Some("127.0.0.1"))?;
+            url.set_port(Some(port))
+                .map_err(|()| anyhow::anyhow!("Can't set port of {backend_address}"))?;
+            return Ok(url.as_str().trim_end_matches('/').to_string());
+        }
+        anyhow::ensure!(
+            matches!(url.host(), Some(Host::Domain(_))) || is_loopback(&url),
+            "Network isolation needs a hostname or loopback address for the backend, not \
+             {backend_address}"
+        );
+        Ok(backend_address.to_string())
+    }
+
+    /// Configure `cmd` to run inside the sandbox. If `backend_address` is
+    /// set and network isolation is on, the process gets its own network
+    /// namespace. The returned [`SandboxedProcess`] must be kept alive until
+    /// the process exits.
+    pub(crate) fn apply(
+        &self,
+        cmd: &mut TokioCommand,
+        backend_address: Option<&str>,
+    ) -> anyhow::Result<SandboxedProcess> {
+        let scratch_dir = match self.tmp_dir_root {
+            Some(ref root) => {
+                let dir = tempfile::Builder::new()
+                    .prefix("node-action-")
+                    .tempdir_in(root)?;
+                cmd.current_dir(dir.path())
+                    .env("TMPDIR", dir.path())
+                    .env("TMP", dir.path())
+                    .env("TEMP", dir.path());
+                Some(dir)
+            },
+            None => None,
+        };
+        let relay = match backend_address {
+            Some(backend_address) if self.network_isolation => {
+                Some(self.isolate_network(cmd, backend_address)?)
+            },
+            _ => None,
+        };
+
+        let rlimits = self.rlimits();
+        if !rlimits.is_empty() {
+            // SAFETY: The closure runs in the forked child before `exec` and
+            // only calls `setrlimit`, which is async-signal-safe.
+            unsafe {
+                cmd.pre_exec(move || {
+                    for (resource, soft, hard) in &rlimits {
+                        let limit = libc::rlimit {
+                            rlim_cur: *soft,
+                            rlim_max: *hard,
+                        };
+                        if libc::setrlimit(*resource, &limit) != 0 {
+                            return Err(io::Error::last_os_error());
+                        }
+                    }
+                    Ok(())
+                });
+            }
+        }
+        Ok(SandboxedProcess {
+            _scratch_dir: scratch_dir,
+            relay,
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    fn isolate_network(
+        &self,
+        cmd: &mut TokioCommand,
+        backend_address: &str,
+    ) -> anyhow::Result<PendingRelay> {
+        use std::os::fd::AsRawFd;
+
+        let backend_url = Url::parse(backend_address)?;
+        let backend_port = backend_port(&backend_url)?;
+        let mut ports = vec![backend_port];
+        let mut targets = vec![format!(
+            "{}:{backend_port}",
+            backend_url.host_str().unwrap_or("127.0.0.1")
+        )];
+        let mut no_proxy = "localhost,127.0.0.1".to_string();
+        if let Some(Host::Domain(backend_host)) = backend_url.host() {
+            cmd.env(ISOLATED_BACKEND_HOST_ENV, backend_host);
+            no_proxy = format!("{no_proxy},{backend_host}");
+        }
+        if let Some(ref proxy) = self.network_proxy {
+            anyhow::ensure!(
+                backend_port != ISOLATED_PROXY_PORT,
+                "The backend can't use port {ISOLATED_PROXY_PORT} with network isolation"
+            );
+            let proxy_port = proxy
+                .port_or_known_default()
+                .ok_or_else(|| anyhow::anyhow!("Proxy URL {proxy} has no port"))?;
+            let proxy_host = proxy
+                .host_str()
+                .ok_or_else(|| anyhow::anyhow!("Proxy URL {proxy} has no host"))?;
+            ports.push(ISOLATED_PROXY_PORT);
+            targets.push(format!("{proxy_host}:{proxy_port}"));
+
+            let mut isolated_proxy = proxy.clone();
+            isolated_proxy.set_host(Some("127.0.0.1"))?;
+            isolated_proxy
+                .set_port(Some(ISOLATED_PROXY_PORT))
+                .map_err(|()| anyhow::anyhow!("Can't set port of {proxy}"))?;
+            for (name, value) in [
+                (ISOLATED_PROXY_ENV, isolated_proxy.as_str()),
+                ("HTTP_PROXY", isolated_proxy.as_str()),
+                ("HTTPS_PROXY", isolated_proxy.as_str()),
+                ("http_proxy", isolated_proxy.as_str()),
+                ("https_proxy", isolated_proxy.as_str()),
+                ("NO_PROXY", no_proxy.as_str()),
+                ("no_proxy", no_proxy.as_str()),
+            ] {
+                cmd.env(name, value);
+            }
+        }
+
+        let (socket, child_socket) = UnixStream::pair()?;
+        // SAFETY: The closure runs in the forked child before `exec` and only
+        // makes async-signal-safe system calls, without allocating.
+        unsafe {
+            cmd.pre_exec(move || {
+                isolation::enter_isolated_network(&ports, child_socket.as_raw_fd())
+            });
+        }
+        Ok(PendingRelay { socket, targets })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn isolate_network(
+        &self,
+        _cmd: &mut TokioCommand,
+        _backend_address: &str,
+    ) -> anyhow::Result<PendingRelay> {
+        anyhow::bail!("Network isolation for Node actions is only supported on Linux")
+    }
+
+    fn rlimits(&self) -> Vec<(RlimitResource, libc::rlim_t, libc::rlim_t)> {
+        let mut rlimits = vec![];
+        if let Some(mb) = self.memory_limit_mb {
+            let bytes = (mb + ADDRESS_SPACE_HEADROOM_MB) * (1 << 20);
+            rlimits.push((libc::RLIMIT_AS, bytes, bytes));
+        }
+        if let Some(cpu_time) = self.cpu_time_limit {
+            // Deliver SIGXCPU at the soft limit, which Node doesn't handle, so
+            // the process dies of it and we can tell a CPU limit violation
+            // apart from other kills. The hard limit SIGKILLs a second later
+            // if SIGXCPU is somehow ignored.
+            let secs = cpu_time.as_secs().max(1);
+            rlimits.push((libc::RLIMIT_CPU, secs, secs + 1));
+        }
+        if let Some(open_files) = self.open_files_limit {
+            rlimits.push((libc::RLIMIT_NOFILE, open_files, open_files));
+        }
+        rlimits
+    }
+
+    /// Determine whether a Node process that exited with `status` did so
+    /// because it hit one of our limits.
+    pub(crate) fn classify_exit(
+        &self,
+        status: &ExitStatus,
+        stderr: &StderrTail,
+    ) -> Option<ResourceLimitViolation> {
+        if status.success() {
+            return None;
+        }
+        if let Some(limit_mb) = self.memory_limit_mb
+            && stderr.contains_any(&["JavaScript heap out of memory", "Allocation failed"])
+        {
+            return Some(ResourceLimitViolation::Memory { limit_mb });
+        }
+        // A SIGKILL could also be the OOM killer, so only SIGXCPU counts.
+        if let Some(limit) = self.cpu_time_limit
+            && status.signal() == Some(libc::SIGXCPU)
+        {
+            return Some(ResourceLimitViolation::CpuTime { limit });
+        }
+        if let Some(limit) = self.open_files_limit
+            && stderr.contains_any(&["EMFILE", "too many open files"])
+        {
+            return Some(ResourceLimitViolation::OpenFiles { limit });
+        }
+        None
+    }
+}
+
+impl SandboxedProcess {
+    /// Relays connections from an isolated process to the backend and proxy
+    /// until the returned future is dropped. Call after the process has been
+    /// spawned.
+    pub(crate) fn relay(&mut self) -> anyhow::Result<impl Future<Output = ()>> {
+        let listeners = match self.relay.take() {
+            Some(pending) => pending.listeners()?,
+            None => vec![],
+        };
+        Ok(async move {
+            future::join_all(
+                listeners
+                    .into_iter()
+                    .map(|(listener, target)| relay_connections(listener, target)),
+            )
+            .await;
+        })
+    }
+}
+
+impl PendingRelay {
+    #[cfg(target_os = "linux")]
+    fn listeners(self) -> anyhow::Result<Vec<(TcpListener, String)>> {
+        let fds = isolation::receive_fds(&self.socket, self.targets.len())?;
+        fds.into_iter()
+            .zip(self.targets)
+            .map(|(fd, target)| {
+                let listener = std::net::TcpListener::from(fd);
+                listener.set_nonblocking(true)?;
+                Ok((TcpListener::from_std(listener)?, target))
+            })
+            .collect()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn listeners(self) -> anyhow::Result<Vec<(TcpListener, String)>> {
+        anyhow::bail!("Network isolation for Node actions is only supported on Linux")
+    }
+}
+
+async fn relay_connections(listener: TcpListener, target: String) {
+    stream::unfold(listener, |listener| async move {
+        let accepted = listener.accept().await;
+        Some((accepted, listener))
+    })
+    .for_each_concurrent(None, |accepted| {
+        let target = &target;
+        async move {
+            let mut inbound = match accepted {
+                Ok((inbound, _)) => inbound,
+                Err(e) => {
+                    tracing::warn!("Failed to accept connection from Node process: {e}");
+                    return;
+                },
+            };
+            match TcpStream::connect(target).await {
+                Ok(mut outbound) => {
+                    _ = tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await;
+                },
+                Err(e) => {
+                    tracing::warn!("Failed to relay connection from Node process to {target}: {e}")
+                },
+            }
+        }
+    })
+    .await
+}
+
+fn is_loopback(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain == "localhost",
+        Some(Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+fn backend_port(url: &Url) -> anyhow::Result<u16> {
+    url.port_or_known_default()
+        .ok_or_else(|| anyhow::anyhow!("Backend address {url} has no port"))
+}
+
+/// System calls for running a process in its own network namespace. The
+/// child opens a listener in the namespace for each relayed port and sends
+/// them to the executor over a Unix socket before `exec`, so connections
+/// queue up until the executor starts relaying.
+#[cfg(target_os = "linux")]
+mod isolation {
+    use std::{
+        io,
+        mem,
+        net::Ipv4Addr,
+        os::{
+            fd::{
+                AsRawFd,
+                FromRawFd,
+                OwnedFd,
+                RawFd,
+            },
+            unix::net::UnixStream,
+        },
+        ptr,
+    };
+
+    /// Most listeners a process sends: the backend and the proxy.
+    const MAX_RELAYED_PORTS: usize = 2;
+    /// Large enough for `CMSG_SPACE` of `MAX_RELAYED_PORTS` descriptors, and
+    /// aligned like `cmsghdr`.
+    type ControlBuffer = [u64; 8];
+
+    fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(result)
+    }
+
+    /// Runs in the forked child, so it must not allocate.
+    pub(super) fn enter_isolated_network(ports: &[u16], socket: RawFd) -> io::Result<()> {
+        if ports.len() > MAX_RELAYED_PORTS {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+        // SAFETY: Plain system calls on descriptors we own and stack buffers.
+        unsafe {
+            // A new user namespace grants the capabilities needed to create
+            // and configure a network namespace without privileges.
+            check(libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET))?;
+            bring_up_loopback()?;
+            let mut listeners = [-1; MAX_RELAYED_PORTS];
+            for (listener, port) in listeners.iter_mut().zip(ports) {
+                *listener = loopback_listener(*port)?;
+            }
+            send_fds(socket, &listeners[..ports.len()])
+        }
+    }
+
+    /// A new network namespace starts with its loopback interface down.
+    unsafe fn bring_up_loopback() -> io::Result<()> {
+        let sock = check(libc::socket(
+            libc::AF_INET,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            0,
+        ))?;
+        let mut request: libc::ifreq = mem::zeroed();
+        for (dst, src) in request.ifr_name.iter_mut().zip(b"lo\0") {
+            *dst = *src as libc::c_char;
+        }
+        let result =
+            check(libc::ioctl(sock, libc::SIOCGIFFLAGS as _, &mut request)).and_then(|_| {
+                request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+                check(libc::ioctl(sock, libc::SIOCSIFFLAGS as _, &request))
+            });
+        libc::close(sock);
+        result.map(|_| ())
+    }
+
+    unsafe fn loopback_listener(port: u16) -> io::Result<RawFd> {
+        let fd = check(libc::socket(
+            libc::AF_INET,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+            0,
+        ))?;
+        let address = libc::sockaddr_in {
+            sin_family: libc::AF_INET as libc::sa_family_t,
+            sin_port: port.to_be(),
+            sin_addr: libc::in_addr {
+                s_addr: u32::from(Ipv4Addr::LOCALHOST).to_be(),
+            },
+            sin_zero: [0; 8],
+        };
+        check(libc::bind(
+            fd,
+            &address as *const libc::sockaddr_in as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        ))?;
+        check(libc::listen(fd, 128))?;
+        Ok(fd)
+    }
+
+    unsafe fn send_fds(socket: RawFd, fds: &[RawFd]) -> io::Result<()> {
+        let mut byte = [0u8];
+        let mut iov = libc::iovec {
+            iov_base: byte.as_mut_ptr() as *mut libc::c_void,
+            iov_len: byte.len(),
+        };
+        let mut control: ControlBuffer = [0; 8];
+        let data_len = mem::size_of_val(fds) as libc::c_uint;
+        let mut message: libc::msghdr = mem::zeroed();
+        message.msg_iov = &mut iov;
+        message.msg_iovlen = 1;
+        message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        message.msg_controllen = libc::CMSG_SPACE(data_len) as _;
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_RIGHTS;
+        (*header).cmsg_len = libc::CMSG_LEN(data_len) as _;
+        ptr::copy_nonoverlapping(
+            fds.as_ptr(),
+            libc::CMSG_DATA(header) as *mut RawFd,
+            fds.len(),
+        );
+        if libc::sendmsg(socket, &message, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Receive the `count` listeners a spawned process sent. The process
+    /// sends them before `exec`, which `spawn` waits for, so this doesn't
+    /// block.
+    pub(super) fn receive_fds(socket: &UnixStream, count: usize) -> anyhow::Result<Vec<OwnedFd>> {
+        anyhow::ensure!(count <= MAX_RELAYED_PORTS);
+        let mut byte = [0u8];
+        let mut iov = libc::iovec {
+            iov_base: byte.as_mut_ptr() as *mut libc::c_void,
+            iov_len: byte.len(),
+        };
+        let mut control: ControlBuffer = [0; 8];
+        // SAFETY: `message` points at live stack buffers, and we only read
+        // control messages the kernel filled in.
+        unsafe {
+            let mut message: libc::msghdr = mem::zeroed();
+            message.msg_iov = &mut iov;
+            message.msg_iovlen = 1;
+            message.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            message.msg_controllen = mem::size_of::<ControlBuffer>() as _;
+            if libc::recvmsg(socket.as_raw_fd(), &mut message, libc::MSG_CMSG_CLOEXEC) < 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+            let header = libc::CMSG_FIRSTHDR(&message);
+            anyhow::ensure!(
+                !header.is_null()
+                    && (*header).cmsg_level == libc::SOL_SOCKET
+                    && (*header).cmsg_type == libc::SCM_RIGHTS,
+                "Node process didn't send its network listeners"
+            );
+            let data_len = (*header).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+            let received = data_len / mem::size_of::<RawFd>();
+            let data = libc::CMSG_DATA(header) as *const RawFd;
+            let fds: Vec<_> = (0..received)
+                .map(|i| OwnedFd::from_raw_fd(ptr::read_unaligned(data.add(i))))
+                .collect();
+            anyhow::ensure!(
+                fds.len() == count,
+                "Expected {count} listeners from Node process, got {}",
+                fds.len()
+            );
+            Ok(fds)
+        }
+    }
+}
+
+/// A sandbox limit that a Node process exceeded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResourceLimitViolation {
+    Memory { limit_mb: u64 },
+    CpuTime { limit: Duration },
+    OpenFiles { limit: u64 },
+}
+
+impl fmt::Display for ResourceLimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Memory { limit_mb } => write!(
+                f,
+                "Node action ran out of memory (limit: {limit_mb} MB). Reduce the amount of data \
+                 held in memory at once."
+            ),
+            Self::CpuTime { limit } => {
+                write!(f, "Node action exceeded its CPU time limit of {:?}.", limit)
+            },
+            Self::OpenFiles { limit } => write!(
+                f,
+                "Node action exceeded its limit of {limit} open file descriptors."
+            ),
+        }
+    }
+}
+
+/// The most recent lines a Node process wrote to stderr.
+#[derive(Default)]
+pub(crate) struct StderrTail {
+    lines: VecDeque<String>,
+}
+
+impl StderrTail {
+    pub(crate) fn push(&mut self, line: String) {
+        if self.lines.len() == MAX_STDERR_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    fn contains_any(&self, needles: &[&str]) -> bool {
+        self.lines
+            .iter()
+            .any(|line| needles.iter().any(|needle| line.contains(needle)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        os::unix::process::ExitStatusExt,
+        process::ExitStatus,
+        time::Duration,
+    };
+
+    use super::{
+        NodeSandboxConfig,
+        ResourceLimitViolation,
+        StderrTail,
+    };
+
+    fn config() -> NodeSandboxConfig {
+        NodeSandboxConfig {
+            memory_limit_mb: Some(256),
+            cpu_time_limit: Some(Duration::from_secs(10)),
+            open_files_limit: Some(64),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_node_args() {
+        assert_eq!(config().node_args(), vec!["--max-old-space-size=256"]);
+        assert!(NodeSandboxConfig::default().node_args().is_empty());
+    }
+
+    #[test]
+    fn test_classify_success() {
+        let status = ExitStatus::from_raw(0);
+        assert_eq!(
+            config().classify_exit(&status, &StderrTail::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_classify_out_of_memory() {
+        let mut stderr = StderrTail::default();
+        stderr.push(
+            "FATAL ERROR: Reached heap limit Allocation failed - JavaScript heap out of memory"
+                .to_string(),
+        );
+        // Exited via SIGABRT.
+        let status = ExitStatus::from_raw(libc::SIGABRT);
+        assert_eq!(
+            config().classify_exit(&status, &stderr),
+            Some(ResourceLimitViolation::Memory { limit_mb: 256 })
+        );
+        // Without a configured limit we don't blame the sandbox.
+        assert_eq!(
+            NodeSandboxConfig::default().classify_exit(&status, &stderr),
+            None
+        );
+    }
+
+    #[test]
+    fn test_classify_cpu_time() {
+        let status = ExitStatus::from_raw(libc::SIGXCPU);
+        assert_eq!(
+            config().classify_exit(&status, &StderrTail::default()),
+            Some(ResourceLimitViolation::CpuTime {
+                limit: Duration::from_secs(10)
+            })
+        );
+        // SIGKILL may come from the OOM killer instead.
+        let status = ExitStatus::from_raw(libc::SIGKILL);
+        assert_eq!(
+            config().classify_exit(&status, &StderrTail::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_isolated_backend_address() -> anyhow::Result<()> {
+        let config = NodeSandboxConfig {
+            network_isolation: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.isolated_backend_address("http://localhost:3210")?,
+            "http://127.0.0.1:3210"
+        );
+        // Hostnames are kept so TLS can verify them.
+        assert_eq!(
+            config.isolated_backend_address("https://backend.example.com")?,
+            "https://backend.example.com"
+        );
+        assert_eq!(
+            config.isolated_backend_address("http://backend.internal:3210")?,
+            "http://backend.internal:3210"
+        );
+        // Other IP addresses can't be reached from the namespace.
+        assert!(config
+            .isolated_backend_address("http://10.0.0.5:3210")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_classify_open_files() {
+        let mut stderr = StderrTail::default();
+        stderr.push("Error: EMFILE: too many open files, open '/tmp/foo'".to_string());
+        let status = ExitStatus::from_raw(1 << 8);
+        assert_eq!(
+            config().classify_exit(&status, &stderr),
+            Some(ResourceLimitViolation::OpenFiles { limit: 64 })
+        );
+    }
+
+    #[test]
+    fn test_stderr_tail_is_bounded() {
+        let mut stderr = StderrTail::default();
+        stderr.push("EMFILE".to_string());
+        for _ in 0..super::MAX_STDERR_LINES {
+            stderr.push("noise".to_string());
+        }
+        assert!(!stderr.contains_any(&["EMFILE"]));
+    }
+}
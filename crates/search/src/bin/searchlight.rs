@@ -0,0 +1,223 @@
+//! Standalone Searchlight server, running text and vector searches for
+//! backends started with `--searchlight-url`.
+//!
+//! Searchlight reads segments from the backend's local storage directory, so
+//! when it runs on another host that directory must be on a shared filesystem.
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use clap::Parser;
+use cmd_util::env::config_service;
+use common::{
+    errors::MainError,
+    http::serve_grpc,
+    version::COMPILED_REVISION,
+};
+use futures::FutureExt;
+use keybroker::{
+    InstanceSecret,
+    KeyBroker,
+    DEV_INSTANCE_NAME,
+    DEV_SECRET,
+};
+use runtime::prod::ProdRuntime;
+use search::searcher::{
+    SearcherImpl,
+    SearchlightService,
+};
+use storage::{
+    LocalDirStorage,
+    StorageUseCase,
+};
+use tokio::signal;
+
+#[derive(Parser, Clone)]
+#[clap(version = COMPILED_REVISION, author = "Convex, Inc. <no-reply@convex.dev>")]
+struct SearchlightConfig {
+    /// The backend's local storage directory, read for search index segments
+    #[clap(long, default_value = "convex_local_storage")]
+    local_storage: String,
+
+    /// Instance name of the backend, which must match its `--instance-name`
+    #[clap(long, requires = "instance_secret")]
+    instance_name: Option<String>,
+
+    /// Instance secret of the backend, used to authenticate its requests
+    #[clap(long, requires = "instance_name")]
+    instance_secret: Option<String>,
+
+    /// Directory for Searchlight's cache of fetched segments
+    #[clap(long, default_value = "searchlight_cache")]
+    cache_dir: String,
+
+    /// Maximum size in bytes of the segment cache on disk
+    #[clap(long, default_value_t = bytesize::gib(10u64))]
+    max_disk_cache_size: u64,
+
+    /// Log vector queries slower than this
+    #[clap(long, default_value = "100")]
+    slow_vector_query_threshold_millis: u64,
+
+    /// Use exact rather than approximate nearest neighbor vector search
+    #[clap(long)]
+    require_exact_vector_search: bool,
+
+    /// Host interface to bind to
+    #[clap(short, long, default_value = "0.0.0.0")]
+    interface: ::std::net::Ipv4Addr,
+
+    /// Host port to serve the Searchlight gRPC service on
+    #[clap(short, long, default_value = "3214")]
+    port: u16,
+
+    /// Host port to serve the IncrementalSearchlight gRPC service on
+    #[clap(long, default_value = "3215")]
+    incremental_port: u16,
+}
+
+impl std::fmt::Debug for SearchlightConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SearchlightConfig")
+            .field("local_storage", &self.local_storage)
+            .field("instance_name", &self.instance_name)
+            .field("cache_dir", &self.cache_dir)
+            .field("max_disk_cache_size", &self.max_disk_cache_size)
+            .field("interface", &self.interface)
+            .field("port", &self.port)
+            .field("incremental_port", &self.incremental_port)
+            .finish()
+    }
+}
+
+impl SearchlightConfig {
+    fn key_broker(&self) -> anyhow::Result<KeyBroker> {
+        let name = self
+            .instance_name
+            .clone()
+            .unwrap_or(DEV_INSTANCE_NAME.to_owned());
+        let secret = InstanceSecret::try_from(
+            self.instance_secret
+                .clone()
+                .unwrap_or(DEV_SECRET.to_owned())
+                .as_str(),
+        )?;
+        KeyBroker::new(&name, secret)
+    }
+}
+
+fn main() -> Result<(), MainError> {
+    tracing::info!("Starting Searchlight");
+    let _guard = config_service();
+    let config = SearchlightConfig::parse();
+    tracing::info!("Starting with config {:?}", config);
+
+    sodiumoxide::init().map_err(|()| anyhow!("sodiumoxide initialization failed"))?;
+
+    let tokio = ProdRuntime::init_tokio()?;
+    let runtime = ProdRuntime::new(&tokio);
+
+    let runtime_ = runtime.clone();
+    let server_future = async move {
+        run_server(runtime_, config).await?;
+        Ok(())
+    };
+
+    runtime.block_on("main", server_future)
+}
+
+async fn run_server(runtime: ProdRuntime, config: SearchlightConfig) -> anyhow::Result<()> {
+    let searcher = SearcherImpl::new(
+        &config.cache_dir,
+        config.max_disk_cache_size,
+        config.slow_vector_query_threshold_millis,
+        config.require_exact_vector_search,
+        runtime.clone(),
+    )
+    .await?;
+    let search_storage = Arc::new(LocalDirStorage::for_use_case(
+        runtime,
+        &config.local_storage,
+        StorageUseCase::SearchIndexes,
+    )?);
+    let service = SearchlightService::new(searcher, search_storage, config.key_broker()?);
+
+    let shutdown = signal::ctrl_c()
+        .map(|r| {
+            if let Err(e) = r {
+                tracing::error!("Failed to listen for Ctrl-C: {e}");
+            }
+            tracing::info!("Received Ctrl-C signal!");
+        })
+        .shared();
+    let interface = config.interface.octets();
+    futures::try_join!(
+        serve_grpc(
+            service.clone().into_server(),
+            (interface, config.port).into(),
+            shutdown.clone(),
+        ),
+        serve_grpc(
+            service.into_incremental_server(),
+            (interface, config.incremental_port).into(),
+            shutdown,
+        ),
+    )?;
+    tracing::info!("Server successfully shut down.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use keybroker::{
+        InstanceSecret,
+        KeyBroker,
+    };
+
+    use super::SearchlightConfig;
+
+    #[test]
+    fn test_config_defaults_to_dev_instance() -> anyhow::Result<()> {
+        let config = SearchlightConfig::try_parse_from(["searchlight"])?;
+        assert_eq!(config.local_storage, "convex_local_storage");
+        let system_key = KeyBroker::dev().issue_system_key().to_string();
+        assert!(config
+            .key_broker()?
+            .check_admin_key(&system_key)?
+            .is_system());
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_instance_secret() -> anyhow::Result<()> {
+        let secret = InstanceSecret::random();
+        let config = SearchlightConfig::try_parse_from([
+            "searchlight",
+            "--local-storage",
+            "/mnt/convex_local_storage",
+            "--instance-name",
+            "carnitas",
+            "--instance-secret",
+            &secret.to_string(),
+        ])?;
+        let system_key = KeyBroker::new("carnitas", secret)?
+            .issue_system_key()
+            .to_string();
+        assert!(config
+            .key_broker()?
+            .check_admin_key(&system_key)?
+            .is_system());
+        let dev_key = KeyBroker::dev().issue_system_key().to_string();
+        assert!(config.key_broker()?.check_admin_key(&dev_key).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_instance_name_requires_secret() {
+        assert!(
+            SearchlightConfig::try_parse_from(["searchlight", "--instance-name", "carnitas"])
+                .is_err()
+        );
+    }
+}
@@ -27,6 +27,21 @@ use itertools::{
     Itertools,
 };
 use maplit::btreemap;
+use pb::{
+    convex_token::{
+        search_text_query_term::Term::{
+            Exact as ExactProto,
+            Fuzzy as FuzzyProto,
+        },
+        SearchExactTextTerm as SearchExactTextTermProto,
+        SearchFuzzyTextTerm as SearchFuzzyTextTermProto,
+        SearchTextQueryTerm as SearchTextQueryTermProto,
+    },
+    funrun::{
+        FilterConditionRead as FilterConditionReadProto,
+        SearchQueryReads as SearchQueryReadsProto,
+    },
+};
 #[cfg(any(test, feature = "testing"))]
 use proptest::arbitrary::{
     any,
@@ -746,6 +761,102 @@ impl QueryReads {
     }
 }
 
+// The proto's `index_name` is owned by the enclosing read set, so these
+// conversions leave it unset and ignore it respectively.
+impl From<QueryReads> for SearchQueryReadsProto {
+    fn from(
+        QueryReads {
+            text_queries,
+            filter_conditions,
+            ..
+        }: QueryReads,
+    ) -> Self {
+        let text_queries = text_queries
+            .into_iter()
+            .map(|text_query_term| SearchTextQueryTermProto {
+                field_path: Some(text_query_term.field_path.clone().into()),
+                term: Some(match text_query_term.term {
+                    TextQueryTerm::Exact(token) => ExactProto(SearchExactTextTermProto { token }),
+                    TextQueryTerm::Fuzzy {
+                        token,
+                        max_distance,
+                        prefix,
+                    } => FuzzyProto(SearchFuzzyTextTermProto {
+                        token,
+                        max_distance: (*max_distance).into(),
+                        prefix,
+                    }),
+                }),
+            })
+            .collect();
+        SearchQueryReadsProto {
+            index_name: None,
+            text_queries,
+            filter_conditions: filter_conditions
+                .into_iter()
+                .map(|filter_condition| match filter_condition {
+                    FilterConditionRead::Must(field_path, filter_value) => {
+                        FilterConditionReadProto {
+                            field_path: Some(field_path.into()),
+                            filter_value: Some(filter_value),
+                        }
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<SearchQueryReadsProto> for QueryReads {
+    type Error = anyhow::Error;
+
+    fn try_from(
+        SearchQueryReadsProto {
+            index_name: _,
+            text_queries,
+            filter_conditions,
+        }: SearchQueryReadsProto,
+    ) -> anyhow::Result<Self> {
+        let text_queries = text_queries
+            .into_iter()
+            .map(|SearchTextQueryTermProto { field_path, term }| {
+                let field_path = field_path
+                    .ok_or_else(|| anyhow::anyhow!("Missing field_path"))?
+                    .try_into()?;
+                let term = match term.ok_or_else(|| anyhow::anyhow!("Missing term!"))? {
+                    ExactProto(exact) => TextQueryTerm::Exact(exact.token),
+                    FuzzyProto(fuzzy) => TextQueryTerm::Fuzzy {
+                        token: fuzzy.token,
+                        max_distance: u8::try_from(fuzzy.max_distance)?.try_into()?,
+                        prefix: fuzzy.prefix,
+                    },
+                };
+                anyhow::Ok(TextQueryTermRead::new(field_path, term))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into();
+        let filter_conditions = filter_conditions
+            .into_iter()
+            .map(
+                |FilterConditionReadProto {
+                     field_path,
+                     filter_value,
+                 }| {
+                    let q = FilterConditionRead::Must(
+                        field_path
+                            .ok_or_else(|| anyhow::anyhow!("Missing field_path"))?
+                            .try_into()?,
+                        filter_value.ok_or_else(|| anyhow::anyhow!("Missing filter_value"))?,
+                    );
+                    anyhow::Ok::<FilterConditionRead>(q)
+                },
+            )
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into();
+        Ok(QueryReads::new(text_queries, filter_conditions))
+    }
+}
+
 #[cfg(any(test, feature = "testing"))]
 impl Arbitrary for QueryReads {
     type Parameters = ();
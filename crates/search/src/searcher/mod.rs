@@ -2,7 +2,9 @@ mod in_process;
 mod metrics;
 #[allow(clippy::module_inception)]
 mod searcher;
+mod searchlight_client;
 mod searchlight_knobs;
+mod searchlight_service;
 mod segment_cache;
 
 pub use in_process::{
@@ -13,3 +15,5 @@ pub use searcher::{
     Searcher,
     SearcherImpl,
 };
+pub use searchlight_client::SearchlightClient;
+pub use searchlight_service::SearchlightService;
@@ -0,0 +1,188 @@
+//! [`Searcher`] that runs searches and compactions on a remote Searchlight
+//! server.
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use common::{
+    bootstrap_model::index::vector_index::FragmentedVectorSegment,
+    knobs::MAX_SEARCHLIGHT_MESSAGE_SIZE,
+    types::ObjectKey,
+};
+use keybroker::SystemKey;
+use pb::{
+    error_metadata::ErrorMetadataStatusExt,
+    searchlight::{
+        searchlight_client::SearchlightClient as SearchlightGrpcClient,
+        FragmentedVectorSegmentPaths,
+        FragmentedVectorSegmentPathsList,
+        QueryRequest,
+        StorageKey,
+        VectorCompactionRequest,
+        VectorPrefetchRequest,
+        VectorQueryRequest,
+    },
+};
+use storage::Storage;
+use tonic::{
+    metadata::{
+        Ascii,
+        MetadataValue,
+    },
+    transport::{
+        Channel,
+        Endpoint,
+    },
+    Request,
+};
+use vector::{
+    CompiledVectorSearch,
+    QdrantSchema,
+    VectorSearchQueryResult,
+    VectorSearcher,
+};
+
+use super::searchlight_service::SYSTEM_KEY_METADATA_KEY;
+use crate::{
+    query::{
+        CompiledQuery,
+        TermShortlist,
+    },
+    scoring::Bm25StatisticsDiff,
+    SearchQueryResult,
+    Searcher,
+    TantivySearchIndexSchema,
+};
+
+#[derive(Clone)]
+pub struct SearchlightClient {
+    client: SearchlightGrpcClient<Channel>,
+    system_key: MetadataValue<Ascii>,
+}
+
+impl SearchlightClient {
+    /// `system_key` must be issued from the instance secret Searchlight was
+    /// started with.
+    pub fn new(searchlight_url: String, system_key: SystemKey) -> anyhow::Result<Self> {
+        let channel = Endpoint::from_shared(searchlight_url)?.connect_lazy();
+        let client = SearchlightGrpcClient::new(channel)
+            .max_encoding_message_size(*MAX_SEARCHLIGHT_MESSAGE_SIZE)
+            .max_decoding_message_size(*MAX_SEARCHLIGHT_MESSAGE_SIZE);
+        let system_key = system_key.to_string().parse()?;
+        Ok(Self { client, system_key })
+    }
+
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert(SYSTEM_KEY_METADATA_KEY, self.system_key.clone());
+        request
+    }
+
+    /// Asks Searchlight to start fetching vector segments into its cache
+    /// without waiting for the fetches to finish.
+    pub async fn queue_prefetch_segments(
+        &self,
+        search_storage: Arc<dyn Storage>,
+        segments: Vec<FragmentedVectorSegmentPaths>,
+    ) -> anyhow::Result<()> {
+        let request = VectorPrefetchRequest {
+            segments: Some(FragmentedVectorSegmentPathsList { segments }),
+            storage_type: Some(search_storage.storage_type_proto()),
+        };
+        self.client
+            .clone()
+            .queue_vector_prefetch(self.request(request))
+            .await
+            .map_err(|status| status.into_anyhow())?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Searcher for SearchlightClient {
+    async fn execute_query(
+        &self,
+        search_storage: Arc<dyn Storage>,
+        disk_index: &ObjectKey,
+        schema: &TantivySearchIndexSchema,
+        search: CompiledQuery,
+        memory_statistics_diff: Bm25StatisticsDiff,
+        memory_shortlisted_terms: TermShortlist,
+        limit: usize,
+    ) -> anyhow::Result<SearchQueryResult> {
+        let request = QueryRequest {
+            index_config: Some(schema.into()),
+            query: Some(search.into()),
+            memory_statistics_diff: Some(memory_statistics_diff.into()),
+            memory_shortlisted_terms: Some(memory_shortlisted_terms.into()),
+            limit: limit.try_into()?,
+            disk_index: Some(StorageKey {
+                storage_key: disk_index.clone().into(),
+            }),
+            storage_type: Some(search_storage.storage_type_proto()),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute_query(self.request(request))
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        SearchQueryResult::try_from_query_response(response, schema.search_field)
+    }
+}
+
+#[async_trait]
+impl VectorSearcher for SearchlightClient {
+    async fn execute_multi_segment_vector_query(
+        &self,
+        search_storage: Arc<dyn Storage>,
+        segments: Vec<FragmentedVectorSegmentPaths>,
+        schema: QdrantSchema,
+        search: CompiledVectorSearch,
+        overfetch_delta: u32,
+    ) -> anyhow::Result<Vec<VectorSearchQueryResult>> {
+        let request = VectorQueryRequest {
+            index_config: Some(schema.into()),
+            query: Some(search.into()),
+            overfetch_delta,
+            segments: Some(FragmentedVectorSegmentPathsList { segments }),
+            storage_type: Some(search_storage.storage_type_proto()),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute_vector_query(self.request(request))
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response
+            .results
+            .into_iter()
+            .map(VectorSearchQueryResult::try_from)
+            .collect()
+    }
+
+    async fn execute_vector_compaction(
+        &self,
+        search_storage: Arc<dyn Storage>,
+        segments: Vec<FragmentedVectorSegmentPaths>,
+        dimension: usize,
+    ) -> anyhow::Result<FragmentedVectorSegment> {
+        let request = VectorCompactionRequest {
+            segments: Some(FragmentedVectorSegmentPathsList { segments }),
+            dimension: dimension.try_into()?,
+            storage_type: Some(search_storage.storage_type_proto()),
+        };
+        let response = self
+            .client
+            .clone()
+            .execute_vector_compaction(self.request(request))
+            .await
+            .map_err(|status| status.into_anyhow())?
+            .into_inner();
+        response.segment.context("Missing segment")?.try_into()
+    }
+}
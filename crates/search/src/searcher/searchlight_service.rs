@@ -0,0 +1,593 @@
+//! gRPC server for Searchlight, running searches and compactions with a
+//! [`SearcherImpl`] on behalf of remote backends.
+//!
+//! Searchlight reads segments from its own view of the backend's search
+//! storage, so a Searchlight on another host needs the backend's local storage
+//! directory on a shared filesystem. S3 search storage isn't supported.
+use std::{
+    path::{
+        Component,
+        Path,
+    },
+    sync::Arc,
+};
+
+use anyhow::Context;
+use common::{
+    bootstrap_model::index::search_index::DeveloperSearchIndexConfig,
+    knobs::MAX_SEARCHLIGHT_MESSAGE_SIZE,
+    runtime::Runtime,
+    types::ObjectKey,
+};
+use errors::ErrorMetadata;
+use keybroker::KeyBroker;
+use pb::{
+    error_metadata::ErrorMetadataStatusExt,
+    searchlight::{
+        incremental_searchlight_server::{
+            IncrementalSearchlight,
+            IncrementalSearchlightServer,
+        },
+        searchlight_server::{
+            Searchlight,
+            SearchlightServer,
+        },
+        storage_type::StorageType as StorageTypeProto,
+        FragmentedTextSegmentPaths,
+        FragmentedVectorSegmentPaths,
+        QueryBm25StatsRequest,
+        QueryBm25StatsResponse,
+        QueryPostingListsRequest,
+        QueryPostingListsResponse,
+        QueryRequest,
+        QueryResponse,
+        QueryTokensRequest,
+        QueryTokensResponse,
+        SearchIndexConfig,
+        StorageKey,
+        StorageType,
+        VectorCompactionRequest,
+        VectorCompactionResponse,
+        VectorPrefetchRequest,
+        VectorPrefetchResponse,
+        VectorQueryRequest,
+        VectorQueryResponse,
+    },
+};
+use storage::Storage;
+use tantivy::Term;
+use tonic::{
+    Request,
+    Response,
+    Status,
+};
+use vector::{
+    CompiledVectorSearch,
+    QdrantSchema,
+    VectorSearcher,
+};
+
+use super::searcher::{
+    FragmentedTextSegmentStorageKeys,
+    PostingListQuery,
+    TokenQuery,
+};
+use crate::{
+    query::{
+        CompiledQuery,
+        TermShortlist,
+    },
+    searcher::SearcherImpl,
+    Searcher,
+    TantivySearchIndexSchema,
+};
+
+/// Metadata key carrying the system key that authenticates each request.
+pub(crate) const SYSTEM_KEY_METADATA_KEY: &str = "authorization";
+
+/// Serves the `Searchlight` and `IncrementalSearchlight` gRPC services.
+///
+/// Every request must carry a system key issued from the instance secret, and
+/// storage keys in requests are resolved relative to `search_storage`.
+pub struct SearchlightService<RT: Runtime> {
+    searcher: Arc<SearcherImpl<RT>>,
+    search_storage: Arc<dyn Storage>,
+    key_broker: KeyBroker,
+}
+
+impl<RT: Runtime> Clone for SearchlightService<RT> {
+    fn clone(&self) -> Self {
+        Self {
+            searcher: self.searcher.clone(),
+            search_storage: self.search_storage.clone(),
+            key_broker: self.key_broker.clone(),
+        }
+    }
+}
+
+impl<RT: Runtime> SearchlightService<RT> {
+    pub fn new(
+        searcher: SearcherImpl<RT>,
+        search_storage: Arc<dyn Storage>,
+        key_broker: KeyBroker,
+    ) -> Self {
+        Self {
+            searcher: Arc::new(searcher),
+            search_storage,
+            key_broker,
+        }
+    }
+
+    pub fn into_server(self) -> SearchlightServer<Self> {
+        SearchlightServer::new(self)
+            .max_decoding_message_size(*MAX_SEARCHLIGHT_MESSAGE_SIZE)
+            .max_encoding_message_size(*MAX_SEARCHLIGHT_MESSAGE_SIZE)
+    }
+
+    pub fn into_incremental_server(self) -> IncrementalSearchlightServer<Self> {
+        IncrementalSearchlightServer::new(self)
+            .max_decoding_message_size(*MAX_SEARCHLIGHT_MESSAGE_SIZE)
+            .max_encoding_message_size(*MAX_SEARCHLIGHT_MESSAGE_SIZE)
+    }
+
+    fn authenticate<T>(&self, request: &Request<T>) -> anyhow::Result<()> {
+        let is_system = request
+            .metadata()
+            .get(SYSTEM_KEY_METADATA_KEY)
+            .and_then(|key| key.to_str().ok())
+            .and_then(|key| self.key_broker.check_admin_key(key).ok())
+            .is_some_and(|identity| identity.is_system());
+        anyhow::ensure!(
+            is_system,
+            ErrorMetadata::unauthenticated(
+                "InvalidSystemKey",
+                "Searchlight requests must be authenticated with a system key"
+            )
+        );
+        Ok(())
+    }
+
+    /// Segments are always read from Searchlight's own search storage. The
+    /// backend's storage type is only checked, never used to open storage.
+    fn storage(&self, storage_type: Option<StorageType>) -> anyhow::Result<Arc<dyn Storage>> {
+        let storage_type = storage_type
+            .and_then(|storage_type| storage_type.storage_type)
+            .context("Missing storage_type")?;
+        match storage_type {
+            StorageTypeProto::Local(_) => Ok(self.search_storage.clone()),
+            StorageTypeProto::S3(_) => {
+                anyhow::bail!("S3 search storage is not supported by this Searchlight")
+            },
+        }
+    }
+
+    fn schema(index_config: Option<SearchIndexConfig>) -> anyhow::Result<TantivySearchIndexSchema> {
+        let index_config =
+            DeveloperSearchIndexConfig::try_from(index_config.context("Missing index_config")?)?;
+        Ok(TantivySearchIndexSchema::new(&index_config))
+    }
+
+    async fn execute_query_inner(&self, request: QueryRequest) -> anyhow::Result<QueryResponse> {
+        let QueryRequest {
+            index_config,
+            query,
+            memory_statistics_diff,
+            memory_shortlisted_terms,
+            limit,
+            disk_index,
+            storage_type,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let schema = Self::schema(index_config)?;
+        let query = CompiledQuery::try_from_text_query_proto(
+            query.context("Missing query")?,
+            schema.search_field,
+        )?;
+        let memory_statistics_diff = memory_statistics_diff
+            .context("Missing memory_statistics_diff")?
+            .into();
+        let memory_shortlisted_terms = TermShortlist::try_from_proto(
+            memory_shortlisted_terms.context("Missing memory_shortlisted_terms")?,
+            schema.search_field,
+        )?;
+        let disk_index = disk_index.context("Missing disk_index")?.storage_key;
+        check_relative_key(&disk_index)?;
+        let disk_index = ObjectKey::try_from(disk_index)?;
+        let results = self
+            .searcher
+            .execute_query(
+                search_storage,
+                &disk_index,
+                &schema,
+                query,
+                memory_statistics_diff,
+                memory_shortlisted_terms,
+                limit as usize,
+            )
+            .await?;
+        results.try_into()
+    }
+
+    async fn execute_vector_query_inner(
+        &self,
+        request: VectorQueryRequest,
+    ) -> anyhow::Result<VectorQueryResponse> {
+        let VectorQueryRequest {
+            index_config,
+            query,
+            overfetch_delta,
+            segments,
+            storage_type,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let schema = QdrantSchema::try_from(index_config.context("Missing index_config")?)?;
+        let query = CompiledVectorSearch::try_from(query.context("Missing query")?)?;
+        let segments = segments.context("Missing segments")?.segments;
+        check_vector_segment_keys(&segments)?;
+        let results = self
+            .searcher
+            .execute_multi_segment_vector_query(
+                search_storage,
+                segments,
+                schema,
+                query,
+                overfetch_delta,
+            )
+            .await?;
+        Ok(VectorQueryResponse {
+            results: results.into_iter().map(|result| result.into()).collect(),
+        })
+    }
+
+    async fn execute_vector_compaction_inner(
+        &self,
+        request: VectorCompactionRequest,
+    ) -> anyhow::Result<VectorCompactionResponse> {
+        let VectorCompactionRequest {
+            segments,
+            dimension,
+            storage_type,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let segments = segments.context("Missing segments")?.segments;
+        check_vector_segment_keys(&segments)?;
+        let segment = self
+            .searcher
+            .execute_vector_compaction(search_storage, segments, dimension as usize)
+            .await?;
+        Ok(VectorCompactionResponse {
+            segment: Some(segment.into()),
+        })
+    }
+
+    fn queue_vector_prefetch_inner(
+        &self,
+        request: VectorPrefetchRequest,
+    ) -> anyhow::Result<VectorPrefetchResponse> {
+        let VectorPrefetchRequest {
+            segments,
+            storage_type,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let segments = segments.context("Missing segments")?.segments;
+        check_vector_segment_keys(&segments)?;
+        self.searcher
+            .queue_prefetch_segments(search_storage, segments)?;
+        Ok(VectorPrefetchResponse {})
+    }
+
+    async fn query_tokens_inner(
+        &self,
+        request: QueryTokensRequest,
+    ) -> anyhow::Result<QueryTokensResponse> {
+        let QueryTokensRequest {
+            storage_type,
+            segment,
+            index_config,
+            token_queries,
+            max_results,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let segment = segment.context("Missing segment")?;
+        check_text_segment_keys(&segment)?;
+        let storage_keys = FragmentedTextSegmentStorageKeys::try_from(segment)?;
+        let schema = Self::schema(index_config)?;
+        let token_queries = token_queries
+            .into_iter()
+            .map(TokenQuery::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let token_matches = self
+            .searcher
+            .query_tokens(
+                search_storage,
+                storage_keys,
+                schema,
+                token_queries,
+                max_results as usize,
+            )
+            .await?;
+        Ok(QueryTokensResponse {
+            token_matches: token_matches
+                .into_iter()
+                .map(|token_match| token_match.try_into())
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        })
+    }
+
+    async fn query_bm25_stats_inner(
+        &self,
+        request: QueryBm25StatsRequest,
+    ) -> anyhow::Result<QueryBm25StatsResponse> {
+        let QueryBm25StatsRequest {
+            storage_type,
+            segment,
+            terms,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let segment = segment.context("Missing segment")?;
+        check_text_segment_keys(&segment)?;
+        let storage_keys = FragmentedTextSegmentStorageKeys::try_from(segment)?;
+        let terms = terms.into_iter().map(Term::wrap).collect();
+        let stats = self
+            .searcher
+            .query_bm25_stats(search_storage, storage_keys, terms)
+            .await?;
+        stats.try_into()
+    }
+
+    async fn query_posting_lists_inner(
+        &self,
+        request: QueryPostingListsRequest,
+    ) -> anyhow::Result<QueryPostingListsResponse> {
+        let QueryPostingListsRequest {
+            storage_type,
+            segment,
+            query,
+        } = request;
+        let search_storage = self.storage(storage_type)?;
+        let segment = segment.context("Missing segment")?;
+        check_text_segment_keys(&segment)?;
+        let storage_keys = FragmentedTextSegmentStorageKeys::try_from(segment)?;
+        let query = PostingListQuery::try_from(query.context("Missing query")?)?;
+        let matches = self
+            .searcher
+            .query_posting_lists(search_storage, storage_keys, query)
+            .await?;
+        Ok(QueryPostingListsResponse {
+            matches: matches
+                .into_iter()
+                .map(|posting_list_match| posting_list_match.try_into())
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        })
+    }
+}
+
+/// Rejects storage keys that could resolve outside of the search storage
+/// directory.
+fn check_relative_key(key: &str) -> anyhow::Result<()> {
+    let is_relative = !key.is_empty()
+        && Path::new(key)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    anyhow::ensure!(
+        is_relative,
+        ErrorMetadata::bad_request(
+            "InvalidStorageKey",
+            format!("Storage key {key:?} must be a relative path within search storage")
+        )
+    );
+    Ok(())
+}
+
+fn check_storage_keys<'a>(
+    keys: impl IntoIterator<Item = &'a Option<StorageKey>>,
+) -> anyhow::Result<()> {
+    for key in keys.into_iter().flatten() {
+        check_relative_key(&key.storage_key)?;
+    }
+    Ok(())
+}
+
+fn check_vector_segment_keys(segments: &[FragmentedVectorSegmentPaths]) -> anyhow::Result<()> {
+    for segment in segments {
+        check_storage_keys([
+            &segment.segment,
+            &segment.id_tracker,
+            &segment.deleted_bitset,
+        ])?;
+    }
+    Ok(())
+}
+
+fn check_text_segment_keys(segment: &FragmentedTextSegmentPaths) -> anyhow::Result<()> {
+    check_storage_keys([&segment.segment, &segment.id_tracker, &segment.deletions])
+}
+
+fn into_response<T>(result: anyhow::Result<T>) -> Result<Response<T>, Status> {
+    result.map(Response::new).map_err(Status::from_anyhow)
+}
+
+#[tonic::async_trait]
+impl<RT: Runtime> Searchlight for SearchlightService<RT> {
+    async fn execute_query(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(self.execute_query_inner(request.into_inner()).await)
+    }
+
+    async fn execute_vector_query(
+        &self,
+        request: Request<VectorQueryRequest>,
+    ) -> Result<Response<VectorQueryResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(self.execute_vector_query_inner(request.into_inner()).await)
+    }
+
+    async fn execute_vector_compaction(
+        &self,
+        request: Request<VectorCompactionRequest>,
+    ) -> Result<Response<VectorCompactionResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(
+            self.execute_vector_compaction_inner(request.into_inner())
+                .await,
+        )
+    }
+
+    async fn queue_vector_prefetch(
+        &self,
+        request: Request<VectorPrefetchRequest>,
+    ) -> Result<Response<VectorPrefetchResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(self.queue_vector_prefetch_inner(request.into_inner()))
+    }
+}
+
+#[tonic::async_trait]
+impl<RT: Runtime> IncrementalSearchlight for SearchlightService<RT> {
+    async fn query_tokens(
+        &self,
+        request: Request<QueryTokensRequest>,
+    ) -> Result<Response<QueryTokensResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(self.query_tokens_inner(request.into_inner()).await)
+    }
+
+    async fn query_bm25_stats(
+        &self,
+        request: Request<QueryBm25StatsRequest>,
+    ) -> Result<Response<QueryBm25StatsResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(self.query_bm25_stats_inner(request.into_inner()).await)
+    }
+
+    async fn query_posting_lists(
+        &self,
+        request: Request<QueryPostingListsRequest>,
+    ) -> Result<Response<QueryPostingListsResponse>, Status> {
+        self.authenticate(&request).map_err(Status::from_anyhow)?;
+        into_response(self.query_posting_lists_inner(request.into_inner()).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::Duration,
+    };
+
+    use common::{
+        http::serve_grpc,
+        runtime::Runtime,
+    };
+    use errors::ErrorMetadataAnyhowExt;
+    use futures::channel::oneshot;
+    use keybroker::{
+        InstanceSecret,
+        KeyBroker,
+    };
+    use pb::searchlight::{
+        FragmentedVectorSegmentPaths,
+        StorageKey,
+    };
+    use runtime::prod::ProdRuntime;
+    use storage::{
+        LocalDirStorage,
+        Storage,
+    };
+
+    use super::check_relative_key;
+    use crate::searcher::{
+        SearcherImpl,
+        SearchlightClient,
+        SearchlightService,
+    };
+
+    fn segment_paths(segment: &str) -> FragmentedVectorSegmentPaths {
+        let storage_key = |key: &str| {
+            Some(StorageKey {
+                storage_key: key.to_string(),
+            })
+        };
+        FragmentedVectorSegmentPaths {
+            segment: storage_key(segment),
+            id_tracker: storage_key("id_tracker"),
+            deleted_bitset: storage_key("deleted_bitset"),
+        }
+    }
+
+    #[test]
+    fn test_check_relative_key() {
+        check_relative_key("segment").unwrap();
+        check_relative_key("some/nested/segment").unwrap();
+        for key in ["", "/etc/passwd", "../segment", "some/../../segment"] {
+            let err = check_relative_key(key).unwrap_err();
+            assert!(err.is_bad_request(), "{key:?}: {err:?}");
+        }
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_searchlight_service_round_trip(rt: ProdRuntime) -> anyhow::Result<()> {
+        let cache_dir = tempfile::tempdir()?;
+        let searcher = SearcherImpl::new(cache_dir.path(), 1 << 30, 100, false, rt.clone()).await?;
+        let search_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let instance_secret = InstanceSecret::random();
+        let key_broker = KeyBroker::new("carnitas", instance_secret)?;
+        let service = SearchlightService::new(searcher, search_storage.clone(), key_broker.clone());
+        let port = portpicker::pick_unused_port().expect("No ports free");
+        let addr = format!("127.0.0.1:{port}").parse()?;
+        let (_shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        rt.spawn("searchlight_server", async move {
+            let shutdown = async move {
+                let _ = shutdown_rx.await;
+            };
+            serve_grpc(service.into_server(), addr, shutdown)
+                .await
+                .expect("Searchlight server failed");
+        });
+        let searchlight_url = format!("http://{addr}");
+        let client =
+            SearchlightClient::new(searchlight_url.clone(), key_broker.issue_system_key())?;
+
+        // It can take a moment for the server to start listening.
+        let mut attempts = 0;
+        loop {
+            match client
+                .queue_prefetch_segments(search_storage.clone(), vec![])
+                .await
+            {
+                Ok(()) => break,
+                Err(e) if attempts < 100 => {
+                    tracing::info!("Searchlight server not ready: {e:#}");
+                    attempts += 1;
+                    rt.wait(Duration::from_millis(10)).await;
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Keys that could escape the search storage directory are rejected.
+        let err = client
+            .queue_prefetch_segments(
+                search_storage.clone(),
+                vec![segment_paths("../../etc/passwd")],
+            )
+            .await
+            .unwrap_err();
+        assert!(err.is_bad_request(), "{err:?}");
+
+        // Keys issued from another instance secret are rejected.
+        let other_key_broker = KeyBroker::new("carnitas", InstanceSecret::random())?;
+        let other_client =
+            SearchlightClient::new(searchlight_url, other_key_broker.issue_system_key())?;
+        let err = other_client
+            .queue_prefetch_segments(search_storage, vec![])
+            .await
+            .unwrap_err();
+        assert!(err.is_unauthenticated(), "{err:?}");
+        Ok(())
+    }
+}
@@ -15,20 +15,27 @@ use std::{
         OpenOptions,
     },
     io::{
+        self,
         Read,
         Seek,
         SeekFrom,
         Write,
     },
     mem,
-    path::PathBuf,
+    path::{
+        Path,
+        PathBuf,
+    },
     pin::Pin,
     sync::Arc,
     task::{
         Context,
         Poll,
     },
-    time::Duration,
+    time::{
+        Duration,
+        SystemTime,
+    },
 };
 
 use anyhow::Context as _;
@@ -163,6 +170,12 @@ pub trait Storage: Send + Sync + Debug {
         key: &ObjectKey,
         bytes_range: std::ops::Range<u64>,
     ) -> BoxFuture<'static, anyhow::Result<StorageGetStream>>;
+    /// Deletes an object. Deleting an object that doesn't exist is not an
+    /// error.
+    async fn delete_object(&self, key: &ObjectKey) -> anyhow::Result<()>;
+    /// Lists every object in storage, including objects from uploads that
+    /// haven't completed yet. Intended for garbage collection.
+    async fn list_objects(&self) -> anyhow::Result<Vec<StorageObject>>;
     fn storage_type_proto(&self) -> pb::searchlight::StorageType;
     /// Return a cache key suitable for the given ObjectKey, even in
     /// a multi-tenant cache.
@@ -173,6 +186,12 @@ pub struct ObjectAttributes {
     pub size: u64,
 }
 
+pub struct StorageObject {
+    pub key: ObjectKey,
+    pub size: u64,
+    pub last_modified: SystemTime,
+}
+
 pub struct SizeAndHash {
     sha256: Sha256,
     size: usize,
@@ -827,6 +846,12 @@ struct ClientDrivenUpload {
     filepath: PathBuf,
 }
 
+/// Where `LocalDirStorage` keeps a part of the client-driven upload to
+/// `filepath` until the upload finishes.
+fn part_path(filepath: &Path, part_number: u16) -> PathBuf {
+    filepath.with_extension(format!("part{part_number}.blob"))
+}
+
 impl TryFrom<ClientDrivenUpload> for ClientDrivenUploadToken {
     type Error = anyhow::Error;
 
@@ -922,32 +947,46 @@ impl<RT: Runtime> Storage for LocalDirStorage<RT> {
     async fn upload_part(
         &self,
         token: ClientDrivenUploadToken,
-        _part_number: u16,
+        part_number: u16,
         part: Bytes,
     ) -> anyhow::Result<ClientDrivenUploadPartToken> {
         let ClientDrivenUpload {
-            object_key,
+            object_key: _,
             filepath,
         } = token.try_into()?;
-        let file = OpenOptions::new().append(true).open(filepath)?;
-        let mut upload = LocalDirUpload {
-            object_key,
-            file: Some(file),
-            num_parts: 0, // unused
-        };
-        upload.write(part).await?;
-        Ok(ClientDrivenUploadPartToken(String::new()))
+        anyhow::ensure!(part.len() <= MAX_PART_SIZE);
+        // Like S3, each part is stored separately until the upload finishes, and
+        // uploading a part number again replaces it.
+        fs::write(part_path(&filepath, part_number), &part)?;
+        Ok(ClientDrivenUploadPartToken(part_number.to_string()))
     }
 
     async fn finish_client_driven_upload(
         &self,
         token: ClientDrivenUploadToken,
-        _part_tokens: Vec<ClientDrivenUploadPartToken>,
+        part_tokens: Vec<ClientDrivenUploadPartToken>,
     ) -> anyhow::Result<ObjectKey> {
         let ClientDrivenUpload {
             object_key,
-            filepath: _,
+            filepath,
         } = token.try_into()?;
+        let mut file = OpenOptions::new().append(true).open(&filepath)?;
+        let mut part_paths = Vec::with_capacity(part_tokens.len());
+        for part_token in part_tokens {
+            let part_number: u16 = part_token
+                .0
+                .parse()
+                .with_context(|| format!("Invalid part token {:?}", part_token.0))?;
+            let part_path = part_path(&filepath, part_number);
+            io::copy(&mut File::open(&part_path)?, &mut file)?;
+            part_paths.push(part_path);
+        }
+        file.sync_all()?;
+        // Parts that weren't included are left for garbage collection, which
+        // sees them as objects of their own.
+        for part_path in part_paths {
+            fs::remove_file(part_path)?;
+        }
         Ok(object_key)
     }
 
@@ -1020,6 +1059,25 @@ impl<RT: Runtime> Storage for LocalDirStorage<RT> {
         }))
     }
 
+    async fn delete_object(&self, key: &ObjectKey) -> anyhow::Result<()> {
+        let key = self.path_for_key(key.clone());
+        let path = self.dir.join(key);
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != IoErrorKind::NotFound => Err(e).context(format!(
+                "Local dir storage couldn't delete {}",
+                path.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list_objects(&self) -> anyhow::Result<Vec<StorageObject>> {
+        // Walking a large directory tree blocks, so keep it off the async
+        // runtime's threads.
+        let root = self.dir.clone();
+        tokio::task::spawn_blocking(move || list_dir_objects(&root)).await?
+    }
+
     fn storage_type_proto(&self) -> pb::searchlight::StorageType {
         pb::searchlight::StorageType {
             storage_type: Some(pb::searchlight::storage_type::StorageType::Local(
@@ -1031,6 +1089,37 @@ impl<RT: Runtime> Storage for LocalDirStorage<RT> {
     }
 }
 
+/// Lists the objects stored under `root`, where keys containing "/" are
+/// stored in subdirectories.
+fn list_dir_objects(root: &Path) -> anyhow::Result<Vec<StorageObject>> {
+    let mut objects = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let path = entry.path();
+            if metadata.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let Some(key) = path
+                .strip_prefix(root)?
+                .to_str()
+                .and_then(|path| path.strip_suffix(".blob"))
+            else {
+                continue;
+            };
+            objects.push(StorageObject {
+                key: key.to_string().try_into()?,
+                size: metadata.len(),
+                last_modified: metadata.modified()?,
+            });
+        }
+    }
+    Ok(objects)
+}
+
 pub struct LocalDirUpload {
     object_key: ObjectKey,
     file: Option<File>,
@@ -1169,6 +1258,58 @@ mod local_storage_tests {
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_client_driven_upload(rt: TestRuntime) -> anyhow::Result<()> {
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt)?);
+        let token = storage.start_client_driven_upload().await?;
+        let part1 = storage
+            .upload_part(token.clone(), 1, Bytes::from_static(b"pinna "))
+            .await?;
+        let part2 = storage
+            .upload_part(token.clone(), 2, Bytes::from_static(b"park"))
+            .await?;
+        // Uploading a part again replaces it.
+        let part1 = storage
+            .upload_part(token.clone(), 1, Bytes::from_static(b"PINNA "))
+            .await?;
+        // Parts that aren't passed to `finish_client_driven_upload` are ignored.
+        storage
+            .upload_part(token.clone(), 3, Bytes::from_static(b"ignored"))
+            .await?;
+        let key = storage
+            .finish_client_driven_upload(token, vec![part1, part2])
+            .await?;
+
+        let contents = storage
+            .get(&key)
+            .await?
+            .context("object not found")?
+            .collect_as_bytes()
+            .await?;
+        assert_eq!(contents, Bytes::from_static(b"PINNA park"));
+        assert_eq!(storage.list_objects().await?.len(), 2);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_list_and_delete_objects(rt: TestRuntime) -> anyhow::Result<()> {
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt)?);
+        let mut upload = storage.start_upload().await?;
+        upload.write(Bytes::from_static(b"pinna park")).await?;
+        let key = upload.complete().await?;
+
+        let objects = storage.list_objects().await?;
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].key, key);
+        assert_eq!(objects[0].size, 10);
+
+        storage.delete_object(&key).await?;
+        assert!(storage.list_objects().await?.is_empty());
+        // Deleting a missing object is a no-op.
+        storage.delete_object(&key).await?;
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_local_storage(rt: TestRuntime) -> anyhow::Result<()> {
         let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt)?);
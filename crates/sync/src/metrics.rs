@@ -85,6 +85,17 @@ pub fn log_query_result_dedup(same_value: bool) {
     log_counter(&SYNC_QUERY_RESULT_DEDUP_TOTAL, sample);
 }
 
+register_convex_counter!(
+    SYNC_QUERY_DIFF_TOTAL,
+    "Number of query results sent to clients that support diffs, labeled by whether a diff was \
+     sent",
+    &["diffed"]
+);
+pub fn log_query_diff(diffed: bool) {
+    let labels = vec![MetricLabel::new("diffed", diffed.to_string())];
+    log_counter_with_labels(&SYNC_QUERY_DIFF_TOTAL, 1, labels);
+}
+
 register_convex_counter!(SYNC_EMPTY_TRANSITION_TOTAL, "Number of empty transitions");
 pub fn log_empty_transition() {
     log_counter(&SYNC_EMPTY_TRANSITION_TOTAL, 1);
@@ -9,12 +9,16 @@ use application::redaction::{
     RedactedLogLines,
 };
 use common::{
+    knobs::SYNC_QUERY_DIFF_MAX_VALUE_BYTES,
     sha256::{
         Sha256,
         Sha256Digest,
     },
     types::SessionId,
-    value::ConvexValue,
+    value::{
+        ConvexValue,
+        Size,
+    },
 };
 use database::Subscription;
 use errors::ErrorMetadata;
@@ -30,7 +34,12 @@ use futures::{
     StreamExt,
 };
 use keybroker::Identity;
+use serde_json::Value as JsonValue;
 use sync_types::{
+    diff::{
+        value_hash,
+        ValueDiff,
+    },
     IdentityVersion,
     Query,
     QueryId,
@@ -67,6 +76,16 @@ pub struct SyncedQuery {
     ///   time.
     result_hash: Option<Result<ValueDigest, ErrorDigest>>,
 
+    /// The last successful return value sent to the client, kept only when
+    /// the client supports query diffs so later results can be sent as a
+    /// diff against it. Values above `SyncState::query_diff_max_value_bytes`
+    /// aren't kept.
+    ///
+    /// - Starts `None`: Query is newly inserted.
+    /// - `SyncState::complete_fetch` sets it to the new value on success and
+    ///   clears it on failure.
+    last_value: Option<JsonValue>,
+
     /// Handle to the query's current invalidation future. This future completes
     /// when `self.subscription` is no longer valid and the query should be
     /// rerun.
@@ -139,6 +158,11 @@ pub struct SyncState {
     pending_identity: Option<Identity>,
     /// These are the query set version and identity according to the client.
     received_client_version: ClientVersion,
+
+    /// Whether to send `StateModification::QueryDiffed` to the client.
+    query_diffs_enabled: bool,
+    /// Results larger than this are sent in full and not kept for diffing.
+    query_diff_max_value_bytes: usize,
 }
 
 impl SyncState {
@@ -156,6 +180,9 @@ impl SyncState {
             pending_query_updates: vec![],
             pending_identity: None,
             received_client_version: ClientVersion::initial(),
+
+            query_diffs_enabled: false,
+            query_diff_max_value_bytes: *SYNC_QUERY_DIFF_MAX_VALUE_BYTES,
         }
     }
 
@@ -167,6 +194,14 @@ impl SyncState {
         self.session_id
     }
 
+    pub fn set_query_diffs_enabled(&mut self, enabled: bool) {
+        self.query_diffs_enabled = enabled;
+    }
+
+    pub fn set_query_diff_max_value_bytes(&mut self, max_value_bytes: usize) {
+        self.query_diff_max_value_bytes = max_value_bytes;
+    }
+
     /// What is the current state version?
     pub fn current_version(&self) -> StateVersion {
         self.current_version
@@ -382,6 +417,7 @@ impl SyncState {
                 query,
                 subscription: None,
                 result_hash: None,
+                last_value: None,
                 invalidation_future: None,
             };
             if self.queries.insert(query_id, sq).is_some() {
@@ -420,6 +456,44 @@ impl SyncState {
             None
         } else {
             let modification = match result {
+                Ok(value)
+                    if self.query_diffs_enabled
+                        && value.size() > self.query_diff_max_value_bytes =>
+                {
+                    query.last_value = None;
+                    metrics::log_query_diff(false);
+                    StateModification::QueryUpdated {
+                        query_id,
+                        value,
+                        log_lines: log_lines.into(),
+                        journal,
+                    }
+                },
+                Ok(value) if self.query_diffs_enabled => {
+                    let new_value = JsonValue::from(value.clone());
+                    let diff = query
+                        .last_value
+                        .as_ref()
+                        .and_then(|last_value| ValueDiff::new_if_smaller(last_value, &new_value));
+                    metrics::log_query_diff(diff.is_some());
+                    let modification = match diff {
+                        Some(diff) => StateModification::QueryDiffed {
+                            query_id,
+                            diff,
+                            value_hash: value_hash(&new_value),
+                            log_lines: log_lines.into(),
+                            journal,
+                        },
+                        None => StateModification::QueryUpdated {
+                            query_id,
+                            value,
+                            log_lines: log_lines.into(),
+                            journal,
+                        },
+                    };
+                    query.last_value = Some(new_value);
+                    modification
+                },
                 Ok(value) => StateModification::QueryUpdated {
                     query_id,
                     value,
@@ -427,6 +501,7 @@ impl SyncState {
                     journal,
                 },
                 Err(error) => {
+                    query.last_value = None;
                     metrics::log_query_failed();
                     StateModification::QueryFailed {
                         query_id,
@@ -47,6 +47,7 @@ use runtime::testing::{
     TestFutureHandle,
     TestRuntime,
 };
+use serde_json::Value as JsonValue;
 use sync_types::{
     AuthenticationToken,
     ClientMessage,
@@ -112,13 +113,14 @@ impl SyncTest {
 
     fn new_worker(&self) -> anyhow::Result<TestSyncWorker> {
         let config = SyncWorkerConfig::default();
-        self.new_worker_with_config(config, None)
+        self.new_worker_with_config(config, None, false)
     }
 
     fn new_worker_with_config(
         &self,
         config: SyncWorkerConfig,
         max_observed_timestamp: Option<Timestamp>,
+        supports_query_diffs: bool,
     ) -> anyhow::Result<TestSyncWorker> {
         let worker_failed = Arc::new(Mutex::new(None));
         let (client_tx, client_rx) = mpsc::unbounded();
@@ -144,6 +146,7 @@ impl SyncTest {
                 connection_count: 0,
                 last_close_reason: "InitialConnect".to_string(),
                 max_observed_timestamp,
+                supports_query_diffs,
             },
             self.rt.monotonic_now(),
         ))?;
@@ -852,7 +855,7 @@ async fn test_max_observed_timestamp(rt: TestRuntime) -> anyhow::Result<()> {
     let test = SyncTest::new(rt).await?;
 
     let config = SyncWorkerConfig::default();
-    let mut sync_worker = test.new_worker_with_config(config, Some(Timestamp::MAX))?;
+    let mut sync_worker = test.new_worker_with_config(config, Some(Timestamp::MAX), false)?;
     must_let!(let Err(err) = sync_worker.receive().await);
     assert!(
         format!("{err}")
@@ -862,3 +865,115 @@ async fn test_max_observed_timestamp(rt: TestRuntime) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_query_diffs(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt).await?;
+    let config = SyncWorkerConfig::default();
+    let mut sync_worker = test.new_worker_with_config(config, None, true)?;
+
+    for (i, name) in ["orinoco", "tizoncito", "lucia"].into_iter().enumerate() {
+        sync_worker
+            .mutation(
+                "sync:initialize",
+                assert_obj!("name" => name, "balance" => 100.0),
+                i as SessionRequestSeqNumber,
+            )
+            .await?;
+        must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    }
+
+    let query = Query {
+        query_id: QueryId::new(0),
+        udf_path: "sync:listAccounts".parse()?,
+        args: vec![assert_obj!().into()],
+        journal: None,
+    };
+    sync_worker.send(ClientMessage::ModifyQuerySet {
+        base_version: 0,
+        new_version: 1,
+        modifications: vec![QuerySetModification::Add(query)],
+    })?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    assert_eq!(modifications.len(), 1);
+    // The first result has nothing to diff against.
+    must_let!(let StateModification::QueryUpdated { value, .. } = &modifications[0]);
+    let value = JsonValue::from(value.clone());
+
+    // Changing one account sends a diff that only touches that account.
+    sync_worker
+        .mutation(
+            "sync:deposit",
+            assert_obj!("name" => "tizoncito", "balance" => 50.0),
+            3,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    assert_eq!(modifications.len(), 1);
+    must_let!(let StateModification::QueryDiffed {
+        query_id,
+        diff,
+        value_hash,
+        ..
+    } = &modifications[0]);
+    assert_eq!(*query_id, QueryId::new(0));
+    let new_value = diff.clone().apply(value)?;
+    assert_eq!(*value_hash, sync_types::diff::value_hash(&new_value));
+    must_let!(let JsonValue::Array(accounts) = new_value);
+    assert_eq!(accounts[1]["balance"], JsonValue::from(150.0));
+
+    sync_worker.shutdown().await?;
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_query_diffs_skipped_above_max_value_size(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt).await?;
+    let config = SyncWorkerConfig {
+        query_diff_max_value_bytes: 10,
+        ..SyncWorkerConfig::default()
+    };
+    let mut sync_worker = test.new_worker_with_config(config, None, true)?;
+
+    for (i, name) in ["orinoco", "tizoncito"].into_iter().enumerate() {
+        sync_worker
+            .mutation(
+                "sync:initialize",
+                assert_obj!("name" => name, "balance" => 100.0),
+                i as SessionRequestSeqNumber,
+            )
+            .await?;
+        must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    }
+
+    let query = Query {
+        query_id: QueryId::new(0),
+        udf_path: "sync:listAccounts".parse()?,
+        args: vec![assert_obj!().into()],
+        journal: None,
+    };
+    sync_worker.send(ClientMessage::ModifyQuerySet {
+        base_version: 0,
+        new_version: 1,
+        modifications: vec![QuerySetModification::Add(query)],
+    })?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    must_let!(let StateModification::QueryUpdated { .. } = &modifications[0]);
+
+    // The result is too large to keep, so the next one is sent in full.
+    sync_worker
+        .mutation(
+            "sync:deposit",
+            assert_obj!("name" => "tizoncito", "balance" => 50.0),
+            2,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    assert_eq!(modifications.len(), 1);
+    must_let!(let StateModification::QueryUpdated { value, .. } = &modifications[0]);
+    must_let!(let JsonValue::Array(accounts) = JsonValue::from(value.clone()));
+    assert_eq!(accounts[1]["balance"], JsonValue::from(150.0));
+
+    sync_worker.shutdown().await?;
+    Ok(())
+}
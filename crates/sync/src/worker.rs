@@ -24,7 +24,11 @@ use application::{
 };
 use cmd_util::env::env_config;
 use common::{
-    knobs::SYNC_MAX_SEND_TRANSITION_COUNT,
+    knobs::{
+        SYNC_MAX_SEND_TRANSITION_COUNT,
+        SYNC_QUERY_DIFFS_ENABLED,
+        SYNC_QUERY_DIFF_MAX_VALUE_BYTES,
+    },
     minitrace_helpers::get_sampled_span,
     pause::PauseClient,
     query_journal::QueryJournal,
@@ -101,12 +105,15 @@ const SYNC_WORKER_PROCESS_TIMEOUT: Duration = Duration::from_secs(60);
 #[derive(Clone, Debug)]
 pub struct SyncWorkerConfig {
     pub client_version: ClientVersion,
+    /// Query results larger than this are never sent as diffs.
+    pub query_diff_max_value_bytes: usize,
 }
 
 impl Default for SyncWorkerConfig {
     fn default() -> Self {
         Self {
             client_version: ClientVersion::unknown(),
+            query_diff_max_value_bytes: *SYNC_QUERY_DIFF_MAX_VALUE_BYTES,
         }
     }
 }
@@ -380,11 +387,16 @@ impl<RT: Runtime> SyncWorker<RT> {
                 last_close_reason,
                 max_observed_timestamp,
                 connection_count,
+                supports_query_diffs,
             } => {
                 if let Some(timer) = self.connect_timer.take() {
                     timer.finish();
                 }
                 self.state.set_session_id(session_id);
+                self.state
+                    .set_query_diffs_enabled(supports_query_diffs && *SYNC_QUERY_DIFFS_ENABLED);
+                self.state
+                    .set_query_diff_max_value_bytes(self.config.query_diff_max_value_bytes);
                 if let Some(max_observed_timestamp) = max_observed_timestamp {
                     let latest_timestamp = *self.application.now_ts_for_reads();
                     if max_observed_timestamp > latest_timestamp {
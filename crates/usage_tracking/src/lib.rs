@@ -267,6 +267,7 @@ impl UsageCounter {
             UdfIdentifier::Function(path) => (!path.is_system(), "function"),
             UdfIdentifier::Http(_) => (true, "http"),
             UdfIdentifier::Cli(_) => (false, "cli"),
+            UdfIdentifier::System(_) => (false, "system"),
         };
         usage_metrics.push(UsageEvent::FunctionCall {
             id: execution_id.to_string(),
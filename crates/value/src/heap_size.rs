@@ -35,6 +35,7 @@ use proptest::{
 };
 use serde_json::Value as JsonValue;
 use sync_types::{
+    diff::ValueDiff,
     CanonicalizedUdfPath,
     ErrorPayload,
     LogLinesMessage,
@@ -741,6 +742,30 @@ impl HeapSize for JsonValue {
     }
 }
 
+impl HeapSize for ValueDiff {
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Unchanged => 0,
+            Self::Replace(value) => value.heap_size(),
+            Self::Object { set, remove } => {
+                let mut size = estimate_btree_heap_size::<String, ValueDiff>(set.len());
+                for (k, v) in set {
+                    size += k.heap_size();
+                    size += v.heap_size();
+                }
+                size + estimate_vec_size(remove)
+            },
+            Self::Array { items, .. } => {
+                let mut size = items.capacity() * mem::size_of::<ValueDiff>();
+                for item in items {
+                    size += item.heap_size();
+                }
+                size
+            },
+        }
+    }
+}
+
 impl HeapSize for LogLinesMessage {
     fn heap_size(&self) -> usize {
         estimate_vec_size(&self.0)
@@ -791,6 +816,18 @@ impl<V: HeapSize> HeapSize for StateModification<V> {
                 log_lines,
                 journal,
             } => value.heap_size() + log_lines.heap_size() + journal.heap_size(),
+            StateModification::QueryDiffed {
+                query_id: _,
+                diff,
+                value_hash,
+                log_lines,
+                journal,
+            } => {
+                diff.heap_size()
+                    + value_hash.heap_size()
+                    + log_lines.heap_size()
+                    + journal.heap_size()
+            },
             StateModification::QueryFailed {
                 query_id: _,
                 error_message,
@@ -840,8 +877,12 @@ mod tests {
         mem,
     };
 
+    use serde_json::json;
+    use sync_types::diff::ValueDiff;
+
     use super::{
         estimate_btree_heap_size,
+        HeapSize,
         InternalNode,
         LeafNode,
         WithHeapSize,
@@ -887,6 +928,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_value_diff_array_heap_size() {
+        let items = vec![
+            ValueDiff::Replace(json!("hello")),
+            ValueDiff::Object {
+                set: BTreeMap::new(),
+                remove: vec!["world!".to_owned()],
+            },
+        ];
+        let items_size = items.capacity() * mem::size_of::<ValueDiff>();
+        let remove_size = mem::size_of::<String>() + "world!".len();
+        let diff = ValueDiff::Array {
+            prefix: 1,
+            suffix: 0,
+            items,
+        };
+        assert_eq!(diff.heap_size(), items_size + "hello".len() + remove_size);
+    }
+
     #[test]
     fn test_vec_with_heap_size() {
         let mut vec: WithHeapSize<Vec<String>> = WithHeapSize::default();
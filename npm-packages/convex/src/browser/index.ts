@@ -30,6 +30,11 @@ export type {
 } from "./sync/optimistic_updates.js";
 export type { QueryToken } from "./sync/udf_path_utils.js";
 export { ConvexHttpClient } from "./http_client.js";
+export { uploadResumable } from "./resumable_upload.js";
+export type {
+  ResumableUploadOptions,
+  ResumableUploadSession,
+} from "./resumable_upload.js";
 export type { QueryJournal } from "./sync/protocol.js";
 /** @internal */
 export type { UserIdentityAttributes } from "./sync/protocol.js";
@@ -0,0 +1,136 @@
+/**
+ * The state of a resumable upload, as returned by the backend.
+ *
+ * @public
+ */
+export type ResumableUploadSession = {
+  /** Identifies the upload. Pass it to {@link uploadResumable} to resume. */
+  uploadId: string;
+  /** Number of bytes received so far, which is where the next part starts. */
+  offset: number;
+  /** Milliseconds since the epoch after which the upload can't be used. */
+  expiresAt: number;
+};
+
+/**
+ * Options for {@link uploadResumable}.
+ *
+ * @public
+ */
+export type ResumableUploadOptions = {
+  /**
+   * The `uploadId` of an earlier, interrupted upload of the same file to
+   * continue instead of starting a new one.
+   */
+  uploadId?: string;
+  /**
+   * Size of each part in bytes. Parts other than the last must be at least
+   * 5MiB. Defaults to 8MiB.
+   */
+  partSize?: number;
+  /**
+   * How many times to retry a part after a network error before giving up.
+   * Defaults to 3.
+   */
+  maxRetries?: number;
+  /** Called with the upload's state after it starts and after each part. */
+  onProgress?: (session: ResumableUploadSession) => void;
+};
+
+const DEFAULT_PART_SIZE = 8 * 1024 * 1024;
+const DEFAULT_MAX_RETRIES = 3;
+
+/**
+ * Upload a file in parts to a URL from
+ * `ctx.storage.generateUploadUrl({ resumable: true })`.
+ *
+ * If a part fails, the upload asks the backend how much it has received and
+ * continues from there. If the whole call fails, e.g. because the page was
+ * closed, pass the last `uploadId` reported to `onProgress` to resume with
+ * a fresh URL or the same one.
+ *
+ * @param uploadUrl - A resumable upload URL.
+ * @param file - The file to upload.
+ * @param options - See {@link ResumableUploadOptions}.
+ * @returns The `storageId` of the stored file.
+ *
+ * @public
+ */
+export async function uploadResumable(
+  uploadUrl: string,
+  file: Blob,
+  options: ResumableUploadOptions = {},
+): Promise<{ storageId: string }> {
+  const partSize = options.partSize ?? DEFAULT_PART_SIZE;
+  const maxRetries = options.maxRetries ?? DEFAULT_MAX_RETRIES;
+  let session: ResumableUploadSession;
+  if (options.uploadId !== undefined) {
+    session = await request(sessionUrl(uploadUrl, options.uploadId), {
+      method: "GET",
+    });
+  } else {
+    session = await request(uploadUrl, {
+      method: "POST",
+      headers: file.type ? { "Content-Type": file.type } : {},
+    });
+  }
+  options.onProgress?.(session);
+  const url = sessionUrl(uploadUrl, session.uploadId);
+
+  let retries = 0;
+  while (session.offset < file.size) {
+    const part = file.slice(session.offset, session.offset + partSize);
+    try {
+      session = await request(`${url}?offset=${session.offset}`, {
+        method: "PUT",
+        body: part,
+      });
+      retries = 0;
+    } catch (e) {
+      if (retries >= maxRetries) {
+        throw e;
+      }
+      retries += 1;
+      // The part may or may not have been received, so pick up from wherever
+      // the backend is.
+      session = await request(url, { method: "GET" });
+    }
+    options.onProgress?.(session);
+  }
+
+  const sha256 = await crypto.subtle.digest(
+    "SHA-256",
+    await file.arrayBuffer(),
+  );
+  return await request(`${url}/finish`, {
+    method: "POST",
+    headers: { Digest: `sha-256=${toBase64(sha256)}` },
+  });
+}
+
+function sessionUrl(uploadUrl: string, uploadId: string): string {
+  const url = new URL(uploadUrl);
+  url.search = "";
+  url.pathname = `${url.pathname}/${encodeURIComponent(uploadId)}`;
+  return url.toString();
+}
+
+async function request(url: string, init: RequestInit): Promise<any> {
+  const response = await fetch(url, init);
+  if (!response.ok) {
+    throw new Error(
+      `Resumable upload request failed with ${
+        response.status
+      }: ${await response.text()}`,
+    );
+  }
+  return await response.json();
+}
+
+function toBase64(buffer: ArrayBuffer): string {
+  let binary = "";
+  for (const byte of new Uint8Array(buffer)) {
+    binary += String.fromCharCode(byte);
+  }
+  return btoa(binary);
+}
@@ -305,7 +305,8 @@ function getDeplymentEnvVarWatch(
   return getFunctionWatch(
     ctx,
     credentials,
-    "_system/cli/queryEnvironmentVariables",
+    // Values aren't listed, so watch their hashes to notice value changes.
+    "_system/cli/queryEnvironmentVariables:valueHashes",
     () => (shouldRetryOnDeploymentEnvVarChange ? {} : null),
   );
 }
@@ -73,8 +73,23 @@ const envGet = new Command("get")
       logFailure(ctx, `Environment variable "${envVarName}" not found.`);
       return;
     }
-    const { value } = envVar;
-    logOutput(ctx, `${value}`);
+    // Listings only include names, so fetch the value explicitly.
+    const client = deploymentClient(url);
+    try {
+      const res = await client.post<{ name: string; value: string }>(
+        "/api/reveal_environment_variable",
+        { name: envVarName },
+        {
+          headers: {
+            Authorization: `Convex ${adminKey}`,
+            "Convex-Client": `npm-cli-${version}`,
+          },
+        },
+      );
+      logOutput(ctx, `${res.data.value}`);
+    } catch (e) {
+      return await logAndHandleAxiosError(ctx, e);
+    }
   });
 
 const envRemove = new Command("remove")
@@ -99,8 +114,11 @@ const envRemove = new Command("remove")
   });
 
 const envList = new Command("list")
-  .summary("List all variables")
-  .description("List all variables: `npx convex env list`")
+  .summary("List all variable names")
+  .description(
+    "List all variable names: `npx convex env list`\n" +
+      "Values are not shown. Use `npx convex env get NAME` to print one.",
+  )
   .configureHelp({ showGlobalOptions: true })
   .allowExcessArguments(false)
   .action(async (_options, cmd) => {
@@ -125,8 +143,8 @@ const envList = new Command("list")
       logMessage(ctx, "No environment variables set.");
       return;
     }
-    for (const { name, value } of envs) {
-      logOutput(ctx, `${name}=${value}`);
+    for (const { name } of envs) {
+      logOutput(ctx, name);
     }
   });
 
@@ -135,9 +153,9 @@ type EnvVarChange = {
   value?: string;
 };
 
+// Environment variables as returned by system queries, which redact values.
 type EnvVar = {
   name: string;
-  value: string;
 };
 
 async function callUpdateEnvironmentVariables(
@@ -182,7 +200,7 @@ export const env = new Command("env")
     "Set and view environment variables on your deployment\n\n" +
       "  Set a variable: `npx convex env set NAME value`\n" +
       "  Unset a variable: `npx convex env remove NAME`\n" +
-      "  List all variables: `npx convex env list`\n" +
+      "  List all variable names: `npx convex env list`\n" +
       "  Print a variable's value: `npx convex env get NAME`\n\n" +
       "By default, this sets and views variables on your dev deployment.",
   )
@@ -330,4 +330,26 @@ export class QueryImpl implements Query<GenericTableInfo> {
     }
     return first_two_array[0];
   }
+
+  async count(): Promise<number> {
+    return this.aggregate("count");
+  }
+
+  async sum(): Promise<number> {
+    return this.aggregate("sum");
+  }
+
+  private async aggregate(aggregate: "count" | "sum"): Promise<number> {
+    const query = this.takeQuery();
+    if (query.source.type !== "IndexRange" || query.operators.length > 0) {
+      throw new Error(
+        `${aggregate}() can only be called on a withIndex() query without filter() or limit().`,
+      );
+    }
+    const syscallJSON = await performAsyncSyscall("1.0/count", {
+      query,
+      aggregate,
+    });
+    return jsonToConvex(syscallJSON) as number;
+  }
 }
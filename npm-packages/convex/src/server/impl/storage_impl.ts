@@ -32,10 +32,11 @@ export function setupStorageReader(requestId: string): StorageReader {
 export function setupStorageWriter(requestId: string): StorageWriter {
   const reader = setupStorageReader(requestId);
   return {
-    generateUploadUrl: async () => {
+    generateUploadUrl: async (options?: { resumable?: boolean }) => {
       return await performAsyncSyscall("1.0/storageGenerateUploadUrl", {
         requestId,
         version,
+        ...(options?.resumable ? { resumable: true } : {}),
       });
     },
     delete: async (storageId: FileStorageId) => {
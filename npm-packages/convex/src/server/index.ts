@@ -98,7 +98,12 @@ export {
 } from "./impl/registration_impl.js";
 export type { IndexRange, IndexRangeBuilder } from "./index_range_builder.js";
 export * from "./pagination.js";
-export type { OrderedQuery, Query, QueryInitializer } from "./query.js";
+export type {
+  IndexQuery,
+  OrderedQuery,
+  Query,
+  QueryInitializer,
+} from "./query.js";
 export type {
   ActionBuilder,
   ArgsArray,
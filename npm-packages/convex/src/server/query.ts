@@ -64,7 +64,7 @@ export interface QueryInitializer<TableInfo extends GenericTableInfo>
         NamedIndex<TableInfo, IndexName>
       >,
     ) => IndexRange,
-  ): Query<TableInfo>;
+  ): IndexQuery<TableInfo>;
 
   /**
    * Query by running a full text search against a search index.
@@ -149,6 +149,41 @@ export interface Query<TableInfo extends GenericTableInfo>
   order(order: "asc" | "desc"): OrderedQuery<TableInfo>;
 }
 
+/**
+ * A {@link Query} over an index range, returned by
+ * {@link QueryInitializer.withIndex}.
+ *
+ * Besides everything a {@link Query} can do, it can read totals over the
+ * range from indexes defined with `aggregate`:
+ *
+ * |                                              | |
+ * |----------------------------------------------|-|
+ * | [`count()`](#count)                          | Return the number of results of an aggregate index range. |
+ * | [`sum()`](#sum)                              | Return the sum of a field over an aggregate index range. |
+ *
+ * @public
+ */
+export interface IndexQuery<TableInfo extends GenericTableInfo>
+  extends Query<TableInfo> {
+  /**
+   * The number of documents in the index range, read from totals the index
+   * maintains instead of by loading the documents.
+   *
+   * The index must be defined with `aggregate`, and the range may only use
+   * `eq` on a prefix of its fields. Like loading the documents, the result
+   * updates whenever the count changes.
+   */
+  count(): Promise<number>;
+
+  /**
+   * The sum of the index's `aggregate.sum` field over the documents in the
+   * index range. Documents where the field isn't a number count as zero.
+   *
+   * The same restrictions as {@link IndexQuery.count} apply.
+   */
+  sum(): Promise<number>;
+}
+
 /**
  * A {@link Query} with an order that has already been defined.
  *
@@ -43,7 +43,13 @@ import {
   SystemIndexes,
 } from "../server/system_fields.js";
 import { Expand } from "../type_utils.js";
+import { JSONValue } from "../values/index.js";
 import { ObjectValidator, v, Validator } from "../values/validator.js";
+import { ExpressionOrValue, FilterBuilder } from "./filter_builder.js";
+import {
+  filterBuilderImpl,
+  serializeExpression,
+} from "./impl/filter_builder_impl.js";
 
 /**
  * Extract all of the index field paths within a {@link Validator}.
@@ -132,6 +138,27 @@ export type VectorIndex = {
 export type Index = {
   indexDescriptor: string;
   fields: string[];
+  unique?: boolean;
+  filter?: JSONValue;
+  multikeyField?: string;
+  aggregate?: { sumField?: string };
+};
+
+/**
+ * @internal
+ */
+export type TableTtl = {
+  field: string;
+  durationMs: number;
+};
+
+/**
+ * @internal
+ */
+export type ForeignKey = {
+  field: string;
+  table: string;
+  onDelete: "restrict" | "cascade" | "setNull";
 };
 
 /**
@@ -161,6 +188,8 @@ export class TableDefinition<
   private indexes: Index[];
   private searchIndexes: SearchIndex[];
   private vectorIndexes: VectorIndex[];
+  private ttlConfig: TableTtl | undefined;
+  private foreignKeys: ForeignKey[];
   // The type of documents stored in this table.
   private documentType: Validator<any, any, any>;
 
@@ -171,6 +200,7 @@ export class TableDefinition<
     this.indexes = [];
     this.searchIndexes = [];
     this.vectorIndexes = [];
+    this.foreignKeys = [];
     this.documentType = documentType;
   }
 
@@ -182,6 +212,17 @@ export class TableDefinition<
    * @param name - The name of the index.
    * @param fields - The fields to index, in order. Must specify at least one
    * field.
+   * @param options - Pass `{ unique: true }` to reject writes that would give
+   * two documents the same values for `fields`. Documents missing any of the
+   * fields are exempt. Pass a `filter` to only index documents matching it,
+   * which must only compare fields with constant values. Queries using a
+   * filtered index must apply the same filter. Pass `multikey` with one of
+   * `fields` to index each element of that field separately when it's an
+   * array, so queries can match documents containing a given element.
+   * Multikey indexes can't be unique. Pass `aggregate` to maintain a count
+   * of the documents matching each prefix of `fields`, and optionally a sum of
+   * a numeric field, which `.withIndex(...).count()` and `.sum()` read
+   * without loading the documents. Multikey indexes can't have aggregates.
    * @returns A {@link TableDefinition} with this index included.
    */
   index<
@@ -191,6 +232,20 @@ export class TableDefinition<
   >(
     name: IndexName,
     fields: [FirstFieldPath, ...RestFieldPaths],
+    options?: {
+      unique?: boolean;
+      filter?: (
+        q: FilterBuilder<{
+          document: Document;
+          fieldPaths: FieldPaths;
+          indexes: Indexes;
+          searchIndexes: SearchIndexes;
+          vectorIndexes: VectorIndexes;
+        }>,
+      ) => ExpressionOrValue<boolean>;
+      multikey?: FirstFieldPath | RestFieldPaths[number];
+      aggregate?: boolean | { sum?: FieldPaths };
+    },
   ): TableDefinition<
     Document,
     FieldPaths,
@@ -206,7 +261,27 @@ export class TableDefinition<
     SearchIndexes,
     VectorIndexes
   > {
-    this.indexes.push({ indexDescriptor: name, fields });
+    this.indexes.push({
+      indexDescriptor: name,
+      fields,
+      ...(options?.unique ? { unique: true } : {}),
+      ...(options?.filter
+        ? {
+            filter: serializeExpression(
+              options.filter(filterBuilderImpl as any),
+            ),
+          }
+        : {}),
+      ...(options?.multikey ? { multikeyField: options.multikey } : {}),
+      ...(options?.aggregate
+        ? {
+            aggregate:
+              typeof options.aggregate === "object" && options.aggregate.sum
+                ? { sumField: options.aggregate.sum }
+                : {},
+          }
+        : {}),
+    });
     return this;
   }
 
@@ -294,6 +369,70 @@ export class TableDefinition<
     return this;
   }
 
+  /**
+   * Automatically delete documents once `durationMs` milliseconds have passed
+   * since the time in `field`, in milliseconds since the Unix epoch like
+   * `Date.now()`. Documents where the field is missing or isn't a number never
+   * expire.
+   *
+   * The table must have an index starting with `field`, which is used to
+   * find expired documents. Deletion happens in the background, so queries
+   * may still see expired documents for a while.
+   *
+   * @param field - The field holding the time each document's TTL starts.
+   * @param options - How long documents live after that time.
+   * @returns A {@link TableDefinition} with this TTL.
+   */
+  ttl(
+    field: FieldPaths,
+    options: { durationMs: number },
+  ): TableDefinition<
+    Document,
+    FieldPaths,
+    Indexes,
+    SearchIndexes,
+    VectorIndexes
+  > {
+    this.ttlConfig = { field, durationMs: options.durationMs };
+    return this;
+  }
+
+  /**
+   * Make a top-level `v.id(table)` field a foreign key. Inserts and updates
+   * fail if the field isn't null or the ID of an existing document, and
+   * deleting a referenced document does what `onDelete` says:
+   *
+   * - `"restrict"`: the delete fails.
+   * - `"cascade"`: the referencing documents are deleted too.
+   * - `"setNull"`: the field is set to null in the referencing documents, so
+   * its validator must allow null.
+   *
+   * Convex maintains an index on `field`, named `_foreign_key_<field>`, to
+   * find the referencing documents.
+   *
+   * @param field - The field holding the ID.
+   * @param options - The referenced table and what to do when a referenced
+   * document is deleted.
+   * @returns A {@link TableDefinition} with this foreign key.
+   */
+  foreignKey(
+    field: FieldPaths,
+    options: { table: string; onDelete: "restrict" | "cascade" | "setNull" },
+  ): TableDefinition<
+    Document,
+    FieldPaths,
+    Indexes,
+    SearchIndexes,
+    VectorIndexes
+  > {
+    this.foreignKeys.push({
+      field,
+      table: options.table,
+      onDelete: options.onDelete,
+    });
+    return this;
+  }
+
   /**
    * Work around for https://github.com/microsoft/TypeScript/issues/57035
    */
@@ -318,6 +457,10 @@ export class TableDefinition<
       searchIndexes: this.searchIndexes,
       vectorIndexes: this.vectorIndexes,
       documentType: this.documentType.json,
+      ...(this.ttlConfig ? { ttl: this.ttlConfig } : {}),
+      ...(this.foreignKeys.length > 0
+        ? { foreignKeys: this.foreignKeys }
+        : {}),
     };
   }
 }
@@ -442,14 +585,22 @@ export class SchemaDefinition<
   export(): string {
     return JSON.stringify({
       tables: Object.entries(this.tables).map(([tableName, definition]) => {
-        const { indexes, searchIndexes, vectorIndexes, documentType } =
-          definition.export();
+        const {
+          indexes,
+          searchIndexes,
+          vectorIndexes,
+          documentType,
+          ttl,
+          foreignKeys,
+        } = definition.export();
         return {
           tableName,
           indexes,
           searchIndexes,
           vectorIndexes,
           documentType,
+          ...(ttl ? { ttl } : {}),
+          ...(foreignKeys ? { foreignKeys } : {}),
         };
       }),
       schemaValidation: this.schemaValidation,
@@ -100,9 +100,27 @@ export interface StorageWriter extends StorageReader {
    *
    * The POST URL accepts an optional standard HTTP Digest header with a sha256 checksum.
    *
+   * Pass `{ resumable: true }` to get a URL for a resumable upload instead,
+   * which lets large files be uploaded in parts and resumed after a dropped
+   * connection. Upload to it with `uploadResumable` from `convex/browser`, or
+   * follow the protocol directly:
+   *
+   * 1. POST to the URL, with the file's `Content-Type`, to start a session.
+   *    The response is `{ uploadId, offset, expiresAt }`.
+   * 2. PUT each part to
+   *    `<origin>/api/storage/upload/resumable/<uploadId>?offset=<offset>`,
+   *    where `offset` is the number of bytes uploaded so far. A part at any
+   *    other offset is rejected, so a part can't be received twice.
+   * 3. To resume, GET `<origin>/api/storage/upload/resumable/<uploadId>` for
+   *    the current offset.
+   * 4. POST to `<origin>/api/storage/upload/resumable/<uploadId>/finish` with
+   *    an HTTP Digest header holding the sha256 of the whole file. The response
+   *    is `{ storageId }`.
+   *
+   * @param options - Pass `resumable: true` for a resumable upload URL.
    * @returns - A url that allows file upload via an HTTP POST.
    */
-  generateUploadUrl(): Promise<string>;
+  generateUploadUrl(options?: { resumable?: boolean }): Promise<string>;
   /**
    * Delete a file from Convex storage.
    *
@@ -20,7 +20,11 @@ export type {
   ObjectValidator,
 } from "./validator.js";
 /* @internal */
-export type { ValidatorJSON, ObjectFieldType } from "./validator.js";
+export type {
+  ValidatorJSON,
+  ValidatorConstraintsJSON,
+  ObjectFieldType,
+} from "./validator.js";
 import * as Base64 from "./base64.js";
 export { Base64 };
 export type { Infer } from "./validator.js";
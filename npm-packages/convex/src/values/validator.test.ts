@@ -0,0 +1,61 @@
+import { test, expect, describe } from "@jest/globals";
+
+import { v } from "./validator.js";
+
+describe("validator constraints", () => {
+  test("serialize next to the validator's type", () => {
+    expect(
+      v.string().minLength(1).maxLength(20).pattern("^[a-z]+$").json,
+    ).toEqual({
+      type: "string",
+      minLength: 1,
+      maxLength: 20,
+      pattern: "^[a-z]+$",
+    });
+    expect(v.array(v.number()).maxLength(3).json).toEqual({
+      type: "array",
+      value: { type: "number" },
+      maxLength: 3,
+    });
+    expect(v.float64().min(0).max(1.5).integer().json).toEqual({
+      type: "number",
+      min: 0,
+      max: 1.5,
+      integer: true,
+    });
+    expect(v.int64().min(-1n).json).toEqual({
+      type: "bigint",
+      min: { $integer: "//////////8=" },
+    });
+  });
+
+  test("are kept by v.optional and nested validators", () => {
+    const validator = v.object({
+      name: v.optional(v.string().minLength(1)),
+    });
+    expect(validator.json).toEqual({
+      type: "object",
+      value: {
+        name: {
+          fieldType: { type: "string", minLength: 1 },
+          optional: true,
+        },
+      },
+    });
+  });
+
+  test("don't modify the original validator", () => {
+    const validator = v.string();
+    validator.maxLength(10);
+    expect(validator.json).toEqual({ type: "string" });
+  });
+
+  test("only apply to matching types", () => {
+    // @ts-expect-error minLength doesn't apply to booleans
+    v.boolean().minLength(1);
+    // @ts-expect-error pattern doesn't apply to numbers
+    v.number().pattern("a");
+    // @ts-expect-error integer doesn't apply to bigints
+    v.int64().integer();
+  });
+});
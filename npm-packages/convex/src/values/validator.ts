@@ -38,6 +38,78 @@ export class Validator<
     this.json = json;
     this.optional = optional;
   }
+
+  /**
+   * Require strings to have at least `minLength` characters, bytes to have
+   * at least `minLength` bytes, or arrays to have at least `minLength`
+   * elements.
+   */
+  minLength<T extends string | ArrayBuffer | any[]>(
+    this: Validator<T, IsOptional, FieldPaths>,
+    minLength: number,
+  ): Validator<T, IsOptional, FieldPaths> {
+    return this.withConstraints({ minLength });
+  }
+
+  /**
+   * Require strings to have at most `maxLength` characters, bytes to have at
+   * most `maxLength` bytes, or arrays to have at most `maxLength` elements.
+   */
+  maxLength<T extends string | ArrayBuffer | any[]>(
+    this: Validator<T, IsOptional, FieldPaths>,
+    maxLength: number,
+  ): Validator<T, IsOptional, FieldPaths> {
+    return this.withConstraints({ maxLength });
+  }
+
+  /**
+   * Require numbers or bigints to be at least `min`. The bound must be a
+   * number for `v.float64()` and a bigint for `v.int64()`.
+   */
+  min<T extends number | bigint>(
+    this: Validator<T, IsOptional, FieldPaths>,
+    min: T,
+  ): Validator<T, IsOptional, FieldPaths> {
+    return this.withConstraints({ min: convexToJson(min) });
+  }
+
+  /**
+   * Require numbers or bigints to be at most `max`. The bound must be a
+   * number for `v.float64()` and a bigint for `v.int64()`.
+   */
+  max<T extends number | bigint>(
+    this: Validator<T, IsOptional, FieldPaths>,
+    max: T,
+  ): Validator<T, IsOptional, FieldPaths> {
+    return this.withConstraints({ max: convexToJson(max) });
+  }
+
+  /**
+   * Require strings to match the regular expression `pattern`, which uses
+   * the syntax of the Rust `regex` crate and can match anywhere in the
+   * string unless it's anchored with `^` and `$`.
+   */
+  pattern<T extends string>(
+    this: Validator<T, IsOptional, FieldPaths>,
+    pattern: string,
+  ): Validator<T, IsOptional, FieldPaths> {
+    return this.withConstraints({ pattern });
+  }
+
+  /**
+   * Require `v.float64()` values to be integers.
+   */
+  integer<T extends number>(
+    this: Validator<T, IsOptional, FieldPaths>,
+  ): Validator<T, IsOptional, FieldPaths> {
+    return this.withConstraints({ integer: true });
+  }
+
+  private withConstraints(
+    constraints: ValidatorConstraintsJSON,
+  ): Validator<TypeScriptType, IsOptional, FieldPaths> {
+    return new Validator({ ...this.json, ...constraints }, this.optional);
+  }
 }
 
 /**
@@ -45,10 +117,24 @@ export class Validator<
  */
 export type ObjectFieldType = { fieldType: ValidatorJSON; optional: boolean };
 
+/**
+ * Constraints on a validator, serialized next to its `type`.
+ *
+ * @internal
+ */
+export type ValidatorConstraintsJSON = {
+  minLength?: number;
+  maxLength?: number;
+  min?: JSONValue;
+  max?: JSONValue;
+  pattern?: string;
+  integer?: boolean;
+};
+
 /**
  * @internal
  */
-export type ValidatorJSON =
+export type ValidatorJSON = (
   | {
       type: "null";
     }
@@ -66,7 +152,9 @@ export type ValidatorJSON =
   | { type: "array"; value: ValidatorJSON }
   | { type: "record"; keys: ValidatorJSON; values: ObjectFieldType }
   | { type: "object"; value: Record<string, ObjectFieldType> }
-  | { type: "union"; value: ValidatorJSON[] };
+  | { type: "union"; value: ValidatorJSON[] }
+) &
+  ValidatorConstraintsJSON;
 
 /**
  * The validator builder.
@@ -2,6 +2,7 @@ import { Command } from "commander";
 import { invoke } from "./executor";
 import { v4 as uuidv4 } from "uuid";
 import { log, setDebugLogging } from "./log";
+import { configureNetworkIsolation } from "./network_isolation";
 import os from "node:os";
 import crypto from "crypto";
 import fs from "node:fs";
@@ -26,6 +27,8 @@ async function main(request_str: string, debug: boolean) {
   fs.mkdirSync(tempdir);
   os.tmpdir = () => tempdir;
 
+  configureNetworkIsolation();
+
   const responseStream = new Writable({
     write: (chunk, _encoding, callback) => {
       log(chunk.toString());
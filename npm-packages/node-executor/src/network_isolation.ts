@@ -0,0 +1,72 @@
+import dns from "node:dns";
+import { Agent, Dispatcher, ProxyAgent, setGlobalDispatcher } from "undici";
+
+// Set by the executor when the action runs in its own network namespace,
+// where the backend and the proxy are only reachable through relays on the
+// loopback interface.
+const ISOLATED_BACKEND_HOST_ENV = "CONVEX_ISOLATED_BACKEND_HOST";
+const ISOLATED_PROXY_ENV = "CONVEX_ISOLATED_PROXY";
+
+const LOOPBACK_ADDRESS = "127.0.0.1";
+
+// Sends requests for `directHosts` straight to their relays, and everything
+// else through the proxy.
+class IsolatedDispatcher extends Dispatcher {
+  constructor(
+    private direct: Dispatcher,
+    private proxy: Dispatcher | null,
+    private directHosts: Set<string>,
+  ) {
+    super();
+  }
+
+  dispatch(
+    options: Dispatcher.DispatchOptions,
+    handler: Dispatcher.DispatchHandlers,
+  ): boolean {
+    const hostname = new URL(String(options.origin)).hostname;
+    const dispatcher =
+      this.proxy === null || this.directHosts.has(hostname)
+        ? this.direct
+        : this.proxy;
+    return dispatcher.dispatch(options, handler);
+  }
+}
+
+// Resolves the backend's hostname to its relay on the loopback interface, so
+// TLS still verifies the backend's certificate for that hostname.
+function lookupWithBackendHost(backendHost: string | undefined) {
+  return (hostname: string, options: any, callback: any) => {
+    if (hostname !== backendHost) {
+      dns.lookup(hostname, options, callback);
+    } else if (options.all) {
+      callback(null, [{ address: LOOPBACK_ADDRESS, family: 4 }]);
+    } else {
+      callback(null, LOOPBACK_ADDRESS, 4);
+    }
+  };
+}
+
+// Node's built-in `fetch` ignores `HTTP_PROXY`, so route it through the
+// proxy with a global dispatcher when the action's network is isolated.
+export function configureNetworkIsolation() {
+  const backendHost = process.env[ISOLATED_BACKEND_HOST_ENV];
+  const proxy = process.env[ISOLATED_PROXY_ENV];
+  if (backendHost === undefined && proxy === undefined) {
+    return;
+  }
+  const directHosts = new Set(["localhost", LOOPBACK_ADDRESS]);
+  if (backendHost !== undefined) {
+    directHosts.add(backendHost);
+  }
+  const direct = new Agent({
+    connect: { lookup: lookupWithBackendHost(backendHost) },
+  });
+  setGlobalDispatcher(
+    new IsolatedDispatcher(
+      direct,
+      proxy === undefined ? null : new ProxyAgent(proxy),
+      directHosts,
+    ),
+  );
+}
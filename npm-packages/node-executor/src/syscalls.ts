@@ -523,6 +523,7 @@ export class SyscallsImpl {
   async syscallStorageGenerateUploadUrl(rawArgs: string): Promise<JSONValue> {
     const storageGenerateUploadUrlArgs = z.object({
       version: z.string(),
+      resumable: z.boolean().optional(),
     });
     const operationName = "generate upload url";
     const args = this.validateArgs(
@@ -530,10 +531,13 @@ export class SyscallsImpl {
       storageGenerateUploadUrlArgs,
       operationName,
     );
-    return this._storageGenerateUploadUrl(args.version);
+    return this._storageGenerateUploadUrl(args.version, args.resumable);
   }
 
-  async _storageGenerateUploadUrl(version: string): Promise<string> {
+  async _storageGenerateUploadUrl(
+    version: string,
+    resumable?: boolean,
+  ): Promise<string> {
     const storageGenerateUploadUrlReturn = z.object({
       url: z.string(),
     });
@@ -541,7 +545,9 @@ export class SyscallsImpl {
     const result = await this.actionCallback({
       version,
       body: {},
-      path: "/api/actions/storage_generate_upload_url",
+      path: resumable
+        ? "/api/actions/storage_generate_upload_url?resumable=true"
+        : "/api/actions/storage_generate_upload_url",
       operationName,
       responseValidator: storageGenerateUploadUrlReturn,
     });
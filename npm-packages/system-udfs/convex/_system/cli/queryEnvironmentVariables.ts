@@ -1,4 +1,8 @@
 import { v } from "convex/values";
+import {
+  hashEnvironmentVariableValue,
+  redactEnvironmentVariable,
+} from "../environmentVariables";
 import { queryPrivateSystem } from "../secretSystemTables";
 
 // This query returns a new result every time
@@ -6,11 +10,12 @@ import { queryPrivateSystem } from "../secretSystemTables";
 export default queryPrivateSystem({
   args: {},
   handler: async ({ db }) => {
-    return await db
+    const envVars = await db
       .query("_environment_variables")
       .withIndex("by_name")
       .order("asc")
       .collect();
+    return envVars.map(redactEnvironmentVariable);
   },
 });
 
@@ -19,10 +24,30 @@ export const get = queryPrivateSystem({
     name: v.string(),
   },
   handler: async ({ db }, { name }) => {
-    return await db
+    const envVar = await db
       .query("_environment_variables")
       .withIndex("by_name", (q) => q.eq("name", name))
       .order("asc")
       .unique();
+    return envVar === null ? null : redactEnvironmentVariable(envVar);
+  },
+});
+
+// Like the default query, but also changes when a variable's value does,
+// since listings don't include values.
+export const valueHashes = queryPrivateSystem({
+  args: {},
+  handler: async ({ db }) => {
+    const envVars = await db
+      .query("_environment_variables")
+      .withIndex("by_name")
+      .order("asc")
+      .collect();
+    return await Promise.all(
+      envVars.map(async (envVar) => ({
+        name: envVar.name,
+        valueHash: await hashEnvironmentVariableValue(envVar),
+      })),
+    );
   },
 });
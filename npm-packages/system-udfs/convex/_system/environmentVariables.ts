@@ -0,0 +1,32 @@
+import { Doc } from "../_generated/dataModel";
+
+export type RedactedEnvironmentVariable = Omit<
+  Doc<"_environment_variables">,
+  "value" | "encryptedValue"
+>;
+
+// Values are secret: listings only expose names. Admins read a value through
+// the backend's `/api/reveal_environment_variable` endpoint, which is audit
+// logged.
+export function redactEnvironmentVariable(
+  envVar: Doc<"_environment_variables">,
+): RedactedEnvironmentVariable {
+  const { value: _value, encryptedValue: _encryptedValue, ...rest } = envVar;
+  return rest;
+}
+
+// Identifies a stored value without revealing it, so watchers can tell when a
+// value changes. Encrypted values are hashed as ciphertext, which changes on
+// every write.
+export async function hashEnvironmentVariableValue(
+  envVar: Doc<"_environment_variables">,
+): Promise<string> {
+  const stored = envVar.encryptedValue ?? envVar.value ?? "";
+  const digest = await crypto.subtle.digest(
+    "SHA-256",
+    new TextEncoder().encode(stored),
+  );
+  return Array.from(new Uint8Array(digest))
+    .map((byte) => byte.toString(16).padStart(2, "0"))
+    .join("");
+}
@@ -1,12 +1,17 @@
-import { Doc } from "../../_generated/dataModel";
+import {
+  RedactedEnvironmentVariable,
+  redactEnvironmentVariable,
+} from "../environmentVariables";
 import { queryPrivateSystem } from "../secretSystemTables";
+
 export default queryPrivateSystem({
   args: {},
-  handler: async ({ db }): Promise<Doc<"_environment_variables">[]> => {
-    return await db
+  handler: async ({ db }): Promise<RedactedEnvironmentVariable[]> => {
+    const envVars = await db
       .query("_environment_variables")
       .withIndex("by_name")
       .order("asc")
       .collect();
+    return envVars.map(redactEnvironmentVariable);
   },
 });
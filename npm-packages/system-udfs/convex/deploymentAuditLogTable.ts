@@ -34,6 +34,14 @@ const replaceEnvironmentVariable = v.object({
   }),
 });
 
+const revealEnvironmentVariable = v.object({
+  action: v.literal("reveal_environment_variable"),
+  member_id: v.int64(),
+  metadata: v.object({
+    variable_name: v.string(),
+  }),
+});
+
 const databaseIndex = v.object({
   name: v.optional(v.string()),
   type: v.literal("database"),
@@ -66,43 +74,54 @@ export const buildIndexes = v.object({
   }),
 });
 
-export const pushConfig = v.object({
-  action: v.literal("push_config"),
-  member_id: v.int64(),
-  metadata: v.object({
-    auth: v.object({
-      added: v.array(v.string()),
-      removed: v.array(v.string()),
+const configDiff = {
+  auth: v.object({
+    added: v.array(v.string()),
+    removed: v.array(v.string()),
+  }),
+  server_version: v.union(
+    v.null(),
+    v.object({
+      previous_version: v.string(),
+      next_version: v.string(),
     }),
-    server_version: v.union(
-      v.null(),
-      v.object({
-        previous_version: v.string(),
-        next_version: v.string(),
-      }),
-    ),
-    modules: v.object({
+  ),
+  modules: v.object({
+    added: v.array(v.string()),
+    removed: v.array(v.string()),
+  }),
+  crons: v.optional(
+    v.object({
       added: v.array(v.string()),
-      removed: v.array(v.string()),
+      updated: v.array(v.string()),
+      deleted: v.array(v.string()),
     }),
-    crons: v.optional(
+  ),
+  schema: v.optional(
+    v.union(
+      v.null(),
       v.object({
-        added: v.array(v.string()),
-        updated: v.array(v.string()),
-        deleted: v.array(v.string()),
+        previous_schema_id: v.union(v.id("_schemas"), v.null()),
+        next_schema_id: v.union(v.id("_schemas"), v.null()),
+        previous_schema: v.optional(v.union(v.string(), v.null())),
+        next_schema: v.optional(v.union(v.string(), v.null())),
       }),
     ),
-    schema: v.optional(
-      v.union(
-        v.null(),
-        v.object({
-          previous_schema_id: v.union(v.id("_schemas"), v.null()),
-          next_schema_id: v.union(v.id("_schemas"), v.null()),
-          previous_schema: v.optional(v.union(v.string(), v.null())),
-          next_schema: v.optional(v.union(v.string(), v.null())),
-        }),
-      ),
-    ),
+  ),
+};
+
+export const pushConfig = v.object({
+  action: v.literal("push_config"),
+  member_id: v.int64(),
+  metadata: v.object(configDiff),
+});
+
+export const rollbackConfig = v.object({
+  action: v.literal("rollback_config"),
+  member_id: v.int64(),
+  metadata: v.object({
+    ...configDiff,
+    deployment_id: v.string(),
   }),
 });
 
@@ -158,17 +177,66 @@ export const snapshotImport = v.object({
   }),
 });
 
+export const requestImport = v.object({
+  action: v.literal("request_import"),
+  member_id: v.union(v.int64(), v.null()),
+  metadata: v.object({
+    import_id: v.string(),
+    import_mode: snapshotImportMode,
+    import_format: snapshotImportFormat,
+  }),
+});
+
+export const requestExport = v.object({
+  action: v.literal("request_export"),
+  member_id: v.union(v.int64(), v.null()),
+  metadata: v.object({
+    export_format: v.union(
+      v.literal("internal_json"),
+      v.literal("clean_jsonl"),
+      v.object({
+        format: v.literal("zip"),
+        include_storage: v.boolean(),
+      }),
+    ),
+  }),
+});
+
+export const deleteTables = v.object({
+  action: v.literal("delete_tables"),
+  member_id: v.union(v.int64(), v.null()),
+  metadata: v.object({
+    table_names: v.array(v.string()),
+    document_count: v.int64(),
+  }),
+});
+
+export const cancelAllJobs = v.object({
+  action: v.literal("cancel_all_jobs"),
+  member_id: v.union(v.int64(), v.null()),
+  metadata: v.object({
+    udf_path: v.union(v.string(), v.null()),
+    job_count: v.int64(),
+  }),
+});
+
 const deploymentAuditLogTable = defineTable(
   v.union(
     createEnvironmentVariable,
     deleteEnvironmentVariable,
     updateEnvironmentVariable,
     replaceEnvironmentVariable,
+    revealEnvironmentVariable,
     buildIndexes,
     pushConfig,
+    rollbackConfig,
     changeDeploymentState,
     clearTables,
     snapshotImport,
+    requestImport,
+    requestExport,
+    deleteTables,
+    cancelAllJobs,
   ),
 );
 
@@ -289,7 +289,10 @@ export default defineSchema({
   }),
   _environment_variables: defineTable({
     name: v.string(),
-    value: v.string(),
+    // Values are encrypted at rest. Variables set before encryption keep a
+    // plaintext `value` until the backend encrypts them on startup.
+    value: v.optional(v.string()),
+    encryptedValue: v.optional(v.string()),
   }).index("by_name", ["name"]),
   _exports: defineTable(
     v.union(
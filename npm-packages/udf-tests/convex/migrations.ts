@@ -0,0 +1,9 @@
+import { mutation } from "./_generated/server";
+
+// Migration mutations are called with the document being migrated.
+
+export const markMigrated = mutation(async ({ db }, doc: any) => {
+  if (doc.migrated === undefined) {
+    await db.patch(doc._id, { migrated: true });
+  }
+});
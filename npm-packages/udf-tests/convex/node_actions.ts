@@ -68,6 +68,13 @@ export const workHardForAnHour = actionGeneric(async () => {
   return "I worked for a whole hour";
 });
 
+export const fetchUrl = actionGeneric(
+  async (_, { url }: { url: string }) => {
+    const response = await fetch(url);
+    return response.status;
+  },
+);
+
 export const getTestEnvVar = actionGeneric(async () => {
   if (process.env.UNKNOWN_VAR !== undefined) {
     throw new Error("Unexpected environment variable defined for UNKNOWN_VAR");
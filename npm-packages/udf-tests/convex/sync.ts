@@ -33,6 +33,10 @@ export const accountBalance = query(
   },
 );
 
+export const listAccounts = query(async ({ db }) => {
+  return await db.query("accounts").collect();
+});
+
 export const transfer = mutation(
   async (
     { db },